@@ -0,0 +1,207 @@
+use crate::blockchain::{
+    report::{BlockchainTestReport, TestStatus},
+    runner::{run_blockchain_test, BlockchainTestError},
+    types::BlockchainTestUnit,
+};
+use clap::Parser;
+use ethrex_vm::{EvmConfig, EvmEngine};
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+pub mod report;
+pub mod runner;
+pub mod types;
+
+#[derive(Parser)]
+pub struct BlockchainTestRunnerOptions {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Directory holding the blockchain tests, defaults to vectors/BlockchainTests"
+    )]
+    pub path: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        value_name = "FORK",
+        use_value_delimiter = true,
+        help = "Only run the tests of networks containing any of these forks, e.g. Cancun"
+    )]
+    pub fork: Vec<String>,
+    #[arg(
+        short,
+        long,
+        value_name = "DIR",
+        use_value_delimiter = true,
+        help = "Only run the test files under any of these subdirectories"
+    )]
+    pub dir: Vec<String>,
+    #[arg(
+        short,
+        long,
+        value_name = "TESTS",
+        use_value_delimiter = true,
+        help = "Only run the tests whose name contains any of these strings"
+    )]
+    pub tests: Vec<String>,
+    #[arg(long, value_name = "SKIP", use_value_delimiter = true)]
+    pub skip: Vec<String>,
+    #[arg(long, value_name = "ENGINE", default_value = "levm")]
+    pub engine: EvmEngine,
+    #[arg(
+        short,
+        long,
+        value_name = "JOBS",
+        help = "Number of tests run in parallel, defaults to the number of CPUs"
+    )]
+    pub jobs: Option<usize>,
+    #[arg(long, value_name = "FILE", help = "Write a JSON report of every test")]
+    pub json: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write a JUnit XML report of every test"
+    )]
+    pub junit: Option<PathBuf>,
+    #[arg(long, value_name = "VERBOSE", default_value = "false")]
+    pub verbose: bool,
+}
+
+/// Runs the blockchain tests selected by the options in parallel, writing the requested reports
+pub fn run_blockchain_tests(
+    opts: &BlockchainTestRunnerOptions,
+) -> Result<Vec<BlockchainTestReport>, String> {
+    let root = opts.path.clone().unwrap_or_else(|| {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("vectors/BlockchainTests")
+    });
+    let mut files = Vec::new();
+    collect_test_files(&root, &mut files)
+        .map_err(|error| format!("Failed to read {root:?}: {error}"))?;
+    files.retain(|file| {
+        let relative = file.strip_prefix(&root).unwrap_or(file);
+        opts.dir.is_empty() || opts.dir.iter().any(|dir| relative.starts_with(dir))
+    });
+    files.sort();
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(opts.jobs.unwrap_or_default())
+        .build()
+        .map_err(|error| format!("Failed to build the thread pool: {error}"))?;
+    let mut reports: Vec<BlockchainTestReport> = pool.install(|| {
+        files
+            .par_iter()
+            .flat_map_iter(|file| run_test_file(file, &root, opts))
+            .collect()
+    });
+    reports.sort_by(|a, b| (&a.file, &a.name).cmp(&(&b.file, &b.name)));
+
+    if let Some(path) = &opts.json {
+        report::write_json(&reports, path)
+            .map_err(|error| format!("Failed to write {path:?}: {error}"))?;
+    }
+    if let Some(path) = &opts.junit {
+        report::write_junit(&reports, path)
+            .map_err(|error| format!("Failed to write {path:?}: {error}"))?;
+    }
+    Ok(reports)
+}
+
+fn collect_test_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_test_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Parses a test file and runs the tests in it that pass the filters. The file is only kept in
+/// memory while its tests run, as the whole suite is too big to load at once
+fn run_test_file(
+    file: &Path,
+    root: &Path,
+    opts: &BlockchainTestRunnerOptions,
+) -> Vec<BlockchainTestReport> {
+    let file_name = file
+        .strip_prefix(root)
+        .unwrap_or(file)
+        .display()
+        .to_string();
+    let tests: HashMap<String, BlockchainTestUnit> = match std::fs::read_to_string(file)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|error| error.to_string()))
+    {
+        Ok(tests) => tests,
+        Err(error) => {
+            return vec![BlockchainTestReport {
+                name: file_name.clone(),
+                file: file_name,
+                network: String::new(),
+                status: TestStatus::Failed,
+                reason: Some(format!("Failed to parse test file: {error}")),
+                time: 0.0,
+            }]
+        }
+    };
+
+    let evm_config = EvmConfig {
+        engine: opts.engine,
+        differential: false,
+    };
+    let mut reports = Vec::new();
+    for (name, test) in tests {
+        let selected = (opts.tests.is_empty() || opts.tests.iter().any(|t| name.contains(t)))
+            && (opts.fork.is_empty() || opts.fork.iter().any(|f| test.network.contains(f)));
+        if !selected {
+            continue;
+        }
+        if opts.verbose {
+            println!("Running test: {name}");
+        }
+        let start = Instant::now();
+        let result = if opts.skip.iter().any(|skip| name.contains(skip)) {
+            Err(BlockchainTestError::Skipped(
+                "skipped by the --skip option".to_string(),
+            ))
+        } else {
+            // A panic fails the test instead of aborting the whole run
+            catch_unwind(AssertUnwindSafe(|| run_blockchain_test(&test, evm_config)))
+                .unwrap_or_else(|panic| {
+                    let message = panic
+                        .downcast_ref::<&str>()
+                        .map(ToString::to_string)
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    Err(BlockchainTestError::Panic(message))
+                })
+        };
+        let (status, reason) = match result {
+            Ok(()) => (TestStatus::Passed, None),
+            Err(
+                error @ (BlockchainTestError::UnsupportedNetwork(_)
+                | BlockchainTestError::Skipped(_)),
+            ) => (TestStatus::Skipped, Some(error.to_string())),
+            Err(error) => (TestStatus::Failed, Some(error.to_string())),
+        };
+        reports.push(BlockchainTestReport {
+            name,
+            file: file_name.clone(),
+            network: test.network.clone(),
+            status,
+            reason,
+            time: start.elapsed().as_secs_f64(),
+        });
+    }
+    reports
+}
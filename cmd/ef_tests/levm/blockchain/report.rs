@@ -0,0 +1,139 @@
+use crate::report::format_duration_as_mm_ss;
+use colored::Colorize;
+use itertools::Itertools;
+use serde::Serialize;
+use std::{fmt::Write, path::Path, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockchainTestReport {
+    pub name: String,
+    pub file: String,
+    pub network: String,
+    pub status: TestStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Execution time in seconds
+    pub time: f64,
+}
+
+fn count(reports: &[BlockchainTestReport], status: TestStatus) -> usize {
+    reports
+        .iter()
+        .filter(|report| report.status == status)
+        .count()
+}
+
+pub fn summary_for_shell(reports: &[BlockchainTestReport], time: Duration) -> String {
+    let mut summary = format!(
+        "{}: {} {} {} - {}\n",
+        "Blockchain Tests".bold(),
+        format!("{} passed", count(reports, TestStatus::Passed))
+            .green()
+            .bold(),
+        format!("{} failed", count(reports, TestStatus::Failed))
+            .red()
+            .bold(),
+        format!("{} skipped", count(reports, TestStatus::Skipped))
+            .yellow()
+            .bold(),
+        format_duration_as_mm_ss(time)
+    );
+    for report in reports
+        .iter()
+        .filter(|report| report.status == TestStatus::Failed)
+    {
+        let _ = writeln!(
+            summary,
+            "{} {} ({}): {}",
+            "FAILED".red(),
+            report.name,
+            report.file,
+            report.reason.as_deref().unwrap_or_default()
+        );
+    }
+    summary
+}
+
+pub fn write_json(reports: &[BlockchainTestReport], path: &Path) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(reports)?;
+    std::fs::write(path, json)
+}
+
+/// Writes the reports in the JUnit XML format understood by CI systems, with a test suite per
+/// test file
+pub fn write_junit(reports: &[BlockchainTestReport], path: &Path) -> std::io::Result<()> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"blockchain-tests\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+        reports.len(),
+        count(reports, TestStatus::Failed),
+        count(reports, TestStatus::Skipped),
+    );
+    let suites = reports
+        .iter()
+        .sorted_by(|a, b| a.file.cmp(&b.file))
+        .chunk_by(|report| report.file.clone());
+    for (file, suite) in &suites {
+        let suite: Vec<_> = suite.collect();
+        let time: f64 = suite.iter().map(|report| report.time).sum();
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{time:.3}\">",
+            escape_xml(&file),
+            suite.len(),
+            suite
+                .iter()
+                .filter(|report| report.status == TestStatus::Failed)
+                .count(),
+            suite
+                .iter()
+                .filter(|report| report.status == TestStatus::Skipped)
+                .count(),
+        );
+        for report in suite {
+            let _ = write!(
+                xml,
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape_xml(&report.name),
+                escape_xml(&report.network),
+                report.time
+            );
+            let reason = escape_xml(report.reason.as_deref().unwrap_or_default());
+            match report.status {
+                TestStatus::Passed => xml.push_str("/>\n"),
+                TestStatus::Failed => {
+                    let _ = write!(
+                        xml,
+                        ">\n      <failure message=\"{reason}\"/>\n    </testcase>\n"
+                    );
+                }
+                TestStatus::Skipped => {
+                    let _ = write!(
+                        xml,
+                        ">\n      <skipped message=\"{reason}\"/>\n    </testcase>\n"
+                    );
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    std::fs::write(path, xml)
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
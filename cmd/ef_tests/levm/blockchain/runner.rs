@@ -0,0 +1,226 @@
+use crate::blockchain::types::{BlockchainTestUnit, EngineNewPayload};
+use ef_tests_ethrex::types::Account;
+use ethrex_blockchain::{add_block, fork_choice::apply_fork_choice};
+use ethrex_core::{
+    types::{Account as CoreAccount, Block},
+    Address, H256,
+};
+use ethrex_rlp::decode::RLPDecode;
+use ethrex_storage::{error::StoreError, EngineType, Store};
+use ethrex_vm::EvmConfig;
+use std::collections::HashMap;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlockchainTestError {
+    #[error("Network {0} is not supported by block execution")]
+    UnsupportedNetwork(String),
+    #[error("Genesis mismatch: {0}")]
+    GenesisMismatch(String),
+    #[error("Block {0} was expected to be rejected with {1}, but it was imported")]
+    BlockNotRejected(usize, String),
+    #[error("Block {0} was rejected unexpectedly: {1}")]
+    BlockRejected(usize, String),
+    #[error("Last block hash mismatch: expected {expected:#x}, got {got:#x}")]
+    LastBlockHashMismatch { expected: H256, got: H256 },
+    #[error("Post-state mismatch: {0}")]
+    PostStateMismatch(String),
+    #[error("Invalid test: {0}")]
+    InvalidTest(String),
+    #[error("Store error: {0}")]
+    Store(#[from] StoreError),
+    #[error("Panicked: {0}")]
+    Panic(String),
+    #[error("Skipped: {0}")]
+    Skipped(String),
+}
+
+/// Imports the blocks of the test one by one, checking that only the ones the test expects to be
+/// invalid are rejected, and then checks the resulting chain head and state
+pub fn run_blockchain_test(
+    test: &BlockchainTestUnit,
+    evm_config: EvmConfig,
+) -> Result<(), BlockchainTestError> {
+    let chain_config = test
+        .chain_config()
+        .ok_or_else(|| BlockchainTestError::UnsupportedNetwork(test.network.clone()))?;
+    let store = Store::new("", EngineType::InMemory)?;
+    let genesis = test.genesis(chain_config);
+    let genesis_hash = genesis.get_block().hash();
+    if genesis_hash != test.genesis_block_header.hash {
+        return Err(BlockchainTestError::GenesisMismatch(format!(
+            "expected hash {:#x}, got {genesis_hash:#x}",
+            test.genesis_block_header.hash
+        )));
+    }
+    if !test.genesis_rlp.is_empty() {
+        let decoded = Block::decode(&test.genesis_rlp)
+            .map_err(|error| BlockchainTestError::GenesisMismatch(error.to_string()))?;
+        if decoded.hash() != genesis_hash {
+            return Err(BlockchainTestError::GenesisMismatch(
+                "genesis RLP doesn't match the genesis header".to_string(),
+            ));
+        }
+    }
+    store.add_initial_state(genesis)?;
+
+    for (index, fixture) in test.blocks.iter().enumerate() {
+        let block = match Block::decode(&fixture.rlp) {
+            Ok(block) => block,
+            // Blocks that can't be decoded are never imported
+            Err(error) => match &fixture.expect_exception {
+                Some(_) => continue,
+                None => return Err(BlockchainTestError::BlockRejected(index, error.to_string())),
+            },
+        };
+        import_block(
+            &store,
+            index,
+            block,
+            fixture.expect_exception.as_ref(),
+            evm_config,
+        )?;
+    }
+    for (index, payload) in test.engine_new_payloads.iter().enumerate() {
+        match payload_to_block(payload) {
+            Ok(block) => import_block(
+                &store,
+                index,
+                block,
+                payload.validation_error.as_ref(),
+                evm_config,
+            )?,
+            Err(error) => {
+                if payload.validation_error.is_none() {
+                    return Err(BlockchainTestError::BlockRejected(index, error));
+                }
+            }
+        }
+    }
+
+    check_post_state(test, &store)
+}
+
+/// Adds the block to the store, making it the head of the chain if it's valid
+fn import_block(
+    store: &Store,
+    index: usize,
+    block: Block,
+    expected_exception: Option<&String>,
+    evm_config: EvmConfig,
+) -> Result<(), BlockchainTestError> {
+    let hash = block.hash();
+    match (add_block(&block, store, evm_config), expected_exception) {
+        (Ok(()), Some(exception)) => Err(BlockchainTestError::BlockNotRejected(
+            index,
+            exception.clone(),
+        )),
+        (Ok(()), None) => {
+            apply_fork_choice(store, hash, hash, hash).map_err(|error| {
+                BlockchainTestError::BlockRejected(index, format!("fork choice failed: {error}"))
+            })?;
+            Ok(())
+        }
+        (Err(_), Some(_)) => Ok(()),
+        (Err(error), None) => Err(BlockchainTestError::BlockRejected(index, error.to_string())),
+    }
+}
+
+/// Builds the block of an `engine_newPayload` call, running the checks done on the payload
+/// before executing it
+fn payload_to_block(payload: &EngineNewPayload) -> Result<Block, String> {
+    let execution_payload = payload
+        .execution_payload()
+        .map_err(|error| format!("invalid payload: {error}"))?;
+    let versioned_hashes = payload
+        .versioned_hashes()
+        .map_err(|error| format!("invalid versioned hashes: {error}"))?;
+    let parent_beacon_block_root = payload
+        .parent_beacon_block_root()
+        .map_err(|error| format!("invalid parent beacon block root: {error}"))?;
+    let expected_hash = execution_payload.block_hash;
+    let block = execution_payload
+        .into_block(parent_beacon_block_root)
+        .map_err(|error| format!("invalid transactions: {error}"))?;
+    if block.hash() != expected_hash {
+        return Err(format!(
+            "block hash mismatch: payload {expected_hash:#x}, computed {:#x}",
+            block.hash()
+        ));
+    }
+    if let Some(versioned_hashes) = versioned_hashes {
+        let blob_hashes: Vec<H256> = block
+            .body
+            .transactions
+            .iter()
+            .flat_map(|tx| tx.blob_versioned_hashes())
+            .collect();
+        if blob_hashes != versioned_hashes {
+            return Err("versioned hashes don't match the blob transactions".to_string());
+        }
+    }
+    Ok(block)
+}
+
+fn check_post_state(test: &BlockchainTestUnit, store: &Store) -> Result<(), BlockchainTestError> {
+    let head_number = store.get_latest_block_number()?;
+    let head_header = store
+        .get_block_header(head_number)?
+        .ok_or_else(|| BlockchainTestError::InvalidTest("head header missing".to_string()))?;
+    let head_hash = head_header.compute_block_hash();
+    if head_hash != test.lastblockhash {
+        return Err(BlockchainTestError::LastBlockHashMismatch {
+            expected: test.lastblockhash,
+            got: head_hash,
+        });
+    }
+    if let Some(state_root) = test.post_state_hash {
+        if head_header.state_root != state_root {
+            return Err(BlockchainTestError::PostStateMismatch(format!(
+                "expected state root {state_root:#x}, got {:#x}",
+                head_header.state_root
+            )));
+        }
+    }
+    if let Some(post_state) = &test.post_state {
+        check_accounts(post_state, store, head_number)?;
+    }
+    Ok(())
+}
+
+fn check_accounts(
+    post_state: &HashMap<Address, Account>,
+    store: &Store,
+    block_number: u64,
+) -> Result<(), BlockchainTestError> {
+    for (address, account) in post_state {
+        let expected: CoreAccount = account.clone().into();
+        let info = store
+            .get_account_info(block_number, *address)?
+            .ok_or_else(|| {
+                BlockchainTestError::PostStateMismatch(format!("account {address:#x} missing"))
+            })?;
+        if info != expected.info {
+            return Err(BlockchainTestError::PostStateMismatch(format!(
+                "account {address:#x}: expected {:?}, got {info:?}",
+                expected.info
+            )));
+        }
+        let code = store.get_account_code(info.code_hash)?.unwrap_or_default();
+        if code != expected.code {
+            return Err(BlockchainTestError::PostStateMismatch(format!(
+                "code of account {address:#x} differs"
+            )));
+        }
+        for (key, value) in expected.storage {
+            let stored = store
+                .get_storage_at(block_number, *address, key)?
+                .unwrap_or_default();
+            if stored != value {
+                return Err(BlockchainTestError::PostStateMismatch(format!(
+                    "storage of account {address:#x} at {key:#x}: expected {value}, got {stored}"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
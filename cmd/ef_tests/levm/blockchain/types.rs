@@ -0,0 +1,101 @@
+use bytes::Bytes;
+use ef_tests_ethrex::{
+    network::Network,
+    types::{Account, Header},
+};
+use ethrex_core::{
+    types::{ChainConfig, Genesis},
+    Address, H256,
+};
+use ethrex_rpc::types::payload::ExecutionPayload;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A blockchain test, either in the `blocks` format, where each block is given as RLP, or in
+/// the engine format, where each block is given as the payload of an `engine_newPayload` call
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockchainTestUnit {
+    pub network: String,
+    pub genesis_block_header: Header,
+    #[serde(
+        rename = "genesisRLP",
+        default,
+        with = "ethrex_core::serde_utils::bytes"
+    )]
+    pub genesis_rlp: Bytes,
+    #[serde(default)]
+    pub blocks: Vec<BlockWithRLP>,
+    #[serde(default)]
+    pub engine_new_payloads: Vec<EngineNewPayload>,
+    pub lastblockhash: H256,
+    pub pre: HashMap<Address, Account>,
+    pub post_state: Option<HashMap<Address, Account>>,
+    pub post_state_hash: Option<H256>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockWithRLP {
+    #[serde(with = "ethrex_core::serde_utils::bytes")]
+    pub rlp: Bytes,
+    pub expect_exception: Option<String>,
+}
+
+/// Parameters of an `engine_newPayload` call: the execution payload, followed by the versioned
+/// hashes of its blobs and the parent beacon block root since V3
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineNewPayload {
+    pub params: Vec<serde_json::Value>,
+    pub validation_error: Option<String>,
+}
+
+impl EngineNewPayload {
+    pub fn execution_payload(&self) -> Result<ExecutionPayload, serde_json::Error> {
+        let payload = self.params.first().cloned().unwrap_or_default();
+        serde_json::from_value(payload)
+    }
+
+    pub fn versioned_hashes(&self) -> Result<Option<Vec<H256>>, serde_json::Error> {
+        let hashes = self.params.get(1).cloned().unwrap_or_default();
+        serde_json::from_value(hashes)
+    }
+
+    pub fn parent_beacon_block_root(&self) -> Result<Option<H256>, serde_json::Error> {
+        let root = self.params.get(2).cloned().unwrap_or_default();
+        serde_json::from_value(root)
+    }
+}
+
+impl BlockchainTestUnit {
+    /// Chain config of the test's network, if block execution supports it
+    pub fn chain_config(&self) -> Option<ChainConfig> {
+        let network = serde_json::Value::String(self.network.clone());
+        serde_json::from_value::<Network>(network)
+            .ok()
+            .map(|network| *network.chain_config())
+    }
+
+    pub fn genesis(&self, config: ChainConfig) -> Genesis {
+        let header = &self.genesis_block_header;
+        Genesis {
+            config,
+            alloc: self
+                .pre
+                .iter()
+                .map(|(address, account)| (*address, account.clone().into()))
+                .collect(),
+            coinbase: header.coinbase,
+            difficulty: header.difficulty,
+            extra_data: header.extra_data.clone(),
+            gas_limit: header.gas_limit.as_u64(),
+            nonce: header.nonce.to_low_u64_be(),
+            mix_hash: header.mix_hash,
+            timestamp: header.timestamp.as_u64(),
+            base_fee_per_gas: header.base_fee_per_gas.map(|fee| fee.as_u64()),
+            blob_gas_used: header.blob_gas_used.map(|gas| gas.as_u64()),
+            excess_blob_gas: header.excess_blob_gas.map(|gas| gas.as_u64()),
+        }
+    }
+}
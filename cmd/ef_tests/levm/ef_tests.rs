@@ -1,3 +1,4 @@
+pub mod blockchain;
 mod deserialize;
 pub mod parser;
 mod report;
@@ -6,7 +6,7 @@ use crate::{
 };
 use colored::Colorize;
 use spinoff::{spinners::Dots, Color, Spinner};
-use std::fs::DirEntry;
+use std::{fs::DirEntry, path::Path};
 
 #[derive(Debug, thiserror::Error)]
 pub enum EFTestParseError {
@@ -160,16 +160,21 @@ pub fn parse_ef_test_dir(
             continue;
         }
 
-        let test_file = std::fs::File::open(test.path()).map_err(|err| {
-            EFTestParseError::FailedToReadFile(format!("{:?}: {err}", test.path()))
-        })?;
-        let mut tests: EFTests = serde_json::from_reader(test_file).map_err(|err| {
-            EFTestParseError::FailedToParseTestFile(format!("{:?} parse error: {err}", test.path()))
-        })?;
-        for test in tests.0.iter_mut() {
+        let mut tests = parse_ef_test_file(&test.path())?;
+        for test in tests.iter_mut() {
             test.dir = test_dir.file_name().into_string().unwrap();
         }
-        directory_tests.extend(tests.0);
+        directory_tests.extend(tests);
     }
     Ok(directory_tests)
 }
+
+/// Parses the tests of a single state test file
+pub fn parse_ef_test_file(path: &Path) -> Result<Vec<EFTest>, EFTestParseError> {
+    let test_file = std::fs::File::open(path)
+        .map_err(|err| EFTestParseError::FailedToReadFile(format!("{path:?}: {err}")))?;
+    let tests: EFTests = serde_json::from_reader(test_file).map_err(|err| {
+        EFTestParseError::FailedToParseTestFile(format!("{path:?} parse error: {err}"))
+    })?;
+    Ok(tests.0)
+}
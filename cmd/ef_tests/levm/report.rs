@@ -1,18 +1,15 @@
 use crate::runner::{EFTestRunnerError, InternalError};
 use colored::Colorize;
-use ethrex_core::{Address, H256};
-use ethrex_levm::{
-    errors::{TransactionReport, TxResult, VMError},
-    Account, StorageSlot,
-};
-use ethrex_storage::{error::StoreError, AccountUpdate};
-use ethrex_vm::SpecId;
+use ethrex_core::H256;
+use ethrex_levm::errors::{TransactionReport, TxResult, VMError};
+use ethrex_storage::error::StoreError;
+use ethrex_vm::{ComparisonReport, SpecId};
 use itertools::Itertools;
 use revm::primitives::{EVMError, ExecutionResult as RevmExecutionResult};
 use serde::{Deserialize, Serialize};
 use spinoff::{spinners::Dots, Color, Spinner};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     fmt::{self, Display},
     path::PathBuf,
     time::Duration,
@@ -473,313 +470,6 @@ impl EFTestReport {
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
-pub struct ComparisonReport {
-    pub levm_post_state_root: H256,
-    pub revm_post_state_root: H256,
-    pub initial_accounts: HashMap<Address, Account>,
-    pub levm_account_updates: Vec<AccountUpdate>,
-    pub revm_account_updates: Vec<AccountUpdate>,
-    pub levm_updated_accounts_only: HashSet<Address>,
-    pub revm_updated_accounts_only: HashSet<Address>,
-    pub shared_updated_accounts: HashSet<Address>,
-}
-
-impl fmt::Display for ComparisonReport {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.levm_post_state_root != self.revm_post_state_root {
-            writeln!(
-                f,
-                "Post-state roots mismatch: LEVM: {levm_post_state_root:#x}, REVM: {revm_post_state_root:#x}",
-                levm_post_state_root = self.levm_post_state_root,
-                revm_post_state_root = self.revm_post_state_root
-            )?;
-        } else {
-            writeln!(
-                f,
-                "Post-state roots match to: {levm_post_state_root:#x}",
-                levm_post_state_root = self.levm_post_state_root
-            )?;
-        }
-        writeln!(f, "Account Updates:")?;
-        for levm_updated_account_only in self.levm_updated_accounts_only.iter() {
-            writeln!(f, "  {levm_updated_account_only:#x}:")?;
-            writeln!(f, "    Was updated in LEVM but not in REVM")?;
-            let initial_account = self
-                .initial_accounts
-                .get(levm_updated_account_only)
-                .cloned()
-                .unwrap_or_default();
-            let updated_account_update = self
-                .levm_account_updates
-                .iter()
-                .find(|account_update| &account_update.address == levm_updated_account_only)
-                .unwrap();
-            let updated_account_storage = updated_account_update
-                .added_storage
-                .iter()
-                .map(|(key, value)| {
-                    let storage_slot = StorageSlot {
-                        original_value: initial_account
-                            .storage
-                            .get(key)
-                            .cloned()
-                            .unwrap_or_default()
-                            .original_value,
-                        current_value: *value,
-                    };
-                    (*key, storage_slot)
-                })
-                .collect();
-            let updated_account_info = updated_account_update.info.clone().unwrap();
-            let updated_account = Account::new(
-                updated_account_info.balance,
-                updated_account_update.code.clone().unwrap_or_default(),
-                updated_account_info.nonce,
-                updated_account_storage,
-            );
-            let mut updates = 0;
-            if initial_account.info.balance != updated_account.info.balance {
-                writeln!(
-                    f,
-                    "      Balance updated: {initial_balance} -> {updated_balance}",
-                    initial_balance = initial_account.info.balance,
-                    updated_balance = updated_account.info.balance
-                )?;
-                updates += 1;
-            }
-            if initial_account.info.nonce != updated_account.info.nonce {
-                writeln!(
-                    f,
-                    "      Nonce updated: {initial_nonce} -> {updated_nonce}",
-                    initial_nonce = initial_account.info.nonce,
-                    updated_nonce = updated_account.info.nonce
-                )?;
-                updates += 1;
-            }
-            if initial_account.info.bytecode != updated_account.info.bytecode {
-                writeln!(
-                    f,
-                    "      Code updated: {initial_code}, {updated_code}",
-                    initial_code = if initial_account.info.bytecode.is_empty() {
-                        "was empty".to_string()
-                    } else {
-                        hex::encode(&initial_account.info.bytecode)
-                    },
-                    updated_code = hex::encode(&updated_account.info.bytecode)
-                )?;
-                updates += 1;
-            }
-            for (added_storage_address, added_storage_slot) in updated_account.storage.iter() {
-                writeln!(
-                    f,
-                    "      Storage slot added: {added_storage_address}: {} -> {}",
-                    added_storage_slot.original_value, added_storage_slot.current_value
-                )?;
-                updates += 1;
-            }
-            if updates == 0 {
-                writeln!(f, "      No changes")?;
-            }
-        }
-        for revm_updated_account_only in self.revm_updated_accounts_only.iter() {
-            writeln!(f, "  {revm_updated_account_only:#x}:")?;
-            writeln!(f, "    Was updated in REVM but not in LEVM")?;
-            let initial_account = self
-                .initial_accounts
-                .get(revm_updated_account_only)
-                .cloned()
-                .unwrap_or_default();
-            let updated_account_update = self
-                .revm_account_updates
-                .iter()
-                .find(|account_update| &account_update.address == revm_updated_account_only)
-                .unwrap();
-            let updated_account_storage = updated_account_update
-                .added_storage
-                .iter()
-                .map(|(key, value)| {
-                    let storage_slot = StorageSlot {
-                        original_value: initial_account
-                            .storage
-                            .get(key)
-                            .cloned()
-                            .unwrap_or_default()
-                            .original_value,
-                        current_value: *value,
-                    };
-                    (*key, storage_slot)
-                })
-                .collect();
-            let Some(updated_account_info) = updated_account_update.info.clone() else {
-                continue;
-            };
-            let updated_account = Account::new(
-                updated_account_info.balance,
-                updated_account_update.code.clone().unwrap_or_default(),
-                updated_account_info.nonce,
-                updated_account_storage,
-            );
-            let mut updates = 0;
-            if initial_account.info.balance != updated_account.info.balance {
-                writeln!(
-                    f,
-                    "      Balance updated: {initial_balance} -> {updated_balance}",
-                    initial_balance = initial_account.info.balance,
-                    updated_balance = updated_account.info.balance
-                )?;
-                updates += 1;
-            }
-            if initial_account.info.nonce != updated_account.info.nonce {
-                writeln!(
-                    f,
-                    "      Nonce updated: {initial_nonce} -> {updated_nonce}",
-                    initial_nonce = initial_account.info.nonce,
-                    updated_nonce = updated_account.info.nonce
-                )?;
-                updates += 1;
-            }
-            if initial_account.info.bytecode != updated_account.info.bytecode {
-                writeln!(
-                    f,
-                    "      Code updated: {initial_code}, {updated_code}",
-                    initial_code = if initial_account.info.bytecode.is_empty() {
-                        "was empty".to_string()
-                    } else {
-                        hex::encode(&initial_account.info.bytecode)
-                    },
-                    updated_code = hex::encode(&updated_account.info.bytecode)
-                )?;
-                updates += 1;
-            }
-            for (added_storage_address, added_storage_slot) in updated_account.storage.iter() {
-                writeln!(
-                    f,
-                    "      Storage slot added: {added_storage_address}: {} -> {}",
-                    added_storage_slot.original_value, added_storage_slot.current_value
-                )?;
-                updates += 1;
-            }
-            if updates == 0 {
-                writeln!(f, "      No changes")?;
-            }
-        }
-        for shared_updated_account in self.shared_updated_accounts.iter() {
-            writeln!(f, "  {shared_updated_account:#x}:")?;
-
-            writeln!(f, "    Was updated in both LEVM and REVM")?;
-
-            let levm_updated_account = self
-                .levm_account_updates
-                .iter()
-                .find(|account_update| &account_update.address == shared_updated_account)
-                .unwrap();
-            let revm_updated_account = self
-                .revm_account_updates
-                .iter()
-                .find(|account_update| &account_update.address == shared_updated_account)
-                .unwrap();
-
-            let mut diffs = 0;
-            match (levm_updated_account.removed, revm_updated_account.removed) {
-                (true, false) => {
-                    writeln!(f, "      Removed in LEVM but not in REVM")?;
-                    diffs += 1;
-                }
-                (false, true) => {
-                    writeln!(f, "      Removed in REVM but not in LEVM")?;
-                    diffs += 1;
-                }
-                // Account was removed in both VMs.
-                (false, false) | (true, true) => {}
-            }
-
-            match (&levm_updated_account.code, &revm_updated_account.code) {
-                (None, Some(revm_account_code)) => {
-                    if **revm_account_code != *b"" {
-                        writeln!(f, "      Has code in REVM but not in LEVM")?;
-                        writeln!(f, "      REVM code: {}", hex::encode(revm_account_code))?;
-                        diffs += 1;
-                    }
-                }
-                (Some(levm_account_code), None) => {
-                    if **levm_account_code != *b"" {
-                        writeln!(f, "      Has code in LEVM but not in REVM")?;
-                        writeln!(f, "      LEVM code: {}", hex::encode(levm_account_code))?;
-                        diffs += 1;
-                    }
-                }
-                (Some(levm_account_code), Some(revm_account_code)) => {
-                    if levm_account_code != revm_account_code {
-                        writeln!(f,
-                            "      Code mismatch: LEVM: {levm_account_code}, REVM: {revm_account_code}",
-                            levm_account_code = hex::encode(levm_account_code),
-                            revm_account_code = hex::encode(revm_account_code)
-                        )?;
-                    }
-                }
-                (None, None) => {}
-            }
-
-            match (&levm_updated_account.info, &revm_updated_account.info) {
-                (None, Some(_)) => {
-                    writeln!(
-                        f,
-                        "      Account {shared_updated_account:#x} has info in REVM but not in LEVM"
-                    )?;
-                    diffs += 1;
-                }
-                (Some(levm_account_info), Some(revm_account_info)) => {
-                    if levm_account_info.balance != revm_account_info.balance {
-                        writeln!(f,
-                            "      Balance mismatch: LEVM: {levm_account_balance}, REVM: {revm_account_balance}",
-                            levm_account_balance = levm_account_info.balance,
-                            revm_account_balance = revm_account_info.balance
-                        )?;
-                        diffs += 1;
-                    }
-                    if levm_account_info.nonce != revm_account_info.nonce {
-                        writeln!(f,
-                                "      Nonce mismatch: LEVM: {levm_account_nonce}, REVM: {revm_account_nonce}",
-                                levm_account_nonce = levm_account_info.nonce,
-                                revm_account_nonce = revm_account_info.nonce
-                        )?;
-                        diffs += 1;
-                    }
-                }
-                // We ignore the case (Some(_), None) because we always add the account info to the account update.
-                (Some(_), None) | (None, None) => {}
-            }
-
-            for (levm_key, levm_value) in levm_updated_account.added_storage.iter() {
-                if let Some(revm_value) = revm_updated_account.added_storage.get(levm_key) {
-                    if revm_value != levm_value {
-                        writeln!(f, "      Storage slot added {levm_key} -> value mismatch REVM: {revm_value} LEVM: {levm_value}")?;
-                        diffs += 1;
-                    }
-                } else {
-                    writeln!(f, "      Storage slot added key is in LEVM but not in REVM {levm_key} -> {levm_value}")?;
-                    diffs += 1;
-                }
-            }
-            for (revm_key, revm_value) in revm_updated_account.added_storage.iter() {
-                if !levm_updated_account.added_storage.contains_key(revm_key) {
-                    writeln!(
-                        f,
-                        "      Storage slot added key is in REVM but not in LEVM: {revm_key} -> {revm_value}"
-                    )?;
-                    diffs += 1;
-                }
-            }
-
-            if diffs == 0 {
-                writeln!(f, "      Same changes")?;
-            }
-        }
-        Ok(())
-    }
-}
-
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct TestReRunExecutionReport {
     pub execution_result_mismatch: Option<(TxResult, RevmExecutionResult)>,
@@ -12,11 +12,12 @@ use ethrex_levm::{
     db::CacheDB,
     errors::{TransactionReport, TxValidationError, VMError},
     vm::VM,
-    Environment,
+    Environment, Fork,
 };
 use ethrex_storage::AccountUpdate;
-use ethrex_vm::{db::StoreWrapper, EvmState};
+use ethrex_vm::{db::StoreWrapper, EvmState, SpecId};
 use keccak_hash::keccak;
+
 use std::{collections::HashMap, sync::Arc};
 
 pub fn run_ef_test(test: &EFTest) -> Result<EFTestReport, EFTestRunnerError> {
@@ -94,12 +95,14 @@ pub fn prepare_vm_for_tx(vector: &TestVector, test: &EFTest) -> Result<VM, EFTes
         tx.to.clone(),
         Environment {
             origin: tx.sender,
+            fork: levm_fork(test.fork()),
             refunded_gas: 0,
             gas_limit: tx.gas_limit,
             block_number: test.env.current_number,
             coinbase: test.env.current_coinbase,
             timestamp: test.env.current_timestamp,
             prev_randao: test.env.current_random,
+            difficulty: test.env.current_difficulty,
             chain_id: U256::from(1729),
             base_fee_per_gas: test.env.current_base_fee.unwrap_or_default(),
             gas_price: effective_gas_price(test, &tx)?,
@@ -121,6 +124,26 @@ pub fn prepare_vm_for_tx(vector: &TestVector, test: &EFTest) -> Result<VM, EFTes
     .map_err(|err| EFTestRunnerError::VMInitializationFailed(err.to_string()))
 }
 
+/// LEVM only implements the rules of Cancun onwards, older tests are run with them
+pub fn levm_fork(spec_id: SpecId) -> Fork {
+    match spec_id {
+        SpecId::FRONTIER | SpecId::FRONTIER_THAWING => Fork::Frontier,
+        SpecId::HOMESTEAD | SpecId::DAO_FORK => Fork::Homestead,
+        SpecId::TANGERINE => Fork::TangerineWhistle,
+        SpecId::SPURIOUS_DRAGON => Fork::SpuriousDragon,
+        SpecId::BYZANTIUM => Fork::Byzantium,
+        SpecId::CONSTANTINOPLE => Fork::Constantinople,
+        SpecId::PETERSBURG => Fork::Petersburg,
+        SpecId::ISTANBUL | SpecId::MUIR_GLACIER => Fork::Istanbul,
+        SpecId::BERLIN => Fork::Berlin,
+        SpecId::LONDON | SpecId::ARROW_GLACIER | SpecId::GRAY_GLACIER => Fork::London,
+        SpecId::MERGE => Fork::Paris,
+        SpecId::SHANGHAI => Fork::Shanghai,
+        SpecId::CANCUN => Fork::Cancun,
+        _ => Fork::Prague,
+    }
+}
+
 pub fn ensure_pre_state(evm: &VM, test: &EFTest) -> Result<(), EFTestRunnerError> {
     let world_state = &evm.db;
     for (address, pre_value) in &test.pre.0 {
@@ -1,5 +1,5 @@
 use crate::{
-    report::{ComparisonReport, EFTestReport, TestReRunReport, TestVector},
+    report::{EFTestReport, TestReRunReport, TestVector},
     runner::{
         levm_runner::{self, post_state_root},
         EFTestRunnerError, InternalError,
@@ -14,7 +14,7 @@ use ethrex_levm::{
     Account, StorageSlot,
 };
 use ethrex_storage::{error::StoreError, AccountUpdate};
-use ethrex_vm::{db::StoreWrapper, EvmState, RevmAddress, RevmU256, SpecId};
+use ethrex_vm::{db::StoreWrapper, ComparisonReport, EvmState, RevmAddress, RevmU256, SpecId};
 use revm::{
     db::State,
     inspectors::TracerEip3155 as RevmTracerEip3155,
@@ -24,7 +24,7 @@ use revm::{
     },
     Evm as Revm,
 };
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 pub fn re_run_failed_ef_test(
     test: &EFTest,
@@ -331,34 +331,14 @@ pub fn compare_levm_revm_account_updates(
     initial_accounts
         .entry(test.env.current_coinbase)
         .or_default();
-    let levm_updated_accounts = levm_account_updates
-        .iter()
-        .map(|account_update| account_update.address)
-        .collect::<HashSet<Address>>();
-    let revm_updated_accounts = revm_account_updates
-        .iter()
-        .map(|account_update| account_update.address)
-        .collect::<HashSet<Address>>();
 
-    ComparisonReport {
+    ComparisonReport::new(
         levm_post_state_root,
         revm_post_state_root,
         initial_accounts,
-        levm_account_updates: levm_account_updates.to_vec(),
-        revm_account_updates: revm_account_updates.to_vec(),
-        levm_updated_accounts_only: levm_updated_accounts
-            .difference(&revm_updated_accounts)
-            .cloned()
-            .collect::<HashSet<Address>>(),
-        revm_updated_accounts_only: revm_updated_accounts
-            .difference(&levm_updated_accounts)
-            .cloned()
-            .collect::<HashSet<Address>>(),
-        shared_updated_accounts: levm_updated_accounts
-            .intersection(&revm_updated_accounts)
-            .cloned()
-            .collect::<HashSet<Address>>(),
-    }
+        levm_account_updates.to_vec(),
+        revm_account_updates.to_vec(),
+    )
 }
 
 pub fn _run_ef_test_revm(test: &EFTest) -> Result<EFTestReport, EFTestRunnerError> {
@@ -0,0 +1,21 @@
+use clap::Parser;
+use ef_tests_levm::blockchain::{
+    self,
+    report::{self, TestStatus},
+    BlockchainTestRunnerOptions,
+};
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let opts = BlockchainTestRunnerOptions::parse();
+    let start = std::time::Instant::now();
+    let reports = blockchain::run_blockchain_tests(&opts)?;
+    println!("{}", report::summary_for_shell(&reports, start.elapsed()));
+    if reports
+        .iter()
+        .any(|report| report.status == TestStatus::Failed)
+    {
+        return Err("Some blockchain tests failed".into());
+    }
+    Ok(())
+}
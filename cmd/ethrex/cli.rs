@@ -1,5 +1,6 @@
 use clap::{Arg, ArgAction, Command};
 use ethrex_net::bootnode::BootNode;
+use ethrex_vm::EvmEngine;
 use tracing::Level;
 
 pub fn cli() -> Command {
@@ -28,27 +29,9 @@ pub fn cli() -> Command {
                 .required(false)
                 .action(ArgAction::Set),
         )
-        .arg(
-            Arg::new("authrpc.addr")
-                .long("authrpc.addr")
-                .default_value("localhost")
-                .value_name("ADDRESS")
-                .action(ArgAction::Set),
-        )
-        .arg(
-            Arg::new("authrpc.port")
-                .long("authrpc.port")
-                .default_value("8551")
-                .value_name("PORT")
-                .action(ArgAction::Set),
-        )
-        .arg(
-            Arg::new("authrpc.jwtsecret")
-                .long("authrpc.jwtsecret")
-                .default_value("jwt.hex")
-                .value_name("JWTSECRET_PATH")
-                .action(ArgAction::Set),
-        )
+        .arg(authrpc_addr_arg())
+        .arg(authrpc_port_arg())
+        .arg(authrpc_jwtsecret_arg())
         .arg(
             Arg::new("p2p.addr")
                 .long("p2p.addr")
@@ -83,15 +66,7 @@ pub fn cli() -> Command {
                 .value_name("GENESIS_FILE_PATH")
                 .action(ArgAction::Set),
         )
-        .arg(
-            Arg::new("bootnodes")
-                .long("bootnodes")
-                .value_name("BOOTNODE_LIST")
-                .value_parser(clap::value_parser!(BootNode))
-                .value_delimiter(',')
-                .num_args(1..)
-                .action(ArgAction::Set),
-        )
+        .arg(bootnodes_arg())
         .arg(
             Arg::new("datadir")
                 .long("datadir")
@@ -116,6 +91,23 @@ pub fn cli() -> Command {
                 .required(false)
                 .value_name("BLOCKS_DIR_PATH"),
         )
+        .arg(
+            Arg::new("evm")
+                .long("evm")
+                .required(false)
+                .default_value("revm")
+                .value_name("EVM")
+                .value_parser(clap::value_parser!(EvmEngine))
+                .help("EVM used to execute the imported blocks (revm or levm)")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("evm.differential")
+                .long("evm.differential")
+                .required(false)
+                .help("Also execute every block with the other EVM and log the first transaction in which both diverge")
+                .action(ArgAction::SetTrue),
+        )
         .subcommand(
             Command::new("removedb").about("Remove the database").arg(
                 Arg::new("datadir")
@@ -124,4 +116,173 @@ pub fn cli() -> Command {
                     .action(ArgAction::Set),
             ),
         )
+        .subcommand(
+            Command::new("bootnode")
+                .about("Run a discovery-only node that other nodes can use to find each other")
+                .arg(
+                    Arg::new("discovery.addr")
+                        .long("discovery.addr")
+                        .default_value("0.0.0.0")
+                        .value_name("ADDRESS")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("discovery.port")
+                        .long("discovery.port")
+                        .default_value("30301")
+                        .value_name("PORT")
+                        .action(ArgAction::Set),
+                )
+                .arg(bootnodes_arg())
+                .arg(
+                    Arg::new("nodekey")
+                        .long("nodekey")
+                        .value_name("NODE_KEY_PATH")
+                        .help("Hex encoded private key of the node, generated if it doesn't exist. Defaults to DATADIR/node.key")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("datadir")
+                        .long("datadir")
+                        .value_name("DATABASE_DIRECTORY")
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("crawl")
+                .about("Crawl the discovery network and dump the nodes found as JSON")
+                .arg(
+                    Arg::new("discovery.addr")
+                        .long("discovery.addr")
+                        .default_value("0.0.0.0")
+                        .value_name("ADDRESS")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("discovery.port")
+                        .long("discovery.port")
+                        .default_value("30304")
+                        .value_name("PORT")
+                        .action(ArgAction::Set),
+                )
+                .arg(bootnodes_arg().required(true))
+                .arg(
+                    Arg::new("max_nodes")
+                        .long("max_nodes")
+                        .default_value("1000")
+                        .value_name("MAX_NODES")
+                        .value_parser(clap::value_parser!(usize))
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("OUTPUT_FILE_PATH")
+                        .help("File to write the crawled nodes to. Defaults to stdout")
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("db")
+                .about("Manage the database")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("backup")
+                        .about("Ask the running node to copy its database into a new directory of its filesystem, through the authenticated RPC")
+                        .arg(
+                            Arg::new("destination")
+                                .required(true)
+                                .value_name("BACKUP_DIRECTORY")
+                                .action(ArgAction::Set),
+                        )
+                        .arg(authrpc_addr_arg())
+                        .arg(authrpc_port_arg())
+                        .arg(authrpc_jwtsecret_arg()),
+                )
+                .subcommand(
+                    Command::new("restore")
+                        .about("Create the database from a backup, the data directory must not have one")
+                        .arg(
+                            Arg::new("backup")
+                                .required(true)
+                                .value_name("BACKUP_DIRECTORY")
+                                .action(ArgAction::Set),
+                        )
+                        .arg(datadir_arg()),
+                )
+                .subcommand(
+                    Command::new("stats")
+                        .about("Print the number of entries and the size of each table of the database, which is opened read-only")
+                        .arg(datadir_arg()),
+                ),
+        )
+        .subcommand(
+            Command::new("dump-state")
+                .about("Dump the state at a canonical block as JSON, or as the alloc of a genesis file to fork the chain locally")
+                .arg(
+                    Arg::new("block")
+                        .required(true)
+                        .value_name("BLOCK_NUMBER")
+                        .value_parser(clap::value_parser!(u64))
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .default_value("json")
+                        .value_name("FORMAT")
+                        .value_parser(["json", "alloc"])
+                        .help("json dumps every account with its hashed address, alloc only the accounts with a known address")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("OUTPUT_FILE_PATH")
+                        .help("File to write the dump to. Defaults to stdout")
+                        .action(ArgAction::Set),
+                )
+                .arg(datadir_arg()),
+        )
+}
+
+fn authrpc_addr_arg() -> Arg {
+    Arg::new("authrpc.addr")
+        .long("authrpc.addr")
+        .default_value("localhost")
+        .value_name("ADDRESS")
+        .action(ArgAction::Set)
+}
+
+fn authrpc_port_arg() -> Arg {
+    Arg::new("authrpc.port")
+        .long("authrpc.port")
+        .default_value("8551")
+        .value_name("PORT")
+        .action(ArgAction::Set)
+}
+
+fn authrpc_jwtsecret_arg() -> Arg {
+    Arg::new("authrpc.jwtsecret")
+        .long("authrpc.jwtsecret")
+        .default_value("jwt.hex")
+        .value_name("JWTSECRET_PATH")
+        .action(ArgAction::Set)
+}
+
+fn datadir_arg() -> Arg {
+    Arg::new("datadir")
+        .long("datadir")
+        .value_name("DATABASE_DIRECTORY")
+        .action(ArgAction::Set)
+}
+
+fn bootnodes_arg() -> Arg {
+    Arg::new("bootnodes")
+        .long("bootnodes")
+        .value_name("BOOTNODE_LIST")
+        .value_parser(clap::value_parser!(BootNode))
+        .value_delimiter(',')
+        .num_args(1..)
+        .action(ArgAction::Set)
 }
@@ -2,31 +2,36 @@ use bytes::Bytes;
 use directories::ProjectDirs;
 use ethrex_blockchain::{add_block, fork_choice::apply_fork_choice};
 use ethrex_core::{
-    types::{Block, Genesis},
-    H256,
+    types::{Block, Genesis, GenesisAccount},
+    Address, H256,
 };
+use ethrex_dev::utils::engine_client::EngineClient;
 use ethrex_net::{
     bootnode::BootNode,
-    node_id_from_signing_key, peer_table,
+    crawler, node_id_from_signing_key, peer_table,
     sync::{SyncManager, SyncMode},
     types::Node,
 };
 use ethrex_rlp::decode::RLPDecode;
+use ethrex_rpc::types::state_dump::DumpAccount;
 use ethrex_storage::{EngineType, Store};
+use ethrex_vm::{EvmConfig, EvmEngine};
 use k256::ecdsa::SigningKey;
 use local_ip_address::local_ip;
+use rand::rngs::OsRng;
 use std::{
+    collections::HashMap,
     fs::{self, File},
     future::IntoFuture,
-    io,
-    net::{Ipv4Addr, SocketAddr, ToSocketAddrs},
+    io::{self, BufWriter, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs},
     path::Path,
     str::FromStr as _,
     time::Duration,
 };
 use tokio_util::task::TaskTracker;
 use tracing::{error, info, warn};
-use tracing_subscriber::{filter::Directive, EnvFilter, FmtSubscriber};
+use tracing_subscriber::{filter::Directive, fmt::writer::BoxMakeWriter, EnvFilter, FmtSubscriber};
 mod cli;
 mod decode;
 
@@ -56,11 +61,38 @@ async fn main() {
             Directive::from_str(log_level).expect("Not supported log level provided"),
         )
         .from_env_lossy();
+    // The state dump can be written to stdout, so its logs go to stderr
+    let log_writer = if matches.subcommand_name() == Some("dump-state") {
+        BoxMakeWriter::new(io::stderr)
+    } else {
+        BoxMakeWriter::new(io::stdout)
+    };
     let subscriber = FmtSubscriber::builder()
         .with_env_filter(log_filter)
+        .with_writer(log_writer)
         .finish();
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
+    if let Some(matches) = matches.subcommand_matches("bootnode") {
+        run_bootnode(matches).await;
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("crawl") {
+        run_crawler(matches).await;
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("db") {
+        run_db_command(matches).await;
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("dump-state") {
+        run_dump_state(matches);
+        return;
+    }
+
     let http_addr = matches
         .get_one::<String>("http.addr")
         .expect("http.addr is required");
@@ -120,15 +152,15 @@ async fn main() {
 
     let sync_mode = sync_mode(&matches);
 
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "redb")] {
-            let store = Store::new(&data_dir, EngineType::RedB).expect("Failed to create Store");
-        } else if #[cfg(feature = "libmdbx")] {
-            let store = Store::new(&data_dir, EngineType::Libmdbx).expect("Failed to create Store");
-        } else {
-            let store = Store::new(&data_dir, EngineType::InMemory).expect("Failed to create Store");
-        }
-    }
+    let evm_config = EvmConfig {
+        engine: *matches
+            .get_one::<EvmEngine>("evm")
+            .expect("evm has a default value"),
+        differential: matches.get_flag("evm.differential"),
+    };
+    info!("Executing blocks with {}", evm_config.engine);
+
+    let store = Store::new(&data_dir, engine_type()).expect("Failed to create Store");
 
     let genesis = read_genesis_file(genesis_file_path);
     store
@@ -138,7 +170,7 @@ async fn main() {
     if let Some(chain_rlp_path) = matches.get_one::<String>("import") {
         info!("Importing blocks from chain file: {}", chain_rlp_path);
         let blocks = read_chain_file(chain_rlp_path);
-        import_blocks(&store, &blocks);
+        import_blocks(&store, &blocks, evm_config);
     }
 
     if let Some(blocks_path) = matches.get_one::<String>("import_dir") {
@@ -157,7 +189,7 @@ async fn main() {
             blocks.push(read_block_file(s));
         }
 
-        import_blocks(&store, &blocks);
+        import_blocks(&store, &blocks, evm_config);
     }
 
     let jwt_secret = read_jwtsecret_file(authrpc_jwtsecret);
@@ -170,13 +202,7 @@ async fn main() {
     let signer = SigningKey::from_slice(key_bytes.as_bytes()).unwrap();
     let local_node_id = node_id_from_signing_key(&signer);
 
-    // TODO: If hhtp.addr is 0.0.0.0 we get the local ip as the one of the node, otherwise we use the provided one.
-    // This is fine for now, but we might need to support more options in the future.
-    let p2p_node_ip = if udp_socket_addr.ip() == Ipv4Addr::new(0, 0, 0, 0) {
-        local_ip().expect("Failed to get local ip")
-    } else {
-        udp_socket_addr.ip()
-    };
+    let p2p_node_ip = p2p_node_ip(udp_socket_addr);
 
     let local_p2p_node = Node {
         ip: p2p_node_ip,
@@ -187,7 +213,7 @@ async fn main() {
     // Create Kademlia Table here so we can access it from rpc server (for syncing)
     let peer_table = peer_table(signer.clone());
     // Create SyncManager
-    let syncer = SyncManager::new(peer_table.clone(), sync_mode);
+    let syncer = SyncManager::new(peer_table.clone(), sync_mode, evm_config);
 
     // TODO: Check every module starts properly.
     let tracker = TaskTracker::new();
@@ -198,6 +224,7 @@ async fn main() {
         jwt_secret,
         local_p2p_node,
         syncer,
+        evm_config,
     )
     .into_future();
 
@@ -249,6 +276,338 @@ async fn main() {
     }
 }
 
+/// Runs a discovery-only node, which doesn't need a store nor exposes the RPC API
+async fn run_bootnode(matches: &clap::ArgMatches) {
+    let udp_addr = matches
+        .get_one::<String>("discovery.addr")
+        .expect("discovery.addr is required");
+    let udp_port = matches
+        .get_one::<String>("discovery.port")
+        .expect("discovery.port is required");
+    let udp_socket_addr =
+        parse_socket_addr(udp_addr, udp_port).expect("Failed to parse discovery address and port");
+    let bootnodes: Vec<BootNode> = matches
+        .get_many("bootnodes")
+        .map(Iterator::copied)
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    let data_dir = matches
+        .get_one::<String>("datadir")
+        .map_or(set_datadir(DEFAULT_DATADIR), |datadir| set_datadir(datadir));
+    let node_key_path = matches
+        .get_one::<String>("nodekey")
+        .cloned()
+        .unwrap_or_else(|| format!("{data_dir}/node.key"));
+
+    // The node key is persisted so that the bootnode's enode doesn't change across restarts
+    let signer = read_node_key_file(&node_key_path);
+    let local_node = Node {
+        ip: p2p_node_ip(udp_socket_addr),
+        udp_port: udp_socket_addr.port(),
+        tcp_port: udp_socket_addr.port(),
+        node_id: node_id_from_signing_key(&signer),
+    };
+    info!("Bootnode: {}", local_node.enode_url());
+
+    let peer_table = peer_table(signer.clone());
+    tokio::select! {
+        _ = ethrex_net::start_bootnode(udp_socket_addr, bootnodes, signer, peer_table) => {}
+        _ = tokio::signal::ctrl_c() => {
+            info!("Bootnode shutting down!");
+        }
+    }
+}
+
+/// Crawls the discovery network and writes the nodes found as JSON
+async fn run_crawler(matches: &clap::ArgMatches) {
+    let udp_addr = matches
+        .get_one::<String>("discovery.addr")
+        .expect("discovery.addr is required");
+    let udp_port = matches
+        .get_one::<String>("discovery.port")
+        .expect("discovery.port is required");
+    let udp_socket_addr =
+        parse_socket_addr(udp_addr, udp_port).expect("Failed to parse discovery address and port");
+    let bootnodes: Vec<BootNode> = matches
+        .get_many("bootnodes")
+        .map(Iterator::copied)
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    let max_nodes = *matches
+        .get_one::<usize>("max_nodes")
+        .expect("max_nodes is used with a default value");
+
+    // The crawler uses a new identity on each run
+    let signer = SigningKey::random(&mut OsRng);
+    let nodes = crawler::crawl(udp_socket_addr, signer, bootnodes, max_nodes)
+        .await
+        .expect("Failed to crawl the network");
+    let nodes_json = serde_json::to_string_pretty(&nodes).expect("Failed to serialize nodes");
+    match matches.get_one::<String>("output") {
+        Some(output_path) => {
+            fs::write(output_path, nodes_json).expect("Failed to write crawled nodes file");
+            info!("Wrote {} nodes to {output_path}", nodes.len());
+        }
+        None => println!("{nodes_json}"),
+    }
+}
+
+/// Runs the `db` subcommands. Backups are made by the running node, which holds the database,
+/// the rest run on the database of the data directory
+async fn run_db_command(matches: &clap::ArgMatches) {
+    let (command, matches) = matches.subcommand().expect("db requires a subcommand");
+    if command == "backup" {
+        run_db_backup(matches).await;
+        return;
+    }
+    let data_dir = matches
+        .get_one::<String>("datadir")
+        .map_or(set_datadir(DEFAULT_DATADIR), |datadir| set_datadir(datadir));
+    match command {
+        "restore" => {
+            let backup = matches
+                .get_one::<String>("backup")
+                .expect("backup is required");
+            Store::restore(backup, &data_dir, engine_type())
+                .expect("Failed to restore the database");
+            info!("Restored database from {backup} into {data_dir}");
+        }
+        "stats" => {
+            if !Path::new(&data_dir).exists() {
+                error!("Data directory does not exist: {data_dir}");
+                return;
+            }
+            // The node may be running, so the database isn't written to
+            let store =
+                Store::open_read_only(&data_dir, engine_type()).expect("Failed to open Store");
+            let stats = store.table_stats().expect("Failed to read table stats");
+            println!("{:<28} {:>14} {:>12}", "TABLE", "ENTRIES", "SIZE");
+            for table in stats {
+                let size = table.size.map_or("-".to_string(), human_readable_size);
+                println!("{:<28} {:>14} {:>12}", table.name, table.entries, size);
+            }
+        }
+        other => unreachable!("Unknown db subcommand {other}"),
+    }
+}
+
+/// Sends an authenticated request to the running node to back up its database
+async fn run_db_backup(matches: &clap::ArgMatches) {
+    let destination = matches
+        .get_one::<String>("destination")
+        .expect("destination is required");
+    let authrpc_addr = matches
+        .get_one::<String>("authrpc.addr")
+        .expect("authrpc.addr is required");
+    let authrpc_port = matches
+        .get_one::<String>("authrpc.port")
+        .expect("authrpc.port is required");
+    let authrpc_jwtsecret = matches
+        .get_one::<String>("authrpc.jwtsecret")
+        .expect("authrpc.jwtsecret is required");
+    let authrpc_socket_addr = parse_socket_addr(authrpc_addr, authrpc_port)
+        .expect("Failed to parse authrpc address and port");
+    let mut jwt_secret_file =
+        File::open(authrpc_jwtsecret).expect("Failed to open the node's JWT secret file");
+    let jwt_secret = decode::jwtsecret_file(&mut jwt_secret_file);
+    // The node resolves the destination, so relative paths are made absolute here
+    let destination = std::path::absolute(destination)
+        .expect("Failed to resolve the backup directory")
+        .display()
+        .to_string();
+
+    let client = EngineClient::new(
+        &format!("http://{authrpc_socket_addr}"),
+        hex::encode(jwt_secret).into(),
+    );
+    info!("Backing up the node's database to {destination}");
+    client
+        .admin_backup_database(destination.clone())
+        .await
+        .expect("Failed to back up the database");
+    info!("Backed up the node's database to {destination}");
+}
+
+/// Writes the state at a canonical block, one account at a time so the whole state is never
+/// held in memory
+fn run_dump_state(matches: &clap::ArgMatches) {
+    let block_number = *matches.get_one::<u64>("block").expect("block is required");
+    let format = matches
+        .get_one::<String>("format")
+        .expect("format is used with a default value");
+    let data_dir = matches
+        .get_one::<String>("datadir")
+        .map_or(set_datadir(DEFAULT_DATADIR), |datadir| set_datadir(datadir));
+    if !Path::new(&data_dir).exists() {
+        error!("Data directory does not exist: {data_dir}");
+        return;
+    }
+    let store = Store::new(&data_dir, engine_type()).expect("Failed to open Store");
+    let Some(header) = store
+        .get_block_header(block_number)
+        .expect("Failed to read block header")
+    else {
+        error!("Block {block_number} is not in the canonical chain");
+        return;
+    };
+    // An alloc can't hold accounts and slots by hash, they would be silently left out
+    if format == "alloc"
+        && store
+            .are_preimages_incomplete()
+            .expect("Failed to read preimages status")
+    {
+        error!("The addresses and storage keys of some of the state aren't known, as it was snap synced or stored before preimages were. Dump it with --format json instead");
+        std::process::exit(1);
+    }
+    let output: Box<dyn Write> = match matches.get_one::<String>("output") {
+        Some(output_path) => {
+            Box::new(File::create(output_path).expect("Failed to create output file"))
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut output = BufWriter::new(output);
+    match format.as_str() {
+        "json" => dump_state_json(&store, header.state_root, &mut output),
+        "alloc" => dump_state_alloc(&store, header.state_root, &mut output),
+        other => unreachable!("Unknown dump format {other}"),
+    }
+    .and_then(|_| output.flush())
+    .expect("Failed to write state dump");
+}
+
+/// Writes the state as geth's `dump`, with the accounts keyed by address or by
+/// `pre(<hashed address>)` if the address isn't known
+fn dump_state_json(store: &Store, state_root: H256, output: &mut impl Write) -> io::Result<()> {
+    writeln!(output, "{{\"root\":\"{state_root:#x}\",\"accounts\":{{")?;
+    let mut accounts = 0;
+    for (hashed_address, account) in store.iter_accounts(state_root) {
+        let account = DumpAccount::new(store, state_root, hashed_address, account, true, true)
+            .expect("Failed to read account");
+        let separator = if accounts == 0 { "" } else { ",\n" };
+        let key = serde_json::to_string(&account.range_key())?;
+        let account = serde_json::to_string(&account)?;
+        write!(output, "{separator}{key}:{account}")?;
+        accounts += 1;
+    }
+    writeln!(output, "\n}}}}")?;
+    info!("Dumped {accounts} accounts");
+    Ok(())
+}
+
+/// Writes the state as the `alloc` of a genesis file. Accounts and storage slots whose address
+/// or key isn't known can't be written and are skipped, which only happens if a preimage
+/// failed to be stored
+fn dump_state_alloc(store: &Store, state_root: H256, output: &mut impl Write) -> io::Result<()> {
+    writeln!(output, "{{")?;
+    let (mut accounts, mut skipped_accounts, mut skipped_slots) = (0, 0, 0);
+    for (hashed_address, account) in store.iter_accounts(state_root) {
+        let Some(address) = store
+            .get_preimage(hashed_address)
+            .expect("Failed to read preimage")
+        else {
+            skipped_accounts += 1;
+            continue;
+        };
+        let mut storage = HashMap::new();
+        for (hashed_key, value) in store
+            .iter_storage(state_root, hashed_address)
+            .expect("Failed to read account storage")
+            .into_iter()
+            .flatten()
+        {
+            match store
+                .get_preimage(hashed_key)
+                .expect("Failed to read preimage")
+            {
+                Some(key) => {
+                    storage.insert(H256::from_slice(&key), value);
+                }
+                None => skipped_slots += 1,
+            }
+        }
+        let genesis_account = GenesisAccount {
+            code: store
+                .get_account_code(account.code_hash)
+                .expect("Failed to read account code")
+                .unwrap_or_default(),
+            storage,
+            balance: account.balance,
+            nonce: account.nonce,
+        };
+        let separator = if accounts == 0 { "" } else { ",\n" };
+        let address = Address::from_slice(&address);
+        let genesis_account = serde_json::to_string(&genesis_account)?;
+        write!(output, "{separator}\"{address:#x}\":{genesis_account}")?;
+        accounts += 1;
+    }
+    writeln!(output, "\n}}")?;
+    info!("Dumped {accounts} accounts");
+    if skipped_accounts > 0 || skipped_slots > 0 {
+        warn!("Skipped {skipped_accounts} accounts and {skipped_slots} storage slots without a known address or key");
+    }
+    Ok(())
+}
+
+fn human_readable_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.2} {}", UNITS[unit])
+    }
+}
+
+/// Storage engine the binary was built with
+fn engine_type() -> EngineType {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "redb")] {
+            EngineType::RedB
+        } else if #[cfg(feature = "rocksdb")] {
+            EngineType::RocksDB
+        } else if #[cfg(feature = "libmdbx")] {
+            EngineType::Libmdbx
+        } else {
+            EngineType::InMemory
+        }
+    }
+}
+
+/// Reads the hex encoded node key from the given path, generating and storing a new one if it doesn't exist
+fn read_node_key_file(node_key_path: &str) -> SigningKey {
+    match fs::read_to_string(node_key_path) {
+        Ok(node_key) => {
+            let key_bytes = hex::decode(node_key.trim()).expect("Failed to decode node key");
+            SigningKey::from_slice(&key_bytes).expect("Invalid node key")
+        }
+        Err(_) => {
+            info!("Node key not found in the provided path, generating a new one");
+            let signer = SigningKey::random(&mut OsRng);
+            if let Some(parent) = Path::new(node_key_path).parent() {
+                fs::create_dir_all(parent).expect("Failed to create node key directory");
+            }
+            fs::write(node_key_path, hex::encode(signer.to_bytes()))
+                .expect("Unable to write node key file");
+            signer
+        }
+    }
+}
+
+// TODO: If the address is 0.0.0.0 we get the local ip as the one of the node, otherwise we use the provided one.
+// This is fine for now, but we might need to support more options in the future.
+fn p2p_node_ip(udp_socket_addr: SocketAddr) -> IpAddr {
+    if udp_socket_addr.ip() == Ipv4Addr::new(0, 0, 0, 0) {
+        local_ip().expect("Failed to get local ip")
+    } else {
+        udp_socket_addr.ip()
+    }
+}
+
 fn read_jwtsecret_file(jwt_secret_path: &str) -> Bytes {
     match File::open(jwt_secret_path) {
         Ok(mut file) => decode::jwtsecret_file(&mut file),
@@ -321,7 +680,7 @@ fn set_datadir(datadir: &str) -> String {
         .to_owned()
 }
 
-fn import_blocks(store: &Store, blocks: &Vec<Block>) {
+fn import_blocks(store: &Store, blocks: &Vec<Block>, evm_config: EvmConfig) {
     let size = blocks.len();
     for block in blocks {
         let hash = block.hash();
@@ -329,7 +688,7 @@ fn import_blocks(store: &Store, blocks: &Vec<Block>) {
             "Adding block {} with hash {:#x}.",
             block.header.number, hash
         );
-        let result = add_block(block, store);
+        let result = add_block(block, store, evm_config);
         if let Some(error) = result.err() {
             warn!(
                 "Failed to add block {} with hash {:#x}: {}.",
@@ -11,6 +11,7 @@ use ethrex_rpc::types::block::BlockBodyWrapper;
 use eyre::OptionExt;
 use hex::FromHexError;
 use itertools::Itertools;
+use std::path::PathBuf;
 
 const CLAIM_WITHDRAWAL_SIGNATURE: &str =
     "claimWithdrawal(bytes32,uint256,uint256,uint256,bytes32[])";
@@ -162,6 +163,40 @@ pub(crate) enum Command {
         #[clap(long = "gas-price", required = false)]
         gas_price: Option<u64>,
     },
+    #[clap(about = "Profile the gas spent by a call to a contract")]
+    ProfileCall {
+        #[clap(long = "to")]
+        to: Address,
+        #[clap(long = "calldata", value_parser = decode_hex, required = false, default_value = "")]
+        calldata: Bytes,
+        #[clap(
+            long = "l1",
+            required = false,
+            help = "If set it will profile the call on L1, defaults to L2"
+        )]
+        l1: bool,
+        #[clap(
+            long = "value",
+            value_parser = U256::from_dec_str,
+            default_value = "0",
+            required = false,
+            help = "Value to send in wei"
+        )]
+        value: U256,
+        #[clap(long = "from", required = false)]
+        from: Option<Address>,
+        #[clap(long = "gas-limit", required = false)]
+        gas_limit: Option<u64>,
+        #[clap(long = "gas-price", required = false)]
+        gas_price: Option<u64>,
+        #[clap(
+            long = "output",
+            short = 'o',
+            required = false,
+            help = "File to write the folded stacks to, which can be rendered with flamegraph tools"
+        )]
+        output: Option<PathBuf>,
+    },
     #[clap(about = "Deploy a contract")]
     Deploy {
         #[clap(long = "bytecode", value_parser = decode_hex)]
@@ -546,6 +581,64 @@ impl Command {
 
                 println!("{result}");
             }
+            Command::ProfileCall {
+                to,
+                calldata,
+                l1,
+                value,
+                from,
+                gas_limit,
+                gas_price,
+                output,
+            } => {
+                let client = match l1 {
+                    true => eth_client,
+                    false => rollup_client,
+                };
+
+                let call_profile = client
+                    .profile_call(
+                        to,
+                        calldata,
+                        Overrides {
+                            from,
+                            value: value.into(),
+                            gas_limit,
+                            gas_price,
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+
+                println!(
+                    "{} using {} gas",
+                    if call_profile.success {
+                        "Call succeeded"
+                    } else {
+                        "Call reverted"
+                    },
+                    call_profile.gas_used
+                );
+                println!("Gas per opcode:");
+                for opcode in &call_profile.profile.opcodes {
+                    println!(
+                        "  {:<16}{:>12} gas{:>10} times",
+                        opcode.opcode, opcode.gas, opcode.count
+                    );
+                }
+                println!("Gas per contract:");
+                for contract in &call_profile.profile.contracts {
+                    println!(
+                        "  {:#x}{:>12} gas{:>10} calls",
+                        contract.address, contract.gas, contract.calls
+                    );
+                }
+
+                if let Some(output) = output {
+                    std::fs::write(&output, &call_profile.profile.folded_stacks)?;
+                    println!("Folded stacks written to {}", output.display());
+                }
+            }
             Command::Deploy {
                 bytecode,
                 l1,
@@ -0,0 +1,166 @@
+use bytes::Bytes;
+use ethrex_core::{
+    types::{Genesis, GenesisAccount},
+    Address, H256, U256,
+};
+use ethrex_levm::{db::CacheDB, Account, Fork, StorageSlot};
+use ethrex_storage::AccountUpdate;
+use eyre::WrapErr;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+/// State of a set of accounts, in the `alloc` format used by geth's `evm` and the t8n tools
+pub type Alloc = BTreeMap<Address, AllocAccount>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocAccount {
+    #[serde(
+        default,
+        with = "ethrex_core::serde_utils::bytes",
+        skip_serializing_if = "Bytes::is_empty"
+    )]
+    pub code: Bytes,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<U256, U256>,
+    #[serde(
+        default,
+        deserialize_with = "ethrex_core::serde_utils::u256::deser_hex_or_dec_str"
+    )]
+    pub balance: U256,
+    #[serde(default, with = "ethrex_core::serde_utils::u64::hex_str")]
+    pub nonce: u64,
+}
+
+impl AllocAccount {
+    pub fn is_empty(&self) -> bool {
+        self.balance.is_zero() && self.nonce == 0 && self.code.is_empty()
+    }
+}
+
+impl From<&AllocAccount> for Account {
+    fn from(account: &AllocAccount) -> Self {
+        let storage = account
+            .storage
+            .iter()
+            .map(|(key, value)| {
+                (
+                    u256_to_h256(key),
+                    StorageSlot {
+                        original_value: *value,
+                        current_value: *value,
+                    },
+                )
+            })
+            .collect();
+        Account::new(
+            account.balance,
+            account.code.clone(),
+            account.nonce,
+            storage,
+        )
+    }
+}
+
+/// Accounts of the alloc, to initialize the LEVM database with
+pub fn levm_accounts(alloc: &Alloc) -> Vec<(Address, Account)> {
+    alloc
+        .iter()
+        .map(|(address, account)| (*address, Account::from(account)))
+        .collect()
+}
+
+/// Applies the changes cached by LEVM to the alloc
+/// Accounts emptied by a SELFDESTRUCT are cached as default accounts and removed along with their
+/// storage. Since Spurious Dragon, touched accounts left empty are removed too [EIP-161]
+pub fn apply_cache(alloc: &mut Alloc, cache: &CacheDB, fork: Fork) {
+    for (address, account) in cache {
+        if *account == Account::default() || (fork >= Fork::SpuriousDragon && account.is_empty()) {
+            alloc.remove(address);
+            continue;
+        }
+        let alloc_account = alloc.entry(*address).or_default();
+        alloc_account.balance = account.info.balance;
+        alloc_account.nonce = account.info.nonce;
+        alloc_account.code = account.info.bytecode.clone();
+        for (key, slot) in &account.storage {
+            let key = U256::from_big_endian(key.as_bytes());
+            if slot.current_value.is_zero() {
+                alloc_account.storage.remove(&key);
+            } else {
+                alloc_account.storage.insert(key, slot.current_value);
+            }
+        }
+    }
+}
+
+/// Applies the account updates resulting from executing a block to the alloc
+pub fn apply_account_updates(alloc: &mut Alloc, account_updates: &[AccountUpdate]) {
+    for update in account_updates {
+        if update.removed {
+            alloc.remove(&update.address);
+            continue;
+        }
+        let account = alloc.entry(update.address).or_default();
+        if let Some(info) = &update.info {
+            account.balance = info.balance;
+            account.nonce = info.nonce;
+        }
+        if let Some(code) = &update.code {
+            account.code = code.clone();
+        }
+        for (key, value) in &update.added_storage {
+            let key = U256::from_big_endian(key.as_bytes());
+            if value.is_zero() {
+                account.storage.remove(&key);
+            } else {
+                account.storage.insert(key, *value);
+            }
+        }
+    }
+}
+
+/// Accounts of the alloc, to initialize a store with them as its genesis state
+pub fn genesis_alloc(alloc: &Alloc) -> HashMap<Address, GenesisAccount> {
+    alloc
+        .iter()
+        .map(|(address, account)| {
+            let storage = account
+                .storage
+                .iter()
+                .map(|(key, value)| (u256_to_h256(key), *value))
+                .collect();
+            let account = GenesisAccount {
+                code: account.code.clone(),
+                storage,
+                balance: account.balance,
+                nonce: account.nonce,
+            };
+            (*address, account)
+        })
+        .collect()
+}
+
+/// Root of the state trie holding the accounts of the alloc
+pub fn state_root(alloc: &Alloc) -> H256 {
+    Genesis {
+        alloc: genesis_alloc(alloc),
+        ..Default::default()
+    }
+    .compute_state_root()
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> eyre::Result<T> {
+    let file = std::fs::File::open(path).wrap_err_with(|| format!("Failed to open {path:?}"))?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .wrap_err_with(|| format!("Failed to parse {path:?}"))
+}
+
+pub fn u256_to_h256(value: &U256) -> H256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    H256(bytes)
+}
@@ -0,0 +1,211 @@
+use crate::alloc::read_json;
+use bytes::Bytes;
+use clap::Args;
+use ethrex_core::{
+    types::{
+        compute_transactions_root, compute_withdrawals_root, Block, BlockBody, BlockHeader,
+        Transaction, Withdrawal,
+    },
+    Address, Bloom, H256, U256,
+};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+use eyre::{eyre, WrapErr};
+use keccak_hash::keccak;
+use serde::{Deserialize, Serialize};
+use std::{io::Read, path::PathBuf};
+
+/// Assembles a block from its header and body, taking the inputs and writing the output the way
+/// the `b11r` tool of geth's `evm` does
+#[derive(Args)]
+pub struct Command {
+    #[clap(
+        long = "input.header",
+        default_value = "header.json",
+        help = "Header file, or `stdin` to read all the inputs from stdin"
+    )]
+    input_header: String,
+    #[clap(
+        long = "input.txs",
+        default_value = "txs.rlp",
+        help = "File holding the RLP of the transactions, as written by t8n's `output.body`"
+    )]
+    input_txs: String,
+    #[clap(long = "input.withdrawals")]
+    input_withdrawals: Option<String>,
+    #[clap(
+        long = "input.ommers",
+        help = "File holding the RLP of every ommer header"
+    )]
+    input_ommers: Option<String>,
+    #[clap(
+        long = "output.basedir",
+        default_value = ".",
+        help = "Directory the output file is written to"
+    )]
+    output_basedir: PathBuf,
+    #[clap(
+        long = "output.block",
+        default_value = "block.json",
+        help = "File to write the block to, or `stdout`"
+    )]
+    output_block: String,
+}
+
+/// Header of the block to build, where the roots of the body may be left out to compute them
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HeaderInput {
+    parent_hash: H256,
+    uncle_hash: Option<H256>,
+    #[serde(alias = "miner")]
+    coinbase: Address,
+    state_root: H256,
+    transactions_root: Option<H256>,
+    receipts_root: H256,
+    logs_bloom: Bloom,
+    #[serde(default)]
+    difficulty: U256,
+    number: U256,
+    gas_limit: U256,
+    gas_used: U256,
+    timestamp: U256,
+    #[serde(default, with = "ethrex_core::serde_utils::bytes")]
+    extra_data: Bytes,
+    #[serde(default)]
+    mix_hash: H256,
+    #[serde(default)]
+    nonce: U256,
+    base_fee_per_gas: Option<U256>,
+    withdrawals_root: Option<H256>,
+    blob_gas_used: Option<U256>,
+    excess_blob_gas: Option<U256>,
+    parent_beacon_block_root: Option<H256>,
+}
+
+#[derive(Deserialize)]
+struct StdinInput {
+    header: HeaderInput,
+    #[serde(default)]
+    txs: Option<String>,
+    withdrawals: Option<Vec<Withdrawal>>,
+    #[serde(default)]
+    ommers: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct BlockOutput {
+    #[serde(with = "ethrex_core::serde_utils::bytes")]
+    rlp: Bytes,
+    hash: H256,
+}
+
+impl Command {
+    pub fn run(self) -> eyre::Result<()> {
+        let (header, txs, withdrawals, ommers) = self.read_inputs()?;
+        let transactions = match txs {
+            Some(txs) => Vec::<Transaction>::decode(&decode_hex(&txs)?)
+                .map_err(|error| eyre!("Invalid transactions RLP: {error}"))?,
+            None => vec![],
+        };
+        let ommers = ommers
+            .iter()
+            .map(|ommer| {
+                BlockHeader::decode(&decode_hex(ommer)?)
+                    .map_err(|error| eyre!("Invalid ommer RLP: {error}"))
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let header = BlockHeader {
+            parent_hash: header.parent_hash,
+            ommers_hash: header
+                .uncle_hash
+                .unwrap_or_else(|| keccak(ommers.encode_to_vec())),
+            coinbase: header.coinbase,
+            state_root: header.state_root,
+            transactions_root: header
+                .transactions_root
+                .unwrap_or_else(|| compute_transactions_root(&transactions)),
+            receipts_root: header.receipts_root,
+            logs_bloom: header.logs_bloom,
+            difficulty: header.difficulty,
+            number: header.number.as_u64(),
+            gas_limit: header.gas_limit.as_u64(),
+            gas_used: header.gas_used.as_u64(),
+            timestamp: header.timestamp.as_u64(),
+            extra_data: header.extra_data,
+            prev_randao: header.mix_hash,
+            nonce: header.nonce.as_u64(),
+            base_fee_per_gas: header.base_fee_per_gas.map(|fee| fee.as_u64()),
+            withdrawals_root: header.withdrawals_root.or_else(|| {
+                withdrawals
+                    .as_ref()
+                    .map(|withdrawals| compute_withdrawals_root(withdrawals))
+            }),
+            blob_gas_used: header.blob_gas_used.map(|gas| gas.as_u64()),
+            excess_blob_gas: header.excess_blob_gas.map(|gas| gas.as_u64()),
+            parent_beacon_block_root: header.parent_beacon_block_root,
+        };
+        let block = Block::new(
+            header,
+            BlockBody {
+                transactions,
+                ommers,
+                withdrawals,
+            },
+        );
+        let output = BlockOutput {
+            rlp: block.encode_to_vec().into(),
+            hash: block.header.compute_block_hash(),
+        };
+
+        let contents = serde_json::to_string_pretty(&output)?;
+        match self.output_block.as_str() {
+            "stdout" => println!("{contents}"),
+            file => {
+                let path = self.output_basedir.join(file);
+                std::fs::write(&path, contents)
+                    .wrap_err_with(|| format!("Failed to write {path:?}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn read_inputs(
+        &self,
+    ) -> eyre::Result<(
+        HeaderInput,
+        Option<String>,
+        Option<Vec<Withdrawal>>,
+        Vec<String>,
+    )> {
+        let inputs = [
+            Some(&self.input_header),
+            Some(&self.input_txs),
+            self.input_withdrawals.as_ref(),
+            self.input_ommers.as_ref(),
+        ];
+        if inputs.iter().flatten().any(|input| *input == "stdin") {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            let input: StdinInput =
+                serde_json::from_str(&input).wrap_err("Failed to parse the input from stdin")?;
+            return Ok((input.header, input.txs, input.withdrawals, input.ommers));
+        }
+        let header = read_json(self.input_header.as_ref())?;
+        let txs = read_json(self.input_txs.as_ref())?;
+        let withdrawals = match &self.input_withdrawals {
+            Some(path) => read_json(path.as_ref())?,
+            None => None,
+        };
+        let ommers = match &self.input_ommers {
+            Some(path) => read_json(path.as_ref())?,
+            None => vec![],
+        };
+        Ok((header, txs, withdrawals, ommers))
+    }
+}
+
+fn decode_hex(s: &str) -> eyre::Result<Vec<u8>> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).wrap_err("Invalid hex")
+}
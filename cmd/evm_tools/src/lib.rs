@@ -0,0 +1,3 @@
+pub mod alloc;
+pub mod b11r;
+pub mod t8n;
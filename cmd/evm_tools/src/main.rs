@@ -0,0 +1,25 @@
+use clap::{Parser, Subcommand};
+use evm_tools::{b11r, t8n};
+
+#[derive(Parser)]
+#[command(name = "ethrex-evm", author, version, about = "Implements the state transition and block building tools used by execution-spec-tests to fill tests", long_about = None)]
+struct EvmCLI {
+    #[command(subcommand)]
+    command: EvmCommand,
+}
+
+#[derive(Subcommand)]
+enum EvmCommand {
+    #[clap(about = "Execute a state transition with ethrex's block execution.")]
+    T8n(t8n::Command),
+    #[clap(about = "Build a block from its header and body.")]
+    B11r(b11r::Command),
+}
+
+fn main() -> eyre::Result<()> {
+    let EvmCLI { command } = EvmCLI::parse();
+    match command {
+        EvmCommand::T8n(cmd) => cmd.run(),
+        EvmCommand::B11r(cmd) => cmd.run(),
+    }
+}
@@ -0,0 +1,626 @@
+use crate::alloc::{
+    apply_account_updates, genesis_alloc, read_json, state_root, u256_to_h256, Alloc,
+};
+use bytes::Bytes;
+use clap::Args;
+use ethrex_core::{
+    types::{
+        calculate_base_fee_per_gas, compute_receipts_root, compute_transactions_root,
+        compute_withdrawals_root, Block, BlockBody, BlockHeader, ChainConfig, Genesis, Log,
+        Receipt, Signable, Transaction, TxKind, Withdrawal, GWEI_TO_WEI,
+    },
+    Address, Bloom, H160, H256, U256,
+};
+use ethrex_levm::{vm::VM, Fork};
+use ethrex_rlp::encode::RLPEncode;
+use ethrex_storage::{EngineType, Store};
+use ethrex_vm::{evm_state, execute_transition, EvmEngine};
+use eyre::{eyre, WrapErr};
+use keccak_hash::keccak;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Contract storing the parent beacon block roots [EIP-4788]
+const BEACON_ROOTS_ADDRESS: H160 = H160([
+    0x00, 0x0f, 0x3d, 0xf6, 0xd7, 0x32, 0x80, 0x7e, 0xf1, 0x31, 0x9f, 0xb7, 0xb8, 0xbb, 0x85, 0x22,
+    0xd0, 0xbe, 0xac, 0x02,
+]);
+const HISTORY_BUFFER_LENGTH: u64 = 8191;
+const GAS_PER_BLOB: u64 = 131072;
+const TARGET_BLOB_GAS_PER_BLOCK: u64 = 393216;
+
+/// Inputs and outputs of the `t8n` tool of geth's `evm`, which execution-spec-tests uses to fill
+/// tests
+#[derive(Args)]
+pub struct T8nArgs {
+    #[clap(
+        long = "input.alloc",
+        default_value = "alloc.json",
+        help = "Prestate alloc file, or `stdin` to read all the inputs from stdin"
+    )]
+    pub input_alloc: String,
+    #[clap(long = "input.env", default_value = "env.json")]
+    pub input_env: String,
+    #[clap(long = "input.txs", default_value = "txs.json")]
+    pub input_txs: String,
+    #[clap(
+        long = "output.basedir",
+        default_value = ".",
+        help = "Directory the output files are written to"
+    )]
+    pub output_basedir: PathBuf,
+    #[clap(
+        long = "output.result",
+        default_value = "result.json",
+        help = "File to write the result to, or `stdout`"
+    )]
+    pub output_result: String,
+    #[clap(
+        long = "output.alloc",
+        default_value = "alloc.json",
+        help = "File to write the poststate alloc to, or `stdout`"
+    )]
+    pub output_alloc: String,
+    #[clap(
+        long = "output.body",
+        help = "File to write the RLP of the included transactions to, or `stdout`"
+    )]
+    pub output_body: Option<String>,
+    #[clap(long = "state.fork", default_value = "Cancun")]
+    pub fork: Fork,
+    #[clap(long = "state.chainid", default_value_t = 1)]
+    pub chain_id: u64,
+    #[clap(
+        long = "state.reward",
+        default_value_t = -1,
+        allow_negative_numbers = true,
+        help = "Block reward in wei, negative to disable rewards"
+    )]
+    pub reward: i128,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Env {
+    pub current_coinbase: Address,
+    pub current_gas_limit: U256,
+    pub current_number: U256,
+    pub current_timestamp: U256,
+    pub current_difficulty: Option<U256>,
+    pub current_random: Option<U256>,
+    pub current_base_fee: Option<U256>,
+    pub parent_base_fee: Option<U256>,
+    pub parent_gas_used: Option<U256>,
+    pub parent_gas_limit: Option<U256>,
+    pub current_excess_blob_gas: Option<U256>,
+    pub parent_excess_blob_gas: Option<U256>,
+    pub parent_blob_gas_used: Option<U256>,
+    pub parent_beacon_block_root: Option<H256>,
+    #[serde(default)]
+    pub block_hashes: BTreeMap<U256, H256>,
+    #[serde(default)]
+    pub ommers: Vec<Ommer>,
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+#[derive(Deserialize)]
+pub struct Ommer {
+    pub delta: u64,
+    pub address: Address,
+}
+
+#[derive(Deserialize)]
+struct StdinInput {
+    alloc: Alloc,
+    env: Env,
+    #[serde(default)]
+    txs: Vec<Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionResult {
+    pub state_root: H256,
+    pub tx_root: H256,
+    pub receipts_root: H256,
+    pub logs_hash: H256,
+    pub logs_bloom: Bloom,
+    pub receipts: Vec<ReceiptResult>,
+    pub rejected: Vec<RejectedTx>,
+    pub current_difficulty: Option<U256>,
+    pub gas_used: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_base_fee: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_excess_blob_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U256>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptResult {
+    #[serde(rename = "type")]
+    pub tx_type: U256,
+    #[serde(with = "ethrex_core::serde_utils::bytes")]
+    pub root: Bytes,
+    pub status: U256,
+    pub cumulative_gas_used: U256,
+    pub logs_bloom: Bloom,
+    pub logs: Vec<LogResult>,
+    pub transaction_hash: H256,
+    pub contract_address: Address,
+    pub gas_used: U256,
+    pub block_hash: H256,
+    pub transaction_index: U256,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogResult {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    #[serde(with = "ethrex_core::serde_utils::bytes")]
+    pub data: Bytes,
+    pub block_number: U256,
+    pub transaction_hash: H256,
+    pub transaction_index: U256,
+    pub block_hash: H256,
+    pub log_index: U256,
+    pub removed: bool,
+}
+
+#[derive(Serialize)]
+pub struct RejectedTx {
+    pub index: usize,
+    pub error: String,
+}
+
+/// Transactions included in the block being built, along with their receipts
+#[derive(Default)]
+pub struct BlockReceipts {
+    pub transactions: Vec<Transaction>,
+    pub receipts: Vec<Receipt>,
+    pub results: Vec<ReceiptResult>,
+    pub logs: Vec<Log>,
+    pub logs_bloom: Bloom,
+    pub cumulative_gas_used: u64,
+    pub blob_gas_used: u64,
+}
+
+impl BlockReceipts {
+    /// Includes an executed transaction in the block
+    pub fn push(
+        &mut self,
+        tx: Transaction,
+        block_number: u64,
+        succeeded: bool,
+        gas_used: u64,
+        logs: Vec<Log>,
+        contract_address: Address,
+    ) {
+        self.cumulative_gas_used += gas_used;
+        self.blob_gas_used += GAS_PER_BLOB * tx.blob_versioned_hashes().len() as u64;
+        let receipt = Receipt::new(tx.tx_type(), succeeded, self.cumulative_gas_used, logs);
+        self.logs_bloom.accrue_bloom(&receipt.bloom);
+
+        let tx_hash = tx.compute_hash();
+        let transaction_index = U256::from(self.results.len());
+        let logs = receipt
+            .logs
+            .iter()
+            .map(|log| {
+                let log_index = U256::from(self.logs.len());
+                self.logs.push(log.clone());
+                LogResult {
+                    address: log.address,
+                    topics: log.topics.clone(),
+                    data: log.data.clone(),
+                    block_number: block_number.into(),
+                    transaction_hash: tx_hash,
+                    transaction_index,
+                    block_hash: H256::zero(),
+                    log_index,
+                    removed: false,
+                }
+            })
+            .collect();
+        self.results.push(ReceiptResult {
+            tx_type: (tx.tx_type() as u8).into(),
+            root: Bytes::new(),
+            status: u8::from(succeeded).into(),
+            cumulative_gas_used: self.cumulative_gas_used.into(),
+            logs_bloom: receipt.bloom,
+            logs,
+            transaction_hash: tx_hash,
+            contract_address,
+            gas_used: gas_used.into(),
+            block_hash: H256::zero(),
+            transaction_index,
+        });
+        self.receipts.push(receipt);
+        self.transactions.push(tx);
+    }
+}
+
+/// Executes a state transition with ethrex's block execution, the way it's done when importing
+/// blocks
+#[derive(Args)]
+pub struct Command {
+    #[command(flatten)]
+    args: T8nArgs,
+    #[clap(
+        long = "engine",
+        default_value = "revm",
+        help = "EVM to execute the block with"
+    )]
+    engine: EvmEngine,
+}
+
+impl Command {
+    pub fn run(self) -> eyre::Result<()> {
+        let args = &self.args;
+        let (mut alloc, env, txs) = args.read_inputs()?;
+        let mut header = args.block_header(&env)?;
+
+        // The prestate is stored as the genesis state of a chain with every fork up to the
+        // requested one active since genesis
+        let store = Store::new("", EngineType::InMemory)?;
+        let genesis = Genesis {
+            config: chain_config(args.fork, args.chain_id)?,
+            alloc: genesis_alloc(&alloc),
+            ..Default::default()
+        };
+        let genesis_hash = genesis.get_block().hash();
+        store.add_initial_state(genesis)?;
+        for (number, hash) in &env.block_hashes {
+            store.set_canonical_block(number.as_u64(), *hash)?;
+        }
+        header.parent_hash = genesis_hash;
+
+        let mut rejected = Vec::new();
+        let mut indexes = Vec::new();
+        let mut transactions = Vec::new();
+        for (index, tx) in txs.into_iter().enumerate() {
+            match parse_transaction(tx)? {
+                Ok(tx) => {
+                    indexes.push(index);
+                    transactions.push(tx);
+                }
+                Err(error) => rejected.push(RejectedTx { index, error }),
+            }
+        }
+        let block = Block::new(
+            header,
+            BlockBody {
+                transactions,
+                ommers: vec![],
+                withdrawals: env.withdrawals.clone(),
+            },
+        );
+        let mut state = evm_state(store, genesis_hash);
+        let transition = execute_transition(self.engine, &block, &mut state)?;
+
+        // Indexes in the transition refer to the valid transactions, not to the inputs
+        let original_index = |index: usize| {
+            indexes
+                .get(index)
+                .copied()
+                .ok_or_else(|| eyre!("Transaction {index} isn't part of the block"))
+        };
+        for rejection in transition.rejected {
+            rejected.push(RejectedTx {
+                index: original_index(rejection.index)?,
+                error: rejection.error,
+            });
+        }
+        rejected.sort_by_key(|rejection| rejection.index);
+
+        let mut receipts = BlockReceipts::default();
+        for (index, receipt) in transition.included.into_iter().zip(transition.receipts) {
+            let tx = block
+                .body
+                .transactions
+                .get(index)
+                .cloned()
+                .ok_or_else(|| eyre!("Transaction {index} isn't part of the block"))?;
+            let contract_address = match tx.to() {
+                TxKind::Create => VM::calculate_create_address(tx.sender(), tx.nonce())?,
+                TxKind::Call(_) => Address::zero(),
+            };
+            let gas_used = receipt.cumulative_gas_used - receipts.cumulative_gas_used;
+            receipts.push(
+                tx,
+                block.header.number,
+                receipt.succeeded,
+                gas_used,
+                receipt.logs,
+                contract_address,
+            );
+        }
+
+        apply_account_updates(&mut alloc, &transition.account_updates);
+        args.finish(alloc, &env, &block.header, receipts, rejected)
+    }
+}
+
+/// Configuration of a chain with every fork up to the given one active since genesis
+fn chain_config(fork: Fork, chain_id: u64) -> eyre::Result<ChainConfig> {
+    if !(Fork::Paris..=Fork::Cancun).contains(&fork) {
+        return Err(eyre!(
+            "Block execution supports the forks from Paris to Cancun, got {fork:?}"
+        ));
+    }
+    let since_genesis = |activation: Fork| (fork >= activation).then_some(0);
+    Ok(ChainConfig {
+        chain_id,
+        homestead_block: Some(0),
+        eip150_block: Some(0),
+        eip155_block: Some(0),
+        eip158_block: Some(0),
+        byzantium_block: Some(0),
+        constantinople_block: Some(0),
+        petersburg_block: Some(0),
+        istanbul_block: Some(0),
+        berlin_block: Some(0),
+        london_block: Some(0),
+        merge_netsplit_block: Some(0),
+        shanghai_time: since_genesis(Fork::Shanghai),
+        cancun_time: since_genesis(Fork::Cancun),
+        terminal_total_difficulty: Some(0),
+        terminal_total_difficulty_passed: true,
+        ..Default::default()
+    })
+}
+
+impl T8nArgs {
+    pub fn read_inputs(&self) -> eyre::Result<(Alloc, Env, Vec<Value>)> {
+        if [&self.input_alloc, &self.input_env, &self.input_txs].contains(&&"stdin".to_string()) {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            let input: StdinInput =
+                serde_json::from_str(&input).wrap_err("Failed to parse the input from stdin")?;
+            return Ok((input.alloc, input.env, input.txs));
+        }
+        let txs: Value = read_json(Path::new(&self.input_txs))?;
+        let txs = match txs {
+            Value::Array(txs) => txs,
+            Value::Null => vec![],
+            _ => return Err(eyre!("{} must hold a list of transactions", self.input_txs)),
+        };
+        Ok((
+            read_json(Path::new(&self.input_alloc))?,
+            read_json(Path::new(&self.input_env))?,
+            txs,
+        ))
+    }
+
+    /// Header of the block being built, with the values derived from the parent block filled in
+    pub fn block_header(&self, env: &Env) -> eyre::Result<BlockHeader> {
+        let gas_limit = env.current_gas_limit.as_u64();
+        let base_fee_per_gas = match (env.current_base_fee, env.parent_base_fee) {
+            _ if self.fork < Fork::London => None,
+            (Some(base_fee), _) => Some(base_fee.as_u64()),
+            (None, Some(parent_base_fee)) => Some(
+                calculate_base_fee_per_gas(
+                    gas_limit,
+                    env.parent_gas_limit.unwrap_or_default().as_u64(),
+                    env.parent_gas_used.unwrap_or_default().as_u64(),
+                    parent_base_fee.as_u64(),
+                )
+                .ok_or_else(|| eyre!("Invalid gas limit with respect to the parent's"))?,
+            ),
+            (None, None) => return Err(eyre!("currentBaseFee is required since London")),
+        };
+        let excess_blob_gas = match (
+            env.current_excess_blob_gas,
+            env.parent_excess_blob_gas,
+            env.parent_blob_gas_used,
+        ) {
+            _ if self.fork < Fork::Cancun => None,
+            (Some(excess_blob_gas), _, _) => Some(excess_blob_gas.as_u64()),
+            (None, Some(parent_excess_blob_gas), Some(parent_blob_gas_used)) => Some(
+                (parent_excess_blob_gas + parent_blob_gas_used)
+                    .as_u64()
+                    .saturating_sub(TARGET_BLOB_GAS_PER_BLOCK),
+            ),
+            _ => Some(0),
+        };
+        let prev_randao = match env.current_random {
+            Some(random) => u256_to_h256(&random),
+            None if self.fork >= Fork::Paris => {
+                return Err(eyre!("currentRandom is required since Paris"))
+            }
+            None => H256::zero(),
+        };
+        Ok(BlockHeader {
+            coinbase: env.current_coinbase,
+            difficulty: env.current_difficulty.unwrap_or_default(),
+            number: env.current_number.as_u64(),
+            gas_limit,
+            timestamp: env.current_timestamp.as_u64(),
+            prev_randao,
+            base_fee_per_gas,
+            excess_blob_gas,
+            parent_beacon_block_root: env.parent_beacon_block_root,
+            ..Default::default()
+        })
+    }
+
+    /// Pays the block rewards, then writes the poststate, the result of the transition and the
+    /// body of the block
+    pub fn finish(
+        &self,
+        mut alloc: Alloc,
+        env: &Env,
+        header: &BlockHeader,
+        receipts: BlockReceipts,
+        rejected: Vec<RejectedTx>,
+    ) -> eyre::Result<()> {
+        if let Ok(reward) = u128::try_from(self.reward) {
+            reward_miners(&mut alloc, env, reward.into(), self.fork);
+        }
+        let result = ExecutionResult {
+            state_root: state_root(&alloc),
+            tx_root: compute_transactions_root(&receipts.transactions),
+            receipts_root: compute_receipts_root(&receipts.receipts),
+            logs_hash: keccak(receipts.logs.encode_to_vec()),
+            logs_bloom: receipts.logs_bloom,
+            receipts: receipts.results,
+            rejected,
+            current_difficulty: env.current_difficulty,
+            gas_used: receipts.cumulative_gas_used.into(),
+            current_base_fee: header.base_fee_per_gas.map(U256::from),
+            withdrawals_root: env
+                .withdrawals
+                .as_ref()
+                .map(|withdrawals| compute_withdrawals_root(withdrawals)),
+            current_excess_blob_gas: header.excess_blob_gas.map(U256::from),
+            blob_gas_used: (self.fork >= Fork::Cancun).then_some(receipts.blob_gas_used.into()),
+        };
+        let body = format!("0x{}", hex::encode(receipts.transactions.encode_to_vec()));
+        self.write_outputs(alloc, result, body)
+    }
+
+    fn write_outputs(
+        &self,
+        alloc: Alloc,
+        result: ExecutionResult,
+        body: String,
+    ) -> eyre::Result<()> {
+        let mut stdout = serde_json::Map::new();
+        let outputs = [
+            (
+                "alloc",
+                Some(&self.output_alloc),
+                serde_json::to_value(alloc)?,
+            ),
+            (
+                "result",
+                Some(&self.output_result),
+                serde_json::to_value(result)?,
+            ),
+            ("body", self.output_body.as_ref(), Value::String(body)),
+        ];
+        for (name, output, value) in outputs {
+            match output.map(String::as_str) {
+                None => {}
+                Some("stdout") => {
+                    stdout.insert(name.to_string(), value);
+                }
+                Some(file) => {
+                    let path = self.output_basedir.join(file);
+                    let contents = serde_json::to_string_pretty(&value)?;
+                    std::fs::write(&path, contents)
+                        .wrap_err_with(|| format!("Failed to write {path:?}"))?;
+                }
+            }
+        }
+        if !stdout.is_empty() {
+            println!("{}", serde_json::to_string_pretty(&stdout)?);
+        }
+        Ok(())
+    }
+}
+
+/// Parses a transaction in the format used by execution-spec-tests, signing it with its
+/// `secretKey` if it doesn't carry a signature. Invalid transactions are returned as errors to
+/// reject them instead of failing the whole transition
+pub fn parse_transaction(tx: Value) -> eyre::Result<Result<Transaction, String>> {
+    let Value::Object(mut tx) = tx else {
+        return Err(eyre!("Transactions must be JSON objects"));
+    };
+    if let Some(data) = tx.remove("data") {
+        tx.entry("input").or_insert(data);
+    }
+    if !tx.contains_key("type") {
+        tx.insert("type".to_string(), Value::String("0x0".to_string()));
+    }
+    let secret_key = match tx.remove("secretKey") {
+        Some(secret_key) => Some(serde_json::from_value::<H256>(secret_key)?),
+        None => None,
+    };
+    let is_signed = ["r", "s"].iter().any(|field| {
+        tx.get(*field)
+            .and_then(|value| serde_json::from_value::<U256>(value.clone()).ok())
+            .is_some_and(|value| !value.is_zero())
+    });
+    if !is_signed {
+        for field in ["v", "r", "s"] {
+            tx.insert(field.to_string(), Value::String("0x0".to_string()));
+        }
+    }
+    // Typed transactions carry the parity of the signature, which may be given as `v`
+    if let Some(v) = tx.get("v").cloned() {
+        tx.entry("yParity").or_insert(v);
+    }
+    let mut tx = match serde_json::from_value::<Transaction>(Value::Object(tx)) {
+        Ok(tx) => tx,
+        Err(error) => return Ok(Err(error.to_string())),
+    };
+    if !is_signed {
+        let secret_key =
+            secret_key.ok_or_else(|| eyre!("Unsigned transaction without secretKey"))?;
+        let secret_key = secp256k1::SecretKey::from_slice(secret_key.as_bytes())?;
+        tx.sign_inplace(&secret_key);
+        // Legacy transactions are signed without replay protection
+        if let Transaction::LegacyTransaction(tx) = &mut tx {
+            tx.v += U256::from(27);
+        }
+    }
+    Ok(Ok(tx))
+}
+
+/// Stores the parent beacon block root the way the system call made at the start of the block
+/// does [EIP-4788]
+pub fn store_beacon_root(alloc: &mut Alloc, timestamp: u64, root: H256) {
+    let Some(contract) = alloc.get_mut(&BEACON_ROOTS_ADDRESS) else {
+        return;
+    };
+    if contract.code.is_empty() {
+        return;
+    }
+    let timestamp_slot = timestamp % HISTORY_BUFFER_LENGTH;
+    let root_slot = timestamp_slot + HISTORY_BUFFER_LENGTH;
+    contract
+        .storage
+        .insert(timestamp_slot.into(), timestamp.into());
+    contract
+        .storage
+        .insert(root_slot.into(), U256::from_big_endian(root.as_bytes()));
+}
+
+/// Credits the withdrawals of the block to their recipients [EIP-4895]
+pub fn apply_withdrawals(alloc: &mut Alloc, withdrawals: &[Withdrawal]) {
+    for withdrawal in withdrawals {
+        if withdrawal.amount > 0 {
+            alloc.entry(withdrawal.address).or_default().balance +=
+                U256::from(withdrawal.amount) * U256::from(GWEI_TO_WEI);
+        }
+    }
+}
+
+/// Pays the block reward to the coinbase and the ommers' miners, as done before the merge
+fn reward_miners(alloc: &mut Alloc, env: &Env, reward: U256, fork: Fork) {
+    let ommers = U256::from(env.ommers.len());
+    let mut rewards = vec![(env.current_coinbase, reward + reward / 32 * ommers)];
+    for ommer in &env.ommers {
+        let ommer_reward = U256::from(8u64.saturating_sub(ommer.delta)) * reward / 8;
+        rewards.push((ommer.address, ommer_reward));
+    }
+    for (address, reward) in rewards {
+        let account = alloc.entry(address).or_default();
+        account.balance += reward;
+        // A zero reward touches the account, which is removed if empty [EIP-161]
+        if fork >= Fork::SpuriousDragon && account.is_empty() {
+            alloc.remove(&address);
+        }
+    }
+}
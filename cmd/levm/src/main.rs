@@ -0,0 +1,32 @@
+use clap::{Parser, Subcommand};
+
+mod run;
+mod statetest;
+mod t8n;
+mod trace;
+
+#[derive(Parser)]
+#[command(name = "levm", author, version, about = "Runs EVM bytecode, state tests and state transitions with LEVM", long_about = None)]
+struct LevmCLI {
+    #[command(subcommand)]
+    command: LevmCommand,
+}
+
+#[derive(Subcommand)]
+enum LevmCommand {
+    #[clap(about = "Run arbitrary bytecode.")]
+    Run(run::Command),
+    #[clap(about = "Run the tests of a single state test file.")]
+    Statetest(statetest::Command),
+    #[clap(about = "Execute a state transition, compatible with execution-spec-tests filling.")]
+    T8n(t8n::Command),
+}
+
+fn main() -> eyre::Result<()> {
+    let LevmCLI { command } = LevmCLI::parse();
+    match command {
+        LevmCommand::Run(cmd) => cmd.run(),
+        LevmCommand::Statetest(cmd) => cmd.run(),
+        LevmCommand::T8n(cmd) => cmd.run(),
+    }
+}
@@ -0,0 +1,187 @@
+use crate::trace::{write_trace, TraceSummary};
+use bytes::Bytes;
+use clap::Args;
+use ethrex_core::{types::TxKind, Address, U256};
+use ethrex_levm::{
+    db::{CacheDB, Db},
+    errors::TxResult,
+    tracer::Tracer,
+    vm::VM,
+    Environment, Fork,
+};
+use evm_tools::alloc::{apply_cache, levm_accounts, read_json, Alloc};
+use eyre::{eyre, WrapErr};
+use serde::Serialize;
+use std::{path::PathBuf, sync::Arc};
+
+#[derive(Args)]
+pub struct Command {
+    #[clap(help = "Bytecode to run, in hex", required_unless_present = "codefile")]
+    code: Option<String>,
+    #[clap(
+        long = "codefile",
+        conflicts_with = "code",
+        help = "File holding the bytecode to run, in hex"
+    )]
+    codefile: Option<PathBuf>,
+    #[clap(long = "input", value_parser = decode_hex, default_value = "", help = "Calldata, in hex")]
+    input: Bytes,
+    #[clap(
+        long = "value",
+        value_parser = U256::from_dec_str,
+        default_value = "0",
+        help = "Value to send in wei"
+    )]
+    value: U256,
+    #[clap(long = "gas", default_value_t = 10_000_000)]
+    gas: u64,
+    #[clap(
+        long = "price",
+        value_parser = U256::from_dec_str,
+        default_value = "0",
+        help = "Gas price in wei"
+    )]
+    price: U256,
+    #[clap(
+        long = "sender",
+        default_value = "0x000000000000000000000000000073656e646572"
+    )]
+    sender: Address,
+    #[clap(
+        long = "receiver",
+        default_value = "0x0000000000000000000000007265636569766572",
+        help = "Address the code is run at"
+    )]
+    receiver: Address,
+    #[clap(
+        long = "prestate",
+        help = "Alloc file with the state to run the code over"
+    )]
+    prestate: Option<PathBuf>,
+    #[clap(long = "fork", default_value = "Cancun")]
+    fork: Fork,
+    #[clap(
+        long = "create",
+        help = "Run the code as initcode, deploying the code it returns"
+    )]
+    create: bool,
+    #[clap(
+        long = "trace",
+        help = "Print an EIP-3155 trace of the execution to stderr"
+    )]
+    trace: bool,
+    #[clap(long = "json", help = "Print the result as JSON")]
+    json: bool,
+    #[clap(
+        long = "dump",
+        help = "Print the state after the execution as an alloc"
+    )]
+    dump: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunResult {
+    #[serde(with = "ethrex_core::serde_utils::bytes")]
+    output: Bytes,
+    #[serde(with = "ethrex_core::serde_utils::u64::hex_str")]
+    gas_used: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Command {
+    /// Runs the code over the prestate the way geth's `evm run` does: the code is executed
+    /// directly, without charging the intrinsic gas nor validating the sender
+    pub fn run(self) -> eyre::Result<()> {
+        let code = match (&self.code, &self.codefile) {
+            (Some(code), _) => decode_hex(code.trim())?,
+            (None, Some(path)) => {
+                let code = std::fs::read_to_string(path)
+                    .wrap_err_with(|| format!("Failed to read {path:?}"))?;
+                decode_hex(code.trim())?
+            }
+            (None, None) => return Err(eyre!("No code to run")),
+        };
+        let mut alloc: Alloc = match &self.prestate {
+            Some(path) => read_json(path)?,
+            None => Alloc::new(),
+        };
+        let (tx_kind, calldata) = if self.create {
+            (TxKind::Create, code.clone())
+        } else {
+            alloc.entry(self.receiver).or_default().code = code.clone();
+            (TxKind::Call(self.receiver), self.input.clone())
+        };
+
+        let mut db = Db::new();
+        db.add_accounts(levm_accounts(&alloc));
+        let env = Environment {
+            fork: self.fork,
+            gas_limit: self.gas,
+            block_gas_limit: self.gas,
+            gas_price: self.price,
+            ..Environment::default_from_address(self.sender)
+        };
+        let mut vm = VM::new(
+            tx_kind,
+            env,
+            self.value,
+            calldata,
+            Arc::new(db),
+            CacheDB::default(),
+            vec![],
+        )?;
+        if self.trace {
+            vm.tracer = Some(Tracer::default());
+        }
+
+        let mut call_frame = vm
+            .call_frames
+            .pop()
+            .ok_or_else(|| eyre!("LEVM didn't create the initial call frame"))?;
+        if self.create {
+            call_frame.assign_bytecode(code);
+            call_frame.calldata = Bytes::new();
+        } else {
+            vm.increase_account_balance(self.receiver, self.value)?;
+        }
+        vm.decrease_account_balance(self.sender, self.value)?;
+        let report = vm.execute(&mut call_frame)?;
+
+        let result = RunResult {
+            output: report.output.clone(),
+            gas_used: report.gas_used,
+            error: match &report.result {
+                TxResult::Success => None,
+                TxResult::Revert(error) => Some(error.to_string()),
+            },
+        };
+        if let Some(tracer) = vm.tracer.take() {
+            let summary = TraceSummary {
+                output: result.output.clone(),
+                gas_used: result.gas_used,
+                error: result.error.clone(),
+            };
+            write_trace(&mut std::io::stderr().lock(), &tracer.steps, &summary)?;
+        }
+
+        if self.json {
+            println!("{}", serde_json::to_string(&result)?);
+        } else {
+            println!("0x{}", hex::encode(&result.output));
+            if let Some(error) = &result.error {
+                eprintln!("error: {error}");
+            }
+        }
+        if self.dump {
+            apply_cache(&mut alloc, &vm.cache, self.fork);
+            println!("{}", serde_json::to_string_pretty(&alloc)?);
+        }
+        Ok(())
+    }
+}
+
+pub fn decode_hex(s: &str) -> Result<Bytes, hex::FromHexError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map(Into::into)
+}
@@ -0,0 +1,101 @@
+use crate::trace::{write_trace, TraceSummary};
+use clap::Args;
+use ef_tests_levm::{
+    parser::parse_ef_test_file,
+    runner::levm_runner::{ensure_post_state, levm_fork, prepare_vm_for_tx},
+};
+use ethrex_levm::{errors::TxResult, tracer::Tracer};
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct Command {
+    #[clap(help = "State test file to run")]
+    file: PathBuf,
+    #[clap(
+        long = "run",
+        help = "Only run the tests whose name contains this string"
+    )]
+    run: Option<String>,
+    #[clap(
+        long = "trace",
+        help = "Print an EIP-3155 trace of every test to stderr"
+    )]
+    trace: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateTestResult {
+    name: String,
+    pass: bool,
+    fork: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Command {
+    /// Runs every transaction of the tests in the file and prints whether its post state matches
+    /// the expected one, as a JSON array
+    pub fn run(self) -> eyre::Result<()> {
+        let mut results = Vec::new();
+        for test in parse_ef_test_file(&self.file)? {
+            if self
+                .run
+                .as_ref()
+                .is_some_and(|run| !test.name.contains(run))
+            {
+                continue;
+            }
+            let fork = format!("{:?}", levm_fork(test.fork()));
+            let mut vectors: Vec<_> = test.transactions.keys().collect();
+            vectors.sort();
+            for vector in vectors {
+                let name = format!("{}[{}-{}-{}]", test.name, vector.0, vector.1, vector.2);
+                let mut vm = match prepare_vm_for_tx(vector, &test) {
+                    Ok(vm) => vm,
+                    Err(error) => {
+                        results.push(StateTestResult {
+                            name,
+                            pass: false,
+                            fork: fork.clone(),
+                            error: Some(error.to_string()),
+                        });
+                        continue;
+                    }
+                };
+                if self.trace {
+                    vm.tracer = Some(Tracer::default());
+                }
+                let execution = vm.transact();
+                if let Some(tracer) = vm.tracer.take() {
+                    let summary = match &execution {
+                        Ok(report) => TraceSummary {
+                            output: report.output.clone(),
+                            gas_used: report.gas_used,
+                            error: match &report.result {
+                                TxResult::Success => None,
+                                TxResult::Revert(error) => Some(error.to_string()),
+                            },
+                        },
+                        Err(error) => TraceSummary {
+                            output: Default::default(),
+                            gas_used: 0,
+                            error: Some(error.to_string()),
+                        },
+                    };
+                    write_trace(&mut std::io::stderr().lock(), &tracer.steps, &summary)?;
+                }
+                let post_state = ensure_post_state(&execution, vector, &test);
+                results.push(StateTestResult {
+                    name,
+                    pass: post_state.is_ok(),
+                    fork: fork.clone(),
+                    error: post_state.err().map(|error| error.to_string()),
+                });
+            }
+        }
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        Ok(())
+    }
+}
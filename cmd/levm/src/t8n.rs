@@ -0,0 +1,176 @@
+use crate::trace::{write_trace, TraceSummary};
+use bytes::Bytes;
+use clap::Args;
+use ethrex_core::{types::TxKind, Address};
+use ethrex_levm::{
+    db::{CacheDB, Database, Db},
+    errors::TxResult,
+    tracer::Tracer,
+    Fork,
+};
+use ethrex_vm::levm_vm_for_tx;
+use evm_tools::{
+    alloc::{apply_cache, levm_accounts},
+    t8n::{
+        apply_withdrawals, parse_transaction, store_beacon_root, BlockReceipts, RejectedTx, T8nArgs,
+    },
+};
+use eyre::WrapErr;
+use std::sync::Arc;
+
+/// Executes a state transition with LEVM alone, taking the inputs and writing the outputs the way
+/// the `t8n` tool of geth's `evm` does
+#[derive(Args)]
+pub struct Command {
+    #[command(flatten)]
+    args: T8nArgs,
+    #[clap(
+        long = "trace",
+        help = "Write an EIP-3155 trace of every transaction to the output directory"
+    )]
+    trace: bool,
+}
+
+impl Command {
+    pub fn run(self) -> eyre::Result<()> {
+        let args = &self.args;
+        let (mut alloc, env, txs) = args.read_inputs()?;
+        let fork = args.fork;
+        let header = args.block_header(&env)?;
+        let txs = txs
+            .into_iter()
+            .map(parse_transaction)
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        if fork >= Fork::Cancun {
+            if let Some(root) = env.parent_beacon_block_root {
+                store_beacon_root(&mut alloc, header.timestamp, root);
+            }
+        }
+
+        let mut db = Db::new();
+        db.add_accounts(levm_accounts(&alloc));
+        db.add_block_hashes(
+            env.block_hashes
+                .iter()
+                .map(|(number, hash)| (number.as_u64(), *hash))
+                .collect(),
+        );
+        let db: Arc<dyn Database> = Arc::new(db);
+
+        let mut cache = CacheDB::default();
+        let mut receipts = BlockReceipts::default();
+        let mut rejected = Vec::new();
+        for (index, tx) in txs.into_iter().enumerate() {
+            let tx = match tx {
+                Ok(tx) => tx,
+                Err(error) => {
+                    rejected.push(RejectedTx { index, error });
+                    continue;
+                }
+            };
+            if receipts.cumulative_gas_used + tx.gas_limit() > header.gas_limit {
+                rejected.push(RejectedTx {
+                    index,
+                    error: format!(
+                        "gas limit reached: tx gas {}, block gas left {}",
+                        tx.gas_limit(),
+                        header.gas_limit - receipts.cumulative_gas_used
+                    ),
+                });
+                continue;
+            }
+            // LEVM leaves the nonce to be checked by the block validation
+            let sender = tx.sender();
+            let sender_nonce = match cache.get(&sender) {
+                Some(account) => account.info.nonce,
+                None => db.get_account_info(sender).nonce,
+            };
+            if tx.nonce() != sender_nonce {
+                rejected.push(RejectedTx {
+                    index,
+                    error: format!(
+                        "nonce mismatch: address {sender:#x}, tx nonce {}, state nonce {sender_nonce}",
+                        tx.nonce()
+                    ),
+                });
+                continue;
+            }
+            let mut vm = match levm_vm_for_tx(&tx, &header, fork, db.clone(), cache.clone()) {
+                Ok(vm) => vm,
+                Err(error) => {
+                    rejected.push(RejectedTx {
+                        index,
+                        error: error.to_string(),
+                    });
+                    continue;
+                }
+            };
+            vm.env.chain_id = args.chain_id.into();
+            if self.trace {
+                vm.tracer = Some(Tracer::default());
+            }
+            let execution = vm.transact();
+            let tx_hash = tx.compute_hash();
+            if let Some(tracer) = vm.tracer.take() {
+                let summary = match &execution {
+                    Ok(report) => TraceSummary {
+                        output: report.output.clone(),
+                        gas_used: report.gas_used,
+                        error: match &report.result {
+                            TxResult::Success => None,
+                            TxResult::Revert(error) => Some(error.to_string()),
+                        },
+                    },
+                    Err(error) => TraceSummary {
+                        output: Bytes::new(),
+                        gas_used: 0,
+                        error: Some(error.to_string()),
+                    },
+                };
+                let path = args
+                    .output_basedir
+                    .join(format!("trace-{index}-{tx_hash:#x}.jsonl"));
+                let mut file = std::fs::File::create(&path)
+                    .wrap_err_with(|| format!("Failed to create {path:?}"))?;
+                write_trace(&mut file, &tracer.steps, &summary)?;
+            }
+            let report = match execution {
+                Ok(report) => report,
+                Err(error) => {
+                    rejected.push(RejectedTx {
+                        index,
+                        error: error.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            cache = report.new_state;
+            // The values left by this transaction are the original ones for the next transaction
+            for account in cache.values_mut() {
+                for slot in account.storage.values_mut() {
+                    slot.original_value = slot.current_value;
+                }
+            }
+            let contract_address = match tx.to() {
+                TxKind::Create => report.created_address.unwrap_or_default(),
+                TxKind::Call(_) => Address::zero(),
+            };
+            receipts.push(
+                tx,
+                header.number,
+                matches!(report.result, TxResult::Success),
+                report.gas_used,
+                report.logs,
+                contract_address,
+            );
+        }
+
+        apply_cache(&mut alloc, &cache, fork);
+        if let Some(withdrawals) = &env.withdrawals {
+            apply_withdrawals(&mut alloc, withdrawals);
+        }
+        args.finish(alloc, &env, &header, receipts, rejected)
+    }
+}
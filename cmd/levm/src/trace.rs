@@ -0,0 +1,33 @@
+use bytes::Bytes;
+use ethrex_levm::tracer::TraceStep;
+use serde::Serialize;
+use std::io::Write;
+
+/// Last line of an [EIP-3155] trace, with the result of the execution
+///
+/// [EIP-3155]: https://eips.ethereum.org/EIPS/eip-3155
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceSummary {
+    #[serde(with = "ethrex_core::serde_utils::bytes")]
+    pub output: Bytes,
+    #[serde(with = "ethrex_core::serde_utils::u64::hex_str")]
+    pub gas_used: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Writes the trace as JSON lines, one per step followed by the summary
+pub fn write_trace(
+    writer: &mut impl Write,
+    steps: &[TraceStep],
+    summary: &TraceSummary,
+) -> eyre::Result<()> {
+    for step in steps {
+        serde_json::to_writer(&mut *writer, step)?;
+        writeln!(writer)?;
+    }
+    serde_json::to_writer(&mut *writer, summary)?;
+    writeln!(writer)?;
+    Ok(())
+}
@@ -15,8 +15,12 @@ use ethrex_core::types::{
 use ethrex_core::H256;
 
 use ethrex_storage::error::StoreError;
-use ethrex_storage::Store;
-use ethrex_vm::{evm_state, execute_block, spec_id, EvmState, SpecId};
+use ethrex_storage::{AccountUpdate, Store, WriteBatch};
+use ethrex_vm::{
+    evm_state, execute_block_differential, execute_block_with_engine, spec_id, EvmConfig, EvmState,
+    SpecId,
+};
+use tracing::error;
 
 //TODO: Implement a struct Chain or BlockChain to encapsulate
 //functionality and canonical chain state and config
@@ -26,10 +30,8 @@ use ethrex_vm::{evm_state, execute_block, spec_id, EvmState, SpecId};
 /// canonical chain/head. Fork choice needs to be updated for that in a separate step.
 ///
 /// Performs pre and post execution validation, and updates the database with the post state.
-#[cfg(not(feature = "levm"))]
-pub fn add_block(block: &Block, storage: &Store) -> Result<(), ChainError> {
-    use ethrex_vm::get_state_transitions;
-
+/// The block is executed with the EVM selected by `evm_config`.
+pub fn add_block(block: &Block, storage: &Store, evm_config: EvmConfig) -> Result<(), ChainError> {
     let block_hash = block.header.compute_block_hash();
 
     // Validate if it can be the new head and find the parent
@@ -43,17 +45,19 @@ pub fn add_block(block: &Block, storage: &Store) -> Result<(), ChainError> {
     // Validate the block pre-execution
     validate_block(block, &parent_header, &state)?;
 
-    let receipts = execute_block(block, &mut state)?;
+    let (receipts, account_updates) = execute_block_with_config(block, &mut state, evm_config)?;
 
     validate_gas_used(&receipts, &block.header)?;
 
-    let account_updates = get_state_transitions(&mut state);
+    // All the writes of the block are committed together, so that a failure can't leave it
+    // partially stored
+    let mut batch = WriteBatch::new();
 
     // Apply the account updates over the last block's state and compute the new state root
     let new_state_root = state
         .database()
         .ok_or(ChainError::StoreError(StoreError::MissingStore))?
-        .apply_account_updates(block.header.parent_hash, &account_updates)?
+        .apply_account_updates_to_batch(&mut batch, block.header.parent_hash, &account_updates)?
         .ok_or(ChainError::ParentStateNotFound)?;
 
     // Check state root matches the one in block header after execution
@@ -62,56 +66,28 @@ pub fn add_block(block: &Block, storage: &Store) -> Result<(), ChainError> {
     // Check receipts root matches the one in block header after execution
     validate_receipts_root(&block.header, &receipts)?;
 
-    store_block(storage, block.clone())?;
-    store_receipts(storage, receipts, block_hash)?;
+    storage.add_block_to_batch(&mut batch, block.clone())?;
+    batch.add_receipts(block_hash, receipts);
+    storage.commit_batch(batch)?;
 
     Ok(())
 }
 
-/// Adds a new block to the store. It may or may not be canonical, as long as its ancestry links
-/// with the canonical chain and its parent's post-state is calculated. It doesn't modify the
-/// canonical chain/head. Fork choice needs to be updated for that in a separate step.
-///
-/// Performs pre and post execution validation, and updates the database with the post state.
-#[cfg(feature = "levm")]
-pub fn add_block(block: &Block, storage: &Store) -> Result<(), ChainError> {
-    let block_hash = block.header.compute_block_hash();
-
-    // Validate if it can be the new head and find the parent
-    let Ok(parent_header) = find_parent_header(&block.header, storage) else {
-        // If the parent is not present, we store it as pending.
-        storage.add_pending_block(block.clone())?;
-        return Err(ChainError::ParentNotFound);
-    };
-    let mut state = evm_state(storage.clone(), block.header.parent_hash);
-
-    // Validate the block pre-execution
-    validate_block(block, &parent_header, &state)?;
-
-    let (receipts, account_updates) = execute_block(block, &mut state)?;
-
-    // Note: these is commented because it is still being used in development.
-    // dbg!(&account_updates);
-
-    validate_gas_used(&receipts, &block.header)?;
-
-    // Apply the account updates over the last block's state and compute the new state root
-    let new_state_root = state
-        .database()
-        .ok_or(ChainError::StoreError(StoreError::MissingStore))?
-        .apply_account_updates(block.header.parent_hash, &account_updates)?
-        .ok_or(ChainError::ParentStateNotFound)?;
-
-    // Check state root matches the one in block header after execution
-    validate_state_root(&block.header, new_state_root)?;
-
-    // Check receipts root matches the one in block header after execution
-    validate_receipts_root(&block.header, &receipts)?;
-
-    store_block(storage, block.clone())?;
-    store_receipts(storage, receipts, block_hash)?;
-
-    Ok(())
+/// Executes the block with the configured engine, also executing it with the other engine and
+/// reporting the first divergent transaction if differential execution is enabled
+fn execute_block_with_config(
+    block: &Block,
+    state: &mut EvmState,
+    config: EvmConfig,
+) -> Result<(Vec<Receipt>, Vec<AccountUpdate>), ChainError> {
+    if !config.differential {
+        return Ok(execute_block_with_engine(config.engine, block, state)?);
+    }
+    let execution = execute_block_differential(block, state, config.engine)?;
+    if let Some(divergence) = execution.divergence {
+        error!("{divergence}");
+    }
+    Ok((execution.receipts, execution.account_updates))
 }
 
 /// Stores block and header in the database
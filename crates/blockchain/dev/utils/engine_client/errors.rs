@@ -18,6 +18,8 @@ pub enum EngineClientError {
     FailedDuringGetPayload(#[from] GetPayloadError),
     #[error("{0}")]
     FailedDuringNewPayload(#[from] NewPayloadError),
+    #[error("{0}")]
+    FailedDuringBackupDatabase(#[from] BackupDatabaseError),
     #[error("EngineClient failed to prepare JWT: {0}")]
     FailedToGetSystemTime(#[from] std::time::SystemTimeError),
     #[error("EngineClient failed to decode JWT secret: {0}")]
@@ -81,3 +83,9 @@ pub enum NewPayloadError {
     #[error("{0}")]
     ParseIntError(#[from] std::num::ParseIntError),
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupDatabaseError {
+    #[error("{0}")]
+    RPCError(String),
+}
@@ -1,10 +1,11 @@
 use bytes::Bytes;
 use errors::{
-    EngineClientError, ExchangeCapabilitiesError, ForkChoiceUpdateError, GetPayloadError,
-    NewPayloadError,
+    BackupDatabaseError, EngineClientError, ExchangeCapabilitiesError, ForkChoiceUpdateError,
+    GetPayloadError, NewPayloadError,
 };
 use ethereum_types::H256;
 use ethrex_rpc::{
+    admin::BackupDatabaseRequest,
     engine::{
         fork_choice::ForkChoiceUpdatedV3,
         payload::{GetPayloadV3Request, NewPayloadV3Request},
@@ -148,6 +149,22 @@ impl EngineClient {
         }
     }
 
+    /// Asks the node to back up its database into `path`, which is on the node's filesystem.
+    /// Returns once the backup is complete
+    pub async fn admin_backup_database(&self, path: String) -> Result<(), EngineClientError> {
+        let request = BackupDatabaseRequest { path }.into();
+
+        match self.send_request(request).await {
+            Ok(RpcResponse::Success(_)) => Ok(()),
+            Ok(RpcResponse::Error(error_response)) => Err(BackupDatabaseError::RPCError(format!(
+                "{}: {:?}",
+                error_response.error.message, error_response.error.data
+            ))
+            .into()),
+            Err(error) => Err(error),
+        }
+    }
+
     fn auth_token(&self) -> Result<String, EngineClientError> {
         // Header
         let header = jsonwebtoken::Header::default();
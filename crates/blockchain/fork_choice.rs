@@ -8,7 +8,6 @@ use crate::{
     error::{self, InvalidForkChoice},
     is_canonical,
 };
-use tracing::error;
 
 /// Applies new fork choice data to the current blockchain. It performs validity checks:
 /// - The finalized, safe and head hashes must correspond to already saved blocks.
@@ -55,9 +54,7 @@ pub fn apply_fork_choice(
     }
 
     let Some(head_block) = head_res else {
-        if let Some(block) = store.get_pending_block(head_hash)? {
-            trigger_sync(block);
-        };
+        // The engine API will trigger a sync towards the head block
         return Err(InvalidForkChoice::Syncing);
     };
 
@@ -138,16 +135,6 @@ pub fn apply_fork_choice(
     Ok(head)
 }
 
-// Trigger a backfill sync from the block until we find a valid block that we're familiar with or
-// something goes wrong.
-fn trigger_sync(head_block: Block) {
-    // TODO(#438): add immediate reorg if all needed blocks are pending.
-    error!(
-        "A sync for block {} should be triggered but it's not yet supported.",
-        head_block.header.compute_block_hash()
-    );
-}
-
 // Checks that block 1 is prior to block 2 and that if the second is present, the first one is too.
 fn check_order(block_1: &Option<Block>, block_2: &Option<Block>) -> Result<(), InvalidForkChoice> {
     // We don't need to perform the check if the hashes are null
@@ -6,15 +6,21 @@ mod blockchain_integration_test {
         add_block,
         error::{ChainError, InvalidForkChoice},
         fork_choice::apply_fork_choice,
-        is_canonical, latest_canonical_block_hash,
+        is_canonical, latest_canonical_block_hash, mempool,
         payload::{build_payload, create_payload, BuildPayloadArgs},
     };
 
+    use bytes::Bytes;
     use ethrex_core::{
-        types::{Block, BlockHeader},
-        H160, H256,
+        types::{
+            Block, BlockHeader, EIP1559Transaction, Genesis, GenesisAccount, Signable, Transaction,
+            TxKind,
+        },
+        Address, H160, H256, U256,
     };
     use ethrex_storage::{EngineType, Store};
+    use ethrex_vm::{evm_state, execute_block_differential, EvmConfig, EvmEngine};
+    use secp256k1::SecretKey;
 
     #[test]
     fn test_small_to_long_reorg() {
@@ -26,7 +32,7 @@ mod blockchain_integration_test {
         // Add first block. We'll make it canonical.
         let block_1a = new_block(&store, &genesis_header);
         let hash_1a = block_1a.hash();
-        add_block(&block_1a, &store).unwrap();
+        add_block(&block_1a, &store, EvmConfig::default()).unwrap();
         store.set_canonical_block(1, hash_1a).unwrap();
         let retrieved_1a = store.get_block_header(1).unwrap().unwrap();
 
@@ -36,7 +42,7 @@ mod blockchain_integration_test {
         // Add second block at height 1. Will not be canonical.
         let block_1b = new_block(&store, &genesis_header);
         let hash_1b = block_1b.hash();
-        add_block(&block_1b, &store).expect("Could not add block 1b.");
+        add_block(&block_1b, &store, EvmConfig::default()).expect("Could not add block 1b.");
         let retrieved_1b = store.get_block_header_by_hash(hash_1b).unwrap().unwrap();
 
         assert_ne!(retrieved_1a, retrieved_1b);
@@ -45,7 +51,7 @@ mod blockchain_integration_test {
         // Add a third block at height 2, child to the non canonical block.
         let block_2 = new_block(&store, &block_1b.header);
         let hash_2 = block_2.hash();
-        add_block(&block_2, &store).expect("Could not add block 2.");
+        add_block(&block_2, &store, EvmConfig::default()).expect("Could not add block 2.");
         let retrieved_2 = store.get_block_header_by_hash(hash_2).unwrap();
 
         assert!(retrieved_2.is_some());
@@ -75,14 +81,14 @@ mod blockchain_integration_test {
         // Build a single valid block.
         let block_1 = new_block(&store, &genesis_header);
         let hash_1 = block_1.header.compute_block_hash();
-        add_block(&block_1, &store).unwrap();
+        add_block(&block_1, &store, EvmConfig::default()).unwrap();
         apply_fork_choice(&store, hash_1, H256::zero(), H256::zero()).unwrap();
 
         // Build a child, then change its parent, making it effectively a pending block.
         let mut block_2 = new_block(&store, &block_1.header);
         block_2.header.parent_hash = H256::random();
         let hash_2 = block_2.header.compute_block_hash();
-        let result = add_block(&block_2, &store);
+        let result = add_block(&block_2, &store, EvmConfig::default());
         assert!(matches!(result, Err(ChainError::ParentNotFound)));
 
         // block 2 should now be pending.
@@ -105,7 +111,7 @@ mod blockchain_integration_test {
         // Add first block. Not canonical.
         let block_1a = new_block(&store, &genesis_header);
         let hash_1a = block_1a.hash();
-        add_block(&block_1a, &store).unwrap();
+        add_block(&block_1a, &store, EvmConfig::default()).unwrap();
         let retrieved_1a = store.get_block_header_by_hash(hash_1a).unwrap().unwrap();
 
         assert!(!is_canonical(&store, 1, hash_1a).unwrap());
@@ -113,7 +119,7 @@ mod blockchain_integration_test {
         // Add second block at height 1. Canonical.
         let block_1b = new_block(&store, &genesis_header);
         let hash_1b = block_1b.hash();
-        add_block(&block_1b, &store).expect("Could not add block 1b.");
+        add_block(&block_1b, &store, EvmConfig::default()).expect("Could not add block 1b.");
         apply_fork_choice(&store, hash_1b, genesis_hash, genesis_hash).unwrap();
         let retrieved_1b = store.get_block_header(1).unwrap().unwrap();
 
@@ -125,7 +131,7 @@ mod blockchain_integration_test {
         // Add a third block at height 2, child to the canonical one.
         let block_2 = new_block(&store, &block_1b.header);
         let hash_2 = block_2.hash();
-        add_block(&block_2, &store).expect("Could not add block 2.");
+        add_block(&block_2, &store, EvmConfig::default()).expect("Could not add block 2.");
         apply_fork_choice(&store, hash_2, genesis_hash, genesis_hash).unwrap();
         let retrieved_2 = store.get_block_header_by_hash(hash_2).unwrap();
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_2);
@@ -160,12 +166,12 @@ mod blockchain_integration_test {
         // Add block at height 1.
         let block_1 = new_block(&store, &genesis_header);
         let hash_1 = block_1.hash();
-        add_block(&block_1, &store).expect("Could not add block 1b.");
+        add_block(&block_1, &store, EvmConfig::default()).expect("Could not add block 1b.");
 
         // Add child at height 2.
         let block_2 = new_block(&store, &block_1.header);
         let hash_2 = block_2.hash();
-        add_block(&block_2, &store).expect("Could not add block 2.");
+        add_block(&block_2, &store, EvmConfig::default()).expect("Could not add block 2.");
 
         assert!(!is_canonical(&store, 1, hash_1).unwrap());
         assert!(!is_canonical(&store, 2, hash_2).unwrap());
@@ -201,12 +207,12 @@ mod blockchain_integration_test {
 
         // Add block at height 1.
         let block_1 = new_block(&store, &genesis_header);
-        add_block(&block_1, &store).expect("Could not add block 1b.");
+        add_block(&block_1, &store, EvmConfig::default()).expect("Could not add block 1b.");
 
         // Add child at height 2.
         let block_2 = new_block(&store, &block_1.header);
         let hash_2 = block_2.hash();
-        add_block(&block_2, &store).expect("Could not add block 2.");
+        add_block(&block_2, &store, EvmConfig::default()).expect("Could not add block 2.");
 
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), genesis_hash);
 
@@ -218,7 +224,7 @@ mod blockchain_integration_test {
         // Add a new, non canonical block, starting from genesis.
         let block_1b = new_block(&store, &genesis_header);
         let hash_b = block_1b.hash();
-        add_block(&block_1b, &store).expect("Could not add block b.");
+        add_block(&block_1b, &store, EvmConfig::default()).expect("Could not add block b.");
 
         // The latest block should be the same.
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_2);
@@ -230,6 +236,89 @@ mod blockchain_integration_test {
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_b);
     }
 
+    #[test]
+    fn levm_execution_should_match_the_built_block() {
+        // Increments the counter in slot 0 and logs its new value
+        // PUSH0 SLOAD PUSH1 1 ADD DUP1 PUSH0 SSTORE PUSH0 MSTORE PUSH1 32 PUSH0 LOG0 STOP
+        let counter_code = Bytes::from_static(&[
+            0x5f, 0x54, 0x60, 0x01, 0x01, 0x80, 0x5f, 0x55, 0x5f, 0x52, 0x60, 0x20, 0x5f, 0xa0,
+            0x00,
+        ]);
+        // Returns the counter code that follows it
+        // PUSH1 15 PUSH1 10 PUSH0 CODECOPY PUSH1 15 PUSH0 RETURN
+        let counter_init_code = [
+            &[0x60, 0x0f, 0x60, 0x0a, 0x5f, 0x39, 0x60, 0x0f, 0x5f, 0xf3],
+            counter_code.as_ref(),
+        ]
+        .concat();
+        let counter = Address::from_low_u64_be(0xc0de);
+        let recipient = Address::from_low_u64_be(0xbeef);
+
+        let mut genesis = test_genesis();
+        let key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let chain_id = genesis.config.chain_id;
+        let transactions: Vec<Transaction> = [
+            (TxKind::Call(recipient), U256::exp10(18), Bytes::new()),
+            (TxKind::Call(counter), U256::zero(), Bytes::new()),
+            (TxKind::Call(counter), U256::one(), Bytes::new()),
+            (TxKind::Create, U256::zero(), Bytes::from(counter_init_code)),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(nonce, (to, value, data))| {
+            Transaction::EIP1559Transaction(EIP1559Transaction {
+                chain_id,
+                nonce: nonce as u64,
+                max_priority_fee_per_gas: 1_000_000_000,
+                max_fee_per_gas: 10_000_000_000,
+                gas_limit: 100_000,
+                to,
+                value,
+                data,
+                ..Default::default()
+            })
+            .sign(&key)
+        })
+        .collect();
+        let sender = transactions[0].sender();
+        genesis
+            .alloc
+            .insert(sender, genesis_account(U256::exp10(21), Bytes::new()));
+        genesis
+            .alloc
+            .insert(counter, genesis_account(U256::zero(), counter_code));
+        let store = store_with_genesis(genesis);
+        for transaction in transactions {
+            mempool::add_transaction(transaction, &store).unwrap();
+        }
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+
+        let block_1 = new_block(&store, &genesis_header);
+        assert_eq!(block_1.body.transactions.len(), 4);
+        let mut state = evm_state(store.clone(), genesis_header.compute_block_hash());
+        let execution = execute_block_differential(&block_1, &mut state, EvmEngine::LEVM).unwrap();
+
+        assert!(execution.divergence.is_none(), "{:?}", execution.divergence);
+        assert!(execution.receipts.iter().all(|receipt| receipt.succeeded));
+        let state_root = store
+            .apply_account_updates(block_1.header.parent_hash, &execution.account_updates)
+            .unwrap()
+            .unwrap();
+        assert_eq!(state_root, block_1.header.state_root);
+
+        add_block(&block_1, &store, EvmConfig::default()).unwrap();
+        let block_hash = block_1.hash();
+        let counter_value = store
+            .get_storage_at_hash(block_hash, counter, H256::zero())
+            .unwrap();
+        assert_eq!(counter_value, Some(U256::from(2)));
+        let recipient_info = store
+            .get_account_info_by_hash(block_hash, recipient)
+            .unwrap()
+            .unwrap();
+        assert_eq!(recipient_info.balance, U256::exp10(18));
+    }
+
     fn new_block(store: &Store, parent: &BlockHeader) -> Block {
         let args = BuildPayloadArgs {
             parent: parent.compute_block_hash(),
@@ -247,13 +336,26 @@ mod blockchain_integration_test {
     }
 
     fn test_store() -> Store {
-        // Get genesis
+        store_with_genesis(test_genesis())
+    }
+
+    fn test_genesis() -> Genesis {
         let file = File::open("../../test_data/genesis-execution-api.json")
             .expect("Failed to open genesis file");
         let reader = BufReader::new(file);
-        let genesis = serde_json::from_reader(reader).expect("Failed to deserialize genesis file");
+        serde_json::from_reader(reader).expect("Failed to deserialize genesis file")
+    }
+
+    fn genesis_account(balance: U256, code: Bytes) -> GenesisAccount {
+        GenesisAccount {
+            code,
+            storage: Default::default(),
+            balance,
+            nonce: 0,
+        }
+    }
 
-        // Build store with genesis
+    fn store_with_genesis(genesis: Genesis) -> Store {
         let store =
             Store::new("store.db", EngineType::InMemory).expect("Failed to build DB for testing");
 
@@ -245,6 +245,10 @@ pub fn compute_receipts_root(receipts: &[Receipt]) -> H256 {
     Trie::compute_hash_from_unsorted_iter(iter)
 }
 
+pub fn compute_ommers_hash(ommers: &[BlockHeader]) -> H256 {
+    keccak(ommers.to_vec().encode_to_vec())
+}
+
 // See [EIP-4895](https://eips.ethereum.org/EIPS/eip-4895)
 pub fn compute_withdrawals_root(withdrawals: &[Withdrawal]) -> H256 {
     let iter = withdrawals
@@ -7,10 +7,12 @@ use ethrex_rlp::{
 };
 
 use ethereum_types::H32;
+use serde::Serialize;
 
 use super::{BlockHash, BlockNumber, ChainConfig};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ForkId {
     fork_hash: H32,
     fork_next: BlockNumber,
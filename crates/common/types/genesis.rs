@@ -78,6 +78,8 @@ pub struct ChainConfig {
     pub shanghai_time: Option<u64>,
     pub cancun_time: Option<u64>,
     pub prague_time: Option<u64>,
+    /// Devnets testing the EVM Object Format, which isn't scheduled for Prague anymore
+    pub osaka_time: Option<u64>,
     pub verkle_time: Option<u64>,
 
     /// Amount of total difficulty reached by the network that triggers the consensus upgrade.
@@ -104,6 +106,14 @@ impl ChainConfig {
         self.cancun_time.is_some_and(|time| time <= block_timestamp)
     }
 
+    pub fn is_osaka_activated(&self, block_timestamp: u64) -> bool {
+        self.osaka_time.is_some_and(|time| time <= block_timestamp)
+    }
+
+    pub fn is_verkle_activated(&self, block_timestamp: u64) -> bool {
+        self.verkle_time.is_some_and(|time| time <= block_timestamp)
+    }
+
     pub fn is_istanbul_activated(&self, block_number: BlockNumber) -> bool {
         self.istanbul_block.is_some_and(|num| num <= block_number)
     }
@@ -149,6 +159,7 @@ impl ChainConfig {
             self.shanghai_time,
             self.cancun_time,
             self.prague_time,
+            self.osaka_time,
             self.verkle_time,
         ];
 
@@ -157,7 +168,7 @@ impl ChainConfig {
 }
 
 #[allow(unused)]
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct GenesisAccount {
     #[serde(default, with = "crate::serde_utils::bytes")]
     pub code: Bytes,
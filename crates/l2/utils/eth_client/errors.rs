@@ -32,6 +32,8 @@ pub enum EthClientError {
     GetBalanceError(#[from] GetBalanceError),
     #[error("eth_getTransactionByHash request error: {0}")]
     GetTransactionByHashError(#[from] GetTransactionByHashError),
+    #[error("debug_profileCall request error: {0}")]
+    ProfileCallError(#[from] ProfileCallError),
     #[error("Unreachable nonce")]
     UnrecheableNonce,
     #[error("Error: {0}")]
@@ -179,3 +181,13 @@ pub enum GetTransactionByHashError {
     #[error("{0}")]
     ParseIntError(#[from] std::num::ParseIntError),
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileCallError {
+    #[error("{0}")]
+    ReqwestError(#[from] reqwest::Error),
+    #[error("{0}")]
+    SerdeJSONError(#[from] serde_json::Error),
+    #[error("{0}")]
+    RPCError(String),
+}
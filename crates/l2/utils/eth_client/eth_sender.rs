@@ -1,5 +1,5 @@
 use crate::utils::eth_client::{
-    errors::{CallError, EthClientError},
+    errors::{CallError, EthClientError, ProfileCallError},
     EthClient, RpcResponse,
 };
 use bytes::Bytes;
@@ -7,6 +7,7 @@ use ethereum_types::{Address, U256};
 use ethrex_core::types::{GenericTransaction, TxKind};
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_rpc::utils::{RpcRequest, RpcRequestId};
+use ethrex_vm::CallProfile;
 use keccak_hash::{keccak, H256};
 use secp256k1::SecretKey;
 use serde_json::json;
@@ -72,6 +73,49 @@ impl EthClient {
         }
     }
 
+    /// Simulates the call with LEVM on the latest block and returns where its gas was spent
+    pub async fn profile_call(
+        &self,
+        to: Address,
+        calldata: Bytes,
+        overrides: Overrides,
+    ) -> Result<CallProfile, EthClientError> {
+        let mut tx = serde_json::Map::new();
+        tx.insert("to".to_string(), json!(format!("{to:#x}")));
+        tx.insert("input".to_string(), json!(format!("0x{calldata:#x}")));
+        tx.insert(
+            "value".to_string(),
+            json!(format!("{:#x}", overrides.value.unwrap_or_default())),
+        );
+        tx.insert(
+            "from".to_string(),
+            json!(format!("{:#x}", overrides.from.unwrap_or_default())),
+        );
+        if let Some(gas_limit) = overrides.gas_limit {
+            tx.insert("gas".to_string(), json!(format!("{gas_limit:#x}")));
+        }
+        if let Some(gas_price) = overrides.gas_price {
+            tx.insert("gasPrice".to_string(), json!(format!("{gas_price:#x}")));
+        }
+
+        let request = RpcRequest {
+            id: RpcRequestId::Number(1),
+            jsonrpc: "2.0".to_string(),
+            method: "debug_profileCall".to_string(),
+            params: Some(vec![tx.into(), json!("latest")]),
+        };
+
+        match self.send_request(request).await {
+            Ok(RpcResponse::Success(result)) => serde_json::from_value(result.result)
+                .map_err(ProfileCallError::SerdeJSONError)
+                .map_err(EthClientError::from),
+            Ok(RpcResponse::Error(error_response)) => {
+                Err(ProfileCallError::RPCError(error_response.error.message).into())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     pub async fn deploy(
         &self,
         deployer: Address,
@@ -5,7 +5,7 @@ use ethrex_blockchain::add_block;
 use ethrex_core::types::{Block, Genesis};
 use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 use ethrex_storage::{EngineType, Store};
-use ethrex_vm::execution_db::ExecutionDB;
+use ethrex_vm::{execution_db::ExecutionDB, EvmConfig};
 use tracing::info;
 use zkvm_interface::io::ProgramInput;
 
@@ -73,7 +73,7 @@ pub fn generate_program_input(
     let store = Store::new("memory", EngineType::InMemory)?;
     store.add_initial_state(genesis)?;
     for block in chain {
-        add_block(&block, &store)?;
+        add_block(&block, &store, EvmConfig::default())?;
     }
 
     let parent_block_header = store
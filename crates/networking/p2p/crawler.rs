@@ -0,0 +1,308 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use ethrex_core::{types::ForkId, H256, H512};
+use ethrex_rlp::decode::RLPDecode;
+use k256::ecdsa::SigningKey;
+use rand::rngs::OsRng;
+use serde::Serialize;
+use tokio::{
+    net::UdpSocket,
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        Mutex,
+    },
+    task::JoinSet,
+    time::Instant,
+};
+use tracing::{debug, info};
+
+use crate::{
+    bootnode::BootNode,
+    discv4::{get_expiration, ENRRequestMessage, FindNodeMessage, Message, Packet},
+    kademlia::MAX_NODES_PER_BUCKET,
+    node_id_from_signing_key, ping, pong,
+    types::{Node, NodeRecord},
+    MAX_DISC_PACKET_SIZE,
+};
+
+/// Max amount of nodes being queried at the same time
+const MAX_CONCURRENT_QUERIES: usize = 16;
+/// Time to wait for each response before considering the node unresponsive
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Amount of random targets we look up on each node, besides its own id,
+/// so that we also learn about nodes from buckets far away from it
+const RANDOM_LOOKUPS_PER_NODE: usize = 2;
+
+/// A node found while crawling that answered our requests
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrawledNode {
+    pub node_id: H512,
+    pub enode: String,
+    pub ip: IpAddr,
+    pub udp_port: u16,
+    pub tcp_port: u16,
+    /// Textual form of the node's record, if it answered our ENR request
+    pub record: Option<String>,
+    pub seq: Option<u64>,
+    /// Fork id advertised in the node's record
+    pub fork_id: Option<ForkId>,
+}
+
+/// Senders for the messages received from the nodes being queried, indexed by node id
+type PendingQueries = Arc<Mutex<HashMap<H512, UnboundedSender<Message>>>>;
+
+/// Walks the discovery DHT starting from the given bootnodes, sending `FindNode` requests to
+/// every node found until there are no new nodes left to query or `max_nodes` have been queried
+/// Each node is also asked for its record so its fork id can be known
+/// Returns the nodes that answered our requests
+pub async fn crawl(
+    udp_addr: SocketAddr,
+    signer: SigningKey,
+    bootnodes: Vec<BootNode>,
+    max_nodes: usize,
+) -> std::io::Result<Vec<CrawledNode>> {
+    let udp_socket = Arc::new(UdpSocket::bind(udp_addr).await?);
+    let pending_queries = PendingQueries::default();
+    let receiver_handle = tokio::spawn(receive_messages(
+        udp_socket.clone(),
+        signer.clone(),
+        pending_queries.clone(),
+    ));
+
+    let mut seen: HashSet<H512> = HashSet::from([node_id_from_signing_key(&signer)]);
+    let mut pending_nodes: VecDeque<Node> = VecDeque::new();
+    for bootnode in bootnodes {
+        if seen.insert(bootnode.node_id) {
+            pending_nodes.push_back(Node {
+                ip: bootnode.socket_address.ip(),
+                udp_port: bootnode.socket_address.port(),
+                tcp_port: bootnode.socket_address.port(),
+                node_id: bootnode.node_id,
+            });
+        }
+    }
+
+    let mut queried = 0;
+    let mut crawled_nodes = vec![];
+    let mut queries = JoinSet::new();
+    loop {
+        while queries.len() < MAX_CONCURRENT_QUERIES && queried < max_nodes {
+            let Some(node) = pending_nodes.pop_front() else {
+                break;
+            };
+            queried += 1;
+            queries.spawn(query_node(
+                node,
+                udp_addr,
+                udp_socket.clone(),
+                signer.clone(),
+                pending_queries.clone(),
+            ));
+        }
+        let Some(result) = queries.join_next().await else {
+            break;
+        };
+        let Ok(Some((crawled_node, neighbors))) = result else {
+            continue;
+        };
+        for neighbor in neighbors {
+            if seen.insert(neighbor.node_id) {
+                pending_nodes.push_back(neighbor);
+            }
+        }
+        crawled_nodes.push(crawled_node);
+        if crawled_nodes.len() % 100 == 0 {
+            info!(
+                "Crawled {} nodes, {} pending",
+                crawled_nodes.len(),
+                pending_nodes.len()
+            );
+        }
+    }
+    receiver_handle.abort();
+    info!(
+        "Crawl finished: {} nodes answered out of {queried} queried",
+        crawled_nodes.len()
+    );
+    Ok(crawled_nodes)
+}
+
+/// Receives the messages sent to the crawler, answers pings so that the nodes we query consider
+/// our endpoint proven, and forwards every message to the query of the node that sent it
+async fn receive_messages(
+    udp_socket: Arc<UdpSocket>,
+    signer: SigningKey,
+    pending_queries: PendingQueries,
+) {
+    let mut buf = vec![0; MAX_DISC_PACKET_SIZE];
+    loop {
+        let Ok((read, from)) = udp_socket.recv_from(&mut buf).await else {
+            continue;
+        };
+        let packet = match Packet::decode(&buf[..read]) {
+            Ok(packet) => packet,
+            Err(error) => {
+                debug!("Could not decode packet: {error:?}");
+                continue;
+            }
+        };
+        if let Message::Ping(_) = packet.get_message() {
+            pong(&udp_socket, from, packet.get_hash(), &signer).await;
+        }
+        let node_id = packet.get_node_id();
+        if let Some(sender) = pending_queries.lock().await.get(&node_id) {
+            let _ = sender.send(packet.into_message());
+        }
+    }
+}
+
+/// Bonds with the node and asks it for its neighbors and its record
+/// Returns None if the node doesn't answer our ping
+async fn query_node(
+    node: Node,
+    udp_addr: SocketAddr,
+    udp_socket: Arc<UdpSocket>,
+    signer: SigningKey,
+    pending_queries: PendingQueries,
+) -> Option<(CrawledNode, Vec<Node>)> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    pending_queries.lock().await.insert(node.node_id, sender);
+    let node_addr = SocketAddr::new(node.ip, node.udp_port);
+
+    let result = async {
+        // Bond with the node: it must answer our ping, and we must answer its ping
+        // (which is done by `receive_messages`) before it answers our requests
+        let ping_hash = ping(&udp_socket, udp_addr, node_addr, &signer).await?;
+        wait_for_message(&mut receiver, |msg| match msg {
+            Message::Pong(pong) if pong.ping_hash == ping_hash => Some(()),
+            _ => None,
+        })
+        .await?;
+        let _ = wait_for_message(&mut receiver, |msg| {
+            matches!(msg, Message::Ping(_)).then_some(())
+        })
+        .await;
+
+        let mut targets = vec![node.node_id];
+        targets.extend(
+            (0..RANDOM_LOOKUPS_PER_NODE)
+                .map(|_| node_id_from_signing_key(&SigningKey::random(&mut OsRng))),
+        );
+        let mut neighbors = vec![];
+        for target in targets {
+            neighbors
+                .extend(find_node(&udp_socket, node_addr, &signer, target, &mut receiver).await);
+        }
+
+        // The record is only used if it was signed by the node we are querying
+        let record = request_record(&udp_socket, node_addr, &signer, &mut receiver)
+            .await
+            .filter(|record| {
+                let valid = record.verify(node.node_id);
+                if !valid {
+                    debug!("Node {node_addr} sent an invalid record, dropping it");
+                }
+                valid
+            });
+        // Use the tcp port advertised in the record if available
+        let tcp_port = record
+            .as_ref()
+            .and_then(|record| {
+                let (_key, tcp) = record.pairs.iter().find(|(k, _v)| k.eq("tcp".as_bytes()))?;
+                u16::decode(tcp).ok()
+            })
+            .unwrap_or(node.tcp_port);
+        let crawled_node = CrawledNode {
+            node_id: node.node_id,
+            enode: Node { tcp_port, ..node }.enode_url(),
+            ip: node.ip,
+            udp_port: node.udp_port,
+            tcp_port,
+            record: record.as_ref().map(NodeRecord::enr_url),
+            seq: record.as_ref().map(|record| record.seq),
+            fork_id: record.as_ref().and_then(NodeRecord::fork_id),
+        };
+        Some((crawled_node, neighbors))
+    }
+    .await;
+
+    pending_queries.lock().await.remove(&node.node_id);
+    if result.is_none() {
+        debug!("Node {node_addr} didn't answer our requests");
+    }
+    result
+}
+
+/// Sends a `FindNode` request and collects the nodes sent in the `Neighbors` responses
+async fn find_node(
+    udp_socket: &UdpSocket,
+    to_addr: SocketAddr,
+    signer: &SigningKey,
+    target: H512,
+    receiver: &mut UnboundedReceiver<Message>,
+) -> Vec<Node> {
+    let msg = Message::FindNode(FindNodeMessage::new(target, get_expiration(20)));
+    let mut buf = Vec::new();
+    msg.encode_with_header(&mut buf, signer);
+    if udp_socket.send_to(&buf, to_addr).await.is_err() {
+        return vec![];
+    }
+    // Neighbors may be split across many messages
+    let mut nodes = vec![];
+    while nodes.len() < MAX_NODES_PER_BUCKET {
+        let Some(mut found_nodes) = wait_for_message(receiver, |msg| match msg {
+            Message::Neighbors(neighbors) => Some(neighbors.nodes),
+            _ => None,
+        })
+        .await
+        else {
+            break;
+        };
+        nodes.append(&mut found_nodes);
+    }
+    nodes
+}
+
+/// Sends an `ENRRequest` and waits for the node's record
+async fn request_record(
+    udp_socket: &UdpSocket,
+    to_addr: SocketAddr,
+    signer: &SigningKey,
+    receiver: &mut UnboundedReceiver<Message>,
+) -> Option<NodeRecord> {
+    let msg = Message::ENRRequest(ENRRequestMessage::new(get_expiration(20)));
+    let mut buf = Vec::new();
+    msg.encode_with_header(&mut buf, signer);
+    udp_socket.send_to(&buf, to_addr).await.ok()?;
+    let request_hash = H256::from_slice(&buf[0..32]);
+    wait_for_message(receiver, |msg| match msg {
+        Message::ENRResponse(response) if response.request_hash == request_hash => {
+            Some(response.node_record)
+        }
+        _ => None,
+    })
+    .await
+}
+
+/// Waits for a message accepted by the `filter`, discarding any other messages
+/// Returns None if no such message arrived within `RESPONSE_TIMEOUT`
+async fn wait_for_message<T>(
+    receiver: &mut UnboundedReceiver<Message>,
+    mut filter: impl FnMut(Message) -> Option<T>,
+) -> Option<T> {
+    let deadline = Instant::now() + RESPONSE_TIMEOUT;
+    loop {
+        let msg = tokio::time::timeout_at(deadline, receiver.recv())
+            .await
+            .ok()??;
+        if let Some(value) = filter(msg) {
+            return Some(value);
+        }
+    }
+}
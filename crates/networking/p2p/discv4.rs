@@ -121,6 +121,10 @@ impl Packet {
     pub fn get_node_id(&self) -> H512 {
         self.node_id
     }
+
+    pub fn into_message(self) -> Message {
+        self.message
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -475,7 +479,13 @@ impl RLPDecode for ENRResponseMessage {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct ENRRequestMessage {
-    expiration: u64,
+    pub expiration: u64,
+}
+
+impl ENRRequestMessage {
+    pub fn new(expiration: u64) -> Self {
+        Self { expiration }
+    }
 }
 
 impl RLPDecode for ENRRequestMessage {
@@ -0,0 +1,436 @@
+use std::{collections::VecDeque, future::Future, sync::Arc, time::Duration};
+
+use ethrex_core::{
+    types::{
+        compute_ommers_hash, compute_transactions_root, compute_withdrawals_root, Block, BlockBody,
+        BlockHeader, BlockNumber,
+    },
+    H256,
+};
+use tokio::{
+    sync::{mpsc, Mutex},
+    task::{JoinError, JoinHandle},
+};
+use tracing::{debug, info};
+
+use crate::{
+    kademlia::KademliaTable, peer_channels::PeerChannels, rlpx::eth::blocks::BLOCK_HEADER_LIMIT,
+};
+
+/// Maximum amount of range requests that will be in flight at the same time
+const MAX_CONCURRENT_REQUESTS: usize = 16;
+/// Maximum amount of times a range will be requested (each time from a different peer) before giving up
+const MAX_RANGE_RETRIES: usize = 5;
+/// Amount of block bodies requested in a single batch
+const BODY_BATCH_SIZE: usize = 128;
+/// Time to wait before checking again for peers when none are available
+const PEER_WAIT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Downloads block headers and bodies by splitting them into ranges that are requested concurrently from multiple peers
+/// Ranges that fail to download or that contain invalid data are retried with a different peer
+#[derive(Debug, Clone)]
+pub(crate) struct Downloader {
+    peers: Arc<Mutex<KademliaTable>>,
+}
+
+impl Downloader {
+    pub fn new(peers: Arc<Mutex<KademliaTable>>) -> Self {
+        Self { peers }
+    }
+
+    /// Returns the channels of all active peers, waiting until at least one is available
+    async fn peers(&self) -> Vec<PeerChannels> {
+        loop {
+            // Don't hold on to the table lock while waiting so new peers can be added
+            let peers = self.peers.lock().await.get_all_peer_channels();
+            if !peers.is_empty() {
+                return peers;
+            }
+            info!("[Sync] No peers available, retrying in 10 sec");
+            tokio::time::sleep(PEER_WAIT_INTERVAL).await;
+        }
+    }
+
+    /// Fetches the header of the block with the given hash, trying with a different peer on each failed attempt
+    pub async fn request_header(&self, hash: H256) -> Result<BlockHeader, DownloadError> {
+        let peers = self.peers().await;
+        let mut peer_idx = rand::random::<usize>() % peers.len();
+        for _ in 0..MAX_RANGE_RETRIES {
+            if let Some(header) = peers[peer_idx].request_block_header(hash).await {
+                return Ok(header);
+            }
+            peer_idx = (peer_idx + 1) % peers.len();
+        }
+        Err(DownloadError::HeaderNotFound(hash))
+    }
+
+    /// Downloads all block headers after `current_head` up to (and including) the `sync_head`
+    /// The range is split into chunks which are requested concurrently from different peers
+    /// Each chunk is checked to form a valid chain on its own and then linked to the previous one
+    /// Chunks that don't link are requested again, without asking the peer that sent them
+    pub async fn download_headers(
+        &self,
+        current_head: &BlockHeader,
+        sync_head: H256,
+    ) -> Result<Vec<BlockHeader>, DownloadError> {
+        let sync_head_number = self.request_header(sync_head).await?.number;
+        if sync_head_number <= current_head.number {
+            return Err(DownloadError::InvalidHeaderChain(sync_head_number));
+        }
+        debug!(
+            "Downloading headers from block {} to block {sync_head_number}",
+            current_head.number + 1
+        );
+        let mut peers = self.peers().await;
+        let mut ranges = (current_head.number + 1..=sync_head_number)
+            .step_by(BLOCK_HEADER_LIMIT as usize)
+            .map(|start| (start, BLOCK_HEADER_LIMIT.min(sync_head_number - start + 1)));
+        let mut headers: Vec<BlockHeader> =
+            Vec::with_capacity((sync_head_number - current_head.number) as usize);
+        let mut window = TaskWindow::new();
+        let mut link_failures = 0;
+        loop {
+            while !window.is_full() {
+                let Some((start, count)) = ranges.next() else {
+                    break;
+                };
+                window.spawn(fetch_header_range(peers.clone(), start, count));
+            }
+            let Some(chunk) = window.next().await else {
+                break;
+            };
+            let (peer, chunk) = chunk?;
+            let parent = headers.last().unwrap_or(current_head);
+            if let Err(error) = validate_header_chain(parent, &chunk) {
+                link_failures += 1;
+                if link_failures >= MAX_RANGE_RETRIES {
+                    return Err(error);
+                }
+                // The peer that sent the start of the chunk follows a different chain, so it
+                // isn't asked for any more headers and the chunk is requested again before the
+                // following ones are processed
+                debug!(
+                    "Headers from block {} don't link to the previous ones, retrying",
+                    parent.number + 1
+                );
+                if peers.len() > 1 {
+                    peers.retain(|other| !other.is_same_peer(&peer));
+                }
+                window.spawn_front(fetch_header_range(
+                    peers.clone(),
+                    parent.number + 1,
+                    chunk.len() as u64,
+                ));
+                continue;
+            }
+            link_failures = 0;
+            headers.extend(chunk);
+        }
+        // Check that the chain leads to the sync head
+        if headers.last().map(|header| header.compute_block_hash()) != Some(sync_head) {
+            return Err(DownloadError::InvalidHeaderChain(sync_head_number));
+        }
+        Ok(headers)
+    }
+
+    /// Downloads the bodies for the given block headers, splitting them into batches which are requested concurrently from different peers
+    /// Bodies are validated against their headers and the resulting blocks are sent through the `sender` in order as soon as they are available,
+    /// so that they can be processed while the following batches are being downloaded
+    pub async fn download_bodies(
+        &self,
+        headers: Vec<BlockHeader>,
+        sender: mpsc::Sender<Vec<Block>>,
+    ) -> Result<(), DownloadError> {
+        let peers = self.peers().await;
+        let mut batches = headers.chunks(BODY_BATCH_SIZE);
+        let mut window = TaskWindow::new();
+        loop {
+            while !window.is_full() {
+                let Some(batch) = batches.next() else {
+                    break;
+                };
+                window.spawn(fetch_block_bodies(peers.clone(), batch.to_vec()));
+            }
+            let Some(blocks) = window.next().await else {
+                break;
+            };
+            sender
+                .send(blocks?)
+                .await
+                .map_err(|_| DownloadError::ChannelClosed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fetches the `count` headers starting from block number `start`, switching to a different peer after each failed request
+/// Returns the headers along with the peer that sent the first ones, which determine the chain the range links to
+async fn fetch_header_range(
+    peers: Vec<PeerChannels>,
+    start: BlockNumber,
+    count: u64,
+) -> Result<(PeerChannels, Vec<BlockHeader>), DownloadError> {
+    let mut headers: Vec<BlockHeader> = Vec::with_capacity(count as usize);
+    let mut peer_idx = rand::random::<usize>() % peers.len();
+    let mut first_peer = None;
+    let mut failures = 0;
+    while (headers.len() as u64) < count {
+        if failures >= MAX_RANGE_RETRIES {
+            return Err(DownloadError::HeaderRangeFailed(start));
+        }
+        let next = start + headers.len() as u64;
+        let batch = peers[peer_idx]
+            .request_block_headers_by_number(next, count - headers.len() as u64)
+            .await;
+        // Peers may return less headers than requested, keep the valid ones and request the rest
+        let valid_batch = batch.filter(|batch| {
+            match headers.last() {
+                Some(parent) => validate_header_chain(parent, batch),
+                None => validate_header_chain(&batch[0], &batch[1..]),
+            }
+            .is_ok()
+        });
+        match valid_batch {
+            Some(batch) => {
+                first_peer.get_or_insert_with(|| peers[peer_idx].clone());
+                headers.extend(batch);
+            }
+            None => {
+                failures += 1;
+                peer_idx = (peer_idx + 1) % peers.len();
+            }
+        }
+    }
+    let first_peer = first_peer.unwrap_or_else(|| peers[peer_idx].clone());
+    Ok((first_peer, headers))
+}
+
+/// Fetches the bodies for the given headers, switching to a different peer after each failed request or invalid response
+async fn fetch_block_bodies(
+    peers: Vec<PeerChannels>,
+    headers: Vec<BlockHeader>,
+) -> Result<Vec<Block>, DownloadError> {
+    let hashes = headers
+        .iter()
+        .map(|header| header.compute_block_hash())
+        .collect::<Vec<_>>();
+    let mut blocks: Vec<Block> = Vec::with_capacity(hashes.len());
+    let mut peer_idx = rand::random::<usize>() % peers.len();
+    let mut failures = 0;
+    while blocks.len() < hashes.len() {
+        if failures >= MAX_RANGE_RETRIES {
+            return Err(DownloadError::BodyRangeFailed(hashes[blocks.len()]));
+        }
+        let bodies = peers[peer_idx]
+            .request_block_bodies(hashes[blocks.len()..].to_vec())
+            .await
+            .unwrap_or_default();
+        // Keep the bodies until the first one that doesn't match its header
+        let mut valid_response = !bodies.is_empty();
+        for body in bodies {
+            let header = &headers[blocks.len()];
+            if !validate_body(header, &body) {
+                valid_response = false;
+                break;
+            }
+            blocks.push(Block::new(header.clone(), body));
+        }
+        if !valid_response {
+            failures += 1;
+            peer_idx = (peer_idx + 1) % peers.len();
+        }
+    }
+    Ok(blocks)
+}
+
+/// Checks that the headers form a chain following the given parent: each header's number is one greater than its parent's,
+/// its parent hash matches its parent's hash and its timestamp is greater than its parent's
+fn validate_header_chain(
+    parent: &BlockHeader,
+    headers: &[BlockHeader],
+) -> Result<(), DownloadError> {
+    let mut parent_hash = parent.compute_block_hash();
+    let mut parent = parent;
+    for header in headers {
+        if header.number != parent.number + 1
+            || header.parent_hash != parent_hash
+            || header.timestamp <= parent.timestamp
+        {
+            return Err(DownloadError::InvalidHeaderChain(header.number));
+        }
+        parent_hash = header.compute_block_hash();
+        parent = header;
+    }
+    Ok(())
+}
+
+/// Checks that the transactions, ommers and withdrawals in the body match the ones committed to in the header
+fn validate_body(header: &BlockHeader, body: &BlockBody) -> bool {
+    let withdrawals_match = match (&header.withdrawals_root, &body.withdrawals) {
+        (Some(root), Some(withdrawals)) => compute_withdrawals_root(withdrawals) == *root,
+        (None, None) => true,
+        _ => false,
+    };
+    withdrawals_match
+        && compute_ommers_hash(&body.ommers) == header.ommers_hash
+        && compute_transactions_root(&body.transactions) == header.transactions_root
+}
+
+/// Keeps up to `MAX_CONCURRENT_REQUESTS` spawned download tasks in flight and returns their results in the order in which they were spawned
+/// Pending tasks are aborted when the window is dropped
+struct TaskWindow<T> {
+    tasks: VecDeque<JoinHandle<Result<T, DownloadError>>>,
+}
+
+impl<T: Send + 'static> TaskWindow<T> {
+    fn new() -> Self {
+        Self {
+            tasks: VecDeque::new(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.tasks.len() >= MAX_CONCURRENT_REQUESTS
+    }
+
+    fn spawn(&mut self, task: impl Future<Output = Result<T, DownloadError>> + Send + 'static) {
+        self.tasks.push_back(tokio::spawn(task));
+    }
+
+    /// Spawns a task whose result will be returned before the ones of the tasks already in the window
+    fn spawn_front(
+        &mut self,
+        task: impl Future<Output = Result<T, DownloadError>> + Send + 'static,
+    ) {
+        self.tasks.push_front(tokio::spawn(task));
+    }
+
+    /// Waits for the oldest task to finish and returns its result, or None if there are no tasks left
+    async fn next(&mut self) -> Option<Result<T, DownloadError>> {
+        let task = self.tasks.pop_front()?;
+        Some(task.await.map_err(DownloadError::from).and_then(|res| res))
+    }
+}
+
+impl<T> Drop for TaskWindow<T> {
+    fn drop(&mut self) {
+        for task in self.tasks.iter() {
+            task.abort();
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum DownloadError {
+    #[error("Failed to fetch header for block {0}")]
+    HeaderNotFound(H256),
+    #[error("Failed to download headers starting from block {0}")]
+    HeaderRangeFailed(BlockNumber),
+    #[error("Failed to download block bodies starting from block {0}")]
+    BodyRangeFailed(H256),
+    #[error("Downloaded headers don't form a valid chain at block {0}")]
+    InvalidHeaderChain(BlockNumber),
+    #[error("Downloaded blocks receiver was dropped")]
+    ChannelClosed,
+    #[error(transparent)]
+    JoinHandle(#[from] JoinError),
+}
+
+#[cfg(test)]
+mod tests {
+    use ethrex_core::types::BlockHeader;
+
+    use super::*;
+
+    fn child_of(parent: &BlockHeader) -> BlockHeader {
+        BlockHeader {
+            parent_hash: parent.compute_block_hash(),
+            number: parent.number + 1,
+            timestamp: parent.timestamp + 12,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn valid_header_chain() {
+        let genesis = BlockHeader::default();
+        let first = child_of(&genesis);
+        let second = child_of(&first);
+        assert!(validate_header_chain(&genesis, &[first, second]).is_ok());
+    }
+
+    #[test]
+    fn header_chain_with_wrong_parent_hash() {
+        let genesis = BlockHeader::default();
+        let first = child_of(&genesis);
+        let mut second = child_of(&first);
+        second.parent_hash = H256::random();
+        assert!(matches!(
+            validate_header_chain(&genesis, &[first, second]),
+            Err(DownloadError::InvalidHeaderChain(2))
+        ));
+    }
+
+    #[test]
+    fn header_chain_with_non_increasing_timestamp() {
+        let genesis = BlockHeader::default();
+        let mut first = child_of(&genesis);
+        first.timestamp = genesis.timestamp;
+        assert!(validate_header_chain(&genesis, &[first]).is_err());
+    }
+
+    #[tokio::test]
+    async fn requeued_task_is_returned_first() {
+        let mut window = TaskWindow::new();
+        window.spawn(async { Ok(1) });
+        window.spawn(async { Ok(2) });
+        window.spawn_front(async { Ok(0) });
+        for expected in 0..3 {
+            assert_eq!(window.next().await.unwrap().unwrap(), expected);
+        }
+        assert!(window.next().await.is_none());
+    }
+
+    #[test]
+    fn peer_channels_identify_their_peer() {
+        let (peer, _, _) = PeerChannels::create();
+        let (other_peer, _, _) = PeerChannels::create();
+        assert!(peer.is_same_peer(&peer.clone()));
+        assert!(!peer.is_same_peer(&other_peer));
+    }
+
+    #[test]
+    fn body_not_matching_header() {
+        let header = BlockHeader {
+            ommers_hash: compute_ommers_hash(&[]),
+            withdrawals_root: Some(compute_withdrawals_root(&[])),
+            transactions_root: compute_transactions_root(&[]),
+            ..Default::default()
+        };
+        let mut body = BlockBody {
+            withdrawals: Some(vec![]),
+            ..BlockBody::empty()
+        };
+        assert!(validate_body(&header, &body));
+        body.withdrawals = None;
+        assert!(!validate_body(&header, &body));
+    }
+
+    #[test]
+    fn body_with_ommers() {
+        let ommers = vec![child_of(&BlockHeader::default())];
+        let header = BlockHeader {
+            ommers_hash: compute_ommers_hash(&ommers),
+            transactions_root: compute_transactions_root(&[]),
+            ..Default::default()
+        };
+        let mut body = BlockBody {
+            ommers,
+            transactions: vec![],
+            withdrawals: None,
+        };
+        assert!(validate_body(&header, &body));
+        body.ommers.clear();
+        assert!(!validate_body(&header, &body));
+    }
+}
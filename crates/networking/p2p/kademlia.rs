@@ -3,7 +3,14 @@ use crate::{
     peer_channels::PeerChannels,
     types::Node,
 };
+use bytes::BufMut;
 use ethrex_core::{H256, H512, U256};
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
 use sha3::{Digest, Keccak256};
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::info;
@@ -105,6 +112,37 @@ impl KademliaTable {
         }
     }
 
+    /// Inserts a peer loaded from the db, keeping the liveness and last pong it had when stored
+    /// The peer won't be considered proven until it answers a new ping
+    /// # Returns
+    /// A bool indicating if the peer was inserted to the table
+    pub fn insert_known_peer(&mut self, known_peer: KnownPeer) -> bool {
+        let node_id = known_peer.node.node_id;
+        let (_, inserted_to_table) = self.insert_node(known_peer.node);
+        if inserted_to_table {
+            if let Some(peer) = self.get_by_node_id_mut(node_id) {
+                peer.last_pong = known_peer.last_pong;
+                peer.liveness = known_peer.liveness;
+            }
+        }
+        inserted_to_table
+    }
+
+    /// Returns the peers from the table that have answered our pings in the last `expiration_in_hs` hours,
+    /// so that they can be stored and used to seed the table on the next startup
+    pub fn get_known_peers(&self, expiration_in_hs: u64) -> Vec<KnownPeer> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.peers.iter())
+            .filter(|peer| !is_known_peer_expired(peer.last_pong, expiration_in_hs))
+            .map(|peer| KnownPeer {
+                node: peer.node,
+                last_pong: peer.last_pong,
+                liveness: peer.liveness,
+            })
+            .collect()
+    }
+
     fn insert_as_replacement(&mut self, node: &PeerData, bucket_idx: usize) {
         let bucket = &mut self.buckets[bucket_idx];
         if bucket.replacements.len() >= MAX_NUMBER_OF_REPLACEMENTS {
@@ -290,6 +328,15 @@ impl KademliaTable {
             tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
         }
     }
+
+    /// Returns the channel ends to all active peer connections
+    pub fn get_all_peer_channels(&self) -> Vec<PeerChannels> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.peers.iter())
+            .filter_map(|peer| peer.channels.clone())
+            .collect()
+    }
 }
 
 /// Computes the distance between two nodes according to the discv4 protocol
@@ -352,6 +399,54 @@ impl PeerData {
     }
 }
 
+/// A peer that has been stored in the db so that it can be reused after a restart
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownPeer {
+    pub node: Node,
+    /// unix timestamp of the last pong received from the peer
+    pub last_pong: u64,
+    pub liveness: u16,
+}
+
+impl KnownPeer {
+    pub fn is_expired(&self, expiration_in_hs: u64) -> bool {
+        is_known_peer_expired(self.last_pong, expiration_in_hs)
+    }
+}
+
+/// Peers that never answered us or haven't done so in the last `expiration_in_hs` hours are expired
+fn is_known_peer_expired(last_pong: u64, expiration_in_hs: u64) -> bool {
+    last_pong == 0 || time_now_unix().saturating_sub(last_pong) >= expiration_in_hs * 60 * 60
+}
+
+impl RLPEncode for KnownPeer {
+    fn encode(&self, buf: &mut dyn BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.node)
+            .encode_field(&self.last_pong)
+            .encode_field(&self.liveness)
+            .finish();
+    }
+}
+
+impl RLPDecode for KnownPeer {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (node, decoder) = decoder.decode_field("node")?;
+        let (last_pong, decoder) = decoder.decode_field("last_pong")?;
+        let (liveness, decoder) = decoder.decode_field("liveness")?;
+        let remaining = decoder.finish()?;
+        Ok((
+            KnownPeer {
+                node,
+                last_pong,
+                liveness,
+            },
+            remaining,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -539,4 +634,50 @@ mod tests {
         assert!(replacement.is_none());
         assert!(len_before - 1 == len_after);
     }
+
+    #[test]
+    fn known_peers_should_be_restored_into_a_new_table() {
+        let mut table = get_test_table();
+        let node = Node {
+            ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            tcp_port: 30303,
+            udp_port: 30303,
+            node_id: node_id_from_signing_key(&SigningKey::random(&mut OsRng)),
+        };
+        let unanswered_node = Node {
+            node_id: node_id_from_signing_key(&SigningKey::random(&mut OsRng)),
+            ..node
+        };
+        table.insert_node(node);
+        table.insert_node(unanswered_node);
+        table.pong_answered(node.node_id);
+        table
+            .get_by_node_id_mut(node.node_id)
+            .unwrap()
+            .increment_liveness();
+
+        // only peers that answered our pings are known
+        let known_peers = table.get_known_peers(24);
+        assert_eq!(known_peers.len(), 1);
+        let known_peer = KnownPeer::decode(&known_peers[0].encode_to_vec()).unwrap();
+        assert_eq!(known_peer, known_peers[0]);
+        assert_eq!(known_peer.node, node);
+        assert_eq!(known_peer.liveness, 2);
+
+        let mut new_table = get_test_table();
+        assert!(new_table.insert_known_peer(known_peer.clone()));
+        assert!(!new_table.insert_known_peer(known_peer.clone()));
+        let peer = new_table.get_by_node_id(node.node_id).unwrap();
+        assert_eq!(peer.liveness, 2);
+        assert_eq!(peer.last_pong, known_peer.last_pong);
+        assert!(!peer.is_proven);
+        // not proven in the new table yet, but still known until it expires
+        assert_eq!(new_table.get_known_peers(24), vec![known_peer.clone()]);
+
+        let expired_peer = KnownPeer {
+            last_pong: time_now_unix() - 25 * 60 * 60,
+            ..known_peer
+        };
+        assert!(expired_peer.is_expired(24));
+    }
 }
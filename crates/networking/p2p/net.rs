@@ -7,29 +7,32 @@ use std::{
 
 use bootnode::BootNode;
 use discv4::{
-    get_expiration, is_expired, time_now_unix, time_since_in_hs, FindNodeMessage, Message,
-    NeighborsMessage, Packet, PingMessage, PongMessage,
+    get_expiration, is_expired, time_now_unix, time_since_in_hs, ENRResponseMessage,
+    FindNodeMessage, Message, NeighborsMessage, Packet, PingMessage, PongMessage,
 };
 use ethrex_core::{H256, H512};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 use ethrex_storage::Store;
 use k256::{
     ecdsa::SigningKey,
     elliptic_curve::{sec1::ToEncodedPoint, PublicKey},
 };
 pub use kademlia::KademliaTable;
-use kademlia::{bucket_number, MAX_NODES_PER_BUCKET};
+use kademlia::{bucket_number, KnownPeer, MAX_NODES_PER_BUCKET};
 use rand::rngs::OsRng;
-use rlpx::{connection::RLPxConnection, message::Message as RLPxMessage};
+use rlpx::{connection::RLPxConnection, eth::backend::get_status, message::Message as RLPxMessage};
 use tokio::{
     net::{TcpSocket, TcpStream, UdpSocket},
     sync::{broadcast, Mutex},
     try_join,
 };
 use tracing::{debug, error, info};
-use types::{Endpoint, Node};
+use types::{Endpoint, Node, NodeRecord};
 
 pub mod bootnode;
+pub mod crawler;
 pub(crate) mod discv4;
+pub(crate) mod downloader;
 pub(crate) mod kademlia;
 pub mod peer_channels;
 pub mod rlpx;
@@ -50,6 +53,14 @@ pub fn peer_table(signer: SigningKey) -> Arc<Mutex<KademliaTable>> {
     Arc::new(Mutex::new(KademliaTable::new(local_node_id)))
 }
 
+/// Handles needed to establish RLPx connections with the peers found via discovery
+#[derive(Clone)]
+struct RLPxHandles {
+    tcp_port: u16,
+    storage: Store,
+    connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
+}
+
 pub async fn start_network(
     udp_addr: SocketAddr,
     tcp_addr: SocketAddr,
@@ -64,13 +75,17 @@ pub async fn start_network(
         tokio::task::Id,
         Arc<RLPxMessage>,
     )>(MAX_MESSAGES_TO_BROADCAST);
+    let rlpx_handles = RLPxHandles {
+        tcp_port: tcp_addr.port(),
+        storage: storage.clone(),
+        connection_broadcast: channel_broadcast_send_end.clone(),
+    };
     let discovery_handle = tokio::spawn(discover_peers(
         udp_addr,
         signer.clone(),
-        storage.clone(),
+        Some(rlpx_handles),
         peer_table.clone(),
         bootnodes,
-        channel_broadcast_send_end.clone(),
     ));
     let server_handle = tokio::spawn(serve_requests(
         tcp_addr,
@@ -83,23 +98,40 @@ pub async fn start_network(
     try_join!(discovery_handle, server_handle).unwrap();
 }
 
+/// Starts a node that only takes part in discovery: it answers discovery requests and keeps its
+/// table up to date, but doesn't establish RLPx connections nor needs a store
+/// Useful as a lightweight bootnode for other nodes to find each other
+pub async fn start_bootnode(
+    udp_addr: SocketAddr,
+    bootnodes: Vec<BootNode>,
+    signer: SigningKey,
+    peer_table: Arc<Mutex<KademliaTable>>,
+) {
+    info!("Starting bootnode discovery service at {udp_addr}");
+    discover_peers(udp_addr, signer, None, peer_table, bootnodes).await;
+}
+
+/// Runs the discovery protocol, if `rlpx_handles` are provided RLPx connections will be started
+/// with the peers that answer our pings, and the peers found will be kept in the store across restarts
 async fn discover_peers(
     udp_addr: SocketAddr,
     signer: SigningKey,
-    storage: Store,
+    rlpx_handles: Option<RLPxHandles>,
     table: Arc<Mutex<KademliaTable>>,
     bootnodes: Vec<BootNode>,
-    connection_broadcast: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
 ) {
     let udp_socket = Arc::new(UdpSocket::bind(udp_addr).await.unwrap());
+    let node_record = Arc::new(local_node_record(udp_addr, &signer, rlpx_handles.as_ref()));
+    let storage = rlpx_handles.as_ref().map(|handles| handles.storage.clone());
+    let known_peers = storage.as_ref().map(load_known_peers).unwrap_or_default();
 
     let server_handler = tokio::spawn(discover_peers_server(
         udp_addr,
         udp_socket.clone(),
-        storage,
+        rlpx_handles,
         table.clone(),
         signer.clone(),
-        connection_broadcast,
+        node_record,
     ));
     let revalidation_handler = tokio::spawn(peers_revalidation(
         udp_addr,
@@ -115,8 +147,16 @@ async fn discover_peers(
         table.clone(),
         signer.clone(),
         bootnodes,
+        known_peers,
     )
     .await;
+    if let Some(storage) = storage {
+        tokio::spawn(known_peers_persistence(
+            table.clone(),
+            storage,
+            KNOWN_PEERS_PERSISTENCE_INTERVAL_IN_SECONDS,
+        ));
+    }
 
     // a first initial lookup runs without waiting for the interval
     // so we need to allow some time to the pinged peers to ping us back and acknowledge us
@@ -132,13 +172,34 @@ async fn discover_peers(
     try_join!(server_handler, revalidation_handler, lookup_handler).unwrap();
 }
 
+/// Builds the record we advertise to peers requesting our ENR
+/// If we accept RLPx connections, our TCP port and current fork id are advertised too
+fn local_node_record(
+    udp_addr: SocketAddr,
+    signer: &SigningKey,
+    rlpx_handles: Option<&RLPxHandles>,
+) -> NodeRecord {
+    let node = Node {
+        ip: udp_addr.ip(),
+        udp_port: udp_addr.port(),
+        tcp_port: rlpx_handles
+            .map(|handles| handles.tcp_port)
+            .unwrap_or_default(),
+        node_id: node_id_from_signing_key(signer),
+    };
+    let fork_id = rlpx_handles
+        .and_then(|handles| get_status(&handles.storage).ok())
+        .map(|status| status.fork_id);
+    NodeRecord::new(&node, time_now_unix(), signer, fork_id)
+}
+
 async fn discover_peers_server(
     udp_addr: SocketAddr,
     udp_socket: Arc<UdpSocket>,
-    storage: Store,
+    rlpx_handles: Option<RLPxHandles>,
     table: Arc<Mutex<KademliaTable>>,
     signer: SigningKey,
-    tx_broadcaster_send: broadcast::Sender<(tokio::task::Id, Arc<RLPxMessage>)>,
+    node_record: Arc<NodeRecord>,
 ) {
     let mut buf = vec![0; MAX_DISC_PACKET_SIZE];
 
@@ -220,19 +281,21 @@ async fn discover_peers_server(
                     if peer.last_ping_hash.unwrap() == msg.ping_hash {
                         table.lock().await.pong_answered(peer.node.node_id);
 
+                        // Bootnodes don't establish RLPx connections
+                        let Some(rlpx_handles) = rlpx_handles.clone() else {
+                            continue;
+                        };
                         let mut msg_buf = vec![0; read - 32];
                         buf[32..read].clone_into(&mut msg_buf);
                         let signer = signer.clone();
-                        let storage = storage.clone();
-                        let broadcaster = tx_broadcaster_send.clone();
                         tokio::spawn(async move {
                             handle_peer_as_initiator(
                                 signer,
                                 &msg_buf,
                                 &peer.node,
-                                storage,
+                                rlpx_handles.storage,
                                 table,
-                                broadcaster,
+                                rlpx_handles.connection_broadcast,
                             )
                             .await;
                         });
@@ -331,21 +394,43 @@ async fn discover_peers_server(
                     }
                 }
             }
+            Message::ENRRequest(msg) => {
+                if is_expired(msg.expiration) {
+                    debug!("Ignoring enr request msg as it is expired.");
+                    continue;
+                };
+                let is_proven = {
+                    let table = table.lock().await;
+                    table
+                        .get_by_node_id(packet.get_node_id())
+                        .is_some_and(|peer| peer.is_proven)
+                };
+                if !is_proven {
+                    debug!("Ignoring enr request as the node isn't proven!");
+                    continue;
+                }
+                let response = discv4::Message::ENRResponse(ENRResponseMessage {
+                    request_hash: packet.get_hash(),
+                    node_record: node_record.as_ref().clone(),
+                });
+                let mut buf = Vec::new();
+                response.encode_with_header(&mut buf, &signer);
+                let _ = udp_socket.send_to(&buf, from).await;
+            }
             _ => {}
         }
     }
 }
 
-// this is just an arbitrary number, maybe we should get this from some kind of cfg
-/// This is a really basic startup and should be improved when we have the nodes stored in the db
-/// currently, since we are not storing nodes, the only way to have startup nodes is by providing
-/// an array of bootnodes.
+/// Seeds the table with the given bootnodes and the peers known from previous runs, and pings them
+/// Once they answer, they will be considered proven and RLPx connections will be started with them
 async fn discovery_startup(
     udp_addr: SocketAddr,
     udp_socket: Arc<UdpSocket>,
     table: Arc<Mutex<KademliaTable>>,
     signer: SigningKey,
     bootnodes: Vec<BootNode>,
+    known_peers: Vec<KnownPeer>,
 ) {
     for bootnode in bootnodes {
         table.lock().await.insert_node(Node {
@@ -362,6 +447,66 @@ async fn discovery_startup(
             .await
             .update_peer_ping(bootnode.node_id, ping_hash);
     }
+    for known_peer in known_peers {
+        let node = known_peer.node;
+        if !table.lock().await.insert_known_peer(known_peer) {
+            continue;
+        }
+        let to_addr = SocketAddr::new(node.ip, node.udp_port);
+        let ping_hash = ping(&udp_socket, udp_addr, to_addr, &signer).await;
+        table.lock().await.update_peer_ping(node.node_id, ping_hash);
+    }
+}
+
+// these are just arbitrary numbers, maybe we should get them from some kind of cfg
+const KNOWN_PEERS_PERSISTENCE_INTERVAL_IN_SECONDS: u64 = 60 * 5;
+const KNOWN_PEER_EXPIRATION_IN_HS: u64 = 24;
+
+/// Loads the peers stored in previous runs, discarding those that haven't answered us in
+/// the last `KNOWN_PEER_EXPIRATION_IN_HS` hours
+fn load_known_peers(storage: &Store) -> Vec<KnownPeer> {
+    let stored_peers = match storage.get_known_peers() {
+        Ok(stored_peers) => stored_peers,
+        Err(error) => {
+            error!("Could not load known peers from the db: {error}");
+            return vec![];
+        }
+    };
+    let known_peers: Vec<KnownPeer> = stored_peers
+        .iter()
+        .filter_map(|encoded| KnownPeer::decode(encoded).ok())
+        .filter(|known_peer| !known_peer.is_expired(KNOWN_PEER_EXPIRATION_IN_HS))
+        .collect();
+    info!("Loaded {} known peers from the db", known_peers.len());
+    known_peers
+}
+
+/// Starts a tokio scheduler that periodically stores the peers from the table that answered our pings,
+/// replacing the previously stored ones so that peers removed from the table or expired are dropped
+async fn known_peers_persistence(
+    table: Arc<Mutex<KademliaTable>>,
+    storage: Store,
+    interval_time_in_seconds: u64,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_time_in_seconds));
+    // first tick starts immediately
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let known_peers = table
+            .lock()
+            .await
+            .get_known_peers(KNOWN_PEER_EXPIRATION_IN_HS);
+        debug!("Storing {} known peers", known_peers.len());
+        let known_peers = known_peers
+            .iter()
+            .map(|known_peer| (known_peer.node.node_id, known_peer.encode_to_vec()))
+            .collect();
+        if let Err(error) = storage.set_known_peers(known_peers) {
+            error!("Could not store known peers: {error}");
+        }
+    }
 }
 
 const REVALIDATION_INTERVAL_IN_SECONDS: usize = 30; // this is just an arbitrary number, maybe we should get this from some kind of cfg
@@ -878,14 +1023,21 @@ mod tests {
             tokio::task::Id,
             Arc<RLPxMessage>,
         )>(MAX_MESSAGES_TO_BROADCAST);
+        let rlpx_handles = RLPxHandles {
+            tcp_port: 0,
+            storage,
+            connection_broadcast: channel_broadcast_send_end,
+        };
         if should_start_server {
+            // The test store has no chain config to compute the fork id from
+            let node_record = Arc::new(local_node_record(addr, &signer, None));
             tokio::spawn(discover_peers_server(
                 addr,
                 udp_socket.clone(),
-                storage.clone(),
+                Some(rlpx_handles),
                 table.clone(),
                 signer.clone(),
-                channel_broadcast_send_end,
+                node_record,
             ));
         }
 
@@ -1045,6 +1197,41 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    /** This test tests the crawler, the idea is as follows:
+     * - We'll start two discovery servers (`a` & `b`) that will connect between each other
+     * - We'll crawl the network using `a` as the only bootnode
+     * - We expect to find both servers, along with their records
+     */
+    async fn discovery_crawler() {
+        let mut server_a = start_mock_discovery_server(8006, true).await;
+        let mut server_b = start_mock_discovery_server(8007, true).await;
+
+        connect_servers(&mut server_a, &mut server_b).await;
+
+        let bootnode = BootNode {
+            node_id: server_a.node_id,
+            socket_address: server_a.addr,
+        };
+        let crawler_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8008);
+        let crawled_nodes = crawler::crawl(
+            crawler_addr,
+            SigningKey::random(&mut OsRng),
+            vec![bootnode],
+            10,
+        )
+        .await
+        .unwrap();
+
+        for node_id in [server_a.node_id, server_b.node_id] {
+            let node = crawled_nodes
+                .iter()
+                .find(|node| node.node_id == node_id)
+                .unwrap();
+            assert!(node.record.is_some());
+        }
+    }
+
     #[tokio::test]
     /** This test tests the lookup function, the idea is as follows:
      * - We'll start four discovery servers (`a`, `b`, `c` & `d`)
@@ -1,21 +1,26 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
 use bytes::Bytes;
 use ethrex_core::{
-    types::{AccountState, BlockBody, BlockHeader},
+    types::{AccountState, BlockBody, BlockHeader, BlockNumber, Receipt},
     H256, U256,
 };
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_trie::verify_range;
+use ethrex_trie::{verify_range, Nibbles, Node};
 use tokio::sync::{mpsc, Mutex};
 
 use crate::{
     rlpx::{
-        eth::blocks::{
-            BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders, BLOCK_HEADER_LIMIT,
+        eth::{
+            blocks::{
+                BlockBodies, BlockHeaders, GetBlockBodies, GetBlockHeaders, HashOrNumber,
+                BLOCK_HEADER_LIMIT,
+            },
+            receipts::{GetReceipts, Receipts},
         },
         snap::{
-            AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, StorageRanges,
+            AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
+            StorageRanges, TrieNodes,
         },
     },
     snap::encodable_to_proof,
@@ -52,17 +57,62 @@ impl PeerChannels {
         )
     }
 
+    /// Returns whether both channels lead to the same peer connection
+    pub fn is_same_peer(&self, other: &PeerChannels) -> bool {
+        self.sender.same_channel(&other.sender)
+    }
+
     /// Requests block headers from the peer, starting from the `start` block hash towards newer blocks
     /// Returns the block headers or None if:
     /// - There are no available peers (the node just started up or was rejected by all other nodes)
     /// - The response timed out
     /// - The response was empty or not valid
     pub async fn request_block_headers(&self, start: H256) -> Option<Vec<BlockHeader>> {
+        self.request_block_header_range(start.into(), BLOCK_HEADER_LIMIT)
+            .await
+    }
+
+    /// Requests the header of the block with the given hash from the peer
+    /// Returns the block header or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_block_header(&self, hash: H256) -> Option<BlockHeader> {
+        let block_header = self
+            .request_block_header_range(hash.into(), 1)
+            .await?
+            .remove(0);
+        (block_header.compute_block_hash() == hash).then_some(block_header)
+    }
+
+    /// Requests up to `limit` consecutive block headers from the peer, starting from the `start` block number towards newer blocks
+    /// Returns the block headers or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_block_headers_by_number(
+        &self,
+        start: BlockNumber,
+        limit: u64,
+    ) -> Option<Vec<BlockHeader>> {
+        let block_headers = self
+            .request_block_header_range(HashOrNumber::Number(start), limit)
+            .await?;
+        // Check that the response starts at the requested block and does not contain more headers than requested
+        (block_headers[0].number == start && block_headers.len() as u64 <= limit)
+            .then_some(block_headers)
+    }
+
+    async fn request_block_header_range(
+        &self,
+        start: HashOrNumber,
+        limit: u64,
+    ) -> Option<Vec<BlockHeader>> {
         let request_id = rand::random();
         let request = RLPxMessage::GetBlockHeaders(GetBlockHeaders {
             id: request_id,
-            startblock: start.into(),
-            limit: BLOCK_HEADER_LIMIT,
+            startblock: start,
+            limit,
             skip: 0,
             reverse: false,
         });
@@ -121,6 +171,38 @@ impl PeerChannels {
         (!block_bodies.is_empty() && block_bodies.len() <= block_hashes_len).then_some(block_bodies)
     }
 
+    /// Requests all receipts in a set of blocks from the peer given their block hashes
+    /// Returns the lists of receipts or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_receipts(&self, block_hashes: Vec<H256>) -> Option<Vec<Vec<Receipt>>> {
+        let block_hashes_len = block_hashes.len();
+        let request_id = rand::random();
+        let request = RLPxMessage::GetReceipts(GetReceipts {
+            id: request_id,
+            block_hashes,
+        });
+        self.sender.send(request).await.ok()?;
+        let mut receiver = self.receiver.lock().await;
+        let receipts = tokio::time::timeout(PEER_REPLY_TIMOUT, async move {
+            loop {
+                match receiver.recv().await {
+                    Some(RLPxMessage::Receipts(Receipts { id, receipts })) if id == request_id => {
+                        return Some(receipts)
+                    }
+                    // Ignore replies that don't match the expected id (such as late responses)
+                    Some(_) => continue,
+                    None => return None,
+                }
+            }
+        })
+        .await
+        .ok()??;
+        // Check that the response is not empty and does not contain more receipts than the ones requested
+        (!receipts.is_empty() && receipts.len() <= block_hashes_len).then_some(receipts)
+    }
+
     /// Requests an account range from the peer given the state trie's root and the starting hash (the limit hash will be the maximum value of H256)
     /// Will also return a boolean indicating if there is more state to be fetched towards the right of the trie
     /// Returns the response message or None if:
@@ -318,4 +400,154 @@ impl PeerChannels {
         }
         Some((storage_keys, storage_values, should_continue))
     }
+
+    /// Requests the remaining storage range of a single account, starting from the `start` hashed key
+    /// Used to continue fetching the storage of accounts whose storage was too big to be fetched in a single request
+    /// Will also return a boolean indicating if there is more storage to be fetched towards the right of the trie
+    /// Returns the list of hashed storage keys and values or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_storage_range(
+        &self,
+        state_root: H256,
+        storage_root: H256,
+        account_hash: H256,
+        start: H256,
+    ) -> Option<(Vec<H256>, Vec<U256>, bool)> {
+        let request_id = rand::random();
+        let request = RLPxMessage::GetStorageRanges(GetStorageRanges {
+            id: request_id,
+            root_hash: state_root,
+            account_hashes: vec![account_hash],
+            starting_hash: start,
+            limit_hash: HASH_MAX,
+            response_bytes: MAX_RESPONSE_BYTES,
+        });
+        self.sender.send(request).await.ok()?;
+        let mut receiver = self.receiver.lock().await;
+        let (mut slots, proof) = tokio::time::timeout(PEER_REPLY_TIMOUT, async move {
+            loop {
+                match receiver.recv().await {
+                    Some(RLPxMessage::StorageRanges(StorageRanges { id, slots, proof }))
+                        if id == request_id =>
+                    {
+                        return Some((slots, proof))
+                    }
+                    // Ignore replies that don't match the expected id (such as late responses)
+                    Some(_) => continue,
+                    None => return None,
+                }
+            }
+        })
+        .await
+        .ok()??;
+        // We only requested a single storage range
+        if slots.len() != 1 {
+            return None;
+        }
+        // Unzip & validate response
+        let proof = encodable_to_proof(&proof);
+        let (storage_keys, storage_values): (Vec<H256>, Vec<U256>) = slots
+            .remove(0)
+            .into_iter()
+            .map(|slot| (slot.hash, slot.data))
+            .unzip();
+        let encoded_values = storage_values
+            .iter()
+            .map(|val| val.encode_to_vec())
+            .collect::<Vec<_>>();
+        // As we are requesting a range starting from a key other than the first, we will always receive proofs
+        let should_continue =
+            verify_range(storage_root, &start, &storage_keys, &encoded_values, &proof).ok()?;
+        Some((storage_keys, storage_values, should_continue))
+    }
+
+    /// Requests state trie nodes given the root of the trie where they are contained and their paths (be them full or partial)
+    /// Returns the nodes or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_state_trienodes(
+        &self,
+        state_root: H256,
+        paths: Vec<Nibbles>,
+    ) -> Option<Vec<Node>> {
+        let request_id = rand::random();
+        let expected_nodes = paths.len();
+        let request = RLPxMessage::GetTrieNodes(GetTrieNodes {
+            id: request_id,
+            root_hash: state_root,
+            // [acc_path, acc_path,...] -> [[acc_path], [acc_path]]
+            paths: paths
+                .into_iter()
+                .map(|vec| vec![Bytes::from(vec.encode_compact())])
+                .collect(),
+            bytes: MAX_RESPONSE_BYTES,
+        });
+        let nodes = self.request_trienodes(request_id, request).await?;
+        (!nodes.is_empty() && nodes.len() <= expected_nodes).then_some(nodes)
+    }
+
+    /// Requests storage trie nodes given the root of the state trie where they are contained and
+    /// a hashmap mapping the path to the account in the state trie (aka hashed address) to the paths to the nodes in its storage trie (can be full or partial)
+    /// Returns the nodes or None if:
+    /// - There are no available peers (the node just started up or was rejected by all other nodes)
+    /// - The response timed out
+    /// - The response was empty or not valid
+    pub async fn request_storage_trienodes(
+        &self,
+        state_root: H256,
+        paths: BTreeMap<H256, Vec<Nibbles>>,
+    ) -> Option<Vec<Node>> {
+        let request_id = rand::random();
+        let expected_nodes = paths.values().map(|paths| paths.len()).sum::<usize>();
+        let request = RLPxMessage::GetTrieNodes(GetTrieNodes {
+            id: request_id,
+            root_hash: state_root,
+            // {acc_path: [path, path, ...]} -> [[acc_path, path, path, ...]]
+            paths: paths
+                .into_iter()
+                .map(|(acc_path, paths)| {
+                    [
+                        vec![Bytes::from(acc_path.0.to_vec())],
+                        paths
+                            .into_iter()
+                            .map(|path| Bytes::from(path.encode_compact()))
+                            .collect(),
+                    ]
+                    .concat()
+                })
+                .collect(),
+            bytes: MAX_RESPONSE_BYTES,
+        });
+        let nodes = self.request_trienodes(request_id, request).await?;
+        (!nodes.is_empty() && nodes.len() <= expected_nodes).then_some(nodes)
+    }
+
+    /// Sends a GetTrieNodes request and waits for its response, decoding the received nodes
+    /// Returns None if the response timed out or contained invalid nodes
+    async fn request_trienodes(&self, request_id: u64, request: RLPxMessage) -> Option<Vec<Node>> {
+        self.sender.send(request).await.ok()?;
+        let mut receiver = self.receiver.lock().await;
+        let nodes = tokio::time::timeout(PEER_REPLY_TIMOUT, async move {
+            loop {
+                match receiver.recv().await {
+                    Some(RLPxMessage::TrieNodes(TrieNodes { id, nodes })) if id == request_id => {
+                        return Some(nodes)
+                    }
+                    // Ignore replies that don't match the expected id (such as late responses)
+                    Some(_) => continue,
+                    None => return None,
+                }
+            }
+        })
+        .await
+        .ok()??;
+        nodes
+            .iter()
+            .map(|node| Node::decode_raw(node))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+    }
 }
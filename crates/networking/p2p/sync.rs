@@ -1,23 +1,53 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::{Arc, Mutex as StdMutex, MutexGuard, PoisonError},
+};
 
 use ethrex_blockchain::error::ChainError;
 use ethrex_core::{
-    types::{Block, BlockHash, BlockHeader, EMPTY_KECCACK_HASH},
+    types::{
+        code_hash, AccountState, Block, BlockHash, BlockHeader, BlockNumber, EMPTY_KECCACK_HASH,
+    },
     H256,
 };
-use ethrex_rlp::encode::RLPEncode;
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 use ethrex_storage::{error::StoreError, Store};
-use ethrex_trie::EMPTY_TRIE_HASH;
+use ethrex_trie::{LeafNode, Nibbles, Node, NodeHash, TrieDB, TrieError, EMPTY_TRIE_HASH};
+use ethrex_vm::EvmConfig;
+use sha3::{Digest, Keccak256};
 use tokio::{
     sync::{
-        mpsc::{self, error::SendError, Receiver, Sender},
+        mpsc::{self, error::SendError, Receiver},
         Mutex,
     },
+    task::JoinError,
     time::Instant,
 };
 use tracing::{debug, info, warn};
 
-use crate::kademlia::KademliaTable;
+use crate::{
+    downloader::{DownloadError, Downloader},
+    kademlia::KademliaTable,
+    peer_channels::HASH_MAX,
+};
+
+/// Maximum amount of times we will ask a peer for an account/storage range or trie nodes
+/// before giving up and assuming that the pivot block's state is no longer available (stale)
+const MAX_RETRIES: usize = 10;
+/// The minimum amount of blocks from the head that we want to full sync during a snap sync
+const MIN_FULL_BLOCKS: usize = 64;
+/// Max size of a bytecode batch
+const BYTECODE_BATCH_SIZE: usize = 200;
+/// Max size of a storage range batch
+const STORAGE_BATCH_SIZE: usize = 100;
+/// Max size of a state trie node batch
+const NODE_BATCH_SIZE: usize = 900;
+/// Max amount of accounts whose storage trie nodes are requested in a single batch
+const STORAGE_HEAL_BATCH_SIZE: usize = 200;
+/// Max size of a receipt batch
+const BLOCK_BATCH_SIZE: usize = 128;
+/// Max amount of downloaded block batches waiting to be executed or stored
+const MAX_PENDING_BLOCK_BATCHES: usize = 8;
 
 #[derive(Debug)]
 pub enum SyncMode {
@@ -25,17 +55,100 @@ pub enum SyncMode {
     Snap,
 }
 
+/// Progress of an active sync process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncProgress {
+    /// Latest canonical block when the sync process started
+    pub starting_block: BlockNumber,
+    /// Number of the sync head, same as the starting block until its header is downloaded
+    pub highest_block: BlockNumber,
+}
+
+/// Status of the sync process, shared between the `SyncManager` and the engine API
+/// so that it can be queried and updated while a sync cycle is running
+#[derive(Debug, Clone, Default)]
+pub struct SyncStatus(Arc<StdMutex<SyncStatusInner>>);
+
+#[derive(Debug, Default)]
+struct SyncStatusInner {
+    /// Latest sync head sent by the consensus client that we haven't started syncing to yet
+    pending_sync_head: Option<H256>,
+    /// Progress of the active sync process, if any
+    progress: Option<SyncProgress>,
+}
+
+impl SyncStatus {
+    /// Sets the head we should sync to, replacing any older head that wasn't synced to yet
+    pub fn set_sync_head(&self, sync_head: H256) {
+        self.lock().pending_sync_head = Some(sync_head);
+    }
+
+    /// Returns the progress of the active sync process, or None if we are not syncing
+    pub fn progress(&self) -> Option<SyncProgress> {
+        self.lock().progress
+    }
+
+    /// Returns true if there is an active sync process
+    pub fn is_syncing(&self) -> bool {
+        self.lock().progress.is_some()
+    }
+
+    /// Returns true if there is a sync head that we haven't started syncing to yet
+    pub fn has_sync_head(&self) -> bool {
+        self.lock().pending_sync_head.is_some()
+    }
+
+    fn take_sync_head(&self) -> Option<H256> {
+        self.lock().pending_sync_head.take()
+    }
+
+    fn start(&self, starting_block: BlockNumber) {
+        self.lock().progress = Some(SyncProgress {
+            starting_block,
+            highest_block: starting_block,
+        });
+    }
+
+    fn set_highest_block(&self, highest_block: BlockNumber) {
+        if let Some(progress) = self.lock().progress.as_mut() {
+            progress.highest_block = highest_block;
+        }
+    }
+
+    fn finish(&self) {
+        self.lock().progress = None;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SyncStatusInner> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
 /// Manager in charge the sync process
-/// Only performs full-sync but will also be in charge of snap-sync in the future
+/// Performs either full-sync or snap-sync depending on the sync mode
 #[derive(Debug)]
 pub struct SyncManager {
     sync_mode: SyncMode,
+    /// How the downloaded blocks are executed
+    evm_config: EvmConfig,
     peers: Arc<Mutex<KademliaTable>>,
+    downloader: Downloader,
+    status: SyncStatus,
 }
 
 impl SyncManager {
-    pub fn new(peers: Arc<Mutex<KademliaTable>>, sync_mode: SyncMode) -> Self {
-        Self { sync_mode, peers }
+    pub fn new(
+        peers: Arc<Mutex<KademliaTable>>,
+        sync_mode: SyncMode,
+        evm_config: EvmConfig,
+    ) -> Self {
+        Self {
+            sync_mode,
+            evm_config,
+            downloader: Downloader::new(peers.clone()),
+            peers,
+            status: SyncStatus::default(),
+        }
     }
 
     /// Creates a dummy SyncManager for tests where syncing is not needed
@@ -44,27 +157,69 @@ impl SyncManager {
         let dummy_peer_table = Arc::new(Mutex::new(KademliaTable::new(Default::default())));
         Self {
             sync_mode: SyncMode::Full,
+            evm_config: EvmConfig::default(),
+            downloader: Downloader::new(dummy_peer_table.clone()),
             peers: dummy_peer_table,
+            status: SyncStatus::default(),
+        }
+    }
+
+    /// Returns a handle to the status of the sync process, which can be used to query its progress
+    /// and to set new sync heads while a sync is running
+    pub fn status(&self) -> SyncStatus {
+        self.status.clone()
+    }
+
+    /// Syncs to the latest sync head set in the sync status (see `SyncStatus::set_sync_head`),
+    /// starting from the latest canonical block
+    /// If a newer sync head is set while a sync cycle is running, a new cycle will be started
+    /// once it finishes, until there are no more sync heads left to sync to
+    pub async fn sync_to_latest_head(&mut self, store: Store) {
+        while let Some(sync_head) = self.status.take_sync_head() {
+            // Skip heads that are already part of the canonical chain
+            if is_canonical(&store, sync_head).unwrap_or_default() {
+                continue;
+            }
+            let current_head = match store
+                .get_latest_block_number()
+                .and_then(|number| store.get_canonical_block_hash(number))
+            {
+                Ok(Some(current_head)) => current_head,
+                Ok(None) => {
+                    warn!("Missing latest canonical block, aborting sync");
+                    return;
+                }
+                Err(error) => {
+                    warn!("Failed to fetch latest canonical block due to {error}, aborting sync");
+                    return;
+                }
+            };
+            self.start_sync(current_head, sync_head, store.clone())
+                .await;
         }
     }
 
     /// Starts a sync cycle, updating the state with all blocks between the current head and the sync head
     /// Will perforn either full or snap sync depending on the manager's `snap_mode`
     /// In full mode, all blocks will be fetched via p2p eth requests and executed to rebuild the state
-    /// In snap mode, blocks and receipts will be fetched and stored in parallel while the state is fetched via p2p snap requests
-    /// After the sync cycle is complete, the sync mode will be set to full
+    /// In snap mode, a pivot block close to the sync head will be selected, blocks and receipts up to the pivot will be fetched and stored
+    /// in parallel while the pivot's state is fetched via p2p snap requests and healed, the blocks after the pivot are then full-synced
+    /// After a snap sync cycle is complete, the sync mode will be set to full
     /// If the sync fails, no error will be returned but a warning will be emitted
+    /// Snap sync progress is persisted in the store so that an aborted snap sync can be resumed in a later cycle
     pub async fn start_sync(&mut self, current_head: H256, sync_head: H256, store: Store) {
         info!("Syncing from current head {current_head} to sync_head {sync_head}");
         let start_time = Instant::now();
-        match self.sync_cycle(current_head, sync_head, store).await {
+        self.status
+            .start(store.get_latest_block_number().unwrap_or_default());
+        let result = self.sync_cycle(current_head, sync_head, store).await;
+        self.status.finish();
+        match result {
             Ok(()) => {
                 info!(
                     "Sync finished, time elapsed: {} secs",
                     start_time.elapsed().as_secs()
                 );
-                // Next sync will be full-sync
-                self.sync_mode = SyncMode::Full;
             }
             Err(error) => warn!(
                 "Sync failed due to {error}, time elapsed: {} secs ",
@@ -76,94 +231,132 @@ impl SyncManager {
     /// Performs the sync cycle described in `start_sync`, returns an error if the sync fails at any given step and aborts all active processes
     async fn sync_cycle(
         &mut self,
-        mut current_head: H256,
+        current_head: H256,
         sync_head: H256,
         store: Store,
     ) -> Result<(), SyncError> {
         // Request all block headers between the current head and the sync head
         // We will begin from the current head so that we download the earliest state first
-        // This step is not parallelized
+        // Header ranges are requested concurrently from all available peers
         let mut all_block_headers = vec![];
         let mut all_block_hashes = vec![];
-        loop {
-            let peer = self.peers.lock().await.get_peer_channels().await;
-            debug!("Requesting Block Headers from {current_head}");
-            // Request Block Headers from Peer
-            if let Some(block_headers) = peer.request_block_headers(current_head).await {
-                debug!("Received {} block headers", block_headers.len());
-                let block_hashes = block_headers
-                    .iter()
-                    .map(|header| header.compute_block_hash())
-                    .collect::<Vec<_>>();
-                // Keep headers so we can process them later
-                // Discard the first header as we already have it
-                all_block_headers.extend_from_slice(&block_headers[1..]);
-                all_block_hashes.extend_from_slice(&block_hashes[1..]);
-
-                // Check if we already reached our sync head or if we need to fetch more blocks
-                if !block_hashes.contains(&sync_head) {
-                    // Update the request to fetch the next batch
-                    current_head = *block_hashes.last().unwrap();
-                } else {
-                    // No more headers to request
-                    break;
-                }
+        // Check if we have some blocks downloaded from a previous sync attempt
+        if matches!(self.sync_mode, SyncMode::Snap) {
+            if let Some(last_header) = store.get_header_download_checkpoint()? {
+                // Recover the headers we already downloaded and resume from the last one
+                (all_block_hashes, all_block_headers) =
+                    recover_downloaded_headers(current_head, last_header, &store)?;
             }
         }
+        // Don't request headers if we already reached the sync head in a previous attempt
+        if !all_block_hashes.contains(&sync_head) {
+            let start_header = match all_block_headers.last() {
+                Some(header) => header.clone(),
+                None => store
+                    .get_block_header_by_hash(current_head)?
+                    .ok_or(SyncError::MissingHeader(current_head))?,
+            };
+            let block_headers = self
+                .downloader
+                .download_headers(&start_header, sync_head)
+                .await?;
+            debug!("Downloaded {} block headers", block_headers.len());
+            // Store the headers so that they can be recovered if this sync cycle is aborted
+            for header in block_headers {
+                let hash = header.compute_block_hash();
+                store.add_block_header(hash, header.clone())?;
+                store.add_block_number(hash, header.number)?;
+                all_block_hashes.push(hash);
+                all_block_headers.push(header);
+            }
+            if matches!(self.sync_mode, SyncMode::Snap) {
+                store.set_header_download_checkpoint(sync_head)?;
+            }
+        }
+        if let Some(sync_head_header) = all_block_headers.last() {
+            self.status.set_highest_block(sync_head_header.number);
+        }
         // We finished fetching all headers, now we can process them
         match self.sync_mode {
             SyncMode::Snap => {
-                // snap-sync: launch tasks to fetch blocks and state in parallel
-                // - Fetch each block's state via snap p2p requests
-                // - Fetch each blocks and its receipts via eth p2p requests
-                // TODO: We are currently testing against our implementation that doesn't hold an independant snapshot and can provide all historic state
-                //       We should fetch all available state and then resort to state healing to fetch the rest
-                let (bytecode_sender, bytecode_receiver) = mpsc::channel::<Vec<H256>>(500);
-                let mut set = tokio::task::JoinSet::new();
-                set.spawn(bytecode_fetcher(
-                    bytecode_receiver,
-                    self.peers.clone(),
-                    store.clone(),
-                ));
-                set.spawn(fetch_blocks_and_receipts(
-                    all_block_hashes.clone(),
-                    self.peers.clone(),
+                // Discard any headers past the sync head (they may have been fetched in a previous attempt)
+                if let Some(sync_head_idx) = all_block_hashes.iter().position(|h| *h == sync_head) {
+                    all_block_hashes.truncate(sync_head_idx + 1);
+                    all_block_headers.truncate(sync_head_idx + 1);
+                }
+                if all_block_hashes.is_empty() {
+                    return Ok(());
+                }
+                // snap-sync: select a pivot block that is close enough to the head so that its state is still available
+                // - Fetch the pivot's state via snap p2p requests and heal it
+                // - Fetch all blocks up to the pivot and their receipts via eth p2p requests
+                // - Full sync the blocks after the pivot
+                let pivot_idx = all_block_hashes.len().saturating_sub(MIN_FULL_BLOCKS);
+                // The downloaded state comes without the preimages of its addresses and keys
+                store.set_preimages_incomplete()?;
+                let pivot_header = all_block_headers[pivot_idx].clone();
+                debug!(
+                    "Selected block {} as pivot for snap sync",
+                    pivot_header.number
+                );
+                let (snap_block_hashes, full_block_hashes) =
+                    all_block_hashes.split_at(pivot_idx + 1);
+                let (snap_block_headers, full_block_headers) =
+                    all_block_headers.split_at(pivot_idx + 1);
+                // Skip the blocks whose bodies we already stored in a previous attempt
+                let mut pending_block_hashes = vec![];
+                let mut pending_block_headers = vec![];
+                for (hash, header) in snap_block_hashes.iter().zip(snap_block_headers) {
+                    if store.get_block_body_by_hash(*hash)?.is_none() {
+                        pending_block_hashes.push(*hash);
+                        pending_block_headers.push(header.clone());
+                    }
+                }
+                let store_bodies_handle = tokio::spawn(store_block_bodies(
+                    pending_block_headers,
+                    self.downloader.clone(),
                     store.clone(),
                 ));
-                let state_roots = all_block_headers
-                    .iter()
-                    .map(|header| header.state_root)
-                    .collect::<Vec<_>>();
-                set.spawn(fetch_snap_state(
-                    bytecode_sender,
-                    state_roots.clone(),
+                let store_receipts_handle = tokio::spawn(store_receipts(
+                    pending_block_hashes,
                     self.peers.clone(),
                     store.clone(),
                 ));
-                // Store headers
-                let mut latest_block_number = 0;
-                for (header, hash) in all_block_headers
-                    .into_iter()
-                    .zip(all_block_hashes.into_iter())
-                {
-                    latest_block_number = header.number;
-                    store.set_canonical_block(header.number, hash)?;
-                    store.add_block_header(hash, header)?;
+                // Perform snap sync
+                if !snap_sync(pivot_header.state_root, &store, self.peers.clone()).await? {
+                    // Snap sync was not completed, abort and resume it on the next cycle
+                    store_bodies_handle.abort();
+                    store_receipts_handle.abort();
+                    return Err(SyncError::StalePivot);
                 }
-                // If all processes failed then they are likely to have a common cause (such as unaccessible storage), so return the first error
-                for result in set.join_all().await {
-                    result?;
+                // Wait for all bodies and receipts to be downloaded
+                store_bodies_handle.await??;
+                store_receipts_handle.await??;
+                // Set the blocks up to the pivot as canonical
+                for (hash, header) in snap_block_hashes.iter().zip(snap_block_headers.iter()) {
+                    store.set_canonical_block(header.number, *hash)?;
+                }
+                store.update_latest_block_number(pivot_header.number)?;
+                // The snap sync is complete, clear its checkpoints and switch to full sync for the remaining blocks
+                store.clear_snap_state()?;
+                self.sync_mode = SyncMode::Full;
+                if !full_block_hashes.is_empty() {
+                    download_and_run_blocks(
+                        full_block_headers.to_vec(),
+                        self.downloader.clone(),
+                        store.clone(),
+                        self.evm_config,
+                    )
+                    .await?
                 }
-                // Set latest block number here to avoid reading state that is currently being synced
-                store.update_latest_block_number(latest_block_number)?;
             }
             SyncMode::Full => {
                 // full-sync: Fetch all block bodies and execute them sequentially to build the state
                 download_and_run_blocks(
-                    all_block_hashes,
                     all_block_headers,
-                    self.peers.clone(),
+                    self.downloader.clone(),
                     store.clone(),
+                    self.evm_config,
                 )
                 .await?
             }
@@ -172,183 +365,247 @@ impl SyncManager {
     }
 }
 
+/// Returns true if the block with the given hash is part of the canonical chain
+fn is_canonical(store: &Store, block_hash: BlockHash) -> Result<bool, StoreError> {
+    let Some(number) = store.get_block_number(block_hash)? else {
+        return Ok(false);
+    };
+    Ok(store.get_canonical_block_hash(number)? == Some(block_hash))
+}
+
+/// Recovers the block headers downloaded in a previous sync attempt by following the parent hashes from the last downloaded header back to the current head
+/// Returns the hashes and headers (excluding the current head's) ordered from oldest to newest
+/// If the downloaded headers don't lead back to the current head they are discarded and empty lists are returned
+fn recover_downloaded_headers(
+    current_head: H256,
+    last_header: H256,
+    store: &Store,
+) -> Result<(Vec<BlockHash>, Vec<BlockHeader>), StoreError> {
+    let mut block_hashes = vec![];
+    let mut block_headers = vec![];
+    let mut next_hash = last_header;
+    while next_hash != current_head {
+        let Some(header) = store.get_block_header_by_hash(next_hash)? else {
+            warn!("Failed to recover downloaded headers from previous sync attempt");
+            return Ok((vec![], vec![]));
+        };
+        block_hashes.push(next_hash);
+        next_hash = header.parent_hash;
+        block_headers.push(header);
+    }
+    block_hashes.reverse();
+    block_headers.reverse();
+    Ok((block_hashes, block_headers))
+}
+
 /// Requests block bodies from peers via p2p, executes and stores them
-/// Returns an error if there was a problem while executing or validating the blocks
+/// Bodies are downloaded concurrently while the blocks already received are being executed
+/// Returns an error if there was a problem while downloading, executing or validating the blocks
 async fn download_and_run_blocks(
-    mut block_hashes: Vec<BlockHash>,
-    mut block_headers: Vec<BlockHeader>,
-    peers: Arc<Mutex<KademliaTable>>,
+    block_headers: Vec<BlockHeader>,
+    downloader: Downloader,
     store: Store,
-) -> Result<(), ChainError> {
-    loop {
-        let peer = peers.lock().await.get_peer_channels().await;
-        debug!("Requesting Block Bodies ");
-        if let Some(block_bodies) = peer.request_block_bodies(block_hashes.clone()).await {
-            let block_bodies_len = block_bodies.len();
-            debug!("Received {} Block Bodies", block_bodies_len);
-            // Execute and store blocks
-            for body in block_bodies.into_iter() {
-                // We already validated that there are no more block bodies than the ones requested
-                let header = block_headers.remove(0);
-                let hash = block_hashes.remove(0);
-                let number = header.number;
-                let block = Block::new(header, body);
-                if let Err(error) = ethrex_blockchain::add_block(&block, &store) {
-                    warn!("Failed to add block during FullSync: {error}");
-                    return Err(error);
-                }
-                store.set_canonical_block(number, hash)?;
-                store.update_latest_block_number(number)?;
-            }
-            debug!("Executed & stored {} blocks", block_bodies_len);
-            // Check if we need to ask for another batch
-            if block_hashes.is_empty() {
-                break;
+    evm_config: EvmConfig,
+) -> Result<(), SyncError> {
+    let (sender, mut receiver) = mpsc::channel::<Vec<Block>>(MAX_PENDING_BLOCK_BATCHES);
+    let download_handle =
+        tokio::spawn(async move { downloader.download_bodies(block_headers, sender).await });
+    while let Some(blocks) = receiver.recv().await {
+        let blocks_len = blocks.len();
+        // Execute and store blocks
+        for block in blocks {
+            let hash = block.hash();
+            let number = block.header.number;
+            if let Err(error) = ethrex_blockchain::add_block(&block, &store, evm_config) {
+                warn!("Failed to add block during FullSync: {error}");
+                download_handle.abort();
+                return Err(error.into());
             }
+            store.set_canonical_block(number, hash)?;
+            store.update_latest_block_number(number)?;
         }
+        debug!("Executed & stored {} blocks", blocks_len);
     }
+    download_handle.await??;
     Ok(())
 }
 
-async fn fetch_blocks_and_receipts(
+/// Fetches all block bodies for the given block headers via p2p and stores them
+async fn store_block_bodies(
+    block_headers: Vec<BlockHeader>,
+    downloader: Downloader,
+    store: Store,
+) -> Result<(), SyncError> {
+    let (sender, mut receiver) = mpsc::channel::<Vec<Block>>(MAX_PENDING_BLOCK_BATCHES);
+    let download_handle =
+        tokio::spawn(async move { downloader.download_bodies(block_headers, sender).await });
+    while let Some(blocks) = receiver.recv().await {
+        debug!(" Received {} Block Bodies", blocks.len());
+        for block in blocks {
+            store.add_block_body(block.hash(), block.body)?;
+        }
+    }
+    download_handle.await??;
+    Ok(())
+}
+
+/// Fetches all receipts for the given block hashes via p2p and stores them
+async fn store_receipts(
     mut block_hashes: Vec<BlockHash>,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
 ) -> Result<(), SyncError> {
-    // Snap state fetching will take much longer than this so we don't need to paralelize fetching blocks and receipts
-    // Fetch Block Bodies
-    loop {
+    while !block_hashes.is_empty() {
         let peer = peers.lock().await.get_peer_channels().await;
-        debug!("Requesting Block Headers ");
-        if let Some(block_bodies) = peer.request_block_bodies(block_hashes.clone()).await {
-            debug!(" Received {} Block Bodies", block_bodies.len());
-            // Track which bodies we have already fetched
-            let (fetched_hashes, remaining_hashes) = block_hashes.split_at(block_bodies.len());
-            // Store Block Bodies
-            for (hash, body) in fetched_hashes.iter().zip(block_bodies.into_iter()) {
-                store.add_block_body(*hash, body)?
-            }
-
-            // Check if we need to ask for another batch
-            if remaining_hashes.is_empty() {
-                break;
-            } else {
-                block_hashes = remaining_hashes.to_vec();
+        let batch = &block_hashes[..BLOCK_BATCH_SIZE.min(block_hashes.len())];
+        debug!("Requesting Receipts ");
+        if let Some(receipts) = peer.request_receipts(batch.to_vec()).await {
+            debug!(" Received {} Receipts", receipts.len());
+            // Track which blocks we have already fetched receipts for
+            let fetched_hashes = block_hashes.drain(..receipts.len());
+            // Store Receipts
+            for (hash, receipts) in fetched_hashes.zip(receipts) {
+                store.add_receipts(hash, receipts)?
             }
         }
     }
-    // TODO: Fetch Receipts and store them
     Ok(())
 }
 
-async fn fetch_snap_state(
-    bytecode_sender: Sender<Vec<H256>>,
-    state_roots: Vec<BlockHash>,
+/// Rebuilds the pivot block's state trie by fetching it via snap requests and then heals it
+/// Returns true if the state was fully synced and healed, and false if the pivot became stale before we could finish
+/// The progress will be stored in the snap state checkpoints so that it can be resumed in a later cycle
+async fn snap_sync(
+    state_root: H256,
+    store: &Store,
     peers: Arc<Mutex<KademliaTable>>,
-    store: Store,
-) -> Result<(), SyncError> {
-    debug!("Syncing state roots: {}", state_roots.len());
-    // Fetch newer state first: This will be useful to detect where to switch to healing
-    for state_root in state_roots.into_iter().rev() {
-        // TODO: maybe spawn taks here instead of awaiting
-        rebuild_state_trie(
-            bytecode_sender.clone(),
-            state_root,
-            peers.clone(),
-            store.clone(),
-        )
-        .await?
-    }
-    // We finished syncing the available state, lets make the fetcher processes aware
-    // Send empty batches to signal that no more batches are incoming
-    bytecode_sender.send(vec![]).await?;
-    Ok(())
+) -> Result<bool, SyncError> {
+    // Check if we already fetched all account ranges in a previous cycle
+    let key_checkpoint = store.get_state_trie_key_checkpoint()?;
+    if key_checkpoint != Some(HASH_MAX)
+        && !rebuild_state_trie(state_root, peers.clone(), store.clone()).await?
+    {
+        return Ok(false);
+    }
+    // Fetch the remaining state by healing the trie
+    heal_state_trie(state_root, store.clone(), peers).await
 }
 
-/// Rebuilds a Block's state trie by requesting snap state from peers
+/// Rebuilds a Block's state trie by requesting snap state from peers, resuming from the latest checkpoint if available
+/// Accounts whose storage could not be fully fetched will be queued for storage healing
+/// Returns false if the state root became stale before all account ranges were fetched
 async fn rebuild_state_trie(
-    bytecode_sender: Sender<Vec<H256>>,
     state_root: H256,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
-) -> Result<(), SyncError> {
-    // Spawn a storage fetcher for this blocks's storage
+) -> Result<bool, SyncError> {
+    // Resume download from checkpoint if available or start from an empty trie
+    // We cannot keep an open trie here so we will track the root between lookups
+    let mut start_account_hash = store.get_state_trie_key_checkpoint()?.unwrap_or_default();
+    let mut current_state_root = store
+        .get_state_trie_root_checkpoint()?
+        .unwrap_or(*EMPTY_TRIE_HASH);
+    // Spawn storage & bytecode fetchers
+    let (bytecode_sender, bytecode_receiver) = mpsc::channel::<Vec<H256>>(500);
     let (storage_sender, storage_receiver) = mpsc::channel::<Vec<(H256, H256)>>(500);
-    let storage_fetcher_handler = tokio::spawn(storage_fetcher(
+    let bytecode_fetcher_handle = tokio::spawn(bytecode_fetcher(
+        bytecode_receiver,
+        peers.clone(),
+        store.clone(),
+    ));
+    let storage_fetcher_handle = tokio::spawn(storage_fetcher(
         storage_receiver,
         peers.clone(),
         store.clone(),
         state_root,
     ));
-    let mut start_account_hash = H256::zero();
-    // Start from an empty state trie
-    // We cannot keep an open trie here so we will track the root between lookups
-    let mut current_state_root = *EMPTY_TRIE_HASH;
+    let mut retry_count = 0;
+    let mut completed = false;
     // Fetch Account Ranges
-    loop {
-        let peer = peers.clone().lock().await.get_peer_channels().await;
+    while retry_count < MAX_RETRIES {
+        let peer = peers.lock().await.get_peer_channels().await;
         debug!("Requesting Account Range for state root {state_root}, starting hash: {start_account_hash}");
-        if let Some((account_hashes, accounts, should_continue)) = peer
+        let Some((account_hashes, accounts, should_continue)) = peer
             .request_account_range(state_root, start_account_hash)
             .await
-        {
-            // Update starting hash for next batch
-            if should_continue {
-                start_account_hash = *account_hashes.last().unwrap();
-            }
-            // Fetch Account Storage & Bytecode
-            let mut code_hashes = vec![];
-            let mut account_hashes_and_storage_roots = vec![];
-            for (account_hash, account) in account_hashes.iter().zip(accounts.iter()) {
-                // Build the batch of code hashes to send to the bytecode fetcher
-                // Ignore accounts without code / code we already have stored
-                if account.code_hash != *EMPTY_KECCACK_HASH
-                    && store.get_account_code(account.code_hash)?.is_none()
-                {
-                    code_hashes.push(account.code_hash)
-                }
-                // Build the batch of hashes and roots to send to the storage fetcher
-                // Ignore accounts without storage
-                // TODO: We could also check if the account's storage root is already part of the trie
-                // Aka, if the account was not changed shouldn't fetch the state we already have
-                if account.storage_root != *EMPTY_TRIE_HASH {
-                    account_hashes_and_storage_roots.push((*account_hash, account.storage_root));
-                }
+        else {
+            retry_count += 1;
+            continue;
+        };
+        retry_count = 0;
+        // Fetch Account Storage & Bytecode
+        let mut code_hashes = vec![];
+        let mut account_hashes_and_storage_roots = vec![];
+        for (account_hash, account) in account_hashes.iter().zip(accounts.iter()) {
+            // Build the batch of code hashes to send to the bytecode fetcher
+            // Ignore accounts without code / code we already have stored
+            if account.code_hash != *EMPTY_KECCACK_HASH
+                && store.get_account_code(account.code_hash)?.is_none()
+            {
+                code_hashes.push(account.code_hash)
             }
-            // Send code hash batch to the bytecode fetcher
-            if !code_hashes.is_empty() {
-                bytecode_sender.send(code_hashes).await?;
+            // Build the batch of hashes and roots to send to the storage fetcher
+            // Ignore accounts without storage and accounts whose storage we already have
+            if account.storage_root != *EMPTY_TRIE_HASH
+                && !store.contains_storage_node(*account_hash, account.storage_root)?
+            {
+                account_hashes_and_storage_roots.push((*account_hash, account.storage_root));
             }
-            // Send hash and root batch to the storage fetcher
-            if !account_hashes_and_storage_roots.is_empty() {
-                storage_sender
-                    .send(account_hashes_and_storage_roots)
-                    .await?;
-            }
-            // Update trie
-            let mut trie = store.open_state_trie(current_state_root);
-            for (account_hash, account) in account_hashes.iter().zip(accounts.iter()) {
-                trie.insert(account_hash.0.to_vec(), account.encode_to_vec())
-                    .map_err(StoreError::Trie)?;
-            }
-            current_state_root = trie.hash().map_err(StoreError::Trie)?;
+        }
+        // Send code hash batch to the bytecode fetcher
+        if !code_hashes.is_empty() {
+            bytecode_sender.send(code_hashes).await?;
+        }
+        // Send hash and root batch to the storage fetcher
+        if !account_hashes_and_storage_roots.is_empty() {
+            storage_sender
+                .send(account_hashes_and_storage_roots)
+                .await?;
+        }
+        // Update trie
+        let mut trie = store.open_state_trie(current_state_root);
+        for (account_hash, account) in account_hashes.iter().zip(accounts.iter()) {
+            trie.insert(account_hash.0.to_vec(), account.encode_to_vec())?;
+        }
+        current_state_root = trie.hash()?;
 
-            if !should_continue {
-                // All accounts fetched!
-                break;
-            }
+        if !should_continue {
+            // All accounts fetched!
+            completed = true;
+            break;
         }
+        // Update starting hash for next batch
+        start_account_hash = *account_hashes.last().unwrap();
     }
-    if current_state_root != state_root {
-        warn!("State sync failed for state root {state_root}");
+    // Save the current progress so it can be resumed in a later cycle
+    // Mark the state as fully fetched if we finished fetching all account ranges
+    store.set_state_trie_key_checkpoint(if completed {
+        HASH_MAX
+    } else {
+        start_account_hash
+    })?;
+    store.set_state_trie_root_checkpoint(current_state_root)?;
+    if completed {
+        debug!("Completed state sync for state root {state_root}");
+    } else {
+        debug!("Pivot state root {state_root} became stale while fetching account ranges");
     }
     // Send empty batch to signal that no more batches are incoming
     storage_sender.send(vec![]).await?;
-    storage_fetcher_handler
-        .await
-        .map_err(|_| StoreError::Custom(String::from("Failed to join storage_fetcher task")))??;
-    debug!("Completed state sync for state root {state_root}");
-    Ok(())
+    bytecode_sender.send(vec![]).await?;
+    // Accounts whose storage could not be fully fetched will be healed later on
+    let stale_storages = storage_fetcher_handle.await??;
+    bytecode_fetcher_handle.await??;
+    if !stale_storages.is_empty() {
+        let mut storage_heal_paths = store.get_storage_heal_paths()?.unwrap_or_default();
+        storage_heal_paths.extend(
+            stale_storages
+                .into_iter()
+                .map(|account_hash| (account_hash, vec![Nibbles::default()])),
+        );
+        store.set_storage_heal_paths(storage_heal_paths)?;
+    }
+    Ok(completed)
 }
 
 /// Waits for incoming code hashes from the receiver channel endpoint, queues them, and fetches and stores their bytecodes in batches
@@ -357,17 +614,24 @@ async fn bytecode_fetcher(
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
 ) -> Result<(), SyncError> {
-    const BATCH_SIZE: usize = 200;
     // Pending list of bytecodes to fetch
     let mut pending_bytecodes: Vec<H256> = vec![];
+    // Keep track of the queued code hashes so we don't fetch the same code twice
+    let mut queued_bytecodes: HashSet<H256> = HashSet::new();
     loop {
         match receiver.recv().await {
             Some(code_hashes) if !code_hashes.is_empty() => {
                 // Add hashes to the queue
-                pending_bytecodes.extend(code_hashes);
+                pending_bytecodes.extend(
+                    code_hashes
+                        .into_iter()
+                        .filter(|hash| queued_bytecodes.insert(*hash)),
+                );
                 // If we have enought pending bytecodes to fill a batch, spawn a fetch process
-                while pending_bytecodes.len() >= BATCH_SIZE {
-                    let next_batch = pending_bytecodes.drain(..BATCH_SIZE).collect::<Vec<_>>();
+                while pending_bytecodes.len() >= BYTECODE_BATCH_SIZE {
+                    let next_batch = pending_bytecodes
+                        .drain(..BYTECODE_BATCH_SIZE)
+                        .collect::<Vec<_>>();
                     let remaining =
                         fetch_bytecode_batch(next_batch, peers.clone(), store.clone()).await?;
                     // Add unfeched bytecodes back to the queue
@@ -381,7 +645,7 @@ async fn bytecode_fetcher(
     // We have no more incoming requests, process the remaining batches
     while !pending_bytecodes.is_empty() {
         let next_batch = pending_bytecodes
-            .drain(..BATCH_SIZE.min(pending_bytecodes.len()))
+            .drain(..BYTECODE_BATCH_SIZE.min(pending_bytecodes.len()))
             .collect::<Vec<_>>();
         let remaining = fetch_bytecode_batch(next_batch, peers.clone(), store.clone()).await?;
         // Add unfeched bytecodes back to the queue
@@ -400,8 +664,11 @@ async fn fetch_bytecode_batch(
         let peer = peers.lock().await.get_peer_channels().await;
         if let Some(bytecodes) = peer.request_bytecodes(batch.clone()).await {
             debug!("Received {} bytecodes", bytecodes.len());
-            // Store the bytecodes
+            // Store the bytecodes, stopping at the first one that doesn't match the requested hash
             for code in bytecodes.into_iter() {
+                if code_hash(&code) != batch[0] {
+                    break;
+                }
                 store.add_account_code(batch.remove(0), code)?;
             }
             // Return remaining code hashes in the batch if we couldn't fetch all of them
@@ -410,58 +677,69 @@ async fn fetch_bytecode_batch(
     }
 }
 
-/// Waits for incoming account hashes & storage roots from the receiver channel endpoint, queues them, and fetches and stores their bytecodes in batches
+/// Waits for incoming account hashes & storage roots from the receiver channel endpoint, queues them, and fetches and stores their storage ranges in batches
+/// Returns the hashed addresses of the accounts whose storage could not be fetched because the state root became stale
 async fn storage_fetcher(
     mut receiver: Receiver<Vec<(H256, H256)>>,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
     state_root: H256,
-) -> Result<(), StoreError> {
-    const BATCH_SIZE: usize = 100;
-    // Pending list of bytecodes to fetch
+) -> Result<Vec<H256>, StoreError> {
+    // Pending list of storages to fetch
     let mut pending_storage: Vec<(H256, H256)> = vec![];
-    // TODO: Also add a queue for storages that were incompletely fecthed,
-    // but for the first iteration we will asume not fully fetched -> fetch again
+    // Accounts whose storage could not be fetched and will need to be healed
+    let mut stale_storages: Vec<H256> = vec![];
+    // Once the state root becomes stale we stop requesting storage ranges
+    let mut stale = false;
     loop {
         match receiver.recv().await {
             Some(account_and_root) if !account_and_root.is_empty() => {
                 // Add hashes to the queue
                 pending_storage.extend(account_and_root);
-                // If we have enought pending bytecodes to fill a batch, spawn a fetch process
-                while pending_storage.len() >= BATCH_SIZE {
-                    let next_batch = pending_storage.drain(..BATCH_SIZE).collect::<Vec<_>>();
-                    let remaining =
+                // If we have enought pending storages to fill a batch, spawn a fetch process
+                while !stale && pending_storage.len() >= STORAGE_BATCH_SIZE {
+                    let next_batch = pending_storage
+                        .drain(..STORAGE_BATCH_SIZE)
+                        .collect::<Vec<_>>();
+                    let (remaining, failed) =
                         fetch_storage_batch(next_batch, state_root, peers.clone(), store.clone())
                             .await?;
-                    // Add unfeched bytecodes back to the queue
+                    stale_storages.extend(failed);
+                    // Add unfeched storages back to the queue
+                    stale |= remaining.len() == STORAGE_BATCH_SIZE;
                     pending_storage.extend(remaining);
                 }
             }
-            // Disconnect / Empty message signaling no more bytecodes to sync
+            // Disconnect / Empty message signaling no more storages to sync
             _ => break,
         }
     }
     // We have no more incoming requests, process the remaining batches
-    while !pending_storage.is_empty() {
-        let next_batch = pending_storage
-            .drain(..BATCH_SIZE.min(pending_storage.len()))
-            .collect::<Vec<_>>();
-        let remaining =
+    while !stale && !pending_storage.is_empty() {
+        let batch_size = STORAGE_BATCH_SIZE.min(pending_storage.len());
+        let next_batch = pending_storage.drain(..batch_size).collect::<Vec<_>>();
+        let (remaining, failed) =
             fetch_storage_batch(next_batch, state_root, peers.clone(), store.clone()).await?;
-        // Add unfeched bytecodes back to the queue
+        stale_storages.extend(failed);
+        // Add unfeched storages back to the queue
+        stale |= remaining.len() == batch_size;
         pending_storage.extend(remaining);
     }
-    Ok(())
+    // Storages we couldn't fetch will be healed
+    stale_storages.extend(pending_storage.into_iter().map(|(hash, _)| hash));
+    Ok(stale_storages)
 }
 
-/// Receives a batch of account hashes with their storage roots, fetches their respective storage ranges via p2p and returns a list of the code hashes that couldn't be fetched in the request (if applicable)
+/// Receives a batch of account hashes with their storage roots, fetches their respective storage ranges via p2p and stores them
+/// Returns a list of the accounts & roots that couldn't be fetched in the request (if applicable), which will be the full batch if the state root is stale,
+/// and the list of accounts whose storage was fetched but will need to be healed
 async fn fetch_storage_batch(
     mut batch: Vec<(H256, H256)>,
     state_root: H256,
     peers: Arc<Mutex<KademliaTable>>,
     store: Store,
-) -> Result<Vec<(H256, H256)>, StoreError> {
-    loop {
+) -> Result<(Vec<(H256, H256)>, Vec<H256>), StoreError> {
+    for _ in 0..MAX_RETRIES {
         let peer = peers.lock().await.get_peer_channels().await;
         let (batch_hahses, batch_roots) = batch.clone().into_iter().unzip();
         if let Some((mut keys, mut values, incomplete)) = peer
@@ -469,29 +747,572 @@ async fn fetch_storage_batch(
             .await
         {
             debug!("Received {} storage ranges", keys.len());
-            let mut _last_range;
-            // Hold on to the last batch (if incomplete)
+            let mut failed = vec![];
+            // Handle the last range separately if it is incomplete
             if incomplete {
                 // An incomplete range cannot be empty
-                _last_range = (keys.pop().unwrap(), values.pop().unwrap());
+                let (last_keys, last_values) = (keys.pop().unwrap(), values.pop().unwrap());
+                let (account_hash, storage_root) = batch.remove(keys.len());
+                if !handle_large_storage_range(
+                    state_root,
+                    account_hash,
+                    storage_root,
+                    last_keys,
+                    last_values,
+                    peers.clone(),
+                    store.clone(),
+                )
+                .await?
+                {
+                    failed.push(account_hash);
+                }
             }
             // Store the storage ranges & rebuild the storage trie for each account
-            for (keys, values) in keys.into_iter().zip(values.into_iter()) {
+            for (keys, values) in keys.into_iter().zip(values) {
                 let (account_hash, storage_root) = batch.remove(0);
                 let mut trie = store.open_storage_trie(account_hash, *EMPTY_TRIE_HASH);
-                for (key, value) in keys.into_iter().zip(values.into_iter()) {
+                for (key, value) in keys.into_iter().zip(values) {
                     trie.insert(key.0.to_vec(), value.encode_to_vec())?;
                 }
                 if trie.hash()? != storage_root {
                     warn!("State sync failed for storage root {storage_root}");
+                    failed.push(account_hash);
                 }
             }
-            // TODO: if the last range is incomplete add it to the incomplete batches queue
-            // For now we will fetch the full range again
             // Return remaining code hashes in the batch if we couldn't fetch all of them
-            return Ok(batch);
+            return Ok((batch, failed));
         }
     }
+    // The state root is most likely stale
+    Ok((batch, vec![]))
+}
+
+/// Fetches the full storage of an account whose storage was too big to be fetched in a single request
+/// Receives the first range of keys and values, which were already fetched and validated
+/// Returns false if the storage could not be fully fetched (and will need to be healed)
+async fn handle_large_storage_range(
+    state_root: H256,
+    account_hash: H256,
+    storage_root: H256,
+    keys: Vec<H256>,
+    values: Vec<ethrex_core::U256>,
+    peers: Arc<Mutex<KademliaTable>>,
+    store: Store,
+) -> Result<bool, StoreError> {
+    // Store the first range
+    let mut next_key = *keys.last().unwrap();
+    let mut current_root = {
+        let mut trie = store.open_storage_trie(account_hash, *EMPTY_TRIE_HASH);
+        for (key, value) in keys.into_iter().zip(values) {
+            trie.insert(key.0.to_vec(), value.encode_to_vec())?;
+        }
+        trie.hash()?
+    };
+    // Keep fetching the following ranges
+    let mut retry_count = 0;
+    while retry_count < MAX_RETRIES {
+        let peer = peers.lock().await.get_peer_channels().await;
+        let Some((keys, values, should_continue)) = peer
+            .request_storage_range(state_root, storage_root, account_hash, next_key)
+            .await
+        else {
+            retry_count += 1;
+            continue;
+        };
+        retry_count = 0;
+        next_key = *keys.last().unwrap_or(&next_key);
+        let mut trie = store.open_storage_trie(account_hash, current_root);
+        for (key, value) in keys.into_iter().zip(values) {
+            trie.insert(key.0.to_vec(), value.encode_to_vec())?;
+        }
+        current_root = trie.hash()?;
+        if !should_continue {
+            break;
+        }
+    }
+    Ok(current_root == storage_root)
+}
+
+/// Heals the trie given its state_root by fetching any missing nodes in it via p2p
+/// Resumes from the paths stored in a previous healing attempt (if any)
+/// Returns true if healing was fully completed or false if we need to resume healing on the next sync cycle
+async fn heal_state_trie(
+    state_root: H256,
+    store: Store,
+    peers: Arc<Mutex<KademliaTable>>,
+) -> Result<bool, SyncError> {
+    // Spawn a storage healer & bytecode fetcher for the accounts found during healing
+    let (bytecode_sender, bytecode_receiver) = mpsc::channel::<Vec<H256>>(500);
+    let (storage_sender, storage_receiver) = mpsc::channel::<Vec<H256>>(500);
+    let bytecode_fetcher_handle = tokio::spawn(bytecode_fetcher(
+        bytecode_receiver,
+        peers.clone(),
+        store.clone(),
+    ));
+    let storage_healer_handle = tokio::spawn(storage_healer(
+        state_root,
+        storage_receiver,
+        peers.clone(),
+        store.clone(),
+    ));
+    // Resume healing from the paths left by a previous attempt
+    // Also start from the root, as the pivot may have changed since then
+    let mut paths: VecDeque<Nibbles> = store.get_state_heal_paths()?.unwrap_or_default().into();
+    if !store.contains_state_node(state_root)? {
+        // Fetch it first, as the resumed paths can only be reached through it
+        paths.push_front(Nibbles::default());
+    }
+    let get_state_node = |hash| stored_node(store.open_state_trie(*EMPTY_TRIE_HASH).db(), hash);
+    let mut retry_count = 0;
+    while !paths.is_empty() && retry_count < MAX_RETRIES {
+        // Pair the next paths with the hash referenced by their parent node
+        let mut batch: Vec<(Nibbles, H256)> = vec![];
+        let mut incomplete = vec![];
+        while batch.len() < NODE_BATCH_SIZE {
+            let Some(path) = paths.pop_front() else {
+                break;
+            };
+            match follow_stored_path(state_root, &path, get_state_node)? {
+                StoredPath::Node(hash) => batch.push((path, hash)),
+                // Left by a previous pivot, retry once the nodes leading to it are healed
+                StoredPath::Incomplete => incomplete.push(path),
+                // Left by a previous pivot and no longer part of the trie
+                StoredPath::Absent => {}
+            }
+        }
+        paths.extend(incomplete);
+        if batch.is_empty() {
+            // None of the pending paths can be reached until the nodes leading to them are healed
+            break;
+        }
+        let peer = peers.lock().await.get_peer_channels().await;
+        let requested_paths = batch.iter().map(|(path, _)| path.clone()).collect();
+        let Some(nodes) = peer
+            .request_state_trienodes(state_root, requested_paths)
+            .await
+        else {
+            retry_count += 1;
+            requeue_paths(&mut paths, batch);
+            continue;
+        };
+        if !valid_state_nodes(&batch, &nodes) {
+            debug!("Received invalid state nodes");
+            retry_count += 1;
+            requeue_paths(&mut paths, batch);
+            continue;
+        }
+        retry_count = 0;
+        debug!("Received {} state nodes", nodes.len());
+        // Return the paths of the nodes that the peer didn't send to the queue
+        let unfetched = batch.split_off(nodes.len().min(batch.len()));
+        requeue_paths(&mut paths, unfetched);
+        let mut hashed_addresses = vec![];
+        let mut code_hashes = vec![];
+        let mut nodes_to_write = vec![];
+        // Process the nodes in the order in which they were requested
+        for ((path, hash), node) in batch.into_iter().zip(nodes) {
+            // If the node is a leaf, we need to check the account's storage and bytecode
+            // Leaves at invalid paths were already rejected along with the response
+            if let Node::Leaf(leaf) = &node {
+                if let Some(hashed_address) = leaf_hashed_path(&path, leaf) {
+                    let account =
+                        AccountState::decode(&leaf.value).map_err(StoreError::RLPDecode)?;
+                    if account.storage_root != *EMPTY_TRIE_HASH
+                        && !store.contains_storage_node(hashed_address, account.storage_root)?
+                    {
+                        hashed_addresses.push(hashed_address);
+                    }
+                    if account.code_hash != *EMPTY_KECCACK_HASH
+                        && store.get_account_code(account.code_hash)?.is_none()
+                    {
+                        code_hashes.push(account.code_hash);
+                    }
+                }
+            }
+            // Queue the node's missing children for healing
+            let missing_children =
+                node_missing_children(&node, &path, |hash| store.contains_state_node(hash))?;
+            paths.extend(missing_children);
+            nodes_to_write.push((NodeHash::Hashed(hash).into(), node.encode_to_vec()));
+        }
+        // Write the nodes to the state trie
+        store
+            .open_state_trie(*EMPTY_TRIE_HASH)
+            .db()
+            .put_batch(nodes_to_write)
+            .map_err(StoreError::Trie)?;
+        // Send the storage & bytecodes requests
+        if !hashed_addresses.is_empty() {
+            storage_sender.send(hashed_addresses).await?;
+        }
+        if !code_hashes.is_empty() {
+            bytecode_sender.send(code_hashes).await?;
+        }
+    }
+    debug!("State Healing stopped, pending paths: {}", paths.len());
+    // Save the pending paths so that healing can be resumed later on
+    let state_healed = paths.is_empty();
+    store.set_state_heal_paths(paths.into())?;
+    // Send empty batch to signal that no more batches are incoming
+    storage_sender.send(vec![]).await?;
+    bytecode_sender.send(vec![]).await?;
+    let storage_healed = storage_healer_handle.await??;
+    bytecode_fetcher_handle.await??;
+    Ok(state_healed && storage_healed)
+}
+
+/// Waits for incoming hashed addresses from the receiver channel endpoint and queues the associated root nodes for state retrieval
+/// Also retrieves their children nodes until we have the full storage trie stored
+/// Resumes from the paths stored in a previous healing attempt (if any) and saves the pending paths on exit
+/// Returns true if all storages were healed or false if the state root became stale before we could finish
+async fn storage_healer(
+    state_root: H256,
+    mut receiver: Receiver<Vec<H256>>,
+    peers: Arc<Mutex<KademliaTable>>,
+    store: Store,
+) -> Result<bool, SyncError> {
+    // Pending list of storages to heal, mapping hashed address to the paths of the nodes we need to fetch
+    let mut pending_paths: BTreeMap<H256, Vec<Nibbles>> = BTreeMap::new();
+    for (hashed_address, paths) in store.get_storage_heal_paths()?.unwrap_or_default() {
+        pending_paths
+            .entry(hashed_address)
+            .or_default()
+            .extend(paths);
+    }
+    let mut incoming = true;
+    let mut stale = false;
+    // Set when none of the pending paths can be reached until more state nodes are healed
+    let mut blocked = false;
+    while !stale && (incoming || !pending_paths.is_empty()) {
+        // If we have enough pending storages to fill a batch, or there is no more incoming data, heal them
+        if !blocked
+            && (pending_paths.len() >= STORAGE_HEAL_BATCH_SIZE
+                || (!incoming && !pending_paths.is_empty()))
+        {
+            match heal_storage_batch(state_root, &mut pending_paths, peers.clone(), &store).await? {
+                StorageHealOutcome::Healed => {}
+                // Wait for more state nodes, or resume on the next cycle if there won't be any
+                StorageHealOutcome::Blocked if incoming => blocked = true,
+                StorageHealOutcome::Blocked => break,
+                StorageHealOutcome::Stale => stale = true,
+            }
+            continue;
+        }
+        blocked = false;
+        match receiver.recv().await {
+            Some(hashed_addresses) if !hashed_addresses.is_empty() => {
+                for hashed_address in hashed_addresses {
+                    pending_paths
+                        .entry(hashed_address)
+                        .or_default()
+                        .push(Nibbles::default());
+                }
+            }
+            // Disconnect / Empty message signaling no more storages to heal
+            _ => incoming = false,
+        }
+    }
+    // Keep receiving incoming requests (if any) so that they are stored along with the pending ones
+    if incoming {
+        while let Some(hashed_addresses) = receiver.recv().await {
+            if hashed_addresses.is_empty() {
+                break;
+            }
+            for hashed_address in hashed_addresses {
+                pending_paths
+                    .entry(hashed_address)
+                    .or_default()
+                    .push(Nibbles::default());
+            }
+        }
+    }
+    let healed = pending_paths.is_empty();
+    // Save the pending paths so that healing can be resumed later on
+    store.set_storage_heal_paths(pending_paths.into_iter().collect())?;
+    Ok(healed)
+}
+
+/// Outcome of healing a batch of storage tries
+enum StorageHealOutcome {
+    Healed,
+    /// None of the pending paths can be reached until more state nodes are healed
+    Blocked,
+    /// The state root became stale
+    Stale,
+}
+
+/// Receives a set of storage trie paths (grouped by their corresponding account's hashed address),
+/// fetches their respective nodes, stores them, and queues their missing children for healing
+async fn heal_storage_batch(
+    state_root: H256,
+    pending_paths: &mut BTreeMap<H256, Vec<Nibbles>>,
+    peers: Arc<Mutex<KademliaTable>>,
+    store: &Store,
+) -> Result<StorageHealOutcome, SyncError> {
+    // Take the first accounts that can be reached out of the queue, pairing each path with the
+    // hash referenced by its parent node or the account's storage root
+    let mut batch: BTreeMap<H256, Vec<(Nibbles, H256)>> = BTreeMap::new();
+    let mut incomplete = vec![];
+    while batch.len() < STORAGE_HEAL_BATCH_SIZE {
+        let Some((hashed_address, paths)) = pending_paths.pop_first() else {
+            break;
+        };
+        let storage_root = match stored_storage_root(store, state_root, hashed_address)? {
+            StoredPath::Node(storage_root) => storage_root,
+            StoredPath::Incomplete => {
+                incomplete.push((hashed_address, paths));
+                continue;
+            }
+            StoredPath::Absent => continue,
+        };
+        let get_storage_node = |hash| {
+            stored_node(
+                store
+                    .open_storage_trie(hashed_address, *EMPTY_TRIE_HASH)
+                    .db(),
+                hash,
+            )
+        };
+        let mut reachable = vec![];
+        let mut unreachable = vec![];
+        for path in paths {
+            match follow_stored_path(storage_root, &path, get_storage_node)? {
+                StoredPath::Node(hash) => reachable.push((path, hash)),
+                StoredPath::Incomplete => unreachable.push(path),
+                StoredPath::Absent => {}
+            }
+        }
+        if !unreachable.is_empty() {
+            incomplete.push((hashed_address, unreachable));
+        }
+        if !reachable.is_empty() {
+            batch.insert(hashed_address, reachable);
+        }
+    }
+    for (hashed_address, paths) in incomplete {
+        pending_paths
+            .entry(hashed_address)
+            .or_default()
+            .extend(paths);
+    }
+    if batch.is_empty() {
+        return Ok(StorageHealOutcome::Blocked);
+    }
+    let requested_paths: BTreeMap<H256, Vec<Nibbles>> = batch
+        .iter()
+        .map(|(hashed_address, paths)| {
+            let paths = paths.iter().map(|(path, _)| path.clone()).collect();
+            (*hashed_address, paths)
+        })
+        .collect();
+    for _ in 0..MAX_RETRIES {
+        let peer = peers.lock().await.get_peer_channels().await;
+        let Some(nodes) = peer
+            .request_storage_trienodes(state_root, requested_paths.clone())
+            .await
+        else {
+            continue;
+        };
+        // Discard the whole response if any node isn't the one referenced by its parent
+        if !batch
+            .values()
+            .flatten()
+            .zip(&nodes)
+            .all(|((_, hash), node)| node_hash(node) == *hash)
+        {
+            debug!("Received invalid storage nodes");
+            continue;
+        }
+        debug!("Received {} storage nodes", nodes.len());
+        // Process the nodes for each account path in the order in which they were requested
+        let mut nodes = nodes.into_iter();
+        for (hashed_address, paths) in batch.iter_mut() {
+            let mut nodes_to_write = vec![];
+            let fetched_paths = paths
+                .drain(..nodes.len().min(paths.len()))
+                .collect::<Vec<_>>();
+            for ((path, hash), node) in fetched_paths.into_iter().zip(nodes.by_ref()) {
+                // Queue the node's missing children for healing
+                let missing_children = node_missing_children(&node, &path, |hash| {
+                    store.contains_storage_node(*hashed_address, hash)
+                })?;
+                pending_paths
+                    .entry(*hashed_address)
+                    .or_default()
+                    .extend(missing_children);
+                nodes_to_write.push((NodeHash::Hashed(hash).into(), node.encode_to_vec()));
+            }
+            store
+                .open_storage_trie(*hashed_address, *EMPTY_TRIE_HASH)
+                .db()
+                .put_batch(nodes_to_write)
+                .map_err(StoreError::Trie)?;
+            if nodes.len() == 0 {
+                break;
+            }
+        }
+        // Return the remaining paths to the queue
+        requeue_storage_paths(pending_paths, batch);
+        return Ok(StorageHealOutcome::Healed);
+    }
+    // The state root is most likely stale, return all paths to the queue
+    requeue_storage_paths(pending_paths, batch);
+    Ok(StorageHealOutcome::Stale)
+}
+
+/// Returns the paths of the given batch to the front of the state healing queue
+fn requeue_paths(paths: &mut VecDeque<Nibbles>, batch: Vec<(Nibbles, H256)>) {
+    for (path, _) in batch.into_iter().rev() {
+        paths.push_front(path);
+    }
+}
+
+/// Returns the paths of the given batch to the storage healing queue
+fn requeue_storage_paths(
+    pending_paths: &mut BTreeMap<H256, Vec<Nibbles>>,
+    batch: BTreeMap<H256, Vec<(Nibbles, H256)>>,
+) {
+    for (hashed_address, paths) in batch {
+        if !paths.is_empty() {
+            pending_paths
+                .entry(hashed_address)
+                .or_default()
+                .extend(paths.into_iter().map(|(path, _)| path));
+        }
+    }
+}
+
+/// Where a trie path leads when following the nodes already stored from the trie's root
+#[derive(Debug, PartialEq)]
+enum StoredPath {
+    /// The path leads to the node with the given hash, as referenced by its parent
+    Node(H256),
+    /// A node along the path hasn't been stored yet
+    Incomplete,
+    /// The path isn't part of the trie
+    Absent,
+}
+
+/// Follows the path from the root through the stored nodes, to find the hash that the node at the
+/// path must have
+fn follow_stored_path(
+    root_hash: H256,
+    path: &Nibbles,
+    get_node: impl Fn(H256) -> Result<Option<Node>, StoreError>,
+) -> Result<StoredPath, StoreError> {
+    let mut hash = root_hash;
+    let mut path = path.clone();
+    while !path.is_empty() {
+        let Some(node) = get_node(hash)? else {
+            return Ok(StoredPath::Incomplete);
+        };
+        let child = match node {
+            Node::Branch(node) => match path.next_choice() {
+                Some(choice) => node.choices[choice].clone(),
+                None => return Ok(StoredPath::Absent),
+            },
+            Node::Extension(node) if path.skip_prefix(&node.prefix) => node.child,
+            _ => return Ok(StoredPath::Absent),
+        };
+        // Inlined children are contained in their parent, so they are never requested
+        let NodeHash::Hashed(child_hash) = child else {
+            return Ok(StoredPath::Absent);
+        };
+        hash = child_hash;
+    }
+    Ok(StoredPath::Node(hash))
+}
+
+/// Looks up the storage root of an account through the stored state trie nodes
+fn stored_storage_root(
+    store: &Store,
+    state_root: H256,
+    hashed_address: H256,
+) -> Result<StoredPath, SyncError> {
+    match store
+        .open_state_trie(state_root)
+        .get(&hashed_address.as_bytes().to_vec())
+    {
+        Ok(Some(encoded)) => {
+            let account = AccountState::decode(&encoded).map_err(StoreError::RLPDecode)?;
+            if account.storage_root == *EMPTY_TRIE_HASH {
+                Ok(StoredPath::Absent)
+            } else {
+                Ok(StoredPath::Node(account.storage_root))
+            }
+        }
+        Ok(None) => Ok(StoredPath::Absent),
+        // A node along the path to the account hasn't been healed yet
+        Err(TrieError::InconsistentTree) => Ok(StoredPath::Incomplete),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Reads a node from the trie's underlying db
+fn stored_node(db: &dyn TrieDB, hash: H256) -> Result<Option<Node>, StoreError> {
+    db.get(NodeHash::Hashed(hash).into())?
+        .map(|encoded| Node::decode(&encoded).map_err(StoreError::RLPDecode))
+        .transpose()
+}
+
+/// Hash of a node, under which it is referenced by its parent if its encoding isn't inlined
+fn node_hash(node: &Node) -> H256 {
+    H256::from_slice(&Keccak256::digest(node.encode_raw()))
+}
+
+/// Returns true if every state node has the hash referenced by its parent, and every leaf is at
+/// the full path of a hashed address
+fn valid_state_nodes(batch: &[(Nibbles, H256)], nodes: &[Node]) -> bool {
+    batch.iter().zip(nodes).all(|((path, hash), node)| {
+        node_hash(node) == *hash
+            && match node {
+                Node::Leaf(leaf) => leaf_hashed_path(path, leaf).is_some(),
+                _ => true,
+            }
+    })
+}
+
+/// Returns the hashed key under which the leaf at the given path is stored, if the path is the
+/// 64 nibbles of a hash
+fn leaf_hashed_path(path: &Nibbles, leaf: &LeafNode) -> Option<H256> {
+    let mut full_path = path.clone();
+    full_path.extend(&leaf.partial);
+    let nibbles = full_path.len() - usize::from(full_path.is_leaf());
+    (nibbles == 64).then(|| H256::from_slice(&full_path.to_bytes()))
+}
+
+/// Returns the partial paths to the node's children if they are not already part of the trie state
+fn node_missing_children(
+    node: &Node,
+    parent_path: &Nibbles,
+    contains_node: impl Fn(H256) -> Result<bool, StoreError>,
+) -> Result<Vec<Nibbles>, StoreError> {
+    let mut paths = Vec::new();
+    match node {
+        Node::Branch(node) => {
+            for (index, child) in node.choices.iter().enumerate() {
+                // Inlined children are already contained in the node
+                if let NodeHash::Hashed(hash) = child {
+                    if !contains_node(*hash)? {
+                        let mut path = parent_path.clone();
+                        path.append(index as u8);
+                        paths.push(path);
+                    }
+                }
+            }
+        }
+        Node::Extension(node) => {
+            if let NodeHash::Hashed(hash) = node.child {
+                if !contains_node(hash)? {
+                    let mut path = parent_path.clone();
+                    path.extend(&node.prefix);
+                    paths.push(path);
+                }
+            }
+        }
+        Node::Leaf(_) => {}
+    }
+    Ok(paths)
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -501,7 +1322,131 @@ enum SyncError {
     #[error(transparent)]
     Store(#[from] StoreError),
     #[error(transparent)]
-    SendBytecode(#[from] SendError<Vec<H256>>),
+    Trie(#[from] TrieError),
+    #[error(transparent)]
+    SendHashes(#[from] SendError<Vec<H256>>),
     #[error(transparent)]
     SendStorage(#[from] SendError<Vec<(H256, H256)>>),
+    #[error(transparent)]
+    JoinHandle(#[from] JoinError),
+    #[error(transparent)]
+    Download(#[from] DownloadError),
+    #[error("Missing header for block {0}")]
+    MissingHeader(H256),
+    #[error("Pivot block's state became stale before snap sync could finish, will resume on the next cycle")]
+    StalePivot,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethrex_trie::{BranchNode, ExtensionNode};
+
+    use super::*;
+
+    #[test]
+    fn sync_status_tracks_progress_and_latest_sync_head() {
+        let status = SyncStatus::default();
+        assert!(!status.is_syncing());
+        status.set_sync_head(H256::repeat_byte(1));
+        status.set_sync_head(H256::repeat_byte(2));
+        assert!(status.has_sync_head());
+        assert_eq!(status.take_sync_head(), Some(H256::repeat_byte(2)));
+        assert_eq!(status.take_sync_head(), None);
+        assert!(!status.has_sync_head());
+        status.start(10);
+        status.set_highest_block(20);
+        assert_eq!(
+            status.progress(),
+            Some(SyncProgress {
+                starting_block: 10,
+                highest_block: 20
+            })
+        );
+        status.finish();
+        assert_eq!(status.progress(), None);
+    }
+
+    #[test]
+    fn node_missing_children_skips_stored_and_inlined_children() {
+        let stored = H256::repeat_byte(1);
+        let missing = H256::repeat_byte(2);
+        let mut choices = BranchNode::EMPTY_CHOICES;
+        choices[0] = NodeHash::Hashed(stored);
+        choices[3] = NodeHash::Hashed(missing);
+        choices[5] = NodeHash::Inline(vec![0xc2, 0x20, 0x01]);
+        let node = Node::Branch(BranchNode::new(Box::new(choices)));
+        let parent_path = Nibbles::from_hex(vec![7]);
+        let paths = node_missing_children(&node, &parent_path, |hash| Ok(hash == stored)).unwrap();
+        assert_eq!(paths, vec![Nibbles::from_hex(vec![7, 3])]);
+    }
+
+    #[test]
+    fn node_missing_children_extension_and_leaf() {
+        let missing = H256::repeat_byte(2);
+        let node = Node::Extension(ExtensionNode {
+            prefix: Nibbles::from_hex(vec![1, 2]),
+            child: NodeHash::Hashed(missing),
+        });
+        let paths = node_missing_children(&node, &Nibbles::default(), |_| Ok(false)).unwrap();
+        assert_eq!(paths, vec![Nibbles::from_hex(vec![1, 2])]);
+
+        let leaf = Node::Leaf(LeafNode::new(Nibbles::from_hex(vec![1, 16]), vec![0x01]));
+        assert!(
+            node_missing_children(&leaf, &Nibbles::default(), |_| Ok(false))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn follow_stored_path_resolves_referenced_hashes() {
+        let missing = H256::repeat_byte(2);
+        let extension = Node::Extension(ExtensionNode {
+            prefix: Nibbles::from_hex(vec![4, 5]),
+            child: NodeHash::Hashed(missing),
+        });
+        let mut choices = BranchNode::EMPTY_CHOICES;
+        choices[1] = NodeHash::Hashed(node_hash(&extension));
+        choices[2] = NodeHash::Inline(vec![0xc2, 0x20, 0x01]);
+        let root = Node::Branch(BranchNode::new(Box::new(choices)));
+        let stored: HashMap<H256, Node> = [
+            (node_hash(&root), root.clone()),
+            (node_hash(&extension), extension.clone()),
+        ]
+        .into();
+        let get_node = |hash| Ok(stored.get(&hash).cloned());
+        let follow = |path: Vec<u8>| {
+            follow_stored_path(node_hash(&root), &Nibbles::from_hex(path), get_node).unwrap()
+        };
+        assert_eq!(follow(vec![]), StoredPath::Node(node_hash(&root)));
+        assert_eq!(follow(vec![1]), StoredPath::Node(node_hash(&extension)));
+        assert_eq!(follow(vec![1, 4, 5]), StoredPath::Node(missing));
+        assert_eq!(follow(vec![1, 4, 5, 0]), StoredPath::Incomplete);
+        // Diverging from the extension's prefix, an empty choice and an inlined child
+        assert_eq!(follow(vec![1, 4, 6]), StoredPath::Absent);
+        assert_eq!(follow(vec![3]), StoredPath::Absent);
+        assert_eq!(follow(vec![2]), StoredPath::Absent);
+    }
+
+    #[test]
+    fn leaf_hashed_path_requires_full_path() {
+        let hashed_address = H256::repeat_byte(0xab);
+        let full_path = Nibbles::from_raw(hashed_address.as_bytes(), false);
+        let leaf = |partial: Nibbles| {
+            let mut partial = partial;
+            partial.append(16);
+            LeafNode::new(partial, vec![0x01])
+        };
+        let path = full_path.slice(0, 3);
+        assert_eq!(
+            leaf_hashed_path(&path, &leaf(full_path.offset(3))),
+            Some(hashed_address)
+        );
+        assert_eq!(leaf_hashed_path(&path, &leaf(full_path.slice(3, 63))), None);
+        let mut too_long = full_path.offset(3);
+        too_long.append(1);
+        assert_eq!(leaf_hashed_path(&path, &leaf(too_long)), None);
+    }
 }
@@ -1,11 +1,14 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use bytes::{BufMut, Bytes};
-use ethrex_core::H512;
+use ethrex_core::{types::ForkId, H512};
 use ethrex_rlp::{
     decode::RLPDecode,
     encode::RLPEncode,
     error::RLPDecodeError,
     structs::{self, Decoder, Encoder},
 };
+use k256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
 use std::net::{IpAddr, SocketAddr};
 
 const MAX_NODE_RECORD_ENCODED_SIZE: usize = 300;
@@ -91,7 +94,7 @@ impl Node {
 }
 
 /// Reference: [ENR records](https://github.com/ethereum/devp2p/blob/master/enr.md)
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct NodeRecord {
     pub signature: H512,
     pub seq: u64,
@@ -99,6 +102,99 @@ pub struct NodeRecord {
     pub pairs: Vec<(Bytes, Bytes)>,
 }
 
+impl NodeRecord {
+    /// Creates a record for the given node signed with the "v4" identity scheme
+    /// The fork id is advertised in the "eth" entry so that peers can filter out nodes from other networks
+    pub fn new(node: &Node, seq: u64, signer: &SigningKey, fork_id: Option<ForkId>) -> NodeRecord {
+        let id = String::from("v4");
+        // Keys must be sorted and unique
+        let mut pairs: Vec<(Bytes, Bytes)> = vec![];
+        if let Some(fork_id) = fork_id {
+            pairs.push(("eth".into(), vec![fork_id].encode_to_vec().into()));
+        }
+        pairs.push(("id".into(), id.encode_to_vec().into()));
+        // Don't advertise an unspecified address, peers will use the one we send our messages from
+        if !node.ip.is_unspecified() {
+            let ip_key = if node.ip.is_ipv4() { "ip" } else { "ip6" };
+            pairs.push((ip_key.into(), node.ip.encode_to_vec().into()));
+        }
+        let public_key = signer.verifying_key().to_encoded_point(true);
+        pairs.push((
+            "secp256k1".into(),
+            public_key.as_bytes().encode_to_vec().into(),
+        ));
+        if node.tcp_port != 0 {
+            pairs.push(("tcp".into(), node.tcp_port.encode_to_vec().into()));
+        }
+        pairs.push(("udp".into(), node.udp_port.encode_to_vec().into()));
+
+        let mut record = NodeRecord {
+            signature: H512::zero(),
+            seq,
+            id,
+            pairs,
+        };
+        let digest = Keccak256::digest(record.signed_content());
+        let (signature, _recovery_id) = signer
+            .sign_prehash_recoverable(&digest)
+            .expect("failed to sign");
+        record.signature = H512::from_slice(&signature.to_bytes());
+        record
+    }
+
+    /// Returns true if the record uses the "v4" identity scheme, its signature is valid for its
+    /// content and it was signed by the node with the given id
+    pub fn verify(&self, node_id: H512) -> bool {
+        if self.id != "v4" {
+            return false;
+        }
+        let Some((_key, public_key)) = self
+            .pairs
+            .iter()
+            .find(|(k, _v)| k.eq("secp256k1".as_bytes()))
+        else {
+            return false;
+        };
+        let Some(verifying_key) = Bytes::decode(public_key)
+            .ok()
+            .and_then(|public_key| VerifyingKey::from_sec1_bytes(&public_key).ok())
+        else {
+            return false;
+        };
+        // The node id is the uncompressed public key without its 0x04 prefix
+        if verifying_key.to_encoded_point(false).as_bytes()[1..] != node_id.0 {
+            return false;
+        }
+        let Ok(signature) = Signature::from_slice(self.signature.as_bytes()) else {
+            return false;
+        };
+        let digest = Keccak256::digest(self.signed_content());
+        verifying_key.verify_prehash(&digest, &signature).is_ok()
+    }
+
+    /// Content covered by the signature: the rlp list [seq, k, v, ...]
+    fn signed_content(&self) -> Vec<u8> {
+        let mut content = vec![];
+        Encoder::new(&mut content)
+            .encode_field(&self.seq)
+            .encode_key_value_list::<Bytes>(&self.pairs)
+            .finish();
+        content
+    }
+
+    /// Returns the fork id advertised in the "eth" entry, if any
+    pub fn fork_id(&self) -> Option<ForkId> {
+        let (_key, eth) = self.pairs.iter().find(|(k, _v)| k.eq("eth".as_bytes()))?;
+        let (fork_ids, _rest) = Vec::<ForkId>::decode_unfinished(eth).ok()?;
+        fork_ids.into_iter().next()
+    }
+
+    /// Returns the textual representation of the record: "enr:" followed by its base64 encoded rlp
+    pub fn enr_url(&self) -> String {
+        format!("enr:{}", URL_SAFE_NO_PAD.encode(self.encode_to_vec()))
+    }
+}
+
 impl RLPDecode for NodeRecord {
     fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
         if rlp.len() > MAX_NODE_RECORD_ENCODED_SIZE {
@@ -166,3 +262,82 @@ impl RLPEncode for Node {
             .finish();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_core::H32;
+    use rand::rngs::OsRng;
+    use std::net::Ipv4Addr;
+
+    fn node_id(signer: &SigningKey) -> H512 {
+        H512::from_slice(&signer.verifying_key().to_encoded_point(false).as_bytes()[1..])
+    }
+
+    #[test]
+    fn node_record_round_trip() {
+        let signer = SigningKey::random(&mut OsRng);
+        let node = Node {
+            ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            udp_port: 30303,
+            tcp_port: 30304,
+            node_id: H512::zero(),
+        };
+        let fork_id = ForkId::decode(&(H32::repeat_byte(1), 7_u64).encode_to_vec()).unwrap();
+        let record = NodeRecord::new(&node, 3, &signer, Some(fork_id.clone()));
+
+        let decoded = NodeRecord::decode(&record.encode_to_vec()).unwrap();
+        assert_eq!(decoded, record);
+        assert_eq!(decoded.id, "v4");
+        assert_eq!(decoded.fork_id(), Some(fork_id));
+        assert!(decoded.enr_url().starts_with("enr:"));
+
+        // The signature must be valid for the record's content
+        assert!(decoded.verify(node_id(&signer)));
+    }
+
+    #[test]
+    fn node_record_verification() {
+        let signer = SigningKey::random(&mut OsRng);
+        let node = Node {
+            ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            udp_port: 30303,
+            tcp_port: 30304,
+            node_id: H512::zero(),
+        };
+        let record = NodeRecord::new(&node, 1, &signer, None);
+        assert!(record.verify(node_id(&signer)));
+
+        // Signed by another node
+        let other_signer = SigningKey::random(&mut OsRng);
+        assert!(!record.verify(node_id(&other_signer)));
+
+        // Content changed after signing
+        let mut tampered = record.clone();
+        tampered.seq += 1;
+        assert!(!tampered.verify(node_id(&signer)));
+        let mut tampered = record.clone();
+        let (_key, tcp) = tampered
+            .pairs
+            .iter_mut()
+            .find(|(k, _v)| k.eq("tcp".as_bytes()))
+            .unwrap();
+        *tcp = 1_u16.encode_to_vec().into();
+        assert!(!tampered.verify(node_id(&signer)));
+
+        // Public key of another node, which doesn't match the signature
+        let mut tampered = record;
+        let (_key, public_key) = tampered
+            .pairs
+            .iter_mut()
+            .find(|(k, _v)| k.eq("secp256k1".as_bytes()))
+            .unwrap();
+        *public_key = other_signer
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .encode_to_vec()
+            .into();
+        assert!(!tampered.verify(node_id(&other_signer)));
+    }
+}
@@ -2,10 +2,13 @@ use ethrex_core::types::ChainConfig;
 use ethrex_net::types::Node;
 use ethrex_storage::Store;
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 
-use crate::utils::RpcErr;
+use crate::{
+    utils::{RpcErr, RpcRequest},
+    RpcApiContext, RpcHandler,
+};
 
 #[derive(Serialize, Debug)]
 struct NodeInfo {
@@ -51,3 +54,38 @@ pub fn node_info(storage: Store, local_node: Node) -> Result<Value, RpcErr> {
     };
     serde_json::to_value(node_info).map_err(|error| RpcErr::Internal(error.to_string()))
 }
+
+/// Copies the node's database into a new directory of the node's filesystem, while the node keeps
+/// running
+pub struct BackupDatabaseRequest {
+    pub path: String,
+}
+
+impl From<BackupDatabaseRequest> for RpcRequest {
+    fn from(val: BackupDatabaseRequest) -> Self {
+        RpcRequest {
+            method: "admin_backupDatabase".to_string(),
+            params: Some(vec![json!(val.path)]),
+            ..Default::default()
+        }
+    }
+}
+
+impl RpcHandler for BackupDatabaseRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams("Expected 1 param".to_owned()));
+        };
+        Ok(BackupDatabaseRequest {
+            path: serde_json::from_value(params[0].clone())?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        context.storage.backup(&self.path)?;
+        Ok(Value::Null)
+    }
+}
@@ -1,13 +1,15 @@
 use ethrex_blockchain::{
+    add_block,
     error::{ChainError, InvalidForkChoice},
     fork_choice::apply_fork_choice,
     latest_canonical_block_hash,
     payload::{create_payload, BuildPayloadArgs},
 };
-use ethrex_core::types::BlockHeader;
+use ethrex_core::{types::BlockHeader, H256};
 use serde_json::Value;
 use tracing::{info, warn};
 
+use super::trigger_sync;
 use crate::{
     types::{
         fork_choice::{ForkChoiceResponse, ForkChoiceState, PayloadAttributesV3},
@@ -154,6 +156,8 @@ fn handle_forkchoice(
         fork_choice_state.finalized_block_hash
     );
 
+    execute_pending_head(fork_choice_state.head_block_hash, &context)?;
+
     match apply_fork_choice(
         &context.storage,
         fork_choice_state.head_block_hash,
@@ -174,24 +178,7 @@ fn handle_forkchoice(
                     ))
                 }
                 InvalidForkChoice::Syncing => {
-                    // Start sync
-                    let current_number = context.storage.get_latest_block_number()?;
-                    let Some(current_head) =
-                        context.storage.get_canonical_block_hash(current_number)?
-                    else {
-                        return Err(RpcErr::Internal(
-                            "Missing latest canonical block".to_owned(),
-                        ));
-                    };
-                    let sync_head = fork_choice_state.head_block_hash;
-                    tokio::spawn(async move {
-                        // If we can't get hold of the syncer, then it means that there is an active sync in process
-                        if let Ok(mut syncer) = context.syncer.try_lock() {
-                            syncer
-                                .start_sync(current_head, sync_head, context.storage.clone())
-                                .await
-                        }
-                    });
+                    trigger_sync(&context, fork_choice_state.head_block_hash);
                     ForkChoiceResponse::from(PayloadStatus::syncing())
                 }
                 reason => {
@@ -204,6 +191,36 @@ fn handle_forkchoice(
     }
 }
 
+/// Executes the head block and the pending blocks it builds on, if they are payloads that were
+/// stored as pending (because they were received while syncing or before their parent) and the
+/// oldest of them links with a block that is already present. They are executed in order, up to
+/// the first one that fails
+fn execute_pending_head(head_hash: H256, context: &RpcApiContext) -> Result<(), RpcErr> {
+    let storage = &context.storage;
+    if context.sync_status.is_syncing() {
+        return Ok(());
+    }
+    // Walk back from the head through the pending blocks until the parent of one is present
+    let mut pending_blocks = Vec::new();
+    let mut block_hash = head_hash;
+    while storage.get_block_header_by_hash(block_hash)?.is_none() {
+        let Some(block) = storage.get_pending_block(block_hash)? else {
+            return Ok(());
+        };
+        block_hash = block.header.parent_hash;
+        pending_blocks.push(block);
+    }
+    for block in pending_blocks.into_iter().rev() {
+        let block_hash = block.hash();
+        info!("Executing pending block with hash: {block_hash:#x}");
+        if let Err(error) = add_block(&block, storage, context.evm_config) {
+            warn!("Failed to execute pending block {block_hash:#x}: {error}");
+            break;
+        }
+    }
+    Ok(())
+}
+
 fn validate_v1(attributes: &PayloadAttributesV3, head_block: BlockHeader) -> Result<(), RpcErr> {
     validate_timestamp(attributes, head_block)
 }
@@ -291,3 +308,71 @@ fn build_payload(
 
     Ok(payload_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ethrex_blockchain::payload::build_payload;
+    use ethrex_core::{types::Block, H160};
+    use ethrex_net::sync::SyncManager;
+    use ethrex_storage::{EngineType, Store};
+    use tokio::sync::Mutex as TokioMutex;
+
+    use super::*;
+    use crate::utils::test_utils::{example_p2p_node, TEST_GENESIS};
+
+    #[test]
+    fn pending_blocks_up_to_the_head_are_executed() {
+        // The blocks are built by another node and received while syncing
+        let builder = test_store();
+        let genesis_header = builder.get_block_header(0).unwrap().unwrap();
+        let block_1 = new_block(&builder, &genesis_header);
+        add_block(&block_1, &builder, Default::default()).unwrap();
+        let block_2 = new_block(&builder, &block_1.header);
+
+        let storage = test_store();
+        storage.add_pending_block(block_1.clone()).unwrap();
+        storage.add_pending_block(block_2.clone()).unwrap();
+        let context = RpcApiContext {
+            storage: storage.clone(),
+            jwt_secret: Default::default(),
+            local_p2p_node: example_p2p_node(),
+            active_filters: Default::default(),
+            syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
+        };
+        execute_pending_head(block_2.hash(), &context).unwrap();
+
+        for block in [block_1, block_2] {
+            assert_eq!(
+                storage.get_block_header_by_hash(block.hash()).unwrap(),
+                Some(block.header)
+            );
+        }
+    }
+
+    fn new_block(store: &Store, parent: &BlockHeader) -> Block {
+        let args = BuildPayloadArgs {
+            parent: parent.compute_block_hash(),
+            timestamp: parent.timestamp + 12,
+            fee_recipient: H160::random(),
+            random: H256::random(),
+            withdrawals: Some(Vec::new()),
+            beacon_root: Some(H256::random()),
+            version: 3,
+        };
+        let mut block = create_payload(&args, store).unwrap();
+        build_payload(&mut block, store).unwrap();
+        block
+    }
+
+    fn test_store() -> Store {
+        let store = Store::new("", EngineType::InMemory).unwrap();
+        store
+            .add_initial_state(serde_json::from_str(TEST_GENESIS).unwrap())
+            .unwrap();
+        store
+    }
+}
@@ -3,6 +3,7 @@ pub mod fork_choice;
 pub mod payload;
 
 use crate::{utils::RpcRequest, RpcApiContext, RpcErr, RpcHandler};
+use ethrex_core::H256;
 use serde_json::{json, Value};
 
 pub type ExchangeCapabilitiesRequest = Vec<String>;
@@ -34,3 +35,22 @@ impl RpcHandler for ExchangeCapabilitiesRequest {
         Ok(json!(*self))
     }
 }
+
+/// Sets the given block as the new sync head and starts syncing to it in the background
+/// If there is already an active sync process, it will sync to the new head once its current cycle finishes
+fn trigger_sync(context: &RpcApiContext, sync_head: H256) {
+    context.sync_status.set_sync_head(sync_head);
+    let context = context.clone();
+    tokio::spawn(async move {
+        // If we can't get hold of the syncer, then it means that there is an active sync in process
+        // The syncer may have already found no new head before this one was set, so whoever holds
+        // it checks again after releasing it
+        while let Ok(mut syncer) = context.syncer.try_lock() {
+            syncer.sync_to_latest_head(context.storage.clone()).await;
+            drop(syncer);
+            if !context.sync_status.has_sync_head() {
+                break;
+            }
+        }
+    });
+}
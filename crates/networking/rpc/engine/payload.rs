@@ -6,6 +6,7 @@ use ethrex_core::{H256, U256};
 use serde_json::Value;
 use tracing::{error, info, warn};
 
+use super::trigger_sync;
 use crate::types::payload::{ExecutionPayload, ExecutionPayloadResponse, PayloadStatus};
 use crate::utils::RpcRequest;
 use crate::{RpcApiContext, RpcErr, RpcHandler};
@@ -242,10 +243,20 @@ fn execute_payload(block: &Block, context: &RpcApiContext) -> Result<PayloadStat
         return Ok(PayloadStatus::valid_with_hash(block_hash));
     }
 
+    // Don't execute payloads while syncing, store them so they can be executed once the sync is complete
+    if context.sync_status.is_syncing() {
+        storage.add_pending_block(block.clone())?;
+        return Ok(PayloadStatus::syncing());
+    }
+
     // Execute and store the block
     info!("Executing payload with block hash: {block_hash:#x}");
-    match add_block(block, storage) {
-        Err(ChainError::ParentNotFound) => Ok(PayloadStatus::syncing()),
+    match add_block(block, storage, context.evm_config) {
+        Err(ChainError::ParentNotFound) => {
+            // The block was stored as pending, sync up to its parent so it can be executed later on
+            trigger_sync(context, block.header.parent_hash);
+            Ok(PayloadStatus::syncing())
+        }
         // Under the current implementation this is not possible: we always calculate the state
         // transition of any new payload as long as the parent is present. If we received the
         // parent payload but it was stashed, then new payload would stash this one too, with a
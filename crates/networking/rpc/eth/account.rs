@@ -1,9 +1,14 @@
+use std::{collections::BTreeMap, iter};
+
 use ethrex_blockchain::mempool;
+use ethrex_storage::{error::StoreError, hash_address, hash_key, StorageSlot};
+use ethrex_vm::evm_state;
 use serde_json::Value;
 use tracing::info;
 
 use crate::types::account_proof::{AccountProof, StorageProof};
 use crate::types::block_identifier::{BlockIdentifierOrHash, BlockTag};
+use crate::types::state_dump::{AccountRange, DumpAccount, StorageEntry, StorageRange};
 use crate::RpcApiContext;
 use crate::{utils::RpcErr, RpcHandler};
 use ethrex_core::{Address, BigEndianHash, H256, U256};
@@ -35,6 +40,26 @@ pub struct GetProofRequest {
     pub block: BlockIdentifierOrHash,
 }
 
+/// Maximum number of accounts or storage slots returned by a range request, as in geth
+const MAX_RANGE_RESULTS: usize = 256;
+
+pub struct AccountRangeRequest {
+    pub block: BlockIdentifierOrHash,
+    pub start: H256,
+    pub max_results: usize,
+    pub no_code: bool,
+    pub no_storage: bool,
+    pub incompletes: bool,
+}
+
+pub struct StorageRangeAtRequest {
+    pub block_hash: H256,
+    pub tx_index: usize,
+    pub address: Address,
+    pub key_start: H256,
+    pub max_results: usize,
+}
+
 impl RpcHandler for GetBalanceRequest {
     fn parse(params: &Option<Vec<Value>>) -> Result<GetBalanceRequest, RpcErr> {
         let params = params
@@ -247,3 +272,244 @@ impl RpcHandler for GetProofRequest {
         serde_json::to_value(account_proof).map_err(|error| RpcErr::Internal(error.to_string()))
     }
 }
+
+impl RpcHandler for AccountRangeRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 6 {
+            return Err(RpcErr::BadParams("Expected 6 params".to_owned()));
+        };
+        Ok(AccountRangeRequest {
+            block: BlockIdentifierOrHash::parse(params[0].clone(), 0)?,
+            start: parse_range_start(&params[1])?,
+            max_results: serde_json::from_value(params[2].clone())?,
+            no_code: serde_json::from_value(params[3].clone())?,
+            no_storage: serde_json::from_value(params[4].clone())?,
+            incompletes: serde_json::from_value(params[5].clone())?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let storage = &context.storage;
+        info!(
+            "Requested account range at block {} starting from {:#x}",
+            self.block, self.start
+        );
+        let Some(block_number) = self.block.resolve_block_number(storage)? else {
+            return Err(RpcErr::BadParams("Block not found".to_owned()));
+        };
+        let Some(header) = storage.get_block_header(block_number)? else {
+            return Err(RpcErr::BadParams("Block not found".to_owned()));
+        };
+        let max_results = match self.max_results {
+            0 => MAX_RANGE_RESULTS,
+            max_results => max_results.min(MAX_RANGE_RESULTS),
+        };
+        let mut range = AccountRange {
+            root: header.state_root,
+            accounts: BTreeMap::new(),
+            next: None,
+        };
+        for entry in storage.iter_accounts_from(header.state_root, self.start)? {
+            let (hashed_address, account) = entry?;
+            if range.accounts.len() == max_results {
+                range.next = Some(hashed_address);
+                break;
+            }
+            let account = DumpAccount::new(
+                storage,
+                header.state_root,
+                hashed_address,
+                account,
+                !self.no_code,
+                !self.no_storage,
+            )?;
+            // Accounts without a known address can't be imported elsewhere, so they are only
+            // returned when asked for
+            if account.address.is_some() || self.incompletes {
+                range.accounts.insert(account.range_key(), account);
+            }
+        }
+        serde_json::to_value(range).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for StorageRangeAtRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 5 {
+            return Err(RpcErr::BadParams("Expected 5 params".to_owned()));
+        };
+        Ok(StorageRangeAtRequest {
+            block_hash: serde_json::from_value(params[0].clone())?,
+            tx_index: serde_json::from_value(params[1].clone())?,
+            address: serde_json::from_value(params[2].clone())?,
+            key_start: parse_range_start(&params[3])?,
+            max_results: serde_json::from_value(params[4].clone())?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let storage = &context.storage;
+        info!(
+            "Requested storage range of account {} at block {:#x} before transaction {} starting from {:#x}",
+            self.address, self.block_hash, self.tx_index, self.key_start
+        );
+        let Some(block) = storage.get_block_by_hash(self.block_hash)? else {
+            return Err(RpcErr::BadParams("Block not found".to_owned()));
+        };
+        let max_results = match self.max_results {
+            0 => MAX_RANGE_RESULTS,
+            max_results => max_results.min(MAX_RANGE_RESULTS),
+        };
+        // The state is only stored at the block boundaries, so the state before a transaction is
+        // the parent's state with the writes of the block's previous transactions on top
+        let mut cleared = false;
+        let mut written = BTreeMap::new();
+        let state_root = if self.tx_index < block.body.transactions.len() {
+            let Some(parent) = storage.get_block_header_by_hash(block.header.parent_hash)? else {
+                return Err(RpcErr::BadParams("Parent block not found".to_owned()));
+            };
+            let account_updates = ethrex_vm::execute_block_prefix(
+                &block,
+                self.tx_index,
+                &mut evm_state(storage.clone(), block.header.parent_hash),
+            )?;
+            for update in account_updates {
+                if update.address != self.address {
+                    continue;
+                }
+                if update.removed {
+                    cleared = true;
+                    written.clear();
+                }
+                for (key, value) in update.added_storage {
+                    written.insert(H256::from_slice(&hash_key(&key)), value);
+                }
+            }
+            parent.state_root
+        } else {
+            block.header.state_root
+        };
+        let hashed_address = H256::from_slice(&hash_address(&self.address));
+        let stored = if cleared {
+            None
+        } else {
+            storage.iter_storage_from(state_root, hashed_address, self.key_start)?
+        };
+        let mut range = StorageRange {
+            storage: BTreeMap::new(),
+            next_key: None,
+        };
+        for entry in merge_storage(stored.into_iter().flatten(), &written, self.key_start) {
+            let (hashed_key, value) = entry?;
+            if range.storage.len() == max_results {
+                range.next_key = Some(hashed_key);
+                break;
+            }
+            let key = storage
+                .get_preimage(hashed_key)?
+                .map(|preimage| H256::from_slice(&preimage));
+            let entry = StorageEntry {
+                key,
+                value: H256::from_uint(&value),
+            };
+            range.storage.insert(hashed_key, entry);
+        }
+        serde_json::to_value(range).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+/// Merges the stored slots of an account with the slots written on top of them, in hashed key
+/// order from `start`. Written values replace the stored ones and zero values are skipped
+fn merge_storage<'a>(
+    stored: impl Iterator<Item = Result<StorageSlot, StoreError>> + 'a,
+    written: &'a BTreeMap<H256, U256>,
+    start: H256,
+) -> impl Iterator<Item = Result<StorageSlot, StoreError>> + 'a {
+    let mut stored = stored.peekable();
+    let mut written = written.range(start..).peekable();
+    iter::from_fn(move || loop {
+        let next_stored = match stored.peek() {
+            Some(Ok((hashed_key, _))) => Some(*hashed_key),
+            Some(Err(_)) => return stored.next(),
+            None => None,
+        };
+        let next_written = written.peek().map(|(hashed_key, _)| **hashed_key);
+        let (hashed_key, value) = match (next_stored, next_written) {
+            (None, None) => return None,
+            (Some(stored_key), Some(written_key)) if written_key <= stored_key => {
+                if written_key == stored_key {
+                    stored.next();
+                }
+                written
+                    .next()
+                    .map(|(hashed_key, value)| (*hashed_key, *value))?
+            }
+            (Some(_), _) => match stored.next()? {
+                Ok(slot) => slot,
+                Err(error) => return Some(Err(error)),
+            },
+            (None, Some(_)) => written
+                .next()
+                .map(|(hashed_key, value)| (*hashed_key, *value))?,
+        };
+        if !value.is_zero() {
+            return Some(Ok((hashed_key, value)));
+        }
+    })
+}
+
+/// Parses the hex encoded start of a range, the first path of the trie with that prefix
+fn parse_range_start(value: &Value) -> Result<H256, RpcErr> {
+    let start: String = serde_json::from_value(value.clone())?;
+    let start = hex::decode(start.trim_start_matches("0x"))
+        .map_err(|error| RpcErr::BadParams(error.to_string()))?;
+    if start.len() > H256::len_bytes() {
+        return Err(RpcErr::BadParams(
+            "Range start longer than 32 bytes".to_owned(),
+        ));
+    }
+    let mut padded = H256::zero();
+    padded.0[..start.len()].copy_from_slice(&start);
+    Ok(padded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_storage_overlays_written_slots() {
+        let slot = |byte: u8, value: u64| (H256::repeat_byte(byte), U256::from(value));
+        let stored = vec![slot(1, 1), slot(3, 3), slot(5, 5), slot(7, 7)];
+        // Slot 3 is updated, slot 5 is cleared, slots 4 and 6 are added and slot 2 is cleared
+        // without being stored
+        let written = BTreeMap::from([slot(2, 0), slot(3, 30), slot(4, 4), slot(5, 0), slot(6, 6)]);
+        let start = H256::repeat_byte(2);
+        let stored_from_start = stored
+            .into_iter()
+            .filter(|(hashed_key, _)| *hashed_key >= start)
+            .map(Ok);
+        let merged: Vec<_> = merge_storage(stored_from_start, &written, start)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            merged,
+            vec![slot(3, 30), slot(4, 4), slot(6, 6), slot(7, 7)]
+        );
+
+        // A read error of the stored slots is returned instead of ending the range
+        let failing = vec![
+            Ok(slot(1, 1)),
+            Err(StoreError::Custom("corrupted".to_owned())),
+        ];
+        let merged: Vec<_> = merge_storage(failing.into_iter(), &written, H256::zero()).collect();
+        assert!(matches!(merged[0], Ok(entry) if entry == slot(1, 1)));
+        assert!(merged[1].is_err());
+    }
+}
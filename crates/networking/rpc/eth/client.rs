@@ -1,3 +1,5 @@
+use ethrex_core::serde_utils;
+use serde::Serialize;
 use serde_json::Value;
 use tracing::info;
 
@@ -26,7 +28,28 @@ impl RpcHandler for Syncing {
         Ok(Self {})
     }
 
-    fn handle(&self, _context: RpcApiContext) -> Result<Value, RpcErr> {
-        Ok(Value::Bool(false))
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let Some(progress) = context.sync_status.progress() else {
+            return Ok(Value::Bool(false));
+        };
+        let current_block = context.storage.get_latest_block_number()?;
+        serde_json::to_value(SyncingStatus {
+            starting_block: progress.starting_block,
+            current_block,
+            // The sync head's number may not be known yet
+            highest_block: progress.highest_block.max(current_block),
+        })
+        .map_err(|error| RpcErr::Internal(error.to_string()))
     }
 }
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncingStatus {
+    #[serde(with = "serde_utils::u64::hex_str")]
+    starting_block: u64,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    current_block: u64,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    highest_block: u64,
+}
@@ -439,6 +439,8 @@ mod tests {
             local_p2p_node: example_p2p_node(),
             active_filters: filters_pointer.clone(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
         };
         let request: RpcRequest = serde_json::from_value(json_req).expect("Test json is incorrect");
         let genesis_config: Genesis =
@@ -492,6 +494,8 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: active_filters.clone(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
         };
 
         map_http_requests(&uninstall_filter_req, context).unwrap();
@@ -512,6 +516,8 @@ mod tests {
             active_filters: active_filters.clone(),
             jwt_secret: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
         };
         let uninstall_filter_req: RpcRequest = serde_json::from_value(json!(
         {
@@ -384,6 +384,8 @@ mod tests {
             },
             active_filters: Default::default(),
             syncer: Arc::new(Mutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
         }
     }
 }
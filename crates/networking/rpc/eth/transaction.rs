@@ -16,7 +16,7 @@ use ethrex_blockchain::mempool;
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_storage::Store;
 
-use ethrex_vm::{evm_state, ExecutionResult, SpecId};
+use ethrex_vm::{evm_state, profile_call_levm, ExecutionResult, SpecId};
 use serde::Serialize;
 
 use serde_json::Value;
@@ -31,6 +31,12 @@ pub struct CallRequest {
     block: Option<BlockIdentifier>,
 }
 
+/// Simulates a call with LEVM and reports where its gas was spent
+pub struct ProfileCallRequest {
+    transaction: GenericTransaction,
+    block: Option<BlockIdentifier>,
+}
+
 pub struct GetTransactionByBlockNumberAndIndexRequest {
     pub block: BlockIdentifier,
     pub transaction_index: usize,
@@ -111,6 +117,25 @@ impl RpcHandler for CallRequest {
     }
 }
 
+impl RpcHandler for ProfileCallRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<ProfileCallRequest, RpcErr> {
+        let CallRequest { transaction, block } = CallRequest::parse(params)?;
+        Ok(ProfileCallRequest { transaction, block })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let block = self.block.clone().unwrap_or_default();
+        info!("Requested call profile on block: {}", block);
+        let header = match block.resolve_block_header(&context.storage)? {
+            Some(header) => header,
+            // Block not found
+            _ => return Ok(Value::Null),
+        };
+        let profile = profile_call_levm(&self.transaction, &header, context.storage)?;
+        serde_json::to_value(profile).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
 impl RpcHandler for GetTransactionByBlockNumberAndIndexRequest {
     fn parse(
         params: &Option<Vec<Value>>,
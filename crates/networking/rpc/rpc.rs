@@ -1,4 +1,5 @@
 use crate::authentication::authenticate;
+use admin::BackupDatabaseRequest;
 use axum::{routing::post, Json, Router};
 use axum_extra::{
     headers::{authorization::Bearer, Authorization},
@@ -16,8 +17,8 @@ use engine::{
 };
 use eth::{
     account::{
-        GetBalanceRequest, GetCodeRequest, GetProofRequest, GetStorageAtRequest,
-        GetTransactionCountRequest,
+        AccountRangeRequest, GetBalanceRequest, GetCodeRequest, GetProofRequest,
+        GetStorageAtRequest, GetTransactionCountRequest, StorageRangeAtRequest,
     },
     block::{
         BlockNumberRequest, GetBlobBaseFee, GetBlockByHashRequest, GetBlockByNumberRequest,
@@ -32,10 +33,10 @@ use eth::{
     transaction::{
         CallRequest, CreateAccessListRequest, EstimateGasRequest, GetRawTransaction,
         GetTransactionByBlockHashAndIndexRequest, GetTransactionByBlockNumberAndIndexRequest,
-        GetTransactionByHashRequest, GetTransactionReceiptRequest,
+        GetTransactionByHashRequest, GetTransactionReceiptRequest, ProfileCallRequest,
     },
 };
-use ethrex_net::sync::SyncManager;
+use ethrex_net::sync::{SyncManager, SyncStatus};
 use serde_json::Value;
 use std::{
     collections::HashMap,
@@ -51,7 +52,7 @@ use utils::{
     RpcErr, RpcErrorMetadata, RpcErrorResponse, RpcNamespace, RpcRequest, RpcRequestId,
     RpcSuccessResponse,
 };
-mod admin;
+pub mod admin;
 mod authentication;
 pub mod engine;
 mod eth;
@@ -63,6 +64,7 @@ mod web3;
 use axum::extract::State;
 use ethrex_net::types::Node;
 use ethrex_storage::Store;
+use ethrex_vm::EvmConfig;
 
 #[derive(Debug, Clone)]
 pub struct RpcApiContext {
@@ -71,6 +73,8 @@ pub struct RpcApiContext {
     local_p2p_node: Node,
     active_filters: ActiveFilters,
     syncer: Arc<TokioMutex<SyncManager>>,
+    sync_status: SyncStatus,
+    evm_config: EvmConfig,
 }
 
 trait RpcHandler: Sized {
@@ -99,6 +103,7 @@ pub async fn start_api(
     jwt_secret: Bytes,
     local_p2p_node: Node,
     syncer: SyncManager,
+    evm_config: EvmConfig,
 ) {
     // TODO: Refactor how filters are handled,
     // filters are used by the filters endpoints (eth_newFilter, eth_getFilterChanges, ...etc)
@@ -108,7 +113,9 @@ pub async fn start_api(
         jwt_secret,
         local_p2p_node,
         active_filters: active_filters.clone(),
+        sync_status: syncer.status(),
         syncer: Arc::new(TokioMutex::new(syncer)),
+        evm_config,
     };
 
     // Periodically clean up the active filters for the filters endpoints.
@@ -190,11 +197,12 @@ pub fn map_http_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Val
     }
 }
 
-/// Handle requests from consensus client
+/// Handle requests from consensus client, and admin requests which need authentication
 pub fn map_authrpc_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
     match req.namespace() {
         Ok(RpcNamespace::Engine) => map_engine_requests(req, context),
         Ok(RpcNamespace::Eth) => map_eth_requests(req, context),
+        Ok(RpcNamespace::Admin) => map_authenticated_admin_requests(req, context),
         _ => Err(RpcErr::MethodNotFound(req.method.clone())),
     }
 }
@@ -251,6 +259,9 @@ pub fn map_debug_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Va
         "debug_getRawBlock" => GetRawBlockRequest::call(req, context),
         "debug_getRawTransaction" => GetRawTransaction::call(req, context),
         "debug_getRawReceipts" => GetRawReceipts::call(req, context),
+        "debug_profileCall" => ProfileCallRequest::call(req, context),
+        "debug_accountRange" => AccountRangeRequest::call(req, context),
+        "debug_storageRangeAt" => StorageRangeAtRequest::call(req, context),
         unknown_debug_method => Err(RpcErr::MethodNotFound(unknown_debug_method.to_owned())),
     }
 }
@@ -281,6 +292,17 @@ pub fn map_admin_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Va
     }
 }
 
+/// Admin requests that write to the node's filesystem, only served by the authenticated endpoint
+pub fn map_authenticated_admin_requests(
+    req: &RpcRequest,
+    context: RpcApiContext,
+) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "admin_backupDatabase" => BackupDatabaseRequest::call(req, context),
+        _ => map_admin_requests(req, context),
+    }
+}
+
 pub fn map_web3_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
     match req.method.as_str() {
         "web3_clientVersion" => web3::client_version(req, context.storage),
@@ -348,15 +370,44 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
         };
         let result = map_http_requests(&request, context);
         let rpc_response = rpc_response(request.id, result);
         let expected_response = to_rpc_response_success_value(
-            r#"{"jsonrpc":"2.0","id":1,"result":{"enode":"enode://d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666@127.0.0.1:30303","id":"d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666","ip":"127.0.0.1","name":"ethrex/0.1.0/rust1.81","ports":{"discovery":30303,"listener":30303},"protocols":{"eth":{"chainId":3151908,"homesteadBlock":0,"daoForkBlock":null,"daoForkSupport":false,"eip150Block":0,"eip155Block":0,"eip158Block":0,"byzantiumBlock":0,"constantinopleBlock":0,"petersburgBlock":0,"istanbulBlock":0,"muirGlacierBlock":null,"berlinBlock":0,"londonBlock":0,"arrowGlacierBlock":null,"grayGlacierBlock":null,"mergeNetsplitBlock":0,"shanghaiTime":0,"cancunTime":0,"pragueTime":1718232101,"verkleTime":null,"terminalTotalDifficulty":0,"terminalTotalDifficultyPassed":true}}}}"#,
+            r#"{"jsonrpc":"2.0","id":1,"result":{"enode":"enode://d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666@127.0.0.1:30303","id":"d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666","ip":"127.0.0.1","name":"ethrex/0.1.0/rust1.81","ports":{"discovery":30303,"listener":30303},"protocols":{"eth":{"chainId":3151908,"homesteadBlock":0,"daoForkBlock":null,"daoForkSupport":false,"eip150Block":0,"eip155Block":0,"eip158Block":0,"byzantiumBlock":0,"constantinopleBlock":0,"petersburgBlock":0,"istanbulBlock":0,"muirGlacierBlock":null,"berlinBlock":0,"londonBlock":0,"arrowGlacierBlock":null,"grayGlacierBlock":null,"mergeNetsplitBlock":0,"shanghaiTime":0,"cancunTime":0,"pragueTime":1718232101,"osakaTime":null,"verkleTime":null,"terminalTotalDifficulty":0,"terminalTotalDifficultyPassed":true}}}}"#,
         );
         assert_eq!(rpc_response.to_string(), expected_response.to_string())
     }
 
+    #[test]
+    fn admin_backup_database_needs_authentication() {
+        let body =
+            r#"{"jsonrpc":"2.0", "method":"admin_backupDatabase", "params":["backup"], "id":1}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let context = RpcApiContext {
+            local_p2p_node: example_p2p_node(),
+            storage,
+            jwt_secret: Default::default(),
+            active_filters: Default::default(),
+            syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
+        };
+        assert!(matches!(
+            map_http_requests(&request, context.clone()),
+            Err(RpcErr::MethodNotFound(_))
+        ));
+        // The in-memory store has no database to back up
+        assert!(matches!(
+            map_authrpc_requests(&request, context),
+            Err(RpcErr::Internal(_))
+        ));
+    }
+
     // Reads genesis file taken from https://github.com/ethereum/execution-apis/blob/main/tests/genesis.json
     fn read_execution_api_genesis_file() -> Genesis {
         let file = File::open("../../../test_data/genesis-execution-api.json")
@@ -386,6 +437,8 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
         };
         let result = map_http_requests(&request, context);
         let response = rpc_response(request.id, result);
@@ -416,6 +469,8 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
         };
         let result = map_http_requests(&request, context);
         let response =
@@ -476,6 +531,8 @@ mod tests {
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            sync_status: Default::default(),
+            evm_config: Default::default(),
         };
         // Process request
         let result = map_http_requests(&request, context);
@@ -4,4 +4,5 @@ pub mod block_identifier;
 pub mod fork_choice;
 pub mod payload;
 pub mod receipt;
+pub mod state_dump;
 pub mod transaction;
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use ethrex_core::{serde_utils, types::AccountState, Address, H256, U256};
+use ethrex_storage::{error::StoreError, Store};
+use serde::Serialize;
+
+/// Account of a state dump, in the format of geth's `debug_accountRange` and `dump`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpAccount {
+    #[serde(serialize_with = "serialize_decimal")]
+    pub balance: U256,
+    pub nonce: u64,
+    pub root: H256,
+    pub code_hash: H256,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_bytes"
+    )]
+    pub code: Option<Bytes>,
+    /// Storage slots by key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<H256, U256>>,
+    /// Storage slots whose key isn't known, by hashed key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hashed_storage: Option<BTreeMap<H256, U256>>,
+    /// Address of the account, if its preimage is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
+    /// Hashed address of the account, its path in the state trie
+    pub key: H256,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountRange {
+    pub root: H256,
+    /// Accounts by address, or by `pre(<hashed address>)` if the address isn't known
+    pub accounts: BTreeMap<String, DumpAccount>,
+    /// Hashed address to start the next page from, None if this is the last one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<H256>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageRange {
+    /// Storage slots by hashed key
+    pub storage: BTreeMap<H256, StorageEntry>,
+    /// Hashed key to start the next page from, None if this is the last one
+    pub next_key: Option<H256>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageEntry {
+    /// Key of the slot, if its preimage is known
+    pub key: Option<H256>,
+    pub value: H256,
+}
+
+impl DumpAccount {
+    /// Builds the dump of the account stored under `hashed_address` in the state trie given by
+    /// the state_root, loading its code and storage if requested
+    pub fn new(
+        storage: &Store,
+        state_root: H256,
+        hashed_address: H256,
+        account: AccountState,
+        with_code: bool,
+        with_storage: bool,
+    ) -> Result<Self, StoreError> {
+        let address = storage
+            .get_preimage(hashed_address)?
+            .map(|preimage| Address::from_slice(&preimage));
+        let code = if with_code {
+            Some(
+                storage
+                    .get_account_code(account.code_hash)?
+                    .unwrap_or_default(),
+            )
+        } else {
+            None
+        };
+        let (mut storage_slots, mut hashed_slots) = (None, None);
+        if with_storage {
+            let (mut slots, mut hashed) = (BTreeMap::new(), BTreeMap::new());
+            for (hashed_key, value) in storage
+                .iter_storage(state_root, hashed_address)?
+                .into_iter()
+                .flatten()
+            {
+                match storage.get_preimage(hashed_key)? {
+                    Some(preimage) => slots.insert(H256::from_slice(&preimage), value),
+                    None => hashed.insert(hashed_key, value),
+                };
+            }
+            storage_slots = Some(slots);
+            hashed_slots = (!hashed.is_empty()).then_some(hashed);
+        }
+        Ok(DumpAccount {
+            balance: account.balance,
+            nonce: account.nonce,
+            root: account.storage_root,
+            code_hash: account.code_hash,
+            code,
+            storage: storage_slots,
+            hashed_storage: hashed_slots,
+            address,
+            key: hashed_address,
+        })
+    }
+
+    /// Key of the account in an `AccountRange`
+    pub fn range_key(&self) -> String {
+        match self.address {
+            Some(address) => format!("{address:#x}"),
+            None => format!("pre({:#x})", self.key),
+        }
+    }
+}
+
+fn serialize_decimal<S>(value: &U256, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&value.to_string())
+}
+
+fn serialize_optional_bytes<S>(value: &Option<Bytes>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match value {
+        Some(bytes) => serde_utils::bytes::serialize(bytes, serializer),
+        None => serializer.serialize_none(),
+    }
+}
@@ -294,6 +294,7 @@ pub mod test_utils {
             jwt_secret,
             local_p2p_node,
             SyncManager::dummy(),
+            Default::default(),
         )
         .await;
     }
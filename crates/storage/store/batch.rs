@@ -0,0 +1,115 @@
+use bytes::Bytes;
+use ethereum_types::{H256, U256};
+use ethrex_core::types::{BlockBody, BlockHash, BlockHeader, BlockNumber, Index, Receipt};
+use ethrex_trie::NodeChanges;
+
+/// A set of writes to be committed to the store as a single database transaction, so that either
+/// all of them are persisted or none is.
+/// The writes are not visible to the store's readers until the batch is committed
+#[derive(Debug, Default)]
+pub struct WriteBatch {
+    pub(crate) ops: Vec<BatchOp>,
+}
+
+/// A single write of a [WriteBatch], applied by each engine to its own tables
+#[derive(Debug)]
+pub(crate) enum BatchOp {
+    BlockHeader(BlockHash, BlockHeader),
+    BlockBody(BlockHash, BlockBody),
+    BlockNumber(BlockHash, BlockNumber),
+    // TODO (#307): Remove TotalDifficulty.
+    BlockTotalDifficulty(BlockHash, U256),
+    // TODO (#307): Remove TotalDifficulty.
+    LatestTotalDifficulty(U256),
+    TransactionLocations(Vec<(H256, BlockNumber, BlockHash, Index)>),
+    Receipts(BlockHash, Vec<Receipt>),
+    AccountCode(H256, Bytes),
+    /// Encoded state trie nodes by node hash
+    StateTrieNodes(NodeChanges),
+    /// Encoded storage trie nodes by node hash, for the storage trie of the hashed address
+    StorageTrieNodes(H256, NodeChanges),
+    /// Addresses and storage keys by their hash, the path of their trie leaves
+    Preimages(Vec<(H256, Bytes)>),
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Appends the writes of another batch, which will be applied after the ones of this batch
+    pub fn extend(&mut self, other: WriteBatch) {
+        self.ops.extend(other.ops)
+    }
+
+    pub fn add_block_header(&mut self, block_hash: BlockHash, block_header: BlockHeader) {
+        self.ops
+            .push(BatchOp::BlockHeader(block_hash, block_header))
+    }
+
+    pub fn add_block_body(&mut self, block_hash: BlockHash, block_body: BlockBody) {
+        self.ops.push(BatchOp::BlockBody(block_hash, block_body))
+    }
+
+    pub fn add_block_number(&mut self, block_hash: BlockHash, block_number: BlockNumber) {
+        self.ops
+            .push(BatchOp::BlockNumber(block_hash, block_number))
+    }
+
+    // TODO (#307): Remove TotalDifficulty.
+    pub fn add_block_total_difficulty(&mut self, block_hash: BlockHash, total_difficulty: U256) {
+        self.ops
+            .push(BatchOp::BlockTotalDifficulty(block_hash, total_difficulty))
+    }
+
+    // TODO (#307): Remove TotalDifficulty.
+    pub fn update_latest_total_difficulty(&mut self, latest_total_difficulty: U256) {
+        self.ops
+            .push(BatchOp::LatestTotalDifficulty(latest_total_difficulty))
+    }
+
+    /// Adds the location (block number, block hash and index) of each transaction
+    pub fn add_transaction_locations(
+        &mut self,
+        locations: Vec<(H256, BlockNumber, BlockHash, Index)>,
+    ) {
+        self.ops.push(BatchOp::TransactionLocations(locations))
+    }
+
+    /// Adds the receipts of a block, indexed by their position in it
+    pub fn add_receipts(&mut self, block_hash: BlockHash, receipts: Vec<Receipt>) {
+        self.ops.push(BatchOp::Receipts(block_hash, receipts))
+    }
+
+    pub fn add_account_code(&mut self, code_hash: H256, code: Bytes) {
+        self.ops.push(BatchOp::AccountCode(code_hash, code))
+    }
+
+    /// Adds the nodes taken from a state trie through [ethrex_trie::Trie::hash_and_take_changes]
+    pub fn add_state_trie_nodes(&mut self, nodes: NodeChanges) {
+        if !nodes.is_empty() {
+            self.ops.push(BatchOp::StateTrieNodes(nodes))
+        }
+    }
+
+    /// Adds the preimages of hashed addresses and storage keys, so their tries can be walked
+    /// knowing which account or slot each leaf belongs to
+    pub fn add_preimages(&mut self, preimages: Vec<(H256, Bytes)>) {
+        if !preimages.is_empty() {
+            self.ops.push(BatchOp::Preimages(preimages))
+        }
+    }
+
+    /// Adds the nodes taken from the storage trie of an account through
+    /// [ethrex_trie::Trie::hash_and_take_changes]
+    pub fn add_storage_trie_nodes(&mut self, hashed_address: H256, nodes: NodeChanges) {
+        if !nodes.is_empty() {
+            self.ops
+                .push(BatchOp::StorageTrieNodes(hashed_address, nodes))
+        }
+    }
+}
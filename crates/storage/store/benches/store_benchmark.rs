@@ -0,0 +1,328 @@
+//! Benchmarks of the store on each of its engines, starting from the state of each genesis file
+//! of the repo's `test_data` grown by a synthetic chain. The engines of the enabled features are compared, e.g. for all of them:
+//! `cargo bench -p ethrex-storage --features libmdbx,redb,rocksdb`
+
+use std::{cell::RefCell, collections::HashMap, fs::File, io::BufReader, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use ethereum_types::{Address, H256, U256};
+use ethrex_core::types::{
+    AccountInfo, Block, BlockBody, BlockHeader, EIP1559Transaction, Genesis, Transaction, TxKind,
+    EMPTY_KECCACK_HASH,
+};
+use ethrex_storage::{AccountUpdate, EngineType, Store, WriteBatch};
+use sha3::{Digest, Keccak256};
+use tempdir::TempDir;
+
+/// Number of accounts created by the first block of the synthetic chain
+const ACCOUNTS: u64 = 10_000;
+/// Number of contracts created by the first block of the synthetic chain, each with `SLOTS` slots
+const CONTRACTS: u64 = 100;
+const SLOTS: u64 = 100;
+/// Number of blocks after the first one, each updating `UPDATES_PER_BLOCK` random accounts and
+/// a slot of every contract
+const CHAIN_LENGTH: u64 = 20;
+const UPDATES_PER_BLOCK: u64 = 500;
+
+fn engine_types() -> Vec<EngineType> {
+    vec![
+        EngineType::InMemory,
+        #[cfg(feature = "libmdbx")]
+        EngineType::Libmdbx,
+        #[cfg(feature = "redb")]
+        EngineType::RedB,
+        #[cfg(feature = "rocksdb")]
+        EngineType::RocksDB,
+    ]
+}
+
+/// Genesis files of the repo's `test_data` that aren't in ethrex's format: the load test one is
+/// meant for reth, with decimal numbers in its header fields
+const FOREIGN_GENESIS_FILES: [&str; 1] = ["genesis-load-test.json"];
+
+/// Paths of the `genesis-*.json` files of the repo's `test_data`, the synthetic chains are built
+/// on each of them
+fn genesis_files() -> Vec<PathBuf> {
+    let dir = format!("{}/../../../test_data", env!("CARGO_MANIFEST_DIR"));
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|_| panic!("Failed to read {dir}"))
+        .map(|entry| entry.expect("Failed to read directory entry").path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with("genesis-")
+                        && name.ends_with(".json")
+                        && !FOREIGN_GENESIS_FILES.contains(&name)
+                })
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn read_genesis(path: &PathBuf) -> Genesis {
+    let file = File::open(path).unwrap_or_else(|_| panic!("Failed to open {path:?}"));
+    serde_json::from_reader(BufReader::new(file))
+        .unwrap_or_else(|_| panic!("Failed to decode genesis file {path:?}"))
+}
+
+/// Deterministic pseudo-random number from the given seed
+fn random(seed: u64) -> u64 {
+    let hash = Keccak256::digest(seed.to_be_bytes());
+    u64::from_be_bytes(hash[..8].try_into().expect("The hash has 32 bytes"))
+}
+
+fn account(i: u64) -> Address {
+    Address::from_low_u64_be(0x100_0000 + i)
+}
+
+fn contract(i: u64) -> Address {
+    Address::from_low_u64_be(0x200_0000 + i)
+}
+
+fn slot(i: u64) -> H256 {
+    H256::from_low_u64_be(i)
+}
+
+fn account_update(address: Address, nonce: u64, balance: u64) -> AccountUpdate {
+    AccountUpdate {
+        info: Some(AccountInfo {
+            code_hash: *EMPTY_KECCACK_HASH,
+            balance: U256::from(balance),
+            nonce,
+        }),
+        ..AccountUpdate::new(address)
+    }
+}
+
+/// Account updates of the given block of the synthetic chain
+fn synthetic_updates(number: u64) -> Vec<AccountUpdate> {
+    if number == 1 {
+        let accounts = (0..ACCOUNTS).map(|i| account_update(account(i), 0, i));
+        let contracts = (0..CONTRACTS).map(|i| AccountUpdate {
+            added_storage: (0..SLOTS).map(|j| (slot(j), U256::from(j + 1))).collect(),
+            ..account_update(contract(i), 1, 0)
+        });
+        return accounts.chain(contracts).collect();
+    }
+    let accounts = (0..UPDATES_PER_BLOCK).map(|i| {
+        let seed = random(number * UPDATES_PER_BLOCK + i);
+        account_update(account(seed % ACCOUNTS), number, seed)
+    });
+    let contracts = (0..CONTRACTS).map(|i| AccountUpdate {
+        added_storage: HashMap::from([(slot(random(number + i) % SLOTS), U256::from(number))]),
+        ..account_update(contract(i), 1, 0)
+    });
+    accounts.chain(contracts).collect()
+}
+
+/// Block on top of `parent` with a transfer to each updated account and the state root
+/// resulting from applying the updates, which are not committed
+fn synthetic_block(store: &Store, parent: &BlockHeader, updates: &[AccountUpdate]) -> Block {
+    let parent_hash = parent.compute_block_hash();
+    let state_root = store
+        .apply_account_updates_to_batch(&mut WriteBatch::new(), parent_hash, updates)
+        .expect("Failed to apply account updates")
+        .expect("The parent state is stored");
+    let transactions = updates
+        .iter()
+        .enumerate()
+        .map(|(nonce, update)| {
+            Transaction::EIP1559Transaction(EIP1559Transaction {
+                nonce: nonce as u64,
+                to: TxKind::Call(update.address),
+                value: U256::one(),
+                gas_limit: 21_000,
+                ..Default::default()
+            })
+        })
+        .collect();
+    let header = BlockHeader {
+        parent_hash,
+        number: parent.number + 1,
+        state_root,
+        gas_limit: parent.gas_limit,
+        timestamp: parent.timestamp + 12,
+        ..Default::default()
+    };
+    let body = BlockBody {
+        transactions,
+        ommers: Vec::new(),
+        withdrawals: Some(Vec::new()),
+    };
+    Block::new(header, body)
+}
+
+/// Imports the block as the chain does once it is executed: the account updates and the block
+/// are committed together, as a single batch
+fn import_block(store: &Store, block: Block, updates: &[AccountUpdate]) {
+    let mut batch = WriteBatch::new();
+    store
+        .apply_account_updates_to_batch(&mut batch, block.header.parent_hash, updates)
+        .expect("Failed to apply account updates")
+        .expect("The parent state is stored");
+    store
+        .add_block_to_batch(&mut batch, block)
+        .expect("Failed to add block");
+    store.commit_batch(batch).expect("Failed to commit batch");
+}
+
+/// Creates a store with the genesis state and the synthetic chain on top of it
+fn synthetic_store(engine_type: EngineType, genesis_file: &PathBuf, dir: &TempDir) -> Store {
+    let store = Store::new(
+        dir.path().to_str().expect("Temp dir path is valid UTF-8"),
+        engine_type,
+    )
+    .expect("Failed to create store");
+    let genesis = read_genesis(genesis_file);
+    let mut head = genesis.get_block().header;
+    store
+        .add_initial_state(genesis)
+        .expect("Failed to add genesis state");
+    for number in 1..=CHAIN_LENGTH + 1 {
+        let updates = synthetic_updates(number);
+        let block = synthetic_block(&store, &head, &updates);
+        let hash = block.hash();
+        head = block.header.clone();
+        import_block(&store, block, &updates);
+        store
+            .set_canonical_block(number, hash)
+            .expect("Failed to set canonical block");
+        store
+            .update_latest_block_number(number)
+            .expect("Failed to update latest block number");
+    }
+    store
+}
+
+/// Calls `f` with a synthetic store of each engine for each genesis file, and the id of the
+/// benchmark for it
+fn for_each_store(mut f: impl FnMut(BenchmarkId, &Store)) {
+    for genesis_file in genesis_files() {
+        let chain = genesis_file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .expect("Genesis file names are valid UTF-8")
+            .trim_start_matches("genesis-")
+            .to_string();
+        for engine_type in engine_types() {
+            let dir = TempDir::new("store-bench").expect("Failed to create temp dir");
+            let store = synthetic_store(engine_type, &genesis_file, &dir);
+            f(BenchmarkId::new(&chain, format!("{engine_type:?}")), &store);
+        }
+    }
+}
+
+fn head(store: &Store) -> BlockHeader {
+    let number = store
+        .get_latest_block_number()
+        .expect("Failed to get latest block number");
+    store
+        .get_block_header(number)
+        .expect("Failed to get head header")
+        .expect("The head header is stored")
+}
+
+fn bench_block_import(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block_import");
+    group.sample_size(10);
+    for_each_store(|id, store| {
+        let head = RefCell::new(head(store));
+        // Each iteration imports a new block on top of the previous one, its updates and block
+        // are built by the setup so that only the import is measured
+        group.bench_function(id, |b| {
+            b.iter_batched(
+                || {
+                    let number = head.borrow().number + 1;
+                    let updates = synthetic_updates(number);
+                    let block = synthetic_block(store, &head.borrow(), &updates);
+                    *head.borrow_mut() = block.header.clone();
+                    (block, updates)
+                },
+                |(block, updates)| import_block(store, block, &updates),
+                BatchSize::PerIteration,
+            )
+        });
+    });
+    group.finish();
+}
+
+fn bench_state_root_commit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("state_root_commit");
+    group.sample_size(10);
+    for_each_store(|id, store| {
+        let head = head(store);
+        let head_hash = head.compute_block_hash();
+        let updates = synthetic_updates(head.number + 1);
+        // Applies the same updates on top of the head each time, so the nodes written are the
+        // same and the database doesn't grow across iterations
+        group.bench_function(id, |b| {
+            b.iter(|| {
+                store
+                    .apply_account_updates(head_hash, &updates)
+                    .expect("Failed to apply account updates")
+            })
+        });
+    });
+    group.finish();
+}
+
+fn bench_account_reads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_account_reads");
+    for_each_store(|id, store| {
+        let head_number = head(store).number;
+        let mut seed = 0;
+        group.bench_function(id, |b| {
+            b.iter(|| {
+                seed += 1;
+                store
+                    .get_account_info(head_number, account(random(seed) % ACCOUNTS))
+                    .expect("Failed to read account")
+            })
+        });
+    });
+    group.finish();
+}
+
+fn bench_storage_reads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_storage_reads");
+    for_each_store(|id, store| {
+        let head_number = head(store).number;
+        let mut seed = 0;
+        group.bench_function(id, |b| {
+            b.iter(|| {
+                seed += 1;
+                let random = random(seed);
+                store
+                    .get_storage_at(
+                        head_number,
+                        contract(random % CONTRACTS),
+                        slot(random / CONTRACTS % SLOTS),
+                    )
+                    .expect("Failed to read storage slot")
+            })
+        });
+    });
+    group.finish();
+}
+
+fn bench_iter_accounts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iter_accounts");
+    group.sample_size(10);
+    for_each_store(|id, store| {
+        let state_root = head(store).state_root;
+        group.bench_function(id, |b| b.iter(|| store.iter_accounts(state_root).count()));
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_block_import,
+    bench_state_root_commit,
+    bench_account_reads,
+    bench_storage_reads,
+    bench_iter_accounts
+);
+criterion_main!(benches);
@@ -4,4 +4,6 @@ pub mod in_memory;
 pub mod libmdbx;
 #[cfg(feature = "redb")]
 pub mod redb;
+#[cfg(feature = "rocksdb")]
+pub mod rocksdb;
 mod utils;
@@ -1,13 +1,13 @@
 use bytes::Bytes;
-use ethereum_types::{H256, U256};
+use ethereum_types::{H256, H512, U256};
 use ethrex_core::types::{
     BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index,
     Receipt, Transaction,
 };
-use std::{fmt::Debug, panic::RefUnwindSafe};
+use std::{fmt::Debug, panic::RefUnwindSafe, path::Path};
 
-use crate::error::StoreError;
-use ethrex_trie::Trie;
+use crate::{batch::WriteBatch, error::StoreError, TableStats};
+use ethrex_trie::{Nibbles, Trie};
 
 pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     /// Add block header
@@ -116,6 +116,9 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     /// Obtain account code via code hash
     fn get_account_code(&self, code_hash: H256) -> Result<Option<Bytes>, StoreError>;
 
+    /// Obtain the address or storage key hashing to the given hash, if it was ever stored
+    fn get_preimage(&self, hash: H256) -> Result<Option<Bytes>, StoreError>;
+
     fn get_transaction_by_hash(
         &self,
         transaction_hash: H256,
@@ -242,4 +245,69 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     ) -> Result<(), StoreError>;
 
     fn get_receipts_for_block(&self, block_hash: &BlockHash) -> Result<Vec<Receipt>, StoreError>;
+
+    /// Sets the hash of the last header downloaded during a snap sync
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError>;
+
+    /// Gets the hash of the last header downloaded during a snap sync
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError>;
+
+    /// Sets the last key fetched from the state trie being fetched during snap sync
+    fn set_state_trie_key_checkpoint(&self, last_key: H256) -> Result<(), StoreError>;
+
+    /// Gets the last key fetched from the state trie being fetched during snap sync
+    fn get_state_trie_key_checkpoint(&self) -> Result<Option<H256>, StoreError>;
+
+    /// Sets the root of the state trie being rebuilt during snap sync
+    fn set_state_trie_root_checkpoint(&self, current_root: H256) -> Result<(), StoreError>;
+
+    /// Gets the root of the state trie being rebuilt during snap sync
+    fn get_state_trie_root_checkpoint(&self) -> Result<Option<H256>, StoreError>;
+
+    /// Sets the state trie paths in need of healing
+    fn set_state_heal_paths(&self, paths: Vec<Nibbles>) -> Result<(), StoreError>;
+
+    /// Gets the state trie paths in need of healing
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<Nibbles>>, StoreError>;
+
+    /// Sets the storage trie paths in need of healing, grouped by hashed address
+    fn set_storage_heal_paths(&self, accounts: Vec<(H256, Vec<Nibbles>)>)
+        -> Result<(), StoreError>;
+
+    /// Gets the storage trie paths in need of healing, grouped by hashed address
+    #[allow(clippy::type_complexity)]
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<(H256, Vec<Nibbles>)>>, StoreError>;
+
+    /// Clears all checkpoint data created during the last snap sync
+    fn clear_snap_state(&self) -> Result<(), StoreError>;
+
+    /// Replaces the stored peers with the given ones
+    /// Each peer is stored by node id as an encoding defined by the networking layer
+    fn set_known_peers(&self, peers: Vec<(H512, Vec<u8>)>) -> Result<(), StoreError>;
+
+    /// Gets the encodings of all stored peers
+    fn get_known_peers(&self) -> Result<Vec<Vec<u8>>, StoreError>;
+
+    /// Stores the version of the layout the database tables follow
+    fn set_schema_version(&self, version: u64) -> Result<(), StoreError>;
+
+    /// Obtains the version of the layout the database tables follow, if it was ever stored
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError>;
+
+    /// Records that the preimages of some stored addresses and storage keys are missing
+    fn set_preimages_incomplete(&self) -> Result<(), StoreError>;
+
+    /// Returns whether the preimages of some stored addresses and storage keys are missing
+    fn are_preimages_incomplete(&self) -> Result<bool, StoreError>;
+
+    /// Applies all the writes of the batch in a single transaction, persisting either all of
+    /// them or none
+    fn commit_batch(&self, batch: WriteBatch) -> Result<(), StoreError>;
+
+    /// Copies the whole database into a new one at `path`, as it was when the copy started.
+    /// Writes to the store can continue while the copy is made
+    fn backup(&self, path: &Path) -> Result<(), StoreError>;
+
+    /// Obtains the number of entries and the disk usage of each table
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError>;
 }
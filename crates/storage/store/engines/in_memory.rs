@@ -1,13 +1,18 @@
-use crate::error::StoreError;
+use crate::{
+    batch::{BatchOp, WriteBatch},
+    error::StoreError,
+    TableStats,
+};
 use bytes::Bytes;
-use ethereum_types::{H256, U256};
+use ethereum_types::{H256, H512, U256};
 use ethrex_core::types::{
     BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index, Receipt,
 };
-use ethrex_trie::{InMemoryTrieDB, Trie};
+use ethrex_trie::{InMemoryTrieDB, Nibbles, Trie};
 use std::{
     collections::HashMap,
     fmt::Debug,
+    path::Path,
     sync::{Arc, Mutex, MutexGuard},
 };
 
@@ -27,6 +32,8 @@ struct StoreInner {
     headers: HashMap<BlockHash, BlockHeader>,
     // Maps code hashes to code
     account_codes: HashMap<H256, Bytes>,
+    // Maps hashed addresses and storage keys to the address or key
+    preimages: HashMap<H256, Bytes>,
     // Maps transaction hashes to their blocks (height+hash) and index within the blocks.
     transaction_locations: HashMap<H256, Vec<(BlockNumber, BlockHash, Index)>>,
     receipts: HashMap<BlockHash, HashMap<Index, Receipt>>,
@@ -38,6 +45,10 @@ struct StoreInner {
     // Stores local blocks by payload id
     payloads: HashMap<u64, (Block, U256, BlobsBundle, bool)>,
     pending_blocks: HashMap<BlockHash, Block>,
+    // Stores current Snap State
+    snap_state: SnapState,
+    // Peers found by the discovery protocol, kept across restarts
+    known_peers: HashMap<H512, Vec<u8>>,
 }
 
 #[derive(Default, Debug)]
@@ -50,6 +61,23 @@ struct ChainData {
     // TODO (#307): Remove TotalDifficulty.
     latest_total_difficulty: Option<U256>,
     pending_block_number: Option<BlockNumber>,
+    schema_version: Option<u64>,
+    preimages_incomplete: bool,
+}
+
+// Keeps track of the state left by the latest snap attempt
+#[derive(Default, Debug)]
+pub struct SnapState {
+    /// Latest downloaded block header's hash from a previously aborted sync
+    header_download_checkpoint: Option<BlockHash>,
+    /// Last key fetched from the state trie
+    state_trie_key_checkpoint: Option<H256>,
+    /// Root of the state trie being rebuilt
+    state_trie_root_checkpoint: Option<H256>,
+    /// State trie paths in need of healing
+    state_heal_paths: Option<Vec<Nibbles>>,
+    /// Storage trie paths in need of healing, grouped by hashed account address
+    storage_heal_paths: Option<Vec<(H256, Vec<Nibbles>)>>,
 }
 
 impl Store {
@@ -212,6 +240,10 @@ impl StoreEngine for Store {
         Ok(self.inner().account_codes.get(&code_hash).cloned())
     }
 
+    fn get_preimage(&self, hash: H256) -> Result<Option<Bytes>, StoreError> {
+        Ok(self.inner().preimages.get(&hash).cloned())
+    }
+
     fn set_chain_config(&self, chain_config: &ChainConfig) -> Result<(), StoreError> {
         // Store cancun timestamp
         self.inner().chain_data.chain_config = Some(*chain_config);
@@ -412,6 +444,210 @@ impl StoreEngine for Store {
             .insert(payload_id, (block, block_value, blobs_bundle, completed));
         Ok(())
     }
+
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.inner().snap_state.header_download_checkpoint = Some(block_hash);
+        Ok(())
+    }
+
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        Ok(self.inner().snap_state.header_download_checkpoint)
+    }
+
+    fn set_state_trie_key_checkpoint(&self, last_key: H256) -> Result<(), StoreError> {
+        self.inner().snap_state.state_trie_key_checkpoint = Some(last_key);
+        Ok(())
+    }
+
+    fn get_state_trie_key_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        Ok(self.inner().snap_state.state_trie_key_checkpoint)
+    }
+
+    fn set_state_trie_root_checkpoint(&self, current_root: H256) -> Result<(), StoreError> {
+        self.inner().snap_state.state_trie_root_checkpoint = Some(current_root);
+        Ok(())
+    }
+
+    fn get_state_trie_root_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        Ok(self.inner().snap_state.state_trie_root_checkpoint)
+    }
+
+    fn set_state_heal_paths(&self, paths: Vec<Nibbles>) -> Result<(), StoreError> {
+        self.inner().snap_state.state_heal_paths = Some(paths);
+        Ok(())
+    }
+
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<Nibbles>>, StoreError> {
+        Ok(self.inner().snap_state.state_heal_paths.clone())
+    }
+
+    fn set_storage_heal_paths(
+        &self,
+        accounts: Vec<(H256, Vec<Nibbles>)>,
+    ) -> Result<(), StoreError> {
+        self.inner().snap_state.storage_heal_paths = Some(accounts);
+        Ok(())
+    }
+
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<(H256, Vec<Nibbles>)>>, StoreError> {
+        Ok(self.inner().snap_state.storage_heal_paths.clone())
+    }
+
+    fn clear_snap_state(&self) -> Result<(), StoreError> {
+        self.inner().snap_state = Default::default();
+        Ok(())
+    }
+
+    fn set_known_peers(&self, peers: Vec<(H512, Vec<u8>)>) -> Result<(), StoreError> {
+        self.inner().known_peers = peers.into_iter().collect();
+        Ok(())
+    }
+
+    fn get_known_peers(&self) -> Result<Vec<Vec<u8>>, StoreError> {
+        Ok(self.inner().known_peers.values().cloned().collect())
+    }
+
+    fn set_schema_version(&self, version: u64) -> Result<(), StoreError> {
+        self.inner().chain_data.schema_version.replace(version);
+        Ok(())
+    }
+
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError> {
+        Ok(self.inner().chain_data.schema_version)
+    }
+
+    fn set_preimages_incomplete(&self) -> Result<(), StoreError> {
+        self.inner().chain_data.preimages_incomplete = true;
+        Ok(())
+    }
+
+    fn are_preimages_incomplete(&self) -> Result<bool, StoreError> {
+        Ok(self.inner().chain_data.preimages_incomplete)
+    }
+
+    fn commit_batch(&self, batch: WriteBatch) -> Result<(), StoreError> {
+        // Holding the lock for the whole batch keeps readers from seeing it partially applied
+        let mut store = self.inner();
+        for op in batch.ops {
+            match op {
+                BatchOp::BlockHeader(hash, header) => {
+                    store.headers.insert(hash, header);
+                }
+                BatchOp::BlockBody(hash, body) => {
+                    store.bodies.insert(hash, body);
+                }
+                BatchOp::BlockNumber(hash, number) => {
+                    store.block_numbers.insert(hash, number);
+                }
+                BatchOp::BlockTotalDifficulty(hash, total_difficulty) => {
+                    store
+                        .block_total_difficulties
+                        .insert(hash, total_difficulty);
+                }
+                BatchOp::LatestTotalDifficulty(total_difficulty) => {
+                    store
+                        .chain_data
+                        .latest_total_difficulty
+                        .replace(total_difficulty);
+                }
+                BatchOp::TransactionLocations(locations) => {
+                    for (transaction_hash, block_number, block_hash, index) in locations {
+                        store
+                            .transaction_locations
+                            .entry(transaction_hash)
+                            .or_default()
+                            .push((block_number, block_hash, index));
+                    }
+                }
+                BatchOp::Receipts(block_hash, receipts) => {
+                    let entry = store.receipts.entry(block_hash).or_default();
+                    for (index, receipt) in receipts.into_iter().enumerate() {
+                        entry.insert(index as u64, receipt);
+                    }
+                }
+                BatchOp::AccountCode(code_hash, code) => {
+                    store.account_codes.insert(code_hash, code);
+                }
+                BatchOp::StateTrieNodes(nodes) => {
+                    store
+                        .state_trie_nodes
+                        .lock()
+                        .map_err(|error| StoreError::Custom(error.to_string()))?
+                        .extend(nodes);
+                }
+                BatchOp::StorageTrieNodes(hashed_address, nodes) => {
+                    store
+                        .storage_trie_nodes
+                        .entry(hashed_address)
+                        .or_default()
+                        .lock()
+                        .map_err(|error| StoreError::Custom(error.to_string()))?
+                        .extend(nodes);
+                }
+                BatchOp::Preimages(preimages) => store.preimages.extend(preimages),
+            }
+        }
+        Ok(())
+    }
+
+    fn backup(&self, _path: &Path) -> Result<(), StoreError> {
+        Err(StoreError::Custom(
+            "The in-memory store has no database to back up".to_string(),
+        ))
+    }
+
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        let store = self.inner();
+        let state_trie_nodes = store
+            .state_trie_nodes
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .len();
+        let mut storage_trie_nodes = 0;
+        for nodes in store.storage_trie_nodes.values() {
+            storage_trie_nodes += nodes
+                .lock()
+                .map_err(|error| StoreError::Custom(error.to_string()))?
+                .len();
+        }
+        let tables = [
+            ("CanonicalBlockHashes", store.canonical_hashes.len()),
+            ("BlockNumbers", store.block_numbers.len()),
+            (
+                "BlockTotalDifficulties",
+                store.block_total_difficulties.len(),
+            ),
+            ("Headers", store.headers.len()),
+            ("Bodies", store.bodies.len()),
+            ("AccountCodes", store.account_codes.len()),
+            ("Preimages", store.preimages.len()),
+            (
+                "Receipts",
+                store.receipts.values().map(HashMap::len).sum::<usize>(),
+            ),
+            (
+                "TransactionLocations",
+                store
+                    .transaction_locations
+                    .values()
+                    .map(Vec::len)
+                    .sum::<usize>(),
+            ),
+            ("StateTrieNodes", state_trie_nodes),
+            ("StorageTriesNodes", storage_trie_nodes),
+            ("Payloads", store.payloads.len()),
+            ("PendingBlocks", store.pending_blocks.len()),
+            ("KnownPeers", store.known_peers.len()),
+        ];
+        Ok(tables
+            .into_iter()
+            .map(|(name, entries)| TableStats {
+                name: name.to_string(),
+                entries: entries as u64,
+                size: None,
+            })
+            .collect())
+    }
 }
 
 impl Debug for Store {
@@ -1,27 +1,33 @@
 use super::api::StoreEngine;
-use super::utils::ChainDataIndex;
+use super::utils::{ChainDataIndex, SnapStateIndex};
+use crate::batch::{BatchOp, WriteBatch};
 use crate::error::StoreError;
 use crate::rlp::{
     AccountCodeHashRLP, AccountCodeRLP, BlockBodyRLP, BlockHashRLP, BlockHeaderRLP, BlockRLP,
-    BlockTotalDifficultyRLP, ReceiptRLP, Rlp, TransactionHashRLP, TupleRLP,
+    BlockTotalDifficultyRLP, PreimageHashRLP, PreimageRLP, ReceiptRLP, Rlp, TransactionHashRLP,
+    TupleRLP,
 };
+use crate::schema::Migration;
+use crate::TableStats;
 use anyhow::Result;
 use bytes::Bytes;
-use ethereum_types::{H256, U256};
+use ethereum_types::{H256, H512, U256};
 use ethrex_core::types::{
     BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index,
     Receipt, Transaction,
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_trie::{LibmdbxDupsortTrieDB, LibmdbxTrieDB, Trie};
-use libmdbx::orm::{Decodable, Encodable, Table};
+use ethrex_trie::{
+    db::node_hash_to_fixed_size, LibmdbxDupsortTrieDB, LibmdbxTrieDB, Nibbles, Trie,
+};
+use libmdbx::orm::{Decodable, Encodable, Table, UntypedTable};
 use libmdbx::{
     dupsort,
-    orm::{table, Database},
+    orm::{table, Database, DatabaseChart},
     table_info,
 };
-use libmdbx::{DatabaseOptions, Mode, ReadWriteOptions};
+use libmdbx::{DatabaseOptions, Mode, ReadWriteOptions, RO};
 use serde_json;
 use std::fmt::{Debug, Formatter};
 use std::path::Path;
@@ -37,6 +43,13 @@ impl Store {
         })
     }
 
+    /// Opens an existing database in read-only mode, which can be done while the node is running
+    pub fn open_read_only(path: &str) -> Result<Self, StoreError> {
+        Ok(Self {
+            db: Arc::new(Database::open(path, &tables()).map_err(StoreError::LibmdbxError)?),
+        })
+    }
+
     // Helper method to write into a libmdbx table
     fn write<T: Table>(&self, key: T::Key, value: T::Value) -> Result<(), StoreError> {
         let txn = self
@@ -169,6 +182,10 @@ impl StoreEngine for Store {
         Ok(self.read::<AccountCodes>(code_hash.into())?.map(|b| b.to()))
     }
 
+    fn get_preimage(&self, hash: H256) -> Result<Option<Bytes>, StoreError> {
+        Ok(self.read::<Preimages>(hash.into())?.map(|b| b.to()))
+    }
+
     fn add_receipt(
         &self,
         block_hash: BlockHash,
@@ -522,6 +539,288 @@ impl StoreEngine for Store {
 
         Ok(receipts.into_iter().map(|receipt| receipt.to()).collect())
     }
+
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.write::<SnapState>(
+            SnapStateIndex::HeaderDownloadCheckpoint,
+            block_hash.encode_to_vec(),
+        )
+    }
+
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        self.read::<SnapState>(SnapStateIndex::HeaderDownloadCheckpoint)?
+            .map(|ref h| BlockHash::decode(h))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn set_state_trie_key_checkpoint(&self, last_key: H256) -> Result<(), StoreError> {
+        self.write::<SnapState>(
+            SnapStateIndex::StateTrieKeyCheckpoint,
+            last_key.encode_to_vec(),
+        )
+    }
+
+    fn get_state_trie_key_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        self.read::<SnapState>(SnapStateIndex::StateTrieKeyCheckpoint)?
+            .map(|ref h| H256::decode(h))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn set_state_trie_root_checkpoint(&self, current_root: H256) -> Result<(), StoreError> {
+        self.write::<SnapState>(
+            SnapStateIndex::StateTrieRootCheckpoint,
+            current_root.encode_to_vec(),
+        )
+    }
+
+    fn get_state_trie_root_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        self.read::<SnapState>(SnapStateIndex::StateTrieRootCheckpoint)?
+            .map(|ref h| H256::decode(h))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn set_state_heal_paths(&self, paths: Vec<Nibbles>) -> Result<(), StoreError> {
+        self.write::<SnapState>(SnapStateIndex::StateHealPaths, paths.encode_to_vec())
+    }
+
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<Nibbles>>, StoreError> {
+        self.read::<SnapState>(SnapStateIndex::StateHealPaths)?
+            .map(|ref h| <Vec<Nibbles>>::decode(h))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn set_storage_heal_paths(
+        &self,
+        accounts: Vec<(H256, Vec<Nibbles>)>,
+    ) -> Result<(), StoreError> {
+        self.write::<SnapState>(SnapStateIndex::StorageHealPaths, accounts.encode_to_vec())
+    }
+
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<(H256, Vec<Nibbles>)>>, StoreError> {
+        self.read::<SnapState>(SnapStateIndex::StorageHealPaths)?
+            .map(|ref h| <Vec<(H256, Vec<Nibbles>)>>::decode(h))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn clear_snap_state(&self) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        txn.clear_table::<SnapState>()
+            .map_err(StoreError::LibmdbxError)?;
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
+    fn set_known_peers(&self, peers: Vec<(H512, Vec<u8>)>) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        txn.clear_table::<KnownPeers>()
+            .map_err(StoreError::LibmdbxError)?;
+        for (node_id, peer) in peers {
+            txn.upsert::<KnownPeers>(node_id.as_bytes().to_vec(), peer)
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
+    fn get_known_peers(&self) -> Result<Vec<Vec<u8>>, StoreError> {
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        let cursor = txn
+            .cursor::<KnownPeers>()
+            .map_err(StoreError::LibmdbxError)?;
+        cursor
+            .walk(None)
+            .map(|entry| entry.map(|(_node_id, peer)| peer))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StoreError::LibmdbxError)
+    }
+
+    fn set_schema_version(&self, version: u64) -> Result<(), StoreError> {
+        self.write::<ChainData>(ChainDataIndex::SchemaVersion, version.encode_to_vec())
+    }
+
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::SchemaVersion)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn set_preimages_incomplete(&self) -> Result<(), StoreError> {
+        self.write::<ChainData>(ChainDataIndex::PreimagesIncomplete, true.encode_to_vec())
+    }
+
+    fn are_preimages_incomplete(&self) -> Result<bool, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::PreimagesIncomplete)? {
+            None => Ok(false),
+            Some(ref rlp) => RLPDecode::decode(rlp).map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn commit_batch(&self, batch: WriteBatch) -> Result<(), StoreError> {
+        // The transaction is aborted when dropped, discarding the whole batch if a write fails
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        for op in batch.ops {
+            let result = match op {
+                BatchOp::BlockHeader(block_hash, block_header) => {
+                    txn.upsert::<Headers>(block_hash.into(), block_header.into())
+                }
+                BatchOp::BlockBody(block_hash, block_body) => {
+                    txn.upsert::<Bodies>(block_hash.into(), block_body.into())
+                }
+                BatchOp::BlockNumber(block_hash, block_number) => {
+                    txn.upsert::<BlockNumbers>(block_hash.into(), block_number)
+                }
+                BatchOp::BlockTotalDifficulty(block_hash, block_total_difficulty) => {
+                    txn.upsert::<BlockTotalDifficulties>(
+                        block_hash.into(),
+                        block_total_difficulty.into(),
+                    )
+                }
+                BatchOp::LatestTotalDifficulty(latest_total_difficulty) => txn.upsert::<ChainData>(
+                    ChainDataIndex::LatestTotalDifficulty,
+                    latest_total_difficulty.encode_to_vec(),
+                ),
+                BatchOp::TransactionLocations(locations) => locations.into_iter().try_for_each(
+                    |(tx_hash, block_number, block_hash, index)| {
+                        txn.upsert::<TransactionLocations>(
+                            tx_hash.into(),
+                            (block_number, block_hash, index).into(),
+                        )
+                    },
+                ),
+                BatchOp::Receipts(block_hash, receipts) => receipts
+                    .into_iter()
+                    .enumerate()
+                    .try_for_each(|(index, receipt)| {
+                        txn.upsert::<Receipts>((block_hash, index as u64).into(), receipt.into())
+                    }),
+                BatchOp::AccountCode(code_hash, code) => {
+                    txn.upsert::<AccountCodes>(code_hash.into(), code.into())
+                }
+                BatchOp::StateTrieNodes(nodes) => {
+                    nodes.into_iter().try_for_each(|(node_hash, node)| {
+                        txn.upsert::<StateTrieNodes>(node_hash, node)
+                    })
+                }
+                BatchOp::StorageTrieNodes(hashed_address, nodes) => {
+                    nodes.into_iter().try_for_each(|(node_hash, node)| {
+                        txn.upsert::<StorageTriesNodes>(
+                            (hashed_address.0, node_hash_to_fixed_size(node_hash)),
+                            node,
+                        )
+                    })
+                }
+                BatchOp::Preimages(preimages) => {
+                    preimages.into_iter().try_for_each(|(hash, preimage)| {
+                        txn.upsert::<Preimages>(hash.into(), preimage.into())
+                    })
+                }
+            };
+            result.map_err(StoreError::LibmdbxError)?;
+        }
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
+    fn backup(&self, path: &Path) -> Result<(), StoreError> {
+        let backup = init_db(Some(path));
+        // The read transaction sees the database as it was when it started, while the node keeps
+        // committing blocks from this or another process
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        copy_table::<BlockNumbers>(&txn, &backup)?;
+        // TODO (#307): Remove TotalDifficulty.
+        copy_table::<BlockTotalDifficulties>(&txn, &backup)?;
+        copy_table::<Headers>(&txn, &backup)?;
+        copy_table::<Bodies>(&txn, &backup)?;
+        copy_table::<AccountCodes>(&txn, &backup)?;
+        copy_table::<Preimages>(&txn, &backup)?;
+        copy_table::<Receipts>(&txn, &backup)?;
+        copy_table::<TransactionLocations>(&txn, &backup)?;
+        copy_table::<ChainData>(&txn, &backup)?;
+        copy_table::<SnapState>(&txn, &backup)?;
+        copy_table::<StateTrieNodes>(&txn, &backup)?;
+        copy_table::<StorageTriesNodes>(&txn, &backup)?;
+        copy_table::<CanonicalBlockHashes>(&txn, &backup)?;
+        copy_table::<Payloads>(&txn, &backup)?;
+        copy_table::<PendingBlocks>(&txn, &backup)?;
+        copy_table::<KnownPeers>(&txn, &backup)
+    }
+
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        Ok(vec![
+            table_stats::<BlockNumbers>(&txn)?,
+            // TODO (#307): Remove TotalDifficulty.
+            table_stats::<BlockTotalDifficulties>(&txn)?,
+            table_stats::<Headers>(&txn)?,
+            table_stats::<Bodies>(&txn)?,
+            table_stats::<AccountCodes>(&txn)?,
+            table_stats::<Preimages>(&txn)?,
+            table_stats::<Receipts>(&txn)?,
+            table_stats::<TransactionLocations>(&txn)?,
+            table_stats::<ChainData>(&txn)?,
+            table_stats::<SnapState>(&txn)?,
+            table_stats::<StateTrieNodes>(&txn)?,
+            table_stats::<StorageTriesNodes>(&txn)?,
+            table_stats::<CanonicalBlockHashes>(&txn)?,
+            table_stats::<Payloads>(&txn)?,
+            table_stats::<PendingBlocks>(&txn)?,
+            table_stats::<KnownPeers>(&txn)?,
+        ])
+    }
+}
+
+/// Amount of entries copied into a backup by each of its write transactions
+const BACKUP_TXN_ENTRIES: usize = 100_000;
+
+// Helper function to copy every entry of a table, as seen by a read transaction, into another
+// database. The entries are copied as stored, without decoding them
+fn copy_table<T: Table>(
+    txn: &libmdbx::orm::Transaction<'_, RO>,
+    backup: &Database,
+) -> Result<(), StoreError> {
+    let cursor = txn
+        .cursor::<UntypedTable<T>>()
+        .map_err(StoreError::LibmdbxError)?;
+    let mut backup_txn = backup.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+    for (copied, entry) in cursor.walk(None).enumerate() {
+        let (key, value) = entry.map_err(StoreError::LibmdbxError)?;
+        backup_txn
+            .upsert::<UntypedTable<T>>(key, value)
+            .map_err(StoreError::LibmdbxError)?;
+        // Keeps the size of each write transaction bounded for big tables
+        if (copied + 1) % BACKUP_TXN_ENTRIES == 0 {
+            backup_txn.commit().map_err(StoreError::LibmdbxError)?;
+            backup_txn = backup.begin_readwrite().map_err(StoreError::LibmdbxError)?;
+        }
+    }
+    backup_txn.commit().map_err(StoreError::LibmdbxError)
+}
+
+fn table_stats<T: Table>(
+    txn: &libmdbx::orm::Transaction<'_, RO>,
+) -> Result<TableStats, StoreError> {
+    let stat = txn
+        .table_stat::<T>()
+        .map_err(|error| StoreError::LibmdbxError(error.into()))?;
+    Ok(TableStats {
+        name: T::NAME.to_string(),
+        entries: stat.entries() as u64,
+        size: Some(stat.total_size()),
+    })
 }
 
 impl Debug for Store {
@@ -531,6 +830,16 @@ impl Debug for Store {
 }
 
 // Define tables
+// Changing any of these definitions requires bumping the schema version and adding a migration
+// to `MIGRATIONS`
+
+/// Migrations of the tables to each schema version, see [crate::schema]
+pub(crate) const MIGRATIONS: &[Migration<Store>] = &[Migration {
+    from: 1,
+    description: "Add the preimages table, which lacks the preimages of the existing state",
+    // The preimages of the existing state can only be recovered by executing the chain again
+    apply: |store| store.set_preimages_incomplete(),
+}];
 
 table!(
     /// The canonical block hash for each block number. It represents the canonical chain.
@@ -560,6 +869,10 @@ table!(
     /// Account codes table.
     ( AccountCodes ) AccountCodeHashRLP => AccountCodeRLP
 );
+table!(
+    /// Addresses and storage keys by their hash.
+    ( Preimages ) PreimageHashRLP => PreimageRLP
+);
 
 dupsort!(
     /// Receipts table.
@@ -583,6 +896,12 @@ table!(
     ( ChainData ) ChainDataIndex => Vec<u8>
 );
 
+table!(
+    /// Stores snap state, each value is unique and stored as its rlp encoding
+    /// See [SnapStateIndex] for available values
+    ( SnapState ) SnapStateIndex => Vec<u8>
+);
+
 // Trie storages
 
 table!(
@@ -590,6 +909,13 @@ table!(
     ( StateTrieNodes ) Vec<u8> => Vec<u8>
 );
 
+// Networking
+
+table!(
+    /// Peers found by the discovery protocol, indexed by node id
+    ( KnownPeers ) Vec<u8> => Vec<u8>
+);
+
 // Local Blocks
 
 table!(
@@ -669,27 +995,42 @@ impl Encodable for ChainDataIndex {
     }
 }
 
-/// Initializes a new database with the provided path. If the path is `None`, the database
-/// will be temporary.
-pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
-    let tables = [
+impl Encodable for SnapStateIndex {
+    type Encoded = [u8; 4];
+
+    fn encode(self) -> Self::Encoded {
+        (self as u32).encode()
+    }
+}
+
+/// Tables of the database and their settings
+fn tables() -> DatabaseChart {
+    [
         table_info!(BlockNumbers),
         // TODO (#307): Remove TotalDifficulty.
         table_info!(BlockTotalDifficulties),
         table_info!(Headers),
         table_info!(Bodies),
         table_info!(AccountCodes),
+        table_info!(Preimages),
         table_info!(Receipts),
         table_info!(TransactionLocations),
         table_info!(ChainData),
+        table_info!(SnapState),
         table_info!(StateTrieNodes),
         table_info!(StorageTriesNodes),
         table_info!(CanonicalBlockHashes),
         table_info!(Payloads),
         table_info!(PendingBlocks),
+        table_info!(KnownPeers),
     ]
     .into_iter()
-    .collect();
+    .collect()
+}
+
+/// Initializes a new database with the provided path. If the path is `None`, the database
+/// will be temporary.
+pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
     let path = path.map(|p| p.as_ref().to_path_buf());
     let options = DatabaseOptions {
         mode: Mode::ReadWrite(ReadWriteOptions {
@@ -699,7 +1040,7 @@ pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
         }),
         ..Default::default()
     };
-    Database::create_with_options(path, options, &tables).unwrap()
+    Database::create_with_options(path, options, &tables()).unwrap()
 }
 
 #[cfg(test)]
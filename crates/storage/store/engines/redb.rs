@@ -1,28 +1,52 @@
-use std::{borrow::Borrow, panic::RefUnwindSafe, sync::Arc};
+use std::{borrow::Borrow, panic::RefUnwindSafe, path::Path, sync::Arc};
 
 use ethrex_core::types::BlockBody;
 use ethrex_core::{
     types::{BlobsBundle, Block, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index, Receipt},
-    H256, U256,
+    H256, H512, U256,
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_trie::{
-    db::{redb::RedBTrie, redb_multitable::RedBMultiTableTrieDB},
-    Trie,
+    db::{
+        node_hash_to_fixed_size,
+        redb::{RedBTrie, TRIE_NODES_TABLE},
+        redb_multitable::RedBMultiTableTrieDB,
+    },
+    Nibbles, Trie,
+};
+use redb::{
+    AccessGuard, Database, Key, MultimapTableDefinition, ReadTransaction, ReadableMultimapTable,
+    ReadableTable, ReadableTableMetadata, TableDefinition, TypeName, Value, WriteTransaction,
 };
-use redb::{AccessGuard, Database, Key, MultimapTableDefinition, TableDefinition, TypeName, Value};
 
+use crate::batch::{BatchOp, WriteBatch};
 use crate::rlp::{BlockRLP, BlockTotalDifficultyRLP, Rlp, TransactionHashRLP};
+use crate::schema::Migration;
 use crate::{
     error::StoreError,
     rlp::{
-        AccountCodeHashRLP, AccountCodeRLP, BlockBodyRLP, BlockHashRLP, BlockHeaderRLP, ReceiptRLP,
-        TupleRLP,
+        AccountCodeHashRLP, AccountCodeRLP, BlockBodyRLP, BlockHashRLP, BlockHeaderRLP,
+        PreimageHashRLP, PreimageRLP, ReceiptRLP, TupleRLP,
     },
+    TableStats,
 };
 
-use super::{api::StoreEngine, utils::ChainDataIndex};
+use super::{
+    api::StoreEngine,
+    utils::{ChainDataIndex, SnapStateIndex},
+};
+
+// Changing any of these definitions requires bumping the schema version and adding a migration
+// to `MIGRATIONS`
+
+/// Migrations of the tables to each schema version, see [crate::schema]
+pub(crate) const MIGRATIONS: &[Migration<RedBStore>] = &[Migration {
+    from: 1,
+    description: "Add the preimages table, which lacks the preimages of the existing state",
+    // The preimages of the existing state can only be recovered by executing the chain again
+    apply: |store| store.set_preimages_incomplete(),
+}];
 
 const STATE_TRIE_NODES_TABLE: TableDefinition<&[u8], &[u8]> =
     TableDefinition::new("StateTrieNodes");
@@ -36,6 +60,8 @@ const BLOCK_BODIES_TABLE: TableDefinition<BlockHashRLP, BlockBodyRLP> =
     TableDefinition::new("BlockBodies");
 const ACCOUNT_CODES_TABLE: TableDefinition<AccountCodeHashRLP, AccountCodeRLP> =
     TableDefinition::new("AccountCodes");
+const PREIMAGES_TABLE: TableDefinition<PreimageHashRLP, PreimageRLP> =
+    TableDefinition::new("Preimages");
 const RECEIPTS_TABLE: TableDefinition<TupleRLP<BlockHash, Index>, ReceiptRLP> =
     TableDefinition::new("Receipts");
 const CANONICAL_BLOCK_HASHES_TABLE: TableDefinition<BlockNumber, BlockHashRLP> =
@@ -44,15 +70,21 @@ pub const STORAGE_TRIE_NODES_TABLE: MultimapTableDefinition<([u8; 32], [u8; 33])
     MultimapTableDefinition::new("StorageTrieNodes");
 const CHAIN_DATA_TABLE: TableDefinition<ChainDataIndex, Vec<u8>> =
     TableDefinition::new("ChainData");
+const SNAP_STATE_TABLE: TableDefinition<SnapStateIndex, Vec<u8>> =
+    TableDefinition::new("SnapState");
 const PAYLOADS_TABLE: TableDefinition<BlockNumber, Rlp<(Block, U256, BlobsBundle, bool)>> =
     TableDefinition::new("Payloads");
 const PENDING_BLOCKS_TABLE: TableDefinition<BlockHashRLP, BlockRLP> =
     TableDefinition::new("PendingBlocks");
+const KNOWN_PEERS_TABLE: TableDefinition<&[u8], Vec<u8>> = TableDefinition::new("KnownPeers");
 const TRANSACTION_LOCATIONS_TABLE: MultimapTableDefinition<
     TransactionHashRLP,
     Rlp<(BlockNumber, BlockHash, Index)>,
 > = MultimapTableDefinition::new("TransactionLocations");
 
+/// Name of the database file within the data directory
+const DB_FILE_NAME: &str = "ethrex.redb";
+
 #[derive(Debug)]
 pub struct RedBStore {
     db: Arc<Database>,
@@ -60,9 +92,18 @@ pub struct RedBStore {
 
 impl RefUnwindSafe for RedBStore {}
 impl RedBStore {
-    pub fn new() -> Result<Self, StoreError> {
+    pub fn new(path: &str) -> Result<Self, StoreError> {
+        Ok(Self {
+            db: Arc::new(init_db(path)?),
+        })
+    }
+
+    /// Opens an existing database without creating its tables. redb only writes on open to
+    /// repair a database that wasn't closed cleanly. The file is locked, so it can't be opened
+    /// while the node is running
+    pub fn open_read_only(path: &str) -> Result<Self, StoreError> {
         Ok(Self {
-            db: Arc::new(init_db()?),
+            db: Arc::new(Database::open(Path::new(path).join(DB_FILE_NAME))?),
         })
     }
 
@@ -411,6 +452,12 @@ impl StoreEngine for RedBStore {
             .map(|b| b.value().to()))
     }
 
+    fn get_preimage(&self, hash: H256) -> Result<Option<bytes::Bytes>, StoreError> {
+        Ok(self
+            .read(PREIMAGES_TABLE, <H256 as Into<PreimageHashRLP>>::into(hash))?
+            .map(|b| b.value().to()))
+    }
+
     fn get_canonical_block_hash(
         &self,
         block_number: BlockNumber,
@@ -679,6 +726,325 @@ impl StoreEngine for RedBStore {
             .map(|receipt| receipt.to())
             .collect())
     }
+
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE_TABLE,
+            SnapStateIndex::HeaderDownloadCheckpoint,
+            block_hash.encode_to_vec(),
+        )
+    }
+
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        self.read(SNAP_STATE_TABLE, SnapStateIndex::HeaderDownloadCheckpoint)?
+            .map(|rlp| RLPDecode::decode(&rlp.value()))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn set_state_trie_key_checkpoint(&self, last_key: H256) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE_TABLE,
+            SnapStateIndex::StateTrieKeyCheckpoint,
+            last_key.encode_to_vec(),
+        )
+    }
+
+    fn get_state_trie_key_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        self.read(SNAP_STATE_TABLE, SnapStateIndex::StateTrieKeyCheckpoint)?
+            .map(|rlp| RLPDecode::decode(&rlp.value()))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn set_state_trie_root_checkpoint(&self, current_root: H256) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE_TABLE,
+            SnapStateIndex::StateTrieRootCheckpoint,
+            current_root.encode_to_vec(),
+        )
+    }
+
+    fn get_state_trie_root_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        self.read(SNAP_STATE_TABLE, SnapStateIndex::StateTrieRootCheckpoint)?
+            .map(|rlp| RLPDecode::decode(&rlp.value()))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn set_state_heal_paths(&self, paths: Vec<Nibbles>) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE_TABLE,
+            SnapStateIndex::StateHealPaths,
+            paths.encode_to_vec(),
+        )
+    }
+
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<Nibbles>>, StoreError> {
+        self.read(SNAP_STATE_TABLE, SnapStateIndex::StateHealPaths)?
+            .map(|rlp| RLPDecode::decode(&rlp.value()))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn set_storage_heal_paths(
+        &self,
+        accounts: Vec<(H256, Vec<Nibbles>)>,
+    ) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE_TABLE,
+            SnapStateIndex::StorageHealPaths,
+            accounts.encode_to_vec(),
+        )
+    }
+
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<(H256, Vec<Nibbles>)>>, StoreError> {
+        self.read(SNAP_STATE_TABLE, SnapStateIndex::StorageHealPaths)?
+            .map(|rlp| RLPDecode::decode(&rlp.value()))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    fn clear_snap_state(&self) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        // Drop the table and recreate it empty so that later reads don't fail
+        write_txn.delete_table(SNAP_STATE_TABLE)?;
+        write_txn.open_table(SNAP_STATE_TABLE)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn set_known_peers(&self, peers: Vec<(H512, Vec<u8>)>) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            // Drop the table and recreate it so that only the given peers are kept
+            write_txn.delete_table(KNOWN_PEERS_TABLE)?;
+            let mut table = write_txn.open_table(KNOWN_PEERS_TABLE)?;
+            for (node_id, peer) in peers {
+                table.insert(node_id.as_bytes(), peer)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_known_peers(&self) -> Result<Vec<Vec<u8>>, StoreError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(KNOWN_PEERS_TABLE)?;
+        table.iter()?.map(|entry| Ok(entry?.1.value())).collect()
+    }
+
+    fn set_schema_version(&self, version: u64) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA_TABLE,
+            ChainDataIndex::SchemaVersion,
+            version.encode_to_vec(),
+        )
+    }
+
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError> {
+        match self.read(CHAIN_DATA_TABLE, ChainDataIndex::SchemaVersion)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn set_preimages_incomplete(&self) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA_TABLE,
+            ChainDataIndex::PreimagesIncomplete,
+            true.encode_to_vec(),
+        )
+    }
+
+    fn are_preimages_incomplete(&self) -> Result<bool, StoreError> {
+        match self.read(CHAIN_DATA_TABLE, ChainDataIndex::PreimagesIncomplete)? {
+            None => Ok(false),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value()).map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn commit_batch(&self, batch: WriteBatch) -> Result<(), StoreError> {
+        // The transaction is aborted when dropped, discarding the whole batch if a write fails
+        let write_txn = self.db.begin_write()?;
+        for op in batch.ops {
+            match op {
+                BatchOp::BlockHeader(block_hash, block_header) => {
+                    write_txn.open_table(HEADERS_TABLE)?.insert(
+                        <H256 as Into<BlockHashRLP>>::into(block_hash),
+                        <BlockHeader as Into<BlockHeaderRLP>>::into(block_header),
+                    )?;
+                }
+                BatchOp::BlockBody(block_hash, block_body) => {
+                    write_txn.open_table(BLOCK_BODIES_TABLE)?.insert(
+                        <H256 as Into<BlockHashRLP>>::into(block_hash),
+                        <BlockBody as Into<BlockBodyRLP>>::into(block_body),
+                    )?;
+                }
+                BatchOp::BlockNumber(block_hash, block_number) => {
+                    write_txn
+                        .open_table(BLOCK_NUMBERS_TABLE)?
+                        .insert(<H256 as Into<BlockHashRLP>>::into(block_hash), block_number)?;
+                }
+                BatchOp::BlockTotalDifficulty(block_hash, block_total_difficulty) => {
+                    write_txn
+                        .open_table(BLOCK_TOTAL_DIFFICULTIES_TABLE)?
+                        .insert(
+                            <H256 as Into<BlockHashRLP>>::into(block_hash),
+                            <U256 as Into<Rlp<U256>>>::into(block_total_difficulty),
+                        )?;
+                }
+                BatchOp::LatestTotalDifficulty(latest_total_difficulty) => {
+                    write_txn.open_table(CHAIN_DATA_TABLE)?.insert(
+                        ChainDataIndex::LatestTotalDifficulty,
+                        latest_total_difficulty.encode_to_vec(),
+                    )?;
+                }
+                BatchOp::TransactionLocations(locations) => {
+                    let mut table = write_txn.open_multimap_table(TRANSACTION_LOCATIONS_TABLE)?;
+                    for (tx_hash, block_number, block_hash, index) in locations {
+                        table.insert(
+                            <H256 as Into<TransactionHashRLP>>::into(tx_hash),
+                            <(u64, H256, u64) as Into<Rlp<(BlockNumber, BlockHash, Index)>>>::into(
+                                (block_number, block_hash, index),
+                            ),
+                        )?;
+                    }
+                }
+                BatchOp::Receipts(block_hash, receipts) => {
+                    let mut table = write_txn.open_table(RECEIPTS_TABLE)?;
+                    for (index, receipt) in receipts.into_iter().enumerate() {
+                        table.insert(
+                            <(H256, u64) as Into<TupleRLP<BlockHash, Index>>>::into((
+                                block_hash,
+                                index as u64,
+                            )),
+                            <Receipt as Into<ReceiptRLP>>::into(receipt),
+                        )?;
+                    }
+                }
+                BatchOp::AccountCode(code_hash, code) => {
+                    write_txn.open_table(ACCOUNT_CODES_TABLE)?.insert(
+                        <H256 as Into<AccountCodeHashRLP>>::into(code_hash),
+                        <bytes::Bytes as Into<AccountCodeRLP>>::into(code),
+                    )?;
+                }
+                BatchOp::StateTrieNodes(nodes) => {
+                    let mut table = write_txn.open_table(TRIE_NODES_TABLE)?;
+                    for (node_hash, node) in nodes {
+                        table.insert(&*node_hash, &*node)?;
+                    }
+                }
+                BatchOp::StorageTrieNodes(hashed_address, nodes) => {
+                    let mut table = write_txn.open_multimap_table(STORAGE_TRIE_NODES_TABLE)?;
+                    for (node_hash, node) in nodes {
+                        table.insert(
+                            (hashed_address.0, node_hash_to_fixed_size(node_hash)),
+                            &*node,
+                        )?;
+                    }
+                }
+                BatchOp::Preimages(preimages) => {
+                    let mut table = write_txn.open_table(PREIMAGES_TABLE)?;
+                    for (hash, preimage) in preimages {
+                        table.insert(
+                            <H256 as Into<PreimageHashRLP>>::into(hash),
+                            <bytes::Bytes as Into<PreimageRLP>>::into(preimage),
+                        )?;
+                    }
+                }
+            }
+        }
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
+    fn backup(&self, path: &Path) -> Result<(), StoreError> {
+        let backup = init_db(path)?;
+        // The read transaction sees the database as it was when it started, while the node keeps
+        // committing blocks
+        let read_txn = self.db.begin_read()?;
+        let write_txn = backup.begin_write()?;
+        copy_table(&read_txn, &write_txn, STATE_TRIE_NODES_TABLE)?;
+        copy_table(&read_txn, &write_txn, TRIE_NODES_TABLE)?;
+        copy_table(&read_txn, &write_txn, BLOCK_NUMBERS_TABLE)?;
+        copy_table(&read_txn, &write_txn, BLOCK_TOTAL_DIFFICULTIES_TABLE)?;
+        copy_table(&read_txn, &write_txn, HEADERS_TABLE)?;
+        copy_table(&read_txn, &write_txn, BLOCK_BODIES_TABLE)?;
+        copy_table(&read_txn, &write_txn, ACCOUNT_CODES_TABLE)?;
+        copy_table(&read_txn, &write_txn, PREIMAGES_TABLE)?;
+        copy_table(&read_txn, &write_txn, RECEIPTS_TABLE)?;
+        copy_table(&read_txn, &write_txn, CANONICAL_BLOCK_HASHES_TABLE)?;
+        copy_table(&read_txn, &write_txn, CHAIN_DATA_TABLE)?;
+        copy_table(&read_txn, &write_txn, SNAP_STATE_TABLE)?;
+        copy_table(&read_txn, &write_txn, PAYLOADS_TABLE)?;
+        copy_table(&read_txn, &write_txn, PENDING_BLOCKS_TABLE)?;
+        copy_table(&read_txn, &write_txn, KNOWN_PEERS_TABLE)?;
+        copy_multimap_table(&read_txn, &write_txn, STORAGE_TRIE_NODES_TABLE)?;
+        copy_multimap_table(&read_txn, &write_txn, TRANSACTION_LOCATIONS_TABLE)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        let read_txn = self.db.begin_read()?;
+        let mut stats = vec![];
+        for table in read_txn.list_tables()? {
+            let name = redb::TableHandle::name(&table).to_string();
+            let table = read_txn.open_untyped_table(table)?;
+            stats.push(table_stats(name, &table)?);
+        }
+        for table in read_txn.list_multimap_tables()? {
+            let name = redb::MultimapTableHandle::name(&table).to_string();
+            let table = read_txn.open_untyped_multimap_table(table)?;
+            stats.push(table_stats(name, &table)?);
+        }
+        Ok(stats)
+    }
+}
+
+// Helper function to copy every entry of a table from one database to another
+fn copy_table<K: Key + 'static, V: Value + 'static>(
+    read_txn: &ReadTransaction,
+    write_txn: &WriteTransaction,
+    table: TableDefinition<K, V>,
+) -> Result<(), StoreError> {
+    let source = read_txn.open_table(table)?;
+    let mut destination = write_txn.open_table(table)?;
+    for entry in source.iter()? {
+        let (key, value) = entry?;
+        destination.insert(key.value(), value.value())?;
+    }
+    Ok(())
+}
+
+// Helper function to copy every entry of a multimap table from one database to another
+fn copy_multimap_table<K: Key + 'static, V: Key + 'static>(
+    read_txn: &ReadTransaction,
+    write_txn: &WriteTransaction,
+    table: MultimapTableDefinition<K, V>,
+) -> Result<(), StoreError> {
+    let source = read_txn.open_multimap_table(table)?;
+    let mut destination = write_txn.open_multimap_table(table)?;
+    for entry in source.iter()? {
+        let (key, values) = entry?;
+        for value in values {
+            destination.insert(key.value(), value?.value())?;
+        }
+    }
+    Ok(())
+}
+
+fn table_stats(name: String, table: &impl ReadableTableMetadata) -> Result<TableStats, StoreError> {
+    let stats = table.stats()?;
+    Ok(TableStats {
+        name,
+        entries: table.len()?,
+        size: Some(stats.stored_bytes() + stats.metadata_bytes() + stats.fragmented_bytes()),
+    })
 }
 
 impl redb::Value for ChainDataIndex {
@@ -722,20 +1088,69 @@ impl redb::Key for ChainDataIndex {
     }
 }
 
-pub fn init_db() -> Result<Database, StoreError> {
-    let db = Database::create("ethrex.redb")?;
+impl redb::Value for SnapStateIndex {
+    type SelfType<'a>
+        = SnapStateIndex
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = [u8; 1]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        data[0].into()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'a,
+        Self: 'b,
+    {
+        [*value as u8]
+    }
+
+    fn type_name() -> redb::TypeName {
+        TypeName::new("SnapStateIndex")
+    }
+}
+
+impl redb::Key for SnapStateIndex {
+    fn compare(data1: &[u8], data2: &[u8]) -> std::cmp::Ordering {
+        data1.cmp(data2)
+    }
+}
+
+/// Opens the database in the given directory, creating it if missing
+pub fn init_db(path: impl AsRef<Path>) -> Result<Database, StoreError> {
+    std::fs::create_dir_all(&path).map_err(|error| StoreError::Custom(error.to_string()))?;
+    let db = Database::create(path.as_ref().join(DB_FILE_NAME))?;
 
     let table_creation_txn = db.begin_write()?;
     table_creation_txn.open_table(STATE_TRIE_NODES_TABLE)?;
+    table_creation_txn.open_table(TRIE_NODES_TABLE)?;
+    table_creation_txn.open_table(HEADERS_TABLE)?;
+    table_creation_txn.open_table(ACCOUNT_CODES_TABLE)?;
+    table_creation_txn.open_table(PREIMAGES_TABLE)?;
     table_creation_txn.open_table(BLOCK_NUMBERS_TABLE)?;
     table_creation_txn.open_table(BLOCK_TOTAL_DIFFICULTIES_TABLE)?;
     table_creation_txn.open_table(CANONICAL_BLOCK_HASHES_TABLE)?;
     table_creation_txn.open_table(RECEIPTS_TABLE)?;
     table_creation_txn.open_multimap_table(STORAGE_TRIE_NODES_TABLE)?;
     table_creation_txn.open_table(CHAIN_DATA_TABLE)?;
+    table_creation_txn.open_table(SNAP_STATE_TABLE)?;
     table_creation_txn.open_table(BLOCK_BODIES_TABLE)?;
     table_creation_txn.open_table(PAYLOADS_TABLE)?;
     table_creation_txn.open_table(PENDING_BLOCKS_TABLE)?;
+    table_creation_txn.open_table(KNOWN_PEERS_TABLE)?;
     table_creation_txn.open_multimap_table(TRANSACTION_LOCATIONS_TABLE)?;
     table_creation_txn.commit()?;
 
@@ -0,0 +1,833 @@
+use std::{panic::RefUnwindSafe, path::Path, sync::Arc};
+
+use bytes::Bytes;
+use ethereum_types::{H256, H512, U256};
+use ethrex_core::types::{
+    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index, Receipt,
+};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+use ethrex_trie::{
+    db::rocksdb::{fixed_key_node_hash, RocksDBTrieDB},
+    Nibbles, Trie,
+};
+use rocksdb::{
+    checkpoint::Checkpoint, properties, BlockBasedOptions, Cache, ColumnFamily,
+    ColumnFamilyDescriptor, DBCompressionType, DataBlockIndexType, Direction, IteratorMode,
+    Options, DB,
+};
+
+use crate::batch::{BatchOp, WriteBatch};
+use crate::error::StoreError;
+use crate::schema::Migration;
+use crate::TableStats;
+
+use super::{
+    api::StoreEngine,
+    utils::{ChainDataIndex, SnapStateIndex},
+};
+
+// Changing any of these column families or the encoding of their keys requires bumping the schema
+// version and adding a migration to `MIGRATIONS`
+
+/// Migrations of the column families to each schema version, see [crate::schema]
+pub(crate) const MIGRATIONS: &[Migration<RocksDBStore>] = &[Migration {
+    from: 1,
+    description: "Add the preimages column family, which lacks the preimages of the existing state",
+    // The preimages of the existing state can only be recovered by executing the chain again
+    apply: |store| store.set_preimages_incomplete(),
+}];
+
+/// Block number => block hash
+const CANONICAL_BLOCK_HASHES: &str = "CanonicalBlockHashes";
+/// Block hash => block number
+const BLOCK_NUMBERS: &str = "BlockNumbers";
+// TODO (#307): Remove TotalDifficulty.
+/// Block hash => total difficulty
+const BLOCK_TOTAL_DIFFICULTIES: &str = "BlockTotalDifficulties";
+/// Block hash => block header
+const HEADERS: &str = "Headers";
+/// Block hash => block body
+const BODIES: &str = "Bodies";
+/// Code hash => account code
+const ACCOUNT_CODES: &str = "AccountCodes";
+/// Hashed address or storage key => address or storage key
+const PREIMAGES: &str = "Preimages";
+/// (Block hash, index) => receipt
+const RECEIPTS: &str = "Receipts";
+/// (Transaction hash, block hash) => (block number, block hash, index)
+/// A transaction can be included in more than one block if it was reorged
+const TRANSACTION_LOCATIONS: &str = "TransactionLocations";
+/// [ChainDataIndex] => chain data
+const CHAIN_DATA: &str = "ChainData";
+/// [SnapStateIndex] => snap sync state
+const SNAP_STATE: &str = "SnapState";
+/// Node hash => state trie node
+const STATE_TRIE_NODES: &str = "StateTrieNodes";
+/// (Hashed address, node hash) => storage trie node
+const STORAGE_TRIES_NODES: &str = "StorageTriesNodes";
+/// Payload id => (block, block value, blobs bundle, completed)
+const PAYLOADS: &str = "Payloads";
+/// Block hash => block
+const PENDING_BLOCKS: &str = "PendingBlocks";
+/// Node id => encoded peer
+const KNOWN_PEERS: &str = "KnownPeers";
+
+pub(crate) const COLUMN_FAMILIES: [&str; 16] = [
+    CANONICAL_BLOCK_HASHES,
+    BLOCK_NUMBERS,
+    BLOCK_TOTAL_DIFFICULTIES,
+    HEADERS,
+    BODIES,
+    ACCOUNT_CODES,
+    PREIMAGES,
+    RECEIPTS,
+    TRANSACTION_LOCATIONS,
+    CHAIN_DATA,
+    SNAP_STATE,
+    STATE_TRIE_NODES,
+    STORAGE_TRIES_NODES,
+    PAYLOADS,
+    PENDING_BLOCKS,
+    KNOWN_PEERS,
+];
+
+/// Size of the block cache shared by all column families
+const BLOCK_CACHE_SIZE: usize = 512 * 1024 * 1024;
+/// Size of the memtables of the trie column families, which receive most of the writes
+const TRIE_WRITE_BUFFER_SIZE: usize = 128 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct RocksDBStore {
+    db: Arc<DB>,
+}
+
+impl RefUnwindSafe for RocksDBStore {}
+impl RocksDBStore {
+    pub fn new(path: &str) -> Result<Self, StoreError> {
+        Ok(Self {
+            db: Arc::new(init_db(path)?),
+        })
+    }
+
+    /// Opens an existing database in read-only mode, which can be done while the node is running.
+    /// Only its existing column families are opened, as older databases may lack some
+    pub fn open_read_only(path: &str) -> Result<Self, StoreError> {
+        let options = Options::default();
+        let existing = DB::list_cf(&options, path)?;
+        let cache = Cache::new_lru_cache(BLOCK_CACHE_SIZE);
+        let column_families = column_families(&cache)
+            .filter(|descriptor| existing.iter().any(|name| name == descriptor.name()));
+        Ok(Self {
+            db: Arc::new(DB::open_cf_descriptors_read_only(
+                &options,
+                path,
+                column_families,
+                false,
+            )?),
+        })
+    }
+
+    fn cf(&self, name: &str) -> Result<&ColumnFamily, StoreError> {
+        self.db
+            .cf_handle(name)
+            .ok_or_else(|| StoreError::RocksDBMissingColumnFamily(name.to_string()))
+    }
+
+    // Helper method to write into a column family
+    fn write(
+        &self,
+        cf: &str,
+        key: impl AsRef<[u8]>,
+        value: impl AsRef<[u8]>,
+    ) -> Result<(), StoreError> {
+        Ok(self.db.put_cf(self.cf(cf)?, key, value)?)
+    }
+
+    // Helper method to read from a column family
+    fn read(&self, cf: &str, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(self.db.get_cf(self.cf(cf)?, key)?)
+    }
+
+    // Helper method to read and decode an RLP encoded value from a column family
+    fn read_rlp<T: RLPDecode>(
+        &self,
+        cf: &str,
+        key: impl AsRef<[u8]>,
+    ) -> Result<Option<T>, StoreError> {
+        self.read(cf, key)?
+            .map(|value| T::decode(&value))
+            .transpose()
+            .map_err(StoreError::RLPDecode)
+    }
+
+    // Helper method to read every value of a column family whose key starts with `prefix`,
+    // in key order
+    fn read_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<Box<[u8]>>, StoreError> {
+        let mut values = vec![];
+        for entry in self
+            .db
+            .iterator_cf(self.cf(cf)?, IteratorMode::From(prefix, Direction::Forward))
+        {
+            let (key, value) = entry?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    // Helper method to add the removal of every key of a column family to a batch
+    fn clear_cf(&self, batch: &mut rocksdb::WriteBatch, cf: &str) -> Result<(), StoreError> {
+        let cf = self.cf(cf)?;
+        for entry in self.db.iterator_cf(cf, IteratorMode::Start) {
+            let (key, _) = entry?;
+            batch.delete_cf(cf, key);
+        }
+        Ok(())
+    }
+
+    fn get_block_hash_by_block_number(
+        &self,
+        number: BlockNumber,
+    ) -> Result<Option<BlockHash>, StoreError> {
+        self.read_rlp(CANONICAL_BLOCK_HASHES, number.to_be_bytes())
+    }
+}
+
+/// Key of a receipt, so that the receipts of a block are stored together and in order
+fn receipt_key(block_hash: BlockHash, index: Index) -> Vec<u8> {
+    [block_hash.as_bytes(), &index.to_be_bytes()].concat()
+}
+
+/// Key of the location of a transaction in a block
+fn transaction_location_key(transaction_hash: H256, block_hash: BlockHash) -> Vec<u8> {
+    [transaction_hash.as_bytes(), block_hash.as_bytes()].concat()
+}
+
+impl StoreEngine for RocksDBStore {
+    fn add_block_header(
+        &self,
+        block_hash: BlockHash,
+        block_header: BlockHeader,
+    ) -> Result<(), StoreError> {
+        self.write(HEADERS, block_hash, block_header.encode_to_vec())
+    }
+
+    fn get_block_header(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<BlockHeader>, StoreError> {
+        match self.get_block_hash_by_block_number(block_number)? {
+            Some(hash) => self.get_block_header_by_hash(hash),
+            None => Ok(None),
+        }
+    }
+
+    fn add_block_body(
+        &self,
+        block_hash: BlockHash,
+        block_body: BlockBody,
+    ) -> Result<(), StoreError> {
+        self.write(BODIES, block_hash, block_body.encode_to_vec())
+    }
+
+    fn get_block_body(&self, block_number: BlockNumber) -> Result<Option<BlockBody>, StoreError> {
+        match self.get_block_hash_by_block_number(block_number)? {
+            Some(hash) => self.get_block_body_by_hash(hash),
+            None => Ok(None),
+        }
+    }
+
+    fn get_block_body_by_hash(
+        &self,
+        block_hash: BlockHash,
+    ) -> Result<Option<BlockBody>, StoreError> {
+        self.read_rlp(BODIES, block_hash)
+    }
+
+    fn get_block_header_by_hash(
+        &self,
+        block_hash: BlockHash,
+    ) -> Result<Option<BlockHeader>, StoreError> {
+        self.read_rlp(HEADERS, block_hash)
+    }
+
+    fn add_pending_block(&self, block: Block) -> Result<(), StoreError> {
+        self.write(
+            PENDING_BLOCKS,
+            block.header.compute_block_hash(),
+            block.encode_to_vec(),
+        )
+    }
+
+    fn get_pending_block(&self, block_hash: BlockHash) -> Result<Option<Block>, StoreError> {
+        self.read_rlp(PENDING_BLOCKS, block_hash)
+    }
+
+    fn add_block_number(
+        &self,
+        block_hash: BlockHash,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.write(BLOCK_NUMBERS, block_hash, block_number.encode_to_vec())
+    }
+
+    fn get_block_number(&self, block_hash: BlockHash) -> Result<Option<BlockNumber>, StoreError> {
+        self.read_rlp(BLOCK_NUMBERS, block_hash)
+    }
+
+    fn add_block_total_difficulty(
+        &self,
+        block_hash: BlockHash,
+        block_total_difficulty: U256,
+    ) -> Result<(), StoreError> {
+        self.write(
+            BLOCK_TOTAL_DIFFICULTIES,
+            block_hash,
+            block_total_difficulty.encode_to_vec(),
+        )
+    }
+
+    fn get_block_total_difficulty(
+        &self,
+        block_hash: BlockHash,
+    ) -> Result<Option<U256>, StoreError> {
+        self.read_rlp(BLOCK_TOTAL_DIFFICULTIES, block_hash)
+    }
+
+    fn add_transaction_location(
+        &self,
+        transaction_hash: H256,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        index: Index,
+    ) -> Result<(), StoreError> {
+        self.write(
+            TRANSACTION_LOCATIONS,
+            transaction_location_key(transaction_hash, block_hash),
+            (block_number, block_hash, index).encode_to_vec(),
+        )
+    }
+
+    fn add_transaction_locations(
+        &self,
+        locations: Vec<(H256, BlockNumber, BlockHash, Index)>,
+    ) -> Result<(), StoreError> {
+        let mut batch = WriteBatch::new();
+        batch.add_transaction_locations(locations);
+        self.commit_batch(batch)
+    }
+
+    fn get_transaction_location(
+        &self,
+        transaction_hash: H256,
+    ) -> Result<Option<(BlockNumber, BlockHash, Index)>, StoreError> {
+        // Only the location in the canonical chain is returned
+        for location in self.read_prefix(TRANSACTION_LOCATIONS, transaction_hash.as_bytes())? {
+            let (number, hash, index) = <(BlockNumber, BlockHash, Index)>::decode(&location)?;
+            if self.get_block_hash_by_block_number(number)? == Some(hash) {
+                return Ok(Some((number, hash, index)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn add_receipt(
+        &self,
+        block_hash: BlockHash,
+        index: Index,
+        receipt: Receipt,
+    ) -> Result<(), StoreError> {
+        self.write(
+            RECEIPTS,
+            receipt_key(block_hash, index),
+            receipt.encode_to_vec(),
+        )
+    }
+
+    fn add_receipts(
+        &self,
+        block_hash: BlockHash,
+        receipts: Vec<Receipt>,
+    ) -> Result<(), StoreError> {
+        let mut batch = WriteBatch::new();
+        batch.add_receipts(block_hash, receipts);
+        self.commit_batch(batch)
+    }
+
+    fn get_receipt(
+        &self,
+        block_number: BlockNumber,
+        index: Index,
+    ) -> Result<Option<Receipt>, StoreError> {
+        match self.get_block_hash_by_block_number(block_number)? {
+            Some(hash) => self.read_rlp(RECEIPTS, receipt_key(hash, index)),
+            None => Ok(None),
+        }
+    }
+
+    fn add_account_code(&self, code_hash: H256, code: Bytes) -> Result<(), StoreError> {
+        self.write(ACCOUNT_CODES, code_hash, code.encode_to_vec())
+    }
+
+    fn get_account_code(&self, code_hash: H256) -> Result<Option<Bytes>, StoreError> {
+        self.read_rlp(ACCOUNT_CODES, code_hash)
+    }
+
+    fn get_preimage(&self, hash: H256) -> Result<Option<Bytes>, StoreError> {
+        self.read_rlp(PREIMAGES, hash)
+    }
+
+    fn get_canonical_block_hash(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<BlockHash>, StoreError> {
+        self.get_block_hash_by_block_number(block_number)
+    }
+
+    fn set_chain_config(&self, chain_config: &ChainConfig) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::ChainConfig as u8],
+            serde_json::to_string(chain_config)
+                .map_err(|_| StoreError::DecodeError)?
+                .into_bytes(),
+        )
+    }
+
+    fn get_chain_config(&self) -> Result<ChainConfig, StoreError> {
+        match self.read(CHAIN_DATA, [ChainDataIndex::ChainConfig as u8])? {
+            None => Err(StoreError::Custom("Chain config not found".to_string())),
+            Some(bytes) => {
+                let json = String::from_utf8(bytes).map_err(|_| StoreError::DecodeError)?;
+                let chain_config: ChainConfig =
+                    serde_json::from_str(&json).map_err(|_| StoreError::DecodeError)?;
+                Ok(chain_config)
+            }
+        }
+    }
+
+    fn update_earliest_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::EarliestBlockNumber as u8],
+            block_number.encode_to_vec(),
+        )
+    }
+
+    fn get_earliest_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.read_rlp(CHAIN_DATA, [ChainDataIndex::EarliestBlockNumber as u8])
+    }
+
+    fn update_finalized_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::FinalizedBlockNumber as u8],
+            block_number.encode_to_vec(),
+        )
+    }
+
+    fn get_finalized_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.read_rlp(CHAIN_DATA, [ChainDataIndex::FinalizedBlockNumber as u8])
+    }
+
+    fn update_safe_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::SafeBlockNumber as u8],
+            block_number.encode_to_vec(),
+        )
+    }
+
+    fn get_safe_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.read_rlp(CHAIN_DATA, [ChainDataIndex::SafeBlockNumber as u8])
+    }
+
+    fn update_latest_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::LatestBlockNumber as u8],
+            block_number.encode_to_vec(),
+        )
+    }
+
+    fn get_latest_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.read_rlp(CHAIN_DATA, [ChainDataIndex::LatestBlockNumber as u8])
+    }
+
+    fn update_latest_total_difficulty(
+        &self,
+        latest_total_difficulty: U256,
+    ) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::LatestTotalDifficulty as u8],
+            latest_total_difficulty.encode_to_vec(),
+        )
+    }
+
+    fn get_latest_total_difficulty(&self) -> Result<Option<U256>, StoreError> {
+        self.read_rlp(CHAIN_DATA, [ChainDataIndex::LatestTotalDifficulty as u8])
+    }
+
+    fn update_pending_block_number(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::PendingBlockNumber as u8],
+            block_number.encode_to_vec(),
+        )
+    }
+
+    fn get_pending_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.read_rlp(CHAIN_DATA, [ChainDataIndex::PendingBlockNumber as u8])
+    }
+
+    fn open_storage_trie(&self, hashed_address: H256, storage_root: H256) -> Trie {
+        let db = Box::new(RocksDBTrieDB::new_with_fixed_key(
+            self.db.clone(),
+            STORAGE_TRIES_NODES,
+            hashed_address.0,
+        ));
+        Trie::open(db, storage_root)
+    }
+
+    fn open_state_trie(&self, state_root: H256) -> Trie {
+        let db = Box::new(RocksDBTrieDB::new(self.db.clone(), STATE_TRIE_NODES));
+        Trie::open(db, state_root)
+    }
+
+    fn set_canonical_block(&self, number: BlockNumber, hash: BlockHash) -> Result<(), StoreError> {
+        self.write(
+            CANONICAL_BLOCK_HASHES,
+            number.to_be_bytes(),
+            hash.encode_to_vec(),
+        )
+    }
+
+    fn unset_canonical_block(&self, number: BlockNumber) -> Result<(), StoreError> {
+        Ok(self
+            .db
+            .delete_cf(self.cf(CANONICAL_BLOCK_HASHES)?, number.to_be_bytes())?)
+    }
+
+    fn add_payload(&self, payload_id: u64, block: Block) -> Result<(), StoreError> {
+        self.write(
+            PAYLOADS,
+            payload_id.to_be_bytes(),
+            (block, U256::zero(), BlobsBundle::empty(), false).encode_to_vec(),
+        )
+    }
+
+    fn get_payload(
+        &self,
+        payload_id: u64,
+    ) -> Result<Option<(Block, U256, BlobsBundle, bool)>, StoreError> {
+        self.read_rlp(PAYLOADS, payload_id.to_be_bytes())
+    }
+
+    fn update_payload(
+        &self,
+        payload_id: u64,
+        block: Block,
+        block_value: U256,
+        blobs_bundle: BlobsBundle,
+        completed: bool,
+    ) -> Result<(), StoreError> {
+        self.write(
+            PAYLOADS,
+            payload_id.to_be_bytes(),
+            (block, block_value, blobs_bundle, completed).encode_to_vec(),
+        )
+    }
+
+    fn get_receipts_for_block(&self, block_hash: &BlockHash) -> Result<Vec<Receipt>, StoreError> {
+        // Receipt keys are prefixed by the block hash and sorted by index
+        self.read_prefix(RECEIPTS, block_hash.as_bytes())?
+            .iter()
+            .map(|receipt| Ok(Receipt::decode(receipt)?))
+            .collect()
+    }
+
+    fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE,
+            [SnapStateIndex::HeaderDownloadCheckpoint as u8],
+            block_hash.encode_to_vec(),
+        )
+    }
+
+    fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        self.read_rlp(SNAP_STATE, [SnapStateIndex::HeaderDownloadCheckpoint as u8])
+    }
+
+    fn set_state_trie_key_checkpoint(&self, last_key: H256) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE,
+            [SnapStateIndex::StateTrieKeyCheckpoint as u8],
+            last_key.encode_to_vec(),
+        )
+    }
+
+    fn get_state_trie_key_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        self.read_rlp(SNAP_STATE, [SnapStateIndex::StateTrieKeyCheckpoint as u8])
+    }
+
+    fn set_state_trie_root_checkpoint(&self, current_root: H256) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE,
+            [SnapStateIndex::StateTrieRootCheckpoint as u8],
+            current_root.encode_to_vec(),
+        )
+    }
+
+    fn get_state_trie_root_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        self.read_rlp(SNAP_STATE, [SnapStateIndex::StateTrieRootCheckpoint as u8])
+    }
+
+    fn set_state_heal_paths(&self, paths: Vec<Nibbles>) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE,
+            [SnapStateIndex::StateHealPaths as u8],
+            paths.encode_to_vec(),
+        )
+    }
+
+    fn get_state_heal_paths(&self) -> Result<Option<Vec<Nibbles>>, StoreError> {
+        self.read_rlp(SNAP_STATE, [SnapStateIndex::StateHealPaths as u8])
+    }
+
+    fn set_storage_heal_paths(
+        &self,
+        accounts: Vec<(H256, Vec<Nibbles>)>,
+    ) -> Result<(), StoreError> {
+        self.write(
+            SNAP_STATE,
+            [SnapStateIndex::StorageHealPaths as u8],
+            accounts.encode_to_vec(),
+        )
+    }
+
+    fn get_storage_heal_paths(&self) -> Result<Option<Vec<(H256, Vec<Nibbles>)>>, StoreError> {
+        self.read_rlp(SNAP_STATE, [SnapStateIndex::StorageHealPaths as u8])
+    }
+
+    fn clear_snap_state(&self) -> Result<(), StoreError> {
+        let mut batch = rocksdb::WriteBatch::default();
+        self.clear_cf(&mut batch, SNAP_STATE)?;
+        Ok(self.db.write(batch)?)
+    }
+
+    fn set_known_peers(&self, peers: Vec<(H512, Vec<u8>)>) -> Result<(), StoreError> {
+        // Only the given peers are kept
+        let mut batch = rocksdb::WriteBatch::default();
+        self.clear_cf(&mut batch, KNOWN_PEERS)?;
+        let cf = self.cf(KNOWN_PEERS)?;
+        for (node_id, peer) in peers {
+            batch.put_cf(cf, node_id, peer);
+        }
+        Ok(self.db.write(batch)?)
+    }
+
+    fn get_known_peers(&self) -> Result<Vec<Vec<u8>>, StoreError> {
+        self.db
+            .iterator_cf(self.cf(KNOWN_PEERS)?, IteratorMode::Start)
+            .map(|entry| Ok(entry?.1.into_vec()))
+            .collect()
+    }
+
+    fn set_schema_version(&self, version: u64) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::SchemaVersion as u8],
+            version.encode_to_vec(),
+        )
+    }
+
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError> {
+        self.read_rlp(CHAIN_DATA, [ChainDataIndex::SchemaVersion as u8])
+    }
+
+    fn set_preimages_incomplete(&self) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA,
+            [ChainDataIndex::PreimagesIncomplete as u8],
+            true.encode_to_vec(),
+        )
+    }
+
+    fn are_preimages_incomplete(&self) -> Result<bool, StoreError> {
+        Ok(self
+            .read_rlp(CHAIN_DATA, [ChainDataIndex::PreimagesIncomplete as u8])?
+            .unwrap_or(false))
+    }
+
+    fn commit_batch(&self, batch: WriteBatch) -> Result<(), StoreError> {
+        // RocksDB applies a write batch atomically
+        let mut write_batch = rocksdb::WriteBatch::default();
+        for op in batch.ops {
+            match op {
+                BatchOp::BlockHeader(block_hash, block_header) => {
+                    write_batch.put_cf(self.cf(HEADERS)?, block_hash, block_header.encode_to_vec())
+                }
+                BatchOp::BlockBody(block_hash, block_body) => {
+                    write_batch.put_cf(self.cf(BODIES)?, block_hash, block_body.encode_to_vec())
+                }
+                BatchOp::BlockNumber(block_hash, block_number) => write_batch.put_cf(
+                    self.cf(BLOCK_NUMBERS)?,
+                    block_hash,
+                    block_number.encode_to_vec(),
+                ),
+                BatchOp::BlockTotalDifficulty(block_hash, block_total_difficulty) => write_batch
+                    .put_cf(
+                        self.cf(BLOCK_TOTAL_DIFFICULTIES)?,
+                        block_hash,
+                        block_total_difficulty.encode_to_vec(),
+                    ),
+                BatchOp::LatestTotalDifficulty(latest_total_difficulty) => write_batch.put_cf(
+                    self.cf(CHAIN_DATA)?,
+                    [ChainDataIndex::LatestTotalDifficulty as u8],
+                    latest_total_difficulty.encode_to_vec(),
+                ),
+                BatchOp::TransactionLocations(locations) => {
+                    let cf = self.cf(TRANSACTION_LOCATIONS)?;
+                    for (tx_hash, block_number, block_hash, index) in locations {
+                        write_batch.put_cf(
+                            cf,
+                            transaction_location_key(tx_hash, block_hash),
+                            (block_number, block_hash, index).encode_to_vec(),
+                        );
+                    }
+                }
+                BatchOp::Receipts(block_hash, receipts) => {
+                    let cf = self.cf(RECEIPTS)?;
+                    for (index, receipt) in receipts.into_iter().enumerate() {
+                        write_batch.put_cf(
+                            cf,
+                            receipt_key(block_hash, index as u64),
+                            receipt.encode_to_vec(),
+                        );
+                    }
+                }
+                BatchOp::AccountCode(code_hash, code) => {
+                    write_batch.put_cf(self.cf(ACCOUNT_CODES)?, code_hash, code.encode_to_vec())
+                }
+                BatchOp::StateTrieNodes(nodes) => {
+                    let cf = self.cf(STATE_TRIE_NODES)?;
+                    for (node_hash, node) in nodes {
+                        write_batch.put_cf(cf, node_hash, node);
+                    }
+                }
+                BatchOp::StorageTrieNodes(hashed_address, nodes) => {
+                    let cf = self.cf(STORAGE_TRIES_NODES)?;
+                    for (node_hash, node) in nodes {
+                        write_batch.put_cf(
+                            cf,
+                            fixed_key_node_hash(&hashed_address.0, &node_hash),
+                            node,
+                        );
+                    }
+                }
+                BatchOp::Preimages(preimages) => {
+                    let cf = self.cf(PREIMAGES)?;
+                    for (hash, preimage) in preimages {
+                        write_batch.put_cf(cf, hash, preimage.encode_to_vec());
+                    }
+                }
+            }
+        }
+        Ok(self.db.write(write_batch)?)
+    }
+
+    fn backup(&self, path: &Path) -> Result<(), StoreError> {
+        // The checkpoint hard links the immutable SST files as of its creation, so the node can
+        // keep writing. RocksDB creates the directory itself and refuses an existing one
+        if path.exists() {
+            std::fs::remove_dir(path).map_err(|error| StoreError::Custom(error.to_string()))?;
+        }
+        Ok(Checkpoint::new(&*self.db)?.create_checkpoint(path)?)
+    }
+
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        COLUMN_FAMILIES
+            .iter()
+            .map(|name| {
+                let cf = self.cf(name)?;
+                Ok(TableStats {
+                    name: name.to_string(),
+                    entries: self
+                        .db
+                        .property_int_value_cf(cf, properties::ESTIMATE_NUM_KEYS)?
+                        .unwrap_or_default(),
+                    size: self
+                        .db
+                        .property_int_value_cf(cf, properties::TOTAL_SST_FILES_SIZE)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Options of the column families holding trie nodes.
+/// Nodes are read one at a time by hash, which is random and can't be compressed
+fn trie_nodes_options(cache: &Cache) -> Options {
+    let mut block_options = BlockBasedOptions::default();
+    block_options.set_block_cache(cache);
+    // Most lookups are for nodes stored in a single SST file, the filters avoid reading the rest
+    block_options.set_bloom_filter(10.0, false);
+    block_options.set_cache_index_and_filter_blocks(true);
+    block_options.set_pin_l0_filter_and_index_blocks_in_cache(true);
+    // Finds the node within a data block without a binary search
+    block_options.set_data_block_index_type(DataBlockIndexType::BinaryAndHash);
+    block_options.set_data_block_hash_ratio(0.75);
+
+    let mut options = Options::default();
+    options.set_block_based_table_factory(&block_options);
+    options.set_compression_type(DBCompressionType::None);
+    options.set_write_buffer_size(TRIE_WRITE_BUFFER_SIZE);
+    // Lets lookups of nodes that were just written skip the memtables not holding them
+    options.set_memtable_whole_key_filtering(true);
+    options.set_memtable_prefix_bloom_ratio(0.1);
+    options
+}
+
+/// Options of the column families holding chain data, mostly read by key or key prefix
+fn chain_data_options(cache: &Cache) -> Options {
+    let mut block_options = BlockBasedOptions::default();
+    block_options.set_block_cache(cache);
+    let mut options = Options::default();
+    options.set_block_based_table_factory(&block_options);
+    options.set_compression_type(DBCompressionType::Lz4);
+    options
+}
+
+/// Opens the database at the given path, creating it and its column families if missing
+pub fn init_db(path: &str) -> Result<DB, StoreError> {
+    let mut options = Options::default();
+    options.create_if_missing(true);
+    options.create_missing_column_families(true);
+    options.increase_parallelism(
+        std::thread::available_parallelism()
+            .map(|parallelism| parallelism.get() as i32)
+            .unwrap_or(2),
+    );
+    options.set_level_compaction_dynamic_level_bytes(true);
+
+    let cache = Cache::new_lru_cache(BLOCK_CACHE_SIZE);
+    Ok(DB::open_cf_descriptors(
+        &options,
+        path,
+        column_families(&cache),
+    )?)
+}
+
+/// Descriptors of every column family, with the options for their contents
+fn column_families(cache: &Cache) -> impl Iterator<Item = ColumnFamilyDescriptor> + '_ {
+    COLUMN_FAMILIES.into_iter().map(|name| {
+        let options = match name {
+            STATE_TRIE_NODES | STORAGE_TRIES_NODES => trie_nodes_options(cache),
+            _ => chain_data_options(cache),
+        };
+        ColumnFamilyDescriptor::new(name, options)
+    })
+}
@@ -10,6 +10,8 @@ pub enum ChainDataIndex {
     PendingBlockNumber = 5,
     // TODO (#307): Remove TotalDifficulty.
     LatestTotalDifficulty = 6,
+    SchemaVersion = 7,
+    PreimagesIncomplete = 8,
 }
 
 impl From<u8> for ChainDataIndex {
@@ -30,7 +32,46 @@ impl From<u8> for ChainDataIndex {
             x if x == ChainDataIndex::LatestTotalDifficulty as u8 => {
                 ChainDataIndex::LatestTotalDifficulty
             }
+            x if x == ChainDataIndex::SchemaVersion as u8 => ChainDataIndex::SchemaVersion,
+            x if x == ChainDataIndex::PreimagesIncomplete as u8 => {
+                ChainDataIndex::PreimagesIncomplete
+            }
             _ => panic!("Invalid value when casting to ChainDataIndex: {}", value),
         }
     }
 }
+
+/// Represents the key for each unique value of the snap state stored in the db
+// Stores the snap state from previous sync cycles so that an interrupted snap sync can be resumed
+#[derive(Debug, Copy, Clone)]
+pub enum SnapStateIndex {
+    // Hash of the last downloaded header in a previous sync cycle that was aborted
+    HeaderDownloadCheckpoint = 0,
+    // Last key fetched from the state trie
+    StateTrieKeyCheckpoint = 1,
+    // Root of the state trie rebuilt so far from the fetched account ranges
+    StateTrieRootCheckpoint = 2,
+    // Paths from the state trie in need of healing
+    StateHealPaths = 3,
+    // Paths from the storage tries in need of healing, grouped by hashed account address
+    StorageHealPaths = 4,
+}
+
+impl From<u8> for SnapStateIndex {
+    fn from(value: u8) -> Self {
+        match value {
+            x if x == SnapStateIndex::HeaderDownloadCheckpoint as u8 => {
+                SnapStateIndex::HeaderDownloadCheckpoint
+            }
+            x if x == SnapStateIndex::StateTrieKeyCheckpoint as u8 => {
+                SnapStateIndex::StateTrieKeyCheckpoint
+            }
+            x if x == SnapStateIndex::StateTrieRootCheckpoint as u8 => {
+                SnapStateIndex::StateTrieRootCheckpoint
+            }
+            x if x == SnapStateIndex::StateHealPaths as u8 => SnapStateIndex::StateHealPaths,
+            x if x == SnapStateIndex::StorageHealPaths as u8 => SnapStateIndex::StorageHealPaths,
+            _ => panic!("Invalid value when casting to SnapStateIndex: {}", value),
+        }
+    }
+}
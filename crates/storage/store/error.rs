@@ -30,6 +30,12 @@ pub enum StoreError {
     #[error("Redb Cast error")]
     #[cfg(feature = "redb")]
     RedbCastError,
+    #[cfg(feature = "rocksdb")]
+    #[error("RocksDB error: {0}")]
+    RocksDBError(#[from] rocksdb::Error),
+    #[cfg(feature = "rocksdb")]
+    #[error("RocksDB column family {0} not found")]
+    RocksDBMissingColumnFamily(String),
     #[error("{0}")]
     Custom(String),
     #[error(transparent)]
@@ -46,4 +52,12 @@ pub enum StoreError {
     MissingLatestBlockNumber,
     #[error("Missing earliest block number")]
     MissingEarliestBlockNumber,
+    #[error("Incompatible datadir: it has schema version {found}, but up to version {supported} is supported. Use a newer client or a new datadir")]
+    IncompatibleSchemaVersion { found: u64, supported: u64 },
+    #[error("No migration from schema version {0} to the next one")]
+    MissingMigration(u64),
+    #[error("{0} is not empty, a database can only be copied into a new directory")]
+    NonEmptyDestination(String),
+    #[error("No database found at {0}")]
+    MissingDatabase(String),
 }
@@ -18,6 +18,8 @@ use std::any::type_name;
 // Account types
 pub type AccountCodeHashRLP = Rlp<H256>;
 pub type AccountCodeRLP = Rlp<Bytes>;
+pub type PreimageHashRLP = Rlp<H256>;
+pub type PreimageRLP = Rlp<Bytes>;
 
 // Block types
 pub type BlockHashRLP = Rlp<BlockHash>;
@@ -0,0 +1,166 @@
+use tracing::info;
+
+use crate::{engines::api::StoreEngine, error::StoreError};
+
+/// Version of the layout of the database tables.
+/// Must be bumped whenever a table definition of a persistent engine changes, adding a migration
+/// that upgrades datadirs from the previous version
+pub const SCHEMA_VERSION: u64 = 2;
+
+/// Upgrades a datadir of the engine `E` from a schema version to the next one
+pub struct Migration<E> {
+    /// Version upgraded from
+    pub from: u64,
+    pub description: &'static str,
+    /// Rewrites the tables in place. Must be idempotent, as it is run again if the node stops
+    /// before the new version is stored
+    pub apply: fn(&E) -> Result<(), StoreError>,
+}
+
+/// Checks that the datadir can be used with the current schema version, setting it on new
+/// datadirs and migrating old ones in place
+pub(crate) fn check_schema_version<E: StoreEngine>(
+    engine: &E,
+    migrations: &[Migration<E>],
+) -> Result<(), StoreError> {
+    migrate_to(engine, migrations, SCHEMA_VERSION)
+}
+
+/// Checks that the datadir can be used with the current schema version once migrated, without
+/// writing to it
+pub(crate) fn check_schema_is_supported(engine: &dyn StoreEngine) -> Result<(), StoreError> {
+    match engine.get_schema_version()? {
+        Some(version) if version > SCHEMA_VERSION => Err(StoreError::IncompatibleSchemaVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn migrate_to<E: StoreEngine>(
+    engine: &E,
+    migrations: &[Migration<E>],
+    target_version: u64,
+) -> Result<(), StoreError> {
+    let version = match engine.get_schema_version()? {
+        Some(version) => version,
+        // Datadirs with stored blocks but no version predate versioning, which started with the
+        // layout they were created with
+        None if engine.get_earliest_block_number()?.is_some() => {
+            engine.set_schema_version(1)?;
+            1
+        }
+        None => {
+            engine.set_schema_version(target_version)?;
+            return Ok(());
+        }
+    };
+    if version > target_version {
+        return Err(StoreError::IncompatibleSchemaVersion {
+            found: version,
+            supported: target_version,
+        });
+    }
+    for from in version..target_version {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.from == from)
+            .ok_or(StoreError::MissingMigration(from))?;
+        info!(
+            "Migrating database from schema version {from} to {}: {}",
+            from + 1,
+            migration.description
+        );
+        (migration.apply)(engine)?;
+        engine.set_schema_version(from + 1)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_types::H256;
+
+    use super::*;
+    use crate::engines::in_memory::Store as InMemoryStore;
+
+    #[test]
+    fn new_datadir_gets_current_version() {
+        let engine = InMemoryStore::new();
+        check_schema_version(&engine, &[]).unwrap();
+        assert_eq!(engine.get_schema_version().unwrap(), Some(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn newer_datadir_is_refused() {
+        let engine = InMemoryStore::new();
+        engine.set_schema_version(SCHEMA_VERSION + 1).unwrap();
+        assert!(matches!(
+            check_schema_version(&engine, &[]),
+            Err(StoreError::IncompatibleSchemaVersion { found, supported })
+                if found == SCHEMA_VERSION + 1 && supported == SCHEMA_VERSION
+        ));
+    }
+
+    #[test]
+    fn supported_check_does_not_write() {
+        let engine = InMemoryStore::new();
+        check_schema_is_supported(&engine).unwrap();
+        assert_eq!(engine.get_schema_version().unwrap(), None);
+        engine.set_schema_version(SCHEMA_VERSION + 1).unwrap();
+        assert!(matches!(
+            check_schema_is_supported(&engine),
+            Err(StoreError::IncompatibleSchemaVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn datadir_before_versioning_is_first_version() {
+        let engine = InMemoryStore::new();
+        engine.update_earliest_block_number(0).unwrap();
+        migrate_to(&engine, &[], 1).unwrap();
+        assert_eq!(engine.get_schema_version().unwrap(), Some(1));
+        // It must be migrated like any other first version datadir
+        let engine = InMemoryStore::new();
+        engine.update_earliest_block_number(0).unwrap();
+        assert!(matches!(
+            migrate_to(&engine, &[], 2),
+            Err(StoreError::MissingMigration(1))
+        ));
+    }
+
+    #[test]
+    fn migrations_run_in_order() {
+        // Each migration leaves a mark of the version it upgraded from
+        let migrations = [
+            Migration {
+                from: 2,
+                description: "second",
+                apply: |engine: &InMemoryStore| {
+                    assert_eq!(engine.get_header_download_checkpoint()?, Some(H256::zero()));
+                    engine.set_header_download_checkpoint(H256::from_low_u64_be(2))
+                },
+            },
+            Migration {
+                from: 1,
+                description: "first",
+                apply: |engine: &InMemoryStore| engine.set_header_download_checkpoint(H256::zero()),
+            },
+        ];
+        let engine = InMemoryStore::new();
+        engine.set_schema_version(1).unwrap();
+        migrate_to(&engine, &migrations, 3).unwrap();
+        assert_eq!(engine.get_schema_version().unwrap(), Some(3));
+        assert_eq!(
+            engine.get_header_download_checkpoint().unwrap(),
+            Some(H256::from_low_u64_be(2))
+        );
+        // Nothing is run once up to date
+        migrate_to(&engine, &migrations, 3).unwrap();
+        assert_eq!(
+            engine.get_header_download_checkpoint().unwrap(),
+            Some(H256::from_low_u64_be(2))
+        );
+    }
+}
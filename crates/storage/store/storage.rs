@@ -6,7 +6,9 @@ use bytes::Bytes;
 use engines::api::StoreEngine;
 #[cfg(feature = "redb")]
 use engines::redb::RedBStore;
-use ethereum_types::{Address, H256, U256};
+#[cfg(feature = "rocksdb")]
+use engines::rocksdb::RocksDBStore;
+use ethereum_types::{Address, H256, H512, U256};
 use ethrex_core::types::{
     code_hash, AccountInfo, AccountState, BlobsBundle, Block, BlockBody, BlockHash, BlockHeader,
     BlockNumber, ChainConfig, Genesis, GenesisAccount, Index, MempoolTransaction, Receipt,
@@ -14,17 +16,25 @@ use ethrex_core::types::{
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_trie::Trie;
+use ethrex_trie::{Nibbles, NodeHash, Trie};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest as _, Keccak256};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tracing::info;
 
+mod batch;
 mod engines;
 pub mod error;
 mod rlp;
+pub mod schema;
+
+pub use batch::WriteBatch;
+
+/// A slot of an account's storage, given by its hashed key and its value
+pub type StorageSlot = (H256, U256);
 
 #[derive(Debug, Clone)]
 pub struct Store {
@@ -42,6 +52,18 @@ pub enum EngineType {
     Libmdbx,
     #[cfg(feature = "redb")]
     RedB,
+    #[cfg(feature = "rocksdb")]
+    RocksDB,
+}
+
+/// Number of entries and disk usage of a table of the database
+#[derive(Debug, Clone, Serialize)]
+pub struct TableStats {
+    pub name: String,
+    /// Estimated by RocksDB, exact for the other engines
+    pub entries: u64,
+    /// Bytes used on disk, if the engine persists the table
+    pub size: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -77,29 +99,104 @@ impl AccountUpdate {
 impl Store {
     pub fn new(path: &str, engine_type: EngineType) -> Result<Self, StoreError> {
         info!("Starting storage engine ({engine_type:?})");
-        let store = match engine_type {
+        let engine: Arc<dyn StoreEngine> = match engine_type {
             #[cfg(feature = "libmdbx")]
-            EngineType::Libmdbx => Self {
-                engine: Arc::new(LibmdbxStore::new(path)?),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
-                blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
-            },
-            EngineType::InMemory => Self {
-                engine: Arc::new(InMemoryStore::new()),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
-                blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
-            },
+            EngineType::Libmdbx => {
+                let engine = LibmdbxStore::new(path)?;
+                schema::check_schema_version(&engine, engines::libmdbx::MIGRATIONS)?;
+                Arc::new(engine)
+            }
+            EngineType::InMemory => {
+                let engine = InMemoryStore::new();
+                schema::check_schema_version(&engine, &[])?;
+                Arc::new(engine)
+            }
             #[cfg(feature = "redb")]
-            EngineType::RedB => Self {
-                engine: Arc::new(RedBStore::new()?),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
-                blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
-            },
+            EngineType::RedB => {
+                let engine = RedBStore::new(path)?;
+                schema::check_schema_version(&engine, engines::redb::MIGRATIONS)?;
+                Arc::new(engine)
+            }
+            #[cfg(feature = "rocksdb")]
+            EngineType::RocksDB => {
+                let engine = RocksDBStore::new(path)?;
+                schema::check_schema_version(&engine, engines::rocksdb::MIGRATIONS)?;
+                Arc::new(engine)
+            }
+        };
+        let store = Self {
+            engine,
+            mempool: Arc::new(Mutex::new(HashMap::new())),
+            blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
         };
         info!("Started store engine");
         Ok(store)
     }
 
+    /// Opens an existing database without writing to it, neither creating tables nor setting
+    /// or migrating its schema version. Fails if its schema version is newer than supported
+    pub fn open_read_only(path: &str, engine_type: EngineType) -> Result<Self, StoreError> {
+        if !Path::new(path).exists() {
+            return Err(StoreError::MissingDatabase(path.to_string()));
+        }
+        let engine: Result<Arc<dyn StoreEngine>, StoreError> = match engine_type {
+            #[cfg(feature = "libmdbx")]
+            EngineType::Libmdbx => Ok(Arc::new(LibmdbxStore::open_read_only(path)?)),
+            EngineType::InMemory => Err(StoreError::Custom(
+                "The in-memory store has no database to open".to_string(),
+            )),
+            #[cfg(feature = "redb")]
+            EngineType::RedB => Ok(Arc::new(RedBStore::open_read_only(path)?)),
+            #[cfg(feature = "rocksdb")]
+            EngineType::RocksDB => Ok(Arc::new(RocksDBStore::open_read_only(path)?)),
+        };
+        let engine = engine?;
+        schema::check_schema_is_supported(engine.as_ref())?;
+        Ok(Self {
+            engine,
+            mempool: Arc::new(Mutex::new(HashMap::new())),
+            blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Returns the version of the layout of the database tables
+    pub fn get_schema_version(&self) -> Result<Option<u64>, StoreError> {
+        self.engine.get_schema_version()
+    }
+
+    /// Copies the database into a new one at `backup_path`, which must not hold any files.
+    /// The copy is consistent even if blocks keep being added while it is made
+    pub fn backup(&self, backup_path: &str) -> Result<(), StoreError> {
+        let backup_path = Path::new(backup_path);
+        check_empty_destination(backup_path)?;
+        info!("Backing up database to {}", backup_path.display());
+        self.engine.backup(backup_path)?;
+        info!("Finished database backup");
+        Ok(())
+    }
+
+    /// Restores a backup made with [Store::backup] into a new database at `path`
+    pub fn restore(
+        backup_path: &str,
+        path: &str,
+        engine_type: EngineType,
+    ) -> Result<(), StoreError> {
+        check_empty_destination(Path::new(path))?;
+        // Checks that the backup can be used, without modifying it. Nothing else has it open, so
+        // copying its files is enough. Older schema versions are migrated when the node opens it
+        drop(Self::open_read_only(backup_path, engine_type)?);
+        info!("Restoring database from {backup_path}");
+        copy_dir(Path::new(backup_path), Path::new(path))
+            .map_err(|error| StoreError::Custom(error.to_string()))?;
+        info!("Finished database restore");
+        Ok(())
+    }
+
+    /// Returns the number of entries and the disk usage of each table of the database
+    pub fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        self.engine.table_stats()
+    }
+
     pub fn get_account_info(
         &self,
         block_number: BlockNumber,
@@ -371,6 +468,24 @@ impl Store {
         self.engine.get_account_code(code_hash)
     }
 
+    /// Returns the address or storage key that hashes to `hash`, if the node wrote it to a trie.
+    /// State downloaded through snap sync has no preimages
+    pub fn get_preimage(&self, hash: H256) -> Result<Option<Bytes>, StoreError> {
+        self.engine.get_preimage(hash)
+    }
+
+    /// Records that the preimages of some of the stored state are missing, as with state
+    /// downloaded through snap sync or stored before the preimages were
+    pub fn set_preimages_incomplete(&self) -> Result<(), StoreError> {
+        self.engine.set_preimages_incomplete()
+    }
+
+    /// Returns whether the preimages of some of the stored state may be missing, so that its
+    /// addresses and storage keys can't all be recovered
+    pub fn are_preimages_incomplete(&self) -> Result<bool, StoreError> {
+        self.engine.are_preimages_incomplete()
+    }
+
     pub fn get_code_by_account_address(
         &self,
         block_number: BlockNumber,
@@ -414,51 +529,144 @@ impl Store {
         &self,
         block_hash: BlockHash,
         account_updates: &[AccountUpdate],
+    ) -> Result<Option<H256>, StoreError> {
+        let mut batch = WriteBatch::new();
+        let state_root =
+            self.apply_account_updates_to_batch(&mut batch, block_hash, account_updates)?;
+        self.commit_batch(batch)?;
+        Ok(state_root)
+    }
+
+    /// Returns the state root resulting from applying the account updates on top of the block's
+    /// state, without writing any of the new trie nodes or code
+    pub fn state_root_after_updates(
+        &self,
+        block_hash: BlockHash,
+        account_updates: &[AccountUpdate],
+    ) -> Result<Option<H256>, StoreError> {
+        self.apply_account_updates_to_batch(&mut WriteBatch::new(), block_hash, account_updates)
+    }
+
+    /// Applies account updates based on the block's latest storage state and returns the new
+    /// state root, adding the new trie nodes and code to the batch instead of writing them
+    pub fn apply_account_updates_to_batch(
+        &self,
+        batch: &mut WriteBatch,
+        block_hash: BlockHash,
+        account_updates: &[AccountUpdate],
     ) -> Result<Option<H256>, StoreError> {
         let Some(mut state_trie) = self.state_trie(block_hash)? else {
             return Ok(None);
         };
-        for update in account_updates.iter() {
-            let hashed_address = hash_address(&update.address);
+        // Updates to the same account are applied in order, while different accounts (and their
+        // storage tries) are updated in parallel
+        let mut updates_by_address: HashMap<Address, Vec<&AccountUpdate>> = HashMap::new();
+        for update in account_updates {
+            updates_by_address
+                .entry(update.address)
+                .or_default()
+                .push(update);
+        }
+        let apply_updates = |(address, updates): (Address, Vec<&AccountUpdate>)| {
+            self.apply_updates_to_account(&state_trie, address, &updates)
+        };
+        #[cfg(feature = "parallel")]
+        let account_changes = {
+            use rayon::prelude::*;
+            updates_by_address
+                .into_par_iter()
+                .map(apply_updates)
+                .collect::<Result<Vec<_>, StoreError>>()?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let account_changes = updates_by_address
+            .into_iter()
+            .map(apply_updates)
+            .collect::<Result<Vec<_>, StoreError>>()?;
+        let mut account_states = Vec::with_capacity(account_changes.len());
+        for (hashed_address, account_state, account_batch) in account_changes {
+            account_states.push((hashed_address, account_state));
+            batch.extend(account_batch);
+        }
+        state_trie.update_batch(account_states)?;
+        let (state_root, state_trie_nodes) = state_trie.hash_and_take_changes()?;
+        batch.add_state_trie_nodes(state_trie_nodes);
+        Ok(Some(state_root))
+    }
+
+    /// Applies the updates of a single account on top of its state in the given state trie,
+    /// updating its storage trie.
+    /// Returns the hashed address of the account along with its encoded state, or None if the
+    /// account was removed, and a batch with its new code and storage trie nodes
+    #[allow(clippy::type_complexity)]
+    fn apply_updates_to_account(
+        &self,
+        state_trie: &Trie,
+        address: Address,
+        updates: &[&AccountUpdate],
+    ) -> Result<(Vec<u8>, Option<Vec<u8>>, WriteBatch), StoreError> {
+        let hashed_address = hash_address(&address);
+        let mut batch = WriteBatch::new();
+        let mut preimages = vec![(
+            H256::from_slice(&hashed_address),
+            Bytes::copy_from_slice(address.as_bytes()),
+        )];
+        let mut account_state = match state_trie.get(&hashed_address)? {
+            Some(encoded_state) => Some(AccountState::decode(&encoded_state)?),
+            None => None,
+        };
+        // The storage trie is kept open across updates, as its new nodes are only readable
+        // through it until the batch is committed
+        let mut storage_trie: Option<Trie> = None;
+        for update in updates {
             if update.removed {
-                // Remove account from trie
-                state_trie.remove(hashed_address)?;
-            } else {
-                // Add or update AccountState in the trie
-                // Fetch current state or create a new state to be inserted
-                let mut account_state = match state_trie.get(&hashed_address)? {
-                    Some(encoded_state) => AccountState::decode(&encoded_state)?,
-                    None => AccountState::default(),
-                };
-                if let Some(info) = &update.info {
-                    account_state.nonce = info.nonce;
-                    account_state.balance = info.balance;
-                    account_state.code_hash = info.code_hash;
-                    // Store updated code in DB
-                    if let Some(code) = &update.code {
-                        self.add_account_code(info.code_hash, code.clone())?;
-                    }
+                account_state = None;
+                storage_trie = None;
+                continue;
+            }
+            // Fetch current state or create a new state to be inserted
+            let account_state = account_state.get_or_insert_with(AccountState::default);
+            if let Some(info) = &update.info {
+                account_state.nonce = info.nonce;
+                account_state.balance = info.balance;
+                account_state.code_hash = info.code_hash;
+                // Store updated code in DB
+                if let Some(code) = &update.code {
+                    batch.add_account_code(info.code_hash, code.clone());
                 }
-                // Store the added storage in the account's storage trie and compute its new root
-                if !update.added_storage.is_empty() {
-                    let mut storage_trie = self.engine.open_storage_trie(
+            }
+            // Store the added storage in the account's storage trie
+            if !update.added_storage.is_empty() {
+                let storage_trie = storage_trie.get_or_insert_with(|| {
+                    self.engine.open_storage_trie(
                         H256::from_slice(&hashed_address),
                         account_state.storage_root,
-                    );
-                    for (storage_key, storage_value) in &update.added_storage {
-                        let hashed_key = hash_key(storage_key);
-                        if storage_value.is_zero() {
-                            storage_trie.remove(hashed_key)?;
-                        } else {
-                            storage_trie.insert(hashed_key, storage_value.encode_to_vec())?;
-                        }
-                    }
-                    account_state.storage_root = storage_trie.hash()?;
-                }
-                state_trie.insert(hashed_address, account_state.encode_to_vec())?;
+                    )
+                });
+                let storage_updates = update.added_storage.iter().map(|(key, value)| {
+                    let hashed_key = hash_key(key);
+                    preimages.push((
+                        H256::from_slice(&hashed_key),
+                        Bytes::copy_from_slice(key.as_bytes()),
+                    ));
+                    let value = (!value.is_zero()).then(|| value.encode_to_vec());
+                    (hashed_key, value)
+                });
+                storage_trie.update_batch(storage_updates)?;
             }
         }
-        Ok(Some(state_trie.hash()?))
+        // Compute the new storage root of the account, if it still exists
+        if let (Some(account_state), Some(mut storage_trie)) = (&mut account_state, storage_trie) {
+            let (storage_root, storage_trie_nodes) = storage_trie.hash_and_take_changes()?;
+            account_state.storage_root = storage_root;
+            batch.add_storage_trie_nodes(H256::from_slice(&hashed_address), storage_trie_nodes);
+        }
+        batch.add_preimages(preimages);
+        Ok((
+            hashed_address,
+            account_state.map(|state| state.encode_to_vec()),
+            batch,
+        ))
     }
 
     /// Adds all genesis accounts and returns the genesis block's state_root
@@ -467,8 +675,13 @@ impl Store {
         genesis_accounts: HashMap<Address, GenesisAccount>,
     ) -> Result<H256, StoreError> {
         let mut genesis_state_trie = self.engine.open_state_trie(*EMPTY_TRIE_HASH);
+        let mut preimages = Vec::new();
         for (address, account) in genesis_accounts {
             let hashed_address = hash_address(&address);
+            preimages.push((
+                H256::from_slice(&hashed_address),
+                Bytes::copy_from_slice(address.as_bytes()),
+            ));
             // Store account code (as this won't be stored in the trie)
             let code_hash = code_hash(&account.code);
             self.add_account_code(code_hash, account.code)?;
@@ -479,6 +692,10 @@ impl Store {
             for (storage_key, storage_value) in account.storage {
                 if !storage_value.is_zero() {
                     let hashed_key = hash_key(&storage_key);
+                    preimages.push((
+                        H256::from_slice(&hashed_key),
+                        Bytes::copy_from_slice(storage_key.as_bytes()),
+                    ));
                     storage_trie.insert(hashed_key, storage_value.encode_to_vec())?;
                 }
             }
@@ -492,6 +709,9 @@ impl Store {
             };
             genesis_state_trie.insert(hashed_address, account_state.encode_to_vec())?;
         }
+        let mut batch = WriteBatch::new();
+        batch.add_preimages(preimages);
+        self.commit_batch(batch)?;
         Ok(genesis_state_trie.hash()?)
     }
 
@@ -521,19 +741,43 @@ impl Store {
     }
 
     pub fn add_block(&self, block: Block) -> Result<(), StoreError> {
-        // TODO Maybe add both in a single tx?
+        let mut batch = WriteBatch::new();
+        self.add_block_to_batch(&mut batch, block)?;
+        self.commit_batch(batch)
+    }
+
+    /// Adds the writes needed to store the block (header, body, number, total difficulty and
+    /// transaction locations) to the batch
+    pub fn add_block_to_batch(
+        &self,
+        batch: &mut WriteBatch,
+        block: Block,
+    ) -> Result<(), StoreError> {
         let header = block.header;
         let number = header.number;
         let latest_total_difficulty = self.get_latest_total_difficulty()?;
         let block_total_difficulty =
             latest_total_difficulty.unwrap_or(U256::zero()) + header.difficulty;
         let hash = header.compute_block_hash();
-        self.add_transaction_locations(&block.body.transactions, number, hash)?;
-        self.add_block_body(hash, block.body)?;
-        self.add_block_header(hash, header)?;
-        self.add_block_number(hash, number)?;
-        self.add_block_total_difficulty(hash, block_total_difficulty)?;
-        self.update_latest_total_difficulty(block_total_difficulty)
+        let locations = block
+            .body
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(index, transaction)| (transaction.compute_hash(), number, hash, index as Index))
+            .collect();
+        batch.add_transaction_locations(locations);
+        batch.add_block_body(hash, block.body);
+        batch.add_block_header(hash, header);
+        batch.add_block_number(hash, number);
+        batch.add_block_total_difficulty(hash, block_total_difficulty);
+        batch.update_latest_total_difficulty(block_total_difficulty);
+        Ok(())
+    }
+
+    /// Persists all the writes of the batch as a single unit
+    pub fn commit_batch(&self, batch: WriteBatch) -> Result<(), StoreError> {
+        self.engine.commit_batch(batch)
     }
 
     pub fn add_initial_state(&self, genesis: Genesis) -> Result<(), StoreError> {
@@ -811,6 +1055,20 @@ impl Store {
             })
     }
 
+    /// Returns an iterator across the accounts in the state trie given by the state_root, starting
+    /// from the first hashed address equal to or greater than `start`
+    pub fn iter_accounts_from(
+        &self,
+        state_root: H256,
+        start: H256,
+    ) -> Result<impl Iterator<Item = Result<(H256, AccountState), StoreError>>, StoreError> {
+        let mut iter = self.engine.open_state_trie(state_root).into_iter();
+        iter.advance(start.as_bytes().to_vec())?;
+        Ok(iter
+            .content()
+            .map(|(path, value)| Ok((H256::from_slice(&path), AccountState::decode(&value)?))))
+    }
+
     // Returns an iterator across all accounts in the state trie given by the state_root
     // Does not check that the state_root is valid
     pub fn iter_storage(
@@ -834,6 +1092,30 @@ impl Store {
         ))
     }
 
+    /// Returns an iterator across the storage of the account in the state trie given by the
+    /// state_root, starting from the first hashed key equal to or greater than `start`.
+    /// Returns None if the account doesn't exist
+    pub fn iter_storage_from(
+        &self,
+        state_root: H256,
+        hashed_address: H256,
+        start: H256,
+    ) -> Result<Option<impl Iterator<Item = Result<StorageSlot, StoreError>>>, StoreError> {
+        let state_trie = self.engine.open_state_trie(state_root);
+        let Some(account_rlp) = state_trie.get(&hashed_address.as_bytes().to_vec())? else {
+            return Ok(None);
+        };
+        let storage_root = AccountState::decode(&account_rlp)?.storage_root;
+        let mut iter = self
+            .engine
+            .open_storage_trie(hashed_address, storage_root)
+            .into_iter();
+        iter.advance(start.as_bytes().to_vec())?;
+        Ok(Some(iter.content().map(|(path, value)| {
+            Ok((H256::from_slice(&path), U256::decode(&value)?))
+        })))
+    }
+
     pub fn get_account_range_proof(
         &self,
         state_root: H256,
@@ -965,6 +1247,130 @@ impl Store {
     ) -> Result<Vec<Receipt>, StoreError> {
         self.engine.get_receipts_for_block(block_hash)
     }
+
+    /// Returns true if the given node is part of the state trie's underlying db
+    pub fn contains_state_node(&self, node_hash: H256) -> Result<bool, StoreError> {
+        // Root is irrelevant, we only care about the underlying db
+        Ok(self
+            .engine
+            .open_state_trie(*EMPTY_TRIE_HASH)
+            .db()
+            .get(NodeHash::Hashed(node_hash).into())?
+            .is_some())
+    }
+
+    /// Returns true if the given node is part of the given storage trie's underlying db
+    pub fn contains_storage_node(
+        &self,
+        hashed_address: H256,
+        node_hash: H256,
+    ) -> Result<bool, StoreError> {
+        // Root is irrelevant, we only care about the underlying db
+        Ok(self
+            .engine
+            .open_storage_trie(hashed_address, *EMPTY_TRIE_HASH)
+            .db()
+            .get(NodeHash::Hashed(node_hash).into())?
+            .is_some())
+    }
+
+    /// Sets the hash of the last header downloaded during a snap sync
+    pub fn set_header_download_checkpoint(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.engine.set_header_download_checkpoint(block_hash)
+    }
+
+    /// Gets the hash of the last header downloaded during a snap sync
+    pub fn get_header_download_checkpoint(&self) -> Result<Option<BlockHash>, StoreError> {
+        self.engine.get_header_download_checkpoint()
+    }
+
+    /// Sets the last key fetched from the state trie being fetched during snap sync
+    pub fn set_state_trie_key_checkpoint(&self, last_key: H256) -> Result<(), StoreError> {
+        self.engine.set_state_trie_key_checkpoint(last_key)
+    }
+
+    /// Gets the last key fetched from the state trie being fetched during snap sync
+    pub fn get_state_trie_key_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        self.engine.get_state_trie_key_checkpoint()
+    }
+
+    /// Sets the root of the state trie being rebuilt during snap sync
+    pub fn set_state_trie_root_checkpoint(&self, current_root: H256) -> Result<(), StoreError> {
+        self.engine.set_state_trie_root_checkpoint(current_root)
+    }
+
+    /// Gets the root of the state trie being rebuilt during snap sync
+    pub fn get_state_trie_root_checkpoint(&self) -> Result<Option<H256>, StoreError> {
+        self.engine.get_state_trie_root_checkpoint()
+    }
+
+    /// Sets the state trie paths in need of healing
+    pub fn set_state_heal_paths(&self, paths: Vec<Nibbles>) -> Result<(), StoreError> {
+        self.engine.set_state_heal_paths(paths)
+    }
+
+    /// Gets the state trie paths in need of healing
+    pub fn get_state_heal_paths(&self) -> Result<Option<Vec<Nibbles>>, StoreError> {
+        self.engine.get_state_heal_paths()
+    }
+
+    /// Sets the storage trie paths in need of healing, grouped by hashed address
+    pub fn set_storage_heal_paths(
+        &self,
+        accounts: Vec<(H256, Vec<Nibbles>)>,
+    ) -> Result<(), StoreError> {
+        self.engine.set_storage_heal_paths(accounts)
+    }
+
+    /// Gets the storage trie paths in need of healing, grouped by hashed address
+    #[allow(clippy::type_complexity)]
+    pub fn get_storage_heal_paths(&self) -> Result<Option<Vec<(H256, Vec<Nibbles>)>>, StoreError> {
+        self.engine.get_storage_heal_paths()
+    }
+
+    /// Clears all checkpoint data created during the last snap sync
+    pub fn clear_snap_state(&self) -> Result<(), StoreError> {
+        self.engine.clear_snap_state()
+    }
+
+    /// Replaces the stored peers with the given ones
+    /// Each peer is stored by node id as an encoding defined by the networking layer
+    pub fn set_known_peers(&self, peers: Vec<(H512, Vec<u8>)>) -> Result<(), StoreError> {
+        self.engine.set_known_peers(peers)
+    }
+
+    /// Gets the encodings of all stored peers
+    pub fn get_known_peers(&self) -> Result<Vec<Vec<u8>>, StoreError> {
+        self.engine.get_known_peers()
+    }
+}
+
+/// Fails if the path has any file, as databases are only copied into new directories
+fn check_empty_destination(path: &Path) -> Result<(), StoreError> {
+    if path.exists()
+        && std::fs::read_dir(path)
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .next()
+            .is_some()
+    {
+        return Err(StoreError::NonEmptyDestination(path.display().to_string()));
+    }
+    Ok(())
+}
+
+/// Copies the files of a directory and its subdirectories into another one, creating it if needed
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let destination = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &destination)?;
+        } else {
+            std::fs::copy(entry.path(), destination)?;
+        }
+    }
+    Ok(())
 }
 
 pub fn hash_address(address: &Address) -> Vec<u8> {
@@ -988,7 +1394,7 @@ pub fn hash_key(key: &H256) -> Vec<u8> {
 
 #[cfg(test)]
 mod tests {
-    use std::{fs, panic, str::FromStr};
+    use std::{panic, str::FromStr};
 
     use bytes::Bytes;
     use ethereum_types::{H256, U256};
@@ -997,6 +1403,7 @@ mod tests {
         Bloom,
     };
     use ethrex_rlp::decode::RLPDecode;
+    use tempdir::TempDir;
 
     use super::*;
 
@@ -1017,20 +1424,97 @@ mod tests {
         test_store_suite(EngineType::RedB);
     }
 
-    // Creates an empty store, runs the test and then removes the store (if needed)
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn test_rocksdb_store() {
+        test_store_suite(EngineType::RocksDB);
+    }
+
+    #[cfg(feature = "libmdbx")]
+    #[test]
+    fn test_libmdbx_backup_and_restore() {
+        test_backup_and_restore(EngineType::Libmdbx);
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn test_redb_preimages_are_incomplete_after_migration() {
+        test_preimages_are_incomplete_after_migration(EngineType::RedB);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn test_rocksdb_preimages_are_incomplete_after_migration() {
+        test_preimages_are_incomplete_after_migration(EngineType::RocksDB);
+    }
+
+    #[allow(dead_code)]
+    fn test_preimages_are_incomplete_after_migration(engine_type: EngineType) {
+        let dir = TempDir::new("store-test-db").expect("Failed to create temp dir");
+        let path = dir.path().to_str().unwrap();
+        let store = Store::new(path, engine_type).unwrap();
+        assert!(!store.are_preimages_incomplete().unwrap());
+        // Datadirs from before the preimages table have none of the preimages of their state
+        store.engine.set_schema_version(1).unwrap();
+        drop(store);
+        let store = Store::new(path, engine_type).unwrap();
+        assert!(store.are_preimages_incomplete().unwrap());
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn test_redb_backup_and_restore() {
+        test_backup_and_restore(EngineType::RedB);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn test_rocksdb_backup_and_restore() {
+        test_backup_and_restore(EngineType::RocksDB);
+    }
+
+    #[allow(dead_code)]
+    fn test_backup_and_restore(engine_type: EngineType) {
+        let dir = TempDir::new("store-test-db").expect("Failed to create temp dir");
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        let store = Store::new(&path("source"), engine_type).unwrap();
+        let block_header = BlockHeader::default();
+        let block_hash = block_header.compute_block_hash();
+        store
+            .add_block_header(block_hash, block_header.clone())
+            .unwrap();
+        store.backup(&path("backup")).unwrap();
+        assert!(matches!(
+            store.backup(&path("backup")),
+            Err(StoreError::NonEmptyDestination(_))
+        ));
+        drop(store);
+
+        // The backup can be read while it is being restored
+        let backup = Store::open_read_only(&path("backup"), engine_type).unwrap();
+        assert_eq!(
+            backup.get_schema_version().unwrap(),
+            Some(schema::SCHEMA_VERSION)
+        );
+        drop(backup);
+        Store::restore(&path("backup"), &path("restored"), engine_type).unwrap();
+        let restored = Store::new(&path("restored"), engine_type).unwrap();
+        assert_eq!(
+            restored.get_block_header_by_hash(block_hash).unwrap(),
+            Some(block_header)
+        );
+    }
+
+    // Creates an empty store in its own temporary directory, so that tests of different engines
+    // can run in parallel, and runs the test. The directory is removed when dropped
     fn run_test(test_func: &dyn Fn(Store), engine_type: EngineType) {
-        // Remove preexistent DBs in case of a failed previous test
-        if !matches!(engine_type, EngineType::InMemory) {
-            remove_test_dbs("store-test-db");
-        };
-        // Build a new store
-        let store = Store::new("store-test-db", engine_type).expect("Failed to create test db");
-        // Run the test
+        let dir = TempDir::new("store-test-db").expect("Failed to create temp dir");
+        let store = Store::new(
+            dir.path().to_str().expect("Temp dir path is valid UTF-8"),
+            engine_type,
+        )
+        .expect("Failed to create test db");
         test_func(store);
-        // Remove store (if needed)
-        if !matches!(engine_type, EngineType::InMemory) {
-            remove_test_dbs("store-test-db");
-        };
     }
 
     fn test_store_suite(engine_type: EngineType) {
@@ -1043,7 +1527,11 @@ mod tests {
         run_test(&test_store_block_tags, engine_type);
         run_test(&test_chain_config_storage, engine_type);
         run_test(&test_genesis_block, engine_type);
+        run_test(&test_state_ranges, engine_type);
         run_test(&test_filter_mempool_transactions, engine_type);
+        run_test(&test_snap_state, engine_type);
+        run_test(&test_known_peers, engine_type);
+        run_test(&test_write_batch, engine_type);
         run_test(&blobs_bundle_loadtest, engine_type);
     }
 
@@ -1067,11 +1555,59 @@ mod tests {
         .expect_err("genesis with a different block should panic");
     }
 
-    fn remove_test_dbs(path: &str) {
-        // Removes all test databases from filesystem
-        if std::path::Path::new(path).exists() {
-            fs::remove_dir_all(path).expect("Failed to clean test db dir");
+    fn test_state_ranges(store: Store) {
+        const GENESIS_KURTOSIS: &str = include_str!("../../../test_data/genesis-kurtosis.json");
+        let genesis: Genesis =
+            serde_json::from_str(GENESIS_KURTOSIS).expect("deserialize genesis-kurtosis.json");
+        let state_root = genesis.get_block().header.state_root;
+        store.add_initial_state(genesis.clone()).unwrap();
+
+        // Every genesis account can be found from its hashed address
+        let accounts: Vec<_> = store.iter_accounts(state_root).collect();
+        assert_eq!(accounts.len(), genesis.alloc.len());
+        for (hashed_address, _) in &accounts {
+            let address = store.get_preimage(*hashed_address).unwrap().unwrap();
+            assert!(genesis.alloc.contains_key(&Address::from_slice(&address)));
         }
+
+        // Iterating from the middle skips the accounts before it
+        let (start, _) = accounts[accounts.len() / 2];
+        let from_start: Vec<_> = store
+            .iter_accounts_from(state_root, start)
+            .unwrap()
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        let expected: Vec<_> = accounts[accounts.len() / 2..]
+            .iter()
+            .map(|(hashed_address, _)| *hashed_address)
+            .collect();
+        assert_eq!(from_start, expected);
+
+        // The storage of a genesis contract can be iterated from any key
+        let (address, account) = genesis
+            .alloc
+            .iter()
+            .find(|(_, account)| account.storage.len() > 1)
+            .expect("genesis-kurtosis.json has contracts with storage");
+        let hashed_address = H256::from_slice(&hash_address(address));
+        let slots: Vec<_> = store
+            .iter_storage_from(state_root, hashed_address, H256::zero())
+            .unwrap()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let non_zero_slots = account.storage.values().filter(|value| !value.is_zero());
+        assert_eq!(slots.len(), non_zero_slots.count());
+        let (last_key, last_value) = slots[slots.len() - 1];
+        let last_slot = store.get_preimage(last_key).unwrap().unwrap();
+        assert_eq!(account.storage[&H256::from_slice(&last_slot)], last_value);
+        let from_last: Vec<_> = store
+            .iter_storage_from(state_root, hashed_address, last_key)
+            .unwrap()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(from_last, vec![(last_key, last_value)]);
     }
 
     fn test_store_block(store: Store) {
@@ -1331,4 +1867,124 @@ mod tests {
                 .unwrap();
         }
     }
+
+    fn test_snap_state(store: Store) {
+        let header_hash = H256::random();
+        let last_key = H256::random();
+        let root = H256::random();
+        let state_paths = vec![Nibbles::from_hex(vec![1, 2, 3]), Nibbles::from_hex(vec![])];
+        let storage_paths = vec![(H256::random(), vec![Nibbles::from_hex(vec![0xa, 0xb])])];
+
+        assert!(store.get_header_download_checkpoint().unwrap().is_none());
+        assert!(store.get_storage_heal_paths().unwrap().is_none());
+
+        store.set_header_download_checkpoint(header_hash).unwrap();
+        store.set_state_trie_key_checkpoint(last_key).unwrap();
+        store.set_state_trie_root_checkpoint(root).unwrap();
+        store.set_state_heal_paths(state_paths.clone()).unwrap();
+        store.set_storage_heal_paths(storage_paths.clone()).unwrap();
+
+        assert_eq!(
+            store.get_header_download_checkpoint().unwrap(),
+            Some(header_hash)
+        );
+        assert_eq!(
+            store.get_state_trie_key_checkpoint().unwrap(),
+            Some(last_key)
+        );
+        assert_eq!(store.get_state_trie_root_checkpoint().unwrap(), Some(root));
+        assert_eq!(store.get_state_heal_paths().unwrap(), Some(state_paths));
+        assert_eq!(store.get_storage_heal_paths().unwrap(), Some(storage_paths));
+
+        store.clear_snap_state().unwrap();
+
+        assert!(store.get_header_download_checkpoint().unwrap().is_none());
+        assert!(store.get_state_trie_key_checkpoint().unwrap().is_none());
+        assert!(store.get_state_trie_root_checkpoint().unwrap().is_none());
+        assert!(store.get_state_heal_paths().unwrap().is_none());
+        assert!(store.get_storage_heal_paths().unwrap().is_none());
+    }
+
+    fn test_write_batch(store: Store) {
+        const GENESIS_KURTOSIS: &str = include_str!("../../../test_data/genesis-kurtosis.json");
+        let genesis: Genesis =
+            serde_json::from_str(GENESIS_KURTOSIS).expect("deserialize genesis-kurtosis.json");
+        let genesis_hash = genesis.get_block().hash();
+        store.add_initial_state(genesis).unwrap();
+
+        let address = Address::random();
+        let code = Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xf3]);
+        let storage_key = H256::from_low_u64_be(7);
+        let mut update = AccountUpdate::new(address);
+        update.info = Some(AccountInfo {
+            code_hash: code_hash(&code),
+            balance: U256::from(1000),
+            nonce: 1,
+        });
+        update.code = Some(code.clone());
+        update.added_storage.insert(storage_key, U256::from(42));
+
+        let mut batch = WriteBatch::new();
+        let state_root = store
+            .apply_account_updates_to_batch(&mut batch, genesis_hash, &[update.clone()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            store
+                .state_root_after_updates(genesis_hash, &[update])
+                .unwrap(),
+            Some(state_root)
+        );
+        let (mut header, body) = create_block_for_testing();
+        header.parent_hash = genesis_hash;
+        header.state_root = state_root;
+        let block_hash = header.compute_block_hash();
+        store
+            .add_block_to_batch(&mut batch, Block::new(header, body))
+            .unwrap();
+
+        // Nothing is visible until the batch is committed
+        assert!(!store.contains_state_node(state_root).unwrap());
+        assert!(store
+            .get_block_header_by_hash(block_hash)
+            .unwrap()
+            .is_none());
+        assert!(store.get_account_code(code_hash(&code)).unwrap().is_none());
+
+        store.commit_batch(batch).unwrap();
+
+        assert!(store.contains_state_node(state_root).unwrap());
+        assert_eq!(store.get_block_number(block_hash).unwrap(), Some(1));
+        let account = store
+            .get_account_info_by_hash(block_hash, address)
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.balance, U256::from(1000));
+        assert_eq!(
+            store.get_account_code(account.code_hash).unwrap(),
+            Some(code)
+        );
+        assert_eq!(
+            store
+                .get_storage_at_hash(block_hash, address, storage_key)
+                .unwrap(),
+            Some(U256::from(42))
+        );
+    }
+
+    fn test_known_peers(store: Store) {
+        let (node_a, node_b, node_c) = (H512::random(), H512::random(), H512::random());
+        assert!(store.get_known_peers().unwrap().is_empty());
+
+        store
+            .set_known_peers(vec![(node_a, vec![1, 2]), (node_b, vec![3])])
+            .unwrap();
+        let mut peers = store.get_known_peers().unwrap();
+        peers.sort();
+        assert_eq!(peers, vec![vec![1, 2], vec![3]]);
+
+        // Previously stored peers not present in the new set are dropped
+        store.set_known_peers(vec![(node_c, vec![4])]).unwrap();
+        assert_eq!(store.get_known_peers().unwrap(), vec![vec![4]]);
+    }
 }
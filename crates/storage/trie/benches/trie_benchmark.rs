@@ -0,0 +1,245 @@
+//! Benchmarks of the trie on each of its database backends.
+//! The backends of the enabled features are compared, e.g. for all of them:
+//! `cargo bench -p ethrex-trie --features libmdbx,redb,rocksdb`
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use ethereum_types::H256;
+use ethrex_trie::{InMemoryTrieDB, Trie, TrieDB};
+use sha3::{Digest, Keccak256};
+use tempdir::TempDir;
+
+/// Number of leaves of the trie each benchmark starts from
+const TRIE_SIZE: u64 = 10_000;
+/// Number of leaves inserted before committing the trie, about the accounts touched by a block
+const COMMIT_SIZE: u64 = 1_000;
+
+/// Opens a new handle to the database of a backend, all the handles share the same nodes
+type OpenTrieDB = Box<dyn Fn() -> Box<dyn TrieDB>>;
+
+struct Backend {
+    name: &'static str,
+    /// Creates the database of the backend in the given directory
+    create: fn(&Path) -> OpenTrieDB,
+}
+
+fn backends() -> Vec<Backend> {
+    #[allow(unused_mut)]
+    let mut backends = vec![Backend {
+        name: "in_memory",
+        create: in_memory,
+    }];
+    #[cfg(feature = "libmdbx")]
+    backends.extend([
+        Backend {
+            name: "libmdbx",
+            create: libmdbx_backends::libmdbx,
+        },
+        Backend {
+            name: "libmdbx_dupsort",
+            create: libmdbx_backends::libmdbx_dupsort,
+        },
+    ]);
+    #[cfg(feature = "redb")]
+    backends.extend([
+        Backend {
+            name: "redb",
+            create: redb_backends::redb,
+        },
+        Backend {
+            name: "redb_multitable",
+            create: redb_backends::redb_multitable,
+        },
+    ]);
+    #[cfg(feature = "rocksdb")]
+    backends.push(Backend {
+        name: "rocksdb",
+        create: rocksdb_backend::rocksdb,
+    });
+    backends
+}
+
+fn in_memory(_dir: &Path) -> OpenTrieDB {
+    let map = Arc::new(Mutex::new(HashMap::new()));
+    Box::new(move || Box::new(InMemoryTrieDB::new(map.clone())))
+}
+
+#[cfg(feature = "libmdbx")]
+mod libmdbx_backends {
+    use std::{path::Path, sync::Arc};
+
+    use ethrex_trie::{LibmdbxDupsortTrieDB, LibmdbxTrieDB};
+    use libmdbx::{
+        dupsort,
+        orm::{table_info, Database},
+        table,
+    };
+
+    use super::OpenTrieDB;
+
+    table!(
+        /// NodeHash to Node table
+        ( Nodes ) Vec<u8> => Vec<u8>
+    );
+
+    dupsort!(
+        /// (Key + NodeHash) to Node table
+        ( DupsortNodes ) ([u8;32], [u8;33])[[u8;32]] => Vec<u8>
+    );
+
+    pub fn libmdbx(dir: &Path) -> OpenTrieDB {
+        let tables = [table_info!(Nodes)].into_iter().collect();
+        let db = Arc::new(
+            Database::create(Some(dir.to_path_buf()), &tables).expect("Failed to create DB"),
+        );
+        Box::new(move || Box::new(LibmdbxTrieDB::<Nodes>::new(db.clone())))
+    }
+
+    pub fn libmdbx_dupsort(dir: &Path) -> OpenTrieDB {
+        let tables = [table_info!(DupsortNodes)].into_iter().collect();
+        let db = Arc::new(
+            Database::create(Some(dir.to_path_buf()), &tables).expect("Failed to create DB"),
+        );
+        Box::new(move || {
+            Box::new(LibmdbxDupsortTrieDB::<DupsortNodes, [u8; 32]>::new(
+                db.clone(),
+                [1; 32],
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "redb")]
+mod redb_backends {
+    use std::{path::Path, sync::Arc};
+
+    use ethrex_trie::db::{redb::RedBTrie, redb_multitable::RedBMultiTableTrieDB};
+    use redb::Database;
+
+    use super::OpenTrieDB;
+
+    pub fn redb(dir: &Path) -> OpenTrieDB {
+        let db = Arc::new(Database::create(dir.join("trie.redb")).expect("Failed to create DB"));
+        Box::new(move || Box::new(RedBTrie::new(db.clone())))
+    }
+
+    pub fn redb_multitable(dir: &Path) -> OpenTrieDB {
+        let db = Arc::new(Database::create(dir.join("trie.redb")).expect("Failed to create DB"));
+        Box::new(move || Box::new(RedBMultiTableTrieDB::new(db.clone(), [1; 32])))
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+mod rocksdb_backend {
+    use std::{path::Path, sync::Arc};
+
+    use ethrex_trie::RocksDBTrieDB;
+    use rocksdb::{Options, DB};
+
+    use super::OpenTrieDB;
+
+    const NODES: &str = "Nodes";
+
+    pub fn rocksdb(dir: &Path) -> OpenTrieDB {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db = Arc::new(DB::open_cf(&options, dir, [NODES]).expect("Failed to create DB"));
+        Box::new(move || {
+            Box::new(RocksDBTrieDB::new_with_fixed_key(
+                db.clone(),
+                NODES,
+                [1; 32],
+            ))
+        })
+    }
+}
+
+/// Hashed path of the i-th leaf, spreading the leaves over the trie like hashed addresses
+fn path(i: u64) -> Vec<u8> {
+    Keccak256::digest(i.to_be_bytes()).to_vec()
+}
+
+/// Value of the i-th leaf, about the size of an encoded account
+fn value(i: u64) -> Vec<u8> {
+    i.to_be_bytes().repeat(9)
+}
+
+/// Inserts the first `TRIE_SIZE` leaves, committing every `COMMIT_SIZE` of them, and returns the root
+fn populate(open_db: &OpenTrieDB) -> H256 {
+    let mut trie = Trie::new(open_db());
+    for i in 0..TRIE_SIZE {
+        trie.insert(path(i), value(i))
+            .expect("Failed to insert leaf");
+        if (i + 1) % COMMIT_SIZE == 0 {
+            trie.hash().expect("Failed to commit trie");
+        }
+    }
+    trie.hash().expect("Failed to commit trie")
+}
+
+fn bench_insert_and_commit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_and_commit");
+    group.sample_size(10);
+    for backend in backends() {
+        let dir = TempDir::new("trie-bench").expect("Failed to create temp dir");
+        let open_db = (backend.create)(dir.path());
+        let root = populate(&open_db);
+        // Inserts the same new leaves on top of the populated trie each time, so the nodes
+        // written are the same and the database doesn't grow across iterations
+        group.bench_function(BenchmarkId::from_parameter(backend.name), |b| {
+            b.iter_batched(
+                || Trie::open(open_db(), root),
+                |mut trie| {
+                    for i in TRIE_SIZE..TRIE_SIZE + COMMIT_SIZE {
+                        trie.insert(path(i), value(i))
+                            .expect("Failed to insert leaf");
+                    }
+                    trie.hash().expect("Failed to commit trie")
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_get");
+    for backend in backends() {
+        let dir = TempDir::new("trie-bench").expect("Failed to create temp dir");
+        let open_db = (backend.create)(dir.path());
+        let trie = Trie::open(open_db(), populate(&open_db));
+        let paths: Vec<_> = (0..TRIE_SIZE).map(path).collect();
+        let mut paths = paths.iter().cycle();
+        group.bench_function(BenchmarkId::from_parameter(backend.name), |b| {
+            b.iter(|| {
+                let path = paths.next().expect("The paths are cycled");
+                trie.get(path).expect("Failed to get leaf")
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate");
+    group.sample_size(10);
+    for backend in backends() {
+        let dir = TempDir::new("trie-bench").expect("Failed to create temp dir");
+        let open_db = (backend.create)(dir.path());
+        let root = populate(&open_db);
+        group.bench_function(BenchmarkId::from_parameter(backend.name), |b| {
+            b.iter(|| Trie::open(open_db(), root).into_iter().content().count())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert_and_commit, bench_get, bench_iterate);
+criterion_main!(benches);
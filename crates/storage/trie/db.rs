@@ -7,11 +7,16 @@ pub mod libmdbx_dupsort;
 pub mod redb;
 #[cfg(feature = "redb")]
 pub mod redb_multitable;
+#[cfg(feature = "rocksdb")]
+pub mod rocksdb;
 mod utils;
 
+#[cfg(any(feature = "libmdbx", feature = "redb"))]
+pub use utils::node_hash_to_fixed_size;
+
 use crate::error::TrieError;
 
-pub trait TrieDB {
+pub trait TrieDB: Send + Sync {
     fn get(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, TrieError>;
     fn put(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), TrieError>;
     // fn put_batch(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), TrieError>;
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use rocksdb::{ColumnFamily, WriteBatch, DB};
+
+use crate::error::TrieError;
+
+use super::TrieDB;
+
+/// RocksDB implementation for the TrieDB trait, storing the nodes of the trie in a column family.
+/// Tries sharing a column family are kept separate by a fixed key prefixed to each node hash,
+/// such as the hashed address of the account for storage tries
+pub struct RocksDBTrieDB {
+    db: Arc<DB>,
+    column_family: &'static str,
+    fixed_key: Option<[u8; 32]>,
+}
+
+impl RocksDBTrieDB {
+    /// Creates a trie using the whole column family, the column family must be open in the DB
+    pub fn new(db: Arc<DB>, column_family: &'static str) -> Self {
+        Self {
+            db,
+            column_family,
+            fixed_key: None,
+        }
+    }
+
+    /// Creates a trie using the nodes of the column family prefixed by `fixed_key`
+    pub fn new_with_fixed_key(
+        db: Arc<DB>,
+        column_family: &'static str,
+        fixed_key: [u8; 32],
+    ) -> Self {
+        Self {
+            db,
+            column_family,
+            fixed_key: Some(fixed_key),
+        }
+    }
+
+    fn cf(&self) -> Result<&ColumnFamily, TrieError> {
+        self.db
+            .cf_handle(self.column_family)
+            .ok_or_else(|| TrieError::RocksDBMissingColumnFamily(self.column_family.to_string()))
+    }
+
+    fn key(&self, node_hash: Vec<u8>) -> Vec<u8> {
+        match &self.fixed_key {
+            Some(fixed_key) => fixed_key_node_hash(fixed_key, &node_hash),
+            None => node_hash,
+        }
+    }
+}
+
+/// Key under which a node is stored for the trie with the given fixed key.
+/// As the fixed key has a fixed size, node hashes of any length (inlined nodes) can't collide
+pub fn fixed_key_node_hash(fixed_key: &[u8; 32], node_hash: &[u8]) -> Vec<u8> {
+    [fixed_key.as_slice(), node_hash].concat()
+}
+
+impl TrieDB for RocksDBTrieDB {
+    fn get(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, TrieError> {
+        Ok(self.db.get_cf(self.cf()?, self.key(key))?)
+    }
+
+    fn put(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), TrieError> {
+        Ok(self.db.put_cf(self.cf()?, self.key(key), value)?)
+    }
+
+    fn put_batch(&self, key_values: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), TrieError> {
+        let cf = self.cf()?;
+        let mut batch = WriteBatch::default();
+        for (key, value) in key_values {
+            batch.put_cf(cf, self.key(key), value);
+        }
+        Ok(self.db.write(batch)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rocksdb::Options;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    const NODES: &str = "Nodes";
+
+    fn new_db(dir: &TempDir) -> Arc<DB> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        Arc::new(DB::open_cf(&options, dir.path(), [NODES]).expect("Failed to create temp DB"))
+    }
+
+    #[test]
+    fn simple_addition() {
+        let dir = TempDir::new("rocksdb-trie").unwrap();
+        let db = RocksDBTrieDB::new(new_db(&dir), NODES);
+        assert_eq!(db.get("hello".into()).unwrap(), None);
+        db.put("hello".into(), "value".into()).unwrap();
+        assert_eq!(db.get("hello".into()).unwrap(), Some("value".into()));
+    }
+
+    #[test]
+    fn different_keys() {
+        let dir = TempDir::new("rocksdb-trie").unwrap();
+        let inner_db = new_db(&dir);
+        let db_a = RocksDBTrieDB::new_with_fixed_key(inner_db.clone(), NODES, [5; 32]);
+        let db_b = RocksDBTrieDB::new_with_fixed_key(inner_db, NODES, [7; 32]);
+        db_a.put("hello".into(), "hello!".into()).unwrap();
+        db_b.put_batch(vec![("hello".into(), "go away!".into())])
+            .unwrap();
+        assert_eq!(db_a.get("hello".into()).unwrap(), Some("hello!".into()));
+        assert_eq!(db_b.get("hello".into()).unwrap(), Some("go away!".into()));
+    }
+
+    #[test]
+    fn missing_column_family() {
+        let dir = TempDir::new("rocksdb-trie").unwrap();
+        let db = RocksDBTrieDB::new(new_db(&dir), "Missing");
+        assert!(matches!(
+            db.get("hello".into()),
+            Err(TrieError::RocksDBMissingColumnFamily(_))
+        ));
+    }
+}
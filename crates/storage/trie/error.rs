@@ -21,6 +21,12 @@ pub enum TrieError {
     #[error("Redb Transaction error: {0}")]
     #[cfg(feature = "redb")]
     RedbTransactionError(#[from] TransactionError),
+    #[cfg(feature = "rocksdb")]
+    #[error("RocksDB error: {0}")]
+    RocksDBError(#[from] rocksdb::Error),
+    #[cfg(feature = "rocksdb")]
+    #[error("RocksDB column family {0} not found")]
+    RocksDBMissingColumnFamily(String),
     #[error(transparent)]
     RLPDecode(#[from] RLPDecodeError),
     #[error("Verification Error: {0}")]
@@ -0,0 +1,485 @@
+use std::{collections::BTreeMap, fmt};
+
+use ethereum_types::H256;
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+use serde::Serialize;
+
+use crate::{
+    nibbles::Nibbles, node::Node, node_hash::NodeHash, PathRLP, Trie, TrieError, ValueRLP,
+};
+
+/// Node and value counts of a trie
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TrieStats {
+    pub branch_nodes: usize,
+    pub extension_nodes: usize,
+    pub leaf_nodes: usize,
+    /// Nodes embedded in their parent instead of being stored by hash
+    pub inline_nodes: usize,
+    pub values: usize,
+    /// Size of the stored encoding of every node stored by hash
+    pub stored_bytes: usize,
+    /// Amount of values held by nodes at each depth, the root being at depth 0
+    pub depth_histogram: BTreeMap<usize, usize>,
+}
+
+/// Problem found while checking the nodes of a trie
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrieIssue {
+    /// A node referenced by its parent is not stored
+    MissingNode { path: Nibbles, hash: H256 },
+    /// A stored node doesn't hash to the hash it is referenced by
+    HashMismatch {
+        path: Nibbles,
+        expected: H256,
+        found: H256,
+    },
+    /// A node can't be decoded
+    InvalidNode { path: Nibbles, hash: NodeHash },
+}
+
+impl fmt::Display for TrieIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrieIssue::MissingNode { path, hash } => {
+                write!(f, "missing node {hash:#x} at path {}", path_to_hex(path))
+            }
+            TrieIssue::HashMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "node at path {} hashes to {found:#x} instead of {expected:#x}",
+                path_to_hex(path)
+            ),
+            TrieIssue::InvalidNode { path, hash } => write!(
+                f,
+                "undecodable node {} at path {}",
+                hex::encode(hash),
+                path_to_hex(path)
+            ),
+        }
+    }
+}
+
+/// Formats the nibbles of a path as hex digits
+fn path_to_hex(path: &Nibbles) -> String {
+    path.as_ref()
+        .iter()
+        .filter_map(|nibble| char::from_digit(*nibble as u32, 16))
+        .collect()
+}
+
+/// Value of a path that differs between two tries, missing from one of them if `None`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieDiff {
+    pub path: PathRLP,
+    pub left: Option<ValueRLP>,
+    pub right: Option<ValueRLP>,
+}
+
+impl Trie {
+    /// Counts the nodes and values reachable from the root
+    pub fn stats(&self) -> Result<TrieStats, TrieError> {
+        let mut stats = TrieStats::default();
+        let mut stack: Vec<(NodeHash, usize)> =
+            self.root.iter().map(|root| (root.clone(), 0)).collect();
+        while let Some((hash, depth)) = stack.pop() {
+            let node = self
+                .state
+                .get_node(hash.clone())?
+                .ok_or(TrieError::InconsistentTree)?;
+            match hash {
+                NodeHash::Inline(_) => stats.inline_nodes += 1,
+                NodeHash::Hashed(_) => stats.stored_bytes += node.encode_to_vec().len(),
+            }
+            let has_value = match &node {
+                Node::Branch(branch) => {
+                    stats.branch_nodes += 1;
+                    stack.extend(
+                        branch
+                            .choices
+                            .iter()
+                            .filter(|child| child.is_valid())
+                            .map(|child| (child.clone(), depth + 1)),
+                    );
+                    !branch.value.is_empty()
+                }
+                Node::Extension(extension) => {
+                    stats.extension_nodes += 1;
+                    stack.push((extension.child.clone(), depth + 1));
+                    false
+                }
+                Node::Leaf(_) => {
+                    stats.leaf_nodes += 1;
+                    true
+                }
+            };
+            if has_value {
+                stats.values += 1;
+                *stats.depth_histogram.entry(depth).or_default() += 1;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Checks that every node reachable from the root is stored in the DB and hashes to the hash
+    /// it is referenced by, returning the problems found. Nodes are read from the DB directly,
+    /// bypassing the trie's cache, and subtries under missing or corrupted nodes are skipped
+    pub fn check(&self) -> Result<Vec<TrieIssue>, TrieError> {
+        let mut issues = vec![];
+        let mut stack: Vec<(Nibbles, NodeHash)> = self
+            .root
+            .iter()
+            .map(|root| (Nibbles::default(), root.clone()))
+            .collect();
+        while let Some((path, hash)) = stack.pop() {
+            let node = match &hash {
+                NodeHash::Inline(encoded) => Node::decode_raw(encoded).ok(),
+                NodeHash::Hashed(expected) => {
+                    let Some(encoded) = self.state.db().get(expected.as_bytes().to_vec())? else {
+                        issues.push(TrieIssue::MissingNode {
+                            path,
+                            hash: *expected,
+                        });
+                        continue;
+                    };
+                    match Node::decode(&encoded) {
+                        Ok(node) => match node.compute_hash() {
+                            NodeHash::Hashed(found) if found != *expected => {
+                                issues.push(TrieIssue::HashMismatch {
+                                    path,
+                                    expected: *expected,
+                                    found,
+                                });
+                                continue;
+                            }
+                            _ => Some(node),
+                        },
+                        Err(_) => None,
+                    }
+                }
+            };
+            let Some(node) = node else {
+                issues.push(TrieIssue::InvalidNode { path, hash });
+                continue;
+            };
+            match node {
+                Node::Branch(branch) => {
+                    for (choice, child) in branch.choices.iter().enumerate() {
+                        if child.is_valid() {
+                            let mut child_path = path.clone();
+                            child_path.append(choice as u8);
+                            stack.push((child_path, child.clone()));
+                        }
+                    }
+                }
+                Node::Extension(extension) => {
+                    let mut child_path = path.clone();
+                    child_path.extend(&extension.prefix);
+                    stack.push((child_path, extension.child));
+                }
+                Node::Leaf(_) => {}
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Returns the paths whose values differ between this trie and another one, sorted by path.
+    /// Subtries with the same hash in both tries are skipped, so the nodes read are proportional
+    /// to the difference between the tries rather than to their size
+    pub fn diff<'a>(&'a self, other: &'a Trie) -> TrieDiffIterator<'a> {
+        TrieDiffIterator {
+            left: NodeCursor::new(self),
+            right: NodeCursor::new(other),
+        }
+    }
+}
+
+/// Item found while traversing a trie, either a node or a value held by a node
+enum CursorItem {
+    Node(NodeHash),
+    Value(ValueRLP),
+}
+
+impl CursorItem {
+    /// Nodes go before the values they hold when they share a path
+    fn kind(&self) -> u8 {
+        match self {
+            CursorItem::Node(_) => 0,
+            CursorItem::Value(_) => 1,
+        }
+    }
+}
+
+/// Traverses a trie in path order, yielding each node at the path it is referenced from and
+/// each value at the full path of its key
+struct NodeCursor<'a> {
+    trie: &'a Trie,
+    // Items to visit, in reverse order
+    stack: Vec<(Vec<u8>, CursorItem)>,
+}
+
+impl<'a> NodeCursor<'a> {
+    fn new(trie: &'a Trie) -> Self {
+        let stack = trie
+            .root
+            .iter()
+            .map(|root| (vec![], CursorItem::Node(root.clone())))
+            .collect();
+        Self { trie, stack }
+    }
+
+    fn peek(&self) -> Option<(&[u8], u8)> {
+        self.stack
+            .last()
+            .map(|(path, item)| (path.as_slice(), item.kind()))
+    }
+
+    /// Moves past the current item, visiting the children of the current node if `descend`
+    fn advance(&mut self, descend: bool) -> Result<Option<(Vec<u8>, CursorItem)>, TrieError> {
+        let Some((path, item)) = self.stack.pop() else {
+            return Ok(None);
+        };
+        let CursorItem::Node(hash) = &item else {
+            return Ok(Some((path, item)));
+        };
+        if !descend {
+            return Ok(Some((path, item)));
+        }
+        let node = self
+            .trie
+            .state
+            .get_node(hash.clone())?
+            .ok_or(TrieError::InconsistentTree)?;
+        let child_path = |nibbles: &[u8]| {
+            let mut child_path = path.clone();
+            child_path.extend(nibbles.iter().filter(|nibble| **nibble != 16));
+            child_path
+        };
+        match node {
+            Node::Branch(branch) => {
+                for (choice, child) in branch.choices.iter().enumerate().rev() {
+                    if child.is_valid() {
+                        self.stack
+                            .push((child_path(&[choice as u8]), CursorItem::Node(child.clone())));
+                    }
+                }
+                if !branch.value.is_empty() {
+                    self.stack
+                        .push((path.clone(), CursorItem::Value(branch.value)));
+                }
+            }
+            Node::Extension(extension) => self.stack.push((
+                child_path(extension.prefix.as_ref()),
+                CursorItem::Node(extension.child),
+            )),
+            Node::Leaf(leaf) => self.stack.push((
+                child_path(leaf.partial.as_ref()),
+                CursorItem::Value(leaf.value),
+            )),
+        }
+        Ok(Some((path, item)))
+    }
+}
+
+/// Iterator over the differences between two tries, see [Trie::diff]
+pub struct TrieDiffIterator<'a> {
+    left: NodeCursor<'a>,
+    right: NodeCursor<'a>,
+}
+
+impl TrieDiffIterator<'_> {
+    fn next_diff(&mut self) -> Result<Option<TrieDiff>, TrieError> {
+        loop {
+            let order = match (self.left.peek(), self.right.peek()) {
+                (None, None) => return Ok(None),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (Some(left), Some(right)) => left.cmp(&right),
+            };
+            let diff = match order {
+                // Items only found in one of the tries
+                std::cmp::Ordering::Less => match self.left.advance(true)? {
+                    Some((path, CursorItem::Value(value))) => Some(TrieDiff {
+                        path: nibbles_to_path(path),
+                        left: Some(value),
+                        right: None,
+                    }),
+                    _ => None,
+                },
+                std::cmp::Ordering::Greater => match self.right.advance(true)? {
+                    Some((path, CursorItem::Value(value))) => Some(TrieDiff {
+                        path: nibbles_to_path(path),
+                        left: None,
+                        right: Some(value),
+                    }),
+                    _ => None,
+                },
+                std::cmp::Ordering::Equal => {
+                    let same_node = matches!(
+                        (self.left.stack.last(), self.right.stack.last()),
+                        (Some((_, CursorItem::Node(left))), Some((_, CursorItem::Node(right))))
+                            if left == right
+                    );
+                    match (
+                        self.left.advance(!same_node)?,
+                        self.right.advance(!same_node)?,
+                    ) {
+                        (
+                            Some((path, CursorItem::Value(left))),
+                            Some((_, CursorItem::Value(right))),
+                        ) if left != right => Some(TrieDiff {
+                            path: nibbles_to_path(path),
+                            left: Some(left),
+                            right: Some(right),
+                        }),
+                        _ => None,
+                    }
+                }
+            };
+            if diff.is_some() {
+                return Ok(diff);
+            }
+        }
+    }
+}
+
+impl Iterator for TrieDiffIterator<'_> {
+    type Item = Result<TrieDiff, TrieError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_diff().transpose()
+    }
+}
+
+fn nibbles_to_path(nibbles: Vec<u8>) -> PathRLP {
+    Nibbles::from_hex(nibbles).to_bytes()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{BTreeSet, HashMap},
+        sync::{Arc, Mutex},
+    };
+
+    use proptest::{
+        collection::{btree_map, vec},
+        prelude::any,
+        proptest,
+        sample::Index,
+    };
+
+    use super::*;
+    use crate::InMemoryTrieDB;
+
+    fn new_trie(db: &Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>) -> Trie {
+        Trie::new(Box::new(InMemoryTrieDB::new(db.clone())))
+    }
+
+    fn build_trie(values: &[(Vec<u8>, Vec<u8>)]) -> Trie {
+        let mut trie = new_trie(&Default::default());
+        for (path, value) in values {
+            trie.insert(path.clone(), value.clone()).unwrap();
+        }
+        trie.hash().unwrap();
+        trie
+    }
+
+    #[test]
+    fn stats_of_small_trie() {
+        let trie = build_trie(&[
+            (vec![0x00; 32], vec![1; 40]),
+            (vec![0x01; 32], vec![2; 40]),
+            (vec![0x10; 32], vec![3; 40]),
+        ]);
+        let stats = trie.stats().unwrap();
+        assert_eq!(stats.branch_nodes, 2);
+        assert_eq!(stats.leaf_nodes, 3);
+        assert_eq!(stats.values, 3);
+        assert_eq!(stats.depth_histogram, BTreeMap::from([(1, 1), (2, 2)]));
+        assert!(stats.stored_bytes > 120);
+    }
+
+    #[test]
+    fn check_detects_missing_and_corrupted_nodes() {
+        let db = Arc::new(Mutex::new(HashMap::new()));
+        let mut trie = new_trie(&db);
+        for i in 0..50u8 {
+            trie.insert(vec![i; 32], vec![i; 40]).unwrap();
+        }
+        let root = trie.hash().unwrap();
+        let trie = Trie::open(Box::new(InMemoryTrieDB::new(db.clone())), root);
+        assert!(trie.check().unwrap().is_empty());
+
+        // Remove a child of the root and replace another one with a different node
+        let Node::Branch(root_node) = trie.state.get_node(root.into()).unwrap().unwrap() else {
+            panic!("Root should be a branch");
+        };
+        let [missing, corrupted, other] = [0, 1, 2].map(|i| root_node.choices[i].clone());
+        {
+            let mut db = db.lock().unwrap();
+            db.remove(missing.as_ref());
+            let other_node = db[other.as_ref()].clone();
+            db.insert(corrupted.as_ref().to_vec(), other_node);
+        }
+        let issues = trie.check().unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                TrieIssue::HashMismatch {
+                    path: Nibbles::from_hex(vec![1]),
+                    expected: corrupted.finalize(),
+                    found: other.finalize(),
+                },
+                TrieIssue::MissingNode {
+                    path: Nibbles::from_hex(vec![0]),
+                    hash: missing.finalize(),
+                },
+            ]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_diff_matches_values(
+            left in btree_map(any::<[u8; 32]>(), any::<u8>(), 0..100),
+            removed in vec(any::<Index>(), 0..20),
+            updated in vec((any::<Index>(), any::<u8>()), 0..20),
+            added in btree_map(any::<[u8; 32]>(), any::<u8>(), 0..20),
+        ) {
+            let mut right = left.clone();
+            if !left.is_empty() {
+                let paths: Vec<_> = left.keys().copied().collect();
+                for index in removed {
+                    right.remove(index.get(&paths));
+                }
+                for (index, value) in updated {
+                    right.insert(*index.get(&paths), value);
+                }
+            }
+            right.extend(added);
+
+            let as_values = |map: &BTreeMap<[u8; 32], u8>| -> Vec<(Vec<u8>, Vec<u8>)> {
+                map.iter().map(|(path, value)| (path.to_vec(), vec![*value; 40])).collect()
+            };
+            let left_trie = build_trie(&as_values(&left));
+            let right_trie = build_trie(&as_values(&right));
+
+            let paths: BTreeSet<_> = left.keys().chain(right.keys()).collect();
+            let expected: Vec<_> = paths
+                .into_iter()
+                .map(|path| TrieDiff {
+                    path: path.to_vec(),
+                    left: left.get(path).map(|value| vec![*value; 40]),
+                    right: right.get(path).map(|value| vec![*value; 40]),
+                })
+                .filter(|diff| diff.left != diff.right)
+                .collect();
+            let diff: Vec<_> = left_trie.diff(&right_trie).collect::<Result<_, _>>().unwrap();
+            assert_eq!(diff, expected);
+        }
+    }
+}
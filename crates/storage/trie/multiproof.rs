@@ -0,0 +1,362 @@
+use std::collections::BTreeSet;
+
+use ethereum_types::H256;
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    nibbles::Nibbles, node::Node, node_hash::NodeHash, state::TrieState, NodeRLP, PathRLP, Trie,
+    TrieError, ValueRLP, EMPTY_TRIE_HASH,
+};
+
+/// Proof of the values (or absence) of multiple paths of a trie, containing every node traversed
+/// to reach them only once.
+/// Besides the nodes along the paths, it contains the remaining child of every branch node that
+/// would be collapsed by removing them, so the proof can also be used to compute the root of the
+/// trie after updating or removing any of the proven paths.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiProof {
+    /// Encoded root node, None if the trie is empty
+    root: Option<NodeRLP>,
+    /// Encoded nodes referenced by hash, sorted and without duplicates
+    nodes: Vec<NodeRLP>,
+}
+
+impl MultiProof {
+    /// Returns the hash of the root of the trie the proof belongs to
+    pub fn root_hash(&self) -> H256 {
+        self.root
+            .as_ref()
+            .map(|root| NodeHash::from_encoded_raw(root.clone()).finalize())
+            .unwrap_or(*EMPTY_TRIE_HASH)
+    }
+
+    /// Returns the encoded nodes of the proof, starting with the root
+    pub fn nodes(&self) -> impl Iterator<Item = &NodeRLP> {
+        self.root.iter().chain(self.nodes.iter())
+    }
+
+    /// Builds a stateless trie holding only the nodes of the proof.
+    /// Reading or updating a path that isn't covered by the proof will fail with
+    /// [TrieError::InconsistentTree]
+    pub fn trie(&self) -> Result<Trie, TrieError> {
+        Trie::from_nodes(self.root.as_ref(), &self.nodes)
+    }
+
+    /// Checks that the proof belongs to the trie with the given root and returns the values of
+    /// the given paths, None for the ones that aren't part of the trie.
+    /// Fails if the proof doesn't contain the nodes needed to reach any of the paths
+    pub fn verify(
+        &self,
+        root: H256,
+        paths: &[PathRLP],
+    ) -> Result<Vec<Option<ValueRLP>>, TrieError> {
+        let trie = self.verified_trie(root)?;
+        paths
+            .iter()
+            .map(|path| trie.get(path).map_err(|err| missing_nodes(err, path)))
+            .collect()
+    }
+
+    /// Checks that the proof belongs to the trie with the given root and returns the root of the
+    /// trie after applying the updates, removing the paths without a value.
+    /// Every updated path must be covered by the proof
+    pub fn apply_updates(
+        &self,
+        root: H256,
+        updates: impl IntoIterator<Item = (PathRLP, Option<ValueRLP>)>,
+    ) -> Result<H256, TrieError> {
+        let mut trie = self.verified_trie(root)?;
+        trie.update_batch(updates).map_err(|err| match err {
+            TrieError::InconsistentTree => {
+                TrieError::Verify("proof doesn't cover every updated path".to_string())
+            }
+            err => err,
+        })?;
+        Ok(trie.hash_no_commit())
+    }
+
+    /// Encodes the proof as an RLP list of the raw nodes, the most compact way to send it to
+    /// provers and light clients
+    pub fn encode_compact(&self) -> Vec<u8> {
+        self.encode_to_vec()
+    }
+
+    /// Decodes a proof encoded with [MultiProof::encode_compact]
+    pub fn decode_compact(encoded: &[u8]) -> Result<Self, TrieError> {
+        Ok(Self::decode(encoded)?)
+    }
+
+    fn verified_trie(&self, root: H256) -> Result<Trie, TrieError> {
+        let proof_root = self.root_hash();
+        if proof_root != root {
+            return Err(TrieError::Verify(format!(
+                "proof root {proof_root:#x} doesn't match expected root {root:#x}"
+            )));
+        }
+        self.trie()
+    }
+}
+
+fn missing_nodes(err: TrieError, path: &PathRLP) -> TrieError {
+    match err {
+        TrieError::InconsistentTree => {
+            TrieError::Verify(format!("proof doesn't cover path 0x{}", hex::encode(path)))
+        }
+        err => err,
+    }
+}
+
+impl Trie {
+    /// Obtains a [MultiProof] for the given paths, which can later be used to verify their values
+    /// or compute the root after updating them.
+    /// The proof will still be constructed even if some path is not stored in the trie, proving
+    /// its absence
+    pub fn get_multiproof(&self, paths: &[PathRLP]) -> Result<MultiProof, TrieError> {
+        let Some(root) = self.root.clone() else {
+            return Ok(MultiProof::default());
+        };
+        let root_node = self
+            .state
+            .get_node(root)?
+            .ok_or(TrieError::InconsistentTree)?;
+        let mut nodes = BTreeSet::new();
+        let paths = paths.iter().map(|path| Nibbles::from_bytes(path)).collect();
+        collect_proof_nodes(&self.state, root_node.clone(), paths, &mut nodes)?;
+        Ok(MultiProof {
+            root: Some(root_node.encode_raw()),
+            nodes: nodes.into_iter().collect(),
+        })
+    }
+}
+
+/// Adds the nodes referenced by hash along the paths to the proof nodes, along with the siblings
+/// needed to remove them
+fn collect_proof_nodes(
+    state: &TrieState,
+    node: Node,
+    paths: Vec<Nibbles>,
+    nodes: &mut BTreeSet<NodeRLP>,
+) -> Result<(), TrieError> {
+    match node {
+        Node::Branch(branch) => {
+            let mut child_paths: Vec<Vec<Nibbles>> = vec![Vec::new(); 16];
+            for mut path in paths {
+                if let Some(choice) = path.next_choice() {
+                    child_paths[choice].push(path);
+                }
+            }
+            // Removing the paths may leave the branch with a single child and no value, in which
+            // case the branch is replaced by that child, so it has to be part of the proof even
+            // if no path goes through it
+            let untouched: Vec<_> = branch
+                .choices
+                .iter()
+                .zip(child_paths.iter())
+                .filter(|(child, paths)| child.is_valid() && paths.is_empty())
+                .map(|(child, _)| child)
+                .collect();
+            if let ([sibling], true) = (untouched.as_slice(), branch.value.is_empty()) {
+                add_node(state, (*sibling).clone(), nodes)?;
+            }
+            for (child, paths) in branch.choices.iter().zip(child_paths) {
+                if child.is_valid() && !paths.is_empty() {
+                    let child_node = add_node(state, child.clone(), nodes)?;
+                    collect_proof_nodes(state, child_node, paths, nodes)?;
+                }
+            }
+        }
+        Node::Extension(extension) => {
+            let paths: Vec<_> = paths
+                .into_iter()
+                .filter_map(|mut path| path.skip_prefix(&extension.prefix).then_some(path))
+                .collect();
+            if !paths.is_empty() {
+                let child_node = add_node(state, extension.child, nodes)?;
+                collect_proof_nodes(state, child_node, paths, nodes)?;
+            }
+        }
+        Node::Leaf(_) => {}
+    }
+    Ok(())
+}
+
+/// Reads the node with the given hash, adding it to the proof nodes if it isn't inlined in its
+/// parent
+fn add_node(
+    state: &TrieState,
+    hash: NodeHash,
+    nodes: &mut BTreeSet<NodeRLP>,
+) -> Result<Node, TrieError> {
+    let node = state
+        .get_node(hash.clone())?
+        .ok_or(TrieError::InconsistentTree)?;
+    if let NodeHash::Hashed(_) = hash {
+        nodes.insert(node.encode_raw());
+    }
+    Ok(node)
+}
+
+impl RLPEncode for MultiProof {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        // Nodes are already RLP lists, so they are added as they are
+        self.nodes()
+            .fold(Encoder::new(buf), |encoder, node| encoder.encode_raw(node))
+            .finish()
+    }
+}
+
+impl RLPDecode for MultiProof {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let mut decoder = Decoder::new(rlp)?;
+        let mut nodes = Vec::new();
+        while !decoder.is_done() {
+            let (node, rest) = decoder.get_encoded_item()?;
+            nodes.push(node);
+            decoder = rest;
+        }
+        let mut nodes = nodes.into_iter();
+        let root = nodes.next();
+        let proof = MultiProof {
+            root,
+            nodes: nodes.collect(),
+        };
+        Ok((proof, decoder.finish()?))
+    }
+}
+
+impl Serialize for MultiProof {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let encoded = self.encode_compact();
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("0x{}", hex::encode(encoded)))
+        } else {
+            serializer.serialize_bytes(&encoded)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MultiProof {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = if deserializer.is_human_readable() {
+            let encoded = String::deserialize(deserializer)?;
+            hex::decode(encoded.trim_start_matches("0x")).map_err(D::Error::custom)?
+        } else {
+            Vec::<u8>::deserialize(deserializer)?
+        };
+        Self::decode_compact(&encoded).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::{
+        collection::{btree_map, vec},
+        prelude::*,
+    };
+
+    #[test]
+    fn empty_trie_multiproof() {
+        let trie = Trie::new_temp();
+        let proof = trie.get_multiproof(&[vec![0; 32]]).unwrap();
+        assert_eq!(proof.root_hash(), *EMPTY_TRIE_HASH);
+        assert_eq!(
+            proof.verify(*EMPTY_TRIE_HASH, &[vec![0; 32]]).unwrap(),
+            vec![None]
+        );
+        assert_eq!(
+            MultiProof::decode_compact(&proof.encode_compact()).unwrap(),
+            proof
+        );
+    }
+
+    #[test]
+    fn multiproof_rejects_wrong_root() {
+        let mut trie = Trie::new_temp();
+        trie.insert(vec![1; 32], vec![1]).unwrap();
+        trie.insert(vec![2; 32], vec![2]).unwrap();
+        let proof = trie.get_multiproof(&[vec![1; 32]]).unwrap();
+        assert!(matches!(
+            proof.verify(H256::zero(), &[vec![1; 32]]),
+            Err(TrieError::Verify(_))
+        ));
+    }
+
+    #[test]
+    fn multiproof_rejects_uncovered_path() {
+        let mut trie = Trie::new_temp();
+        for i in 0..64u8 {
+            trie.insert(vec![i; 32], vec![i; 32]).unwrap();
+        }
+        let root = trie.hash().unwrap();
+        let proof = trie.get_multiproof(&[vec![1; 32]]).unwrap();
+        assert!(matches!(
+            proof.verify(root, &[vec![63; 32]]),
+            Err(TrieError::Verify(_))
+        ));
+    }
+
+    #[test]
+    fn multiproof_serde_roundtrip() {
+        let mut trie = Trie::new_temp();
+        for i in 0..16u8 {
+            trie.insert(vec![i * 16; 32], vec![i; 40]).unwrap();
+        }
+        let proof = trie.get_multiproof(&[vec![0; 32], vec![32; 32]]).unwrap();
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<MultiProof>(&json).unwrap(), proof);
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_multiproof_verify(
+            data in btree_map(vec(any::<u8>(), 32), vec(any::<u8>(), 1..40), 1..100),
+            absent in vec(vec(any::<u8>(), 32), 0..10),
+        ) {
+            let mut trie = Trie::new_temp();
+            for (path, value) in data.iter() {
+                trie.insert(path.clone(), value.clone()).unwrap();
+            }
+            let root = trie.hash().unwrap();
+            let paths: Vec<_> = data.keys().step_by(3).cloned().chain(absent).collect();
+            let proof = trie.get_multiproof(&paths).unwrap();
+            let proof = MultiProof::decode_compact(&proof.encode_compact()).unwrap();
+
+            let values = proof.verify(root, &paths).unwrap();
+            for (path, value) in paths.iter().zip(values) {
+                prop_assert_eq!(value.as_ref(), data.get(path));
+            }
+        }
+
+        #[test]
+        fn proptest_multiproof_apply_updates(
+            data in btree_map(vec(any::<u8>(), 32), vec(any::<u8>(), 1..40), 1..100),
+            inserted in btree_map(vec(any::<u8>(), 32), vec(any::<u8>(), 1..40), 0..20),
+        ) {
+            let mut trie = Trie::new_temp();
+            for (path, value) in data.iter() {
+                trie.insert(path.clone(), value.clone()).unwrap();
+            }
+            let root = trie.hash().unwrap();
+            // Remove every other value and insert the new ones
+            let updates: Vec<_> = data
+                .keys()
+                .step_by(2)
+                .map(|path| (path.clone(), None))
+                .chain(inserted.into_iter().map(|(path, value)| (path, Some(value))))
+                .collect();
+            let paths: Vec<_> = updates.iter().map(|(path, _)| path.clone()).collect();
+            let proof = trie.get_multiproof(&paths).unwrap();
+
+            let post_root = proof.apply_updates(root, updates.clone()).unwrap();
+            trie.update_batch(updates).unwrap();
+            prop_assert_eq!(post_root, trie.hash().unwrap());
+        }
+    }
+}
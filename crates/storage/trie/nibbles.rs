@@ -8,7 +8,7 @@ use ethrex_rlp::{
 };
 
 /// Struct representing a list of nibbles (half-bytes)
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Nibbles {
     data: Vec<u8>,
 }
@@ -76,13 +76,15 @@ impl Nibbles {
     }
 
     /// Removes and returns the first nibble
-    pub fn next(&mut self) -> Option<u8> {
+    pub fn next_nibble(&mut self) -> Option<u8> {
         (!self.is_empty()).then(|| self.data.remove(0))
     }
 
     /// Removes and returns the first nibble if it is a suitable choice index (aka < 16)
     pub fn next_choice(&mut self) -> Option<usize> {
-        self.next().filter(|choice| *choice < 16).map(usize::from)
+        self.next_nibble()
+            .filter(|choice| *choice < 16)
+            .map(usize::from)
     }
 
     /// Returns the nibbles after the given offset
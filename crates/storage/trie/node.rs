@@ -111,6 +111,15 @@ impl Node {
         }
     }
 
+    /// Computes the node's hash
+    pub fn compute_hash(&self) -> NodeHash {
+        match self {
+            Node::Branch(n) => n.compute_hash(),
+            Node::Extension(n) => n.compute_hash(),
+            Node::Leaf(n) => n.compute_hash(),
+        }
+    }
+
     /// Decodes the node
     pub fn decode_raw(rlp: &[u8]) -> Result<Self, RLPDecodeError> {
         let mut rlp_items = vec![];
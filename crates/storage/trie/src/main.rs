@@ -1,3 +1,312 @@
-fn main() {
-    println!("Hello, world!");
+//! Inspection tool for the state and storage tries of an ethrex datadir (libmdbx engine)
+//!
+//! Usage: `cargo run -p ethrex-trie --features cli -- --datadir <DATADIR> <COMMAND>`
+
+use std::{path::PathBuf, process::ExitCode, sync::Arc};
+
+use anyhow::{bail, Context};
+use clap::{Args, Parser, Subcommand};
+use ethereum_types::{H256, U256};
+use ethrex_rlp::decode::RLPDecode;
+use ethrex_trie::{LibmdbxDupsortTrieDB, LibmdbxTrieDB, Trie, TrieDiff, TrieStats, ValueRLP};
+use libmdbx::{
+    dupsort,
+    orm::{table, Database},
+    table_info,
+};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+// Same tables as the ones used by the libmdbx storage engine
+table!(
+    /// state trie nodes
+    ( StateTrieNodes ) Vec<u8> => Vec<u8>
+);
+dupsort!(
+    /// Table containing all storage trie's nodes
+    /// Each node is stored by hashed account address and node hash in order to keep different storage trie's nodes separate
+    ( StorageTriesNodes ) ([u8;32], [u8;33])[[u8;32]] => Vec<u8>
+);
+
+#[derive(Parser)]
+#[command(
+    name = "trie",
+    about = "Inspect the state and storage tries of an ethrex datadir"
+)]
+struct Cli {
+    /// Path to the node's database
+    #[arg(long)]
+    datadir: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print every account of a state trie, or every slot of a storage trie, as JSON lines
+    Dump(TrieArgs),
+    /// Count the nodes and values of a trie, along with the depth at which values are found
+    Stats {
+        #[command(flatten)]
+        trie: TrieArgs,
+        /// Also add up the stats of every storage trie of the state
+        #[arg(long)]
+        storage: bool,
+    },
+    /// Check that every node of a trie is stored and hashes to the hash it is referenced by
+    Verify {
+        #[command(flatten)]
+        trie: TrieArgs,
+        /// Also check every storage trie of the state
+        #[arg(long)]
+        storage: bool,
+    },
+    /// Print the accounts that differ between two state roots as JSON lines
+    Diff {
+        left: H256,
+        right: H256,
+        /// Also print the slots that differ for accounts whose storage changed
+        #[arg(long)]
+        storage: bool,
+    },
+}
+
+#[derive(Args)]
+struct TrieArgs {
+    /// State root of the trie to inspect
+    #[arg(long)]
+    root: H256,
+    /// Inspect the storage trie of this account instead, given by address or hashed address
+    #[arg(long)]
+    account: Option<String>,
+}
+
+/// Fields of an account stored in the state trie
+struct Account {
+    nonce: u64,
+    balance: U256,
+    storage_root: H256,
+    code_hash: H256,
+}
+
+impl Account {
+    fn decode(value: &[u8]) -> anyhow::Result<Self> {
+        let (nonce, balance, storage_root, code_hash) = <(u64, U256, H256, H256)>::decode(value)?;
+        Ok(Self {
+            nonce,
+            balance,
+            storage_root,
+            code_hash,
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "nonce": self.nonce,
+            "balance": format!("{:#x}", self.balance),
+            "storageRoot": format!("{:#x}", self.storage_root),
+            "codeHash": format!("{:#x}", self.code_hash),
+        })
+    }
+}
+
+struct Datadir {
+    db: Arc<Database>,
+}
+
+impl Datadir {
+    fn open(path: &PathBuf) -> anyhow::Result<Self> {
+        let tables = [table_info!(StateTrieNodes), table_info!(StorageTriesNodes)]
+            .into_iter()
+            .collect();
+        let db = Database::open(path, &tables)
+            .with_context(|| format!("Failed to open database at {}", path.display()))?;
+        Ok(Self { db: Arc::new(db) })
+    }
+
+    fn state_trie(&self, root: H256) -> Trie {
+        Trie::open(
+            Box::new(LibmdbxTrieDB::<StateTrieNodes>::new(self.db.clone())),
+            root,
+        )
+    }
+
+    fn storage_trie(&self, hashed_address: H256, storage_root: H256) -> Trie {
+        Trie::open(
+            Box::new(LibmdbxDupsortTrieDB::<StorageTriesNodes, [u8; 32]>::new(
+                self.db.clone(),
+                hashed_address.0,
+            )),
+            storage_root,
+        )
+    }
+
+    /// Opens the trie selected by the arguments: the state trie or one of its storage tries
+    fn trie(&self, args: &TrieArgs) -> anyhow::Result<Trie> {
+        let state_trie = self.state_trie(args.root);
+        let Some(account) = &args.account else {
+            return Ok(state_trie);
+        };
+        let hashed_address = parse_account(account)?;
+        let Some(value) = state_trie.get(&hashed_address.as_bytes().to_vec())? else {
+            bail!(
+                "Account {hashed_address:#x} not found in state {:#x}",
+                args.root
+            );
+        };
+        let account = Account::decode(&value)?;
+        Ok(self.storage_trie(hashed_address, account.storage_root))
+    }
+
+    /// Returns every account of the state trie with its hashed address
+    fn accounts(&self, root: H256) -> impl Iterator<Item = anyhow::Result<(H256, Account)>> {
+        self.state_trie(root)
+            .into_iter()
+            .content()
+            .map(|(path, value)| Ok((H256::from_slice(&path), Account::decode(&value)?)))
+    }
+}
+
+/// Parses an address, which is hashed, or an already hashed address
+fn parse_account(account: &str) -> anyhow::Result<H256> {
+    let bytes = hex::decode(account.trim_start_matches("0x"))?;
+    match bytes.len() {
+        20 => Ok(H256::from_slice(&Keccak256::digest(&bytes))),
+        32 => Ok(H256::from_slice(&bytes)),
+        _ => bail!("Expected an address or a hashed address"),
+    }
+}
+
+fn storage_value_to_json(value: Option<&ValueRLP>) -> anyhow::Result<Value> {
+    value
+        .map(|value| Ok(json!(format!("{:#x}", U256::decode(value)?))))
+        .unwrap_or(Ok(Value::Null))
+}
+
+fn account_to_json(value: Option<&ValueRLP>) -> anyhow::Result<Value> {
+    value
+        .map(|value| Ok(Account::decode(value)?.to_json()))
+        .unwrap_or(Ok(Value::Null))
+}
+
+fn dump(datadir: &Datadir, args: &TrieArgs) -> anyhow::Result<()> {
+    let trie = datadir.trie(args)?;
+    for (path, value) in trie.into_iter().content() {
+        let mut entry = if args.account.is_some() {
+            json!({ "value": storage_value_to_json(Some(&value))? })
+        } else {
+            account_to_json(Some(&value))?
+        };
+        entry["hash"] = json!(format!("0x{}", hex::encode(path)));
+        println!("{entry}");
+    }
+    Ok(())
+}
+
+fn add_stats(total: &mut TrieStats, stats: TrieStats) {
+    total.branch_nodes += stats.branch_nodes;
+    total.extension_nodes += stats.extension_nodes;
+    total.leaf_nodes += stats.leaf_nodes;
+    total.inline_nodes += stats.inline_nodes;
+    total.values += stats.values;
+    total.stored_bytes += stats.stored_bytes;
+    for (depth, count) in stats.depth_histogram {
+        *total.depth_histogram.entry(depth).or_default() += count;
+    }
+}
+
+fn stats(datadir: &Datadir, args: &TrieArgs, storage: bool) -> anyhow::Result<()> {
+    let stats = datadir.trie(args)?.stats()?;
+    let mut output = json!({ "trie": stats });
+    if storage && args.account.is_none() {
+        let mut storage_stats = TrieStats::default();
+        for account in datadir.accounts(args.root) {
+            let (hashed_address, account) = account?;
+            let trie = datadir.storage_trie(hashed_address, account.storage_root);
+            add_stats(&mut storage_stats, trie.stats()?);
+        }
+        output["storage"] = json!(storage_stats);
+    }
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Prints every issue found and returns their amount
+fn verify(datadir: &Datadir, args: &TrieArgs, storage: bool) -> anyhow::Result<usize> {
+    let mut issue_count = 0;
+    for issue in datadir.trie(args)?.check()? {
+        println!("{issue}");
+        issue_count += 1;
+    }
+    if storage && args.account.is_none() {
+        for account in datadir.accounts(args.root) {
+            let (hashed_address, account) = account?;
+            let trie = datadir.storage_trie(hashed_address, account.storage_root);
+            for issue in trie.check()? {
+                println!("storage of account {hashed_address:#x}: {issue}");
+                issue_count += 1;
+            }
+        }
+    }
+    Ok(issue_count)
+}
+
+fn diff(datadir: &Datadir, left: H256, right: H256, storage: bool) -> anyhow::Result<()> {
+    let left_trie = datadir.state_trie(left);
+    let right_trie = datadir.state_trie(right);
+    for account_diff in left_trie.diff(&right_trie) {
+        let TrieDiff { path, left, right } = account_diff?;
+        let hashed_address = H256::from_slice(&path);
+        let mut entry = json!({
+            "hash": format!("{hashed_address:#x}"),
+            "left": account_to_json(left.as_ref())?,
+            "right": account_to_json(right.as_ref())?,
+        });
+        if storage {
+            let storage_root = |value: Option<ValueRLP>| -> anyhow::Result<H256> {
+                Ok(value
+                    .map(|value| Account::decode(&value))
+                    .transpose()?
+                    .map(|account| account.storage_root)
+                    .unwrap_or(*ethrex_trie::EMPTY_TRIE_HASH))
+            };
+            let left_storage = datadir.storage_trie(hashed_address, storage_root(left)?);
+            let right_storage = datadir.storage_trie(hashed_address, storage_root(right)?);
+            let mut slots = vec![];
+            for slot_diff in left_storage.diff(&right_storage) {
+                let TrieDiff { path, left, right } = slot_diff?;
+                slots.push(json!({
+                    "slot": format!("0x{}", hex::encode(path)),
+                    "left": storage_value_to_json(left.as_ref())?,
+                    "right": storage_value_to_json(right.as_ref())?,
+                }));
+            }
+            entry["storage"] = json!(slots);
+        }
+        println!("{entry}");
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    let cli = Cli::parse();
+    let datadir = Datadir::open(&cli.datadir)?;
+    match cli.command {
+        Command::Dump(args) => dump(&datadir, &args)?,
+        Command::Stats { trie, storage } => stats(&datadir, &trie, storage)?,
+        Command::Verify { trie, storage } => {
+            let issue_count = verify(&datadir, &trie, storage)?;
+            if issue_count > 0 {
+                eprintln!("Found {issue_count} issues");
+                return Ok(ExitCode::FAILURE);
+            }
+            eprintln!("No issues found");
+        }
+        Command::Diff {
+            left,
+            right,
+            storage,
+        } => diff(&datadir, left, right, storage)?,
+    }
+    Ok(ExitCode::SUCCESS)
 }
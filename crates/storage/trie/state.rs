@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::error::TrieError;
 use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
@@ -8,21 +8,59 @@ use super::db::TrieDB;
 /// Libmdbx database representing the trie state
 /// It contains a table mapping node hashes to rlp encoded nodes
 /// All nodes are stored in the DB and no node is ever removed
-use super::{node::Node, node_hash::NodeHash};
+use super::{node::Node, node_hash::NodeHash, NodeChanges};
 pub struct TrieState {
-    db: Box<dyn TrieDB>,
+    db: Arc<dyn TrieDB>,
     cache: HashMap<NodeHash, Node>,
+    /// Cache of the state this one was forked from, read-only while the fork is alive
+    parent_cache: Option<Arc<HashMap<NodeHash, Node>>>,
 }
 
 impl TrieState {
     /// Creates a TrieState referring to a db.
     pub fn new(db: Box<dyn TrieDB>) -> TrieState {
         TrieState {
-            db,
+            db: db.into(),
             cache: Default::default(),
+            parent_cache: None,
         }
     }
 
+    /// Moves the cached nodes out of the state so that they can be shared with its forks
+    #[cfg(feature = "parallel")]
+    pub(crate) fn take_cache(&mut self) -> Arc<HashMap<NodeHash, Node>> {
+        Arc::new(std::mem::take(&mut self.cache))
+    }
+
+    /// Creates a state over the same DB that also reads the nodes cached by its parent, to update
+    /// a subtrie in another thread. The nodes it inserts are kept in its own cache
+    #[cfg(feature = "parallel")]
+    pub(crate) fn fork(&self, parent_cache: Arc<HashMap<NodeHash, Node>>) -> TrieState {
+        TrieState {
+            db: self.db.clone(),
+            cache: Default::default(),
+            parent_cache: Some(parent_cache),
+        }
+    }
+
+    /// Restores the cache taken to fork the state and adds the nodes cached by the forks
+    #[cfg(feature = "parallel")]
+    pub(crate) fn merge(
+        &mut self,
+        parent_cache: Arc<HashMap<NodeHash, Node>>,
+        forks: impl IntoIterator<Item = TrieState>,
+    ) {
+        self.cache = Arc::try_unwrap(parent_cache).unwrap_or_else(|cache| (*cache).clone());
+        for fork in forks {
+            self.cache.extend(fork.cache);
+        }
+    }
+
+    /// Returns a reference to the underlying DB
+    pub fn db(&self) -> &dyn TrieDB {
+        self.db.as_ref()
+    }
+
     /// Retrieves a node based on its hash
     pub fn get_node(&self, hash: NodeHash) -> Result<Option<Node>, TrieError> {
         // Decode the node if it is inlined
@@ -32,6 +70,13 @@ impl TrieState {
         if let Some(node) = self.cache.get(&hash) {
             return Ok(Some(node.clone()));
         };
+        if let Some(node) = self
+            .parent_cache
+            .as_ref()
+            .and_then(|cache| cache.get(&hash))
+        {
+            return Ok(Some(node.clone()));
+        };
         self.db
             .get(hash.into())?
             .map(|rlp| Node::decode(&rlp).map_err(TrieError::RLPDecode))
@@ -49,26 +94,24 @@ impl TrieState {
     /// Commits cache changes to DB and clears it
     /// Only writes nodes that follow the root's canonical trie
     pub fn commit(&mut self, root: &NodeHash) -> Result<(), TrieError> {
-        self.commit_node(root)?;
-        self.cache.clear();
-        Ok(())
+        let to_commit = self.take_changes(root)?;
+        self.db.put_batch(to_commit)
     }
 
-    // Writes a node and its children into the DB
-    fn commit_node(&mut self, node_hash: &NodeHash) -> Result<(), TrieError> {
+    /// Clears the cache and returns the encoded nodes that `commit` would write to the DB,
+    /// so that they can be written by the caller
+    pub fn take_changes(&mut self, root: &NodeHash) -> Result<NodeChanges, TrieError> {
         let mut to_commit = vec![];
-        self.commit_node_tail_recursive(node_hash, &mut to_commit)?;
-
-        self.db.put_batch(to_commit)?;
-
-        Ok(())
+        self.commit_node_tail_recursive(root, &mut to_commit)?;
+        self.cache.clear();
+        Ok(to_commit)
     }
 
     // Writes a node and its children into the DB
     fn commit_node_tail_recursive(
         &mut self,
         node_hash: &NodeHash,
-        acc: &mut Vec<(Vec<u8>, Vec<u8>)>,
+        acc: &mut NodeChanges,
     ) -> Result<(), TrieError> {
         let Some(node) = self.cache.remove(node_hash) else {
             // If the node is not in the cache then it means it is already stored in the DB
@@ -1,5 +1,7 @@
 pub mod db;
 mod error;
+mod inspect;
+mod multiproof;
 mod nibbles;
 mod node;
 mod node_hash;
@@ -11,20 +13,24 @@ mod trie_iter;
 mod verify_range;
 use ethereum_types::H256;
 use ethrex_rlp::constants::RLP_NULL;
-use nibbles::Nibbles;
-use node::Node;
-use node_hash::NodeHash;
 use sha3::{Digest, Keccak256};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
+#[cfg(feature = "rocksdb")]
+pub use self::db::rocksdb::RocksDBTrieDB;
 #[cfg(feature = "libmdbx")]
 pub use self::db::{libmdbx::LibmdbxTrieDB, libmdbx_dupsort::LibmdbxDupsortTrieDB};
 
 pub use self::db::{in_memory::InMemoryTrieDB, TrieDB};
+pub use self::nibbles::Nibbles;
+pub use self::node::{BranchNode, ExtensionNode, LeafNode, Node};
+pub use self::node_hash::NodeHash;
 pub use self::verify_range::verify_range;
 
 pub use self::error::TrieError;
-use self::{node::LeafNode, state::TrieState, trie_iter::TrieIterator};
+pub use self::inspect::{TrieDiff, TrieDiffIterator, TrieIssue, TrieStats};
+pub use self::multiproof::MultiProof;
+use self::{state::TrieState, trie_iter::TrieIterator};
 
 use lazy_static::lazy_static;
 
@@ -44,6 +50,12 @@ pub type PathRLP = Vec<u8>;
 pub type ValueRLP = Vec<u8>;
 /// RLP-encoded trie node
 pub type NodeRLP = Vec<u8>;
+/// RLP-encoded trie nodes along with their encoded hashes, as written to the DB
+pub type NodeChanges = Vec<(Vec<u8>, NodeRLP)>;
+
+/// Minimum number of insertions in a batch for them to be applied in parallel
+#[cfg(feature = "parallel")]
+const PARALLEL_INSERTIONS_THRESHOLD: usize = 64;
 
 /// Libmdx-based Ethereum Compatible Merkle Patricia Trie
 pub struct Trie {
@@ -124,6 +136,103 @@ impl Trie {
         }
     }
 
+    /// Applies a batch of updates to the trie, inserting the paths with a value and removing the
+    /// ones without. If a path is repeated, its last update is the one applied.
+    /// With the `parallel` feature, large batches of insertions are split among the children of
+    /// the root branch, which are updated and hashed in parallel
+    pub fn update_batch(
+        &mut self,
+        updates: impl IntoIterator<Item = (PathRLP, Option<ValueRLP>)>,
+    ) -> Result<(), TrieError> {
+        let updates: BTreeMap<PathRLP, Option<ValueRLP>> = updates.into_iter().collect();
+        let mut insertions = Vec::new();
+        let mut removals = Vec::new();
+        for (path, value) in updates {
+            match value {
+                Some(value) => insertions.push((path, value)),
+                None => removals.push(path),
+            }
+        }
+        #[cfg(feature = "parallel")]
+        if insertions.len() >= PARALLEL_INSERTIONS_THRESHOLD {
+            self.insert_parallel(std::mem::take(&mut insertions))?;
+        }
+        for (path, value) in insertions {
+            self.insert(path, value)?;
+        }
+        // Removals may collapse the root branch, so they are always applied sequentially
+        for path in removals {
+            self.remove(path)?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the values into the subtries under the root branch in parallel, each of them
+    /// working on a fork of the trie state.
+    /// Values are inserted sequentially until the root is a branch
+    #[cfg(feature = "parallel")]
+    fn insert_parallel(&mut self, insertions: Vec<(PathRLP, ValueRLP)>) -> Result<(), TrieError> {
+        use rayon::prelude::*;
+
+        let mut insertions = insertions.into_iter();
+        let mut branch = loop {
+            let root_node = self
+                .root
+                .clone()
+                .map(|root| self.state.get_node(root))
+                .transpose()?
+                .flatten();
+            match root_node {
+                Some(Node::Branch(branch)) => break branch,
+                _ => match insertions.next() {
+                    Some((path, value)) => self.insert(path, value)?,
+                    None => return Ok(()),
+                },
+            }
+        };
+
+        let mut subtrie_insertions: Vec<Vec<(Nibbles, ValueRLP)>> = vec![Vec::new(); 16];
+        for (path, value) in insertions {
+            let mut path = Nibbles::from_bytes(&path);
+            match path.next_choice() {
+                Some(choice) => subtrie_insertions[choice].push((path, value)),
+                None => branch.update(value),
+            }
+        }
+
+        let parent_cache = self.state.take_cache();
+        let results: Vec<_> = subtrie_insertions
+            .into_par_iter()
+            .enumerate()
+            .filter(|(_, insertions)| !insertions.is_empty())
+            .map(|(choice, insertions)| {
+                let mut state = self.state.fork(parent_cache.clone());
+                let child =
+                    insert_into_subtrie(&mut state, branch.choices[choice].clone(), insertions)?;
+                Ok((choice, child, state))
+            })
+            .collect();
+
+        // The nodes of every fork are merged before returning any error to keep the cache intact
+        let mut forks = Vec::with_capacity(results.len());
+        let mut error = None;
+        for result in results {
+            match result {
+                Ok((choice, child, state)) => {
+                    branch.choices[choice] = child;
+                    forks.push(state);
+                }
+                Err(err) => error = error.or(Some(err)),
+            }
+        }
+        self.state.merge(parent_cache, forks);
+        if let Some(error) = error {
+            return Err(error);
+        }
+        self.root = Some(Node::from(branch).insert_self(&mut self.state)?);
+        Ok(())
+    }
+
     /// Return the hash of the trie's root node.
     /// Returns keccak(RLP_NULL) if the trie is empty
     /// Also commits changes to the DB
@@ -138,6 +247,17 @@ impl Trie {
             .unwrap_or(*EMPTY_TRIE_HASH))
     }
 
+    /// Return the hash of the trie's root node along with the encoded nodes that `hash` would
+    /// commit to the DB, leaving them to be written by the caller.
+    /// Until they are written, the trie's new nodes can no longer be read
+    pub fn hash_and_take_changes(&mut self) -> Result<(H256, NodeChanges), TrieError> {
+        let changes = match &self.root {
+            Some(root) => self.state.take_changes(root)?,
+            None => vec![],
+        };
+        Ok((self.hash_no_commit(), changes))
+    }
+
     /// Return the hash of the trie's root node.
     /// Returns keccak(RLP_NULL) if the trie is empty
     pub fn hash_no_commit(&self) -> H256 {
@@ -256,6 +376,12 @@ impl Trie {
         Trie::new(Box::new(NullTrieDB))
     }
 
+    /// Returns a reference to the trie's underlying DB
+    /// Can be used to read or write nodes directly without going through the trie's cache
+    pub fn db(&self) -> &dyn TrieDB {
+        self.state.db()
+    }
+
     /// Obtain the encoded node given its path.
     /// Allows usage of full paths (byte slice of 32 bytes) or compact-encoded nibble slices (with length lower than 32)
     pub fn get_node(&self, partial_path: &PathRLP) -> Result<Vec<u8>, TrieError> {
@@ -343,6 +469,27 @@ impl Trie {
     }
 }
 
+/// Inserts the values into the subtrie with the given root, returning the hash of its new root
+#[cfg(feature = "parallel")]
+fn insert_into_subtrie(
+    state: &mut TrieState,
+    root: NodeHash,
+    insertions: Vec<(Nibbles, ValueRLP)>,
+) -> Result<NodeHash, TrieError> {
+    let mut node = if root.is_valid() {
+        Some(state.get_node(root)?.ok_or(TrieError::InconsistentTree)?)
+    } else {
+        None
+    };
+    for (path, value) in insertions {
+        node = Some(match node {
+            Some(node) => node.insert(state, path, value)?,
+            None => LeafNode::new(path, value).into(),
+        });
+    }
+    node.ok_or(TrieError::InconsistentTree)?.insert_self(state)
+}
+
 impl IntoIterator for Trie {
     type Item = (Nibbles, Node);
 
@@ -963,6 +1110,43 @@ mod test {
 
         }
 
+        #[test]
+        fn proptest_compare_hash_update_batch(
+            initial in btree_set(vec(any::<u8>(), 32), 0..100),
+            updates in vec((vec(any::<u8>(), 32), any::<bool>()), 0..300)
+        ) {
+            // Paths have a fixed length as in the state and storage tries, as cita_trie doesn't
+            // handle removing a path that is a prefix of another
+            let mut trie = Trie::new_temp();
+            let mut cita_trie = cita_trie();
+
+            for val in initial.iter() {
+                trie.insert(val.clone(), val.clone()).unwrap();
+                cita_trie.insert(val.clone(), val.clone()).unwrap();
+            }
+            trie.hash().unwrap();
+            // Removals also target the initial values, half of which are updated
+            let updates = initial
+                .iter()
+                .map(|val| (val.clone(), val.first().is_some_and(|v| v % 2 == 0)))
+                .chain(updates);
+            let mut batch = Vec::new();
+            for (val, remove) in updates {
+                if remove {
+                    cita_trie.remove(&val).unwrap();
+                    batch.push((val, None));
+                } else {
+                    cita_trie.insert(val.clone(), val.iter().rev().cloned().collect()).unwrap();
+                    batch.push((val.clone(), Some(val.into_iter().rev().collect())));
+                }
+            }
+            trie.update_batch(batch).unwrap();
+
+            let hash = trie.hash().unwrap().0.to_vec();
+            let cita_hash = cita_trie.root().unwrap();
+            prop_assert_eq!(hash, cita_hash);
+        }
+
         #[test]
         fn proptest_compare_proof(data in btree_set(vec(any::<u8>(), 1..100), 1..100)) {
             let mut trie = Trie::new_temp();
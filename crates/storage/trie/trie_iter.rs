@@ -1,4 +1,6 @@
-use crate::{nibbles::Nibbles, node::Node, node_hash::NodeHash, PathRLP, Trie, ValueRLP};
+use crate::{
+    nibbles::Nibbles, node::Node, node_hash::NodeHash, PathRLP, Trie, TrieError, ValueRLP,
+};
 
 pub struct TrieIterator {
     trie: Trie,
@@ -54,6 +56,75 @@ impl Iterator for TrieIterator {
 }
 
 impl TrieIterator {
+    /// Moves the iterator to the first path equal to or greater than `key`, skipping the
+    /// subtries before it without reading them
+    pub fn advance(&mut self, key: PathRLP) -> Result<(), TrieError> {
+        self.stack.clear();
+        let Some(mut node_hash) = self.trie.root.clone() else {
+            return Ok(());
+        };
+        let mut path = Nibbles::default();
+        // The nibbles of the key below the current node
+        let mut remaining = Nibbles::from_raw(&key, false);
+        loop {
+            let node = self
+                .trie
+                .state
+                .get_node(node_hash.clone())?
+                .ok_or(TrieError::InconsistentTree)?;
+            match node {
+                Node::Branch(branch_node) => {
+                    // The key ends at this branch, so all of its paths are greater or equal
+                    let Some(choice) = remaining.next_choice() else {
+                        self.stack.push((path, node_hash));
+                        return Ok(());
+                    };
+                    // The children after the key's one are greater, the branch's value is lower
+                    for (greater_choice, child) in branch_node
+                        .choices
+                        .iter()
+                        .enumerate()
+                        .skip(choice + 1)
+                        .rev()
+                    {
+                        if child.is_valid() {
+                            let mut child_path = path.clone();
+                            child_path.append(greater_choice as u8);
+                            self.stack.push((child_path, child.clone()));
+                        }
+                    }
+                    let child = &branch_node.choices[choice];
+                    if !child.is_valid() {
+                        return Ok(());
+                    }
+                    path.append(choice as u8);
+                    node_hash = child.clone();
+                }
+                Node::Extension(extension_node) => {
+                    match remaining.compare_prefix(&extension_node.prefix) {
+                        std::cmp::Ordering::Less => self.stack.push((path, node_hash)),
+                        std::cmp::Ordering::Greater => {}
+                        std::cmp::Ordering::Equal => {
+                            remaining.skip_prefix(&extension_node.prefix);
+                            path.extend(&extension_node.prefix);
+                            node_hash = extension_node.child;
+                            continue;
+                        }
+                    }
+                    return Ok(());
+                }
+                Node::Leaf(leaf_node) => {
+                    let partial = leaf_node.partial.as_ref();
+                    let partial = partial.strip_suffix(&[16]).unwrap_or(partial);
+                    if partial >= remaining.as_ref() {
+                        self.stack.push((path, node_hash));
+                    }
+                    return Ok(());
+                }
+            }
+        }
+    }
+
     // TODO: construct path from nibbles
     pub fn content(self) -> impl Iterator<Item = (PathRLP, ValueRLP)> {
         self.filter_map(|(p, n)| match n {
@@ -90,8 +161,45 @@ mod tests {
         let content = trie.into_iter().content().collect::<Vec<_>>();
         assert_eq!(content, expected_content);
     }
+    #[test]
+    fn trie_iter_advance() {
+        let content = vec![
+            (vec![0, 9], vec![3, 4]),
+            (vec![1, 2], vec![5, 6]),
+            (vec![1, 2, 3], vec![9, 9]),
+            (vec![2, 7], vec![7, 8]),
+        ];
+        let content_from = |key: Vec<u8>| {
+            let mut trie = Trie::new_temp();
+            for (path, value) in content.clone() {
+                trie.insert(path, value).unwrap()
+            }
+            let mut iter = trie.into_iter();
+            iter.advance(key).unwrap();
+            iter.content().collect::<Vec<_>>()
+        };
+        assert_eq!(content_from(vec![]), content);
+        assert_eq!(content_from(vec![1, 2]), content[1..]);
+        assert_eq!(content_from(vec![1, 0]), content[1..]);
+        assert_eq!(content_from(vec![1, 2, 0]), content[2..]);
+        assert_eq!(content_from(vec![2, 8]), vec![]);
+    }
+
     proptest! {
 
+        #[test]
+        fn proptest_trie_iter_advance(data in btree_map(vec(any::<u8>(), 5..100), vec(any::<u8>(), 5..100), 5..100), key in vec(any::<u8>(), 0..100)) {
+            let expected_content = data.clone().into_iter().filter(|(path, _)| *path >= key).collect::<Vec<_>>();
+            let mut trie = Trie::new_temp();
+            for (path, value) in data.into_iter() {
+                trie.insert(path, value).unwrap()
+            }
+            let mut iter = trie.into_iter();
+            iter.advance(key).unwrap();
+            let content = iter.content().collect::<Vec<_>>();
+            assert_eq!(content, expected_content);
+        }
+
         #[test]
         fn proptest_trie_iter_content(data in btree_map(vec(any::<u8>(), 5..100), vec(any::<u8>(), 5..100), 5..100)) {
             let expected_content = data.clone().into_iter().collect::<Vec<_>>();
@@ -0,0 +1,213 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+use ark_ec::{twisted_edwards::TECurveConfig, CurveGroup, Group, VariableBaseMSM};
+use ark_ed_on_bls12_381_bandersnatch::{BandersnatchConfig, EdwardsAffine, EdwardsProjective, Fq};
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+pub use ark_ed_on_bls12_381_bandersnatch::Fr;
+
+/// Element of the Banderwagon group, the quotient of the Bandersnatch curve by its 2-torsion
+/// subgroup, where a point and its negated coordinates represent the same element.
+/// This is the group verkle commitments live in
+#[derive(Debug, Clone, Copy)]
+pub struct Element(EdwardsProjective);
+
+impl Element {
+    /// Serialized size of an element
+    pub const BYTES: usize = 32;
+
+    /// Returns the neutral element
+    pub fn zero() -> Self {
+        Self(EdwardsProjective::zero())
+    }
+
+    /// Returns the generator of the prime order subgroup
+    pub fn generator() -> Self {
+        Self(EdwardsProjective::generator())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    /// Serializes the element as the big endian x coordinate of its representative with a
+    /// lexicographically largest y coordinate
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let affine = self.0.into_affine();
+        let x = if is_positive(affine.y) {
+            affine.x
+        } else {
+            -affine.x
+        };
+        let mut bytes = field_to_le_bytes(&x);
+        bytes.reverse();
+        bytes
+    }
+
+    /// Deserializes an element serialized with [Element::to_bytes], checking that it belongs to
+    /// the group
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut bytes: [u8; 32] = bytes.try_into().ok()?;
+        bytes.reverse();
+        let x = Fq::deserialize_compressed(&bytes[..]).ok()?;
+        let point = point_from_x(x)?;
+        let element = Self(EdwardsProjective::new_unchecked(
+            point.x,
+            point.y,
+            point.x * point.y,
+            Fq::one(),
+        ));
+        element.is_in_subgroup().then_some(element)
+    }
+
+    /// Maps the element to a scalar, used to commit to commitments of child nodes
+    pub fn map_to_scalar_field(&self) -> Fr {
+        // x/y is the same for both representatives of the element
+        let affine = self.0.into_affine();
+        let Some(y_inv) = affine.y.inverse() else {
+            return Fr::zero();
+        };
+        Fr::from_le_bytes_mod_order(&field_to_le_bytes(&(affine.x * y_inv)))
+    }
+
+    /// Computes the multi-scalar multiplication of the given points and scalars
+    pub fn msm(points: &[EdwardsAffine], scalars: &[Fr]) -> Self {
+        Self(EdwardsProjective::msm_unchecked(points, scalars))
+    }
+
+    pub(crate) fn batch_into_affine(elements: &[Element]) -> Vec<EdwardsAffine> {
+        let points: Vec<_> = elements.iter().map(|element| element.0).collect();
+        EdwardsProjective::normalize_batch(&points)
+    }
+
+    /// Checks that 1 - ax² is a quadratic residue, which only holds for points of the subgroup
+    fn is_in_subgroup(&self) -> bool {
+        let affine = self.0.into_affine();
+        let value = Fq::one() - BandersnatchConfig::COEFF_A * affine.x.square();
+        value.legendre().is_qr()
+    }
+}
+
+impl PartialEq for Element {
+    fn eq(&self, other: &Self) -> bool {
+        // Elements are equal if x1/y1 == x2/y2, which doesn't depend on the projective z
+        self.0.x * other.0.y == other.0.x * self.0.y
+    }
+}
+
+impl Eq for Element {}
+
+impl Add for Element {
+    type Output = Element;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Element {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0
+    }
+}
+
+impl Sub for Element {
+    type Output = Element;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Element {
+    type Output = Element;
+
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+impl Mul<Fr> for Element {
+    type Output = Element;
+
+    fn mul(self, rhs: Fr) -> Self::Output {
+        Self(self.0 * rhs)
+    }
+}
+
+/// Serializes a field element as 32 little endian bytes
+pub fn field_to_le_bytes<F: CanonicalSerialize>(value: &F) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    // Field elements of both Bandersnatch fields take 32 bytes
+    let _ = value.serialize_compressed(&mut bytes[..]);
+    bytes
+}
+
+/// Returns true if the coordinate is lexicographically larger than its negation
+fn is_positive(coordinate: Fq) -> bool {
+    coordinate.into_bigint() > (-coordinate).into_bigint()
+}
+
+/// Returns the point with the given x coordinate and a lexicographically largest y coordinate
+fn point_from_x(x: Fq) -> Option<EdwardsAffine> {
+    let x_squared = x.square();
+    let numerator = BandersnatchConfig::COEFF_A * x_squared - Fq::one();
+    let denominator = BandersnatchConfig::COEFF_D * x_squared - Fq::one();
+    let y = (numerator * denominator.inverse()?).sqrt()?;
+    let y = if is_positive(y) { y } else { -y };
+    Some(EdwardsAffine::new_unchecked(x, y))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn serialization_roundtrip() {
+        let mut point = Element::generator();
+        for _ in 0..16 {
+            let bytes = point.to_bytes();
+            let decoded = Element::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded, point);
+            assert_eq!(decoded.to_bytes(), bytes);
+            point = point + point + Element::generator();
+        }
+    }
+
+    #[test]
+    fn negated_coordinates_are_equal() {
+        let point = (Element::generator() * Fr::from(7u64)).0.into_affine();
+        let other = Element(EdwardsAffine::new_unchecked(-point.x, -point.y).into());
+        assert_eq!(Element(point.into()), other);
+        assert_eq!(Element(point.into()).to_bytes(), other.to_bytes());
+        assert_eq!(
+            Element(point.into()).map_to_scalar_field(),
+            other.map_to_scalar_field()
+        );
+    }
+
+    #[test]
+    fn fixed_serialization_vectors() {
+        // Shared with go-ipa and rust-verkle: the generator doubled successively
+        let expected = [
+            hex!("4a2c7486fd924882bf02c6908de395122843e3e05264d7991e18e7985dad51e9"),
+            hex!("43aa74ef706605705989e8fd38df46873b7eae5921fbed115ac9d937399ce4d5"),
+            hex!("5e5f550494159f38aa54d2ed7f11a7e93e4968617990445cc93ac8e59808c126"),
+            hex!("0e7e3748db7c5c999a7bcd93d71d671f1f40090423792266f94cb27ca43fce5c"),
+        ];
+        let mut point = Element::generator();
+        for bytes in expected {
+            assert_eq!(point.to_bytes(), bytes);
+            assert_eq!(Element::from_bytes(&bytes), Some(point));
+            point = point + point;
+        }
+    }
+
+    #[test]
+    fn zero_serializes_to_zero_bytes() {
+        assert_eq!(Element::zero().to_bytes(), [0; 32]);
+        assert_eq!(Element::from_bytes(&[0; 32]), Some(Element::zero()));
+    }
+}
@@ -0,0 +1,95 @@
+use ark_ed_on_bls12_381_bandersnatch::{EdwardsAffine, Fq};
+use ark_ff::PrimeField;
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+
+use crate::banderwagon::{field_to_le_bytes, Element, Fr};
+
+/// Number of children of an internal node, and of values of a stem
+pub const VERKLE_NODE_WIDTH: usize = 256;
+
+/// Seed the commitment generators are derived from
+const PEDERSEN_SEED: &[u8] = b"eth_verkle_oct_2021";
+
+lazy_static! {
+    /// Common reference string shared by every verkle client
+    pub static ref CRS: Crs = Crs::new();
+}
+
+/// Generators used to commit to vectors of [VERKLE_NODE_WIDTH] scalars, along with the extra
+/// generator used by inner product arguments
+pub struct Crs {
+    pub generators: Vec<Element>,
+    pub(crate) affine_generators: Vec<EdwardsAffine>,
+    pub q: Element,
+}
+
+impl Crs {
+    fn new() -> Self {
+        let generators = generate_elements(VERKLE_NODE_WIDTH);
+        let affine_generators = Element::batch_into_affine(&generators);
+        Self {
+            generators,
+            affine_generators,
+            q: Element::generator(),
+        }
+    }
+
+    /// Commits to the given scalars, the ones missing at the end being zero
+    pub fn commit(&self, values: &[Fr]) -> Element {
+        Element::msm(&self.affine_generators[..values.len()], values)
+    }
+
+    /// Commits to the scalars at the given indexes, every other one being zero
+    pub fn commit_sparse(&self, values: &[(usize, Fr)]) -> Element {
+        let (points, scalars): (Vec<_>, Vec<_>) = values
+            .iter()
+            .map(|(index, value)| (self.affine_generators[*index], *value))
+            .unzip();
+        Element::msm(&points, &scalars)
+    }
+}
+
+/// Derives generators by hashing the seed with a counter until getting enough valid elements
+fn generate_elements(count: usize) -> Vec<Element> {
+    (0u64..)
+        .filter_map(|i| {
+            let hash = Sha256::new()
+                .chain_update(PEDERSEN_SEED)
+                .chain_update(i.to_be_bytes())
+                .finalize();
+            let x = Fq::from_be_bytes_mod_order(&hash);
+            let mut bytes = field_to_le_bytes(&x);
+            bytes.reverse();
+            Element::from_bytes(&bytes)
+        })
+        .take(count)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn crs_generators() {
+        assert_eq!(CRS.generators.len(), VERKLE_NODE_WIDTH);
+        assert_eq!(
+            CRS.generators[0].to_bytes(),
+            hex!("01587ad1336675eb912550ec2a28eb8923b824b490dd2ba82e48f14590a298a0")
+        );
+        assert_eq!(
+            CRS.generators[255].to_bytes(),
+            hex!("3de2be346b539395b0c0de56a5ccca54a317f1b5c80107b0802af9a62276a4d8")
+        );
+        let mut hasher = Sha256::new();
+        for generator in CRS.generators.iter() {
+            hasher.update(generator.to_bytes());
+        }
+        assert_eq!(
+            hasher.finalize().as_slice(),
+            hex!("1fcaea10bf24f750200e06fa473c76ff0468007291fa548e2d99f09ba9256fdb")
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use ark_ff::{batch_inversion, Field, One, Zero};
+use lazy_static::lazy_static;
+
+use crate::{banderwagon::Fr, crs::VERKLE_NODE_WIDTH};
+
+lazy_static! {
+    /// Precomputed values to work with polynomials in evaluation form over the domain
+    /// 0..VERKLE_NODE_WIDTH
+    pub(crate) static ref DOMAIN: Domain = Domain::new();
+}
+
+/// Polynomials are represented by their evaluations over the points 0..VERKLE_NODE_WIDTH.
+/// With A(X) being the polynomial vanishing over the domain, this holds A'(x_i) for every point
+/// of the domain and the inverses of every difference between two points
+pub(crate) struct Domain {
+    weights: Vec<Fr>,
+    inverted_weights: Vec<Fr>,
+    /// Inverses of 1..VERKLE_NODE_WIDTH, at index i for 1/i
+    inverted_differences: Vec<Fr>,
+}
+
+impl Domain {
+    fn new() -> Self {
+        let weights: Vec<Fr> = (0..VERKLE_NODE_WIDTH)
+            .map(|i| {
+                (0..VERKLE_NODE_WIDTH)
+                    .filter(|j| *j != i)
+                    .map(|j| Fr::from(i as u64) - Fr::from(j as u64))
+                    .product()
+            })
+            .collect();
+        let mut inverted_weights = weights.clone();
+        batch_inversion(&mut inverted_weights);
+        let mut inverted_differences: Vec<Fr> =
+            (0..VERKLE_NODE_WIDTH).map(|i| Fr::from(i as u64)).collect();
+        batch_inversion(&mut inverted_differences[1..]);
+        Self {
+            weights,
+            inverted_weights,
+            inverted_differences,
+        }
+    }
+
+    /// Returns 1/(i - j) for two different points of the domain
+    fn inverted_difference(&self, i: usize, j: usize) -> Fr {
+        if i > j {
+            self.inverted_differences[i - j]
+        } else {
+            -self.inverted_differences[j - i]
+        }
+    }
+
+    /// Returns the coefficients that evaluate a polynomial at a point outside of the domain
+    /// through its inner product with the polynomial evaluations, i.e. the Lagrange basis
+    /// polynomials evaluated at the point
+    pub fn barycentric_coefficients(&self, point: Fr) -> Vec<Fr> {
+        let mut differences: Vec<Fr> = (0..VERKLE_NODE_WIDTH)
+            .map(|i| point - Fr::from(i as u64))
+            .collect();
+        let vanishing_at_point: Fr = differences.iter().product();
+        // A(z) / (A'(x_i) * (z - x_i))
+        let mut coefficients: Vec<Fr> = differences
+            .iter_mut()
+            .zip(self.weights.iter())
+            .map(|(difference, weight)| *difference * weight)
+            .collect();
+        batch_inversion(&mut coefficients);
+        coefficients
+            .iter()
+            .map(|coefficient| *coefficient * vanishing_at_point)
+            .collect()
+    }
+
+    /// Returns the evaluations of (f(X) - f(x_m)) / (X - x_m), with x_m the point of the domain
+    /// at the given index
+    pub fn divide_by_linear_vanishing(&self, evaluations: &[Fr], index: usize) -> Vec<Fr> {
+        let value = evaluations[index];
+        let mut quotient = vec![Fr::zero(); VERKLE_NODE_WIDTH];
+        for (i, evaluation) in evaluations.iter().enumerate() {
+            if i == index {
+                continue;
+            }
+            let q_i = (*evaluation - value) * self.inverted_difference(i, index);
+            quotient[i] = q_i;
+            // q(x_m) = -sum(A'(x_m)/A'(x_i) * q(x_i))
+            quotient[index] -= self.weights[index] * self.inverted_weights[i] * q_i;
+        }
+        quotient
+    }
+}
+
+/// Returns the inner product of two vectors
+pub(crate) fn inner_product(a: &[Fr], b: &[Fr]) -> Fr {
+    a.iter().zip(b).map(|(a, b)| *a * b).sum()
+}
+
+/// Returns 1, x, x², ... up to the given number of powers
+pub(crate) fn powers_of(x: Fr, count: usize) -> Vec<Fr> {
+    std::iter::successors(Some(Fr::one()), |power| Some(*power * x))
+        .take(count)
+        .collect()
+}
+
+/// Returns the inverse of a non-zero field element
+pub(crate) fn invert(value: Fr) -> Fr {
+    value.inverse().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Evaluations of a polynomial of degree 3 over the domain
+    fn cubic() -> Vec<Fr> {
+        (0..VERKLE_NODE_WIDTH as u64)
+            .map(|x| {
+                let x = Fr::from(x);
+                x * x * x + Fr::from(5u64) * x + Fr::from(7u64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn evaluate_outside_domain() {
+        let point = Fr::from(1000u64);
+        let expected = point * point * point + Fr::from(5u64) * point + Fr::from(7u64);
+        let coefficients = DOMAIN.barycentric_coefficients(point);
+        assert_eq!(inner_product(&cubic(), &coefficients), expected);
+    }
+
+    #[test]
+    fn divide_by_linear() {
+        // (x³ + 5x + 7 - f(m)) / (x - m) = x² + mx + m² + 5
+        let m = 42u64;
+        let quotient = DOMAIN.divide_by_linear_vanishing(&cubic(), m as usize);
+        for (x, value) in quotient.iter().enumerate() {
+            let x = Fr::from(x as u64);
+            let m = Fr::from(m);
+            assert_eq!(*value, x * x + m * x + m * m + Fr::from(5u64));
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use ethrex_rlp::error::RLPDecodeError;
+use ethrex_trie::TrieError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum VerkleError {
+    #[error(transparent)]
+    Trie(#[from] TrieError),
+    #[error(transparent)]
+    RLPDecode(#[from] RLPDecodeError),
+    #[error("Missing node with commitment {0}")]
+    MissingNode(String),
+    #[error("Invalid commitment encoding")]
+    InvalidCommitment,
+    #[error("Verification Error: {0}")]
+    Verify(String),
+}
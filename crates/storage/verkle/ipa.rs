@@ -0,0 +1,157 @@
+use ark_ff::One;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    banderwagon::{Element, Fr},
+    crs::Crs,
+    domain::{inner_product, invert},
+    transcript::Transcript,
+};
+
+/// Inner product argument proving that the polynomial committed to evaluates to a value at a
+/// point, by halving the committed vector on every round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpaProof {
+    #[serde(rename = "cl", with = "crate::serde_utils::element::vec")]
+    pub l: Vec<Element>,
+    #[serde(rename = "cr", with = "crate::serde_utils::element::vec")]
+    pub r: Vec<Element>,
+    #[serde(rename = "finalEvaluation", with = "crate::serde_utils::scalar")]
+    pub a: Fr,
+}
+
+impl IpaProof {
+    /// Proves that the inner product of `a`, committed to in `commitment`, and `b` is the
+    /// evaluation at `input_point` of the polynomial given by `a`
+    pub fn create(
+        transcript: &mut Transcript,
+        crs: &Crs,
+        mut a: Vec<Fr>,
+        commitment: Element,
+        mut b: Vec<Fr>,
+        input_point: Fr,
+    ) -> Self {
+        transcript.domain_sep(b"ipa");
+        let output_point = inner_product(&a, &b);
+        transcript.append_point(&commitment, b"C");
+        transcript.append_scalar(&input_point, b"input point");
+        transcript.append_scalar(&output_point, b"output point");
+        let w = transcript.challenge_scalar(b"w");
+        let q = crs.q * w;
+
+        let mut generators = crs.generators.clone();
+        let mut l = Vec::new();
+        let mut r = Vec::new();
+        while a.len() > 1 {
+            let half = a.len() / 2;
+            let (a_l, a_r) = a.split_at(half);
+            let (b_l, b_r) = b.split_at(half);
+            let (g_l, g_r) = generators.split_at(half);
+
+            let z_l = inner_product(a_r, b_l);
+            let z_r = inner_product(a_l, b_r);
+            let c_l = Element::msm(&Element::batch_into_affine(g_l), a_r) + q * z_l;
+            let c_r = Element::msm(&Element::batch_into_affine(g_r), a_l) + q * z_r;
+            transcript.append_point(&c_l, b"L");
+            transcript.append_point(&c_r, b"R");
+            l.push(c_l);
+            r.push(c_r);
+
+            let x = transcript.challenge_scalar(b"x");
+            let x_inv = invert(x);
+            a = a_l.iter().zip(a_r).map(|(l, r)| *l + x * r).collect();
+            b = b_l.iter().zip(b_r).map(|(l, r)| *l + x_inv * r).collect();
+            generators = g_l.iter().zip(g_r).map(|(l, r)| *l + *r * x_inv).collect();
+        }
+        Self { l, r, a: a[0] }
+    }
+
+    /// Checks that the vector committed to in `commitment` has the given inner product with `b`
+    pub fn verify(
+        &self,
+        transcript: &mut Transcript,
+        crs: &Crs,
+        commitment: Element,
+        b: &[Fr],
+        input_point: Fr,
+        output_point: Fr,
+    ) -> bool {
+        let rounds = self.l.len();
+        if self.r.len() != rounds || b.len() != 1 << rounds || b.len() > crs.generators.len() {
+            return false;
+        }
+        transcript.domain_sep(b"ipa");
+        transcript.append_point(&commitment, b"C");
+        transcript.append_scalar(&input_point, b"input point");
+        transcript.append_scalar(&output_point, b"output point");
+        let w = transcript.challenge_scalar(b"w");
+        let q = crs.q * w;
+
+        let mut commitment = commitment + q * output_point;
+        let mut challenges = Vec::with_capacity(rounds);
+        for (l, r) in self.l.iter().zip(self.r.iter()) {
+            transcript.append_point(l, b"L");
+            transcript.append_point(r, b"R");
+            let x = transcript.challenge_scalar(b"x");
+            commitment = commitment + *l * x + *r * invert(x);
+            challenges.push(invert(x));
+        }
+
+        // The folded generator and b are combinations of the original ones, with every element
+        // scaled by the inverted challenges of the rounds it ended up in the right half
+        let scalars: Vec<Fr> = (0..b.len())
+            .map(|i| {
+                challenges
+                    .iter()
+                    .enumerate()
+                    .filter(|(round, _)| i >> (rounds - 1 - round) & 1 == 1)
+                    .map(|(_, x_inv)| *x_inv)
+                    .fold(Fr::one(), |product, x_inv| product * x_inv)
+            })
+            .collect();
+        let generator = Element::msm(&crs.affine_generators[..b.len()], &scalars);
+        let b = inner_product(b, &scalars);
+
+        commitment == generator * self.a + q * (self.a * b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{crs::CRS, domain::DOMAIN};
+
+    #[test]
+    fn ipa_proof_roundtrip() {
+        let polynomial: Vec<Fr> = (0..256u64).map(|i| Fr::from(i * i + 3)).collect();
+        let commitment = CRS.commit(&polynomial);
+        let point = Fr::from(12345u64);
+        let b = DOMAIN.barycentric_coefficients(point);
+        let value = inner_product(&polynomial, &b);
+
+        let proof = IpaProof::create(
+            &mut Transcript::new(b"test"),
+            &CRS,
+            polynomial,
+            commitment,
+            b.clone(),
+            point,
+        );
+        assert!(proof.verify(
+            &mut Transcript::new(b"test"),
+            &CRS,
+            commitment,
+            &b,
+            point,
+            value
+        ));
+        assert!(!proof.verify(
+            &mut Transcript::new(b"test"),
+            &CRS,
+            commitment,
+            &b,
+            point,
+            value + Fr::one()
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+//! Tree keys of the account headers, code and storage as specified in EIP-6800
+
+use ethereum_types::{Address, H256, U256};
+
+use crate::{
+    banderwagon::{field_to_le_bytes, Fr},
+    crs::{CRS, VERKLE_NODE_WIDTH},
+};
+
+/// First 31 bytes of a tree key, shared by the 256 values of a stem node
+pub type Stem = [u8; 31];
+
+pub const BASIC_DATA_LEAF_KEY: u8 = 0;
+pub const CODE_HASH_LEAF_KEY: u8 = 1;
+pub const HEADER_STORAGE_OFFSET: u64 = 64;
+pub const CODE_OFFSET: u64 = 128;
+/// Number of bytes of code held by each chunk, the remaining byte tracks leading push data
+pub const CODE_CHUNK_SIZE: usize = 31;
+
+/// Offsets of the fields packed in the basic data leaf
+const BASIC_DATA_CODE_SIZE_OFFSET: usize = 5;
+const BASIC_DATA_NONCE_OFFSET: usize = 8;
+const BASIC_DATA_BALANCE_OFFSET: usize = 16;
+
+const PUSH1: u8 = 0x60;
+const PUSH32: u8 = 0x7f;
+
+/// Returns the key of a value of the account's subtree, hashing the address and tree index into
+/// the stem so that every stem holds 256 consecutive values of the same account
+pub fn get_tree_key(address: &Address, tree_index: U256, sub_index: u8) -> [u8; 32] {
+    let mut input = [0; 64];
+    input[12..32].copy_from_slice(address.as_bytes());
+    tree_index.to_little_endian(&mut input[32..]);
+    // The first value encodes the domain and input length, the input is split in 16 byte chunks
+    let mut values = vec![Fr::from(2 + 256 * 64u64)];
+    values.extend(
+        input
+            .chunks(16)
+            .map(|chunk| Fr::from(u128::from_le_bytes(chunk.try_into().unwrap()))),
+    );
+    let hash = field_to_le_bytes(&CRS.commit(&values).map_to_scalar_field());
+    let mut key = hash;
+    key[31] = sub_index;
+    key
+}
+
+/// Returns the key of the leaf holding the version, code size, nonce and balance of an account
+pub fn basic_data_key(address: &Address) -> [u8; 32] {
+    get_tree_key(address, U256::zero(), BASIC_DATA_LEAF_KEY)
+}
+
+/// Returns the key of the leaf holding the code hash of an account
+pub fn code_hash_key(address: &Address) -> [u8; 32] {
+    get_tree_key(address, U256::zero(), CODE_HASH_LEAF_KEY)
+}
+
+/// Returns the key of the leaf holding the given chunk of an account's code
+pub fn code_chunk_key(address: &Address, chunk_id: u64) -> [u8; 32] {
+    let position = U256::from(CODE_OFFSET) + chunk_id;
+    tree_key_at(address, position)
+}
+
+/// Returns the key of the leaf holding the given storage slot of an account.
+/// The first slots live in the account header's stem, the rest in the main storage
+pub fn storage_slot_key(address: &Address, storage_key: H256) -> [u8; 32] {
+    let storage_key = U256::from_big_endian(storage_key.as_bytes());
+    if storage_key < U256::from(CODE_OFFSET - HEADER_STORAGE_OFFSET) {
+        tree_key_at(address, storage_key + HEADER_STORAGE_OFFSET)
+    } else {
+        // The main storage offset (256^31) is a multiple of the width, so it only shifts the
+        // tree index, which avoids overflowing the position for the largest slots
+        let width = U256::from(VERKLE_NODE_WIDTH);
+        let tree_index = storage_key / width + (U256::one() << 240);
+        get_tree_key(address, tree_index, (storage_key % width).low_u32() as u8)
+    }
+}
+
+fn tree_key_at(address: &Address, position: U256) -> [u8; 32] {
+    let width = U256::from(VERKLE_NODE_WIDTH);
+    get_tree_key(
+        address,
+        position / width,
+        (position % width).low_u32() as u8,
+    )
+}
+
+/// Packs the header fields of an account into the value of its basic data leaf
+pub fn basic_data_leaf(version: u8, code_size: u32, nonce: u64, balance: U256) -> [u8; 32] {
+    let mut value = [0; 32];
+    value[0] = version;
+    value[BASIC_DATA_CODE_SIZE_OFFSET..BASIC_DATA_NONCE_OFFSET]
+        .copy_from_slice(&code_size.to_be_bytes()[1..]);
+    value[BASIC_DATA_NONCE_OFFSET..BASIC_DATA_BALANCE_OFFSET].copy_from_slice(&nonce.to_be_bytes());
+    value[BASIC_DATA_BALANCE_OFFSET..].copy_from_slice(&balance.low_u128().to_be_bytes());
+    value
+}
+
+/// Splits code into chunks of 31 bytes, each prefixed with the amount of its leading bytes
+/// which are data of a push instruction started in a previous chunk
+pub fn chunkify_code(code: &[u8]) -> Vec<[u8; 32]> {
+    let chunk_count = code.len().div_ceil(CODE_CHUNK_SIZE);
+    let mut padded_code = code.to_vec();
+    padded_code.resize(chunk_count * CODE_CHUNK_SIZE, 0);
+
+    // Remaining push data bytes at each position of the code
+    let mut push_data_left = vec![0u8; padded_code.len() + 32];
+    let mut pos = 0;
+    while pos < padded_code.len() {
+        let push_data_bytes = match padded_code[pos] {
+            opcode @ PUSH1..=PUSH32 => opcode - PUSH1 + 1,
+            _ => 0,
+        };
+        pos += 1;
+        for i in 0..push_data_bytes {
+            push_data_left[pos + i as usize] = push_data_bytes - i;
+        }
+        pos += push_data_bytes as usize;
+    }
+
+    padded_code
+        .chunks(CODE_CHUNK_SIZE)
+        .enumerate()
+        .map(|(i, code)| {
+            let mut chunk = [0; 32];
+            chunk[0] = push_data_left[i * CODE_CHUNK_SIZE].min(CODE_CHUNK_SIZE as u8);
+            chunk[1..].copy_from_slice(code);
+            chunk
+        })
+        .collect()
+}
+
+/// Splits a key into its stem and the index of its value in the stem
+pub fn split_key(key: &[u8; 32]) -> (Stem, u8) {
+    let mut stem = [0; 31];
+    stem.copy_from_slice(&key[..31]);
+    (stem, key[31])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn account_header_shares_stem() {
+        let address = Address::repeat_byte(0x42);
+        let (stem, suffix) = split_key(&basic_data_key(&address));
+        assert_eq!(suffix, BASIC_DATA_LEAF_KEY);
+        assert_eq!(
+            split_key(&code_hash_key(&address)),
+            (stem, CODE_HASH_LEAF_KEY)
+        );
+        assert_eq!(
+            split_key(&storage_slot_key(&address, H256::from_low_u64_be(3))),
+            (stem, 67)
+        );
+        assert_eq!(split_key(&code_chunk_key(&address, 0)), (stem, 128));
+        assert_eq!(split_key(&code_chunk_key(&address, 127)), (stem, 255));
+        assert_ne!(split_key(&code_chunk_key(&address, 128)).0, stem);
+        assert_ne!(
+            split_key(&storage_slot_key(&address, H256::from_low_u64_be(64))).0,
+            stem
+        );
+        assert_ne!(split_key(&basic_data_key(&Address::zero())).0, stem);
+    }
+
+    #[test]
+    fn main_storage_does_not_overflow() {
+        let address = Address::repeat_byte(0x42);
+        let last = storage_slot_key(&address, H256::repeat_byte(0xff));
+        assert_eq!(last[31], 0xff);
+        assert_ne!(
+            split_key(&last).0,
+            split_key(&storage_slot_key(&address, H256::from_low_u64_be(64))).0
+        );
+    }
+
+    #[test]
+    fn basic_data_layout() {
+        let value = basic_data_leaf(0, 0x010203, 7, U256::from(1000));
+        let mut expected = [0; 32];
+        expected[5..8].copy_from_slice(&[1, 2, 3]);
+        expected[15] = 7;
+        expected[30..].copy_from_slice(&1000u16.to_be_bytes());
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn chunkify_push_data() {
+        // PUSH32 at the third byte leaves room for 28 bytes of its data, the remaining 4 spill
+        // into the second chunk
+        let mut code = vec![0x5f; 2];
+        code.push(PUSH32);
+        code.extend([0xaa; 32]);
+        code.push(0x00);
+        let chunks = chunkify_code(&code);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0][0], 0);
+        assert_eq!(&chunks[0][1..4], &[0x5f, 0x5f, PUSH32]);
+        assert_eq!(chunks[1][0], 4);
+        assert_eq!(&chunks[1][1..6], &[0xaa, 0xaa, 0xaa, 0xaa, 0x00]);
+        assert!(chunks[1][6..].iter().all(|byte| *byte == 0));
+    }
+}
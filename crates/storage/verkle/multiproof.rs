@@ -0,0 +1,176 @@
+use ark_ff::Zero;
+
+use crate::{
+    banderwagon::{Element, Fr},
+    crs::{Crs, VERKLE_NODE_WIDTH},
+    domain::{inner_product, invert, powers_of, DOMAIN},
+    ipa::IpaProof,
+    transcript::Transcript,
+};
+
+/// Opening of a committed polynomial at a point of the domain, as known by the prover
+#[derive(Debug, Clone)]
+pub struct ProverQuery {
+    pub commitment: Element,
+    /// Evaluations of the polynomial over the domain
+    pub polynomial: Vec<Fr>,
+    pub point: u8,
+    pub result: Fr,
+}
+
+/// Opening of a committed polynomial at a point of the domain, as known by the verifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifierQuery {
+    pub commitment: Element,
+    pub point: u8,
+    pub result: Fr,
+}
+
+impl From<&ProverQuery> for VerifierQuery {
+    fn from(query: &ProverQuery) -> Self {
+        Self {
+            commitment: query.commitment,
+            point: query.point,
+            result: query.result,
+        }
+    }
+}
+
+/// Proof of many openings at once, aggregating them into a single polynomial which is opened
+/// outside of the domain with an inner product argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiPointProof {
+    pub ipa: IpaProof,
+    pub d: Element,
+}
+
+impl MultiPointProof {
+    pub fn open(crs: &Crs, transcript: &mut Transcript, queries: &[ProverQuery]) -> Self {
+        transcript.domain_sep(b"multiproof");
+        for query in queries {
+            append_query(transcript, &query.into());
+        }
+        let r = transcript.challenge_scalar(b"r");
+        let powers_of_r = powers_of(r, queries.len());
+
+        // g(X) = sum(r^i * (f_i(X) - y_i) / (X - z_i))
+        let mut g = vec![Fr::zero(); VERKLE_NODE_WIDTH];
+        for (query, power_of_r) in queries.iter().zip(powers_of_r.iter()) {
+            let quotient =
+                DOMAIN.divide_by_linear_vanishing(&query.polynomial, query.point as usize);
+            for (g, quotient) in g.iter_mut().zip(quotient) {
+                *g += *power_of_r * quotient;
+            }
+        }
+        let d = crs.commit(&g);
+        transcript.append_point(&d, b"D");
+        let t = transcript.challenge_scalar(b"t");
+
+        // h(X) = sum(r^i * f_i(X) / (t - z_i))
+        let coefficients =
+            aggregation_coefficients(&powers_of_r, queries.iter().map(|q| q.point), t);
+        let mut h = vec![Fr::zero(); VERKLE_NODE_WIDTH];
+        for (query, coefficient) in queries.iter().zip(coefficients.iter()) {
+            for (h, evaluation) in h.iter_mut().zip(query.polynomial.iter()) {
+                *h += *coefficient * evaluation;
+            }
+        }
+        let e = aggregate_commitments(queries.iter().map(|q| q.commitment), &coefficients);
+        transcript.append_point(&e, b"E");
+
+        let h_minus_g: Vec<Fr> = h.iter().zip(g.iter()).map(|(h, g)| *h - g).collect();
+        let ipa = IpaProof::create(
+            transcript,
+            crs,
+            h_minus_g,
+            e - d,
+            DOMAIN.barycentric_coefficients(t),
+            t,
+        );
+        Self { ipa, d }
+    }
+
+    pub fn check(&self, crs: &Crs, transcript: &mut Transcript, queries: &[VerifierQuery]) -> bool {
+        transcript.domain_sep(b"multiproof");
+        for query in queries {
+            append_query(transcript, query);
+        }
+        let r = transcript.challenge_scalar(b"r");
+        let powers_of_r = powers_of(r, queries.len());
+        transcript.append_point(&self.d, b"D");
+        let t = transcript.challenge_scalar(b"t");
+
+        // (h - g)(t) = sum(r^i * y_i / (t - z_i))
+        let coefficients =
+            aggregation_coefficients(&powers_of_r, queries.iter().map(|q| q.point), t);
+        let results: Vec<Fr> = queries.iter().map(|query| query.result).collect();
+        let evaluation = inner_product(&coefficients, &results);
+        let e = aggregate_commitments(queries.iter().map(|q| q.commitment), &coefficients);
+        transcript.append_point(&e, b"E");
+
+        self.ipa.verify(
+            transcript,
+            crs,
+            e - self.d,
+            &DOMAIN.barycentric_coefficients(t),
+            t,
+            evaluation,
+        )
+    }
+}
+
+fn append_query(transcript: &mut Transcript, query: &VerifierQuery) {
+    transcript.append_point(&query.commitment, b"C");
+    transcript.append_scalar(&Fr::from(query.point), b"z");
+    transcript.append_scalar(&query.result, b"y");
+}
+
+/// Returns r^i / (t - z_i) for every query
+fn aggregation_coefficients(
+    powers_of_r: &[Fr],
+    points: impl Iterator<Item = u8>,
+    t: Fr,
+) -> Vec<Fr> {
+    powers_of_r
+        .iter()
+        .zip(points)
+        .map(|(power_of_r, point)| *power_of_r * invert(t - Fr::from(point)))
+        .collect()
+}
+
+fn aggregate_commitments(
+    commitments: impl Iterator<Item = Element>,
+    coefficients: &[Fr],
+) -> Element {
+    let commitments: Vec<_> = commitments.collect();
+    Element::msm(&Element::batch_into_affine(&commitments), coefficients)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crs::CRS;
+
+    fn query(seed: u64, point: u8) -> ProverQuery {
+        let polynomial: Vec<Fr> = (0..VERKLE_NODE_WIDTH as u64)
+            .map(|i| Fr::from(seed * 1000 + i * i))
+            .collect();
+        ProverQuery {
+            commitment: CRS.commit(&polynomial),
+            result: polynomial[point as usize],
+            polynomial,
+            point,
+        }
+    }
+
+    #[test]
+    fn multiproof_roundtrip() {
+        let queries = vec![query(1, 0), query(2, 17), query(1, 255), query(3, 17)];
+        let proof = MultiPointProof::open(&CRS, &mut Transcript::new(b"test"), &queries);
+        let mut verifier_queries: Vec<VerifierQuery> = queries.iter().map(Into::into).collect();
+        assert!(proof.check(&CRS, &mut Transcript::new(b"test"), &verifier_queries));
+
+        verifier_queries[2].result += Fr::from(1u64);
+        assert!(!proof.check(&CRS, &mut Transcript::new(b"test"), &verifier_queries));
+    }
+}
@@ -0,0 +1,390 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use ark_ff::{One, PrimeField, Zero};
+use ethrex_rlp::{
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+use ethrex_trie::TrieDB;
+
+use crate::{
+    banderwagon::{Element, Fr},
+    crs::{CRS, VERKLE_NODE_WIDTH},
+    error::VerkleError,
+    key::Stem,
+};
+
+/// Value of a leaf of the tree
+pub type VerkleValue = [u8; 32];
+
+/// Nodes encoded by the DB are tagged with their kind
+const INTERNAL_NODE_TAG: u8 = 0;
+const STEM_NODE_TAG: u8 = 1;
+
+/// Number of values committed to by each of the two sub commitments of a stem node
+pub(crate) const VALUES_PER_SUB_COMMITMENT: usize = VERKLE_NODE_WIDTH / 2;
+
+/// Pending and stored nodes, pending ones having their commitment computed on commit
+/// Stored nodes are only loaded from the DB when they are modified
+#[derive(Debug, Clone)]
+pub(crate) enum Child {
+    Empty,
+    Stored(Element),
+    Loaded(Box<Node>),
+}
+
+impl Child {
+    /// Returns the commitment of the child, which is only missing for empty or modified nodes
+    pub fn commitment(&self) -> Option<Element> {
+        match self {
+            Child::Empty => None,
+            Child::Stored(commitment) => Some(*commitment),
+            Child::Loaded(node) => node.commitment(),
+        }
+    }
+
+    /// Returns the scalar the child contributes to its parent's commitment
+    pub fn scalar(&self) -> Fr {
+        self.commitment()
+            .map(|commitment| commitment.map_to_scalar_field())
+            .unwrap_or_default()
+    }
+
+    /// Returns the child node, reading it from the DB if needed
+    pub fn node(&self, db: &dyn TrieDB) -> Result<Option<Cow<'_, Node>>, VerkleError> {
+        Ok(match self {
+            Child::Empty => None,
+            Child::Stored(commitment) => Some(Cow::Owned(Node::load(db, *commitment)?)),
+            Child::Loaded(node) => Some(Cow::Borrowed(node)),
+        })
+    }
+
+    /// Returns the child node so it can be modified, reading it from the DB if needed
+    fn node_mut(&mut self, db: &dyn TrieDB) -> Result<Option<&mut Node>, VerkleError> {
+        if let Child::Stored(commitment) = self {
+            *self = Child::Loaded(Box::new(Node::load(db, *commitment)?));
+        }
+        Ok(match self {
+            Child::Loaded(node) => Some(node),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    Internal(InternalNode),
+    Stem(Box<StemNode>),
+}
+
+impl Node {
+    /// Reads the node with the given commitment from the DB
+    pub fn load(db: &dyn TrieDB, commitment: Element) -> Result<Self, VerkleError> {
+        let key = commitment.to_bytes();
+        let encoded = db
+            .get(key.to_vec())?
+            .ok_or_else(|| VerkleError::MissingNode(hex::encode(key)))?;
+        let mut node = Self::decode(&encoded)?;
+        match &mut node {
+            Node::Internal(internal) => internal.commitment = Some(commitment),
+            Node::Stem(stem) => stem.commitment = Some(commitment),
+        }
+        Ok(node)
+    }
+
+    pub fn commitment(&self) -> Option<Element> {
+        match self {
+            Node::Internal(internal) => internal.commitment,
+            Node::Stem(stem) => stem.commitment,
+        }
+    }
+
+    /// Computes the commitments of every modified node of the subtree, adding them to the
+    /// batch of nodes to be written to the DB, and returns the subtree's commitment
+    pub fn commit(&mut self, batch: &mut Vec<(Vec<u8>, Vec<u8>)>) -> Element {
+        match self {
+            Node::Internal(internal) => internal.commit(batch),
+            Node::Stem(stem) => stem.commit(batch),
+        }
+    }
+
+    /// Decodes a node, leaving its own commitment to be set by the caller
+    pub fn decode(rlp: &[u8]) -> Result<Self, VerkleError> {
+        let decoder = Decoder::new(rlp)?;
+        let (tag, decoder): (u8, _) = decoder.decode_field("tag")?;
+        let node = match tag {
+            INTERNAL_NODE_TAG => {
+                let (children, decoder): (Vec<(u8, [u8; 32])>, _) =
+                    decoder.decode_field("children")?;
+                decoder.finish()?;
+                let mut node = InternalNode::new();
+                for (index, commitment) in children {
+                    node.children[index as usize] = Child::Stored(decode_commitment(&commitment)?);
+                }
+                Node::Internal(node)
+            }
+            STEM_NODE_TAG => {
+                let (stem, decoder) = decoder.decode_field("stem")?;
+                let (values, decoder): (Vec<(u8, VerkleValue)>, _) =
+                    decoder.decode_field("values")?;
+                let (c1, decoder): ([u8; 32], _) = decoder.decode_field("c1")?;
+                let (c2, decoder): ([u8; 32], _) = decoder.decode_field("c2")?;
+                decoder.finish()?;
+                Node::Stem(Box::new(StemNode {
+                    stem,
+                    values: values.into_iter().collect(),
+                    sub_commitments: [Some(decode_commitment(&c1)?), Some(decode_commitment(&c2)?)],
+                    commitment: None,
+                }))
+            }
+            _ => return Err(RLPDecodeError::MalformedData.into()),
+        };
+        Ok(node)
+    }
+}
+
+fn decode_commitment(bytes: &[u8]) -> Result<Element, VerkleError> {
+    Element::from_bytes(bytes).ok_or(VerkleError::InvalidCommitment)
+}
+
+/// Node committing to up to 256 children, indexed by the byte of the stem at the node's depth
+#[derive(Debug, Clone)]
+pub(crate) struct InternalNode {
+    pub children: Vec<Child>,
+    commitment: Option<Element>,
+}
+
+impl InternalNode {
+    pub fn new() -> Self {
+        Self {
+            children: vec![Child::Empty; VERKLE_NODE_WIDTH],
+            commitment: None,
+        }
+    }
+
+    pub fn commitment(&self) -> Option<Element> {
+        self.commitment
+    }
+
+    /// Retrieves the value at the given suffix of a stem from the subtree of this node
+    pub fn get(
+        &self,
+        db: &dyn TrieDB,
+        depth: usize,
+        stem: &Stem,
+        suffix: u8,
+    ) -> Result<Option<VerkleValue>, VerkleError> {
+        let Some(child) = self.children[stem[depth] as usize].node(db)? else {
+            return Ok(None);
+        };
+        match child.as_ref() {
+            Node::Internal(internal) => internal.get(db, depth + 1, stem, suffix),
+            Node::Stem(node) if node.stem == *stem => Ok(node.values.get(&suffix).copied()),
+            Node::Stem(_) => Ok(None),
+        }
+    }
+
+    /// Inserts a value into the subtree of this node, splitting the stem node found in its
+    /// place if it belongs to a different stem
+    pub fn insert(
+        &mut self,
+        db: &dyn TrieDB,
+        depth: usize,
+        stem: Stem,
+        suffix: u8,
+        value: VerkleValue,
+    ) -> Result<(), VerkleError> {
+        self.commitment = None;
+        let index = stem[depth] as usize;
+        let split_index = match self.children[index].node_mut(db)? {
+            None => {
+                let mut node = StemNode::new(stem);
+                node.set(suffix, value);
+                self.children[index] = Child::Loaded(Box::new(Node::Stem(Box::new(node))));
+                return Ok(());
+            }
+            Some(Node::Internal(internal)) => {
+                return internal.insert(db, depth + 1, stem, suffix, value)
+            }
+            Some(Node::Stem(node)) if node.stem == stem => {
+                node.set(suffix, value);
+                return Ok(());
+            }
+            Some(Node::Stem(node)) => node.stem[depth + 1] as usize,
+        };
+        // Stem commitments don't depend on their depth, so the existing node keeps its own
+        let mut internal = InternalNode::new();
+        internal.children[split_index] = std::mem::replace(&mut self.children[index], Child::Empty);
+        internal.insert(db, depth + 1, stem, suffix, value)?;
+        self.children[index] = Child::Loaded(Box::new(Node::Internal(internal)));
+        Ok(())
+    }
+
+    pub fn commit(&mut self, batch: &mut Vec<(Vec<u8>, Vec<u8>)>) -> Element {
+        if let Some(commitment) = self.commitment {
+            return commitment;
+        }
+        let values: Vec<(usize, Fr)> = self
+            .children
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, child)| {
+                let commitment = match child {
+                    Child::Empty => return None,
+                    Child::Stored(commitment) => *commitment,
+                    Child::Loaded(node) => node.commit(batch),
+                };
+                Some((index, commitment.map_to_scalar_field()))
+            })
+            .collect();
+        let commitment = CRS.commit_sparse(&values);
+        self.commitment = Some(commitment);
+        batch.push((commitment.to_bytes().to_vec(), self.encode()));
+        commitment
+    }
+
+    /// Encodes the node, the commitments of its children must have been computed
+    fn encode(&self) -> Vec<u8> {
+        let children: Vec<(u8, [u8; 32])> = self
+            .children
+            .iter()
+            .enumerate()
+            .filter_map(|(index, child)| {
+                child
+                    .commitment()
+                    .map(|commitment| (index as u8, commitment.to_bytes()))
+            })
+            .collect();
+        let mut buf = vec![];
+        Encoder::new(&mut buf)
+            .encode_field(&INTERNAL_NODE_TAG)
+            .encode_field(&children)
+            .finish();
+        buf
+    }
+
+    /// Returns the scalars committed to by the node, those of its children
+    pub fn polynomial(&self) -> Vec<Fr> {
+        self.children.iter().map(Child::scalar).collect()
+    }
+}
+
+/// Node holding the values of every key sharing a stem. The values are split in two halves,
+/// each committed to in a sub commitment, which are in turn committed to along with the stem
+#[derive(Debug, Clone)]
+pub(crate) struct StemNode {
+    pub stem: Stem,
+    pub values: BTreeMap<u8, VerkleValue>,
+    sub_commitments: [Option<Element>; 2],
+    commitment: Option<Element>,
+}
+
+impl StemNode {
+    pub fn new(stem: Stem) -> Self {
+        Self {
+            stem,
+            values: BTreeMap::new(),
+            sub_commitments: [None, None],
+            commitment: None,
+        }
+    }
+
+    pub fn commitment(&self) -> Option<Element> {
+        self.commitment
+    }
+
+    pub fn set(&mut self, suffix: u8, value: VerkleValue) {
+        self.values.insert(suffix, value);
+        self.sub_commitments[suffix as usize / VALUES_PER_SUB_COMMITMENT] = None;
+        self.commitment = None;
+    }
+
+    pub fn commit(&mut self, batch: &mut Vec<(Vec<u8>, Vec<u8>)>) -> Element {
+        if let Some(commitment) = self.commitment {
+            return commitment;
+        }
+        for half in 0..2 {
+            if self.sub_commitments[half].is_none() {
+                self.sub_commitments[half] = Some(CRS.commit_sparse(&self.half_values(half)));
+            }
+        }
+        let commitment = CRS.commit(&self.polynomial());
+        self.commitment = Some(commitment);
+        batch.push((commitment.to_bytes().to_vec(), self.encode()));
+        commitment
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let values: Vec<(u8, VerkleValue)> = self.values.iter().map(|(k, v)| (*k, *v)).collect();
+        let [c1, c2] = self
+            .sub_commitments
+            .map(|commitment| commitment.unwrap_or_else(Element::zero).to_bytes());
+        let mut buf = vec![];
+        Encoder::new(&mut buf)
+            .encode_field(&STEM_NODE_TAG)
+            .encode_field(&self.stem)
+            .encode_field(&values)
+            .encode_field(&c1)
+            .encode_field(&c2)
+            .finish();
+        buf
+    }
+
+    /// Returns the sub commitment of the given half of the values, if computed
+    pub fn sub_commitment(&self, half: usize) -> Option<Element> {
+        self.sub_commitments[half]
+    }
+
+    /// Returns the scalars committed to by the node: a marker, the stem and its sub commitments
+    pub fn polynomial(&self) -> Vec<Fr> {
+        let mut polynomial = vec![Fr::zero(); VERKLE_NODE_WIDTH];
+        polynomial[0] = Fr::one();
+        polynomial[1] = stem_to_scalar(&self.stem);
+        for (half, commitment) in self.sub_commitments.iter().enumerate() {
+            polynomial[2 + half] = commitment
+                .map(|commitment| commitment.map_to_scalar_field())
+                .unwrap_or_default();
+        }
+        polynomial
+    }
+
+    /// Returns the scalars committed to by the sub commitment of the given half of the values
+    pub fn half_polynomial(&self, half: usize) -> Vec<Fr> {
+        let mut polynomial = vec![Fr::zero(); VERKLE_NODE_WIDTH];
+        for (index, value) in self.half_values(half) {
+            polynomial[index] = value;
+        }
+        polynomial
+    }
+
+    /// Returns the non-zero scalars of the given half of the values, each value being split
+    /// in its lower and upper 16 bytes
+    fn half_values(&self, half: usize) -> Vec<(usize, Fr)> {
+        self.values
+            .iter()
+            .filter(|(suffix, _)| **suffix as usize / VALUES_PER_SUB_COMMITMENT == half)
+            .flat_map(|(suffix, value)| {
+                let index = 2 * (*suffix as usize % VALUES_PER_SUB_COMMITMENT);
+                let (lower, upper) = value_to_scalars(Some(value));
+                [(index, lower), (index + 1, upper)]
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn stem_to_scalar(stem: &Stem) -> Fr {
+    Fr::from_le_bytes_mod_order(stem)
+}
+
+/// Splits a value in the scalars of its lower and upper 16 bytes. Present values have 2^128
+/// added to their lower half so that they can be told apart from missing ones
+pub(crate) fn value_to_scalars(value: Option<&VerkleValue>) -> (Fr, Fr) {
+    let Some(value) = value else {
+        return (Fr::zero(), Fr::zero());
+    };
+    let leaf_marker = Fr::from(u128::MAX) + Fr::one();
+    (
+        Fr::from_le_bytes_mod_order(&value[..16]) + leaf_marker,
+        Fr::from_le_bytes_mod_order(&value[16..]),
+    )
+}
@@ -0,0 +1,191 @@
+use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+
+use crate::banderwagon::Element;
+
+fn decode_hex<E: Error>(value: &str) -> Result<Vec<u8>, E> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|e| E::custom(e.to_string()))
+}
+
+fn decode_fixed_hex<E: Error, const N: usize>(value: &str) -> Result<[u8; N], E> {
+    let bytes = decode_hex::<E>(value)?;
+    bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| E::custom(format!("Expected {N} bytes, got {}", bytes.len())))
+}
+
+fn serialize_vec<S, T>(
+    values: &[T],
+    serializer: S,
+    encode: impl Fn(&T) -> String,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(values.len()))?;
+    for value in values {
+        seq.serialize_element(&encode(value))?;
+    }
+    seq.end()
+}
+
+/// Serializes to and deserializes from 0x prefixed hex string
+pub mod bytes {
+    use super::*;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        decode_hex(&String::deserialize(d)?)
+    }
+
+    pub fn serialize<S>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{}", hex::encode(value)))
+    }
+}
+
+/// Serializes byte arrays to and deserializes them from 0x prefixed hex strings
+pub mod fixed_bytes {
+    use super::*;
+
+    pub fn deserialize<'de, D, const N: usize>(d: D) -> Result<[u8; N], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        decode_fixed_hex(&String::deserialize(d)?)
+    }
+
+    pub fn serialize<S, const N: usize>(value: &[u8; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{}", hex::encode(value)))
+    }
+
+    /// Missing values are serialized as null
+    pub mod option {
+        use super::*;
+
+        pub fn deserialize<'de, D, const N: usize>(d: D) -> Result<Option<[u8; N]>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Option::<String>::deserialize(d)?
+                .map(|value| decode_fixed_hex(&value))
+                .transpose()
+        }
+
+        pub fn serialize<S, const N: usize>(
+            value: &Option<[u8; N]>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match value {
+                Some(value) => super::serialize(value, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+    }
+
+    pub mod vec {
+        use super::*;
+
+        pub fn deserialize<'de, D, const N: usize>(d: D) -> Result<Vec<[u8; N]>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Vec::<String>::deserialize(d)?
+                .iter()
+                .map(|value| decode_fixed_hex(value))
+                .collect()
+        }
+
+        pub fn serialize<S, const N: usize>(
+            value: &[[u8; N]],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serialize_vec(value, serializer, |value| {
+                format!("0x{}", hex::encode(value))
+            })
+        }
+    }
+}
+
+/// Serializes group elements to and deserializes them from their 0x prefixed hex encoding
+pub mod element {
+    use super::*;
+
+    fn decode<E: Error>(value: &str) -> Result<Element, E> {
+        let bytes: [u8; 32] = decode_fixed_hex(value)?;
+        Element::from_bytes(&bytes).ok_or_else(|| E::custom("Invalid group element"))
+    }
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Element, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        decode(&String::deserialize(d)?)
+    }
+
+    pub fn serialize<S>(value: &Element, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{}", hex::encode(value.to_bytes())))
+    }
+
+    pub mod vec {
+        use super::*;
+
+        pub fn deserialize<'de, D>(d: D) -> Result<Vec<Element>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Vec::<String>::deserialize(d)?
+                .iter()
+                .map(|value| decode(value))
+                .collect()
+        }
+
+        pub fn serialize<S>(value: &[Element], serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serialize_vec(value, serializer, |value| {
+                format!("0x{}", hex::encode(value.to_bytes()))
+            })
+        }
+    }
+}
+
+/// Serializes scalars to and deserializes them from their 0x prefixed little endian hex encoding
+pub mod scalar {
+    use ark_serialize::CanonicalDeserialize;
+
+    use super::*;
+    use crate::banderwagon::{field_to_le_bytes, Fr};
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Fr, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes: [u8; 32] = decode_fixed_hex(&String::deserialize(d)?)?;
+        Fr::deserialize_compressed(&bytes[..]).map_err(|_| D::Error::custom("Invalid scalar"))
+    }
+
+    pub fn serialize<S>(value: &Fr, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{}", hex::encode(field_to_le_bytes(value))))
+    }
+}
@@ -0,0 +1,45 @@
+use ark_ff::PrimeField;
+use sha2::{Digest, Sha256};
+
+use crate::banderwagon::{field_to_le_bytes, Element, Fr};
+
+/// Fiat-Shamir transcript used to derive the challenges of the proofs from the messages
+/// exchanged with the verifier
+pub struct Transcript {
+    state: Sha256,
+}
+
+impl Transcript {
+    pub fn new(label: &[u8]) -> Self {
+        Self {
+            state: Sha256::new_with_prefix(label),
+        }
+    }
+
+    /// Separates the messages of different protocols using the same transcript
+    pub fn domain_sep(&mut self, label: &[u8]) {
+        self.state.update(label);
+    }
+
+    fn append_message(&mut self, message: &[u8], label: &[u8]) {
+        self.state.update(label);
+        self.state.update(message);
+    }
+
+    pub fn append_scalar(&mut self, scalar: &Fr, label: &[u8]) {
+        self.append_message(&field_to_le_bytes(scalar), label);
+    }
+
+    pub fn append_point(&mut self, point: &Element, label: &[u8]) {
+        self.append_message(&point.to_bytes(), label);
+    }
+
+    /// Derives a challenge from every message so far, which is then added to the transcript
+    pub fn challenge_scalar(&mut self, label: &[u8]) -> Fr {
+        self.domain_sep(label);
+        let hash = self.state.finalize_reset();
+        let scalar = Fr::from_le_bytes_mod_order(&hash);
+        self.append_scalar(&scalar, label);
+        scalar
+    }
+}
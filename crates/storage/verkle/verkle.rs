@@ -0,0 +1,163 @@
+pub mod banderwagon;
+pub mod crs;
+mod domain;
+mod error;
+pub mod ipa;
+pub mod key;
+pub mod multiproof;
+mod node;
+mod serde_utils;
+pub mod transcript;
+mod witness;
+
+use ethereum_types::H256;
+use ethrex_trie::TrieDB;
+
+pub use self::error::VerkleError;
+pub use self::node::VerkleValue;
+pub use self::witness::{ExecutionWitness, StemStateDiff, SuffixStateDiff, VerkleProof};
+use self::{
+    banderwagon::Element,
+    key::split_key,
+    node::{InternalNode, Node},
+};
+
+/// Serialized commitment of an empty tree
+pub const EMPTY_VERKLE_ROOT: H256 = H256::zero();
+
+/// Verkle tree as specified in EIP-6800, where every node commits to its children with a
+/// vector commitment so that proofs only need one opening per node instead of every sibling.
+/// Nodes are stored in the DB keyed by their serialized commitment
+pub struct VerkleTree {
+    root: InternalNode,
+    db: Box<dyn TrieDB>,
+}
+
+impl VerkleTree {
+    /// Creates a new tree from a clean DB
+    pub fn new(db: Box<dyn TrieDB>) -> Self {
+        Self {
+            root: InternalNode::new(),
+            db,
+        }
+    }
+
+    /// Creates a tree from an already-initialized DB and loads the root node with the given
+    /// commitment
+    pub fn open(db: Box<dyn TrieDB>, root: H256) -> Result<Self, VerkleError> {
+        if root == EMPTY_VERKLE_ROOT {
+            return Ok(Self::new(db));
+        }
+        let commitment =
+            Element::from_bytes(root.as_bytes()).ok_or(VerkleError::InvalidCommitment)?;
+        match Node::load(db.as_ref(), commitment)? {
+            Node::Internal(root) => Ok(Self { root, db }),
+            Node::Stem(_) => Err(VerkleError::InvalidCommitment),
+        }
+    }
+
+    /// Retrieves the value stored at the given key
+    pub fn get(&self, key: &[u8; 32]) -> Result<Option<VerkleValue>, VerkleError> {
+        let (stem, suffix) = split_key(key);
+        self.root.get(self.db.as_ref(), 0, &stem, suffix)
+    }
+
+    /// Inserts a value at the given key. Values can't be removed, only overwritten
+    pub fn insert(&mut self, key: &[u8; 32], value: VerkleValue) -> Result<(), VerkleError> {
+        let (stem, suffix) = split_key(key);
+        self.root.insert(self.db.as_ref(), 0, stem, suffix, value)
+    }
+
+    /// Returns the serialized commitment of the root node, computing the commitments of every
+    /// modified node. Returns [EMPTY_VERKLE_ROOT] if the tree is empty
+    /// Also commits changes to the DB
+    pub fn hash(&mut self) -> Result<H256, VerkleError> {
+        let mut batch = vec![];
+        let commitment = self.root.commit(&mut batch);
+        if commitment.is_zero() {
+            return Ok(EMPTY_VERKLE_ROOT);
+        }
+        self.db.put_batch(batch)?;
+        Ok(H256(commitment.to_bytes()))
+    }
+
+    /// Returns a reference to the tree's underlying DB
+    pub fn db(&self) -> &dyn TrieDB {
+        self.db.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use ethrex_trie::InMemoryTrieDB;
+
+    use super::*;
+
+    pub(crate) fn new_temp() -> VerkleTree {
+        let map = Arc::new(Mutex::new(HashMap::new()));
+        VerkleTree::new(Box::new(InMemoryTrieDB::new(map)))
+    }
+
+    fn key(stem_byte: u8, second_byte: u8, suffix: u8) -> [u8; 32] {
+        let mut key = [stem_byte; 32];
+        key[1] = second_byte;
+        key[31] = suffix;
+        key
+    }
+
+    #[test]
+    fn get_inserted_values() {
+        let mut tree = new_temp();
+        assert_eq!(tree.hash().unwrap(), EMPTY_VERKLE_ROOT);
+        tree.insert(&key(1, 1, 0), [1; 32]).unwrap();
+        tree.insert(&key(1, 1, 200), [2; 32]).unwrap();
+        // Shares the first byte of the stem, splitting the existing stem node
+        tree.insert(&key(1, 2, 5), [3; 32]).unwrap();
+        tree.insert(&key(7, 1, 5), [0; 32]).unwrap();
+
+        assert_eq!(tree.get(&key(1, 1, 0)).unwrap(), Some([1; 32]));
+        assert_eq!(tree.get(&key(1, 1, 200)).unwrap(), Some([2; 32]));
+        assert_eq!(tree.get(&key(1, 2, 5)).unwrap(), Some([3; 32]));
+        assert_eq!(tree.get(&key(7, 1, 5)).unwrap(), Some([0; 32]));
+        assert_eq!(tree.get(&key(1, 1, 1)).unwrap(), None);
+        assert_eq!(tree.get(&key(1, 3, 5)).unwrap(), None);
+        assert_eq!(tree.get(&key(2, 1, 5)).unwrap(), None);
+    }
+
+    #[test]
+    fn root_is_independent_of_insertion_order() {
+        let keys: Vec<_> = (0..20u8).map(|i| key(i % 3, i, i * 7)).collect();
+        let mut tree = new_temp();
+        for (i, key) in keys.iter().enumerate() {
+            tree.insert(key, [i as u8; 32]).unwrap();
+        }
+        let mut other = new_temp();
+        for (i, key) in keys.iter().enumerate().rev() {
+            other.insert(key, [i as u8; 32]).unwrap();
+        }
+        assert_eq!(tree.hash().unwrap(), other.hash().unwrap());
+    }
+
+    #[test]
+    fn reopen_committed_tree() {
+        let map = Arc::new(Mutex::new(HashMap::new()));
+        let mut tree = VerkleTree::new(Box::new(InMemoryTrieDB::new(map.clone())));
+        tree.insert(&key(1, 1, 0), [1; 32]).unwrap();
+        tree.insert(&key(1, 2, 0), [2; 32]).unwrap();
+        let root = tree.hash().unwrap();
+
+        let mut reopened = VerkleTree::open(Box::new(InMemoryTrieDB::new(map)), root).unwrap();
+        assert_eq!(reopened.get(&key(1, 2, 0)).unwrap(), Some([2; 32]));
+        assert_eq!(reopened.hash().unwrap(), root);
+
+        // Updating the reopened tree matches updating the original one
+        reopened.insert(&key(1, 1, 3), [3; 32]).unwrap();
+        tree.insert(&key(1, 1, 3), [3; 32]).unwrap();
+        assert_eq!(reopened.hash().unwrap(), tree.hash().unwrap());
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+
+use ark_ff::One;
+use ethereum_types::H256;
+use ethrex_trie::TrieDB;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    banderwagon::{Element, Fr},
+    crs::CRS,
+    error::VerkleError,
+    ipa::IpaProof,
+    key::{split_key, Stem},
+    multiproof::{MultiPointProof, ProverQuery, VerifierQuery},
+    node::{
+        stem_to_scalar, value_to_scalars, InternalNode, Node, StemNode, VerkleValue,
+        VALUES_PER_SUB_COMMITMENT,
+    },
+    transcript::Transcript,
+    VerkleTree,
+};
+
+/// Label of the transcript of the proofs of execution witnesses
+const TRANSCRIPT_LABEL: &[u8] = b"vt";
+
+/// Status of the stem of an accessed key, packed along with its depth in the proof
+const EXTENSION_ABSENT: u8 = 0;
+const EXTENSION_OTHER_STEM: u8 = 1;
+const EXTENSION_PRESENT: u8 = 2;
+
+/// Identifies an opened commitment by the path to its node and which of the node's
+/// commitments it is: its own or one of the sub commitments of a stem node
+type CommitmentPath = (Vec<u8>, u8);
+const NODE_COMMITMENT: u8 = 0;
+
+fn sub_commitment_path(path: &[u8], half: usize) -> CommitmentPath {
+    (path.to_vec(), 1 + half as u8)
+}
+
+/// Values accessed while executing a block and the proof of their pre-state values, as
+/// specified in EIP-6800
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionWitness {
+    pub state_diff: Vec<StemStateDiff>,
+    pub verkle_proof: VerkleProof,
+}
+
+/// Accessed values sharing a stem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StemStateDiff {
+    #[serde(with = "crate::serde_utils::fixed_bytes")]
+    pub stem: Stem,
+    pub suffix_diffs: Vec<SuffixStateDiff>,
+}
+
+/// Pre-state value of an accessed key, along with its new value if it was written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuffixStateDiff {
+    pub suffix: u8,
+    #[serde(with = "crate::serde_utils::fixed_bytes::option")]
+    pub current_value: Option<VerkleValue>,
+    #[serde(with = "crate::serde_utils::fixed_bytes::option")]
+    pub new_value: Option<VerkleValue>,
+}
+
+/// Proof of the pre-state values of the state diff. Along with the values, it opens the
+/// commitments of every node on the paths to their stems, which are given sorted by path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerkleProof {
+    /// Stems found in place of the stems of absent keys
+    #[serde(with = "crate::serde_utils::fixed_bytes::vec")]
+    pub other_stems: Vec<Stem>,
+    /// Depth at which each stem of the state diff ends in the tree and its status
+    #[serde(with = "crate::serde_utils::bytes")]
+    pub depth_extension_present: Vec<u8>,
+    /// Commitments of every opened node, excluding the root
+    #[serde(with = "crate::serde_utils::element::vec")]
+    pub commitments_by_path: Vec<Element>,
+    #[serde(with = "crate::serde_utils::element")]
+    pub d: Element,
+    pub ipa_proof: IpaProof,
+}
+
+/// Openings gathered while traversing the tree, keeping the polynomial of each opened
+/// commitment to build the queries of the proof
+#[derive(Default)]
+struct Openings {
+    polynomials: BTreeMap<CommitmentPath, (Element, Vec<Fr>)>,
+    points: BTreeSet<(CommitmentPath, u8)>,
+}
+
+impl Openings {
+    fn open(
+        &mut self,
+        path: CommitmentPath,
+        point: u8,
+        commitment: Element,
+        polynomial: impl FnOnce() -> Vec<Fr>,
+    ) {
+        self.points.insert((path.clone(), point));
+        self.polynomials
+            .entry(path)
+            .or_insert_with(|| (commitment, polynomial()));
+    }
+
+    fn open_stem(&mut self, path: &[u8], node: &StemNode, suffixes: &[u8]) {
+        let commitment = node.commitment().unwrap_or_else(Element::zero);
+        for point in [0, 1] {
+            self.open((path.to_vec(), NODE_COMMITMENT), point, commitment, || {
+                node.polynomial()
+            });
+        }
+        for suffix in suffixes {
+            let half = *suffix as usize / VALUES_PER_SUB_COMMITMENT;
+            self.open(
+                (path.to_vec(), NODE_COMMITMENT),
+                2 + half as u8,
+                commitment,
+                || node.polynomial(),
+            );
+            let index = 2 * (*suffix as usize % VALUES_PER_SUB_COMMITMENT) as u8;
+            let sub_commitment = node.sub_commitment(half).unwrap_or_else(Element::zero);
+            for point in [index, index + 1] {
+                self.open(
+                    sub_commitment_path(path, half),
+                    point,
+                    sub_commitment,
+                    || node.half_polynomial(half),
+                );
+            }
+        }
+    }
+
+    fn into_queries(self) -> (Vec<ProverQuery>, Vec<Element>) {
+        let queries = self
+            .points
+            .iter()
+            .map(|(path, point)| {
+                let (commitment, polynomial) = &self.polynomials[path];
+                ProverQuery {
+                    commitment: *commitment,
+                    polynomial: polynomial.clone(),
+                    point: *point,
+                    result: polynomial[*point as usize],
+                }
+            })
+            .collect();
+        let root_path = (vec![], NODE_COMMITMENT);
+        let commitments = self
+            .polynomials
+            .into_iter()
+            .filter(|(path, _)| *path != root_path)
+            .map(|(_, (commitment, _))| commitment)
+            .collect();
+        (queries, commitments)
+    }
+}
+
+/// Where the traversal of the tree towards a stem ended
+struct StemOpening {
+    depth: u8,
+    extension: u8,
+    values: BTreeMap<u8, VerkleValue>,
+}
+
+impl VerkleTree {
+    /// Builds the execution witness of a block from the keys accessed during its execution,
+    /// along with the values written to them, proving their values in the current tree.
+    /// If a key is accessed more than once, the last value written to it is kept
+    pub fn execution_witness(
+        &mut self,
+        accesses: impl IntoIterator<Item = ([u8; 32], Option<VerkleValue>)>,
+    ) -> Result<ExecutionWitness, VerkleError> {
+        // Every opened commitment must be up to date
+        self.hash()?;
+        let mut stems: BTreeMap<Stem, BTreeMap<u8, Option<VerkleValue>>> = BTreeMap::new();
+        for (key, new_value) in accesses {
+            let (stem, suffix) = split_key(&key);
+            let entry = stems.entry(stem).or_default().entry(suffix).or_default();
+            if new_value.is_some() {
+                *entry = new_value;
+            }
+        }
+
+        let mut openings = Openings::default();
+        let mut other_stems = BTreeSet::new();
+        let mut depth_extension_present = Vec::with_capacity(stems.len());
+        let mut state_diff = Vec::with_capacity(stems.len());
+        for (stem, suffixes) in stems {
+            let suffix_keys: Vec<u8> = suffixes.keys().copied().collect();
+            let opening = open_stem(
+                self.db.as_ref(),
+                &self.root,
+                0,
+                &stem,
+                &suffix_keys,
+                &mut openings,
+                &mut other_stems,
+            )?;
+            depth_extension_present.push(opening.depth << 3 | opening.extension);
+            state_diff.push(StemStateDiff {
+                stem,
+                suffix_diffs: suffixes
+                    .into_iter()
+                    .map(|(suffix, new_value)| SuffixStateDiff {
+                        suffix,
+                        current_value: opening.values.get(&suffix).copied(),
+                        new_value,
+                    })
+                    .collect(),
+            });
+        }
+
+        let (queries, commitments_by_path) = openings.into_queries();
+        let proof = MultiPointProof::open(&CRS, &mut Transcript::new(TRANSCRIPT_LABEL), &queries);
+        Ok(ExecutionWitness {
+            state_diff,
+            verkle_proof: VerkleProof {
+                other_stems: other_stems.into_iter().collect(),
+                depth_extension_present,
+                commitments_by_path,
+                d: proof.d,
+                ipa_proof: proof.ipa,
+            },
+        })
+    }
+}
+
+/// Opens the commitments of the path to a stem from the given internal node
+fn open_stem(
+    db: &dyn TrieDB,
+    node: &InternalNode,
+    depth: usize,
+    stem: &Stem,
+    suffixes: &[u8],
+    openings: &mut Openings,
+    other_stems: &mut BTreeSet<Stem>,
+) -> Result<StemOpening, VerkleError> {
+    let index = stem[depth];
+    openings.open(
+        (stem[..depth].to_vec(), NODE_COMMITMENT),
+        index,
+        node.commitment().unwrap_or_else(Element::zero),
+        || node.polynomial(),
+    );
+    let child_depth = depth + 1;
+    let path = &stem[..child_depth];
+    let (extension, values) = match node.children[index as usize].node(db)? {
+        None => (EXTENSION_ABSENT, BTreeMap::new()),
+        Some(child) => match child.as_ref() {
+            Node::Internal(internal) => {
+                return open_stem(
+                    db,
+                    internal,
+                    child_depth,
+                    stem,
+                    suffixes,
+                    openings,
+                    other_stems,
+                )
+            }
+            Node::Stem(leaf) if leaf.stem == *stem => {
+                openings.open_stem(path, leaf, suffixes);
+                (EXTENSION_PRESENT, leaf.values.clone())
+            }
+            Node::Stem(leaf) => {
+                openings.open_stem(path, leaf, &[]);
+                other_stems.insert(leaf.stem);
+                (EXTENSION_OTHER_STEM, BTreeMap::new())
+            }
+        },
+    };
+    Ok(StemOpening {
+        depth: child_depth as u8,
+        extension,
+        values,
+    })
+}
+
+impl ExecutionWitness {
+    /// Checks that the pre-state values of the state diff are the ones of the tree with the
+    /// given root, rebuilding the openings of the proof from the paths to their stems
+    pub fn verify(&self, pre_state_root: H256) -> Result<(), VerkleError> {
+        let proof = &self.verkle_proof;
+        if self.state_diff.len() != proof.depth_extension_present.len() {
+            return Err(VerkleError::Verify(
+                "Every stem needs its depth and extension status".to_string(),
+            ));
+        }
+        if !self.state_diff.windows(2).all(|w| w[0].stem < w[1].stem) {
+            return Err(VerkleError::Verify(
+                "Stems must be sorted and unique".to_string(),
+            ));
+        }
+
+        // Gather every opened commitment to match them to the ones given by the proof
+        let mut paths = BTreeSet::from([(vec![], NODE_COMMITMENT)]);
+        for (diff, depth_extension) in self.state_diff.iter().zip(&proof.depth_extension_present) {
+            let (depth, extension) = split_depth_extension(*depth_extension)?;
+            paths.extend((1..depth).map(|depth| (diff.stem[..depth].to_vec(), NODE_COMMITMENT)));
+            if extension != EXTENSION_ABSENT {
+                paths.insert((diff.stem[..depth].to_vec(), NODE_COMMITMENT));
+            }
+            if extension == EXTENSION_PRESENT {
+                paths.extend(diff.suffix_diffs.iter().map(|suffix_diff| {
+                    let half = suffix_diff.suffix as usize / VALUES_PER_SUB_COMMITMENT;
+                    sub_commitment_path(&diff.stem[..depth], half)
+                }));
+            }
+        }
+        if paths.len() != proof.commitments_by_path.len() + 1 {
+            return Err(VerkleError::Verify(
+                "Commitments don't match the opened paths".to_string(),
+            ));
+        }
+        let root =
+            Element::from_bytes(pre_state_root.as_bytes()).ok_or(VerkleError::InvalidCommitment)?;
+        let commitments: BTreeMap<CommitmentPath, Element> = paths
+            .into_iter()
+            .zip(std::iter::once(root).chain(proof.commitments_by_path.iter().copied()))
+            .collect();
+
+        let mut results = BTreeMap::new();
+        for (diff, depth_extension) in self.state_diff.iter().zip(&proof.depth_extension_present) {
+            let (depth, extension) = split_depth_extension(*depth_extension)?;
+            let stem = &diff.stem;
+            // Internal nodes open to the commitment of their child, or zero if absent
+            for depth in 0..depth {
+                let child = commitments.get(&(stem[..depth + 1].to_vec(), NODE_COMMITMENT));
+                let result = child
+                    .map(|child| child.map_to_scalar_field())
+                    .unwrap_or_default();
+                add_result(
+                    &mut results,
+                    (stem[..depth].to_vec(), NODE_COMMITMENT),
+                    stem[depth],
+                    result,
+                )?;
+            }
+            let path = (stem[..depth].to_vec(), NODE_COMMITMENT);
+            match extension {
+                EXTENSION_ABSENT | EXTENSION_OTHER_STEM => {
+                    if diff.suffix_diffs.iter().any(|d| d.current_value.is_some()) {
+                        return Err(VerkleError::Verify(
+                            "Values of missing stems can't be present".to_string(),
+                        ));
+                    }
+                    if extension == EXTENSION_OTHER_STEM {
+                        let other_stem = proof
+                            .other_stems
+                            .iter()
+                            .find(|other| other[..depth] == stem[..depth] && *other != stem)
+                            .ok_or(VerkleError::Verify("Missing other stem".to_string()))?;
+                        add_result(&mut results, path.clone(), 0, Fr::one())?;
+                        add_result(&mut results, path, 1, stem_to_scalar(other_stem))?;
+                    }
+                }
+                _ => {
+                    add_result(&mut results, path.clone(), 0, Fr::one())?;
+                    add_result(&mut results, path.clone(), 1, stem_to_scalar(stem))?;
+                    for suffix_diff in diff.suffix_diffs.iter() {
+                        let half = suffix_diff.suffix as usize / VALUES_PER_SUB_COMMITMENT;
+                        let sub_path = sub_commitment_path(&stem[..depth], half);
+                        let sub_commitment = commitments[&sub_path].map_to_scalar_field();
+                        add_result(&mut results, path.clone(), 2 + half as u8, sub_commitment)?;
+                        let index =
+                            2 * (suffix_diff.suffix as usize % VALUES_PER_SUB_COMMITMENT) as u8;
+                        let (lower, upper) = value_to_scalars(suffix_diff.current_value.as_ref());
+                        add_result(&mut results, sub_path.clone(), index, lower)?;
+                        add_result(&mut results, sub_path, index + 1, upper)?;
+                    }
+                }
+            }
+        }
+
+        let queries: Vec<VerifierQuery> = results
+            .into_iter()
+            .map(|((path, point), result)| VerifierQuery {
+                commitment: commitments[&path],
+                point,
+                result,
+            })
+            .collect();
+        let proof = MultiPointProof {
+            ipa: proof.ipa_proof.clone(),
+            d: proof.d,
+        };
+        if !proof.check(&CRS, &mut Transcript::new(TRANSCRIPT_LABEL), &queries) {
+            return Err(VerkleError::Verify("Invalid proof".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn split_depth_extension(depth_extension: u8) -> Result<(usize, u8), VerkleError> {
+    let depth = (depth_extension >> 3) as usize;
+    let extension = depth_extension & 0b111;
+    if depth == 0 || depth > 31 || extension > EXTENSION_PRESENT {
+        return Err(VerkleError::Verify(
+            "Invalid depth or extension status".to_string(),
+        ));
+    }
+    Ok((depth, extension))
+}
+
+/// Adds the expected result of an opening, which must match the one added by other stems
+/// sharing the opened node
+fn add_result(
+    results: &mut BTreeMap<(CommitmentPath, u8), Fr>,
+    path: CommitmentPath,
+    point: u8,
+    result: Fr,
+) -> Result<(), VerkleError> {
+    match results.entry((path, point)) {
+        Entry::Vacant(entry) => {
+            entry.insert(result);
+        }
+        Entry::Occupied(entry) if *entry.get() != result => {
+            return Err(VerkleError::Verify(
+                "Conflicting openings of the same node".to_string(),
+            ))
+        }
+        Entry::Occupied(_) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::new_temp;
+
+    fn key(stem_byte: u8, second_byte: u8, suffix: u8) -> [u8; 32] {
+        let mut key = [stem_byte; 32];
+        key[1] = second_byte;
+        key[31] = suffix;
+        key
+    }
+
+    fn populated_tree() -> VerkleTree {
+        let mut tree = new_temp();
+        tree.insert(&key(1, 1, 0), [1; 32]).unwrap();
+        tree.insert(&key(1, 1, 200), [2; 32]).unwrap();
+        tree.insert(&key(1, 2, 5), [3; 32]).unwrap();
+        tree.insert(&key(7, 1, 5), [0; 32]).unwrap();
+        tree
+    }
+
+    #[test]
+    fn witness_of_present_and_absent_keys() {
+        let mut tree = populated_tree();
+        let root = tree.hash().unwrap();
+        let witness = tree
+            .execution_witness([
+                // Present stem, with a present and a missing value
+                (key(1, 1, 0), Some([9; 32])),
+                (key(1, 1, 130), None),
+                // Stem sharing the path to an existing one
+                (key(7, 5, 5), None),
+                // Stem sharing the path to an internal node with an empty child
+                (key(1, 9, 5), Some([4; 32])),
+                // Stem under an empty child of the root
+                (key(2, 1, 5), None),
+            ])
+            .unwrap();
+        witness.verify(root).unwrap();
+
+        assert_eq!(witness.state_diff.len(), 4);
+        assert_eq!(
+            witness.state_diff[0].suffix_diffs,
+            vec![
+                SuffixStateDiff {
+                    suffix: 0,
+                    current_value: Some([1; 32]),
+                    new_value: Some([9; 32]),
+                },
+                SuffixStateDiff {
+                    suffix: 130,
+                    current_value: None,
+                    new_value: None,
+                },
+            ]
+        );
+        assert_eq!(
+            witness.verkle_proof.depth_extension_present,
+            vec![
+                2 << 3 | EXTENSION_PRESENT,
+                2 << 3 | EXTENSION_ABSENT,
+                1 << 3 | EXTENSION_ABSENT,
+                1 << 3 | EXTENSION_OTHER_STEM,
+            ]
+        );
+        assert_eq!(
+            witness.verkle_proof.other_stems,
+            vec![split_key(&key(7, 1, 0)).0]
+        );
+
+        // The proof doesn't hold for a different pre-state
+        let mut tampered = witness.clone();
+        tampered.state_diff[0].suffix_diffs[0].current_value = Some([2; 32]);
+        assert!(tampered.verify(root).is_err());
+        let mut tampered = witness.clone();
+        tampered.state_diff[0].suffix_diffs[1].current_value = Some([0; 32]);
+        assert!(tampered.verify(root).is_err());
+        tree.insert(&key(1, 2, 6), [1; 32]).unwrap();
+        assert!(witness.verify(tree.hash().unwrap()).is_err());
+    }
+
+    #[test]
+    fn witness_of_empty_tree() {
+        let mut tree = new_temp();
+        let witness = tree
+            .execution_witness([(key(1, 1, 0), Some([1; 32]))])
+            .unwrap();
+        witness.verify(tree.hash().unwrap()).unwrap();
+        assert!(witness.verkle_proof.commitments_by_path.is_empty());
+    }
+
+    #[test]
+    fn witness_json_roundtrip() {
+        let mut tree = populated_tree();
+        let root = tree.hash().unwrap();
+        let witness = tree
+            .execution_witness([(key(1, 2, 5), None), (key(3, 3, 3), Some([3; 32]))])
+            .unwrap();
+        let json = serde_json::to_value(&witness).unwrap();
+        assert!(json["verkleProof"]["ipaProof"]["finalEvaluation"].is_string());
+        assert!(json["stateDiff"][0]["suffixDiffs"][0]["newValue"].is_null());
+        let decoded: ExecutionWitness = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, witness);
+        decoded.verify(root).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
-use ethrex_core::{types::BlockHash, Address as CoreAddress, H256 as CoreH256};
+use ethrex_core::{types::BlockHash, Address as CoreAddress, H256 as CoreH256, U256 as CoreU256};
+use ethrex_levm::db::Database as LevmDatabase;
 use ethrex_storage::{error::StoreError, Store};
 use revm::primitives::{
     AccountInfo as RevmAccountInfo, Address as RevmAddress, Bytecode as RevmBytecode,
@@ -10,45 +11,47 @@ pub struct StoreWrapper {
     pub block_hash: BlockHash,
 }
 
-cfg_if::cfg_if! {
-    if #[cfg(feature = "levm")] {
-        use ethrex_core::{U256 as CoreU256};
-        use ethrex_levm::db::Database as LevmDatabase;
-
-        impl LevmDatabase for StoreWrapper {
-            fn get_account_info(&self, address: CoreAddress) -> ethrex_levm::account::AccountInfo {
-                let acc_info = self
-                    .store
-                    .get_account_info_by_hash(self.block_hash, address)
-                    .unwrap()
-                    .unwrap_or_default();
+impl StoreWrapper {
+    /// Returns whether the account is in the state, as opposed to just having empty info
+    pub fn account_exists(&self, address: CoreAddress) -> bool {
+        self.store
+            .get_account_info_by_hash(self.block_hash, address)
+            .unwrap()
+            .is_some()
+    }
+}
 
-                let acc_code = self
-                    .store
-                    .get_account_code(acc_info.code_hash)
-                    .unwrap()
-                    .unwrap_or_default();
+impl LevmDatabase for StoreWrapper {
+    fn get_account_info(&self, address: CoreAddress) -> ethrex_levm::account::AccountInfo {
+        let acc_info = self
+            .store
+            .get_account_info_by_hash(self.block_hash, address)
+            .unwrap()
+            .unwrap_or_default();
 
-                ethrex_levm::account::AccountInfo {
-                    balance: acc_info.balance,
-                    nonce: acc_info.nonce,
-                    bytecode: acc_code,
-                }
-            }
+        let acc_code = self
+            .store
+            .get_account_code(acc_info.code_hash)
+            .unwrap()
+            .unwrap_or_default();
 
-            fn get_storage_slot(&self, address: CoreAddress, key: CoreH256) -> CoreU256 {
-                self.store
-                    .get_storage_at_hash(self.block_hash, address, key)
-                    .unwrap()
-                    .unwrap_or_default()
-            }
+        ethrex_levm::account::AccountInfo {
+            balance: acc_info.balance,
+            nonce: acc_info.nonce,
+            bytecode: acc_code,
+            code_hash: acc_info.code_hash,
+        }
+    }
 
-            fn get_block_hash(&self, block_number: u64) -> Option<CoreH256> {
-                let a = self.store.get_block_header(block_number).unwrap();
+    fn get_storage_slot(&self, address: CoreAddress, key: CoreH256) -> CoreU256 {
+        self.store
+            .get_storage_at_hash(self.block_hash, address, key)
+            .unwrap()
+            .unwrap_or_default()
+    }
 
-                a.map(|a| CoreH256::from(a.compute_block_hash().0))
-            }
-        }
+    fn get_block_hash(&self, block_number: u64) -> Option<CoreH256> {
+        self.store.get_canonical_block_hash(block_number).unwrap()
     }
 }
 
@@ -97,8 +100,8 @@ impl revm::Database for StoreWrapper {
 
     fn block_hash(&mut self, number: u64) -> Result<RevmB256, Self::Error> {
         self.store
-            .get_block_header(number)?
-            .map(|header| RevmB256::from_slice(&header.compute_block_hash().0))
+            .get_canonical_block_hash(number)?
+            .map(|hash| RevmB256::from_slice(&hash.0))
             .ok_or_else(|| StoreError::Custom(format!("Block {number} not found")))
     }
 }
@@ -0,0 +1,280 @@
+use crate::{
+    comparison::ComparisonReport, execute_system_calls, execute_tx, get_state_transitions,
+    levm_executor::LevmBlockExecutor, process_withdrawals, spec_id, EvmEngine, EvmError, EvmState,
+    TxOutcome,
+};
+use ethrex_core::{
+    types::{Block, BlockNumber, Receipt},
+    Address, H256, U256,
+};
+use ethrex_levm::{db::CacheDB, Account, StorageSlot};
+use ethrex_storage::{error::StoreError, AccountUpdate, Store};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// Result of executing a block with both LEVM and revm
+pub struct DifferentialExecution {
+    /// Receipts produced by the selected engine
+    pub receipts: Vec<Receipt>,
+    /// Account updates produced by the selected engine
+    pub account_updates: Vec<AccountUpdate>,
+    /// First transaction in which both executions diverged, if any
+    pub divergence: Option<Divergence>,
+}
+
+/// Differences found when executing a transaction with LEVM and revm
+#[derive(Debug)]
+pub struct Divergence {
+    pub block_number: BlockNumber,
+    pub tx_index: usize,
+    pub tx_hash: H256,
+    /// Outcome of the transaction in each engine, or the error that prevented its execution
+    pub levm_outcome: Result<TxOutcome, String>,
+    pub revm_outcome: Result<TxOutcome, String>,
+    /// Comparison of the account updates made by each engine, if both executed the transaction
+    pub account_updates: Option<ComparisonReport>,
+}
+
+/// Executes a block with both LEVM and revm, comparing the outcome of each transaction, and returns
+/// the receipts and account updates produced by `engine`
+/// Execution fails only if it fails in `engine`. Once a divergence is found, the other engine stops
+/// executing the block
+pub fn execute_block_differential(
+    block: &Block,
+    state: &mut EvmState,
+    engine: EvmEngine,
+) -> Result<DifferentialExecution, EvmError> {
+    let block_header = &block.header;
+    let spec_id = spec_id(&state.chain_config()?, block_header.timestamp);
+    let store = state
+        .database()
+        .cloned()
+        .ok_or(EvmError::DB(StoreError::MissingStore))?;
+
+    // Both engines start from the state left by the system calls, which are performed by revm
+    let system_updates = execute_system_calls(block_header, state)?;
+    let mut levm = LevmBlockExecutor::new(block_header, state, system_updates.clone())?;
+    let mut revm_receipts = Vec::new();
+    let mut revm_account_updates = system_updates.clone();
+    let mut levm_account_updates = system_updates;
+    let mut cumulative_gas_used = 0;
+    let mut divergence: Option<Divergence> = None;
+
+    for (tx_index, transaction) in block.body.transactions.iter().enumerate() {
+        let mut execute_revm_tx = |state: &mut EvmState| {
+            let result = execute_tx(transaction, block_header, state, spec_id)?;
+            cumulative_gas_used += result.gas_used();
+            revm_receipts.push(Receipt::new(
+                transaction.tx_type(),
+                result.is_success(),
+                cumulative_gas_used,
+                result.logs(),
+            ));
+            Ok::<_, EvmError>(TxOutcome {
+                success: result.is_success(),
+                gas_used: result.gas_used(),
+                logs: result.logs(),
+                account_updates: get_state_transitions(state),
+            })
+        };
+
+        if divergence.is_some() {
+            // Once diverged, only the selected engine keeps executing the block
+            match engine {
+                EvmEngine::REVM => {
+                    revm_account_updates.extend(execute_revm_tx(state)?.account_updates)
+                }
+                EvmEngine::LEVM => {
+                    levm.execute_tx(transaction, block_header)?;
+                }
+            }
+            continue;
+        }
+
+        let pre_tx_state = levm.state().clone();
+        let revm_outcome = unless_selected(execute_revm_tx(state), engine == EvmEngine::REVM)?;
+        let levm_outcome = unless_selected(
+            levm.execute_tx(transaction, block_header),
+            engine == EvmEngine::LEVM,
+        )?;
+        if let Ok(outcome) = &revm_outcome {
+            revm_account_updates.extend(outcome.account_updates.iter().cloned());
+        }
+        if let Ok(outcome) = &levm_outcome {
+            levm_account_updates.extend(outcome.account_updates.iter().cloned());
+        }
+
+        let account_updates = match (&levm_outcome, &revm_outcome) {
+            (Ok(levm_tx_outcome), Ok(revm_tx_outcome)) => {
+                if outcomes_match(levm_tx_outcome, revm_tx_outcome) {
+                    continue;
+                }
+                Some(compare_account_updates(
+                    &store,
+                    block_header.parent_hash,
+                    &levm,
+                    &pre_tx_state,
+                    (&levm_account_updates, &levm_tx_outcome.account_updates),
+                    (&revm_account_updates, &revm_tx_outcome.account_updates),
+                )?)
+            }
+            _ => None,
+        };
+        divergence = Some(Divergence {
+            block_number: block_header.number,
+            tx_index,
+            tx_hash: transaction.compute_hash(),
+            levm_outcome,
+            revm_outcome,
+            account_updates,
+        });
+    }
+
+    let (receipts, account_updates) = match engine {
+        EvmEngine::REVM => {
+            if let Some(withdrawals) = &block.body.withdrawals {
+                process_withdrawals(state, withdrawals)?;
+            }
+            revm_account_updates.extend(get_state_transitions(state));
+            (revm_receipts, revm_account_updates)
+        }
+        EvmEngine::LEVM => levm.finish(block.body.withdrawals.as_deref()),
+    };
+    Ok(DifferentialExecution {
+        receipts,
+        account_updates,
+        divergence,
+    })
+}
+
+/// Execution errors are only propagated for the selected engine, for the other one they are reported
+fn unless_selected(
+    outcome: Result<TxOutcome, EvmError>,
+    is_selected: bool,
+) -> Result<Result<TxOutcome, String>, EvmError> {
+    match outcome {
+        Err(error) if is_selected => Err(error),
+        outcome => Ok(outcome.map_err(|error| error.to_string())),
+    }
+}
+
+fn outcomes_match(levm: &TxOutcome, revm: &TxOutcome) -> bool {
+    levm.success == revm.success
+        && levm.gas_used == revm.gas_used
+        && levm.logs == revm.logs
+        && normalized_updates(&levm.account_updates) == normalized_updates(&revm.account_updates)
+}
+
+/// Account update fields that should match between engines, sorted by address
+#[allow(clippy::type_complexity)]
+fn normalized_updates(
+    account_updates: &[AccountUpdate],
+) -> BTreeMap<Address, (bool, Option<(u64, U256, H256)>, BTreeMap<H256, U256>)> {
+    account_updates
+        .iter()
+        .map(|update| {
+            let info = update
+                .info
+                .as_ref()
+                .map(|info| (info.nonce, info.balance, info.code_hash));
+            let storage = update
+                .added_storage
+                .iter()
+                .map(|(key, value)| (*key, *value))
+                .collect();
+            (update.address, (update.removed, info, storage))
+        })
+        .collect()
+}
+
+/// Builds a report comparing the account updates of both engines for the divergent transaction,
+/// along with the state roots resulting from applying the updates of the whole block so far
+fn compare_account_updates(
+    store: &Store,
+    parent_hash: H256,
+    levm: &LevmBlockExecutor,
+    pre_tx_state: &CacheDB,
+    (levm_block_updates, levm_tx_updates): (&[AccountUpdate], &[AccountUpdate]),
+    (revm_block_updates, revm_tx_updates): (&[AccountUpdate], &[AccountUpdate]),
+) -> Result<ComparisonReport, EvmError> {
+    // The roots are only computed, as the divergent updates shouldn't reach the store
+    let levm_post_state_root = store
+        .state_root_after_updates(parent_hash, levm_block_updates)?
+        .unwrap_or_default();
+    let revm_post_state_root = store
+        .state_root_after_updates(parent_hash, revm_block_updates)?
+        .unwrap_or_default();
+
+    // State of the updated accounts before the transaction
+    let mut initial_accounts: HashMap<Address, Account> = HashMap::new();
+    for update in levm_tx_updates.iter().chain(revm_tx_updates) {
+        let account = initial_accounts
+            .entry(update.address)
+            .or_insert_with(|| levm.account_at(pre_tx_state, update.address));
+        for key in update.added_storage.keys() {
+            let value = levm.storage_at(pre_tx_state, update.address, *key);
+            account.storage.insert(
+                *key,
+                StorageSlot {
+                    original_value: value,
+                    current_value: value,
+                },
+            );
+        }
+    }
+
+    Ok(ComparisonReport::new(
+        levm_post_state_root,
+        revm_post_state_root,
+        initial_accounts,
+        levm_tx_updates.to_vec(),
+        revm_tx_updates.to_vec(),
+    ))
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "LEVM and REVM executions of block {} diverged at transaction {} ({:#x})",
+            self.block_number, self.tx_index, self.tx_hash
+        )?;
+        match (&self.levm_outcome, &self.revm_outcome) {
+            (Ok(levm), Ok(revm)) => {
+                if levm.success != revm.success {
+                    writeln!(
+                        f,
+                        "Success mismatch: LEVM: {}, REVM: {}",
+                        levm.success, revm.success
+                    )?;
+                }
+                if levm.gas_used != revm.gas_used {
+                    writeln!(
+                        f,
+                        "Gas used mismatch: LEVM: {}, REVM: {}",
+                        levm.gas_used, revm.gas_used
+                    )?;
+                }
+                if levm.logs != revm.logs {
+                    writeln!(f, "Logs mismatch:")?;
+                    writeln!(f, "  LEVM: {:?}", levm.logs)?;
+                    writeln!(f, "  REVM: {:?}", revm.logs)?;
+                }
+            }
+            (levm, revm) => {
+                if let Err(error) = levm {
+                    writeln!(f, "LEVM execution failed: {error}")?;
+                }
+                if let Err(error) = revm {
+                    writeln!(f, "REVM execution failed: {error}")?;
+                }
+            }
+        }
+        if let Some(account_updates) = &self.account_updates {
+            write!(f, "{account_updates}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use std::{fmt, str::FromStr};
+
+/// EVM implementation used to execute the imported blocks
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EvmEngine {
+    #[default]
+    REVM,
+    LEVM,
+}
+
+impl FromStr for EvmEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "revm" => Ok(EvmEngine::REVM),
+            "levm" => Ok(EvmEngine::LEVM),
+            _ => Err(format!("Invalid EVM engine: {s}, expected revm or levm")),
+        }
+    }
+}
+
+impl fmt::Display for EvmEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvmEngine::REVM => write!(f, "revm"),
+            EvmEngine::LEVM => write!(f, "levm"),
+        }
+    }
+}
+
+/// Selects how the imported blocks are executed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvmConfig {
+    /// Engine whose execution results are used to update the state
+    pub engine: EvmEngine,
+    /// If set, every block is also executed with the other engine and the first transaction
+    /// in which both executions diverge is reported
+    pub differential: bool,
+}
@@ -7,7 +7,7 @@ use ethrex_core::{
 };
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_storage::{hash_address, hash_key, Store};
-use ethrex_trie::{NodeRLP, Trie};
+use ethrex_trie::{MultiProof, Trie};
 use revm::{
     primitives::{
         AccountInfo as RevmAccountInfo, Address as RevmAddress, Bytecode as RevmBytecode,
@@ -35,12 +35,12 @@ pub struct ExecutionDB {
     pub block_hashes: HashMap<u64, RevmB256>,
     /// stored chain config
     pub chain_config: ChainConfig,
-    /// encoded nodes to reconstruct a state trie, but only including relevant data (pruned).
-    /// root node is stored separately from the rest.
-    pub pruned_state_trie: (Option<NodeRLP>, Vec<NodeRLP>),
-    /// encoded nodes to reconstruct every storage trie, but only including relevant data (pruned)
-    /// root nodes are stored separately from the rest.
-    pub pruned_storage_tries: HashMap<H160, (Option<NodeRLP>, Vec<NodeRLP>)>,
+    /// proof of the relevant accounts to reconstruct a pruned state trie, including the nodes
+    /// needed to update or remove them
+    pub pruned_state_trie: MultiProof,
+    /// proofs of the relevant storage slots to reconstruct every pruned storage trie, including
+    /// the nodes needed to update or remove them
+    pub pruned_storage_tries: HashMap<H160, MultiProof>,
 }
 
 impl ExecutionDB {
@@ -105,7 +105,7 @@ impl ExecutionDB {
 
         // Get pruned state trie
         let state_paths: Vec<_> = address_storage_keys.keys().map(hash_address).collect();
-        let pruned_state_trie = state_trie.get_proofs(&state_paths)?;
+        let pruned_state_trie = state_trie.get_multiproof(&state_paths)?;
 
         // Get pruned storage tries for every account
         let mut pruned_storage_tries = HashMap::new();
@@ -117,9 +117,7 @@ impl ExecutionDB {
                     address,
                 ))?;
             let storage_paths: Vec<_> = keys.iter().map(hash_key).collect();
-            let (storage_trie_root, storage_trie_nodes) =
-                storage_trie.get_proofs(&storage_paths)?;
-            pruned_storage_tries.insert(address, (storage_trie_root, storage_trie_nodes));
+            pruned_storage_tries.insert(address, storage_trie.get_multiproof(&storage_paths)?);
         }
 
         Ok(Self {
@@ -140,8 +138,7 @@ impl ExecutionDB {
     /// Verifies that all data in [self] is included in the stored tries, and then builds the
     /// pruned tries from the stored nodes.
     pub fn build_tries(&self) -> Result<(Trie, HashMap<H160, Trie>), ExecutionDBError> {
-        let (state_trie_root, state_trie_nodes) = &self.pruned_state_trie;
-        let state_trie = Trie::from_nodes(state_trie_root.as_ref(), state_trie_nodes)?;
+        let state_trie = self.pruned_state_trie.trie()?;
         let mut storage_tries = HashMap::new();
 
         for (revm_address, account) in &self.accounts {
@@ -152,16 +149,16 @@ impl ExecutionDB {
                 return Err(ExecutionDBError::MissingAccountInStateTrie(address));
             }
 
-            let (storage_trie_root, storage_trie_nodes) =
-                self.pruned_storage_tries
-                    .get(&address)
-                    .ok_or(ExecutionDBError::MissingStorageTrie(address))?;
+            let storage_proof = self
+                .pruned_storage_tries
+                .get(&address)
+                .ok_or(ExecutionDBError::MissingStorageTrie(address))?;
 
             // compare account storage root with storage trie root
-            let storage_trie = Trie::from_nodes(storage_trie_root.as_ref(), storage_trie_nodes)?;
-            if storage_trie.hash_no_commit() != account.storage_root {
+            if storage_proof.root_hash() != account.storage_root {
                 return Err(ExecutionDBError::InvalidStorageTrieRoot(address));
             }
+            let storage_trie = storage_proof.trie()?;
 
             // check all storage keys are in storage trie and compare values
             let storage = self
@@ -1,5 +1,5 @@
 use bytes::Bytes;
-use ethrex_levm::{call_frame::CallFrame, errors::TxResult, utils::new_vm_with_bytecode};
+use ethrex_levm::{errors::TxResult, utils::new_vm_with_bytecode};
 use revm::{
     db::BenchmarkDB,
     primitives::{address, Bytecode, TransactTo},
@@ -11,24 +11,37 @@ pub const FIBONACCI_BYTECODE: &str =
     "5f355f60015b8215601a578181019150909160019003916005565b9150505f5260205ff3";
 pub const FACTORIAL_BYTECODE: &str =
     "5f355f60015b8215601b57906001018091029160019003916005565b9150505f5260205ff3";
+pub const CALLS_BYTECODE: &str =
+    "36600b5760015f555f5ffd5b5f355b80156024575f5f5f5f5f305af15060019003600e565b5f545f5260205ff3";
+/// Size the calls program is padded to, so that analyzing its jump destinations costs about as
+/// much as it does for a typical deployed contract.
+pub const CALLS_CODE_SIZE: usize = 4096;
+
+/// This program calls itself n times, with n passed via calldata. Each call writes a storage slot
+/// and reverts, so it stresses sub-call setup, jump destination analysis and state reverts rather
+/// than arithmetic. The slot is returned to check that every write was rolled back.
+pub fn calls_bytecode() -> String {
+    let padding = "5b".repeat(CALLS_CODE_SIZE - CALLS_BYTECODE.len() / 2);
+    format!("{CALLS_BYTECODE}{padding}")
+}
 
 pub fn run_with_levm(program: &str, runs: usize, number_of_iterations: u32) {
     let bytecode = Bytes::from(hex::decode(program).unwrap());
-    let mut call_frame = CallFrame::new_from_bytecode(bytecode);
     let mut calldata = vec![0x00; 32];
     calldata[28..32].copy_from_slice(&number_of_iterations.to_be_bytes());
-    call_frame.calldata = Bytes::from(calldata);
+    let calldata = Bytes::from(calldata);
 
+    // The program is deployed rather than only set in the call frame so that it can call itself
     for _ in 0..runs - 1 {
-        let mut vm = new_vm_with_bytecode(Bytes::new()).unwrap();
-        *vm.current_call_frame_mut().unwrap() = call_frame.clone();
+        let mut vm = new_vm_with_bytecode(bytecode.clone()).unwrap();
         let mut current_call_frame = vm.call_frames.pop().unwrap();
+        current_call_frame.calldata = calldata.clone();
         let tx_report = black_box(vm.execute(&mut current_call_frame).unwrap());
         assert!(tx_report.result == TxResult::Success);
     }
-    let mut vm = new_vm_with_bytecode(Bytes::new()).unwrap();
-    *vm.current_call_frame_mut().unwrap() = call_frame.clone();
+    let mut vm = new_vm_with_bytecode(bytecode).unwrap();
     let mut current_call_frame = vm.call_frames.pop().unwrap();
+    current_call_frame.calldata = calldata;
     let tx_report = black_box(vm.execute(&mut current_call_frame).unwrap());
     assert!(tx_report.result == TxResult::Success);
 
@@ -5,24 +5,52 @@ use keccak_hash::keccak;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AccountInfo {
     pub balance: U256,
     pub bytecode: Bytes,
+    /// Hash of `bytecode`, kept along with it so that it isn't hashed again every time it's run
+    pub code_hash: H256,
     pub nonce: u64,
 }
 
+impl Default for AccountInfo {
+    fn default() -> Self {
+        Self {
+            balance: U256::zero(),
+            bytecode: Bytes::new(),
+            code_hash: EMPTY_CODE_HASH,
+            nonce: 0,
+        }
+    }
+}
+
 impl AccountInfo {
+    pub fn new(balance: U256, bytecode: Bytes, nonce: u64) -> Self {
+        Self {
+            balance,
+            code_hash: keccak(bytecode.as_ref()).0.into(),
+            bytecode,
+            nonce,
+        }
+    }
+
+    /// Replaces the code of the account, returning the previous one
+    pub fn set_bytecode(&mut self, bytecode: Bytes) -> Bytes {
+        self.code_hash = keccak(bytecode.as_ref()).0.into();
+        std::mem::replace(&mut self.bytecode, bytecode)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.balance.is_zero() && self.nonce == 0 && self.bytecode.is_empty()
     }
 
     pub fn has_code(&self) -> bool {
-        !(self.bytecode.is_empty() || self.bytecode_hash() == EMPTY_CODE_HASH)
+        !(self.bytecode.is_empty() || self.code_hash == EMPTY_CODE_HASH)
     }
 
     pub fn bytecode_hash(&self) -> H256 {
-        keccak(self.bytecode.as_ref()).0.into()
+        self.code_hash
     }
 
     pub fn has_nonce(&self) -> bool {
@@ -59,11 +87,7 @@ impl Account {
         storage: HashMap<H256, StorageSlot>,
     ) -> Self {
         Self {
-            info: AccountInfo {
-                balance,
-                bytecode,
-                nonce,
-            },
+            info: AccountInfo::new(balance, bytecode, nonce),
             storage,
         }
     }
@@ -94,7 +118,7 @@ impl Account {
     }
 
     pub fn with_bytecode(mut self, bytecode: Bytes) -> Self {
-        self.info.bytecode = bytecode;
+        self.info.set_bytecode(bytecode);
         self
     }
 
@@ -1,13 +1,14 @@
 use crate::{
     constants::STACK_LIMIT,
+    eof::EofContainer,
     errors::{InternalError, VMError},
+    jump_destinations::JumpDestinations,
     memory::Memory,
     opcodes::Opcode,
-    vm::get_valid_jump_destinations,
 };
 use bytes::Bytes;
-use ethrex_core::{types::Log, Address, U256};
-use std::collections::HashSet;
+use ethrex_core::{types::Log, Address, H256, U256};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Stack {
@@ -48,6 +49,13 @@ impl Stack {
     }
 }
 
+/// Where to resume execution after RETF, [EIP-4750]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReturnStackItem {
+    pub code_section: usize,
+    pub pc: usize,
+}
+
 #[derive(Debug, Clone, Default, PartialEq)]
 /// A call frame, or execution environment, is the context in which
 /// the EVM is currently executing.
@@ -66,6 +74,9 @@ pub struct CallFrame {
     pub code_address: Address,
     /// Bytecode to execute
     pub bytecode: Bytes,
+    /// Hash of `bytecode` when it's the code of an account, which the analysis of its jump
+    /// destinations is cached by. None for init code
+    pub code_hash: Option<H256>,
     /// Value sent along the transaction
     pub msg_value: U256,
     pub stack: Stack,
@@ -81,27 +92,33 @@ pub struct CallFrame {
     pub logs: Vec<Log>,
     /// Call stack current depth
     pub depth: usize,
-    /// Set of valid jump destinations (where a JUMP or JUMPI can jump to)
-    pub valid_jump_destinations: HashSet<usize>,
-    /// This is set to true if the function that created this callframe is CREATE or CREATE2
+    /// Valid jump destinations (where a JUMP or JUMPI can jump to), analysed on the first jump
+    pub valid_jump_destinations: Option<Arc<JumpDestinations>>,
+    /// This is set to true if the function that created this callframe is CREATE, CREATE2 or
+    /// EOFCREATE
     pub create_op_called: bool,
+    /// Container of the code being executed, None for legacy code. When set, `bytecode` holds the
+    /// code section being executed and `pc` is relative to it
+    pub eof_container: Option<EofContainer>,
+    /// Index of the EOF code section being executed
+    pub code_section: usize,
+    /// Code sections to return to after RETF
+    pub return_stack: Vec<ReturnStackItem>,
 }
 
 impl CallFrame {
     pub fn new_from_bytecode(bytecode: Bytes) -> Self {
-        let valid_jump_destinations = get_valid_jump_destinations(&bytecode).unwrap_or_default();
         Self {
             gas_limit: u64::MAX,
             bytecode,
-            valid_jump_destinations,
             ..Default::default()
         }
     }
 
     pub fn assign_bytecode(&mut self, bytecode: Bytes) {
         self.bytecode = bytecode;
-        self.valid_jump_destinations =
-            get_valid_jump_destinations(&self.bytecode).unwrap_or_default();
+        self.code_hash = None;
+        self.valid_jump_destinations = None;
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -118,7 +135,6 @@ impl CallFrame {
         depth: usize,
         create_op_called: bool,
     ) -> Self {
-        let valid_jump_destinations = get_valid_jump_destinations(&bytecode).unwrap_or_default();
         Self {
             gas_limit,
             msg_sender,
@@ -130,17 +146,42 @@ impl CallFrame {
             is_static,
             depth,
             gas_used,
-            valid_jump_destinations,
             create_op_called,
             ..Default::default()
         }
     }
 
+    /// Starts executing the first code section of an EOF container
+    pub fn assign_eof_container(&mut self, container: EofContainer) -> Result<(), VMError> {
+        self.eof_container = Some(container);
+        self.code_hash = None;
+        self.valid_jump_destinations = None;
+        self.jump_to_code_section(0, 0)
+    }
+
+    /// Moves execution to `pc` in another code section of the EOF container
+    pub fn jump_to_code_section(&mut self, code_section: usize, pc: usize) -> Result<(), VMError> {
+        self.bytecode = self
+            .eof_container
+            .as_ref()
+            .and_then(|container| container.code_sections.get(code_section))
+            .ok_or(VMError::InvalidBytecode)?
+            .clone();
+        self.code_section = code_section;
+        self.pc = pc;
+        Ok(())
+    }
+
     pub fn next_opcode(&mut self) -> Opcode {
-        match self.bytecode.get(self.pc).copied().map(Opcode::from) {
-            Some(opcode) => opcode,
-            None => Opcode::STOP,
-        }
+        Opcode::from(self.next_opcode_byte())
+    }
+
+    /// Byte of the opcode at the PC, running past the end of the code is the same as a STOP
+    pub fn next_opcode_byte(&self) -> u8 {
+        self.bytecode
+            .get(self.pc)
+            .copied()
+            .unwrap_or(u8::from(Opcode::STOP))
     }
 
     pub fn increment_pc_by(&mut self, count: usize) -> Result<(), VMError> {
@@ -7,9 +7,14 @@ pub const WORD_SIZE_IN_BYTES_U64: u64 = 32;
 pub const SUCCESS_FOR_CALL: U256 = U256::one();
 pub const REVERT_FOR_CALL: U256 = U256::zero();
 pub const CREATE_DEPLOYMENT_FAIL: U256 = U256::zero();
+// Results of EXTCALL, EXTDELEGATECALL and EXTSTATICCALL [EIP-7069]
+pub const EXTCALL_SUCCESS: U256 = U256::zero();
+pub const EXTCALL_REVERT: U256 = U256::one();
+pub const EXTCALL_FAILURE: U256 = U256([2, 0, 0, 0]);
 pub const WORD_SIZE: usize = 32;
 
 pub const STACK_LIMIT: usize = 1024;
+pub const RETURN_STACK_LIMIT: usize = 1024;
 
 pub const GAS_REFUND_DENOMINATOR: u64 = 5;
 
@@ -46,6 +51,13 @@ pub const MIN_BASE_FEE_PER_BLOB_GAS: u64 = 1;
 pub const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3338477;
 pub const MAX_BLOB_COUNT: usize = 6;
 pub const VALID_BLOB_PREFIXES: [u8; 2] = [0x01, 0x02];
+pub const FIELD_ELEMENTS_PER_BLOB: U256 = U256([4096, 0, 0, 0]);
+pub const BLS_MODULUS: U256 = U256([
+    0xffffffff00000001,
+    0x53bda402fffe5bfe,
+    0x3339d80809a1d805,
+    0x73eda753299d7d48,
+]);
 
 // Block constants
 pub const LAST_AVAILABLE_BLOCK_LIMIT: U256 = U256([256, 0, 0, 0]);
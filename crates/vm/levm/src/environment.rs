@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use ethrex_core::{Address, H256, U256};
 
+use crate::fork::Fork;
+
 /// [EIP-1153]: https://eips.ethereum.org/EIPS/eip-1153#reference-implementation
 pub type TransientStorage = HashMap<(Address, U256), U256>;
 
@@ -10,12 +12,16 @@ pub struct Environment {
     /// The sender address of the transaction that originated
     /// this execution.
     pub origin: Address,
+    /// Fork whose rules apply to the transaction
+    pub fork: Fork,
     pub refunded_gas: u64,
     pub gas_limit: u64,
     pub block_number: U256,
     pub coinbase: Address,
     pub timestamp: U256,
     pub prev_randao: Option<H256>,
+    /// Difficulty of the block, returned by DIFFICULTY before the merge
+    pub difficulty: U256,
     pub chain_id: U256,
     pub base_fee_per_gas: U256,
     pub gas_price: U256, // Effective gas price
@@ -33,12 +39,14 @@ impl Environment {
     pub fn default_from_address(origin: Address) -> Self {
         Self {
             origin,
+            fork: Default::default(),
             refunded_gas: 0,
             gas_limit: u64::MAX,
             block_number: Default::default(),
             coinbase: Default::default(),
             timestamp: Default::default(),
             prev_randao: Default::default(),
+            difficulty: Default::default(),
             chain_id: U256::one(),
             base_fee_per_gas: Default::default(),
             gas_price: Default::default(),
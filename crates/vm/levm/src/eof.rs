@@ -0,0 +1,757 @@
+use crate::{constants::STACK_LIMIT, errors::EofError, opcodes::Opcode};
+use bytes::Bytes;
+
+/// Prefix of the code in EVM Object Format, [EIP-3540]
+pub const EOF_MAGIC: [u8; 2] = [0xEF, 0x00];
+pub const EOF_VERSION: u8 = 0x01;
+
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0x04;
+const TERMINATOR: u8 = 0x00;
+
+const TYPE_SECTION_SIZE: usize = 4;
+const MAX_CODE_SECTIONS: usize = 1024;
+const MAX_CONTAINER_SECTIONS: usize = 256;
+const MAX_STACK_HEIGHT: u16 = 1023;
+const MAX_INPUTS_OUTPUTS: u8 = 0x7F;
+
+/// Outputs of a code section that never returns to its caller
+pub const NON_RETURNING: u8 = 0x80;
+
+/// Whether the code is in EVM Object Format. It may still be an invalid container
+pub fn is_eof(code: &[u8]) -> bool {
+    code.starts_with(&EOF_MAGIC)
+}
+
+/// Inputs, outputs and max stack height of a code section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeSection {
+    pub inputs: u8,
+    pub outputs: u8,
+    pub max_stack_height: u16,
+}
+
+impl TypeSection {
+    pub fn is_returning(&self) -> bool {
+        self.outputs != NON_RETURNING
+    }
+}
+
+/// How a container is going to be executed, which restricts the instructions it can use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    /// Executed by EOFCREATE or a creation transaction, it must end with RETURNCONTRACT
+    Initcode,
+    /// Deployed code, it can't use RETURNCONTRACT
+    Runtime,
+}
+
+/// An EOF version 1 container, as defined in [EIP-3540]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EofContainer {
+    pub types: Vec<TypeSection>,
+    pub code_sections: Vec<Bytes>,
+    pub container_sections: Vec<Bytes>,
+    pub data: Bytes,
+    /// Size of the data section declared in the header. It can be bigger than the data in
+    /// subcontainers that are going to be deployed with auxiliary data
+    pub data_size: u16,
+}
+
+/// Cursor over the bytes of a container
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8], EofError> {
+        let end = self.position.checked_add(size).ok_or(EofError::Truncated)?;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(EofError::Truncated)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, EofError> {
+        self.take(1)?.first().copied().ok_or(EofError::Truncated)
+    }
+
+    fn u16(&mut self) -> Result<u16, EofError> {
+        let bytes: [u8; 2] = self.take(2)?.try_into().map_err(|_| EofError::Truncated)?;
+        Ok(u16::from_be_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32, EofError> {
+        let bytes: [u8; 4] = self.take(4)?.try_into().map_err(|_| EofError::Truncated)?;
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), EofError> {
+        if self.u8()? != byte {
+            return Err(EofError::InvalidHeader);
+        }
+        Ok(())
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.position)
+    }
+}
+
+impl EofContainer {
+    /// Decodes a whole container, which can't have truncated data
+    pub fn decode(code: &[u8]) -> Result<Self, EofError> {
+        let (container, size) = Self::decode_inner(code, false)?;
+        if size != code.len() {
+            return Err(EofError::TrailingBytes);
+        }
+        Ok(container)
+    }
+
+    /// Decodes the container at the start of `code`, returning it along with its size
+    /// Used by creation transactions, whose data is an initcontainer followed by its input
+    /// [EIP-7698]
+    pub fn decode_prefix(code: &[u8]) -> Result<(Self, usize), EofError> {
+        Self::decode_inner(code, false)
+    }
+
+    /// Decodes a subcontainer deployed by RETURNCONTRACT, whose data section can be truncated as
+    /// it's completed with auxiliary data on deployment [EIP-7620]
+    pub fn decode_deploy_container(code: &[u8]) -> Result<Self, EofError> {
+        let (container, size) = Self::decode_inner(code, true)?;
+        if size != code.len() {
+            return Err(EofError::TrailingBytes);
+        }
+        Ok(container)
+    }
+
+    fn decode_inner(code: &[u8], allow_truncated_data: bool) -> Result<(Self, usize), EofError> {
+        let mut reader = Reader {
+            bytes: code,
+            position: 0,
+        };
+
+        if reader.take(2)? != EOF_MAGIC {
+            return Err(EofError::InvalidMagic);
+        }
+        if reader.u8()? != EOF_VERSION {
+            return Err(EofError::UnsupportedVersion);
+        }
+
+        reader.expect(KIND_TYPES)?;
+        let types_size = usize::from(reader.u16()?);
+
+        reader.expect(KIND_CODE)?;
+        let code_sections_count = usize::from(reader.u16()?);
+        if code_sections_count == 0 || code_sections_count > MAX_CODE_SECTIONS {
+            return Err(EofError::InvalidHeader);
+        }
+        if Some(types_size) != code_sections_count.checked_mul(TYPE_SECTION_SIZE) {
+            return Err(EofError::InvalidTypeSection);
+        }
+        let mut code_sizes = Vec::with_capacity(code_sections_count);
+        for _ in 0..code_sections_count {
+            let size = usize::from(reader.u16()?);
+            if size == 0 {
+                return Err(EofError::InvalidHeader);
+            }
+            code_sizes.push(size);
+        }
+
+        let mut container_sizes = Vec::new();
+        let mut kind = reader.u8()?;
+        if kind == KIND_CONTAINER {
+            let container_sections_count = usize::from(reader.u16()?);
+            if container_sections_count == 0 || container_sections_count > MAX_CONTAINER_SECTIONS {
+                return Err(EofError::InvalidHeader);
+            }
+            for _ in 0..container_sections_count {
+                let size = usize::try_from(reader.u32()?).map_err(|_| EofError::InvalidHeader)?;
+                if size == 0 {
+                    return Err(EofError::InvalidHeader);
+                }
+                container_sizes.push(size);
+            }
+            kind = reader.u8()?;
+        }
+
+        if kind != KIND_DATA {
+            return Err(EofError::InvalidHeader);
+        }
+        let data_size = reader.u16()?;
+        reader.expect(TERMINATOR)?;
+
+        let mut types = Vec::with_capacity(code_sections_count);
+        for _ in 0..code_sections_count {
+            let type_section = TypeSection {
+                inputs: reader.u8()?,
+                outputs: reader.u8()?,
+                max_stack_height: reader.u16()?,
+            };
+            if type_section.inputs > MAX_INPUTS_OUTPUTS
+                || (type_section.outputs > MAX_INPUTS_OUTPUTS && type_section.is_returning())
+                || type_section.max_stack_height > MAX_STACK_HEIGHT
+            {
+                return Err(EofError::InvalidTypeSection);
+            }
+            types.push(type_section);
+        }
+
+        let code_sections = code_sizes
+            .into_iter()
+            .map(|size| reader.take(size).map(Bytes::copy_from_slice))
+            .collect::<Result<Vec<_>, _>>()?;
+        let container_sections = container_sizes
+            .into_iter()
+            .map(|size| reader.take(size).map(Bytes::copy_from_slice))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let data = if allow_truncated_data {
+            let available = reader.remaining().min(usize::from(data_size));
+            reader.take(available)?
+        } else {
+            reader.take(usize::from(data_size))?
+        };
+
+        let container = Self {
+            types,
+            code_sections,
+            container_sections,
+            data: Bytes::copy_from_slice(data),
+            data_size,
+        };
+        Ok((container, reader.position))
+    }
+
+    /// Encodes the container, with the data size declared in `data_size`
+    pub fn encode(&self) -> Result<Bytes, EofError> {
+        let to_u16 = |size: usize| u16::try_from(size).map_err(|_| EofError::InvalidHeader);
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(&EOF_MAGIC);
+        encoded.push(EOF_VERSION);
+
+        encoded.push(KIND_TYPES);
+        let types_size = self
+            .types
+            .len()
+            .checked_mul(TYPE_SECTION_SIZE)
+            .ok_or(EofError::InvalidHeader)?;
+        encoded.extend_from_slice(&to_u16(types_size)?.to_be_bytes());
+
+        encoded.push(KIND_CODE);
+        encoded.extend_from_slice(&to_u16(self.code_sections.len())?.to_be_bytes());
+        for code in &self.code_sections {
+            encoded.extend_from_slice(&to_u16(code.len())?.to_be_bytes());
+        }
+
+        if !self.container_sections.is_empty() {
+            encoded.push(KIND_CONTAINER);
+            encoded.extend_from_slice(&to_u16(self.container_sections.len())?.to_be_bytes());
+            for container in &self.container_sections {
+                let size = u32::try_from(container.len()).map_err(|_| EofError::InvalidHeader)?;
+                encoded.extend_from_slice(&size.to_be_bytes());
+            }
+        }
+
+        encoded.push(KIND_DATA);
+        encoded.extend_from_slice(&self.data_size.to_be_bytes());
+        encoded.push(TERMINATOR);
+
+        for type_section in &self.types {
+            encoded.push(type_section.inputs);
+            encoded.push(type_section.outputs);
+            encoded.extend_from_slice(&type_section.max_stack_height.to_be_bytes());
+        }
+        for code in &self.code_sections {
+            encoded.extend_from_slice(code);
+        }
+        for container in &self.container_sections {
+            encoded.extend_from_slice(container);
+        }
+        encoded.extend_from_slice(&self.data);
+
+        Ok(encoded.into())
+    }
+
+    /// Returns the container with `aux_data` appended to its data section, as deployed by
+    /// RETURNCONTRACT [EIP-7620]
+    pub fn with_aux_data(&self, aux_data: &[u8]) -> Result<Bytes, EofError> {
+        let data = [self.data.as_ref(), aux_data].concat();
+        let data_size = u16::try_from(data.len()).map_err(|_| EofError::DataSectionTooBig)?;
+        // The data declared in the header must have been provided
+        if data_size < self.data_size {
+            return Err(EofError::Truncated);
+        }
+
+        Self {
+            data: data.into(),
+            data_size,
+            ..self.clone()
+        }
+        .encode()
+    }
+
+    /// Validates the code and subcontainers of the container, [EIP-3670], [EIP-4200],
+    /// [EIP-4750], [EIP-5450] and [EIP-7620]
+    pub fn validate(&self, kind: ContainerKind) -> Result<(), EofError> {
+        let first_type = self.types.first().ok_or(EofError::InvalidTypeSection)?;
+        if first_type.inputs != 0 || first_type.is_returning() {
+            return Err(EofError::InvalidTypeSection);
+        }
+
+        let mut container_kinds: Vec<Option<ContainerKind>> =
+            vec![None; self.container_sections.len()];
+        let mut reached_sections = vec![false; self.code_sections.len()];
+        let mut pending_sections = vec![0];
+        if let Some(reached) = reached_sections.first_mut() {
+            *reached = true;
+        }
+
+        while let Some(section) = pending_sections.pop() {
+            for target in self.validate_code_section(section, kind, &mut container_kinds)? {
+                let reached = reached_sections
+                    .get_mut(target)
+                    .ok_or(EofError::InvalidCodeSectionIndex)?;
+                if !*reached {
+                    *reached = true;
+                    pending_sections.push(target);
+                }
+            }
+        }
+        if reached_sections.contains(&false) {
+            return Err(EofError::UnreachableCodeSection);
+        }
+
+        for (container, container_kind) in self.container_sections.iter().zip(container_kinds) {
+            let container_kind = container_kind.ok_or(EofError::UnreferencedContainer)?;
+            // Only the containers deployed by RETURNCONTRACT get their data completed
+            let subcontainer = match container_kind {
+                ContainerKind::Initcode => Self::decode(container)?,
+                ContainerKind::Runtime => Self::decode_deploy_container(container)?,
+            };
+            subcontainer.validate(container_kind)?;
+        }
+
+        Ok(())
+    }
+
+    /// Validates the instructions and stack usage of a code section, returning the code sections
+    /// it calls or jumps to
+    fn validate_code_section(
+        &self,
+        section: usize,
+        kind: ContainerKind,
+        container_kinds: &mut [Option<ContainerKind>],
+    ) -> Result<Vec<usize>, EofError> {
+        let code = self
+            .code_sections
+            .get(section)
+            .ok_or(EofError::InvalidCodeSectionIndex)?;
+        let section_type = self
+            .types
+            .get(section)
+            .ok_or(EofError::InvalidCodeSectionIndex)?;
+
+        // Min and max stack heights before each instruction, None if it wasn't reached yet
+        let mut stack_heights: Vec<Option<(u16, u16)>> = vec![None; code.len()];
+        let mut immediates = vec![false; code.len()];
+        let mut jump_targets = Vec::new();
+        let mut called_sections = Vec::new();
+        let mut returns = false;
+        let inputs = u16::from(section_type.inputs);
+        if let Some(first) = stack_heights.first_mut() {
+            *first = Some((inputs, inputs));
+        }
+        let mut max_stack_height = inputs;
+
+        let mut position = 0;
+        while let Some(&byte) = code.get(position) {
+            let (min_height, max_height) = stack_heights
+                .get(position)
+                .copied()
+                .flatten()
+                .ok_or(EofError::UnreachableCode)?;
+            let opcode = Opcode::from(byte);
+            if !is_valid_in_eof(opcode, byte) {
+                return Err(EofError::UndefinedInstruction);
+            }
+            match (kind, opcode) {
+                (ContainerKind::Runtime, Opcode::RETURNCONTRACT)
+                | (ContainerKind::Initcode, Opcode::RETURN | Opcode::STOP) => {
+                    return Err(EofError::InvalidInstructionForKind)
+                }
+                _ => {}
+            }
+
+            let immediate_start = position.checked_add(1).ok_or(EofError::Truncated)?;
+            let immediate_size = immediate_size(opcode, code, immediate_start)?;
+            let next = immediate_start
+                .checked_add(immediate_size)
+                .ok_or(EofError::Truncated)?;
+            let immediate = code
+                .get(immediate_start..next)
+                .ok_or(EofError::TruncatedImmediate)?;
+            for is_immediate in immediates
+                .get_mut(immediate_start..next)
+                .ok_or(EofError::TruncatedImmediate)?
+            {
+                *is_immediate = true;
+            }
+
+            let (required, outputs) = match opcode {
+                Opcode::CALLF | Opcode::JUMPF => {
+                    let target = usize::from(read_u16(immediate)?);
+                    let target_type = self
+                        .types
+                        .get(target)
+                        .ok_or(EofError::InvalidCodeSectionIndex)?;
+                    called_sections.push(target);
+
+                    let target_inputs = u16::from(target_type.inputs);
+                    // The callee must be able to reach its max stack height
+                    let max_height_in_callee = max_height
+                        .checked_add(target_type.max_stack_height)
+                        .and_then(|height| height.checked_sub(target_inputs))
+                        .ok_or(EofError::StackUnderflow)?;
+                    if usize::from(max_height_in_callee) > STACK_LIMIT {
+                        return Err(EofError::StackOverflow);
+                    }
+
+                    if opcode == Opcode::CALLF {
+                        if !target_type.is_returning() {
+                            return Err(EofError::InvalidReturningFlag);
+                        }
+                        (target_inputs, u16::from(target_type.outputs))
+                    } else if target_type.is_returning() {
+                        // Returns directly to this section's caller, with its outputs
+                        if !section_type.is_returning()
+                            || target_type.outputs > section_type.outputs
+                        {
+                            return Err(EofError::InvalidReturningFlag);
+                        }
+                        returns = true;
+                        let expected_height = u16::from(section_type.outputs)
+                            .checked_add(target_inputs)
+                            .and_then(|height| height.checked_sub(u16::from(target_type.outputs)))
+                            .ok_or(EofError::StackUnderflow)?;
+                        if min_height != expected_height || max_height != expected_height {
+                            return Err(EofError::StackHeightMismatch);
+                        }
+                        (target_inputs, 0)
+                    } else {
+                        (target_inputs, 0)
+                    }
+                }
+                Opcode::RETF => {
+                    if !section_type.is_returning() {
+                        return Err(EofError::InvalidReturningFlag);
+                    }
+                    returns = true;
+                    let outputs = u16::from(section_type.outputs);
+                    if min_height != outputs || max_height != outputs {
+                        return Err(EofError::StackHeightMismatch);
+                    }
+                    (outputs, 0)
+                }
+                Opcode::DATALOADN => {
+                    let offset = usize::from(read_u16(immediate)?);
+                    if offset.checked_add(32) > Some(usize::from(self.data_size)) {
+                        return Err(EofError::InvalidDataOffset);
+                    }
+                    (0, 1)
+                }
+                Opcode::EOFCREATE | Opcode::RETURNCONTRACT => {
+                    let index = usize::from(*immediate.first().ok_or(EofError::Truncated)?);
+                    let container_kind = if opcode == Opcode::EOFCREATE {
+                        ContainerKind::Initcode
+                    } else {
+                        ContainerKind::Runtime
+                    };
+                    let used_as = container_kinds
+                        .get_mut(index)
+                        .ok_or(EofError::InvalidContainerSectionIndex)?;
+                    match used_as {
+                        Some(previous_kind) if *previous_kind != container_kind => {
+                            return Err(EofError::AmbiguousContainerKind)
+                        }
+                        _ => *used_as = Some(container_kind),
+                    }
+                    stack_io(opcode).ok_or(EofError::UndefinedInstruction)?
+                }
+                Opcode::DUPN | Opcode::SWAPN | Opcode::EXCHANGE => {
+                    let immediate = u16::from(*immediate.first().ok_or(EofError::Truncated)?);
+                    match opcode {
+                        Opcode::DUPN => (immediate.saturating_add(1), immediate.saturating_add(2)),
+                        Opcode::SWAPN => (immediate.saturating_add(2), immediate.saturating_add(2)),
+                        _ => {
+                            let (n, m) = exchange_positions(immediate);
+                            let required = n.saturating_add(m).saturating_add(1);
+                            (required, required)
+                        }
+                    }
+                }
+                _ => stack_io(opcode).ok_or(EofError::UndefinedInstruction)?,
+            };
+
+            if min_height < required {
+                return Err(EofError::StackUnderflow);
+            }
+            let new_height = |height: u16| {
+                height
+                    .checked_sub(required)
+                    .and_then(|height| height.checked_add(outputs))
+                    .ok_or(EofError::StackUnderflow)
+            };
+            let new_heights = (new_height(min_height)?, new_height(max_height)?);
+            max_stack_height = max_stack_height.max(new_heights.1);
+            if new_heights.1 > MAX_STACK_HEIGHT {
+                return Err(EofError::StackOverflow);
+            }
+
+            let mut successors = Vec::new();
+            if !is_terminating(opcode) && opcode != Opcode::RJUMP {
+                successors.push(next);
+            }
+            for offset in relative_jump_offsets(opcode, immediate)? {
+                let target = isize::try_from(next)
+                    .ok()
+                    .and_then(|next| next.checked_add(isize::from(offset)))
+                    .and_then(|target| usize::try_from(target).ok())
+                    .ok_or(EofError::InvalidJumpDestination)?;
+                jump_targets.push(target);
+                successors.push(target);
+            }
+
+            for successor in successors {
+                let successor_heights = stack_heights
+                    .get_mut(successor)
+                    .ok_or(EofError::NoTerminatingInstruction)?;
+                if successor <= position {
+                    // Backwards jumps must keep the stack height
+                    if *successor_heights != Some(new_heights) {
+                        return Err(EofError::StackHeightMismatch);
+                    }
+                } else {
+                    *successor_heights = Some(match successor_heights {
+                        Some((min, max)) => ((*min).min(new_heights.0), (*max).max(new_heights.1)),
+                        None => new_heights,
+                    });
+                }
+            }
+
+            position = next;
+        }
+
+        if jump_targets
+            .iter()
+            .any(|target| immediates.get(*target).copied().unwrap_or(true))
+        {
+            return Err(EofError::InvalidJumpDestination);
+        }
+        if max_stack_height != section_type.max_stack_height {
+            return Err(EofError::InvalidMaxStackHeight);
+        }
+        if returns != section_type.is_returning() {
+            return Err(EofError::InvalidReturningFlag);
+        }
+
+        Ok(called_sections)
+    }
+}
+
+/// Positions of the stack items swapped by EXCHANGE, counting from the second item
+pub fn exchange_positions(immediate: u16) -> (u16, u16) {
+    let n = (immediate >> 4).saturating_add(1);
+    let m = (immediate & 0x0F).saturating_add(1);
+    (n, m)
+}
+
+fn read_u16(immediate: &[u8]) -> Result<u16, EofError> {
+    let bytes: [u8; 2] = immediate
+        .get(..2)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(EofError::TruncatedImmediate)?;
+    Ok(u16::from_be_bytes(bytes))
+}
+
+/// Offsets of the relative jumps of RJUMP, RJUMPI and RJUMPV [EIP-4200]
+fn relative_jump_offsets(opcode: Opcode, immediate: &[u8]) -> Result<Vec<i16>, EofError> {
+    let offsets = match opcode {
+        Opcode::RJUMP | Opcode::RJUMPI => immediate,
+        Opcode::RJUMPV => immediate.get(1..).ok_or(EofError::TruncatedImmediate)?,
+        _ => return Ok(Vec::new()),
+    };
+    offsets
+        .chunks_exact(2)
+        .map(|offset| {
+            offset
+                .try_into()
+                .map(i16::from_be_bytes)
+                .map_err(|_| EofError::TruncatedImmediate)
+        })
+        .collect()
+}
+
+/// Size of the immediate arguments of the instruction starting before `immediate_start`
+pub fn immediate_size(
+    opcode: Opcode,
+    code: &[u8],
+    immediate_start: usize,
+) -> Result<usize, EofError> {
+    let size = match opcode {
+        Opcode::RJUMP | Opcode::RJUMPI | Opcode::CALLF | Opcode::JUMPF | Opcode::DATALOADN => 2,
+        Opcode::DUPN
+        | Opcode::SWAPN
+        | Opcode::EXCHANGE
+        | Opcode::EOFCREATE
+        | Opcode::RETURNCONTRACT => 1,
+        Opcode::RJUMPV => {
+            let max_index = code
+                .get(immediate_start)
+                .ok_or(EofError::TruncatedImmediate)?;
+            usize::from(*max_index)
+                .checked_add(1)
+                .and_then(|cases| cases.checked_mul(2))
+                .and_then(|size| size.checked_add(1))
+                .ok_or(EofError::TruncatedImmediate)?
+        }
+        op if (Opcode::PUSH1..=Opcode::PUSH32).contains(&op) => usize::from(u8::from(op))
+            .checked_sub(usize::from(u8::from(Opcode::PUSH0)))
+            .ok_or(EofError::TruncatedImmediate)?,
+        _ => 0,
+    };
+    Ok(size)
+}
+
+/// Whether the instruction ends the execution of its code section
+pub fn is_terminating(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::STOP
+            | Opcode::RETURN
+            | Opcode::REVERT
+            | Opcode::INVALID
+            | Opcode::RETF
+            | Opcode::JUMPF
+            | Opcode::RETURNCONTRACT
+    )
+}
+
+/// Whether the instruction can be used in EOF code. Instructions that inspect code or gas, and
+/// legacy jumps, calls and creations are rejected
+fn is_valid_in_eof(opcode: Opcode, byte: u8) -> bool {
+    if opcode == Opcode::INVALID {
+        return byte == u8::from(Opcode::INVALID);
+    }
+    !matches!(
+        opcode,
+        Opcode::CALLCODE
+            | Opcode::SELFDESTRUCT
+            | Opcode::JUMP
+            | Opcode::JUMPI
+            | Opcode::PC
+            | Opcode::CREATE
+            | Opcode::CREATE2
+            | Opcode::CODESIZE
+            | Opcode::CODECOPY
+            | Opcode::EXTCODESIZE
+            | Opcode::EXTCODECOPY
+            | Opcode::EXTCODEHASH
+            | Opcode::GAS
+            | Opcode::CALL
+            | Opcode::STATICCALL
+            | Opcode::DELEGATECALL
+            | Opcode::JUMPDEST
+    )
+}
+
+/// Number of stack items taken and pushed by the instruction, for the ones where it's fixed
+fn stack_io(opcode: Opcode) -> Option<(u16, u16)> {
+    let io = match opcode {
+        Opcode::STOP | Opcode::INVALID | Opcode::RJUMP => (0, 0),
+        Opcode::ADD
+        | Opcode::MUL
+        | Opcode::SUB
+        | Opcode::DIV
+        | Opcode::SDIV
+        | Opcode::MOD
+        | Opcode::SMOD
+        | Opcode::EXP
+        | Opcode::SIGNEXTEND
+        | Opcode::LT
+        | Opcode::GT
+        | Opcode::SLT
+        | Opcode::SGT
+        | Opcode::EQ
+        | Opcode::AND
+        | Opcode::OR
+        | Opcode::XOR
+        | Opcode::BYTE
+        | Opcode::SHL
+        | Opcode::SHR
+        | Opcode::SAR
+        | Opcode::KECCAK256 => (2, 1),
+        Opcode::ADDMOD | Opcode::MULMOD => (3, 1),
+        Opcode::ISZERO
+        | Opcode::NOT
+        | Opcode::BALANCE
+        | Opcode::CALLDATALOAD
+        | Opcode::BLOCKHASH
+        | Opcode::BLOBHASH
+        | Opcode::MLOAD
+        | Opcode::SLOAD
+        | Opcode::TLOAD
+        | Opcode::DATALOAD
+        | Opcode::RETURNDATALOAD => (1, 1),
+        Opcode::ADDRESS
+        | Opcode::ORIGIN
+        | Opcode::CALLER
+        | Opcode::CALLVALUE
+        | Opcode::CALLDATASIZE
+        | Opcode::GASPRICE
+        | Opcode::RETURNDATASIZE
+        | Opcode::COINBASE
+        | Opcode::TIMESTAMP
+        | Opcode::NUMBER
+        | Opcode::PREVRANDAO
+        | Opcode::GASLIMIT
+        | Opcode::CHAINID
+        | Opcode::SELFBALANCE
+        | Opcode::BASEFEE
+        | Opcode::BLOBBASEFEE
+        | Opcode::MSIZE
+        | Opcode::DATASIZE
+        | Opcode::DATALOADN => (0, 1),
+        Opcode::CALLDATACOPY | Opcode::RETURNDATACOPY | Opcode::MCOPY | Opcode::DATACOPY => (3, 0),
+        Opcode::POP | Opcode::RJUMPI | Opcode::RJUMPV => (1, 0),
+        Opcode::MSTORE
+        | Opcode::MSTORE8
+        | Opcode::SSTORE
+        | Opcode::TSTORE
+        | Opcode::RETURN
+        | Opcode::REVERT
+        | Opcode::RETURNCONTRACT => (2, 0),
+        Opcode::EOFCREATE | Opcode::EXTCALL => (4, 1),
+        Opcode::EXTDELEGATECALL | Opcode::EXTSTATICCALL => (3, 1),
+        op if (Opcode::PUSH0..=Opcode::PUSH32).contains(&op) => (0, 1),
+        op if (Opcode::DUP1..=Opcode::DUP16).contains(&op) => {
+            let depth = u16::from(u8::from(op)).checked_sub(u16::from(u8::from(Opcode::DUP1)))?;
+            (depth.checked_add(1)?, depth.checked_add(2)?)
+        }
+        op if (Opcode::SWAP1..=Opcode::SWAP16).contains(&op) => {
+            let depth = u16::from(u8::from(op)).checked_sub(u16::from(u8::from(Opcode::SWAP1)))?;
+            (depth.checked_add(2)?, depth.checked_add(2)?)
+        }
+        op if (Opcode::LOG0..=Opcode::LOG4).contains(&op) => {
+            let topics = u16::from(u8::from(op)).checked_sub(u16::from(u8::from(Opcode::LOG0)))?;
+            (topics.checked_add(2)?, 0)
+        }
+        _ => return None,
+    };
+    Some(io)
+}
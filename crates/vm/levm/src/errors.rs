@@ -74,6 +74,12 @@ pub enum VMError {
     OutOfBounds,
     #[error("Precompile execution error: {0}")]
     PrecompileError(#[from] PrecompileError),
+    #[error("Invalid EOF container: {0}")]
+    InvalidEof(#[from] EofError),
+    #[error("Address with non-zero high bytes")]
+    InvalidAddress,
+    #[error("Return stack overflow")]
+    ReturnStackOverflow,
 }
 
 impl VMError {
@@ -178,6 +184,8 @@ pub enum InternalError {
     UndefinedState(i32), // This error is temporarily for things that cause an undefined state.
     #[error("Invalid precompile address. Tried to execute a precompile that does not exist.")]
     InvalidPrecompileAddress,
+    #[error("Failed mapping a field element to the curve")]
+    MapToCurveError,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
@@ -188,10 +196,69 @@ pub enum PrecompileError {
     GasConsumedOverflow,
     #[error("There is not enough gas to execute precompiled contract")]
     NotEnoughGas,
+    #[error("The point is not on the curve")]
+    PointNotOnCurve,
+    #[error("The point is not in the expected subgroup")]
+    PointNotInSubgroup,
+    #[error("The KZG proof could not be verified")]
+    InvalidKzgProof,
     #[error("This is a default error")]
     DefaultError,
 }
 
+/// Reasons why an EOF container is rejected, [EIP-3540]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+pub enum EofError {
+    #[error("Invalid magic")]
+    InvalidMagic,
+    #[error("Unsupported version")]
+    UnsupportedVersion,
+    #[error("Invalid header")]
+    InvalidHeader,
+    #[error("Invalid type section")]
+    InvalidTypeSection,
+    #[error("Container is truncated")]
+    Truncated,
+    #[error("Container has trailing bytes")]
+    TrailingBytes,
+    #[error("Data section too big")]
+    DataSectionTooBig,
+    #[error("Undefined instruction")]
+    UndefinedInstruction,
+    #[error("Truncated instruction immediate")]
+    TruncatedImmediate,
+    #[error("Invalid relative jump destination")]
+    InvalidJumpDestination,
+    #[error("Invalid code section index")]
+    InvalidCodeSectionIndex,
+    #[error("Invalid container section index")]
+    InvalidContainerSectionIndex,
+    #[error("Data offset out of bounds")]
+    InvalidDataOffset,
+    #[error("Code section doesn't end with a terminating instruction")]
+    NoTerminatingInstruction,
+    #[error("Unreachable code")]
+    UnreachableCode,
+    #[error("Unreachable code section")]
+    UnreachableCodeSection,
+    #[error("Unreferenced container section")]
+    UnreferencedContainer,
+    #[error("Container section used both as initcode and runtime code")]
+    AmbiguousContainerKind,
+    #[error("Instruction not allowed in this kind of container")]
+    InvalidInstructionForKind,
+    #[error("Stack underflow")]
+    StackUnderflow,
+    #[error("Stack overflow")]
+    StackOverflow,
+    #[error("Stack height mismatch")]
+    StackHeightMismatch,
+    #[error("Declared max stack height doesn't match the code")]
+    InvalidMaxStackHeight,
+    #[error("Code section outputs don't match how it returns")]
+    InvalidReturningFlag,
+}
+
 #[derive(Debug, Clone)]
 pub enum OpcodeSuccess {
     Continue,
@@ -215,6 +282,7 @@ pub enum TxResult {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TransactionReport {
     pub result: TxResult,
+    /// State after the transaction, only filled in by `VM::transact`
     pub new_state: HashMap<Address, Account>,
     pub gas_used: u64,
     pub gas_refunded: u64,
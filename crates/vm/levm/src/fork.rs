@@ -0,0 +1,104 @@
+use crate::{
+    gas_cost::{
+        GasSchedule, BERLIN_SCHEDULE, CANCUN_SCHEDULE, CONSTANTINOPLE_SCHEDULE, FRONTIER_SCHEDULE,
+        HOMESTEAD_SCHEDULE, ISTANBUL_SCHEDULE, LONDON_SCHEDULE, OSAKA_SCHEDULE,
+        PETERSBURG_SCHEDULE, SHANGHAI_SCHEDULE, SPURIOUS_DRAGON_SCHEDULE,
+        TANGERINE_WHISTLE_SCHEDULE,
+    },
+    opcodes::Opcode,
+};
+
+/// Ethereum hard forks supported by LEVM, in activation order
+/// Forks that only delayed the difficulty bomb are left out, as they don't change execution
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fork {
+    Frontier,
+    Homestead,
+    TangerineWhistle,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Paris,
+    Shanghai,
+    #[default]
+    Cancun,
+    Prague,
+    Osaka,
+}
+
+impl Fork {
+    /// Gas costs and rules in force in this fork
+    pub fn gas_schedule(self) -> &'static GasSchedule {
+        match self {
+            Fork::Frontier => &FRONTIER_SCHEDULE,
+            Fork::Homestead => &HOMESTEAD_SCHEDULE,
+            Fork::TangerineWhistle => &TANGERINE_WHISTLE_SCHEDULE,
+            Fork::SpuriousDragon | Fork::Byzantium => &SPURIOUS_DRAGON_SCHEDULE,
+            Fork::Constantinople => &CONSTANTINOPLE_SCHEDULE,
+            Fork::Petersburg => &PETERSBURG_SCHEDULE,
+            Fork::Istanbul => &ISTANBUL_SCHEDULE,
+            Fork::Berlin => &BERLIN_SCHEDULE,
+            Fork::London | Fork::Paris => &LONDON_SCHEDULE,
+            Fork::Shanghai => &SHANGHAI_SCHEDULE,
+            Fork::Cancun | Fork::Prague => &CANCUN_SCHEDULE,
+            Fork::Osaka => &OSAKA_SCHEDULE,
+        }
+    }
+
+    /// Whether the opcode is defined in this fork, executing an undefined opcode is the same as
+    /// executing INVALID
+    pub fn is_opcode_available(self, opcode: &Opcode) -> bool {
+        self >= activation_fork(opcode)
+    }
+}
+
+/// Fork in which the opcode was introduced
+pub(crate) fn activation_fork(opcode: &Opcode) -> Fork {
+    match opcode {
+        Opcode::DELEGATECALL => Fork::Homestead,
+        Opcode::REVERT | Opcode::RETURNDATASIZE | Opcode::RETURNDATACOPY | Opcode::STATICCALL => {
+            Fork::Byzantium
+        }
+        Opcode::SHL | Opcode::SHR | Opcode::SAR | Opcode::CREATE2 | Opcode::EXTCODEHASH => {
+            Fork::Constantinople
+        }
+        Opcode::CHAINID | Opcode::SELFBALANCE => Fork::Istanbul,
+        Opcode::BASEFEE => Fork::London,
+        Opcode::PUSH0 => Fork::Shanghai,
+        Opcode::TLOAD | Opcode::TSTORE | Opcode::MCOPY | Opcode::BLOBHASH | Opcode::BLOBBASEFEE => {
+            Fork::Cancun
+        }
+        opcode if opcode.is_eof_only() => Fork::Osaka,
+        _ => Fork::Frontier,
+    }
+}
+
+impl std::str::FromStr for Fork {
+    type Err = String;
+
+    /// Parses the fork names used by the execution spec tests, along with their aliases
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "Frontier" => Ok(Fork::Frontier),
+            "Homestead" => Ok(Fork::Homestead),
+            "TangerineWhistle" | "EIP150" => Ok(Fork::TangerineWhistle),
+            "SpuriousDragon" | "EIP158" => Ok(Fork::SpuriousDragon),
+            "Byzantium" => Ok(Fork::Byzantium),
+            "Constantinople" => Ok(Fork::Constantinople),
+            "Petersburg" | "ConstantinopleFix" => Ok(Fork::Petersburg),
+            "Istanbul" => Ok(Fork::Istanbul),
+            "Berlin" => Ok(Fork::Berlin),
+            "London" => Ok(Fork::London),
+            "Paris" | "Merge" => Ok(Fork::Paris),
+            "Shanghai" => Ok(Fork::Shanghai),
+            "Cancun" => Ok(Fork::Cancun),
+            "Prague" => Ok(Fork::Prague),
+            "Osaka" => Ok(Fork::Osaka),
+            _ => Err(format!("Unknown fork: {name}")),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
     call_frame::CallFrame,
-    constants::{WORD_SIZE, WORD_SIZE_IN_BYTES_U64},
+    constants::{MAX_CODE_SIZE, WORD_SIZE, WORD_SIZE_IN_BYTES_U64},
     errors::{InternalError, OutOfGasError, PrecompileError, VMError},
+    fork::Fork,
     memory, StorageSlot,
 };
 use bytes::Bytes;
@@ -87,6 +88,26 @@ pub const CODECOPY_DYNAMIC_BASE: u64 = 3;
 pub const GASPRICE: u64 = 2;
 pub const SELFDESTRUCT_STATIC: u64 = 5000;
 pub const SELFDESTRUCT_DYNAMIC: u64 = 25000;
+pub const SELFDESTRUCT_REFUND: u64 = 24000;
+
+// EOF opcodes cost [EIP-7692]
+pub const DATALOAD: u64 = 4;
+pub const DATALOADN: u64 = 3;
+pub const DATASIZE: u64 = 2;
+pub const DATACOPY_STATIC: u64 = 3;
+pub const DATACOPY_DYNAMIC_BASE: u64 = 3;
+pub const RJUMP: u64 = 2;
+pub const RJUMPI: u64 = 4;
+pub const RJUMPV: u64 = 4;
+pub const CALLF: u64 = 5;
+pub const RETF: u64 = 3;
+pub const JUMPF: u64 = 5;
+pub const EXCHANGE: u64 = 3;
+pub const RETURNDATALOAD: u64 = 3;
+pub const EOFCREATE_STATIC: u64 = 32000;
+pub const EOFCREATE_DYNAMIC_BASE: u64 = 6;
+pub const EXTCALL_MIN_RETAINED_GAS: u64 = 5000;
+pub const EXTCALL_MIN_CALLEE_GAS: u64 = 2300;
 
 pub const DEFAULT_STATIC: u64 = 0;
 pub const DEFAULT_COLD_DYNAMIC: u64 = 2600;
@@ -102,6 +123,9 @@ pub const SSTORE_DEFAULT_DYNAMIC: u64 = 100;
 pub const SSTORE_STORAGE_CREATION: u64 = 20000;
 pub const SSTORE_STORAGE_MODIFICATION: u64 = 2900;
 pub const SSTORE_STIPEND: u64 = 2300;
+pub const SSTORE_RESET: u64 = 5000;
+pub const SSTORE_CLEARS_REFUND: u64 = 15000;
+pub const SSTORE_CLEARS_REFUND_LONDON: u64 = 4800;
 
 pub const BALANCE_STATIC: u64 = DEFAULT_STATIC;
 pub const BALANCE_COLD_DYNAMIC: u64 = DEFAULT_COLD_DYNAMIC;
@@ -180,7 +204,271 @@ pub const MODEXP_STATIC_COST: u64 = 0;
 pub const MODEXP_DYNAMIC_BASE: u64 = 200;
 pub const MODEXP_DYNAMIC_QUOTIENT: u64 = 3;
 
-pub fn exp(exponent: U256) -> Result<u64, VMError> {
+pub const ECADD_COST: u64 = 150;
+pub const ECMUL_COST: u64 = 6000;
+pub const ECPAIRING_STATIC_COST: u64 = 45000;
+pub const ECPAIRING_DYNAMIC_BASE: u64 = 34000;
+
+// Costs of the BN254 precompiles before Istanbul
+pub const ECADD_BYZANTIUM_COST: u64 = 500;
+pub const ECMUL_BYZANTIUM_COST: u64 = 40000;
+pub const ECPAIRING_BYZANTIUM_STATIC_COST: u64 = 100000;
+pub const ECPAIRING_BYZANTIUM_DYNAMIC_BASE: u64 = 80000;
+
+pub const MODEXP_BYZANTIUM_QUOTIENT: u64 = 20;
+
+pub const BLAKE2F_ROUND_COST: u64 = 1;
+
+pub const POINT_EVALUATION_COST: u64 = 50000;
+
+// BLS12-381 precompile costs [EIP-2537]
+pub const BLS12_381_G1ADD_COST: u64 = 375;
+pub const BLS12_381_G2ADD_COST: u64 = 600;
+pub const BLS12_381_G1_MUL_COST: u64 = 12000;
+pub const BLS12_381_G2_MUL_COST: u64 = 22500;
+pub const BLS12_381_PAIRING_STATIC_COST: u64 = 37700;
+pub const BLS12_381_PAIRING_DYNAMIC_BASE: u64 = 32600;
+pub const BLS12_381_MAP_FP_TO_G1_COST: u64 = 5500;
+pub const BLS12_381_MAP_FP2_TO_G2_COST: u64 = 23800;
+pub const BLS12_381_MSM_MULTIPLIER: u64 = 1000;
+
+/// Discounts applied to G1MSM, indexed by the number of pairs minus one. The last one applies to
+/// every bigger input
+pub const BLS12_381_G1_MSM_DISCOUNTS: [u64; 128] = [
+    1000, 949, 848, 797, 764, 750, 738, 728, 719, 712, 705, 698, 692, 687, 682, 677, 673, 669, 665,
+    661, 658, 654, 651, 648, 645, 642, 640, 637, 635, 632, 630, 627, 625, 623, 621, 619, 617, 615,
+    613, 611, 609, 608, 606, 604, 603, 601, 599, 598, 596, 595, 593, 592, 591, 589, 588, 586, 585,
+    584, 582, 581, 580, 579, 577, 576, 575, 574, 573, 572, 570, 569, 568, 567, 566, 565, 564, 563,
+    562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 551, 550, 549, 548, 547, 547, 546, 545,
+    544, 543, 542, 541, 540, 540, 539, 538, 537, 536, 536, 535, 534, 533, 532, 532, 531, 530, 529,
+    528, 528, 527, 526, 525, 525, 524, 523, 522, 522, 521, 520, 520, 519,
+];
+
+/// Discounts applied to G2MSM, indexed by the number of pairs minus one. The last one applies to
+/// every bigger input
+pub const BLS12_381_G2_MSM_DISCOUNTS: [u64; 128] = [
+    1000, 1000, 923, 884, 855, 832, 812, 796, 782, 770, 759, 749, 740, 732, 724, 717, 711, 704,
+    699, 693, 688, 683, 679, 674, 670, 666, 663, 659, 655, 652, 649, 646, 643, 640, 637, 634, 632,
+    629, 627, 624, 622, 620, 618, 615, 613, 611, 609, 607, 606, 604, 602, 600, 598, 597, 595, 593,
+    592, 590, 589, 587, 586, 584, 583, 582, 580, 579, 578, 576, 575, 574, 573, 571, 570, 569, 568,
+    567, 566, 565, 563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 552, 551, 550, 549,
+    548, 547, 546, 545, 545, 544, 543, 542, 541, 541, 540, 539, 538, 537, 537, 536, 535, 535, 534,
+    533, 532, 532, 531, 530, 530, 529, 528, 528, 527, 526, 526, 525, 524, 524,
+];
+
+/// Gas costs and rules that changed across forks. The ones not listed here haven't changed since
+/// Frontier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasSchedule {
+    /// Whether accessing an account or storage slot for the first time in the transaction costs
+    /// more, as defined in [EIP-2929]. If set, the flat access costs below are unused
+    pub access_lists: bool,
+    /// Flat cost of BALANCE
+    pub balance: u64,
+    /// Flat cost of EXTCODESIZE and EXTCODECOPY, without the copy and memory expansion costs
+    pub extcode: u64,
+    /// Flat cost of EXTCODEHASH
+    pub extcodehash: u64,
+    /// Flat cost of CALL, CALLCODE, DELEGATECALL and STATICCALL, without the value transfer and
+    /// memory expansion costs
+    pub call: u64,
+    /// Flat cost of SLOAD
+    pub sload: u64,
+    pub sstore: SstoreSchedule,
+    /// Cost of each byte of the exponent of EXP
+    pub exp_byte: u64,
+    pub selfdestruct: u64,
+    /// Cost added to SELFDESTRUCT when the beneficiary has to be created
+    pub selfdestruct_new_account: u64,
+    /// Refund for each account destroyed in the transaction
+    pub selfdestruct_refund: u64,
+    /// Whether only transfers of value pay for creating the target account, [EIP-161]
+    pub new_account_requires_value: bool,
+    /// Whether accounts left empty by a transaction are deleted from the state, [EIP-161]
+    pub clear_empty_accounts: bool,
+    /// Nonce new contracts start with, 1 since [EIP-161]
+    pub created_contract_nonce: u64,
+    /// Whether a creation fails if there's not enough gas left to deposit the contract's code,
+    /// [EIP-2]. Before, the contract was created without code
+    pub code_deposit_out_of_gas_fails: bool,
+    /// Whether calls can only be given all but one 64th of the gas left, [EIP-150]
+    pub all_but_one_64th: bool,
+    /// Refunds are capped to the gas used divided by this quotient
+    pub max_refund_quotient: u64,
+    /// Cost of each non-zero byte of the transaction data
+    pub tx_data_non_zero: u64,
+    /// Cost of contract creation transactions, on top of the base cost
+    pub tx_create: u64,
+    /// Max size of the code of a contract, [EIP-170]
+    pub max_code_size: Option<usize>,
+    /// Whether the init code size is limited and each of its words charged, [EIP-3860]
+    pub limit_init_code: bool,
+    /// Whether code starting with 0xEF can't be deployed, [EIP-3541]
+    pub reject_ef_code: bool,
+    /// Whether the coinbase is warm from the start of the transaction, [EIP-3651]
+    pub warm_coinbase: bool,
+    /// Whether SELFDESTRUCT only deletes accounts created in the same transaction, [EIP-6780]
+    pub selfdestruct_only_created: bool,
+    /// Whether code in EVM Object Format can be deployed and executed, [EIP-7692]
+    pub eof: bool,
+}
+
+/// Gas costs and refunds of SSTORE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SstoreSchedule {
+    /// Setting a zero slot costs 20000 and any other write 5000. Clearing a slot refunds 15000
+    Flat,
+    /// Net gas metering, as defined in [EIP-1283] and [EIP-2200]. Only the first write to a slot in
+    /// the transaction pays the full cost, and restoring its original value is refunded
+    NetMetering {
+        /// Cost of writes that don't change the slot or that overwrite a value written in the
+        /// same transaction
+        noop: u64,
+        /// Cost of the first write to a non-zero slot
+        reset: u64,
+        /// Refund for clearing a slot
+        clear_refund: u64,
+        /// Whether SSTORE fails if there's no more gas left than the call stipend
+        stipend_check: bool,
+    },
+}
+
+pub const FRONTIER_SCHEDULE: GasSchedule = GasSchedule {
+    access_lists: false,
+    balance: 20,
+    extcode: 20,
+    extcodehash: 0,
+    call: 40,
+    sload: 50,
+    sstore: SstoreSchedule::Flat,
+    exp_byte: 10,
+    selfdestruct: 0,
+    selfdestruct_new_account: 0,
+    selfdestruct_refund: SELFDESTRUCT_REFUND,
+    new_account_requires_value: false,
+    clear_empty_accounts: false,
+    created_contract_nonce: 0,
+    code_deposit_out_of_gas_fails: false,
+    all_but_one_64th: false,
+    max_refund_quotient: 2,
+    tx_data_non_zero: 68,
+    tx_create: 0,
+    max_code_size: None,
+    limit_init_code: false,
+    reject_ef_code: false,
+    warm_coinbase: false,
+    selfdestruct_only_created: false,
+    eof: false,
+};
+
+/// [EIP-2]
+pub const HOMESTEAD_SCHEDULE: GasSchedule = GasSchedule {
+    tx_create: CREATE_BASE_COST,
+    code_deposit_out_of_gas_fails: true,
+    ..FRONTIER_SCHEDULE
+};
+
+/// [EIP-150]
+pub const TANGERINE_WHISTLE_SCHEDULE: GasSchedule = GasSchedule {
+    balance: 400,
+    extcode: 700,
+    call: 700,
+    sload: 200,
+    selfdestruct: SELFDESTRUCT_STATIC,
+    selfdestruct_new_account: SELFDESTRUCT_DYNAMIC,
+    all_but_one_64th: true,
+    ..HOMESTEAD_SCHEDULE
+};
+
+/// [EIP-160], [EIP-161] and [EIP-170]. Byzantium didn't change any of them
+pub const SPURIOUS_DRAGON_SCHEDULE: GasSchedule = GasSchedule {
+    exp_byte: EXP_DYNAMIC_BASE,
+    new_account_requires_value: true,
+    clear_empty_accounts: true,
+    created_contract_nonce: 1,
+    max_code_size: Some(MAX_CODE_SIZE),
+    ..TANGERINE_WHISTLE_SCHEDULE
+};
+
+/// [EIP-1052] and [EIP-1283]
+pub const CONSTANTINOPLE_SCHEDULE: GasSchedule = GasSchedule {
+    extcodehash: 400,
+    sstore: SstoreSchedule::NetMetering {
+        noop: 200,
+        reset: SSTORE_RESET,
+        clear_refund: SSTORE_CLEARS_REFUND,
+        stipend_check: false,
+    },
+    ..SPURIOUS_DRAGON_SCHEDULE
+};
+
+/// Constantinople without [EIP-1283]
+pub const PETERSBURG_SCHEDULE: GasSchedule = GasSchedule {
+    sstore: SstoreSchedule::Flat,
+    ..CONSTANTINOPLE_SCHEDULE
+};
+
+/// [EIP-1884], [EIP-2028] and [EIP-2200]
+pub const ISTANBUL_SCHEDULE: GasSchedule = GasSchedule {
+    balance: 700,
+    extcodehash: 700,
+    sload: 800,
+    sstore: SstoreSchedule::NetMetering {
+        noop: 800,
+        reset: SSTORE_RESET,
+        clear_refund: SSTORE_CLEARS_REFUND,
+        stipend_check: true,
+    },
+    tx_data_non_zero: CALLDATA_COST_NON_ZERO_BYTE,
+    ..PETERSBURG_SCHEDULE
+};
+
+/// [EIP-2929]
+pub const BERLIN_SCHEDULE: GasSchedule = GasSchedule {
+    access_lists: true,
+    sstore: SstoreSchedule::NetMetering {
+        noop: SSTORE_DEFAULT_DYNAMIC,
+        reset: SSTORE_STORAGE_MODIFICATION,
+        clear_refund: SSTORE_CLEARS_REFUND,
+        stipend_check: true,
+    },
+    ..ISTANBUL_SCHEDULE
+};
+
+/// [EIP-3529] and [EIP-3541]. Paris didn't change any of them
+pub const LONDON_SCHEDULE: GasSchedule = GasSchedule {
+    sstore: SstoreSchedule::NetMetering {
+        noop: SSTORE_DEFAULT_DYNAMIC,
+        reset: SSTORE_STORAGE_MODIFICATION,
+        clear_refund: SSTORE_CLEARS_REFUND_LONDON,
+        stipend_check: true,
+    },
+    selfdestruct_refund: 0,
+    max_refund_quotient: 5,
+    reject_ef_code: true,
+    ..BERLIN_SCHEDULE
+};
+
+/// [EIP-3651] and [EIP-3860]
+pub const SHANGHAI_SCHEDULE: GasSchedule = GasSchedule {
+    limit_init_code: true,
+    warm_coinbase: true,
+    ..LONDON_SCHEDULE
+};
+
+/// [EIP-6780]. Prague didn't change any of them
+pub const CANCUN_SCHEDULE: GasSchedule = GasSchedule {
+    selfdestruct_only_created: true,
+    ..SHANGHAI_SCHEDULE
+};
+
+/// [EIP-7692]
+pub const OSAKA_SCHEDULE: GasSchedule = GasSchedule {
+    eof: true,
+    ..CANCUN_SCHEDULE
+};
+
+pub fn exp(exponent: U256, fork: Fork) -> Result<u64, VMError> {
     let exponent_byte_size = (exponent
         .bits()
         .checked_add(7)
@@ -191,7 +479,9 @@ pub fn exp(exponent: U256) -> Result<u64, VMError> {
         .try_into()
         .map_err(|_| VMError::VeryLargeNumber)?;
 
-    let exponent_byte_size_cost = EXP_DYNAMIC_BASE
+    let exponent_byte_size_cost = fork
+        .gas_schedule()
+        .exp_byte
         .checked_mul(exponent_byte_size)
         .ok_or(VMError::OutOfGas(OutOfGasError::GasCostOverflow))?;
 
@@ -242,6 +532,20 @@ pub fn returndatacopy(
     )
 }
 
+pub fn datacopy(
+    new_memory_size: usize,
+    current_memory_size: usize,
+    size: usize,
+) -> Result<u64, VMError> {
+    copy_behavior(
+        new_memory_size,
+        current_memory_size,
+        size,
+        DATACOPY_DYNAMIC_BASE,
+        DATACOPY_STATIC,
+    )
+}
+
 fn copy_behavior(
     new_memory_size: usize,
     current_memory_size: usize,
@@ -345,7 +649,12 @@ fn mem_expansion_behavior(
         .ok_or(OutOfGasError::GasCostOverflow)?)
 }
 
-pub fn sload(storage_slot_was_cold: bool) -> Result<u64, VMError> {
+pub fn sload(storage_slot_was_cold: bool, fork: Fork) -> Result<u64, VMError> {
+    let schedule = fork.gas_schedule();
+    if !schedule.access_lists {
+        return Ok(schedule.sload);
+    }
+
     let static_gas = SLOAD_STATIC;
 
     let dynamic_cost = if storage_slot_was_cold {
@@ -364,31 +673,52 @@ pub fn sstore(
     new_value: U256,
     storage_slot_was_cold: bool,
     current_call_frame: &CallFrame,
+    fork: Fork,
 ) -> Result<u64, VMError> {
+    let schedule = fork.gas_schedule();
+    let (noop, reset, stipend_check) = match schedule.sstore {
+        SstoreSchedule::Flat => {
+            let cost = if storage_slot.current_value.is_zero() && !new_value.is_zero() {
+                SSTORE_STORAGE_CREATION
+            } else {
+                SSTORE_RESET
+            };
+            return Ok(cost);
+        }
+        SstoreSchedule::NetMetering {
+            noop,
+            reset,
+            stipend_check,
+            ..
+        } => (noop, reset, stipend_check),
+    };
+
     // EIP-2200
-    let gas_left = current_call_frame
-        .gas_limit
-        .checked_sub(current_call_frame.gas_used)
-        .ok_or(OutOfGasError::ConsumedGasOverflow)?;
-    if gas_left <= SSTORE_STIPEND {
-        return Err(VMError::OutOfGas(OutOfGasError::MaxGasLimitExceeded));
+    if stipend_check {
+        let gas_left = current_call_frame
+            .gas_limit
+            .checked_sub(current_call_frame.gas_used)
+            .ok_or(OutOfGasError::ConsumedGasOverflow)?;
+        if gas_left <= SSTORE_STIPEND {
+            return Err(VMError::OutOfGas(OutOfGasError::MaxGasLimitExceeded));
+        }
     }
 
     let static_gas = SSTORE_STATIC;
 
     let mut base_dynamic_gas = if new_value == storage_slot.current_value {
-        SSTORE_DEFAULT_DYNAMIC
+        noop
     } else if storage_slot.current_value == storage_slot.original_value {
         if storage_slot.original_value.is_zero() {
             SSTORE_STORAGE_CREATION
         } else {
-            SSTORE_STORAGE_MODIFICATION
+            reset
         }
     } else {
-        SSTORE_DEFAULT_DYNAMIC
+        noop
     };
 
-    if storage_slot_was_cold {
+    if schedule.access_lists && storage_slot_was_cold {
         base_dynamic_gas = base_dynamic_gas
             .checked_add(SSTORE_COLD_DYNAMIC)
             .ok_or(OutOfGasError::GasCostOverflow)?;
@@ -399,6 +729,66 @@ pub fn sstore(
         .ok_or(OutOfGasError::GasCostOverflow)?)
 }
 
+/// Change in the refund counter caused by an SSTORE, as a pair of gas to add and gas to subtract
+pub fn sstore_refund(
+    storage_slot: &StorageSlot,
+    new_value: U256,
+    fork: Fork,
+) -> Result<(u64, u64), VMError> {
+    let (noop, reset, clear_refund) = match fork.gas_schedule().sstore {
+        SstoreSchedule::Flat => {
+            let refund = if !storage_slot.current_value.is_zero() && new_value.is_zero() {
+                SSTORE_CLEARS_REFUND
+            } else {
+                0
+            };
+            return Ok((refund, 0));
+        }
+        SstoreSchedule::NetMetering {
+            noop,
+            reset,
+            clear_refund,
+            ..
+        } => (noop, reset, clear_refund),
+    };
+
+    let (mut added, mut subtracted) = (0u64, 0u64);
+    if new_value == storage_slot.current_value {
+        return Ok((added, subtracted));
+    }
+
+    if storage_slot.current_value == storage_slot.original_value {
+        if !storage_slot.original_value.is_zero() && new_value.is_zero() {
+            added = clear_refund;
+        }
+        return Ok((added, subtracted));
+    }
+
+    if !storage_slot.original_value.is_zero() {
+        if storage_slot.current_value.is_zero() {
+            subtracted = clear_refund;
+        } else if new_value.is_zero() {
+            added = clear_refund;
+        }
+    }
+    if new_value == storage_slot.original_value {
+        let restored_cost = if storage_slot.original_value.is_zero() {
+            SSTORE_STORAGE_CREATION
+        } else {
+            reset
+        };
+        added = added
+            .checked_add(
+                restored_cost
+                    .checked_sub(noop)
+                    .ok_or(InternalError::ArithmeticOperationUnderflow)?,
+            )
+            .ok_or(VMError::GasRefundsOverflow)?;
+    }
+
+    Ok((added, subtracted))
+}
+
 pub fn mcopy(
     new_memory_size: usize,
     current_memory_size: usize,
@@ -434,12 +824,14 @@ pub fn create(
     new_memory_size: usize,
     current_memory_size: usize,
     code_size_in_memory: usize,
+    fork: Fork,
 ) -> Result<u64, VMError> {
     compute_gas_create(
         new_memory_size,
         current_memory_size,
         code_size_in_memory,
         false,
+        fork,
     )
 }
 
@@ -447,12 +839,14 @@ pub fn create_2(
     new_memory_size: usize,
     current_memory_size: usize,
     code_size_in_memory: usize,
+    fork: Fork,
 ) -> Result<u64, VMError> {
     compute_gas_create(
         new_memory_size,
         current_memory_size,
         code_size_in_memory,
         true,
+        fork,
     )
 }
 
@@ -461,6 +855,7 @@ fn compute_gas_create(
     current_memory_size: usize,
     code_size_in_memory: usize,
     is_create_2: bool,
+    fork: Fork,
 ) -> Result<u64, VMError> {
     let minimum_word_size = (code_size_in_memory
         .checked_add(31)
@@ -472,9 +867,13 @@ fn compute_gas_create(
         .try_into()
         .map_err(|_| VMError::VeryLargeNumber)?;
 
-    let init_code_cost = minimum_word_size
-        .checked_mul(INIT_CODE_WORD_COST)
-        .ok_or(OutOfGasError::GasCostOverflow)?; // will not panic since it's 2
+    let init_code_cost = if fork.gas_schedule().limit_init_code {
+        minimum_word_size
+            .checked_mul(INIT_CODE_WORD_COST)
+            .ok_or(OutOfGasError::GasCostOverflow)? // will not panic since it's 2
+    } else {
+        0
+    };
 
     let memory_expansion_cost = memory::expansion_cost(new_memory_size, current_memory_size)?;
     let memory_expansion_cost: u64 = memory_expansion_cost
@@ -500,37 +899,70 @@ fn compute_gas_create(
     Ok(gas_create_cost)
 }
 
+/// The initcontainer is hashed to compute the address, but as it was validated on deployment
+/// there's no per word init code cost, [EIP-7620]
+pub fn eofcreate(
+    new_memory_size: usize,
+    current_memory_size: usize,
+    initcontainer_size: usize,
+) -> Result<u64, VMError> {
+    let initcontainer_words: u64 = (initcontainer_size
+        .checked_add(WORD_SIZE)
+        .ok_or(OutOfGasError::GasCostOverflow)?
+        .saturating_sub(1)
+        / WORD_SIZE)
+        .try_into()
+        .map_err(|_| VMError::VeryLargeNumber)?;
+
+    let memory_expansion_cost: u64 = memory::expansion_cost(new_memory_size, current_memory_size)?
+        .try_into()
+        .map_err(|_| VMError::VeryLargeNumber)?;
+
+    let hash_cost = initcontainer_words
+        .checked_mul(EOFCREATE_DYNAMIC_BASE)
+        .ok_or(OutOfGasError::CreationCostIsTooHigh)?;
+
+    Ok(EOFCREATE_STATIC
+        .checked_add(hash_cost)
+        .ok_or(OutOfGasError::CreationCostIsTooHigh)?
+        .checked_add(memory_expansion_cost)
+        .ok_or(OutOfGasError::CreationCostIsTooHigh)?)
+}
+
 pub fn selfdestruct(
     address_was_cold: bool,
     account_is_empty: bool,
     balance_to_transfer: U256,
+    fork: Fork,
 ) -> Result<u64, OutOfGasError> {
-    let mut gas_cost = SELFDESTRUCT_STATIC;
+    let schedule = fork.gas_schedule();
+    let mut gas_cost = schedule.selfdestruct;
 
-    if address_was_cold {
+    if schedule.access_lists && address_was_cold {
         gas_cost = gas_cost
             .checked_add(COLD_ADDRESS_ACCESS_COST)
             .ok_or(OutOfGasError::GasCostOverflow)?;
     }
 
     // If a positive balance is sent to an empty account, the dynamic gas is 25000
-    if account_is_empty && balance_to_transfer > U256::zero() {
+    if account_is_empty && (!schedule.new_account_requires_value || !balance_to_transfer.is_zero())
+    {
         gas_cost = gas_cost
-            .checked_add(SELFDESTRUCT_DYNAMIC)
+            .checked_add(schedule.selfdestruct_new_account)
             .ok_or(OutOfGasError::GasCostOverflow)?;
     }
 
     Ok(gas_cost)
 }
 
-pub fn tx_calldata(calldata: &Bytes) -> Result<u64, OutOfGasError> {
+pub fn tx_calldata(calldata: &Bytes, fork: Fork) -> Result<u64, OutOfGasError> {
     // This cost applies both for call and create
     // 4 gas for each zero byte in the transaction data 16 gas for each non-zero byte in the transaction.
     let mut calldata_cost: u64 = 0;
     for byte in calldata {
         if *byte != 0 {
             calldata_cost = calldata_cost
-                .checked_add(CALLDATA_COST_NON_ZERO_BYTE)
+                .checked_add(fork.gas_schedule().tx_data_non_zero)
                 .ok_or(OutOfGasError::GasUsedOverflow)?;
         } else {
             calldata_cost = calldata_cost
@@ -558,12 +990,19 @@ pub fn tx_creation(code_length: u64, number_of_words: u64) -> Result<u64, OutOfG
         .ok_or(OutOfGasError::GasUsedOverflow)
 }
 
+/// Cost of accessing an account, `flat_cost` being the one before access lists
 fn address_access_cost(
     address_was_cold: bool,
+    fork: Fork,
+    flat_cost: u64,
     static_cost: u64,
     cold_dynamic_cost: u64,
     warm_dynamic_cost: u64,
 ) -> Result<u64, VMError> {
+    if !fork.gas_schedule().access_lists {
+        return Ok(flat_cost);
+    }
+
     let static_gas = static_cost;
     let dynamic_cost: u64 = if address_was_cold {
         cold_dynamic_cost
@@ -576,18 +1015,22 @@ fn address_access_cost(
         .ok_or(OutOfGasError::GasCostOverflow)?)
 }
 
-pub fn balance(address_was_cold: bool) -> Result<u64, VMError> {
+pub fn balance(address_was_cold: bool, fork: Fork) -> Result<u64, VMError> {
     address_access_cost(
         address_was_cold,
+        fork,
+        fork.gas_schedule().balance,
         BALANCE_STATIC,
         BALANCE_COLD_DYNAMIC,
         BALANCE_WARM_DYNAMIC,
     )
 }
 
-pub fn extcodesize(address_was_cold: bool) -> Result<u64, VMError> {
+pub fn extcodesize(address_was_cold: bool, fork: Fork) -> Result<u64, VMError> {
     address_access_cost(
         address_was_cold,
+        fork,
+        fork.gas_schedule().extcode,
         EXTCODESIZE_STATIC,
         EXTCODESIZE_COLD_DYNAMIC,
         EXTCODESIZE_WARM_DYNAMIC,
@@ -599,6 +1042,7 @@ pub fn extcodecopy(
     new_memory_size: usize,
     current_memory_size: usize,
     address_was_cold: bool,
+    fork: Fork,
 ) -> Result<u64, VMError> {
     let base_access_cost = copy_behavior(
         new_memory_size,
@@ -609,6 +1053,8 @@ pub fn extcodecopy(
     )?;
     let expansion_access_cost = address_access_cost(
         address_was_cold,
+        fork,
+        fork.gas_schedule().extcode,
         EXTCODECOPY_STATIC,
         EXTCODECOPY_COLD_DYNAMIC,
         EXTCODECOPY_WARM_DYNAMIC,
@@ -619,9 +1065,11 @@ pub fn extcodecopy(
         .ok_or(OutOfGasError::GasCostOverflow)?)
 }
 
-pub fn extcodehash(address_was_cold: bool) -> Result<u64, VMError> {
+pub fn extcodehash(address_was_cold: bool, fork: Fork) -> Result<u64, VMError> {
     address_access_cost(
         address_was_cold,
+        fork,
+        fork.gas_schedule().extcodehash,
         EXTCODEHASH_STATIC,
         EXTCODEHASH_COLD_DYNAMIC,
         EXTCODEHASH_WARM_DYNAMIC,
@@ -634,6 +1082,7 @@ pub fn call(
     address_was_cold: bool,
     address_is_empty: bool,
     value_to_transfer: U256,
+    fork: Fork,
 ) -> Result<u64, VMError> {
     let static_gas = CALL_STATIC;
 
@@ -644,6 +1093,8 @@ pub fn call(
 
     let address_access_cost = address_access_cost(
         address_was_cold,
+        fork,
+        fork.gas_schedule().call,
         CALL_STATIC,
         CALL_COLD_DYNAMIC,
         CALL_WARM_DYNAMIC,
@@ -655,7 +1106,9 @@ pub fn call(
     } else {
         0
     };
-    let value_to_empty_account = if address_is_empty && !value_to_transfer.is_zero() {
+    let value_to_empty_account = if address_is_empty
+        && (!fork.gas_schedule().new_account_requires_value || !value_to_transfer.is_zero())
+    {
         CALL_TO_EMPTY_ACCOUNT
     } else {
         0
@@ -680,6 +1133,7 @@ pub fn callcode(
     current_memory_size: usize,
     address_was_cold: bool,
     value_to_transfer: U256,
+    fork: Fork,
 ) -> Result<u64, VMError> {
     let static_gas = CALLCODE_STATIC;
 
@@ -690,6 +1144,8 @@ pub fn callcode(
 
     let address_access_cost = address_access_cost(
         address_was_cold,
+        fork,
+        fork.gas_schedule().call,
         CALLCODE_STATIC,
         CALLCODE_COLD_DYNAMIC,
         CALLCODE_WARM_DYNAMIC,
@@ -718,6 +1174,7 @@ pub fn delegatecall(
     new_memory_size: usize,
     current_memory_size: usize,
     address_was_cold: bool,
+    fork: Fork,
 ) -> Result<u64, VMError> {
     let static_gas = DELEGATECALL_STATIC;
 
@@ -728,6 +1185,8 @@ pub fn delegatecall(
 
     let address_access_cost = address_access_cost(
         address_was_cold,
+        fork,
+        fork.gas_schedule().call,
         DELEGATECALL_STATIC,
         DELEGATECALL_COLD_DYNAMIC,
         DELEGATECALL_WARM_DYNAMIC,
@@ -747,6 +1206,7 @@ pub fn staticcall(
     new_memory_size: usize,
     current_memory_size: usize,
     address_was_cold: bool,
+    fork: Fork,
 ) -> Result<u64, VMError> {
     let static_gas = STATICCALL_STATIC;
 
@@ -757,6 +1217,8 @@ pub fn staticcall(
 
     let address_access_cost = address_access_cost(
         address_was_cold,
+        fork,
+        fork.gas_schedule().call,
         STATICCALL_STATIC,
         STATICCALL_COLD_DYNAMIC,
         STATICCALL_WARM_DYNAMIC,
@@ -822,8 +1284,9 @@ pub fn identity(data_size: usize) -> Result<u64, VMError> {
     precompile(data_size, IDENTITY_STATIC_COST, IDENTITY_DYNAMIC_BASE)
 }
 
+/// Cost of MODEXP as defined in [EIP-2565], `exponent_head` being the first 32 bytes of the exponent
 pub fn modexp(
-    exponent: U256,
+    exponent_head: U256,
     base_size: u64,
     exponent_size: u64,
     modulus_size: u64,
@@ -832,45 +1295,10 @@ pub fn modexp(
     let words = (max_length
         .checked_add(7)
         .ok_or(OutOfGasError::GasCostOverflow)?)
-        / WORD_SIZE_IN_BYTES_U64;
+        / 8;
     let multiplication_complexity = words.checked_pow(2).ok_or(OutOfGasError::GasCostOverflow)?;
 
-    let mut iteration_count: u64 = 0;
-    if exponent_size <= WORD_SIZE_IN_BYTES_U64 && exponent.is_zero() {
-        iteration_count = 0;
-    } else if exponent_size <= WORD_SIZE_IN_BYTES_U64 {
-        iteration_count = exponent
-            .bits()
-            .checked_sub(1)
-            .ok_or(InternalError::ArithmeticOperationUnderflow)?
-            .try_into()
-            .map_err(|_| InternalError::ConversionError)?;
-    } else if exponent_size > WORD_SIZE_IN_BYTES_U64 {
-        iteration_count = 8u64
-            .checked_mul(
-                exponent_size
-                    .checked_sub(WORD_SIZE_IN_BYTES_U64)
-                    .ok_or(InternalError::ArithmeticOperationUnderflow)?,
-            )
-            .ok_or(InternalError::ArithmeticOperationOverflow)?
-            .checked_add(
-                (exponent
-                    & (2usize
-                        .checked_pow(256)
-                        .ok_or(InternalError::ArithmeticOperationOverflow)?)
-                    .checked_sub(1)
-                    .ok_or(InternalError::ArithmeticOperationOverflow)?
-                    .into())
-                .bits()
-                .checked_sub(1)
-                .ok_or(InternalError::ArithmeticOperationUnderflow)?
-                .try_into()
-                .map_err(|_| InternalError::ConversionError)?,
-            )
-            .ok_or(InternalError::ArithmeticOperationOverflow)?;
-    }
-
-    let calculate_iteration_count = iteration_count.max(1);
+    let calculate_iteration_count = modexp_iteration_count(exponent_head, exponent_size)?;
 
     let static_gas = MODEXP_STATIC_COST;
 
@@ -886,6 +1314,121 @@ pub fn modexp(
         .ok_or(OutOfGasError::GasCostOverflow)?)
 }
 
+/// Cost of MODEXP before Berlin, as defined in [EIP-198]
+pub fn modexp_byzantium(
+    exponent_head: U256,
+    base_size: u64,
+    exponent_size: u64,
+    modulus_size: u64,
+) -> Result<u64, VMError> {
+    let x = base_size.max(modulus_size);
+    let square = x.checked_pow(2).ok_or(OutOfGasError::GasCostOverflow)?;
+    let multiplication_complexity = if x <= 64 {
+        square
+    } else if x <= 1024 {
+        (square / 4)
+            .checked_add(x.checked_mul(96).ok_or(OutOfGasError::GasCostOverflow)?)
+            .ok_or(OutOfGasError::GasCostOverflow)?
+            .saturating_sub(3072)
+    } else {
+        (square / 16)
+            .checked_add(x.checked_mul(480).ok_or(OutOfGasError::GasCostOverflow)?)
+            .ok_or(OutOfGasError::GasCostOverflow)?
+            .saturating_sub(199680)
+    };
+
+    let iteration_count = modexp_iteration_count(exponent_head, exponent_size)?;
+
+    Ok(multiplication_complexity
+        .checked_mul(iteration_count)
+        .ok_or(OutOfGasError::GasCostOverflow)?
+        / MODEXP_BYZANTIUM_QUOTIENT)
+}
+
+/// Adjusted length of the exponent, which is at least one
+fn modexp_iteration_count(exponent_head: U256, exponent_size: u64) -> Result<u64, VMError> {
+    let exponent_head_bits: u64 = exponent_head
+        .bits()
+        .saturating_sub(1)
+        .try_into()
+        .map_err(|_| InternalError::ConversionError)?;
+    let iteration_count = if exponent_size <= WORD_SIZE_IN_BYTES_U64 {
+        exponent_head_bits
+    } else {
+        exponent_size
+            .checked_sub(WORD_SIZE_IN_BYTES_U64)
+            .ok_or(InternalError::ArithmeticOperationUnderflow)?
+            .checked_mul(8)
+            .ok_or(OutOfGasError::GasCostOverflow)?
+            .checked_add(exponent_head_bits)
+            .ok_or(OutOfGasError::GasCostOverflow)?
+    };
+
+    Ok(iteration_count.max(1))
+}
+
+pub fn ecpairing(pairs: usize) -> Result<u64, VMError> {
+    linear_cost(pairs, ECPAIRING_STATIC_COST, ECPAIRING_DYNAMIC_BASE)
+}
+
+pub fn ecpairing_byzantium(pairs: usize) -> Result<u64, VMError> {
+    linear_cost(
+        pairs,
+        ECPAIRING_BYZANTIUM_STATIC_COST,
+        ECPAIRING_BYZANTIUM_DYNAMIC_BASE,
+    )
+}
+
+pub fn blake2f(rounds: u32) -> Result<u64, VMError> {
+    Ok(BLAKE2F_ROUND_COST
+        .checked_mul(rounds.into())
+        .ok_or(OutOfGasError::GasCostOverflow)?)
+}
+
+pub fn bls12_381_pairing(pairs: usize) -> Result<u64, VMError> {
+    linear_cost(
+        pairs,
+        BLS12_381_PAIRING_STATIC_COST,
+        BLS12_381_PAIRING_DYNAMIC_BASE,
+    )
+}
+
+/// Cost of a multi-scalar multiplication of `pairs` points, with the discounts of [EIP-2537]
+pub fn bls12_381_msm(
+    pairs: usize,
+    multiplication_cost: u64,
+    discounts: &[u64; 128],
+) -> Result<u64, VMError> {
+    let max_discount = discounts.last().copied().unwrap_or_default();
+    let discount = pairs
+        .checked_sub(1)
+        .and_then(|index| discounts.get(index))
+        .copied()
+        .unwrap_or(max_discount);
+    let pairs: u64 = pairs
+        .try_into()
+        .map_err(|_| PrecompileError::ParsingInputError)?;
+
+    Ok(pairs
+        .checked_mul(multiplication_cost)
+        .ok_or(OutOfGasError::GasCostOverflow)?
+        .checked_mul(discount)
+        .ok_or(OutOfGasError::GasCostOverflow)?
+        / BLS12_381_MSM_MULTIPLIER)
+}
+
+fn linear_cost(count: usize, static_cost: u64, dynamic_base: u64) -> Result<u64, VMError> {
+    let count: u64 = count
+        .try_into()
+        .map_err(|_| PrecompileError::ParsingInputError)?;
+
+    Ok(dynamic_base
+        .checked_mul(count)
+        .ok_or(OutOfGasError::GasCostOverflow)?
+        .checked_add(static_cost)
+        .ok_or(OutOfGasError::GasCostOverflow)?)
+}
+
 fn precompile(data_size: usize, static_cost: u64, dynamic_base: u64) -> Result<u64, VMError> {
     let data_size: u64 = data_size
         .try_into()
@@ -906,14 +1449,74 @@ fn precompile(data_size: usize, static_cost: u64, dynamic_base: u64) -> Result<u
         .ok_or(OutOfGasError::GasCostOverflow)?)
 }
 
+/// Cost of EXTCALL, EXTDELEGATECALL and EXTSTATICCALL, only the first can transfer value
+/// [EIP-7069]
+pub fn extcall(
+    new_memory_size: usize,
+    current_memory_size: usize,
+    address_was_cold: bool,
+    address_is_empty: bool,
+    value_to_transfer: U256,
+) -> Result<u64, VMError> {
+    let memory_expansion_cost: u64 = memory::expansion_cost(new_memory_size, current_memory_size)?
+        .try_into()
+        .map_err(|_| VMError::VeryLargeNumber)?;
+
+    let address_access_cost = if address_was_cold {
+        COLD_ADDRESS_ACCESS_COST
+    } else {
+        WARM_ADDRESS_ACCESS_COST
+    };
+    let positive_value_cost = if value_to_transfer.is_zero() {
+        0
+    } else {
+        CALL_POSITIVE_VALUE
+    };
+    let value_to_empty_account = if address_is_empty && !value_to_transfer.is_zero() {
+        CALL_TO_EMPTY_ACCOUNT
+    } else {
+        0
+    };
+
+    Ok(memory_expansion_cost
+        .checked_add(address_access_cost)
+        .ok_or(OutOfGasError::GasCostOverflow)?
+        .checked_add(positive_value_cost)
+        .ok_or(OutOfGasError::GasCostOverflow)?
+        .checked_add(value_to_empty_account)
+        .ok_or(OutOfGasError::GasCostOverflow)?)
+}
+
+/// Gas passed to the callee of the EXT*CALL instructions, all the remaining gas but the biggest
+/// of one 64th of it and 5000. None if it's less than the minimum callee gas, in which case the
+/// call fails without executing [EIP-7069]
+pub fn extcall_callee_gas(current_call_frame: &CallFrame) -> Result<Option<u64>, VMError> {
+    let remaining_gas = current_call_frame
+        .gas_limit
+        .checked_sub(current_call_frame.gas_used)
+        .ok_or(InternalError::GasOverflow)?;
+    let retained_gas = (remaining_gas / 64).max(EXTCALL_MIN_RETAINED_GAS);
+    let callee_gas = remaining_gas.saturating_sub(retained_gas);
+
+    if callee_gas < EXTCALL_MIN_CALLEE_GAS {
+        return Ok(None);
+    }
+    Ok(Some(callee_gas))
+}
+
 /// Max message call gas is all but one 64th of the remaining gas in the current context.
 /// https://eips.ethereum.org/EIPS/eip-150
-pub fn max_message_call_gas(current_call_frame: &CallFrame) -> Result<u64, VMError> {
+/// Before it, it was all the remaining gas
+pub fn max_message_call_gas(current_call_frame: &CallFrame, fork: Fork) -> Result<u64, VMError> {
     let mut remaining_gas = current_call_frame
         .gas_limit
         .checked_sub(current_call_frame.gas_used)
         .ok_or(InternalError::GasOverflow)?;
 
+    if !fork.gas_schedule().all_but_one_64th {
+        return Ok(remaining_gas);
+    }
+
     remaining_gas = remaining_gas
         .checked_sub(remaining_gas / 64)
         .ok_or(InternalError::GasOverflow)?;
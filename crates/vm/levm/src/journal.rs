@@ -0,0 +1,179 @@
+use crate::{
+    account::{Account, StorageSlot},
+    db::{cache, CacheDB},
+    vm::Substate,
+    TransientStorage,
+};
+use bytes::Bytes;
+use ethrex_core::{Address, H256, U256};
+
+/// A change made to the state, along with what's needed to undo it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalEntry {
+    /// The account was loaded into the cache (`previous` is `None`), replaced or removed from it
+    AccountChanged {
+        address: Address,
+        previous: Option<Account>,
+    },
+    BalanceChanged {
+        address: Address,
+        previous: U256,
+    },
+    NonceChanged {
+        address: Address,
+        previous: u64,
+    },
+    CodeChanged {
+        address: Address,
+        previous: Bytes,
+        previous_code_hash: H256,
+    },
+    /// The storage slot was loaded into the cache (`previous` is `None`) or written
+    StorageChanged {
+        address: Address,
+        key: H256,
+        previous: Option<StorageSlot>,
+    },
+    TransientStorageChanged {
+        address: Address,
+        key: U256,
+        previous: Option<U256>,
+    },
+    AccountWarmed {
+        address: Address,
+    },
+    StorageSlotWarmed {
+        address: Address,
+        key: H256,
+    },
+    AccountCreated {
+        address: Address,
+    },
+    AccountDestroyed {
+        address: Address,
+    },
+}
+
+/// Changes made to the state while executing a transaction. A checkpoint is opened before running
+/// each sub-context, so that its changes can be undone if it reverts without having to back up
+/// the whole state.
+#[derive(Debug, Default)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+    /// Position in `entries` of the first change made after each open checkpoint
+    checkpoints: Vec<usize>,
+}
+
+impl Journal {
+    pub fn checkpoint(&mut self) {
+        self.checkpoints.push(self.entries.len());
+    }
+
+    /// Must be called along with every change to the state. Changes made when there's no open
+    /// checkpoint aren't journaled, as there's nothing to revert to.
+    pub fn record(&mut self, entry: JournalEntry) {
+        if !self.checkpoints.is_empty() {
+            self.entries.push(entry);
+        }
+    }
+
+    /// Keeps the changes made since the last checkpoint, which can still be undone by reverting
+    /// an outer checkpoint
+    pub fn commit(&mut self) {
+        self.checkpoints.pop();
+        if self.checkpoints.is_empty() {
+            self.entries.clear();
+        }
+    }
+
+    /// Undoes the changes made since the last checkpoint, from the most recent to the oldest
+    pub fn revert(
+        &mut self,
+        cache: &mut CacheDB,
+        substate: &mut Substate,
+        transient_storage: &mut TransientStorage,
+    ) {
+        let Some(start) = self.checkpoints.pop() else {
+            return;
+        };
+        for entry in self.entries.drain(start..).rev() {
+            entry.undo(cache, substate, transient_storage);
+        }
+    }
+}
+
+impl JournalEntry {
+    fn undo(
+        self,
+        cache: &mut CacheDB,
+        substate: &mut Substate,
+        transient_storage: &mut TransientStorage,
+    ) {
+        match self {
+            JournalEntry::AccountChanged { address, previous } => match previous {
+                Some(account) => {
+                    cache::insert_account(cache, address, account);
+                }
+                None => {
+                    cache::remove_account(cache, &address);
+                }
+            },
+            JournalEntry::BalanceChanged { address, previous } => {
+                if let Some(account) = cache::get_account_mut(cache, &address) {
+                    account.info.balance = previous;
+                }
+            }
+            JournalEntry::NonceChanged { address, previous } => {
+                if let Some(account) = cache::get_account_mut(cache, &address) {
+                    account.info.nonce = previous;
+                }
+            }
+            JournalEntry::CodeChanged {
+                address,
+                previous,
+                previous_code_hash,
+            } => {
+                if let Some(account) = cache::get_account_mut(cache, &address) {
+                    account.info.bytecode = previous;
+                    account.info.code_hash = previous_code_hash;
+                }
+            }
+            JournalEntry::StorageChanged {
+                address,
+                key,
+                previous,
+            } => {
+                if let Some(account) = cache::get_account_mut(cache, &address) {
+                    match previous {
+                        Some(slot) => account.storage.insert(key, slot),
+                        None => account.storage.remove(&key),
+                    };
+                }
+            }
+            JournalEntry::TransientStorageChanged {
+                address,
+                key,
+                previous,
+            } => {
+                match previous {
+                    Some(value) => transient_storage.insert((address, key), value),
+                    None => transient_storage.remove(&(address, key)),
+                };
+            }
+            JournalEntry::AccountWarmed { address } => {
+                substate.touched_accounts.remove(&address);
+            }
+            JournalEntry::StorageSlotWarmed { address, key } => {
+                if let Some(slots) = substate.touched_storage_slots.get_mut(&address) {
+                    slots.remove(&key);
+                }
+            }
+            JournalEntry::AccountCreated { address } => {
+                substate.created_accounts.remove(&address);
+            }
+            JournalEntry::AccountDestroyed { address } => {
+                substate.selfdestrutct_set.remove(&address);
+            }
+        }
+    }
+}
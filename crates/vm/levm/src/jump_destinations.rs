@@ -0,0 +1,53 @@
+use crate::{eof, opcodes::Opcode};
+use ethrex_core::H256;
+use std::{collections::HashMap, sync::Arc};
+
+/// Analysed jump destinations of the accounts' code executed by the VM, indexed by code hash, so
+/// that call frames running the same code share a single analysis. It can be kept across
+/// transactions, since the code behind a hash never changes
+pub type JumpDestinationsCache = HashMap<H256, Arc<JumpDestinations>>;
+
+const BITS_PER_WORD: usize = 64;
+
+/// Bitmap with the positions of a bytecode that a JUMP or JUMPI can jump to: the JUMPDEST opcodes
+/// that are not part of the data of a PUSH
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JumpDestinations {
+    bitmap: Vec<u64>,
+}
+
+impl JumpDestinations {
+    pub fn analyze(code: &[u8]) -> Self {
+        // EOF code only has relative jumps, and legacy code starting with 0xEF halts right away
+        if eof::is_eof(code) {
+            return Self::default();
+        }
+
+        let mut bitmap = vec![0; code.len().div_ceil(BITS_PER_WORD)];
+        let jumpdest = u8::from(Opcode::JUMPDEST);
+        let push1 = u8::from(Opcode::PUSH1);
+        let push32 = u8::from(Opcode::PUSH32);
+        let mut pc: usize = 0;
+
+        while let Some(&opcode) = code.get(pc) {
+            if opcode == jumpdest {
+                if let Some(word) = bitmap.get_mut(pc / BITS_PER_WORD) {
+                    *word |= 1 << (pc % BITS_PER_WORD);
+                }
+            } else if (push1..=push32).contains(&opcode) {
+                // Skip the pushed bytes, PUSH1 pushes one byte and PUSH32 thirty two
+                let push_size = usize::from(opcode.wrapping_sub(push1)).saturating_add(1);
+                pc = pc.saturating_add(push_size);
+            }
+            pc = pc.saturating_add(1);
+        }
+
+        Self { bitmap }
+    }
+
+    pub fn contains(&self, pc: usize) -> bool {
+        self.bitmap
+            .get(pc / BITS_PER_WORD)
+            .is_some_and(|word| word & (1 << (pc % BITS_PER_WORD)) != 0)
+    }
+}
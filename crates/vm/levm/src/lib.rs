@@ -3,14 +3,22 @@ pub mod call_frame;
 pub mod constants;
 pub mod db;
 pub mod environment;
+pub mod eof;
 pub mod errors;
+pub mod fork;
 pub mod gas_cost;
+pub mod journal;
+pub mod jump_destinations;
 pub mod memory;
 pub mod opcode_handlers;
+pub mod opcode_table;
 pub mod opcodes;
 pub mod operations;
 pub mod precompiles;
+pub mod profiler;
+pub mod tracer;
 pub mod utils;
 pub mod vm;
 pub use account::*;
 pub use environment::*;
+pub use fork::Fork;
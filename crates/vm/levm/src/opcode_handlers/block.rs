@@ -2,6 +2,7 @@ use crate::{
     call_frame::CallFrame,
     constants::LAST_AVAILABLE_BLOCK_LIMIT,
     errors::{InternalError, OpcodeSuccess, VMError},
+    fork::Fork,
     gas_cost,
     vm::{address_to_word, VM},
 };
@@ -95,6 +96,12 @@ impl VM {
     ) -> Result<OpcodeSuccess, VMError> {
         self.increase_consumed_gas(current_call_frame, gas_cost::PREVRANDAO)?;
 
+        // Before the merge this opcode was DIFFICULTY
+        if self.env.fork < Fork::Paris {
+            current_call_frame.stack.push(self.env.difficulty)?;
+            return Ok(OpcodeSuccess::Continue);
+        }
+
         let randao = self.env.prev_randao.unwrap_or_default(); // Assuming block_env has been integrated
         current_call_frame
             .stack
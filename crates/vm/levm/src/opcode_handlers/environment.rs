@@ -35,7 +35,10 @@ impl VM {
 
         let (account_info, address_was_cold) = self.access_account(address);
 
-        self.increase_consumed_gas(current_call_frame, gas_cost::balance(address_was_cold)?)?;
+        self.increase_consumed_gas(
+            current_call_frame,
+            gas_cost::balance(address_was_cold, self.env.fork)?,
+        )?;
 
         current_call_frame.stack.push(account_info.balance)?;
 
@@ -277,11 +280,13 @@ impl VM {
 
         let (account_info, address_was_cold) = self.access_account(address);
 
-        self.increase_consumed_gas(current_call_frame, gas_cost::extcodesize(address_was_cold)?)?;
+        self.increase_consumed_gas(
+            current_call_frame,
+            gas_cost::extcodesize(address_was_cold, self.env.fork)?,
+        )?;
 
-        current_call_frame
-            .stack
-            .push(account_info.bytecode.len().into())?;
+        let bytecode = self.code_seen_by_legacy_code(account_info.bytecode);
+        current_call_frame.stack.push(bytecode.len().into())?;
 
         Ok(OpcodeSuccess::Continue)
     }
@@ -311,6 +316,7 @@ impl VM {
                 new_memory_size,
                 current_call_frame.memory.len(),
                 address_was_cold,
+                self.env.fork,
             )?,
         )?;
 
@@ -318,18 +324,13 @@ impl VM {
             return Ok(OpcodeSuccess::Continue);
         }
 
+        let bytecode = self.code_seen_by_legacy_code(account_info.bytecode);
         let mut data = vec![0u8; size];
-        if offset < account_info.bytecode.len().into() {
+        if offset < bytecode.len().into() {
             let offset: usize = offset
                 .try_into()
                 .map_err(|_| VMError::Internal(InternalError::ConversionError))?;
-            for (i, byte) in account_info
-                .bytecode
-                .iter()
-                .skip(offset)
-                .take(size)
-                .enumerate()
-            {
+            for (i, byte) in bytecode.iter().skip(offset).take(size).enumerate() {
                 if let Some(data_byte) = data.get_mut(i) {
                     *data_byte = *byte;
                 }
@@ -360,12 +361,15 @@ impl VM {
         &mut self,
         current_call_frame: &mut CallFrame,
     ) -> Result<OpcodeSuccess, VMError> {
+        let is_eof = current_call_frame.eof_container.is_some();
         let dest_offset = current_call_frame.stack.pop()?;
-        let returndata_offset: usize = current_call_frame
-            .stack
-            .pop()?
-            .try_into()
-            .map_err(|_| VMError::VeryLargeNumber)?;
+        let returndata_offset = current_call_frame.stack.pop()?;
+        // EOF code reads zeros out of bounds, so any offset is valid [EIP-7069]
+        let returndata_offset: usize = match returndata_offset.try_into() {
+            Ok(offset) => offset,
+            Err(_) if is_eof => usize::MAX,
+            Err(_) => return Err(VMError::VeryLargeNumber),
+        };
         let size: usize = current_call_frame
             .stack
             .pop()?
@@ -385,12 +389,14 @@ impl VM {
 
         let sub_return_data_len = current_call_frame.sub_return_data.len();
 
-        let copy_limit = returndata_offset
-            .checked_add(size)
-            .ok_or(VMError::VeryLargeNumber)?;
+        if !is_eof {
+            let copy_limit = returndata_offset
+                .checked_add(size)
+                .ok_or(VMError::VeryLargeNumber)?;
 
-        if copy_limit > sub_return_data_len {
-            return Err(VMError::OutOfBounds);
+            if copy_limit > sub_return_data_len {
+                return Err(VMError::OutOfBounds);
+            }
         }
 
         // Actually we don't need to fill with zeros for out of bounds bytes, this works but is overkill because of the previous validations.
@@ -422,7 +428,10 @@ impl VM {
 
         let (account_info, address_was_cold) = self.access_account(address);
 
-        self.increase_consumed_gas(current_call_frame, gas_cost::extcodehash(address_was_cold)?)?;
+        self.increase_consumed_gas(
+            current_call_frame,
+            gas_cost::extcodehash(address_was_cold, self.env.fork)?,
+        )?;
 
         // An account is considered empty when it has no code and zero nonce and zero balance. [EIP-161]
         if account_info.is_empty() {
@@ -430,7 +439,8 @@ impl VM {
             return Ok(OpcodeSuccess::Continue);
         }
 
-        let hash = U256::from_big_endian(keccak(account_info.bytecode).as_fixed_bytes());
+        let bytecode = self.code_seen_by_legacy_code(account_info.bytecode);
+        let hash = U256::from_big_endian(keccak(bytecode).as_fixed_bytes());
         current_call_frame.stack.push(hash)?;
         Ok(OpcodeSuccess::Continue)
     }
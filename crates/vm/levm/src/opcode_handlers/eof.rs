@@ -0,0 +1,666 @@
+use crate::{
+    call_frame::{CallFrame, ReturnStackItem},
+    constants::{
+        EXTCALL_FAILURE, EXTCALL_REVERT, EXTCALL_SUCCESS, RETURN_STACK_LIMIT, STACK_LIMIT,
+        WORD_SIZE,
+    },
+    eof::{self, EofContainer},
+    errors::{InternalError, OpcodeSuccess, OutOfGasError, ResultReason, TxResult, VMError},
+    gas_cost,
+    memory::{self, calculate_memory_size},
+    vm::{word_to_address, VM},
+};
+use bytes::Bytes;
+use ethrex_core::{Address, U256};
+
+// EVM Object Format Operations (19)
+// Opcodes: DATALOAD, DATALOADN, DATASIZE, DATACOPY, RJUMP, RJUMPI, RJUMPV, CALLF, RETF, JUMPF, DUPN, SWAPN, EXCHANGE, EOFCREATE, RETURNCONTRACT, RETURNDATALOAD, EXTCALL, EXTDELEGATECALL, EXTSTATICCALL
+
+impl VM {
+    // DATALOAD operation
+    pub fn op_dataload(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::DATALOAD)?;
+
+        let offset = current_call_frame.stack.pop()?;
+        let word = load_word(&eof_container(current_call_frame)?.data, offset);
+        current_call_frame.stack.push(word)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // DATALOADN operation
+    pub fn op_dataloadn(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::DATALOADN)?;
+
+        let offset = read_immediate_u16(current_call_frame, 0)?;
+        let word = load_word(&eof_container(current_call_frame)?.data, offset.into());
+        current_call_frame.stack.push(word)?;
+
+        current_call_frame.increment_pc_by(2)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // DATASIZE operation
+    pub fn op_datasize(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::DATASIZE)?;
+
+        let data_size = eof_container(current_call_frame)?.data.len();
+        current_call_frame.stack.push(data_size.into())?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // DATACOPY operation
+    pub fn op_datacopy(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let dest_offset = current_call_frame.stack.pop()?;
+        let offset = current_call_frame.stack.pop()?;
+        let size: usize = current_call_frame
+            .stack
+            .pop()?
+            .try_into()
+            .map_err(|_| VMError::VeryLargeNumber)?;
+
+        let new_memory_size = calculate_memory_size(dest_offset, size)?;
+
+        self.increase_consumed_gas(
+            current_call_frame,
+            gas_cost::datacopy(new_memory_size, current_call_frame.memory.len(), size)?,
+        )?;
+
+        if size == 0 {
+            return Ok(OpcodeSuccess::Continue);
+        }
+
+        let data = copy_padded(&eof_container(current_call_frame)?.data, offset, size);
+        memory::try_store_data(&mut current_call_frame.memory, dest_offset, &data)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // RJUMP operation
+    pub fn op_rjump(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::RJUMP)?;
+
+        let offset = read_immediate_i16(current_call_frame, 0)?;
+        relative_jump(current_call_frame, 2, offset)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // RJUMPI operation
+    pub fn op_rjumpi(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::RJUMPI)?;
+
+        let condition = current_call_frame.stack.pop()?;
+        let offset = if condition.is_zero() {
+            0
+        } else {
+            read_immediate_i16(current_call_frame, 0)?
+        };
+        relative_jump(current_call_frame, 2, offset)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // RJUMPV operation
+    pub fn op_rjumpv(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::RJUMPV)?;
+
+        let case = current_call_frame.stack.pop()?;
+        let max_index = read_immediate_u8(current_call_frame, 0)?;
+        let immediate_size = usize::from(max_index)
+            .checked_add(1)
+            .and_then(|cases| cases.checked_mul(2))
+            .and_then(|size| size.checked_add(1))
+            .ok_or(InternalError::ArithmeticOperationOverflow)?;
+
+        // Cases out of the table fall through to the next instruction
+        let offset = if case <= U256::from(max_index) {
+            let table_position = usize::try_from(case)
+                .map_err(|_| VMError::VeryLargeNumber)?
+                .checked_mul(2)
+                .and_then(|position| position.checked_add(1))
+                .ok_or(InternalError::ArithmeticOperationOverflow)?;
+            read_immediate_i16(current_call_frame, table_position)?
+        } else {
+            0
+        };
+        relative_jump(current_call_frame, immediate_size, offset)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // CALLF operation
+    pub fn op_callf(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::CALLF)?;
+
+        let target = usize::from(read_immediate_u16(current_call_frame, 0)?);
+        check_code_section_stack(current_call_frame, target)?;
+        if current_call_frame.return_stack.len() >= RETURN_STACK_LIMIT {
+            return Err(VMError::ReturnStackOverflow);
+        }
+
+        let return_pc = current_call_frame
+            .pc
+            .checked_add(3)
+            .ok_or(InternalError::PCOverflowed)?;
+        current_call_frame.return_stack.push(ReturnStackItem {
+            code_section: current_call_frame.code_section,
+            pc: return_pc,
+        });
+        current_call_frame.jump_to_code_section(target, 0)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // RETF operation
+    pub fn op_retf(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::RETF)?;
+
+        // Validation makes sure RETF is only reached from sections entered with CALLF
+        let return_to = current_call_frame
+            .return_stack
+            .pop()
+            .ok_or(VMError::InvalidBytecode)?;
+        current_call_frame.jump_to_code_section(return_to.code_section, return_to.pc)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // JUMPF operation
+    pub fn op_jumpf(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::JUMPF)?;
+
+        let target = usize::from(read_immediate_u16(current_call_frame, 0)?);
+        check_code_section_stack(current_call_frame, target)?;
+        current_call_frame.jump_to_code_section(target, 0)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // DUPN operation
+    pub fn op_dupn(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let immediate = read_immediate_u8(current_call_frame, 0)?;
+        let depth = usize::from(immediate)
+            .checked_add(1)
+            .ok_or(InternalError::ArithmeticOperationOverflow)?;
+        self.op_dup(current_call_frame, depth)?;
+
+        current_call_frame.increment_pc_by(1)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // SWAPN operation
+    pub fn op_swapn(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let immediate = read_immediate_u8(current_call_frame, 0)?;
+        let depth = usize::from(immediate)
+            .checked_add(1)
+            .ok_or(InternalError::ArithmeticOperationOverflow)?;
+        self.op_swap(current_call_frame, depth)?;
+
+        current_call_frame.increment_pc_by(1)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // EXCHANGE operation
+    pub fn op_exchange(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::EXCHANGE)?;
+
+        let immediate = read_immediate_u8(current_call_frame, 0)?;
+        let (n, m) = eof::exchange_positions(immediate.into());
+        let stack_top_index = current_call_frame
+            .stack
+            .len()
+            .checked_sub(1)
+            .ok_or(VMError::StackUnderflow)?;
+        let first_index = stack_top_index
+            .checked_sub(n.into())
+            .ok_or(VMError::StackUnderflow)?;
+        let second_index = first_index
+            .checked_sub(m.into())
+            .ok_or(VMError::StackUnderflow)?;
+        current_call_frame.stack.swap(first_index, second_index)?;
+
+        current_call_frame.increment_pc_by(1)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // EOFCREATE operation
+    pub fn op_eofcreate(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let value_in_wei_to_send = current_call_frame.stack.pop()?;
+        let salt = current_call_frame.stack.pop()?;
+        let input_offset = current_call_frame.stack.pop()?;
+        let input_size: usize = current_call_frame
+            .stack
+            .pop()?
+            .try_into()
+            .map_err(|_| VMError::VeryLargeNumber)?;
+
+        let index = usize::from(read_immediate_u8(current_call_frame, 0)?);
+        let initcontainer = eof_container(current_call_frame)?
+            .container_sections
+            .get(index)
+            .ok_or(VMError::InvalidBytecode)?
+            .clone();
+
+        let new_memory_size = calculate_memory_size(input_offset, input_size)?;
+        self.increase_consumed_gas(
+            current_call_frame,
+            gas_cost::eofcreate(
+                new_memory_size,
+                current_call_frame.memory.len(),
+                initcontainer.len(),
+            )?,
+        )?;
+
+        let input = Bytes::from(
+            memory::load_range(&mut current_call_frame.memory, input_offset, input_size)?.to_vec(),
+        );
+        let container = EofContainer::decode(&initcontainer)?;
+
+        current_call_frame.increment_pc_by(1)?;
+
+        self.generic_create(
+            value_in_wei_to_send,
+            initcontainer,
+            input,
+            Some(salt),
+            Some(container),
+            current_call_frame,
+        )
+    }
+
+    // RETURNCONTRACT operation
+    pub fn op_returncontract(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let aux_data_offset = current_call_frame.stack.pop()?;
+        let aux_data_size: usize = current_call_frame
+            .stack
+            .pop()?
+            .try_into()
+            .map_err(|_| VMError::VeryLargeNumber)?;
+
+        let new_memory_size = calculate_memory_size(aux_data_offset, aux_data_size)?;
+        let memory_expansion_cost: u64 =
+            memory::expansion_cost(new_memory_size, current_call_frame.memory.len())?
+                .try_into()
+                .map_err(|_| VMError::Internal(InternalError::ConversionError))?;
+        self.increase_consumed_gas(current_call_frame, memory_expansion_cost)?;
+
+        let index = usize::from(read_immediate_u8(current_call_frame, 0)?);
+        let deploy_container = EofContainer::decode_deploy_container(
+            eof_container(current_call_frame)?
+                .container_sections
+                .get(index)
+                .ok_or(VMError::InvalidBytecode)?,
+        )?;
+        let aux_data = memory::load_range(
+            &mut current_call_frame.memory,
+            aux_data_offset,
+            aux_data_size,
+        )?;
+
+        current_call_frame.output = deploy_container.with_aux_data(aux_data)?;
+
+        Ok(OpcodeSuccess::Result(ResultReason::Return))
+    }
+
+    // RETURNDATALOAD operation
+    pub fn op_returndataload(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        self.increase_consumed_gas(current_call_frame, gas_cost::RETURNDATALOAD)?;
+
+        let offset = current_call_frame.stack.pop()?;
+        let word = load_word(&current_call_frame.sub_return_data, offset);
+        current_call_frame.stack.push(word)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+
+    // EXTCALL operation
+    pub fn op_extcall(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let callee = pop_address(current_call_frame)?;
+        let args_offset = current_call_frame.stack.pop()?;
+        let args_size = pop_size(current_call_frame)?;
+        let value = current_call_frame.stack.pop()?;
+
+        if current_call_frame.is_static && !value.is_zero() {
+            return Err(VMError::OpcodeNotAllowedInStaticContext);
+        }
+
+        let msg_sender = current_call_frame.to;
+        let is_static = current_call_frame.is_static;
+        self.generic_extcall(
+            current_call_frame,
+            value,
+            msg_sender,
+            callee,
+            callee,
+            !value.is_zero(),
+            is_static,
+            args_offset,
+            args_size,
+        )
+    }
+
+    // EXTDELEGATECALL operation
+    pub fn op_extdelegatecall(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let code_address = pop_address(current_call_frame)?;
+        let args_offset = current_call_frame.stack.pop()?;
+        let args_size = pop_size(current_call_frame)?;
+
+        let msg_sender = current_call_frame.msg_sender;
+        let to = current_call_frame.to;
+        let value = current_call_frame.msg_value;
+        let is_static = current_call_frame.is_static;
+        self.generic_extcall(
+            current_call_frame,
+            value,
+            msg_sender,
+            to,
+            code_address,
+            false,
+            is_static,
+            args_offset,
+            args_size,
+        )
+    }
+
+    // EXTSTATICCALL operation
+    pub fn op_extstaticcall(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let callee = pop_address(current_call_frame)?;
+        let args_offset = current_call_frame.stack.pop()?;
+        let args_size = pop_size(current_call_frame)?;
+
+        let msg_sender = current_call_frame.to;
+        self.generic_extcall(
+            current_call_frame,
+            U256::zero(),
+            msg_sender,
+            callee,
+            callee,
+            false,
+            true,
+            args_offset,
+            args_size,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    /// Common behavior for EXTCALL, EXTDELEGATECALL and EXTSTATICCALL. Unlike the legacy calls,
+    /// the callee gets all but max(1/64, 5000) of the remaining gas and the output is only
+    /// available through the return data [EIP-7069]
+    pub fn generic_extcall(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+        value: U256,
+        msg_sender: Address,
+        to: Address,
+        code_address: Address,
+        should_transfer_value: bool,
+        is_static: bool,
+        args_offset: U256,
+        args_size: usize,
+    ) -> Result<OpcodeSuccess, VMError> {
+        let is_delegatecall = code_address != to;
+
+        let new_memory_size = calculate_memory_size(args_offset, args_size)?;
+        let (account_info, address_was_cold) = self.access_account(code_address);
+        self.increase_consumed_gas(
+            current_call_frame,
+            gas_cost::extcall(
+                new_memory_size,
+                current_call_frame.memory.len(),
+                address_was_cold,
+                account_info.is_empty(),
+                if should_transfer_value {
+                    value
+                } else {
+                    U256::zero()
+                },
+            )?,
+        )?;
+
+        let calldata = Bytes::from(
+            memory::load_range(&mut current_call_frame.memory, args_offset, args_size)?.to_vec(),
+        );
+
+        // Clear callframe subreturn data
+        current_call_frame.sub_return_data = Bytes::new();
+
+        // Calls that can't be executed push 1 without consuming the callee gas. EXTDELEGATECALL
+        // can't run legacy code
+        let new_depth = current_call_frame
+            .depth
+            .checked_add(1)
+            .ok_or(InternalError::ArithmeticOperationOverflow)?;
+        let sender_balance = self.access_account(msg_sender).0.balance;
+        let callee_gas = gas_cost::extcall_callee_gas(current_call_frame)?;
+        let can_execute = new_depth <= 1024
+            && !(should_transfer_value && sender_balance < value)
+            && (!is_delegatecall || eof::is_eof(&account_info.bytecode));
+        let callee_gas = match callee_gas {
+            Some(callee_gas) if can_execute => callee_gas,
+            _ => {
+                current_call_frame.stack.push(EXTCALL_REVERT)?;
+                return Ok(OpcodeSuccess::Continue);
+            }
+        };
+
+        let mut new_call_frame = CallFrame::new(
+            msg_sender,
+            to,
+            code_address,
+            account_info.bytecode,
+            value,
+            calldata,
+            is_static,
+            callee_gas,
+            0,
+            new_depth,
+            false,
+        );
+
+        if should_transfer_value {
+            self.decrease_account_balance(msg_sender, value)?;
+            self.increase_account_balance(to, value)?;
+        }
+
+        let tx_report = self.execute(&mut new_call_frame)?;
+
+        current_call_frame.gas_used = current_call_frame
+            .gas_used
+            .checked_add(tx_report.gas_used)
+            .ok_or(VMError::OutOfGas(OutOfGasError::ConsumedGasOverflow))?;
+        current_call_frame.logs.extend(tx_report.logs);
+        current_call_frame.sub_return_data = tx_report.output;
+
+        let result = match tx_report.result {
+            TxResult::Success => EXTCALL_SUCCESS,
+            TxResult::Revert(error) => {
+                if should_transfer_value {
+                    self.decrease_account_balance(to, value)?;
+                    self.increase_account_balance(msg_sender, value)?;
+                }
+                if error == VMError::RevertOpcode {
+                    EXTCALL_REVERT
+                } else {
+                    EXTCALL_FAILURE
+                }
+            }
+        };
+        current_call_frame.stack.push(result)?;
+
+        Ok(OpcodeSuccess::Continue)
+    }
+}
+
+fn eof_container(current_call_frame: &CallFrame) -> Result<&EofContainer, VMError> {
+    current_call_frame
+        .eof_container
+        .as_ref()
+        .ok_or(VMError::InvalidOpcode)
+}
+
+/// Reads the immediate bytes of the current instruction, starting `offset` bytes after the opcode
+fn read_immediate<const N: usize>(
+    current_call_frame: &CallFrame,
+    offset: usize,
+) -> Result<[u8; N], VMError> {
+    let start = current_call_frame
+        .pc
+        .checked_add(1)
+        .and_then(|start| start.checked_add(offset))
+        .ok_or(InternalError::PCOverflowed)?;
+    let end = start.checked_add(N).ok_or(InternalError::PCOverflowed)?;
+    current_call_frame
+        .bytecode
+        .get(start..end)
+        .and_then(|immediate| immediate.try_into().ok())
+        .ok_or(VMError::InvalidBytecode)
+}
+
+fn read_immediate_u8(current_call_frame: &CallFrame, offset: usize) -> Result<u8, VMError> {
+    read_immediate::<1>(current_call_frame, offset).map(u8::from_be_bytes)
+}
+
+fn read_immediate_u16(current_call_frame: &CallFrame, offset: usize) -> Result<u16, VMError> {
+    read_immediate::<2>(current_call_frame, offset).map(u16::from_be_bytes)
+}
+
+fn read_immediate_i16(current_call_frame: &CallFrame, offset: usize) -> Result<i16, VMError> {
+    read_immediate::<2>(current_call_frame, offset).map(i16::from_be_bytes)
+}
+
+/// Moves the PC `offset` bytes from the end of the current instruction, relative jumps are
+/// validated before execution [EIP-4200]
+fn relative_jump(
+    current_call_frame: &mut CallFrame,
+    immediate_size: usize,
+    offset: i16,
+) -> Result<(), VMError> {
+    let next_pc = current_call_frame
+        .pc
+        .checked_add(1)
+        .and_then(|pc| pc.checked_add(immediate_size))
+        .ok_or(InternalError::PCOverflowed)?;
+    current_call_frame.pc = isize::try_from(next_pc)
+        .ok()
+        .and_then(|pc| pc.checked_add(isize::from(offset)))
+        .and_then(|pc| usize::try_from(pc).ok())
+        .ok_or(InternalError::PCOutOfBounds)?;
+    Ok(())
+}
+
+/// Checks the stack has room for the max stack height of the code section about to be executed
+fn check_code_section_stack(
+    current_call_frame: &CallFrame,
+    code_section: usize,
+) -> Result<(), VMError> {
+    let section_type = eof_container(current_call_frame)?
+        .types
+        .get(code_section)
+        .ok_or(VMError::InvalidBytecode)?;
+    let max_stack_height = current_call_frame
+        .stack
+        .len()
+        .checked_add(section_type.max_stack_height.into())
+        .and_then(|height| height.checked_sub(section_type.inputs.into()))
+        .ok_or(VMError::StackUnderflow)?;
+    if max_stack_height > STACK_LIMIT {
+        return Err(VMError::StackOverflow);
+    }
+    Ok(())
+}
+
+/// Reads a word from `data`, zero padded if it goes out of bounds
+fn load_word(data: &[u8], offset: U256) -> U256 {
+    U256::from_big_endian(&copy_padded(data, offset, WORD_SIZE))
+}
+
+/// Copies `size` bytes of `data` from `offset`, zero padded if it goes out of bounds
+fn copy_padded(data: &[u8], offset: U256, size: usize) -> Vec<u8> {
+    let mut copied = vec![0u8; size];
+    if let Ok(offset) = usize::try_from(offset) {
+        for (copied_byte, byte) in copied.iter_mut().zip(data.iter().skip(offset)) {
+            *copied_byte = *byte;
+        }
+    }
+    copied
+}
+
+/// Pops an address, which has to fit in 20 bytes [EIP-7069]
+fn pop_address(current_call_frame: &mut CallFrame) -> Result<Address, VMError> {
+    let word = current_call_frame.stack.pop()?;
+    if word.bits() > 160 {
+        return Err(VMError::InvalidAddress);
+    }
+    Ok(word_to_address(word))
+}
+
+fn pop_size(current_call_frame: &mut CallFrame) -> Result<usize, VMError> {
+    current_call_frame
+        .stack
+        .pop()?
+        .try_into()
+        .map_err(|_| VMError::VeryLargeNumber)
+}
@@ -49,7 +49,8 @@ impl VM {
         )?;
 
         let log = Log {
-            address: current_call_frame.msg_sender, // Should change the addr if we are on a Call/Create transaction (Call should be the contract we are calling, Create should be the original caller)
+            // The account whose code is running, not the one whose code was called with DELEGATECALL
+            address: current_call_frame.to,
             topics,
             data: Bytes::from(
                 memory::load_range(&mut current_call_frame.memory, offset, size)?.to_vec(),
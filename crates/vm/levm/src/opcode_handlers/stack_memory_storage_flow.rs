@@ -3,10 +3,12 @@ use crate::{
     constants::{WORD_SIZE, WORD_SIZE_IN_BYTES_USIZE},
     errors::{OpcodeSuccess, OutOfGasError, VMError},
     gas_cost,
+    jump_destinations::JumpDestinations,
     memory::{self, calculate_memory_size},
     vm::VM,
 };
 use ethrex_core::{H256, U256};
+use std::sync::Arc;
 
 // Stack, Memory, Storage and Flow Operations (15)
 // Opcodes: POP, MLOAD, MSTORE, MSTORE8, SLOAD, SSTORE, JUMP, JUMPI, PC, MSIZE, GAS, JUMPDEST, TLOAD, TSTORE, MCOPY
@@ -51,9 +53,7 @@ impl VM {
 
         let key = current_call_frame.stack.pop()?;
         let value = current_call_frame.stack.pop()?;
-        self.env
-            .transient_storage
-            .insert((current_call_frame.msg_sender, key), value);
+        self.update_transient_storage(current_call_frame.msg_sender, key, value);
 
         Ok(OpcodeSuccess::Continue)
     }
@@ -145,7 +145,10 @@ impl VM {
         let (storage_slot, storage_slot_was_cold) =
             self.access_storage_slot(address, storage_slot_key)?;
 
-        self.increase_consumed_gas(current_call_frame, gas_cost::sload(storage_slot_was_cold)?)?;
+        self.increase_consumed_gas(
+            current_call_frame,
+            gas_cost::sload(storage_slot_was_cold, self.env.fork)?,
+        )?;
 
         current_call_frame.stack.push(storage_slot.current_value)?;
         Ok(OpcodeSuccess::Continue)
@@ -179,42 +182,21 @@ impl VM {
                 new_storage_slot_value,
                 storage_slot_was_cold,
                 current_call_frame,
+                self.env.fork,
             )?,
         )?;
 
         // Gas Refunds
         // Sync gas refund with global env, ensuring consistency accross contexts.
-        let mut gas_refunds = self.env.refunded_gas;
-
-        if new_storage_slot_value != storage_slot.current_value {
-            if storage_slot.current_value == storage_slot.original_value {
-                if !storage_slot.original_value.is_zero() && new_storage_slot_value.is_zero() {
-                    gas_refunds = gas_refunds
-                        .checked_add(4800)
-                        .ok_or(VMError::GasRefundsOverflow)?;
-                }
-            } else if !storage_slot.original_value.is_zero() {
-                if storage_slot.current_value.is_zero() {
-                    gas_refunds = gas_refunds
-                        .checked_sub(4800)
-                        .ok_or(VMError::GasRefundsUnderflow)?;
-                } else if new_storage_slot_value.is_zero() {
-                    gas_refunds = gas_refunds
-                        .checked_add(4800)
-                        .ok_or(VMError::GasRefundsOverflow)?;
-                }
-            } else if new_storage_slot_value == storage_slot.original_value {
-                if storage_slot.original_value.is_zero() {
-                    gas_refunds = gas_refunds
-                        .checked_add(19900)
-                        .ok_or(VMError::GasRefundsOverflow)?;
-                } else {
-                    gas_refunds = gas_refunds
-                        .checked_add(2800)
-                        .ok_or(VMError::GasRefundsOverflow)?;
-                }
-            }
-        };
+        let (refund, refund_removal) =
+            gas_cost::sstore_refund(&storage_slot, new_storage_slot_value, self.env.fork)?;
+        let gas_refunds = self
+            .env
+            .refunded_gas
+            .checked_add(refund)
+            .ok_or(VMError::GasRefundsOverflow)?
+            .checked_sub(refund_removal)
+            .ok_or(VMError::GasRefundsUnderflow)?;
 
         self.env.refunded_gas = gas_refunds;
 
@@ -292,7 +274,7 @@ impl VM {
         self.increase_consumed_gas(current_call_frame, gas_cost::JUMP)?;
 
         let jump_address = current_call_frame.stack.pop()?;
-        Self::jump(current_call_frame, jump_address)?;
+        self.jump(current_call_frame, jump_address)?;
 
         Ok(OpcodeSuccess::Continue)
     }
@@ -300,9 +282,22 @@ impl VM {
     /// JUMP* family (`JUMP` and `JUMP` ATTOW [DEC 2024]) helper
     /// function.
     /// This function returns whether the `jump_address` is a valid JUMPDEST
-    /// for the specified `call_frame` or not.
-    fn is_valid_jump_addr(call_frame: &CallFrame, jump_address: usize) -> bool {
-        call_frame.valid_jump_destinations.contains(&jump_address)
+    /// for the specified `call_frame` or not. The jump destinations of the
+    /// code are analysed on the first jump and, for the code of an account,
+    /// shared by every call frame running it.
+    fn is_valid_jump_addr(&mut self, call_frame: &mut CallFrame, jump_address: usize) -> bool {
+        let jump_destinations = call_frame.valid_jump_destinations.get_or_insert_with(|| {
+            let analyze = || Arc::new(JumpDestinations::analyze(&call_frame.bytecode));
+            match call_frame.code_hash {
+                Some(code_hash) => self
+                    .jump_destinations
+                    .entry(code_hash)
+                    .or_insert_with(analyze)
+                    .clone(),
+                None => analyze(),
+            }
+        });
+        jump_destinations.contains(jump_address)
     }
 
     /// JUMP* family (`JUMP` and `JUMP` ATTOW [DEC 2024]) helper
@@ -310,12 +305,12 @@ impl VM {
     /// This function will change the PC for the specified call frame
     /// to be equal to the specified address. If the address is not a
     /// valid JUMPDEST, it will return an error
-    pub fn jump(call_frame: &mut CallFrame, jump_address: U256) -> Result<(), VMError> {
+    pub fn jump(&mut self, call_frame: &mut CallFrame, jump_address: U256) -> Result<(), VMError> {
         let jump_address_usize = jump_address
             .try_into()
             .map_err(|_err| VMError::VeryLargeNumber)?;
 
-        match Self::is_valid_jump_addr(call_frame, jump_address_usize) {
+        match self.is_valid_jump_addr(call_frame, jump_address_usize) {
             true => {
                 call_frame.pc = jump_address_usize;
                 Ok(())
@@ -335,7 +330,7 @@ impl VM {
         self.increase_consumed_gas(current_call_frame, gas_cost::JUMPI)?;
 
         if !condition.is_zero() {
-            Self::jump(current_call_frame, jump_address)?
+            self.jump(current_call_frame, jump_address)?
         } else {
             current_call_frame.increment_pc()?;
         }
@@ -1,11 +1,12 @@
 use crate::{
     call_frame::CallFrame,
     constants::{CREATE_DEPLOYMENT_FAIL, INIT_CODE_MAX_SIZE, REVERT_FOR_CALL, SUCCESS_FOR_CALL},
-    db::cache,
+    eof::EofContainer,
     errors::{InternalError, OpcodeSuccess, OutOfGasError, ResultReason, TxResult, VMError},
     gas_cost::{
         self, max_message_call_gas, CALLCODE_POSITIVE_VALUE_STIPEND, CALL_POSITIVE_VALUE_STIPEND,
     },
+    journal::JournalEntry,
     memory::{self, calculate_memory_size},
     vm::{address_to_word, word_to_address, VM},
     Account,
@@ -61,6 +62,7 @@ impl VM {
                 address_was_cold,
                 account_info.is_empty(),
                 value_to_transfer,
+                self.env.fork,
             )?,
         )?;
 
@@ -132,6 +134,7 @@ impl VM {
                 current_memory_size,
                 address_was_cold,
                 value_to_transfer,
+                self.env.fork,
             )?,
         )?;
 
@@ -229,7 +232,12 @@ impl VM {
 
         self.increase_consumed_gas(
             current_call_frame,
-            gas_cost::delegatecall(new_memory_size, current_memory_size, address_was_cold)?,
+            gas_cost::delegatecall(
+                new_memory_size,
+                current_memory_size,
+                address_was_cold,
+                self.env.fork,
+            )?,
         )?;
 
         // OPERATION
@@ -287,7 +295,12 @@ impl VM {
 
         self.increase_consumed_gas(
             current_call_frame,
-            gas_cost::staticcall(new_memory_size, current_memory_size, address_was_cold)?,
+            gas_cost::staticcall(
+                new_memory_size,
+                current_memory_size,
+                address_was_cold,
+                self.env.fork,
+            )?,
         )?;
 
         // OPERATION
@@ -333,13 +346,24 @@ impl VM {
                 new_size,
                 current_call_frame.memory.len(),
                 code_size_in_memory,
+                self.env.fork,
             )?,
         )?;
 
+        let code = Bytes::from(
+            memory::load_range(
+                &mut current_call_frame.memory,
+                code_offset_in_memory,
+                code_size_in_memory,
+            )?
+            .to_vec(),
+        );
+
         self.generic_create(
             value_in_wei_to_send,
-            code_offset_in_memory,
-            code_size_in_memory,
+            code,
+            Bytes::new(),
+            None,
             None,
             current_call_frame,
         )
@@ -368,14 +392,25 @@ impl VM {
                 new_size,
                 current_call_frame.memory.len(),
                 code_size_in_memory,
+                self.env.fork,
             )?,
         )?;
 
+        let code = Bytes::from(
+            memory::load_range(
+                &mut current_call_frame.memory,
+                code_offset_in_memory,
+                code_size_in_memory,
+            )?
+            .to_vec(),
+        );
+
         self.generic_create(
             value_in_wei_to_send,
-            code_offset_in_memory,
-            code_size_in_memory,
+            code,
+            Bytes::new(),
             Some(salt),
+            None,
             current_call_frame,
         )
     }
@@ -454,32 +489,50 @@ impl VM {
                 target_account_is_cold,
                 target_account_info.is_empty(),
                 balance_to_transfer,
+                self.env.fork,
             )?,
         )?;
 
         self.increase_account_balance(target_address, balance_to_transfer)?;
         self.decrease_account_balance(current_call_frame.to, balance_to_transfer)?;
 
-        if self
-            .accrued_substate
-            .created_accounts
-            .contains(&current_call_frame.to)
+        // Since Cancun only accounts created in the same transaction are destroyed (EIP-6780)
+        let schedule = self.env.fork.gas_schedule();
+        if !schedule.selfdestruct_only_created
+            || self
+                .accrued_substate
+                .created_accounts
+                .contains(&current_call_frame.to)
         {
-            self.accrued_substate
+            let newly_destroyed = self
+                .accrued_substate
                 .selfdestrutct_set
                 .insert(current_call_frame.to);
+            if newly_destroyed {
+                self.journal.record(JournalEntry::AccountDestroyed {
+                    address: current_call_frame.to,
+                });
+                self.env.refunded_gas = self
+                    .env
+                    .refunded_gas
+                    .checked_add(schedule.selfdestruct_refund)
+                    .ok_or(VMError::GasRefundsOverflow)?;
+            }
         }
 
         Ok(OpcodeSuccess::Result(ResultReason::SelfDestruct))
     }
 
-    /// Common behavior for CREATE and CREATE2 opcodes
+    /// Common behavior for CREATE, CREATE2 and EOFCREATE opcodes
+    /// EOFCREATE passes the initcontainer as `code`, already decoded in `eof_container`, along
+    /// with its input as `calldata`
     pub fn generic_create(
         &mut self,
         value_in_wei_to_send: U256,
-        code_offset_in_memory: U256,
-        code_size_in_memory: usize,
+        code: Bytes,
+        calldata: Bytes,
         salt: Option<U256>,
+        eof_container: Option<EofContainer>,
         current_call_frame: &mut CallFrame,
     ) -> Result<OpcodeSuccess, VMError> {
         // First: Validations that can cause out of gas.
@@ -487,13 +540,17 @@ impl VM {
         if current_call_frame.is_static {
             return Err(VMError::OpcodeNotAllowedInStaticContext);
         }
-        // 2. Cant exceed init code max size
-        if code_size_in_memory > INIT_CODE_MAX_SIZE {
+        // 2. Cant exceed init code max size, since Shanghai. Initcontainers were already
+        // validated as part of the deployed code
+        if self.env.fork.gas_schedule().limit_init_code
+            && eof_container.is_none()
+            && code.len() > INIT_CODE_MAX_SIZE
+        {
             return Err(VMError::OutOfGas(OutOfGasError::ConsumedGasOverflow));
         }
 
         // Reserve gas for subcall
-        let max_message_call_gas = max_message_call_gas(current_call_frame)?;
+        let max_message_call_gas = max_message_call_gas(current_call_frame, self.env.fork)?;
         self.increase_consumed_gas(current_call_frame, max_message_call_gas)?;
 
         // Clear callframe subreturn data
@@ -503,22 +560,13 @@ impl VM {
 
         let deployer_account_info = self.access_account(deployer_address).0;
 
-        let code = Bytes::from(
-            memory::load_range(
-                &mut current_call_frame.memory,
-                code_offset_in_memory,
-                code_size_in_memory,
-            )?
-            .to_vec(),
-        );
-
         let new_address = match salt {
             Some(salt) => Self::calculate_create2_address(deployer_address, &code, salt)?,
             None => Self::calculate_create_address(deployer_address, deployer_account_info.nonce)?,
         };
 
         // touch account
-        self.accrued_substate.touched_accounts.insert(new_address);
+        self.warm_account(new_address);
 
         let new_depth = current_call_frame
             .depth
@@ -558,8 +606,13 @@ impl VM {
             .checked_add(new_account.info.balance)
             .ok_or(VMError::BalanceOverflow)?;
 
-        let new_account = Account::new(new_balance, Bytes::new(), 1, Default::default());
-        cache::insert_account(&mut self.cache, new_address, new_account);
+        let new_account = Account::new(
+            new_balance,
+            Bytes::new(),
+            self.env.fork.gas_schedule().created_contract_nonce,
+            Default::default(),
+        );
+        self.insert_account(new_address, new_account);
 
         // 2. Increment sender's nonce.
         self.increment_account_nonce(deployer_address)?;
@@ -573,15 +626,23 @@ impl VM {
             new_address,
             code,
             value_in_wei_to_send,
-            Bytes::new(),
+            calldata,
             false,
             max_message_call_gas,
             0,
             new_depth,
             true,
         );
+        if let Some(container) = eof_container {
+            new_call_frame.assign_eof_container(container)?;
+        }
 
-        self.accrued_substate.created_accounts.insert(new_address); // Mostly for SELFDESTRUCT during initcode.
+        // Mostly for SELFDESTRUCT during initcode.
+        if self.accrued_substate.created_accounts.insert(new_address) {
+            self.journal.record(JournalEntry::AccountCreated {
+                address: new_address,
+            });
+        }
 
         let tx_report = self.execute(&mut new_call_frame)?;
         let unused_gas = max_message_call_gas
@@ -607,7 +668,7 @@ impl VM {
                 self.increase_account_balance(deployer_address, value_in_wei_to_send)?;
 
                 // Deployment failed so account shouldn't exist
-                cache::remove_account(&mut self.cache, &new_address);
+                self.remove_account(new_address);
                 self.accrued_substate.created_accounts.remove(&new_address);
 
                 // If revert we have to copy the return_data
@@ -661,11 +722,11 @@ impl VM {
             return Ok(OpcodeSuccess::Continue);
         }
 
-        let recipient_bytecode = self.access_account(code_address).0.bytecode;
+        let recipient_info = self.access_account(code_address).0;
         let calldata =
             memory::load_range(&mut current_call_frame.memory, args_offset, args_size)?.to_vec();
         // Gas Limit for the child context is capped.
-        let gas_cap = max_message_call_gas(current_call_frame)?;
+        let gas_cap = max_message_call_gas(current_call_frame, self.env.fork)?;
         let gas_limit = std::cmp::min(gas_limit, gas_cap.into());
 
         // This should always cast correcly because the gas_cap is in
@@ -678,7 +739,7 @@ impl VM {
             msg_sender,
             to,
             code_address,
-            recipient_bytecode,
+            recipient_info.bytecode,
             value,
             calldata.into(),
             is_static,
@@ -687,6 +748,7 @@ impl VM {
             new_depth,
             false,
         );
+        new_call_frame.code_hash = Some(recipient_info.code_hash);
 
         // Transfer value from caller to callee.
         if should_transfer_value {
@@ -0,0 +1,304 @@
+use crate::{
+    call_frame::CallFrame,
+    errors::{OpcodeSuccess, ResultReason, VMError},
+    fork::{activation_fork, Fork},
+    opcodes::Opcode,
+    vm::VM,
+};
+use std::sync::OnceLock;
+
+/// Executes an opcode over the current call frame
+pub type OpcodeHandler = fn(&mut VM, &mut CallFrame) -> Result<OpcodeSuccess, VMError>;
+
+/// What the interpreter needs to know to execute an opcode, looked up by its byte instead of
+/// matching on the decoded `Opcode`
+#[derive(Clone, Copy)]
+pub struct OpcodeInfo {
+    pub handler: OpcodeHandler,
+    /// Fork in which the opcode was introduced, before it the opcode is undefined
+    pub introduced_in: Fork,
+    /// EOF opcodes are undefined in legacy code
+    pub eof_only: bool,
+    /// Jumps set the PC themselves, the rest of the opcodes are followed by the next one
+    pub sets_pc: bool,
+}
+
+impl OpcodeInfo {
+    pub fn is_defined(&self, fork: Fork, is_eof: bool) -> bool {
+        fork >= self.introduced_in && (is_eof || !self.eof_only)
+    }
+}
+
+/// Information of every opcode, indexed by its byte. Undefined bytes are handled as INVALID
+pub fn opcode_info(opcode: u8) -> &'static OpcodeInfo {
+    static OPCODE_TABLE: OnceLock<[OpcodeInfo; 256]> = OnceLock::new();
+    let table = OPCODE_TABLE.get_or_init(|| {
+        std::array::from_fn(|byte| {
+            let opcode = u8::try_from(byte).map_or(Opcode::INVALID, Opcode::from);
+            OpcodeInfo {
+                handler: handler(&opcode),
+                introduced_in: activation_fork(&opcode),
+                eof_only: opcode.is_eof_only(),
+                sets_pc: matches!(
+                    opcode,
+                    Opcode::JUMP
+                        | Opcode::JUMPI
+                        | Opcode::RJUMP
+                        | Opcode::RJUMPI
+                        | Opcode::RJUMPV
+                        | Opcode::CALLF
+                        | Opcode::RETF
+                        | Opcode::JUMPF
+                ),
+            }
+        })
+    });
+    // The table has an entry for every byte
+    table.get(usize::from(opcode)).unwrap_or(&INVALID_INFO)
+}
+
+const INVALID_INFO: OpcodeInfo = OpcodeInfo {
+    handler: op_invalid,
+    introduced_in: Fork::Frontier,
+    eof_only: false,
+    sets_pc: false,
+};
+
+const PUSH_HANDLERS: [OpcodeHandler; 32] = [
+    op_push_n::<1>,
+    op_push_n::<2>,
+    op_push_n::<3>,
+    op_push_n::<4>,
+    op_push_n::<5>,
+    op_push_n::<6>,
+    op_push_n::<7>,
+    op_push_n::<8>,
+    op_push_n::<9>,
+    op_push_n::<10>,
+    op_push_n::<11>,
+    op_push_n::<12>,
+    op_push_n::<13>,
+    op_push_n::<14>,
+    op_push_n::<15>,
+    op_push_n::<16>,
+    op_push_n::<17>,
+    op_push_n::<18>,
+    op_push_n::<19>,
+    op_push_n::<20>,
+    op_push_n::<21>,
+    op_push_n::<22>,
+    op_push_n::<23>,
+    op_push_n::<24>,
+    op_push_n::<25>,
+    op_push_n::<26>,
+    op_push_n::<27>,
+    op_push_n::<28>,
+    op_push_n::<29>,
+    op_push_n::<30>,
+    op_push_n::<31>,
+    op_push_n::<32>,
+];
+const DUP_HANDLERS: [OpcodeHandler; 16] = [
+    op_dup_n::<1>,
+    op_dup_n::<2>,
+    op_dup_n::<3>,
+    op_dup_n::<4>,
+    op_dup_n::<5>,
+    op_dup_n::<6>,
+    op_dup_n::<7>,
+    op_dup_n::<8>,
+    op_dup_n::<9>,
+    op_dup_n::<10>,
+    op_dup_n::<11>,
+    op_dup_n::<12>,
+    op_dup_n::<13>,
+    op_dup_n::<14>,
+    op_dup_n::<15>,
+    op_dup_n::<16>,
+];
+const SWAP_HANDLERS: [OpcodeHandler; 16] = [
+    op_swap_n::<1>,
+    op_swap_n::<2>,
+    op_swap_n::<3>,
+    op_swap_n::<4>,
+    op_swap_n::<5>,
+    op_swap_n::<6>,
+    op_swap_n::<7>,
+    op_swap_n::<8>,
+    op_swap_n::<9>,
+    op_swap_n::<10>,
+    op_swap_n::<11>,
+    op_swap_n::<12>,
+    op_swap_n::<13>,
+    op_swap_n::<14>,
+    op_swap_n::<15>,
+    op_swap_n::<16>,
+];
+const LOG_HANDLERS: [OpcodeHandler; 5] = [
+    op_log_n::<0>,
+    op_log_n::<1>,
+    op_log_n::<2>,
+    op_log_n::<3>,
+    op_log_n::<4>,
+];
+
+fn handler(opcode: &Opcode) -> OpcodeHandler {
+    match opcode {
+        Opcode::STOP => op_stop,
+        Opcode::ADD => VM::op_add,
+        Opcode::MUL => VM::op_mul,
+        Opcode::SUB => VM::op_sub,
+        Opcode::DIV => VM::op_div,
+        Opcode::SDIV => VM::op_sdiv,
+        Opcode::MOD => VM::op_mod,
+        Opcode::SMOD => VM::op_smod,
+        Opcode::ADDMOD => VM::op_addmod,
+        Opcode::MULMOD => VM::op_mulmod,
+        Opcode::EXP => VM::op_exp,
+        Opcode::SIGNEXTEND => VM::op_signextend,
+        Opcode::LT => VM::op_lt,
+        Opcode::GT => VM::op_gt,
+        Opcode::SLT => VM::op_slt,
+        Opcode::SGT => VM::op_sgt,
+        Opcode::EQ => VM::op_eq,
+        Opcode::ISZERO => VM::op_iszero,
+        Opcode::KECCAK256 => VM::op_keccak256,
+        Opcode::CALLDATALOAD => VM::op_calldataload,
+        Opcode::CALLDATASIZE => VM::op_calldatasize,
+        Opcode::CALLDATACOPY => VM::op_calldatacopy,
+        Opcode::RETURNDATASIZE => VM::op_returndatasize,
+        Opcode::RETURNDATACOPY => VM::op_returndatacopy,
+        Opcode::JUMP => VM::op_jump,
+        Opcode::JUMPI => VM::op_jumpi,
+        Opcode::JUMPDEST => VM::op_jumpdest,
+        Opcode::PC => VM::op_pc,
+        Opcode::BLOCKHASH => VM::op_blockhash,
+        Opcode::COINBASE => VM::op_coinbase,
+        Opcode::TIMESTAMP => VM::op_timestamp,
+        Opcode::NUMBER => VM::op_number,
+        Opcode::PREVRANDAO => VM::op_prevrandao,
+        Opcode::GASLIMIT => VM::op_gaslimit,
+        Opcode::CHAINID => VM::op_chainid,
+        Opcode::BASEFEE => VM::op_basefee,
+        Opcode::BLOBHASH => VM::op_blobhash,
+        Opcode::BLOBBASEFEE => VM::op_blobbasefee,
+        Opcode::PUSH0 => VM::op_push0,
+        op if (Opcode::PUSH1..=Opcode::PUSH32).contains(op) => {
+            sized_handler(&PUSH_HANDLERS, op, Opcode::PUSH1)
+        }
+        Opcode::AND => VM::op_and,
+        Opcode::OR => VM::op_or,
+        Opcode::XOR => VM::op_xor,
+        Opcode::NOT => VM::op_not,
+        Opcode::BYTE => VM::op_byte,
+        Opcode::SHL => VM::op_shl,
+        Opcode::SHR => VM::op_shr,
+        Opcode::SAR => VM::op_sar,
+        op if (Opcode::DUP1..=Opcode::DUP16).contains(op) => {
+            sized_handler(&DUP_HANDLERS, op, Opcode::DUP1)
+        }
+        op if (Opcode::SWAP1..=Opcode::SWAP16).contains(op) => {
+            sized_handler(&SWAP_HANDLERS, op, Opcode::SWAP1)
+        }
+        Opcode::POP => VM::op_pop,
+        op if (Opcode::LOG0..=Opcode::LOG4).contains(op) => {
+            sized_handler(&LOG_HANDLERS, op, Opcode::LOG0)
+        }
+        Opcode::MLOAD => VM::op_mload,
+        Opcode::MSTORE => VM::op_mstore,
+        Opcode::MSTORE8 => VM::op_mstore8,
+        Opcode::SLOAD => VM::op_sload,
+        Opcode::SSTORE => VM::op_sstore,
+        Opcode::MSIZE => VM::op_msize,
+        Opcode::GAS => VM::op_gas,
+        Opcode::MCOPY => VM::op_mcopy,
+        Opcode::CALL => VM::op_call,
+        Opcode::CALLCODE => VM::op_callcode,
+        Opcode::RETURN => VM::op_return,
+        Opcode::DELEGATECALL => VM::op_delegatecall,
+        Opcode::STATICCALL => VM::op_staticcall,
+        Opcode::CREATE => VM::op_create,
+        Opcode::CREATE2 => VM::op_create2,
+        Opcode::TLOAD => VM::op_tload,
+        Opcode::TSTORE => VM::op_tstore,
+        Opcode::SELFBALANCE => VM::op_selfbalance,
+        Opcode::ADDRESS => VM::op_address,
+        Opcode::ORIGIN => VM::op_origin,
+        Opcode::BALANCE => VM::op_balance,
+        Opcode::CALLER => VM::op_caller,
+        Opcode::CALLVALUE => VM::op_callvalue,
+        Opcode::CODECOPY => VM::op_codecopy,
+        Opcode::CODESIZE => VM::op_codesize,
+        Opcode::GASPRICE => VM::op_gasprice,
+        Opcode::EXTCODESIZE => VM::op_extcodesize,
+        Opcode::EXTCODECOPY => VM::op_extcodecopy,
+        Opcode::EXTCODEHASH => VM::op_extcodehash,
+        Opcode::REVERT => VM::op_revert,
+        Opcode::SELFDESTRUCT => VM::op_selfdestruct,
+        Opcode::DATALOAD => VM::op_dataload,
+        Opcode::DATALOADN => VM::op_dataloadn,
+        Opcode::DATASIZE => VM::op_datasize,
+        Opcode::DATACOPY => VM::op_datacopy,
+        Opcode::RJUMP => VM::op_rjump,
+        Opcode::RJUMPI => VM::op_rjumpi,
+        Opcode::RJUMPV => VM::op_rjumpv,
+        Opcode::CALLF => VM::op_callf,
+        Opcode::RETF => VM::op_retf,
+        Opcode::JUMPF => VM::op_jumpf,
+        Opcode::DUPN => VM::op_dupn,
+        Opcode::SWAPN => VM::op_swapn,
+        Opcode::EXCHANGE => VM::op_exchange,
+        Opcode::EOFCREATE => VM::op_eofcreate,
+        Opcode::RETURNCONTRACT => VM::op_returncontract,
+        Opcode::RETURNDATALOAD => VM::op_returndataload,
+        Opcode::EXTCALL => VM::op_extcall,
+        Opcode::EXTDELEGATECALL => VM::op_extdelegatecall,
+        Opcode::EXTSTATICCALL => VM::op_extstaticcall,
+        _ => op_invalid,
+    }
+}
+
+/// Handler of the `opcode` in a family of opcodes that only differ in size, like PUSHn
+fn sized_handler(handlers: &[OpcodeHandler], opcode: &Opcode, first: Opcode) -> OpcodeHandler {
+    u8::from(*opcode)
+        .checked_sub(u8::from(first))
+        .and_then(|index| handlers.get(usize::from(index)))
+        .copied()
+        .unwrap_or(op_invalid)
+}
+
+fn op_stop(_vm: &mut VM, _current_call_frame: &mut CallFrame) -> Result<OpcodeSuccess, VMError> {
+    Ok(OpcodeSuccess::Result(ResultReason::Stop))
+}
+
+fn op_invalid(vm: &mut VM, _current_call_frame: &mut CallFrame) -> Result<OpcodeSuccess, VMError> {
+    vm.op_invalid()
+}
+
+fn op_push_n<const N: usize>(
+    vm: &mut VM,
+    current_call_frame: &mut CallFrame,
+) -> Result<OpcodeSuccess, VMError> {
+    vm.op_push(current_call_frame, N)
+}
+
+fn op_dup_n<const N: usize>(
+    vm: &mut VM,
+    current_call_frame: &mut CallFrame,
+) -> Result<OpcodeSuccess, VMError> {
+    vm.op_dup(current_call_frame, N)
+}
+
+fn op_swap_n<const N: usize>(
+    vm: &mut VM,
+    current_call_frame: &mut CallFrame,
+) -> Result<OpcodeSuccess, VMError> {
+    vm.op_swap(current_call_frame, N)
+}
+
+fn op_log_n<const N: u8>(
+    vm: &mut VM,
+    current_call_frame: &mut CallFrame,
+) -> Result<OpcodeSuccess, VMError> {
+    vm.op_log(current_call_frame, N)
+}
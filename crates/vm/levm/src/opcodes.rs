@@ -157,6 +157,23 @@ pub enum Opcode {
     LOG2 = 0xA2,
     LOG3 = 0xA3,
     LOG4 = 0xA4,
+    // EOF Data Section Access
+    DATALOAD = 0xD0,
+    DATALOADN = 0xD1,
+    DATASIZE = 0xD2,
+    DATACOPY = 0xD3,
+    // EOF Control Flow and Stack Operations
+    RJUMP = 0xE0,
+    RJUMPI = 0xE1,
+    RJUMPV = 0xE2,
+    CALLF = 0xE3,
+    RETF = 0xE4,
+    JUMPF = 0xE5,
+    DUPN = 0xE6,
+    SWAPN = 0xE7,
+    EXCHANGE = 0xE8,
+    EOFCREATE = 0xEC,
+    RETURNCONTRACT = 0xEE,
     // // System Operations
     CREATE = 0xF0,
     CALL = 0xF1,
@@ -164,7 +181,11 @@ pub enum Opcode {
     RETURN = 0xF3,
     DELEGATECALL = 0xF4,
     CREATE2 = 0xF5,
+    RETURNDATALOAD = 0xF7,
+    EXTCALL = 0xF8,
+    EXTDELEGATECALL = 0xF9,
     STATICCALL = 0xFA,
+    EXTSTATICCALL = 0xFB,
     REVERT = 0xFD,
     INVALID = 0xFE,
     SELFDESTRUCT = 0xFF,
@@ -323,11 +344,58 @@ impl From<u8> for Opcode {
             0xFA => Opcode::STATICCALL,
             0xFD => Opcode::REVERT,
             0xFF => Opcode::SELFDESTRUCT,
+            0xD0 => Opcode::DATALOAD,
+            0xD1 => Opcode::DATALOADN,
+            0xD2 => Opcode::DATASIZE,
+            0xD3 => Opcode::DATACOPY,
+            0xE0 => Opcode::RJUMP,
+            0xE1 => Opcode::RJUMPI,
+            0xE2 => Opcode::RJUMPV,
+            0xE3 => Opcode::CALLF,
+            0xE4 => Opcode::RETF,
+            0xE5 => Opcode::JUMPF,
+            0xE6 => Opcode::DUPN,
+            0xE7 => Opcode::SWAPN,
+            0xE8 => Opcode::EXCHANGE,
+            0xEC => Opcode::EOFCREATE,
+            0xEE => Opcode::RETURNCONTRACT,
+            0xF7 => Opcode::RETURNDATALOAD,
+            0xF8 => Opcode::EXTCALL,
+            0xF9 => Opcode::EXTDELEGATECALL,
+            0xFB => Opcode::EXTSTATICCALL,
             _ => Opcode::INVALID,
         }
     }
 }
 
+impl Opcode {
+    /// Whether the opcode can only be executed in EOF code, [EIP-7692]
+    pub fn is_eof_only(&self) -> bool {
+        matches!(
+            self,
+            Opcode::DATALOAD
+                | Opcode::DATALOADN
+                | Opcode::DATASIZE
+                | Opcode::DATACOPY
+                | Opcode::RJUMP
+                | Opcode::RJUMPI
+                | Opcode::RJUMPV
+                | Opcode::CALLF
+                | Opcode::RETF
+                | Opcode::JUMPF
+                | Opcode::DUPN
+                | Opcode::SWAPN
+                | Opcode::EXCHANGE
+                | Opcode::EOFCREATE
+                | Opcode::RETURNCONTRACT
+                | Opcode::RETURNDATALOAD
+                | Opcode::EXTCALL
+                | Opcode::EXTDELEGATECALL
+                | Opcode::EXTSTATICCALL
+        )
+    }
+}
+
 impl From<Opcode> for u8 {
     #[allow(clippy::as_conversions)]
     fn from(opcode: Opcode) -> Self {
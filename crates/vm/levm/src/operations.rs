@@ -91,6 +91,25 @@ pub enum Operation {
     Revert,
     Invalid,
     SelfDestruct,
+    Dataload,
+    Dataloadn(u16),
+    Datasize,
+    Datacopy,
+    Rjump(i16),
+    Rjumpi(i16),
+    Rjumpv(Vec<i16>),
+    Callf(u16),
+    Retf,
+    Jumpf(u16),
+    Dupn(u8),
+    Swapn(u8),
+    Exchange(u8),
+    Eofcreate(u8),
+    Returncontract(u8),
+    ReturnDataLoad,
+    Extcall,
+    ExtDelegateCall,
+    ExtStaticCall,
 }
 
 impl Operation {
@@ -238,7 +257,45 @@ impl Operation {
             Operation::Revert => Bytes::copy_from_slice(&[u8::from(Opcode::REVERT)]),
             Operation::Invalid => Bytes::copy_from_slice(&[u8::from(Opcode::INVALID)]),
             Operation::SelfDestruct => Bytes::copy_from_slice(&[u8::from(Opcode::SELFDESTRUCT)]),
+            Operation::Dataload => Bytes::copy_from_slice(&[u8::from(Opcode::DATALOAD)]),
+            Operation::Dataloadn(offset) => {
+                with_immediate(Opcode::DATALOADN, &offset.to_be_bytes())
+            }
+            Operation::Datasize => Bytes::copy_from_slice(&[u8::from(Opcode::DATASIZE)]),
+            Operation::Datacopy => Bytes::copy_from_slice(&[u8::from(Opcode::DATACOPY)]),
+            Operation::Rjump(offset) => with_immediate(Opcode::RJUMP, &offset.to_be_bytes()),
+            Operation::Rjumpi(offset) => with_immediate(Opcode::RJUMPI, &offset.to_be_bytes()),
+            Operation::Rjumpv(offsets) => {
+                assert!(!offsets.is_empty(), "RJUMPV needs at least one case");
+                let max_index = u8::try_from(offsets.len().saturating_sub(1))
+                    .map_err(|_| VMError::Internal(InternalError::ConversionError))?;
+                let mut immediate = vec![max_index];
+                for offset in offsets {
+                    immediate.extend_from_slice(&offset.to_be_bytes());
+                }
+                with_immediate(Opcode::RJUMPV, &immediate)
+            }
+            Operation::Callf(section) => with_immediate(Opcode::CALLF, &section.to_be_bytes()),
+            Operation::Retf => Bytes::copy_from_slice(&[u8::from(Opcode::RETF)]),
+            Operation::Jumpf(section) => with_immediate(Opcode::JUMPF, &section.to_be_bytes()),
+            Operation::Dupn(n) => with_immediate(Opcode::DUPN, &[*n]),
+            Operation::Swapn(n) => with_immediate(Opcode::SWAPN, &[*n]),
+            Operation::Exchange(n) => with_immediate(Opcode::EXCHANGE, &[*n]),
+            Operation::Eofcreate(index) => with_immediate(Opcode::EOFCREATE, &[*index]),
+            Operation::Returncontract(index) => with_immediate(Opcode::RETURNCONTRACT, &[*index]),
+            Operation::ReturnDataLoad => {
+                Bytes::copy_from_slice(&[u8::from(Opcode::RETURNDATALOAD)])
+            }
+            Operation::Extcall => Bytes::copy_from_slice(&[u8::from(Opcode::EXTCALL)]),
+            Operation::ExtDelegateCall => {
+                Bytes::copy_from_slice(&[u8::from(Opcode::EXTDELEGATECALL)])
+            }
+            Operation::ExtStaticCall => Bytes::copy_from_slice(&[u8::from(Opcode::EXTSTATICCALL)]),
         };
         Ok(bytecode)
     }
 }
+
+fn with_immediate(opcode: Opcode, immediate: &[u8]) -> Bytes {
+    [&[u8::from(opcode)], immediate].concat().into()
+}
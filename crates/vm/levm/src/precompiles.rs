@@ -1,273 +0,0 @@
-use bytes::Bytes;
-use ethrex_core::{Address, H160, U256};
-use keccak_hash::keccak256;
-use libsecp256k1::{self, Message, RecoveryId, Signature};
-use sha3::Digest;
-
-use crate::{
-    call_frame::CallFrame,
-    errors::{InternalError, PrecompileError, VMError},
-    gas_cost::{ripemd_160 as ripemd_160_cost, sha2_256 as sha2_256_cost, ECRECOVER_COST},
-};
-
-pub const ECRECOVER_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x01,
-]);
-pub const SHA2_256_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x02,
-]);
-pub const RIPEMD_160_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x03,
-]);
-pub const IDENTITY_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x04,
-]);
-pub const MODEXP_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x05,
-]);
-pub const ECADD_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x06,
-]);
-pub const ECMUL_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x07,
-]);
-pub const ECPAIRING_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x08,
-]);
-pub const BLAKE2F_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x09,
-]);
-pub const POINT_EVALUATION_ADDRESS: H160 = H160([
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x00, 0x00, 0x00, 0x0a,
-]);
-
-pub const PRECOMPILES: [H160; 10] = [
-    ECRECOVER_ADDRESS,
-    SHA2_256_ADDRESS,
-    RIPEMD_160_ADDRESS,
-    IDENTITY_ADDRESS,
-    MODEXP_ADDRESS,
-    ECADD_ADDRESS,
-    ECMUL_ADDRESS,
-    ECPAIRING_ADDRESS,
-    BLAKE2F_ADDRESS,
-    POINT_EVALUATION_ADDRESS,
-];
-
-pub fn is_precompile(callee_address: &Address) -> bool {
-    PRECOMPILES.contains(callee_address)
-}
-
-pub fn execute_precompile(current_call_frame: &mut CallFrame) -> Result<Bytes, VMError> {
-    let callee_address = current_call_frame.code_address;
-    let calldata = current_call_frame.calldata.clone();
-    let gas_for_call = current_call_frame.gas_limit;
-    let consumed_gas = &mut current_call_frame.gas_used;
-
-    let result = match callee_address {
-        address if address == ECRECOVER_ADDRESS => {
-            ecrecover(&calldata, gas_for_call, consumed_gas)?
-        }
-        address if address == IDENTITY_ADDRESS => identity(&calldata, gas_for_call, consumed_gas)?,
-        address if address == SHA2_256_ADDRESS => sha2_256(&calldata, gas_for_call, consumed_gas)?,
-        address if address == RIPEMD_160_ADDRESS => {
-            ripemd_160(&calldata, gas_for_call, consumed_gas)?
-        }
-        address if address == MODEXP_ADDRESS => modexp(&calldata, gas_for_call, consumed_gas)?,
-        address if address == ECADD_ADDRESS => ecadd(&calldata, gas_for_call, consumed_gas)?,
-        address if address == ECMUL_ADDRESS => ecmul(&calldata, gas_for_call, consumed_gas)?,
-        address if address == ECPAIRING_ADDRESS => {
-            ecpairing(&calldata, gas_for_call, consumed_gas)?
-        }
-        address if address == BLAKE2F_ADDRESS => blake2f(&calldata, gas_for_call, consumed_gas)?,
-        address if address == POINT_EVALUATION_ADDRESS => {
-            point_evaluation(&calldata, gas_for_call, consumed_gas)?
-        }
-        _ => return Err(VMError::Internal(InternalError::InvalidPrecompileAddress)),
-    };
-
-    Ok(result)
-}
-
-/// Verifies if the gas cost is higher than the gas limit and consumes the gas cost if it is not
-fn increase_precompile_consumed_gas(
-    gas_for_call: u64,
-    gas_cost: u64,
-    consumed_gas: &mut u64,
-) -> Result<(), VMError> {
-    if gas_for_call < gas_cost {
-        return Err(VMError::PrecompileError(PrecompileError::NotEnoughGas));
-    }
-
-    *consumed_gas = consumed_gas
-        .checked_add(gas_cost)
-        .ok_or(PrecompileError::GasConsumedOverflow)?;
-
-    Ok(())
-}
-
-/// When slice length is less than 128, the rest is filled with zeros. If slice length is
-/// more than 128 the excess bytes are discarded.
-fn fill_with_zeros(slice: &[u8]) -> Result<[u8; 128], VMError> {
-    let mut result = [0; 128];
-
-    let n = slice.len().min(128);
-
-    let trimmed_slice: &[u8] = slice.get(..n).unwrap_or_default();
-
-    for i in 0..n {
-        let byte: &mut u8 = result.get_mut(i).ok_or(InternalError::SlicingError)?;
-        *byte = *trimmed_slice.get(i).ok_or(InternalError::SlicingError)?;
-    }
-
-    Ok(result)
-}
-
-pub fn ecrecover(
-    calldata: &Bytes,
-    gas_for_call: u64,
-    consumed_gas: &mut u64,
-) -> Result<Bytes, VMError> {
-    let gas_cost = ECRECOVER_COST;
-
-    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
-
-    // If calldata does not reach the required length, we should fill the rest with zeros
-    let calldata = fill_with_zeros(calldata)?;
-
-    // Parse the input elements, first as a slice of bytes and then as an specific type of the crate
-    let hash = calldata.get(0..32).ok_or(InternalError::SlicingError)?;
-    let message = Message::parse_slice(hash).map_err(|_| PrecompileError::ParsingInputError)?;
-
-    let v: U256 = calldata
-        .get(32..64)
-        .ok_or(InternalError::SlicingError)?
-        .into();
-
-    // The Recovery identifier is expected to be 27 or 28, any other value is invalid
-    if !(v == U256::from(27) || v == U256::from(28)) {
-        return Ok(Bytes::new());
-    }
-
-    let v = u8::try_from(v).map_err(|_| InternalError::ConversionError)?;
-    let recovery_id = match RecoveryId::parse_rpc(v) {
-        Ok(id) => id,
-        Err(_) => {
-            return Ok(Bytes::new());
-        }
-    };
-
-    // signature is made up of the parameters r and s
-    let sig = calldata.get(64..128).ok_or(InternalError::SlicingError)?;
-    let signature =
-        Signature::parse_standard_slice(sig).map_err(|_| PrecompileError::ParsingInputError)?;
-
-    // Recover the address using secp256k1
-    let mut public_key = match libsecp256k1::recover(&message, &signature, &recovery_id) {
-        Ok(id) => id,
-        Err(_) => {
-            return Ok(Bytes::new());
-        }
-    }
-    .serialize();
-
-    // We need to take the 64 bytes from the public key (discarding the first pos of the slice)
-    keccak256(&mut public_key[1..65]);
-
-    // The output is 32 bytes: the initial 12 bytes with 0s, and the remaining 20 with the recovered address
-    let mut output = vec![0u8; 12];
-    output.extend_from_slice(public_key.get(13..33).ok_or(InternalError::SlicingError)?);
-
-    Ok(Bytes::from(output.to_vec()))
-}
-
-fn identity(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
-) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
-}
-
-pub fn sha2_256(
-    calldata: &Bytes,
-    gas_for_call: u64,
-    consumed_gas: &mut u64,
-) -> Result<Bytes, VMError> {
-    let gas_cost = sha2_256_cost(calldata.len())?;
-
-    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
-
-    let result = sha2::Sha256::digest(calldata).to_vec();
-
-    Ok(Bytes::from(result))
-}
-
-pub fn ripemd_160(
-    calldata: &Bytes,
-    gas_for_call: u64,
-    consumed_gas: &mut u64,
-) -> Result<Bytes, VMError> {
-    let gas_cost = ripemd_160_cost(calldata.len())?;
-
-    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
-
-    let mut hasher = ripemd::Ripemd160::new();
-    hasher.update(calldata);
-    let result = hasher.finalize();
-
-    let mut output = vec![0; 12];
-    output.extend_from_slice(&result);
-
-    Ok(Bytes::from(output))
-}
-
-fn modexp(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
-) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
-}
-
-fn ecadd(_calldata: &Bytes, _gas_for_call: u64, _consumed_gas: &mut u64) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
-}
-
-fn ecmul(_calldata: &Bytes, _gas_for_call: u64, _consumed_gas: &mut u64) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
-}
-
-fn ecpairing(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
-) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
-}
-
-fn blake2f(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
-) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
-}
-
-fn point_evaluation(
-    _calldata: &Bytes,
-    _gas_for_call: u64,
-    _consumed_gas: &mut u64,
-) -> Result<Bytes, VMError> {
-    Ok(Bytes::new())
-}
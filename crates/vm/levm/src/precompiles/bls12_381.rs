@@ -0,0 +1,343 @@
+//! BLS12-381 precompiles, as defined in [EIP-2537](https://eips.ethereum.org/EIPS/eip-2537)
+//!
+//! Curve operations are performed by `ark-bls12-381`, whose `map_to_curve` and cofactor clearing
+//! follow the same hash-to-curve suites as the EIP.
+
+use ark_bls12_381::{
+    g1, g2, Bls12_381, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective,
+};
+use ark_ec::{
+    hashing::{curve_maps::wb::WBMap, map_to_curve_hasher::MapToCurve},
+    pairing::Pairing,
+    AffineRepr, CurveGroup, VariableBaseMSM,
+};
+use ark_ff::{BigInteger, One, PrimeField, Zero};
+use bytes::Bytes;
+
+use crate::{
+    errors::{InternalError, PrecompileError, VMError},
+    gas_cost::{
+        bls12_381_msm, bls12_381_pairing, BLS12_381_G1ADD_COST, BLS12_381_G1_MSM_DISCOUNTS,
+        BLS12_381_G1_MUL_COST, BLS12_381_G2ADD_COST, BLS12_381_G2_MSM_DISCOUNTS,
+        BLS12_381_G2_MUL_COST, BLS12_381_MAP_FP2_TO_G2_COST, BLS12_381_MAP_FP_TO_G1_COST,
+    },
+};
+
+use super::increase_precompile_consumed_gas;
+
+/// Field elements are encoded in 64 bytes, the top 16 of them being zero
+const PADDED_FP_LENGTH: usize = 64;
+const FP_PADDING_LENGTH: usize = 16;
+const PADDED_FP2_LENGTH: usize = 128;
+const G1_POINT_LENGTH: usize = 128;
+const G2_POINT_LENGTH: usize = 256;
+const SCALAR_LENGTH: usize = 32;
+const G1_MSM_PAIR_LENGTH: usize = G1_POINT_LENGTH + SCALAR_LENGTH;
+const G2_MSM_PAIR_LENGTH: usize = G2_POINT_LENGTH + SCALAR_LENGTH;
+const PAIRING_PAIR_LENGTH: usize = G1_POINT_LENGTH + G2_POINT_LENGTH;
+
+/// Big endian encoding of the modulus of the base field
+const FP_MODULUS: [u8; 48] = [
+    0x1a, 0x01, 0x11, 0xea, 0x39, 0x7f, 0xe6, 0x9a, 0x4b, 0x1b, 0xa7, 0xb6, 0x43, 0x4b, 0xac, 0xd7,
+    0x64, 0x77, 0x4b, 0x84, 0xf3, 0x85, 0x12, 0xbf, 0x67, 0x30, 0xd2, 0xa0, 0xf6, 0xb0, 0xf6, 0x24,
+    0x1e, 0xab, 0xff, 0xfe, 0xb1, 0x53, 0xff, 0xff, 0xb9, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xaa, 0xab,
+];
+
+// Group operations on curve points can't overflow
+#[allow(clippy::arithmetic_side_effects)]
+pub fn g1_add(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    increase_precompile_consumed_gas(gas_for_call, BLS12_381_G1ADD_COST, consumed_gas)?;
+
+    if calldata.len() != G1_POINT_LENGTH * 2 {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    // Addition doesn't require the points to be in the subgroup
+    let (first, second) = calldata.split_at(G1_POINT_LENGTH);
+    let first = parse_g1_point(first, false)?;
+    let second = parse_g1_point(second, false)?;
+
+    Ok(encode_g1_point((first + second).into_affine()))
+}
+
+pub fn g1_msm(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    if calldata.is_empty() || calldata.len() % G1_MSM_PAIR_LENGTH != 0 {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let pairs = calldata.len() / G1_MSM_PAIR_LENGTH;
+    let gas_cost = bls12_381_msm(pairs, BLS12_381_G1_MUL_COST, &BLS12_381_G1_MSM_DISCOUNTS)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    let mut points = Vec::with_capacity(pairs);
+    let mut scalars = Vec::with_capacity(pairs);
+    for pair in calldata.chunks_exact(G1_MSM_PAIR_LENGTH) {
+        let (point, scalar) = pair.split_at(G1_POINT_LENGTH);
+        points.push(parse_g1_point(point, true)?);
+        scalars.push(parse_scalar(scalar)?);
+    }
+
+    Ok(encode_g1_point(
+        G1Projective::msm_unchecked(&points, &scalars).into_affine(),
+    ))
+}
+
+// Group operations on curve points can't overflow
+#[allow(clippy::arithmetic_side_effects)]
+pub fn g2_add(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    increase_precompile_consumed_gas(gas_for_call, BLS12_381_G2ADD_COST, consumed_gas)?;
+
+    if calldata.len() != G2_POINT_LENGTH * 2 {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    // Addition doesn't require the points to be in the subgroup
+    let (first, second) = calldata.split_at(G2_POINT_LENGTH);
+    let first = parse_g2_point(first, false)?;
+    let second = parse_g2_point(second, false)?;
+
+    Ok(encode_g2_point((first + second).into_affine()))
+}
+
+pub fn g2_msm(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    if calldata.is_empty() || calldata.len() % G2_MSM_PAIR_LENGTH != 0 {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let pairs = calldata.len() / G2_MSM_PAIR_LENGTH;
+    let gas_cost = bls12_381_msm(pairs, BLS12_381_G2_MUL_COST, &BLS12_381_G2_MSM_DISCOUNTS)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    let mut points = Vec::with_capacity(pairs);
+    let mut scalars = Vec::with_capacity(pairs);
+    for pair in calldata.chunks_exact(G2_MSM_PAIR_LENGTH) {
+        let (point, scalar) = pair.split_at(G2_POINT_LENGTH);
+        points.push(parse_g2_point(point, true)?);
+        scalars.push(parse_scalar(scalar)?);
+    }
+
+    Ok(encode_g2_point(
+        G2Projective::msm_unchecked(&points, &scalars).into_affine(),
+    ))
+}
+
+pub fn pairing(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    if calldata.is_empty() || calldata.len() % PAIRING_PAIR_LENGTH != 0 {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let gas_cost = bls12_381_pairing(calldata.len() / PAIRING_PAIR_LENGTH)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    let mut g1_points = Vec::new();
+    let mut g2_points = Vec::new();
+    for pair in calldata.chunks_exact(PAIRING_PAIR_LENGTH) {
+        let (g1, g2) = pair.split_at(G1_POINT_LENGTH);
+        let g1 = parse_g1_point(g1, true)?;
+        let g2 = parse_g2_point(g2, true)?;
+
+        // Pairs with a point at infinity evaluate to one
+        if g1.is_zero() || g2.is_zero() {
+            continue;
+        }
+        g1_points.push(g1);
+        g2_points.push(g2);
+    }
+
+    let is_one = Bls12_381::multi_pairing(g1_points, g2_points).0.is_one();
+
+    let mut output = [0u8; 32];
+    output[31] = u8::from(is_one);
+    Ok(Bytes::copy_from_slice(&output))
+}
+
+pub fn map_fp_to_g1(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    increase_precompile_consumed_gas(gas_for_call, BLS12_381_MAP_FP_TO_G1_COST, consumed_gas)?;
+
+    if calldata.len() != PADDED_FP_LENGTH {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let element = parse_fp(calldata)?;
+
+    let point = WBMap::<g1::Config>::new()
+        .and_then(|map| map.map_to_curve(element))
+        .map_err(|_| InternalError::MapToCurveError)?;
+
+    Ok(encode_g1_point(point.clear_cofactor()))
+}
+
+pub fn map_fp2_to_g2(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    increase_precompile_consumed_gas(gas_for_call, BLS12_381_MAP_FP2_TO_G2_COST, consumed_gas)?;
+
+    if calldata.len() != PADDED_FP2_LENGTH {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let element = parse_fp2(calldata)?;
+
+    let point = WBMap::<g2::Config>::new()
+        .and_then(|map| map.map_to_curve(element))
+        .map_err(|_| InternalError::MapToCurveError)?;
+
+    Ok(encode_g2_point(point.clear_cofactor()))
+}
+
+/// Parses a base field element, checking its padding and that it's lower than the modulus
+fn parse_fp(bytes: &[u8]) -> Result<Fq, VMError> {
+    let (padding, element) = bytes
+        .get(..PADDED_FP_LENGTH)
+        .ok_or(InternalError::SlicingError)?
+        .split_at(FP_PADDING_LENGTH);
+
+    // Big endian comparison against the modulus
+    if padding.iter().any(|byte| *byte != 0) || element >= FP_MODULUS.as_slice() {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    // The element is lower than the modulus, so it's not reduced
+    Ok(Fq::from_be_bytes_mod_order(element))
+}
+
+/// Parses an element of the quadratic extension field, encoded as its two coefficients
+fn parse_fp2(bytes: &[u8]) -> Result<Fq2, VMError> {
+    let c0 = parse_fp(
+        bytes
+            .get(..PADDED_FP_LENGTH)
+            .ok_or(InternalError::SlicingError)?,
+    )?;
+    let c1 = parse_fp(
+        bytes
+            .get(PADDED_FP_LENGTH..PADDED_FP2_LENGTH)
+            .ok_or(InternalError::SlicingError)?,
+    )?;
+    Ok(Fq2::new(c0, c1))
+}
+
+/// Parses a G1 point encoded as its two coordinates, the point at infinity being all zeros.
+/// The point is checked to be on the curve and, if required, in the subgroup
+fn parse_g1_point(bytes: &[u8], subgroup_check: bool) -> Result<G1Affine, VMError> {
+    let x = parse_fp(
+        bytes
+            .get(..PADDED_FP_LENGTH)
+            .ok_or(InternalError::SlicingError)?,
+    )?;
+    let y = parse_fp(
+        bytes
+            .get(PADDED_FP_LENGTH..G1_POINT_LENGTH)
+            .ok_or(InternalError::SlicingError)?,
+    )?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::zero());
+    }
+
+    let point = G1Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(VMError::PrecompileError(PrecompileError::PointNotOnCurve));
+    }
+    if subgroup_check && !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(VMError::PrecompileError(
+            PrecompileError::PointNotInSubgroup,
+        ));
+    }
+    Ok(point)
+}
+
+/// Parses a G2 point encoded as its two coordinates, the point at infinity being all zeros.
+/// The point is checked to be on the curve and, if required, in the subgroup
+fn parse_g2_point(bytes: &[u8], subgroup_check: bool) -> Result<G2Affine, VMError> {
+    let x = parse_fp2(
+        bytes
+            .get(..PADDED_FP2_LENGTH)
+            .ok_or(InternalError::SlicingError)?,
+    )?;
+    let y = parse_fp2(
+        bytes
+            .get(PADDED_FP2_LENGTH..G2_POINT_LENGTH)
+            .ok_or(InternalError::SlicingError)?,
+    )?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2Affine::zero());
+    }
+
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(VMError::PrecompileError(PrecompileError::PointNotOnCurve));
+    }
+    if subgroup_check && !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(VMError::PrecompileError(
+            PrecompileError::PointNotInSubgroup,
+        ));
+    }
+    Ok(point)
+}
+
+/// Scalars are 32 bytes big endian integers. They can be reduced modulo the group order, as
+/// multiplications are only performed on points that were checked to be in the subgroup
+fn parse_scalar(bytes: &[u8]) -> Result<Fr, VMError> {
+    if bytes.len() != SCALAR_LENGTH {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+    Ok(Fr::from_be_bytes_mod_order(bytes))
+}
+
+fn encode_fp(element: &Fq) -> [u8; PADDED_FP_LENGTH] {
+    let mut output = [0u8; PADDED_FP_LENGTH];
+    let (_padding, encoded) = output.split_at_mut(FP_PADDING_LENGTH);
+    encoded.copy_from_slice(&element.into_bigint().to_bytes_be());
+    output
+}
+
+/// Encodes a G1 point as its two coordinates, the point at infinity being all zeros
+fn encode_g1_point(point: G1Affine) -> Bytes {
+    match point.xy() {
+        Some((x, y)) => [encode_fp(x), encode_fp(y)].concat().into(),
+        None => Bytes::copy_from_slice(&[0; G1_POINT_LENGTH]),
+    }
+}
+
+/// Encodes a G2 point as its two coordinates, the point at infinity being all zeros
+fn encode_g2_point(point: G2Affine) -> Bytes {
+    match point.xy() {
+        Some((x, y)) => [
+            encode_fp(&x.c0),
+            encode_fp(&x.c1),
+            encode_fp(&y.c0),
+            encode_fp(&y.c1),
+        ]
+        .concat()
+        .into(),
+        None => Bytes::copy_from_slice(&[0; G2_POINT_LENGTH]),
+    }
+}
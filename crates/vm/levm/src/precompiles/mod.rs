@@ -0,0 +1,819 @@
+use bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+use bytes::Bytes;
+use c_kzg::{ethereum_kzg_settings, Bytes32, Bytes48, KzgProof};
+use ethrex_core::{Address, H160, U256};
+use keccak_hash::keccak256;
+use libsecp256k1::{self, Message, RecoveryId, Signature};
+use sha3::Digest;
+use std::collections::HashMap;
+
+use crate::{
+    call_frame::CallFrame,
+    constants::{BLS_MODULUS, FIELD_ELEMENTS_PER_BLOB, VERSIONED_HASH_VERSION_KZG},
+    errors::{InternalError, PrecompileError, VMError},
+    fork::Fork,
+    gas_cost::{
+        blake2f as blake2f_cost, ecpairing as ecpairing_cost,
+        ecpairing_byzantium as ecpairing_byzantium_cost, identity as identity_cost,
+        modexp as modexp_cost, modexp_byzantium as modexp_byzantium_cost,
+        ripemd_160 as ripemd_160_cost, sha2_256 as sha2_256_cost, ECADD_BYZANTIUM_COST, ECADD_COST,
+        ECMUL_BYZANTIUM_COST, ECMUL_COST, ECRECOVER_COST, POINT_EVALUATION_COST,
+    },
+};
+
+pub mod bls12_381;
+
+pub const ECRECOVER_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x01,
+]);
+pub const SHA2_256_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x02,
+]);
+pub const RIPEMD_160_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x03,
+]);
+pub const IDENTITY_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x04,
+]);
+pub const MODEXP_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x05,
+]);
+pub const ECADD_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x06,
+]);
+pub const ECMUL_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x07,
+]);
+pub const ECPAIRING_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x08,
+]);
+pub const BLAKE2F_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x09,
+]);
+pub const POINT_EVALUATION_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0a,
+]);
+
+pub const BLS12_381_G1ADD_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0b,
+]);
+pub const BLS12_381_G1MSM_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0c,
+]);
+pub const BLS12_381_G2ADD_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0d,
+]);
+pub const BLS12_381_G2MSM_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0e,
+]);
+pub const BLS12_381_PAIRING_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x0f,
+]);
+pub const BLS12_381_MAP_FP_TO_G1_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x10,
+]);
+pub const BLS12_381_MAP_FP2_TO_G2_ADDRESS: H160 = H160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x11,
+]);
+
+/// Signature shared by all precompiled contracts. Receives the calldata and the gas available for
+/// the call, adds the gas it uses to the consumed gas and returns the output of the call
+pub type PrecompileFn = fn(&Bytes, u64, &mut u64) -> Result<Bytes, VMError>;
+
+/// Precompiled contracts defined by Ethereum, along with the fork they are available from
+/// Precompiles whose gas cost changed are listed once per pricing, in activation order, so the
+/// latest one available in a fork replaces the previous ones
+pub const ETHEREUM_PRECOMPILES: [(H160, Fork, PrecompileFn); 21] = [
+    (ECRECOVER_ADDRESS, Fork::Frontier, ecrecover),
+    (SHA2_256_ADDRESS, Fork::Frontier, sha2_256),
+    (RIPEMD_160_ADDRESS, Fork::Frontier, ripemd_160),
+    (IDENTITY_ADDRESS, Fork::Frontier, identity),
+    (MODEXP_ADDRESS, Fork::Byzantium, modexp_byzantium),
+    (MODEXP_ADDRESS, Fork::Berlin, modexp),
+    (ECADD_ADDRESS, Fork::Byzantium, ecadd_byzantium),
+    (ECADD_ADDRESS, Fork::Istanbul, ecadd),
+    (ECMUL_ADDRESS, Fork::Byzantium, ecmul_byzantium),
+    (ECMUL_ADDRESS, Fork::Istanbul, ecmul),
+    (ECPAIRING_ADDRESS, Fork::Byzantium, ecpairing_byzantium),
+    (ECPAIRING_ADDRESS, Fork::Istanbul, ecpairing),
+    (BLAKE2F_ADDRESS, Fork::Istanbul, blake2f),
+    (POINT_EVALUATION_ADDRESS, Fork::Cancun, point_evaluation),
+    (BLS12_381_G1ADD_ADDRESS, Fork::Prague, bls12_381::g1_add),
+    (BLS12_381_G1MSM_ADDRESS, Fork::Prague, bls12_381::g1_msm),
+    (BLS12_381_G2ADD_ADDRESS, Fork::Prague, bls12_381::g2_add),
+    (BLS12_381_G2MSM_ADDRESS, Fork::Prague, bls12_381::g2_msm),
+    (BLS12_381_PAIRING_ADDRESS, Fork::Prague, bls12_381::pairing),
+    (
+        BLS12_381_MAP_FP_TO_G1_ADDRESS,
+        Fork::Prague,
+        bls12_381::map_fp_to_g1,
+    ),
+    (
+        BLS12_381_MAP_FP2_TO_G2_ADDRESS,
+        Fork::Prague,
+        bls12_381::map_fp2_to_g2,
+    ),
+];
+
+/// Precompiled contracts available to a transaction, by address
+#[derive(Debug, Clone, Default)]
+pub struct PrecompileRegistry {
+    precompiles: HashMap<Address, PrecompileFn>,
+}
+
+impl PrecompileRegistry {
+    /// Returns a registry with the Ethereum precompiles available in the given fork
+    pub fn for_fork(fork: Fork) -> Self {
+        let precompiles = ETHEREUM_PRECOMPILES
+            .iter()
+            .filter(|(_, activation_fork, _)| *activation_fork <= fork)
+            .map(|(address, _, precompile)| (*address, *precompile))
+            .collect();
+        Self { precompiles }
+    }
+
+    /// Registers a precompile at the given address, returning the one it replaces, if any.
+    /// Allows L2s to define their own precompiles, or to override the Ethereum ones
+    pub fn register(&mut self, address: Address, precompile: PrecompileFn) -> Option<PrecompileFn> {
+        self.precompiles.insert(address, precompile)
+    }
+
+    pub fn is_precompile(&self, address: &Address) -> bool {
+        self.precompiles.contains_key(address)
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        self.precompiles.keys()
+    }
+
+    /// Executes the precompile at the code address of the call frame, consuming its gas
+    pub fn execute(&self, current_call_frame: &mut CallFrame) -> Result<Bytes, VMError> {
+        let precompile = self
+            .precompiles
+            .get(&current_call_frame.code_address)
+            .ok_or(VMError::Internal(InternalError::InvalidPrecompileAddress))?;
+
+        precompile(
+            &current_call_frame.calldata,
+            current_call_frame.gas_limit,
+            &mut current_call_frame.gas_used,
+        )
+    }
+}
+
+/// Verifies if the gas cost is higher than the gas limit and consumes the gas cost if it is not
+fn increase_precompile_consumed_gas(
+    gas_for_call: u64,
+    gas_cost: u64,
+    consumed_gas: &mut u64,
+) -> Result<(), VMError> {
+    if gas_for_call < gas_cost {
+        return Err(VMError::PrecompileError(PrecompileError::NotEnoughGas));
+    }
+
+    *consumed_gas = consumed_gas
+        .checked_add(gas_cost)
+        .ok_or(PrecompileError::GasConsumedOverflow)?;
+
+    Ok(())
+}
+
+/// When slice length is less than 128, the rest is filled with zeros. If slice length is
+/// more than 128 the excess bytes are discarded.
+fn fill_with_zeros(slice: &[u8]) -> Result<[u8; 128], VMError> {
+    let mut result = [0; 128];
+
+    let n = slice.len().min(128);
+
+    let trimmed_slice: &[u8] = slice.get(..n).unwrap_or_default();
+
+    for i in 0..n {
+        let byte: &mut u8 = result.get_mut(i).ok_or(InternalError::SlicingError)?;
+        *byte = *trimmed_slice.get(i).ok_or(InternalError::SlicingError)?;
+    }
+
+    Ok(result)
+}
+
+pub fn ecrecover(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    let gas_cost = ECRECOVER_COST;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    // If calldata does not reach the required length, we should fill the rest with zeros
+    let calldata = fill_with_zeros(calldata)?;
+
+    // Parse the input elements, first as a slice of bytes and then as an specific type of the crate
+    let hash = calldata.get(0..32).ok_or(InternalError::SlicingError)?;
+    let message = Message::parse_slice(hash).map_err(|_| PrecompileError::ParsingInputError)?;
+
+    let v: U256 = calldata
+        .get(32..64)
+        .ok_or(InternalError::SlicingError)?
+        .into();
+
+    // The Recovery identifier is expected to be 27 or 28, any other value is invalid
+    if !(v == U256::from(27) || v == U256::from(28)) {
+        return Ok(Bytes::new());
+    }
+
+    let v = u8::try_from(v).map_err(|_| InternalError::ConversionError)?;
+    let recovery_id = match RecoveryId::parse_rpc(v) {
+        Ok(id) => id,
+        Err(_) => {
+            return Ok(Bytes::new());
+        }
+    };
+
+    // signature is made up of the parameters r and s
+    let sig = calldata.get(64..128).ok_or(InternalError::SlicingError)?;
+    let signature =
+        Signature::parse_standard_slice(sig).map_err(|_| PrecompileError::ParsingInputError)?;
+
+    // Recover the address using secp256k1
+    let mut public_key = match libsecp256k1::recover(&message, &signature, &recovery_id) {
+        Ok(id) => id,
+        Err(_) => {
+            return Ok(Bytes::new());
+        }
+    }
+    .serialize();
+
+    // We need to take the 64 bytes from the public key (discarding the first pos of the slice)
+    keccak256(&mut public_key[1..65]);
+
+    // The output is 32 bytes: the initial 12 bytes with 0s, and the remaining 20 with the recovered address
+    let mut output = vec![0u8; 12];
+    output.extend_from_slice(public_key.get(13..33).ok_or(InternalError::SlicingError)?);
+
+    Ok(Bytes::from(output.to_vec()))
+}
+
+pub fn identity(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    let gas_cost = identity_cost(calldata.len())?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    Ok(calldata.clone())
+}
+
+pub fn sha2_256(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    let gas_cost = sha2_256_cost(calldata.len())?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    let result = sha2::Sha256::digest(calldata).to_vec();
+
+    Ok(Bytes::from(result))
+}
+
+pub fn ripemd_160(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    let gas_cost = ripemd_160_cost(calldata.len())?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    let mut hasher = ripemd::Ripemd160::new();
+    hasher.update(calldata);
+    let result = hasher.finalize();
+
+    let mut output = vec![0; 12];
+    output.extend_from_slice(&result);
+
+    Ok(Bytes::from(output))
+}
+
+/// Returns `size` bytes of the calldata starting at `offset`. If the calldata is shorter the rest
+/// is filled with zeros
+fn get_slice_or_zeros(calldata: &Bytes, offset: usize, size: usize) -> Result<Vec<u8>, VMError> {
+    let mut result = vec![0; size];
+
+    let available = calldata.get(offset..).unwrap_or_default();
+    let n = available.len().min(size);
+
+    result
+        .get_mut(..n)
+        .ok_or(InternalError::SlicingError)?
+        .copy_from_slice(available.get(..n).ok_or(InternalError::SlicingError)?);
+
+    Ok(result)
+}
+
+/// Parses the size stored in the 32 byte word at `offset`. Sizes that don't fit in a u64 would
+/// cost more gas than any call could have
+fn parse_modexp_size(calldata: &Bytes, offset: usize) -> Result<u64, VMError> {
+    let size = U256::from_big_endian(&get_slice_or_zeros(calldata, offset, 32)?);
+    size.try_into()
+        .map_err(|_| VMError::PrecompileError(PrecompileError::NotEnoughGas))
+}
+
+fn to_usize(value: u64) -> Result<usize, VMError> {
+    value
+        .try_into()
+        .map_err(|_| VMError::Internal(InternalError::ConversionError))
+}
+
+/// MODEXP as priced since Berlin, by [EIP-2565](https://eips.ethereum.org/EIPS/eip-2565)
+pub fn modexp(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    modexp_with_cost(calldata, gas_for_call, consumed_gas, modexp_cost)
+}
+
+/// MODEXP as priced before Berlin, by [EIP-198](https://eips.ethereum.org/EIPS/eip-198)
+pub fn modexp_byzantium(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    modexp_with_cost(calldata, gas_for_call, consumed_gas, modexp_byzantium_cost)
+}
+
+/// Computes MODEXP, `gas_cost` being given the exponent head and the sizes of the base, exponent
+/// and modulus
+fn modexp_with_cost(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+    gas_cost: fn(U256, u64, u64, u64) -> Result<u64, VMError>,
+) -> Result<Bytes, VMError> {
+    // The sizes of the base, exponent and modulus are stored in the first three words
+    let base_size = parse_modexp_size(calldata, 0)?;
+    let exponent_size = parse_modexp_size(calldata, 32)?;
+    let modulus_size = parse_modexp_size(calldata, 64)?;
+
+    let base_offset: usize = 96;
+    let exponent_offset = base_offset
+        .checked_add(to_usize(base_size)?)
+        .ok_or(InternalError::ArithmeticOperationOverflow)?;
+    let modulus_offset = exponent_offset
+        .checked_add(to_usize(exponent_size)?)
+        .ok_or(InternalError::ArithmeticOperationOverflow)?;
+
+    // Only the first 32 bytes of the exponent are used to compute the gas cost
+    let exponent_head_size = exponent_size.min(32);
+    let exponent_head = U256::from_big_endian(&get_slice_or_zeros(
+        calldata,
+        exponent_offset,
+        to_usize(exponent_head_size)?,
+    )?);
+
+    let gas_cost = gas_cost(exponent_head, base_size, exponent_size, modulus_size)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    if modulus_size == 0 {
+        return Ok(Bytes::new());
+    }
+
+    // Having paid for the call, the base and modulus sizes are bounded by the gas limit
+    let base = get_slice_or_zeros(calldata, base_offset, to_usize(base_size)?)?;
+    let exponent = get_slice_or_zeros(calldata, exponent_offset, to_usize(exponent_size)?)?;
+    let modulus = get_slice_or_zeros(calldata, modulus_offset, to_usize(modulus_size)?)?;
+
+    let result = aurora_engine_modexp::modexp(&base, &exponent, &modulus);
+
+    // The result is left padded with zeros to the size of the modulus
+    let mut output = vec![0; to_usize(modulus_size)?.saturating_sub(result.len())];
+    output.extend(result);
+
+    Ok(Bytes::from(output))
+}
+
+/// Parses a BN254 G1 point encoded as its two 32 byte coordinates. (0, 0) is the point at infinity
+fn parse_bn254_g1(bytes: &[u8]) -> Result<G1, VMError> {
+    let x = parse_bn254_field_element(bytes.get(0..32).ok_or(InternalError::SlicingError)?)?;
+    let y = parse_bn254_field_element(bytes.get(32..64).ok_or(InternalError::SlicingError)?)?;
+
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1::zero());
+    }
+
+    AffineG1::new(x, y)
+        .map(G1::from)
+        .map_err(|_| VMError::PrecompileError(PrecompileError::PointNotOnCurve))
+}
+
+/// Parses a BN254 G2 point encoded as the imaginary and real parts of each of its coordinates
+fn parse_bn254_g2(bytes: &[u8]) -> Result<G2, VMError> {
+    let x_imaginary =
+        parse_bn254_field_element(bytes.get(0..32).ok_or(InternalError::SlicingError)?)?;
+    let x_real = parse_bn254_field_element(bytes.get(32..64).ok_or(InternalError::SlicingError)?)?;
+    let y_imaginary =
+        parse_bn254_field_element(bytes.get(64..96).ok_or(InternalError::SlicingError)?)?;
+    let y_real = parse_bn254_field_element(bytes.get(96..128).ok_or(InternalError::SlicingError)?)?;
+
+    let x = Fq2::new(x_real, x_imaginary);
+    let y = Fq2::new(y_real, y_imaginary);
+
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2::zero());
+    }
+
+    AffineG2::new(x, y)
+        .map(G2::from)
+        .map_err(|_| VMError::PrecompileError(PrecompileError::PointNotOnCurve))
+}
+
+fn parse_bn254_field_element(bytes: &[u8]) -> Result<Fq, VMError> {
+    Fq::from_slice(bytes).map_err(|_| VMError::PrecompileError(PrecompileError::ParsingInputError))
+}
+
+/// Encodes a BN254 G1 point as its two 32 byte coordinates, the point at infinity being (0, 0)
+fn encode_bn254_g1(point: G1) -> Result<Bytes, VMError> {
+    let mut output = [0u8; 64];
+
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        let (x, y) = output.split_at_mut(32);
+        point
+            .x()
+            .to_big_endian(x)
+            .map_err(|_| InternalError::ConversionError)?;
+        point
+            .y()
+            .to_big_endian(y)
+            .map_err(|_| InternalError::ConversionError)?;
+    }
+
+    Ok(Bytes::copy_from_slice(&output))
+}
+
+pub fn ecadd(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    ecadd_with_cost(calldata, gas_for_call, consumed_gas, ECADD_COST)
+}
+
+/// ECADD as priced before Istanbul
+pub fn ecadd_byzantium(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    ecadd_with_cost(calldata, gas_for_call, consumed_gas, ECADD_BYZANTIUM_COST)
+}
+
+// Group operations on curve points can't overflow
+#[allow(clippy::arithmetic_side_effects)]
+fn ecadd_with_cost(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+    gas_cost: u64,
+) -> Result<Bytes, VMError> {
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    // If calldata does not reach the required length, we should fill the rest with zeros
+    let calldata = fill_with_zeros(calldata)?;
+
+    let first_point = parse_bn254_g1(calldata.get(0..64).ok_or(InternalError::SlicingError)?)?;
+    let second_point = parse_bn254_g1(calldata.get(64..128).ok_or(InternalError::SlicingError)?)?;
+
+    encode_bn254_g1(first_point + second_point)
+}
+
+pub fn ecmul(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    ecmul_with_cost(calldata, gas_for_call, consumed_gas, ECMUL_COST)
+}
+
+/// ECMUL as priced before Istanbul
+pub fn ecmul_byzantium(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    ecmul_with_cost(calldata, gas_for_call, consumed_gas, ECMUL_BYZANTIUM_COST)
+}
+
+// Group operations on curve points can't overflow
+#[allow(clippy::arithmetic_side_effects)]
+fn ecmul_with_cost(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+    gas_cost: u64,
+) -> Result<Bytes, VMError> {
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    // If calldata does not reach the required length, we should fill the rest with zeros
+    let calldata = fill_with_zeros(calldata)?;
+
+    let point = parse_bn254_g1(calldata.get(0..64).ok_or(InternalError::SlicingError)?)?;
+    let scalar = Fr::from_slice(calldata.get(64..96).ok_or(InternalError::SlicingError)?)
+        .map_err(|_| PrecompileError::ParsingInputError)?;
+
+    encode_bn254_g1(point * scalar)
+}
+
+/// Length of each (G1, G2) pair in the calldata of ECPAIRING
+const ECPAIRING_PAIR_LENGTH: usize = 192;
+
+pub fn ecpairing(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    ecpairing_with_cost(calldata, gas_for_call, consumed_gas, ecpairing_cost)
+}
+
+/// ECPAIRING as priced before Istanbul
+pub fn ecpairing_byzantium(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    ecpairing_with_cost(
+        calldata,
+        gas_for_call,
+        consumed_gas,
+        ecpairing_byzantium_cost,
+    )
+}
+
+/// Computes ECPAIRING, `gas_cost` being given the number of pairs
+fn ecpairing_with_cost(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+    gas_cost: fn(usize) -> Result<u64, VMError>,
+) -> Result<Bytes, VMError> {
+    let gas_cost = gas_cost(calldata.len() / ECPAIRING_PAIR_LENGTH)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    if calldata.len() % ECPAIRING_PAIR_LENGTH != 0 {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let mut pairs = Vec::new();
+    for pair in calldata.chunks_exact(ECPAIRING_PAIR_LENGTH) {
+        let g1 = parse_bn254_g1(pair.get(0..64).ok_or(InternalError::SlicingError)?)?;
+        let g2 = parse_bn254_g2(pair.get(64..192).ok_or(InternalError::SlicingError)?)?;
+        pairs.push((g1, g2));
+    }
+
+    let success = bn::pairing_batch(&pairs) == Gt::one();
+
+    let mut output = [0u8; 32];
+    output[31] = u8::from(success);
+    Ok(Bytes::copy_from_slice(&output))
+}
+
+/// Initialization vector of BLAKE2b
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// Message word permutations of BLAKE2b, one per round
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+const BLAKE2F_INPUT_LENGTH: usize = 213;
+
+/// BLAKE2b mixing function, applied to the four columns of the working vector at once.
+/// The working vector is split in its four rows
+fn blake2b_mix(rows: &mut [[u64; 4]; 4], x: [u64; 4], y: [u64; 4]) {
+    let [a, b, c, d] = rows;
+    for (((((a, b), c), d), x), y) in a
+        .iter_mut()
+        .zip(b.iter_mut())
+        .zip(c.iter_mut())
+        .zip(d.iter_mut())
+        .zip(x)
+        .zip(y)
+    {
+        *a = a.wrapping_add(*b).wrapping_add(x);
+        *d = (*d ^ *a).rotate_right(32);
+        *c = c.wrapping_add(*d);
+        *b = (*b ^ *c).rotate_right(24);
+        *a = a.wrapping_add(*b).wrapping_add(y);
+        *d = (*d ^ *a).rotate_right(16);
+        *c = c.wrapping_add(*d);
+        *b = (*b ^ *c).rotate_right(63);
+    }
+}
+
+/// BLAKE2b compression function F, as defined in [RFC 7693](https://datatracker.ietf.org/doc/html/rfc7693#section-3.2)
+fn blake2b_compress(
+    rounds: u32,
+    state: &mut [u64; 8],
+    message: &[u64; 16],
+    offset_counter: [u64; 2],
+    final_block: bool,
+) -> Result<(), VMError> {
+    let [h0, h1, h2, h3, h4, h5, h6, h7] = *state;
+    let [iv0, iv1, iv2, iv3, iv4, iv5, iv6, iv7] = BLAKE2B_IV;
+    let [t0, t1] = offset_counter;
+    let mut rows = [
+        [h0, h1, h2, h3],
+        [h4, h5, h6, h7],
+        [iv0, iv1, iv2, iv3],
+        [
+            iv4 ^ t0,
+            iv5 ^ t1,
+            if final_block { !iv6 } else { iv6 },
+            iv7,
+        ],
+    ];
+
+    for sigma in BLAKE2B_SIGMA.iter().cycle().take(to_usize(rounds.into())?) {
+        let mut words = [0u64; 16];
+        for (word, index) in words.iter_mut().zip(sigma) {
+            *word = *message.get(*index).ok_or(InternalError::SlicingError)?;
+        }
+        let [w0, w1, w2, w3, w4, w5, w6, w7, w8, w9, w10, w11, w12, w13, w14, w15] = words;
+
+        // Mix the columns
+        blake2b_mix(&mut rows, [w0, w2, w4, w6], [w1, w3, w5, w7]);
+
+        // Mix the diagonals, by rotating the rows so that they become columns
+        let [_, b, c, d] = &mut rows;
+        b.rotate_left(1);
+        c.rotate_left(2);
+        d.rotate_left(3);
+        blake2b_mix(&mut rows, [w8, w10, w12, w14], [w9, w11, w13, w15]);
+        let [_, b, c, d] = &mut rows;
+        b.rotate_right(1);
+        c.rotate_right(2);
+        d.rotate_right(3);
+    }
+
+    let [[v0, v1, v2, v3], [v4, v5, v6, v7], [v8, v9, v10, v11], [v12, v13, v14, v15]] = rows;
+    *state = [
+        h0 ^ v0 ^ v8,
+        h1 ^ v1 ^ v9,
+        h2 ^ v2 ^ v10,
+        h3 ^ v3 ^ v11,
+        h4 ^ v4 ^ v12,
+        h5 ^ v5 ^ v13,
+        h6 ^ v6 ^ v14,
+        h7 ^ v7 ^ v15,
+    ];
+
+    Ok(())
+}
+
+/// Parses consecutive little endian u64 words
+fn parse_u64_le_words<const N: usize>(bytes: &[u8]) -> Result<[u64; N], VMError> {
+    let mut words = [0u64; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(
+            chunk
+                .try_into()
+                .map_err(|_| InternalError::ConversionError)?,
+        );
+    }
+    Ok(words)
+}
+
+pub fn blake2f(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    if calldata.len() != BLAKE2F_INPUT_LENGTH {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    let rounds = u32::from_be_bytes(
+        calldata
+            .get(0..4)
+            .ok_or(InternalError::SlicingError)?
+            .try_into()
+            .map_err(|_| InternalError::ConversionError)?,
+    );
+
+    let gas_cost = blake2f_cost(rounds)?;
+
+    increase_precompile_consumed_gas(gas_for_call, gas_cost, consumed_gas)?;
+
+    let mut state: [u64; 8] =
+        parse_u64_le_words(calldata.get(4..68).ok_or(InternalError::SlicingError)?)?;
+    let message: [u64; 16] =
+        parse_u64_le_words(calldata.get(68..196).ok_or(InternalError::SlicingError)?)?;
+    let offset_counter: [u64; 2] =
+        parse_u64_le_words(calldata.get(196..212).ok_or(InternalError::SlicingError)?)?;
+    let final_block = match calldata.get(212) {
+        Some(0) => false,
+        Some(1) => true,
+        _ => return Err(VMError::PrecompileError(PrecompileError::ParsingInputError)),
+    };
+
+    blake2b_compress(rounds, &mut state, &message, offset_counter, final_block)?;
+
+    Ok(Bytes::from(
+        state
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<u8>>(),
+    ))
+}
+
+const POINT_EVALUATION_INPUT_LENGTH: usize = 192;
+
+/// Verifies a KZG proof that the blob committed to by the versioned hash evaluates to `y` at `z`,
+/// as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844#point-evaluation-precompile)
+pub fn point_evaluation(
+    calldata: &Bytes,
+    gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    if calldata.len() != POINT_EVALUATION_INPUT_LENGTH {
+        return Err(VMError::PrecompileError(PrecompileError::ParsingInputError));
+    }
+
+    increase_precompile_consumed_gas(gas_for_call, POINT_EVALUATION_COST, consumed_gas)?;
+
+    let versioned_hash = calldata.get(0..32).ok_or(InternalError::SlicingError)?;
+    let z = calldata.get(32..64).ok_or(InternalError::SlicingError)?;
+    let y = calldata.get(64..96).ok_or(InternalError::SlicingError)?;
+    let commitment = calldata.get(96..144).ok_or(InternalError::SlicingError)?;
+    let proof = calldata.get(144..192).ok_or(InternalError::SlicingError)?;
+
+    let mut commitment_hash = sha2::Sha256::digest(commitment);
+    *commitment_hash
+        .first_mut()
+        .ok_or(InternalError::SlicingError)? = VERSIONED_HASH_VERSION_KZG;
+    if commitment_hash.as_slice() != versioned_hash {
+        return Err(VMError::PrecompileError(PrecompileError::InvalidKzgProof));
+    }
+
+    let parsing_error = |_| VMError::PrecompileError(PrecompileError::ParsingInputError);
+    let verified = KzgProof::verify_kzg_proof(
+        &Bytes48::from_bytes(commitment).map_err(parsing_error)?,
+        &Bytes32::from_bytes(z).map_err(parsing_error)?,
+        &Bytes32::from_bytes(y).map_err(parsing_error)?,
+        &Bytes48::from_bytes(proof).map_err(parsing_error)?,
+        ethereum_kzg_settings(),
+    )
+    .unwrap_or(false);
+    if !verified {
+        return Err(VMError::PrecompileError(PrecompileError::InvalidKzgProof));
+    }
+
+    // The output is the number of field elements in a blob and the modulus of the BLS12-381
+    // scalar field
+    let mut output = [0u8; 64];
+    let (field_elements, modulus) = output.split_at_mut(32);
+    FIELD_ELEMENTS_PER_BLOB.to_big_endian(field_elements);
+    BLS_MODULUS.to_big_endian(modulus);
+
+    Ok(Bytes::copy_from_slice(&output))
+}
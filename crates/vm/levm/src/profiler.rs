@@ -0,0 +1,271 @@
+use crate::{opcode_table::opcode_info, opcodes::Opcode};
+use ethrex_core::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Aggregates the gas spent while executing a transaction per opcode, per contract and per basic
+/// block, along with the call stacks it was spent in.
+///
+/// Gas is attributed exclusively: the gas used by a sub-context is not counted in the opcode
+/// that created it, but in the opcodes executed by the sub-context.
+#[derive(Debug, Default)]
+pub struct GasProfiler {
+    /// Frames being executed, from the outermost to the current one
+    frames: Vec<FrameProfile>,
+    /// Folded stack of every frame entered, indexed by frame id
+    stacks: Vec<String>,
+    opcodes: HashMap<u8, GasStats>,
+    contracts: HashMap<Address, GasStats>,
+    blocks: HashMap<(Address, usize), GasStats>,
+    /// Gas spent per frame id, block start and opcode. Gas spent outside of any opcode has no
+    /// block nor opcode
+    folded: HashMap<(usize, Option<(usize, u8)>), u64>,
+}
+
+#[derive(Debug)]
+struct FrameProfile {
+    id: usize,
+    address: Address,
+    /// PC of the first opcode of the basic block being executed
+    block_start: usize,
+    /// Whether the next opcode starts a new basic block
+    starts_block: bool,
+    /// Gas used by the frame when entering it, or after the last profiled opcode
+    gas_used: u64,
+    /// Gas used by the sub-contexts created by the frame
+    children_gas: u64,
+    /// Last opcode executed and the block it belongs to
+    last_opcode: Option<(usize, u8)>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct GasStats {
+    count: u64,
+    gas: u64,
+}
+
+impl GasStats {
+    fn add_gas(&mut self, gas: u64) {
+        self.gas = self.gas.saturating_add(gas);
+    }
+
+    fn increment_count(&mut self) {
+        self.count = self.count.saturating_add(1);
+    }
+}
+
+/// Gas spent while executing a transaction, as aggregated by the [`GasProfiler`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasProfile {
+    /// Gas spent per opcode, sorted from the most to the least expensive
+    pub opcodes: Vec<OpcodeGas>,
+    /// Gas spent in the code of each contract, sorted from the most to the least expensive
+    pub contracts: Vec<ContractGas>,
+    /// Gas spent in each basic block, sorted from the most to the least expensive
+    pub blocks: Vec<BlockGas>,
+    /// Gas spent per call stack, in the folded stacks format used by flamegraph tools: one
+    /// `frame;frame;...;frame gas` line per stack
+    pub folded_stacks: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpcodeGas {
+    pub opcode: String,
+    /// Times the opcode was executed
+    pub count: u64,
+    pub gas: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractGas {
+    pub address: Address,
+    /// Times the contract's code was executed
+    pub calls: u64,
+    pub gas: u64,
+}
+
+/// A basic block starts at the beginning of the code, at every JUMPDEST and after every jump
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockGas {
+    pub address: Address,
+    pub start_pc: usize,
+    /// Times the block was entered
+    pub executions: u64,
+    pub gas: u64,
+}
+
+impl GasProfiler {
+    /// Starts profiling the execution of the code of `address`. The gas already used by the frame,
+    /// such as the intrinsic gas of the transaction, is attributed to the frame itself
+    pub fn enter_frame(&mut self, address: Address, gas_used: u64) {
+        let id = self.stacks.len();
+        let stack = match self.frames.last() {
+            Some(caller) => format!(
+                "{};{address:#x}",
+                self.stacks.get(caller.id).map_or("", String::as_str)
+            ),
+            None => format!("{address:#x}"),
+        };
+        self.stacks.push(stack);
+        self.contracts.entry(address).or_default().increment_count();
+        self.frames.push(FrameProfile {
+            id,
+            address,
+            block_start: 0,
+            starts_block: true,
+            gas_used: 0,
+            children_gas: 0,
+            last_opcode: None,
+        });
+        self.record(None, gas_used);
+        if let Some(frame) = self.frames.last_mut() {
+            frame.gas_used = gas_used;
+        }
+    }
+
+    /// Must be called after executing each opcode of the current frame, with the gas used by the
+    /// frame so far
+    pub fn record_opcode(&mut self, opcode: u8, pc: usize, gas_used: u64) {
+        let Some(frame) = self.frames.last_mut() else {
+            return;
+        };
+        if frame.starts_block || opcode == u8::from(Opcode::JUMPDEST) {
+            frame.block_start = pc;
+            self.blocks
+                .entry((frame.address, pc))
+                .or_default()
+                .increment_count();
+        }
+        // The next opcode is the target of a jump, or the fallthrough of a conditional one
+        frame.starts_block = opcode_info(opcode).sets_pc;
+
+        let spent = gas_used
+            .saturating_sub(frame.gas_used)
+            .saturating_sub(frame.children_gas);
+        let block_start = frame.block_start;
+        frame.gas_used = gas_used;
+        frame.children_gas = 0;
+        frame.last_opcode = Some((block_start, opcode));
+        self.opcodes.entry(opcode).or_default().increment_count();
+        self.record(Some((block_start, opcode)), spent);
+    }
+
+    /// Finishes profiling the current frame, which used `gas_used` in total. The gas not spent by
+    /// any opcode, such as the gas consumed when halting or the code deposit cost, is attributed to
+    /// the last opcode executed
+    pub fn exit_frame(&mut self, gas_used: u64) {
+        let Some(frame) = self.frames.last() else {
+            return;
+        };
+        let spent = gas_used
+            .saturating_sub(frame.gas_used)
+            .saturating_sub(frame.children_gas);
+        let last_opcode = frame.last_opcode;
+        self.record(last_opcode, spent);
+        self.frames.pop();
+        if let Some(caller) = self.frames.last_mut() {
+            caller.children_gas = caller.children_gas.saturating_add(gas_used);
+        }
+    }
+
+    /// Attributes gas to the current frame and, if spent by an opcode, to that opcode and its block
+    fn record(&mut self, opcode: Option<(usize, u8)>, gas: u64) {
+        let Some(frame) = self.frames.last() else {
+            return;
+        };
+        self.contracts
+            .entry(frame.address)
+            .or_default()
+            .add_gas(gas);
+        if let Some((block_start, opcode)) = opcode {
+            self.opcodes.entry(opcode).or_default().add_gas(gas);
+            self.blocks
+                .entry((frame.address, block_start))
+                .or_default()
+                .add_gas(gas);
+        }
+        let folded = self.folded.entry((frame.id, opcode)).or_default();
+        *folded = folded.saturating_add(gas);
+    }
+
+    pub fn finish(self) -> GasProfile {
+        let mut opcodes: Vec<OpcodeGas> = self
+            .opcodes
+            .into_iter()
+            .map(|(opcode, stats)| OpcodeGas {
+                opcode: opcode_name(opcode),
+                count: stats.count,
+                gas: stats.gas,
+            })
+            .collect();
+        opcodes.sort_by(|a, b| b.gas.cmp(&a.gas).then_with(|| a.opcode.cmp(&b.opcode)));
+
+        let mut contracts: Vec<ContractGas> = self
+            .contracts
+            .into_iter()
+            .map(|(address, stats)| ContractGas {
+                address,
+                calls: stats.count,
+                gas: stats.gas,
+            })
+            .collect();
+        contracts.sort_by(|a, b| b.gas.cmp(&a.gas).then_with(|| a.address.cmp(&b.address)));
+
+        let mut blocks: Vec<BlockGas> = self
+            .blocks
+            .into_iter()
+            .map(|((address, start_pc), stats)| BlockGas {
+                address,
+                start_pc,
+                executions: stats.count,
+                gas: stats.gas,
+            })
+            .collect();
+        blocks.sort_by(|a, b| {
+            b.gas
+                .cmp(&a.gas)
+                .then_with(|| (a.address, a.start_pc).cmp(&(b.address, b.start_pc)))
+        });
+
+        // Frames with the same call stack are merged into the same lines
+        let mut folded: HashMap<String, u64> = HashMap::new();
+        for ((id, opcode), gas) in self.folded {
+            if gas == 0 {
+                continue;
+            }
+            let stack = self.stacks.get(id).map_or("", String::as_str);
+            let line = match opcode {
+                Some((block_start, opcode)) => {
+                    format!("{stack};block@{block_start:#x};{}", opcode_name(opcode))
+                }
+                None => stack.to_owned(),
+            };
+            let total = folded.entry(line).or_default();
+            *total = total.saturating_add(gas);
+        }
+        let mut folded: Vec<(String, u64)> = folded.into_iter().collect();
+        folded.sort();
+        let mut folded_stacks = String::new();
+        for (line, gas) in folded {
+            folded_stacks.push_str(&line);
+            folded_stacks.push(' ');
+            folded_stacks.push_str(&gas.to_string());
+            folded_stacks.push('\n');
+        }
+
+        GasProfile {
+            opcodes,
+            contracts,
+            blocks,
+            folded_stacks,
+        }
+    }
+}
+
+fn opcode_name(opcode: u8) -> String {
+    format!("{:?}", Opcode::from(opcode))
+}
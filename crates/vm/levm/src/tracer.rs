@@ -0,0 +1,60 @@
+use crate::{call_frame::CallFrame, errors::VMError, opcodes::Opcode};
+use ethrex_core::U256;
+use serde::Serialize;
+
+/// Records every opcode executed by a transaction, as [EIP-3155] trace steps
+///
+/// [EIP-3155]: https://eips.ethereum.org/EIPS/eip-3155
+#[derive(Debug, Default)]
+pub struct Tracer {
+    pub steps: Vec<TraceStep>,
+}
+
+/// State of the call frame right before executing an opcode, along with the gas it cost
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceStep {
+    pub pc: usize,
+    pub op: u8,
+    /// Gas left before executing the opcode
+    #[serde(with = "ethrex_core::serde_utils::u64::hex_str")]
+    pub gas: u64,
+    #[serde(with = "ethrex_core::serde_utils::u64::hex_str")]
+    pub gas_cost: u64,
+    pub mem_size: usize,
+    pub stack: Vec<U256>,
+    /// Depth of the call frame, starting at 1
+    pub depth: usize,
+    pub refund: u64,
+    pub op_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Tracer {
+    /// Records the state of the call frame before executing `opcode`, returns the index of the
+    /// step to complete it once executed
+    pub fn start_step(&mut self, call_frame: &CallFrame, opcode: u8, refunded_gas: u64) -> usize {
+        self.steps.push(TraceStep {
+            pc: call_frame.pc,
+            op: opcode,
+            gas: call_frame.gas_limit.saturating_sub(call_frame.gas_used),
+            gas_cost: 0,
+            mem_size: call_frame.memory.len(),
+            stack: call_frame.stack.stack.clone(),
+            depth: call_frame.depth.saturating_add(1),
+            refund: refunded_gas,
+            op_name: format!("{:?}", Opcode::from(opcode)),
+            error: None,
+        });
+        self.steps.len().saturating_sub(1)
+    }
+
+    /// Completes the step with the gas its opcode cost and the error it raised, if any
+    pub fn end_step(&mut self, step: usize, gas_cost: u64, error: Option<&VMError>) {
+        if let Some(step) = self.steps.get_mut(step) {
+            step.gas_cost = gas_cost;
+            step.error = error.map(ToString::to_string);
+        }
+    }
+}
@@ -7,17 +7,22 @@ use crate::{
         CacheDB, Database,
     },
     environment::Environment,
+    eof::{self, ContainerKind, EofContainer},
     errors::{
-        InternalError, OpcodeSuccess, OutOfGasError, ResultReason, TransactionReport, TxResult,
+        InternalError, OpcodeSuccess, OutOfGasError, TransactionReport, TxResult,
         TxValidationError, VMError,
     },
     gas_cost::{
         self, fake_exponential, ACCESS_LIST_ADDRESS_COST, ACCESS_LIST_STORAGE_KEY_COST,
-        BLOB_GAS_PER_BLOB, CODE_DEPOSIT_COST, CREATE_BASE_COST,
+        BLOB_GAS_PER_BLOB, CODE_DEPOSIT_COST,
     },
-    opcodes::Opcode,
-    precompiles::{execute_precompile, is_precompile},
-    AccountInfo, TransientStorage,
+    journal::{Journal, JournalEntry},
+    jump_destinations::JumpDestinationsCache,
+    opcode_table::opcode_info,
+    precompiles::{PrecompileFn, PrecompileRegistry},
+    profiler::GasProfiler,
+    tracer::Tracer,
+    AccountInfo,
 };
 use bytes::Bytes;
 use ethrex_core::{types::TxKind, Address, H256, U256};
@@ -26,7 +31,7 @@ use ethrex_rlp::encode::RLPEncode;
 use keccak_hash::keccak;
 use sha3::{Digest, Keccak256};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{hash_map::Entry, HashMap, HashSet},
     sync::Arc,
 };
 
@@ -56,6 +61,24 @@ pub struct VM {
     pub cache: CacheDB,
     pub tx_kind: TxKind,
     pub access_list: AccessList,
+    /// Precompiled contracts available to the transaction
+    pub precompiles: PrecompileRegistry,
+    /// Changes made to the cache by the sub-contexts being executed, to revert them if they fail
+    pub journal: Journal,
+    /// Jump destinations of the accounts' code analysed so far. Empty for a new VM, it can be
+    /// replaced with the one of a previous VM to reuse its analyses
+    pub jump_destinations: JumpDestinationsCache,
+    /// Set to profile the gas spent by the transaction
+    pub profiler: Option<GasProfiler>,
+    /// Set to record every opcode executed by the transaction
+    pub tracer: Option<Tracer>,
+}
+
+/// Info of the account as left by the changes in `cache`, or as stored in `db` if it has none
+fn cached_or_stored_info(db: &dyn Database, cache: &CacheDB, address: Address) -> AccountInfo {
+    cache::get_account(cache, &address)
+        .map(|account| account.info.clone())
+        .unwrap_or_else(|| db.get_account_info(address))
 }
 
 pub fn address_to_word(address: Address) -> U256 {
@@ -84,40 +107,6 @@ pub struct AccessListItem {
 
 type AccessList = Vec<(Address, Vec<H256>)>;
 
-pub fn get_valid_jump_destinations(code: &Bytes) -> Result<HashSet<usize>, VMError> {
-    let mut valid_jump_destinations = HashSet::new();
-    let mut pc = 0;
-
-    while let Some(&opcode_number) = code.get(pc) {
-        let current_opcode = Opcode::from(opcode_number);
-
-        if current_opcode == Opcode::JUMPDEST {
-            // If current opcode is jumpdest, add it to valid destinations set
-            valid_jump_destinations.insert(pc);
-        } else if (Opcode::PUSH1..=Opcode::PUSH32).contains(&current_opcode) {
-            // If current opcode is push, skip as many positions as the size of the push
-            let size_to_push =
-                opcode_number
-                    .checked_sub(u8::from(Opcode::PUSH1))
-                    .ok_or(VMError::Internal(
-                        InternalError::ArithmeticOperationUnderflow,
-                    ))?;
-            let skip_length = usize::from(size_to_push.checked_add(1).ok_or(VMError::Internal(
-                InternalError::ArithmeticOperationOverflow,
-            ))?);
-            pc = pc.checked_add(skip_length).ok_or(VMError::Internal(
-                InternalError::ArithmeticOperationOverflow, // to fail, pc should be at least usize max - 31
-            ))?;
-        }
-
-        pc = pc.checked_add(1).ok_or(VMError::Internal(
-            InternalError::ArithmeticOperationOverflow, // to fail, code len should be more than usize max
-        ))?;
-    }
-
-    Ok(valid_jump_destinations)
-}
-
 impl VM {
     // TODO: Refactor this.
     #[allow(clippy::too_many_arguments)]
@@ -133,8 +122,11 @@ impl VM {
         // Maybe this decision should be made in an upper layer
 
         // Add sender, coinbase and recipient (in the case of a Call) to cache [https://www.evm.codes/about#access_list]
-        let mut default_touched_accounts =
-            HashSet::from_iter([env.origin, env.coinbase].iter().cloned());
+        // The coinbase is only warm since Shanghai (EIP-3651)
+        let mut default_touched_accounts = HashSet::from([env.origin]);
+        if env.fork.gas_schedule().warm_coinbase {
+            default_touched_accounts.insert(env.coinbase);
+        }
 
         let mut default_touched_storage_slots: HashMap<Address, HashSet<H256>> = HashMap::new();
 
@@ -149,25 +141,25 @@ impl VM {
         }
 
         // Add precompiled contracts addresses to cache.
-        // TODO: Use the addresses from precompiles.rs in a future
-        for i in 1..=10 {
-            default_touched_accounts.insert(Address::from_low_u64_be(i));
-        }
+        let precompiles = PrecompileRegistry::for_fork(env.fork);
+        default_touched_accounts.extend(precompiles.addresses().copied());
 
         match to {
             TxKind::Call(address_to) => {
                 default_touched_accounts.insert(address_to);
 
-                // add address_to to cache
-                let recipient_account_info = db.get_account_info(address_to);
-                cache::insert_account(
-                    &mut cache,
-                    address_to,
-                    Account::from(recipient_account_info.clone()),
-                );
+                // add address_to to cache, keeping the changes made to it by previous transactions
+                let recipient_account_info = match cache::get_account(&cache, &address_to) {
+                    Some(account) => account.info.clone(),
+                    None => {
+                        let info = db.get_account_info(address_to);
+                        cache::insert_account(&mut cache, address_to, Account::from(info.clone()));
+                        info
+                    }
+                };
 
                 // CALL tx
-                let initial_call_frame = CallFrame::new(
+                let mut initial_call_frame = CallFrame::new(
                     env.origin,
                     address_to,
                     address_to,
@@ -180,6 +172,7 @@ impl VM {
                     0,
                     false,
                 );
+                initial_call_frame.code_hash = Some(recipient_account_info.code_hash);
 
                 let substate = Substate {
                     selfdestrutct_set: HashSet::new(),
@@ -196,28 +189,38 @@ impl VM {
                     cache,
                     tx_kind: to,
                     access_list,
+                    precompiles,
+                    journal: Journal::default(),
+                    jump_destinations: JumpDestinationsCache::default(),
+                    profiler: None,
+                    tracer: None,
                 })
             }
             TxKind::Create => {
                 // CREATE tx
 
-                let new_contract_address =
-                    VM::calculate_create_address(env.origin, db.get_account_info(env.origin).nonce)
-                        .map_err(|_| {
-                            VMError::Internal(InternalError::CouldNotComputeCreateAddress)
-                        })?;
+                let sender_nonce = cached_or_stored_info(db.as_ref(), &cache, env.origin).nonce;
+                let new_contract_address = VM::calculate_create_address(env.origin, sender_nonce)
+                    .map_err(|_| {
+                    VMError::Internal(InternalError::CouldNotComputeCreateAddress)
+                })?;
 
                 default_touched_accounts.insert(new_contract_address);
 
                 // Since we are in a CREATE transaction, we need to check if the address is already occupied.
                 // If it is, we should not continue with the transaction. We will handle the revert in the next step.
-                let new_account = db.get_account_info(new_contract_address);
+                let new_account = cached_or_stored_info(db.as_ref(), &cache, new_contract_address);
                 let balance = value
                     .checked_add(new_account.balance)
                     .ok_or(VMError::BalanceOverflow)?;
 
                 if !new_account.has_code() && !new_account.has_nonce() {
-                    let created_contract = Account::new(balance, Bytes::new(), 1, HashMap::new());
+                    let created_contract = Account::new(
+                        balance,
+                        Bytes::new(),
+                        env.fork.gas_schedule().created_contract_nonce,
+                        HashMap::new(),
+                    );
                     cache::insert_account(&mut cache, new_contract_address, created_contract);
                 }
 
@@ -250,33 +253,68 @@ impl VM {
                     cache,
                     tx_kind: TxKind::Create,
                     access_list,
+                    precompiles,
+                    journal: Journal::default(),
+                    jump_destinations: JumpDestinationsCache::default(),
+                    profiler: None,
+                    tracer: None,
                 })
             }
         }
     }
 
+    /// Makes `precompile` available at `address` for the rest of the transaction, replacing the
+    /// precompile previously registered there, if any
+    /// The address is warmed as the rest of the precompile addresses
+    pub fn register_precompile(
+        &mut self,
+        address: Address,
+        precompile: PrecompileFn,
+    ) -> Option<PrecompileFn> {
+        self.accrued_substate.touched_accounts.insert(address);
+        self.precompiles.register(address, precompile)
+    }
+
     pub fn execute(
         &mut self,
         current_call_frame: &mut CallFrame,
     ) -> Result<TransactionReport, VMError> {
-        // Backup of Database, Substate, Gas Refunds and Transient Storage if sub-context is reverted
-        let (backup_db, backup_substate, backup_refunded_gas, backup_transient_storage) = (
-            self.cache.clone(),
-            self.accrued_substate.clone(),
-            self.env.refunded_gas,
-            self.env.transient_storage.clone(),
-        );
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.enter_frame(current_call_frame.code_address, current_call_frame.gas_used);
+        }
+        let report = self.execute_frame(current_call_frame);
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.exit_frame(
+                report
+                    .as_ref()
+                    .map_or(current_call_frame.gas_used, |report| report.gas_used),
+            );
+        }
+        report
+    }
+
+    fn execute_frame(
+        &mut self,
+        current_call_frame: &mut CallFrame,
+    ) -> Result<TransactionReport, VMError> {
+        // Changes to the state are journaled in case the sub-context is reverted
+        self.journal.checkpoint();
+        let backup_refunded_gas = self.env.refunded_gas;
 
-        if is_precompile(&current_call_frame.code_address) {
-            let precompile_result = execute_precompile(current_call_frame);
+        if self
+            .precompiles
+            .is_precompile(&current_call_frame.code_address)
+        {
+            let precompile_result = self.precompiles.execute(current_call_frame);
 
             match precompile_result {
                 Ok(output) => {
+                    self.journal.commit();
                     self.call_frames.push(current_call_frame.clone());
 
                     return Ok(TransactionReport {
                         result: TxResult::Success,
-                        new_state: self.cache.clone(),
+                        new_state: CacheDB::default(),
                         gas_used: current_call_frame.gas_used,
                         gas_refunded: 0,
                         output,
@@ -291,16 +329,11 @@ impl VM {
 
                     self.call_frames.push(current_call_frame.clone());
 
-                    self.restore_state(
-                        backup_db,
-                        backup_substate,
-                        backup_refunded_gas,
-                        backup_transient_storage,
-                    );
+                    self.restore_state(current_call_frame, backup_refunded_gas);
 
                     return Ok(TransactionReport {
                         result: TxResult::Revert(error),
-                        new_state: self.cache.clone(),
+                        new_state: CacheDB::default(),
                         gas_used: current_call_frame.gas_limit,
                         gas_refunded: 0,
                         output: Bytes::new(),
@@ -311,117 +344,59 @@ impl VM {
             }
         }
 
-        loop {
-            let opcode = current_call_frame.next_opcode();
-
-            let op_result: Result<OpcodeSuccess, VMError> = match opcode {
-                Opcode::STOP => Ok(OpcodeSuccess::Result(ResultReason::Stop)),
-                Opcode::ADD => self.op_add(current_call_frame),
-                Opcode::MUL => self.op_mul(current_call_frame),
-                Opcode::SUB => self.op_sub(current_call_frame),
-                Opcode::DIV => self.op_div(current_call_frame),
-                Opcode::SDIV => self.op_sdiv(current_call_frame),
-                Opcode::MOD => self.op_mod(current_call_frame),
-                Opcode::SMOD => self.op_smod(current_call_frame),
-                Opcode::ADDMOD => self.op_addmod(current_call_frame),
-                Opcode::MULMOD => self.op_mulmod(current_call_frame),
-                Opcode::EXP => self.op_exp(current_call_frame),
-                Opcode::SIGNEXTEND => self.op_signextend(current_call_frame),
-                Opcode::LT => self.op_lt(current_call_frame),
-                Opcode::GT => self.op_gt(current_call_frame),
-                Opcode::SLT => self.op_slt(current_call_frame),
-                Opcode::SGT => self.op_sgt(current_call_frame),
-                Opcode::EQ => self.op_eq(current_call_frame),
-                Opcode::ISZERO => self.op_iszero(current_call_frame),
-                Opcode::KECCAK256 => self.op_keccak256(current_call_frame),
-                Opcode::CALLDATALOAD => self.op_calldataload(current_call_frame),
-                Opcode::CALLDATASIZE => self.op_calldatasize(current_call_frame),
-                Opcode::CALLDATACOPY => self.op_calldatacopy(current_call_frame),
-                Opcode::RETURNDATASIZE => self.op_returndatasize(current_call_frame),
-                Opcode::RETURNDATACOPY => self.op_returndatacopy(current_call_frame),
-                Opcode::JUMP => self.op_jump(current_call_frame),
-                Opcode::JUMPI => self.op_jumpi(current_call_frame),
-                Opcode::JUMPDEST => self.op_jumpdest(current_call_frame),
-                Opcode::PC => self.op_pc(current_call_frame),
-                Opcode::BLOCKHASH => self.op_blockhash(current_call_frame),
-                Opcode::COINBASE => self.op_coinbase(current_call_frame),
-                Opcode::TIMESTAMP => self.op_timestamp(current_call_frame),
-                Opcode::NUMBER => self.op_number(current_call_frame),
-                Opcode::PREVRANDAO => self.op_prevrandao(current_call_frame),
-                Opcode::GASLIMIT => self.op_gaslimit(current_call_frame),
-                Opcode::CHAINID => self.op_chainid(current_call_frame),
-                Opcode::BASEFEE => self.op_basefee(current_call_frame),
-                Opcode::BLOBHASH => self.op_blobhash(current_call_frame),
-                Opcode::BLOBBASEFEE => self.op_blobbasefee(current_call_frame),
-                Opcode::PUSH0 => self.op_push0(current_call_frame),
-                // PUSHn
-                op if (Opcode::PUSH1..=Opcode::PUSH32).contains(&op) => {
-                    let n_bytes = get_n_value(op, Opcode::PUSH1)?;
-                    self.op_push(current_call_frame, n_bytes)
-                }
-                Opcode::AND => self.op_and(current_call_frame),
-                Opcode::OR => self.op_or(current_call_frame),
-                Opcode::XOR => self.op_xor(current_call_frame),
-                Opcode::NOT => self.op_not(current_call_frame),
-                Opcode::BYTE => self.op_byte(current_call_frame),
-                Opcode::SHL => self.op_shl(current_call_frame),
-                Opcode::SHR => self.op_shr(current_call_frame),
-                Opcode::SAR => self.op_sar(current_call_frame),
-                // DUPn
-                op if (Opcode::DUP1..=Opcode::DUP16).contains(&op) => {
-                    let depth = get_n_value(op, Opcode::DUP1)?;
-                    self.op_dup(current_call_frame, depth)
-                }
-                // SWAPn
-                op if (Opcode::SWAP1..=Opcode::SWAP16).contains(&op) => {
-                    let depth = get_n_value(op, Opcode::SWAP1)?;
-                    self.op_swap(current_call_frame, depth)
+        // Code in EVM Object Format is executed from its container. Invalid containers are left as
+        // legacy code, which halts on the 0xEF byte
+        let is_initial_create_frame = self.is_create() && current_call_frame.depth == 0;
+        if self.env.fork.gas_schedule().eof
+            && current_call_frame.eof_container.is_none()
+            && !current_call_frame.create_op_called
+            && !is_initial_create_frame
+            && eof::is_eof(&current_call_frame.bytecode)
+        {
+            if let Ok(container) = EofContainer::decode(&current_call_frame.bytecode) {
+                if container.validate(ContainerKind::Runtime).is_ok() {
+                    current_call_frame.assign_eof_container(container)?;
                 }
-                Opcode::POP => self.op_pop(current_call_frame),
-                op if (Opcode::LOG0..=Opcode::LOG4).contains(&op) => {
-                    let number_of_topics = get_number_of_topics(op)?;
-                    self.op_log(current_call_frame, number_of_topics)
-                }
-                Opcode::MLOAD => self.op_mload(current_call_frame),
-                Opcode::MSTORE => self.op_mstore(current_call_frame),
-                Opcode::MSTORE8 => self.op_mstore8(current_call_frame),
-                Opcode::SLOAD => self.op_sload(current_call_frame),
-                Opcode::SSTORE => self.op_sstore(current_call_frame),
-                Opcode::MSIZE => self.op_msize(current_call_frame),
-                Opcode::GAS => self.op_gas(current_call_frame),
-                Opcode::MCOPY => self.op_mcopy(current_call_frame),
-                Opcode::CALL => self.op_call(current_call_frame),
-                Opcode::CALLCODE => self.op_callcode(current_call_frame),
-                Opcode::RETURN => self.op_return(current_call_frame),
-                Opcode::DELEGATECALL => self.op_delegatecall(current_call_frame),
-                Opcode::STATICCALL => self.op_staticcall(current_call_frame),
-                Opcode::CREATE => self.op_create(current_call_frame),
-                Opcode::CREATE2 => self.op_create2(current_call_frame),
-                Opcode::TLOAD => self.op_tload(current_call_frame),
-                Opcode::TSTORE => self.op_tstore(current_call_frame),
-                Opcode::SELFBALANCE => self.op_selfbalance(current_call_frame),
-                Opcode::ADDRESS => self.op_address(current_call_frame),
-                Opcode::ORIGIN => self.op_origin(current_call_frame),
-                Opcode::BALANCE => self.op_balance(current_call_frame),
-                Opcode::CALLER => self.op_caller(current_call_frame),
-                Opcode::CALLVALUE => self.op_callvalue(current_call_frame),
-                Opcode::CODECOPY => self.op_codecopy(current_call_frame),
-                Opcode::CODESIZE => self.op_codesize(current_call_frame),
-                Opcode::GASPRICE => self.op_gasprice(current_call_frame),
-                Opcode::EXTCODESIZE => self.op_extcodesize(current_call_frame),
-                Opcode::EXTCODECOPY => self.op_extcodecopy(current_call_frame),
-                Opcode::EXTCODEHASH => self.op_extcodehash(current_call_frame),
-                Opcode::REVERT => self.op_revert(current_call_frame),
-                Opcode::INVALID => self.op_invalid(),
-                Opcode::SELFDESTRUCT => self.op_selfdestruct(current_call_frame),
-
-                _ => Err(VMError::OpcodeNotFound),
-            };
-
-            if opcode != Opcode::JUMP && opcode != Opcode::JUMPI {
+            }
+        }
+
+        let fork = self.env.fork;
+        loop {
+            let opcode_byte = current_call_frame.next_opcode_byte();
+            let pc = current_call_frame.pc;
+            let opcode = opcode_info(opcode_byte);
+            let gas_used_before = current_call_frame.gas_used;
+            let refunded_gas = self.env.refunded_gas;
+            let trace_step = self
+                .tracer
+                .as_mut()
+                .map(|tracer| tracer.start_step(current_call_frame, opcode_byte, refunded_gas));
+
+            // Opcodes introduced in later forks are undefined, which is the same as INVALID
+            // EOF opcodes are undefined in legacy code as well
+            let op_result: Result<OpcodeSuccess, VMError> =
+                if opcode.is_defined(fork, current_call_frame.eof_container.is_some()) {
+                    (opcode.handler)(self, current_call_frame)
+                } else {
+                    Err(VMError::InvalidOpcode)
+                };
+
+            // Jumps set the PC themselves
+            if !opcode.sets_pc {
                 current_call_frame.increment_pc()?;
             }
 
+            if let Some(profiler) = self.profiler.as_mut() {
+                profiler.record_opcode(opcode_byte, pc, current_call_frame.gas_used);
+            }
+            if let (Some(tracer), Some(step)) = (self.tracer.as_mut(), trace_step) {
+                tracer.end_step(
+                    step,
+                    current_call_frame.gas_used.saturating_sub(gas_used_before),
+                    op_result.as_ref().err(),
+                );
+            }
+
             // Gas refunds are applied at the end of a transaction. Should it be implemented here?
 
             match op_result {
@@ -448,37 +423,42 @@ impl VM {
                         // If the first byte of code is 0xef
                         // If the code_length > MAX_CODE_SIZE
                         // If current_consumed_gas + code_deposit_cost > gas_limit
-                        let validate_create = if code_length > MAX_CODE_SIZE {
+                        let schedule = self.env.fork.gas_schedule();
+                        let validate_create = if schedule
+                            .max_code_size
+                            .is_some_and(|max_code_size| code_length > max_code_size)
+                        {
                             Err(VMError::ContractOutputTooBig)
-                        } else if contract_code.first().unwrap_or(&0) == &INVALID_CONTRACT_PREFIX {
+                        } else if schedule.reject_ef_code
+                            && current_call_frame.eof_container.is_none()
+                            && contract_code.first().unwrap_or(&0) == &INVALID_CONTRACT_PREFIX
+                        {
                             Err(VMError::InvalidContractPrefix)
                         } else if self
                             .increase_consumed_gas(current_call_frame, code_deposit_cost)
-                            .is_err()
+                            .is_ok()
                         {
+                            Ok(contract_code)
+                        } else if schedule.code_deposit_out_of_gas_fails {
                             Err(VMError::OutOfGas(OutOfGasError::MaxGasLimitExceeded))
                         } else {
-                            Ok(current_call_frame.to)
+                            // Before Homestead the contract is created without code instead
+                            Ok(Bytes::new())
                         };
 
                         match validate_create {
-                            Ok(new_address) => {
+                            Ok(contract_code) => {
                                 // Set bytecode to new account if success
-                                self.update_account_bytecode(new_address, contract_code)?;
+                                self.update_account_bytecode(current_call_frame.to, contract_code)?;
                             }
                             Err(error) => {
                                 // Revert if error
                                 current_call_frame.gas_used = current_call_frame.gas_limit;
-                                self.restore_state(
-                                    backup_db,
-                                    backup_substate,
-                                    backup_refunded_gas,
-                                    backup_transient_storage,
-                                );
+                                self.restore_state(current_call_frame, backup_refunded_gas);
 
                                 return Ok(TransactionReport {
                                     result: TxResult::Revert(error),
-                                    new_state: self.cache.clone(),
+                                    new_state: CacheDB::default(),
                                     gas_used: current_call_frame.gas_used,
                                     gas_refunded: self.env.refunded_gas,
                                     output: current_call_frame.output.clone(),
@@ -489,9 +469,11 @@ impl VM {
                         }
                     }
 
+                    self.journal.commit();
+
                     return Ok(TransactionReport {
                         result: TxResult::Success,
-                        new_state: self.cache.clone(),
+                        new_state: CacheDB::default(),
                         gas_used: current_call_frame.gas_used,
                         gas_refunded: self.env.refunded_gas,
                         output: current_call_frame.output.clone(),
@@ -515,16 +497,11 @@ impl VM {
                             current_call_frame.gas_used.saturating_add(left_gas);
                     }
 
-                    self.restore_state(
-                        backup_db,
-                        backup_substate,
-                        backup_refunded_gas,
-                        backup_transient_storage,
-                    );
+                    self.restore_state(current_call_frame, backup_refunded_gas);
 
                     return Ok(TransactionReport {
                         result: TxResult::Revert(error),
-                        new_state: self.cache.clone(),
+                        new_state: CacheDB::default(),
                         gas_used: current_call_frame.gas_used,
                         gas_refunded: self.env.refunded_gas,
                         output: current_call_frame.output.clone(), // Bytes::new() if error is not RevertOpcode
@@ -536,17 +513,15 @@ impl VM {
         }
     }
 
-    fn restore_state(
-        &mut self,
-        backup_cache: CacheDB,
-        backup_substate: Substate,
-        backup_refunded_gas: u64,
-        backup_transient_storage: TransientStorage,
-    ) {
-        self.cache = backup_cache;
-        self.accrued_substate = backup_substate;
+    /// Undoes the changes made by the sub-context, including the logs it emitted
+    fn restore_state(&mut self, current_call_frame: &mut CallFrame, backup_refunded_gas: u64) {
+        self.journal.revert(
+            &mut self.cache,
+            &mut self.accrued_substate,
+            &mut self.env.transient_storage,
+        );
         self.env.refunded_gas = backup_refunded_gas;
-        self.env.transient_storage = backup_transient_storage;
+        current_call_frame.logs.clear();
     }
 
     fn is_create(&self) -> bool {
@@ -561,8 +536,8 @@ impl VM {
 
         // Calldata Cost
         // 4 gas for each zero byte in the transaction data 16 gas for each non-zero byte in the transaction.
-        let calldata_cost =
-            gas_cost::tx_calldata(&initial_call_frame.calldata).map_err(VMError::OutOfGas)?;
+        let calldata_cost = gas_cost::tx_calldata(&initial_call_frame.calldata, self.env.fork)
+            .map_err(VMError::OutOfGas)?;
 
         intrinsic_gas = intrinsic_gas
             .checked_add(calldata_cost)
@@ -574,11 +549,14 @@ impl VM {
             .ok_or(OutOfGasError::ConsumedGasOverflow)?;
 
         // Create Cost
+        // Init code is only charged since Shanghai (EIP-3860)
+        let schedule = self.env.fork.gas_schedule();
         if self.is_create() {
             intrinsic_gas = intrinsic_gas
-                .checked_add(CREATE_BASE_COST)
+                .checked_add(schedule.tx_create)
                 .ok_or(OutOfGasError::ConsumedGasOverflow)?;
-
+        }
+        if self.is_create() && schedule.limit_init_code {
             let number_of_words = initial_call_frame.calldata.len().div_ceil(WORD_SIZE);
             let double_number_of_words: u64 = number_of_words
                 .checked_mul(2)
@@ -762,7 +740,9 @@ impl VM {
         // (4) INITCODE_SIZE_EXCEEDED
         if self.is_create() {
             // INITCODE_SIZE_EXCEEDED
-            if initial_call_frame.calldata.len() > INIT_CODE_MAX_SIZE {
+            if self.env.fork.gas_schedule().limit_init_code
+                && initial_call_frame.calldata.len() > INIT_CODE_MAX_SIZE
+            {
                 return Err(VMError::TxValidation(
                     TxValidationError::InitcodeSizeExceeded,
                 ));
@@ -848,13 +828,43 @@ impl VM {
         }
 
         if self.is_create() {
+            let initcode = initial_call_frame.calldata.clone();
+            // EOF creation transactions carry the initcontainer followed by its input [EIP-7698]
+            // Invalid initcontainers are executed as legacy code, which halts on the 0xEF byte
+            if let Some((container, input)) = self.eof_initcontainer(&initcode) {
+                initial_call_frame.assign_eof_container(container)?;
+                initial_call_frame.calldata = input;
+                return Ok(());
+            }
+
             // Assign bytecode to context and empty calldata
-            initial_call_frame.assign_bytecode(initial_call_frame.calldata.clone());
+            initial_call_frame.assign_bytecode(initcode);
             initial_call_frame.calldata = Bytes::new();
         }
         Ok(())
     }
 
+    /// Splits the data of a creation transaction into a valid initcontainer and its input, if
+    /// EOF is enabled and the data starts with one
+    fn eof_initcontainer(&self, initcode: &Bytes) -> Option<(EofContainer, Bytes)> {
+        if !self.env.fork.gas_schedule().eof || !eof::is_eof(initcode) {
+            return None;
+        }
+        let (container, size) = EofContainer::decode_prefix(initcode).ok()?;
+        container.validate(ContainerKind::Initcode).ok()?;
+        let input = Bytes::copy_from_slice(initcode.get(size..)?);
+        Some((container, input))
+    }
+
+    /// Code of an account as seen by legacy code through EXTCODESIZE, EXTCODECOPY and
+    /// EXTCODEHASH. EOF code is replaced by its magic, so it can't be introspected [EIP-3540]
+    pub fn code_seen_by_legacy_code(&self, bytecode: Bytes) -> Bytes {
+        if self.env.fork.gas_schedule().eof && eof::is_eof(&bytecode) {
+            return Bytes::from_static(&eof::EOF_MAGIC);
+        }
+        bytecode
+    }
+
     /// ## Changes post execution
     /// 1. Undo value transfer if the transaction was reverted
     /// 2. Return unused gas + gas refunds to the sender.
@@ -883,7 +893,7 @@ impl VM {
         let consumed_gas = report.gas_used;
         let refunded_gas = report.gas_refunded.min(
             consumed_gas
-                .checked_div(5)
+                .checked_div(self.env.fork.gas_schedule().max_refund_quotient)
                 .ok_or(VMError::Internal(InternalError::UndefinedState(-1)))?,
         );
         // "The max refundable proportion of gas was reduced from one half to one fifth by EIP-3529 by Buterin and Swende [2021] in the London release"
@@ -924,8 +934,13 @@ impl VM {
 
         // 4. Destruct addresses in selfdestruct set.
         // In Cancun the only addresses destroyed are contracts created in this transaction, so we 'destroy' them by just removing them from the cache, as if they never existed.
+        // Before Cancun any account can be destroyed, so it's replaced with an empty one.
         for address in &self.accrued_substate.selfdestrutct_set {
-            remove_account(&mut self.cache, address);
+            if self.env.fork.gas_schedule().selfdestruct_only_created {
+                remove_account(&mut self.cache, address);
+            } else {
+                cache::insert_account(&mut self.cache, *address, Account::default());
+            }
         }
 
         Ok(())
@@ -1040,14 +1055,7 @@ impl VM {
 
     pub fn cache_from_db(&mut self, address: Address) {
         let acc_info = self.db.get_account_info(address);
-        cache::insert_account(
-            &mut self.cache,
-            address,
-            Account {
-                info: acc_info.clone(),
-                storage: HashMap::new(),
-            },
-        );
+        self.insert_account(address, Account::from(acc_info));
     }
 
     /// Accesses to an account's information.
@@ -1056,7 +1064,7 @@ impl VM {
     /// Accessed accounts take place in some gas cost computation.
     #[must_use]
     pub fn access_account(&mut self, address: Address) -> (AccountInfo, bool) {
-        let address_was_cold = self.accrued_substate.touched_accounts.insert(address);
+        let address_was_cold = self.warm_account(address);
         let account = match cache::get_account(&self.cache, &address) {
             Some(account) => account.info.clone(),
             None => self.db.get_account_info(address),
@@ -1064,6 +1072,15 @@ impl VM {
         (account, address_was_cold)
     }
 
+    /// Adds the account to the `touched_accounts` set, returns whether it was cold
+    pub fn warm_account(&mut self, address: Address) -> bool {
+        let address_was_cold = self.accrued_substate.touched_accounts.insert(address);
+        if address_was_cold {
+            self.journal.record(JournalEntry::AccountWarmed { address });
+        }
+        address_was_cold
+    }
+
     /// Accesses to an account's storage slot.
     ///
     /// Accessed storage slots are stored in the `touched_storage_slots` set.
@@ -1079,6 +1096,10 @@ impl VM {
             .entry(address)
             .or_default()
             .insert(key);
+        if storage_slot_was_cold {
+            self.journal
+                .record(JournalEntry::StorageSlotWarmed { address, key });
+        }
         let storage_slot = match cache::get_account(&self.cache, &address) {
             Some(account) => match account.storage.get(&key) {
                 Some(storage_slot) => storage_slot.clone(),
@@ -1101,8 +1122,15 @@ impl VM {
 
         // When updating account storage of an account that's not yet cached we need to store the StorageSlot in the account
         // Note: We end up caching the account because it is the most straightforward way of doing it.
-        let account = self.get_account_mut(address)?;
-        account.storage.insert(key, storage_slot.clone());
+        let account = self.cached_account_mut(address)?;
+        if let Entry::Vacant(entry) = account.storage.entry(key) {
+            entry.insert(storage_slot.clone());
+            self.journal.record(JournalEntry::StorageChanged {
+                address,
+                key,
+                previous: None,
+            });
+        }
 
         Ok((storage_slot, storage_slot_was_cold))
     }
@@ -1112,12 +1140,13 @@ impl VM {
         address: Address,
         increase: U256,
     ) -> Result<(), VMError> {
-        let account = self.get_account_mut(address)?;
-        account.info.balance = account
-            .info
-            .balance
+        let account = self.cached_account_mut(address)?;
+        let previous = account.info.balance;
+        account.info.balance = previous
             .checked_add(increase)
             .ok_or(VMError::BalanceOverflow)?;
+        self.journal
+            .record(JournalEntry::BalanceChanged { address, previous });
         Ok(())
     }
 
@@ -1126,32 +1155,32 @@ impl VM {
         address: Address,
         decrease: U256,
     ) -> Result<(), VMError> {
-        let account = self.get_account_mut(address)?;
-        account.info.balance = account
-            .info
-            .balance
+        let account = self.cached_account_mut(address)?;
+        let previous = account.info.balance;
+        account.info.balance = previous
             .checked_sub(decrease)
             .ok_or(VMError::BalanceUnderflow)?;
+        self.journal
+            .record(JournalEntry::BalanceChanged { address, previous });
         Ok(())
     }
 
     pub fn increment_account_nonce(&mut self, address: Address) -> Result<u64, VMError> {
-        let account = self.get_account_mut(address)?;
-        account.info.nonce = account
-            .info
-            .nonce
-            .checked_add(1)
-            .ok_or(VMError::NonceOverflow)?;
-        Ok(account.info.nonce)
+        let account = self.cached_account_mut(address)?;
+        let previous = account.info.nonce;
+        account.info.nonce = previous.checked_add(1).ok_or(VMError::NonceOverflow)?;
+        let new_nonce = account.info.nonce;
+        self.journal
+            .record(JournalEntry::NonceChanged { address, previous });
+        Ok(new_nonce)
     }
 
     pub fn decrement_account_nonce(&mut self, address: Address) -> Result<(), VMError> {
-        let account = self.get_account_mut(address)?;
-        account.info.nonce = account
-            .info
-            .nonce
-            .checked_sub(1)
-            .ok_or(VMError::NonceUnderflow)?;
+        let account = self.cached_account_mut(address)?;
+        let previous = account.info.nonce;
+        account.info.nonce = previous.checked_sub(1).ok_or(VMError::NonceUnderflow)?;
+        self.journal
+            .record(JournalEntry::NonceChanged { address, previous });
         Ok(())
     }
 
@@ -1160,8 +1189,14 @@ impl VM {
         address: Address,
         new_bytecode: Bytes,
     ) -> Result<(), VMError> {
-        let account = self.get_account_mut(address)?;
-        account.info.bytecode = new_bytecode;
+        let account = self.cached_account_mut(address)?;
+        let previous_code_hash = account.info.code_hash;
+        let previous = account.info.set_bytecode(new_bytecode);
+        self.journal.record(JournalEntry::CodeChanged {
+            address,
+            previous,
+            previous_code_hash,
+        });
         Ok(())
     }
 
@@ -1171,27 +1206,52 @@ impl VM {
         key: H256,
         new_value: U256,
     ) -> Result<(), VMError> {
-        let account = self.get_account_mut(address)?;
-        let account_original_storage_slot_value = account
-            .storage
-            .get(&key)
+        let account = self.cached_account_mut(address)?;
+        let previous = account.storage.get(&key).cloned();
+        let account_original_storage_slot_value = previous
+            .as_ref()
             .map_or(U256::zero(), |slot| slot.original_value);
         let slot = account.storage.entry(key).or_insert(StorageSlot {
             original_value: account_original_storage_slot_value,
             current_value: new_value,
         });
         slot.current_value = new_value;
+        self.journal.record(JournalEntry::StorageChanged {
+            address,
+            key,
+            previous,
+        });
         Ok(())
     }
 
+    /// Sets a transient storage slot of the account, [EIP-1153]
+    pub fn update_transient_storage(&mut self, address: Address, key: U256, value: U256) {
+        let previous = self.env.transient_storage.insert((address, key), value);
+        self.journal.record(JournalEntry::TransientStorageChanged {
+            address,
+            key,
+            previous,
+        });
+    }
+
+    /// Gets the account to modify it, loading it from the database if it isn't cached. As any
+    /// change can be made to it, the whole account is journaled; prefer the specific setters.
     pub fn get_account_mut(&mut self, address: Address) -> Result<&mut Account, VMError> {
+        if let Some(account) = cache::get_account(&self.cache, &address) {
+            self.journal.record(JournalEntry::AccountChanged {
+                address,
+                previous: Some(account.clone()),
+            });
+        }
+        self.cached_account_mut(address)
+    }
+
+    /// Gets the cached account, loading it from the database first if needed. Changes made to it
+    /// must be journaled by the caller.
+    fn cached_account_mut(&mut self, address: Address) -> Result<&mut Account, VMError> {
         if !cache::is_account_cached(&self.cache, &address) {
             let account_info = self.db.get_account_info(address);
-            let account = Account {
-                info: account_info,
-                storage: HashMap::new(),
-            };
-            cache::insert_account(&mut self.cache, address, account.clone());
+            self.insert_account(address, Account::from(account_info));
         }
         cache::get_account_mut(&mut self.cache, &address)
             .ok_or(VMError::Internal(InternalError::AccountNotFound))
@@ -1207,12 +1267,29 @@ impl VM {
                     info: account_info,
                     storage: HashMap::new(),
                 };
-                cache::insert_account(&mut self.cache, address, account.clone());
+                self.insert_account(address, account.clone());
                 account
             }
         }
     }
 
+    /// Inserts the account in the cache, replacing the cached one if any
+    pub fn insert_account(&mut self, address: Address, account: Account) {
+        let previous = cache::insert_account(&mut self.cache, address, account);
+        self.journal
+            .record(JournalEntry::AccountChanged { address, previous });
+    }
+
+    /// Removes the account from the cache
+    pub fn remove_account(&mut self, address: Address) {
+        if let Some(previous) = remove_account(&mut self.cache, &address) {
+            self.journal.record(JournalEntry::AccountChanged {
+                address,
+                previous: Some(previous),
+            });
+        }
+    }
+
     fn handle_create_non_empty_account(
         &mut self,
         initial_call_frame: &CallFrame,
@@ -1233,21 +1310,3 @@ impl VM {
         Ok(report)
     }
 }
-
-fn get_n_value(op: Opcode, base_opcode: Opcode) -> Result<usize, VMError> {
-    let offset = (usize::from(op))
-        .checked_sub(usize::from(base_opcode))
-        .ok_or(VMError::InvalidOpcode)?
-        .checked_add(1)
-        .ok_or(VMError::InvalidOpcode)?;
-
-    Ok(offset)
-}
-
-fn get_number_of_topics(op: Opcode) -> Result<u8, VMError> {
-    let number_of_topics = (u8::from(op))
-        .checked_sub(u8::from(Opcode::LOG0))
-        .ok_or(VMError::InvalidOpcode)?;
-
-    Ok(number_of_topics)
-}
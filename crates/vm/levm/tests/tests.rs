@@ -7,17 +7,28 @@ use ethrex_levm::{
     account::Account,
     constants::*,
     db::{cache, CacheDB, Db},
-    errors::{OutOfGasError, TxResult, VMError},
+    eof::{ContainerKind, EofContainer, TypeSection, NON_RETURNING},
+    errors::{EofError, OutOfGasError, PrecompileError, TxResult, VMError},
     gas_cost::{
-        self, ECRECOVER_COST, RIPEMD_160_DYNAMIC_BASE, RIPEMD_160_STATIC_COST,
+        self, BLS12_381_G1ADD_COST, ECADD_COST, ECRECOVER_COST, IDENTITY_DYNAMIC_BASE,
+        IDENTITY_STATIC_COST, RIPEMD_160_DYNAMIC_BASE, RIPEMD_160_STATIC_COST,
         SHA2_256_DYNAMIC_BASE, SHA2_256_STATIC_COST,
     },
+    jump_destinations::JumpDestinations,
     memory,
     operations::Operation,
-    precompiles::{ecrecover, ripemd_160, sha2_256},
-    utils::{new_vm_with_ops, new_vm_with_ops_addr_bal_db, new_vm_with_ops_db, ops_to_bytecode},
-    vm::{word_to_address, Storage, VM},
-    Environment,
+    precompiles::{
+        blake2f, bls12_381, ecadd, ecrecover, identity, modexp, ripemd_160, sha2_256, PrecompileFn,
+        PrecompileRegistry, BLS12_381_G1ADD_ADDRESS,
+    },
+    profiler::GasProfiler,
+    tracer::Tracer,
+    utils::{
+        new_vm_with_bytecode, new_vm_with_ops, new_vm_with_ops_addr_bal_db, new_vm_with_ops_db,
+        ops_to_bytecode,
+    },
+    vm::{address_to_word, word_to_address, Storage, VM},
+    Environment, Fork, StorageSlot,
 };
 use std::{borrow::BorrowMut, collections::HashMap, sync::Arc};
 
@@ -4534,3 +4545,1061 @@ fn ripemd_160_test() {
         (RIPEMD_160_STATIC_COST + RIPEMD_160_DYNAMIC_BASE)
     );
 }
+
+#[test]
+fn identity_test() {
+    let calldata = Bytes::from(hex::decode("ff").unwrap());
+
+    let mut consumed_gas = 0;
+    let result = identity(&calldata, 10000, &mut consumed_gas).unwrap();
+
+    assert_eq!(result, calldata);
+    assert_eq!(consumed_gas, IDENTITY_STATIC_COST + IDENTITY_DYNAMIC_BASE);
+}
+
+#[test]
+fn modexp_test() {
+    // 3 ** 5 % 7, each value being one byte long
+    let calldata = hex::decode(
+        "0000000000000000000000000000000000000000000000000000000000000001\
+        0000000000000000000000000000000000000000000000000000000000000001\
+        0000000000000000000000000000000000000000000000000000000000000001\
+        030507",
+    )
+    .unwrap();
+
+    let mut consumed_gas = 0;
+    let result = modexp(&Bytes::from(calldata), 10000, &mut consumed_gas).unwrap();
+
+    assert_eq!(result, Bytes::from(vec![5]));
+    // Minimum gas cost
+    assert_eq!(consumed_gas, 200);
+}
+
+#[test]
+fn ecadd_test() {
+    let calldata = hex::decode(
+        "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+        063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
+        07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
+        06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7",
+    )
+    .unwrap();
+
+    let mut consumed_gas = 0;
+    let result = ecadd(&Bytes::from(calldata), 10000, &mut consumed_gas).unwrap();
+
+    let expected_result = hex::decode(
+        "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703\
+        301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915",
+    )
+    .unwrap();
+    assert_eq!(result, Bytes::from(expected_result));
+    assert_eq!(consumed_gas, ECADD_COST);
+}
+
+#[test]
+fn blake2f_test() {
+    // Test vector 5 of EIP-152, the compression of "abc" with 12 rounds
+    let rounds = "0000000c";
+    let state = "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+        d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b";
+    let message = format!("616263{}", "00".repeat(125));
+    let offset_counters = format!("03{}", "00".repeat(15));
+    let final_block_flag = "01";
+    let calldata = hex::decode(format!(
+        "{rounds}{state}{message}{offset_counters}{final_block_flag}"
+    ))
+    .unwrap();
+
+    let mut consumed_gas = 0;
+    let result = blake2f(&Bytes::from(calldata), 10000, &mut consumed_gas).unwrap();
+
+    let expected_result = hex::decode(
+        "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+        7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+    )
+    .unwrap();
+    assert_eq!(result, Bytes::from(expected_result));
+    assert_eq!(consumed_gas, 12);
+}
+
+const BLS12_381_G1_GENERATOR: &str = "0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb0000000000000000000000000000000008b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1";
+
+#[test]
+fn bls12_381_g1_add_test() {
+    let calldata = hex::decode(BLS12_381_G1_GENERATOR.repeat(2)).unwrap();
+
+    let mut consumed_gas = 0;
+    let result = bls12_381::g1_add(&Bytes::from(calldata), 10000, &mut consumed_gas).unwrap();
+
+    let expected_result = hex::decode(
+        "000000000000000000000000000000000572cbea904d67468808c8eb50a9450c9721db309128012543902d0ac358a62ae28f75bb8f1c7c42c39a8c5529bf0f4e00000000000000000000000000000000166a9d8cabc673a322fda673779d8e3822ba3ecb8670e461f73bb9021d5fd76a4c56d9d4cd16bd1bba86881979749d28",
+    )
+    .unwrap();
+    assert_eq!(result, Bytes::from(expected_result));
+    assert_eq!(consumed_gas, BLS12_381_G1ADD_COST);
+}
+
+#[test]
+fn bls12_381_g1_add_point_not_on_curve() {
+    let mut point = [0u8; 128];
+    point[63] = 1;
+    point[127] = 1;
+    let calldata = [point, point].concat();
+
+    let mut consumed_gas = 0;
+    let result = bls12_381::g1_add(&Bytes::from(calldata), 10000, &mut consumed_gas);
+
+    assert!(matches!(
+        result,
+        Err(VMError::PrecompileError(PrecompileError::PointNotOnCurve))
+    ));
+}
+
+#[test]
+fn bls12_381_g1_msm_test() {
+    // 1 * G + 0 * G
+    let calldata = hex::decode(format!(
+        "{BLS12_381_G1_GENERATOR}{:064x}{BLS12_381_G1_GENERATOR}{:064x}",
+        1, 0
+    ))
+    .unwrap();
+
+    let mut consumed_gas = 0;
+    let result = bls12_381::g1_msm(&Bytes::from(calldata), 100000, &mut consumed_gas).unwrap();
+
+    assert_eq!(
+        result,
+        Bytes::from(hex::decode(BLS12_381_G1_GENERATOR).unwrap())
+    );
+}
+
+#[test]
+fn bls12_381_pairing_of_points_at_infinity_is_one() {
+    let calldata = vec![0u8; 384];
+
+    let mut consumed_gas = 0;
+    let result = bls12_381::pairing(&Bytes::from(calldata), 100000, &mut consumed_gas).unwrap();
+
+    let mut expected_result = [0u8; 32];
+    expected_result[31] = 1;
+    assert_eq!(result, Bytes::from(expected_result.to_vec()));
+}
+
+/// Order of the BLS12-381 G1 and G2 subgroups
+const BLS12_381_GROUP_ORDER: &str =
+    "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+#[test]
+fn bls12_381_mapped_points_are_in_the_subgroup() {
+    let element = format!("{:0128x}{:0128x}", 1, 2);
+    let mut consumed_gas = 0;
+    let point = bls12_381::map_fp2_to_g2(
+        &Bytes::from(hex::decode(element).unwrap()),
+        100000,
+        &mut consumed_gas,
+    )
+    .unwrap();
+
+    // The MSM checks the point is in the subgroup, and multiplying it by the order gives the infinity
+    let calldata = [point.to_vec(), hex::decode(BLS12_381_GROUP_ORDER).unwrap()].concat();
+    let mut consumed_gas = 0;
+    let result = bls12_381::g2_msm(&Bytes::from(calldata), 100000, &mut consumed_gas).unwrap();
+
+    assert_eq!(result, Bytes::from(vec![0u8; 256]));
+}
+
+#[test]
+fn bls12_381_pairing_of_opposite_points_is_one() {
+    let mut consumed_gas = 0;
+    let g2_point = bls12_381::map_fp2_to_g2(
+        &Bytes::from(hex::decode(format!("{:0128x}{:0128x}", 3, 4)).unwrap()),
+        100000,
+        &mut consumed_gas,
+    )
+    .unwrap();
+    // -G = (order - 1) * G
+    let minus_one = format!("{}00", &BLS12_381_GROUP_ORDER[..62]);
+    let calldata = hex::decode(format!("{BLS12_381_G1_GENERATOR}{minus_one}")).unwrap();
+    let negated_generator =
+        bls12_381::g1_msm(&Bytes::from(calldata), 100000, &mut consumed_gas).unwrap();
+
+    let pairing = |pairs: Vec<u8>| {
+        let mut consumed_gas = 0;
+        bls12_381::pairing(&Bytes::from(pairs), 200000, &mut consumed_gas).unwrap()
+    };
+    let generator = hex::decode(BLS12_381_G1_GENERATOR).unwrap();
+
+    // e(G, Q) * e(-G, Q) = 1, while e(G, Q) isn't
+    let opposite_pairs = [
+        generator.clone(),
+        g2_point.to_vec(),
+        negated_generator.to_vec(),
+        g2_point.to_vec(),
+    ]
+    .concat();
+    assert_eq!(pairing(opposite_pairs)[31], 1);
+    assert_eq!(pairing([generator, g2_point.to_vec()].concat())[31], 0);
+}
+
+#[test]
+fn precompiles_are_enabled_by_fork() {
+    let cancun_precompiles = PrecompileRegistry::for_fork(Fork::Cancun);
+    let prague_precompiles = PrecompileRegistry::for_fork(Fork::Prague);
+
+    assert!(cancun_precompiles.is_precompile(&Address::from_low_u64_be(0x0a)));
+    assert!(!cancun_precompiles.is_precompile(&BLS12_381_G1ADD_ADDRESS));
+    assert!(prague_precompiles.is_precompile(&BLS12_381_G1ADD_ADDRESS));
+}
+
+fn always_one(
+    _calldata: &Bytes,
+    _gas_for_call: u64,
+    consumed_gas: &mut u64,
+) -> Result<Bytes, VMError> {
+    *consumed_gas = 100;
+    Ok(Bytes::from(vec![1]))
+}
+
+#[test]
+fn call_to_registered_precompile() {
+    let precompile_address = Address::from_low_u64_be(0x100);
+    let operations = [
+        Operation::Push((32, U256::from(1))),  // ret_size
+        Operation::Push((32, U256::from(31))), // ret_offset
+        Operation::Push((32, U256::zero())),   // args_size
+        Operation::Push((32, U256::zero())),   // args_offset
+        Operation::Push((32, U256::zero())),   // value
+        Operation::Push((32, address_to_word(precompile_address))), // address
+        Operation::Push((32, U256::from(100_000))), // gas
+        Operation::Call,
+        Operation::Pop,
+        Operation::Push((32, U256::zero())),
+        Operation::Mload,
+        Operation::Stop,
+    ];
+
+    let mut vm = new_vm_with_ops(&operations).unwrap();
+    assert!(vm
+        .register_precompile(precompile_address, always_one)
+        .is_none());
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    vm.execute(&mut current_call_frame).unwrap();
+
+    assert_eq!(current_call_frame.stack.pop().unwrap(), U256::one());
+    assert!(vm
+        .accrued_substate
+        .touched_accounts
+        .contains(&precompile_address));
+}
+
+#[test]
+fn push0_is_invalid_before_shanghai() {
+    let mut vm = new_vm_with_ops(&[Operation::Push0, Operation::Stop]).unwrap();
+    vm.env.fork = Fork::Paris;
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+
+    assert!(matches!(
+        tx_report.result,
+        TxResult::Revert(VMError::InvalidOpcode)
+    ));
+}
+
+#[test]
+fn sload_cost_depends_on_fork() {
+    assert_eq!(gas_cost::sload(true, Fork::Frontier).unwrap(), 50);
+    assert_eq!(gas_cost::sload(true, Fork::TangerineWhistle).unwrap(), 200);
+    assert_eq!(gas_cost::sload(true, Fork::Istanbul).unwrap(), 800);
+    assert_eq!(gas_cost::sload(true, Fork::Cancun).unwrap(), 2100);
+    assert_eq!(gas_cost::sload(false, Fork::Cancun).unwrap(), 100);
+}
+
+#[test]
+fn sstore_refund_for_restoring_original_value() {
+    let storage_slot = StorageSlot {
+        original_value: U256::one(),
+        current_value: U256::from(2),
+    };
+
+    assert_eq!(
+        gas_cost::sstore_refund(&storage_slot, U256::one(), Fork::Cancun).unwrap(),
+        (2800, 0)
+    );
+    assert_eq!(
+        gas_cost::sstore_refund(&storage_slot, U256::one(), Fork::Istanbul).unwrap(),
+        (4200, 0)
+    );
+    // Before net gas metering only clearing a slot was refunded
+    assert_eq!(
+        gas_cost::sstore_refund(&storage_slot, U256::one(), Fork::Petersburg).unwrap(),
+        (0, 0)
+    );
+    assert_eq!(
+        gas_cost::sstore_refund(&storage_slot, U256::zero(), Fork::Petersburg).unwrap(),
+        (15000, 0)
+    );
+}
+
+#[test]
+fn sstore_refund_for_clearing_slot_depends_on_fork() {
+    let storage_slot = StorageSlot {
+        original_value: U256::one(),
+        current_value: U256::one(),
+    };
+
+    assert_eq!(
+        gas_cost::sstore_refund(&storage_slot, U256::zero(), Fork::Berlin).unwrap(),
+        (15000, 0)
+    );
+    assert_eq!(
+        gas_cost::sstore_refund(&storage_slot, U256::zero(), Fork::London).unwrap(),
+        (4800, 0)
+    );
+}
+
+#[test]
+fn modexp_cost_depends_on_fork() {
+    let exponent_head = U256::one() << 255;
+
+    // mult_complexity(64) * 255 / 20
+    assert_eq!(
+        gas_cost::modexp_byzantium(exponent_head, 64, 32, 64).unwrap(),
+        52224
+    );
+    // (64 / 8)^2 * 255 / 3
+    assert_eq!(gas_cost::modexp(exponent_head, 64, 32, 64).unwrap(), 5440);
+}
+
+#[test]
+fn bn254_precompiles_are_enabled_since_byzantium() {
+    let ecadd_address = Address::from_low_u64_be(0x06);
+    let blake2f_address = Address::from_low_u64_be(0x09);
+
+    let homestead_precompiles = PrecompileRegistry::for_fork(Fork::Homestead);
+    let byzantium_precompiles = PrecompileRegistry::for_fork(Fork::Byzantium);
+
+    assert!(!homestead_precompiles.is_precompile(&ecadd_address));
+    assert!(byzantium_precompiles.is_precompile(&ecadd_address));
+    assert!(!byzantium_precompiles.is_precompile(&blake2f_address));
+}
+
+#[test]
+fn coinbase_is_warm_since_shanghai() {
+    let coinbase = Address::from_low_u64_be(0xc0ffee);
+    let vm_in_fork = |fork| {
+        let mut env = Environment::default_from_address(Address::from_low_u64_be(100));
+        env.coinbase = coinbase;
+        env.fork = fork;
+        VM::new(
+            TxKind::Call(Address::from_low_u64_be(42)),
+            env,
+            Default::default(),
+            Default::default(),
+            Arc::new(Db::new()),
+            CacheDB::default(),
+            Vec::new(),
+        )
+        .unwrap()
+    };
+
+    assert!(!vm_in_fork(Fork::Paris)
+        .accrued_substate
+        .touched_accounts
+        .contains(&coinbase));
+    assert!(vm_in_fork(Fork::Shanghai)
+        .accrued_substate
+        .touched_accounts
+        .contains(&coinbase));
+}
+
+fn eof_container(sections: &[(TypeSection, &[Operation])]) -> EofContainer {
+    EofContainer {
+        types: sections.iter().map(|(types, _)| *types).collect(),
+        code_sections: sections
+            .iter()
+            .map(|(_, ops)| ops_to_bytecode(ops).unwrap())
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn non_returning(max_stack_height: u16) -> TypeSection {
+    TypeSection {
+        inputs: 0,
+        outputs: NON_RETURNING,
+        max_stack_height,
+    }
+}
+
+fn execute_eof(container: &EofContainer) -> (VM, TxResult) {
+    let mut vm = new_vm_with_bytecode(container.encode().unwrap()).unwrap();
+    vm.env.fork = Fork::Osaka;
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+    (vm, tx_report.result)
+}
+
+#[test]
+fn eof_container_is_decoded_and_validated() {
+    let container = eof_container(&[(
+        non_returning(1),
+        &[
+            Operation::Push((1, U256::one())),
+            Operation::Pop,
+            Operation::Stop,
+        ],
+    )]);
+    let encoded = container.encode().unwrap();
+
+    assert_eq!(EofContainer::decode(&encoded).unwrap(), container);
+    assert!(container.validate(ContainerKind::Runtime).is_ok());
+    // Initcode has to end with RETURNCONTRACT or REVERT
+    assert_eq!(
+        container.validate(ContainerKind::Initcode),
+        Err(EofError::InvalidInstructionForKind)
+    );
+    assert_eq!(
+        EofContainer::decode(&[encoded.as_ref(), &[0x00]].concat()),
+        Err(EofError::TrailingBytes)
+    );
+}
+
+#[test]
+fn eof_validation_rejects_invalid_code() {
+    let legacy_jump = eof_container(&[(non_returning(1), &[Operation::Push0, Operation::Jump])]);
+    assert_eq!(
+        legacy_jump.validate(ContainerKind::Runtime),
+        Err(EofError::UndefinedInstruction)
+    );
+
+    let wrong_max_stack_height = eof_container(&[(
+        non_returning(2),
+        &[Operation::Push0, Operation::Pop, Operation::Stop],
+    )]);
+    assert_eq!(
+        wrong_max_stack_height.validate(ContainerKind::Runtime),
+        Err(EofError::InvalidMaxStackHeight)
+    );
+
+    let jump_into_immediate =
+        eof_container(&[(non_returning(1), &[Operation::Rjump(-2), Operation::Stop])]);
+    assert!(jump_into_immediate
+        .validate(ContainerKind::Runtime)
+        .is_err());
+
+    let stack_underflow = eof_container(&[(non_returning(0), &[Operation::Pop, Operation::Stop])]);
+    assert_eq!(
+        stack_underflow.validate(ContainerKind::Runtime),
+        Err(EofError::StackUnderflow)
+    );
+}
+
+#[test]
+fn eof_relative_jumps_and_functions() {
+    // Calls a function that multiplies its input by 3, skipping an INVALID with RJUMPI
+    let container = eof_container(&[
+        (
+            non_returning(2),
+            &[
+                Operation::Push((1, U256::from(2))),
+                Operation::Push((1, U256::one())),
+                Operation::Rjumpi(1),
+                Operation::Invalid,
+                Operation::Callf(1),
+                Operation::Stop,
+            ],
+        ),
+        (
+            TypeSection {
+                inputs: 1,
+                outputs: 1,
+                max_stack_height: 2,
+            },
+            &[
+                Operation::Push((1, U256::from(3))),
+                Operation::Mul,
+                Operation::Retf,
+            ],
+        ),
+    ]);
+    container.validate(ContainerKind::Runtime).unwrap();
+
+    let (mut vm, result) = execute_eof(&container);
+
+    assert_eq!(result, TxResult::Success);
+    let current_call_frame = vm.current_call_frame_mut().unwrap();
+    assert_eq!(current_call_frame.stack.pop().unwrap(), U256::from(6));
+    assert_eq!(current_call_frame.code_section, 0);
+}
+
+#[test]
+fn eof_data_section_is_readable() {
+    let mut container = eof_container(&[(
+        non_returning(2),
+        &[
+            Operation::Push0,
+            Operation::Dataload,
+            Operation::Dataloadn(0),
+            Operation::Eq,
+            Operation::Datasize,
+            Operation::Stop,
+        ],
+    )]);
+    container.data = Bytes::from(vec![0xab; 32]);
+    container.data_size = 32;
+    container.validate(ContainerKind::Runtime).unwrap();
+
+    let (mut vm, result) = execute_eof(&container);
+
+    assert_eq!(result, TxResult::Success);
+    let current_call_frame = vm.current_call_frame_mut().unwrap();
+    assert_eq!(current_call_frame.stack.pop().unwrap(), U256::from(32));
+    assert_eq!(current_call_frame.stack.pop().unwrap(), U256::one());
+}
+
+#[test]
+fn eof_opcodes_are_invalid_in_legacy_code() {
+    let mut vm = new_vm_with_ops(&[Operation::Datasize, Operation::Stop]).unwrap();
+    vm.env.fork = Fork::Osaka;
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+
+    assert_eq!(tx_report.result, TxResult::Revert(VMError::InvalidOpcode));
+}
+
+#[test]
+fn eof_code_is_not_executed_before_osaka() {
+    let container = eof_container(&[(non_returning(0), &[Operation::Stop])]);
+    let mut vm = new_vm_with_bytecode(container.encode().unwrap()).unwrap();
+    vm.env.fork = Fork::Cancun;
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+
+    assert_eq!(tx_report.result, TxResult::Revert(VMError::InvalidOpcode));
+}
+
+#[test]
+fn eofcreate_deploys_container_with_aux_data() {
+    // Deployed with 2 bytes of data to be filled with auxiliary data
+    let mut deploy_container = eof_container(&[(non_returning(0), &[Operation::Stop])]);
+    deploy_container.data_size = 2;
+
+    let mut initcontainer = eof_container(&[(
+        non_returning(2),
+        &[
+            Operation::Push((2, U256::from(0xbeef))),
+            Operation::Push0,
+            Operation::Mstore,
+            Operation::Push((1, U256::from(2))),
+            Operation::Push((1, U256::from(30))),
+            Operation::Returncontract(0),
+        ],
+    )]);
+    initcontainer.container_sections = vec![deploy_container.encode().unwrap()];
+
+    let mut container = eof_container(&[(
+        non_returning(4),
+        &[
+            Operation::Push0,
+            Operation::Push0,
+            Operation::Push0,
+            Operation::Push0,
+            Operation::Eofcreate(0),
+            Operation::Stop,
+        ],
+    )]);
+    let initcontainer = initcontainer.encode().unwrap();
+    container.container_sections = vec![initcontainer.clone()];
+    container.validate(ContainerKind::Runtime).unwrap();
+
+    let (mut vm, result) = execute_eof(&container);
+
+    assert_eq!(result, TxResult::Success);
+    let expected_address =
+        VM::calculate_create2_address(Address::from_low_u64_be(42), &initcontainer, U256::zero())
+            .unwrap();
+    let current_call_frame = vm.current_call_frame_mut().unwrap();
+    assert_eq!(
+        word_to_address(current_call_frame.stack.pop().unwrap()),
+        expected_address
+    );
+
+    let deployed_code = vm.get_account(expected_address).info.bytecode;
+    let deployed_container = EofContainer::decode(&deployed_code).unwrap();
+    assert_eq!(deployed_container.data, Bytes::from(vec![0xbe, 0xef]));
+    assert_eq!(
+        deployed_container.code_sections,
+        deploy_container.code_sections
+    );
+}
+
+#[test]
+fn jump_destinations_skip_push_data() {
+    // PUSH1 0x5B, JUMPDEST, PUSH2 0x5B5B, JUMPDEST
+    let jump_destinations = JumpDestinations::analyze(&[0x60, 0x5B, 0x5B, 0x61, 0x5B, 0x5B, 0x5B]);
+
+    assert!(!jump_destinations.contains(1));
+    assert!(jump_destinations.contains(2));
+    assert!(!jump_destinations.contains(4));
+    assert!(!jump_destinations.contains(5));
+    assert!(jump_destinations.contains(6));
+    assert!(!jump_destinations.contains(7));
+}
+
+#[test]
+fn jump_into_push_data_is_invalid() {
+    // PUSH1 0x5B, PUSH1 0x01, JUMP
+    let mut vm = new_vm_with_bytecode(Bytes::from(vec![0x60, 0x5B, 0x60, 0x01, 0x56])).unwrap();
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+
+    assert_eq!(tx_report.result, TxResult::Revert(VMError::InvalidJump));
+}
+
+#[test]
+fn jump_destinations_are_cached_by_code_hash() {
+    // PUSH1 0x03, JUMP, JUMPDEST
+    let bytecode = Bytes::from(vec![0x60, 0x03, 0x56, 0x5B]);
+    let code_hash = Account::default()
+        .with_bytecode(bytecode.clone())
+        .bytecode_hash();
+
+    let mut vm = new_vm_with_bytecode(bytecode.clone()).unwrap();
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+
+    assert_eq!(tx_report.result, TxResult::Success);
+    assert!(vm.jump_destinations[&code_hash].contains(3));
+
+    // A VM given the analyses of a previous one doesn't analyse the code again
+    let mut vm = new_vm_with_bytecode(bytecode).unwrap();
+    vm.jump_destinations
+        .insert(code_hash, Arc::new(JumpDestinations::default()));
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+
+    assert_eq!(tx_report.result, TxResult::Revert(VMError::InvalidJump));
+}
+
+#[test]
+fn reverted_call_only_undoes_its_own_changes() {
+    let callee_address = Address::from_low_u64_be(22);
+    let callee_ops = [
+        Operation::Push((32, U256::from(5))),
+        Operation::Push0,
+        Operation::Sstore,
+        Operation::Push0,
+        Operation::Push0,
+        Operation::Revert,
+    ];
+    let callee_account = Account::default()
+        .with_balance(50_000.into())
+        .with_bytecode(ops_to_bytecode(&callee_ops).unwrap());
+
+    let caller_ops = [
+        Operation::Push((32, U256::from(7))),
+        Operation::Push((32, U256::one())),
+        Operation::Sstore,
+        Operation::Push0,                           // ret_size
+        Operation::Push0,                           // ret_offset
+        Operation::Push0,                           // args_size
+        Operation::Push0,                           // args_offset
+        Operation::Push((32, U256::from(1000))),    // value
+        Operation::Push((32, U256::from(22))),      // address
+        Operation::Push((32, U256::from(100_000))), // gas
+        Operation::Call,
+        Operation::Push((32, U256::from(9))),
+        Operation::Push((32, U256::from(2))),
+        Operation::Sstore,
+        Operation::Stop,
+    ];
+
+    let mut db = Db::new();
+    db.add_accounts(vec![(callee_address, callee_account.clone())]);
+
+    let mut cache = CacheDB::default();
+    cache::insert_account(&mut cache, callee_address, callee_account);
+
+    let mut vm = new_vm_with_ops_addr_bal_db(
+        ops_to_bytecode(&caller_ops).unwrap(),
+        Address::from_low_u64_be(21),
+        U256::zero(),
+        db,
+        cache,
+    )
+    .unwrap();
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+    assert_eq!(tx_report.result, TxResult::Success);
+
+    let callee = cache::get_account(&vm.cache, &callee_address).unwrap();
+    assert_eq!(callee.info.balance, U256::from(50_000));
+    let callee_slot = callee
+        .storage
+        .get(&H256::zero())
+        .map(|slot| slot.current_value)
+        .unwrap_or_default();
+    assert_eq!(callee_slot, U256::zero());
+
+    let caller = cache::get_account(&vm.cache, &Address::from_low_u64_be(42)).unwrap();
+    assert_eq!(
+        caller
+            .storage
+            .get(&H256::from_low_u64_be(1))
+            .unwrap()
+            .current_value,
+        U256::from(7)
+    );
+    assert_eq!(
+        caller
+            .storage
+            .get(&H256::from_low_u64_be(2))
+            .unwrap()
+            .current_value,
+        U256::from(9)
+    );
+}
+
+#[test]
+fn reverted_call_undoes_transient_storage_and_warm_slots() {
+    let callee_address = Address::from_low_u64_be(22);
+    let callee_ops = [
+        Operation::Push((32, U256::from(5))),
+        Operation::Push0,
+        Operation::Tstore,
+        Operation::Push((32, U256::from(3))),
+        Operation::Sload,
+        Operation::Push0,
+        Operation::Push0,
+        Operation::Revert,
+    ];
+    let callee_account = Account::default().with_bytecode(ops_to_bytecode(&callee_ops).unwrap());
+
+    let caller_ops = [
+        Operation::Push0,                           // ret_size
+        Operation::Push0,                           // ret_offset
+        Operation::Push0,                           // args_size
+        Operation::Push0,                           // args_offset
+        Operation::Push0,                           // value
+        Operation::Push((32, U256::from(22))),      // address
+        Operation::Push((32, U256::from(100_000))), // gas
+        Operation::Call,
+        Operation::Stop,
+    ];
+
+    let mut db = Db::new();
+    db.add_accounts(vec![(callee_address, callee_account.clone())]);
+
+    let mut cache = CacheDB::default();
+    cache::insert_account(&mut cache, callee_address, callee_account);
+
+    let mut vm = new_vm_with_ops_addr_bal_db(
+        ops_to_bytecode(&caller_ops).unwrap(),
+        Address::from_low_u64_be(21),
+        U256::zero(),
+        db,
+        cache,
+    )
+    .unwrap();
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+    assert_eq!(tx_report.result, TxResult::Success);
+
+    assert!(vm.env.transient_storage.is_empty());
+    assert!(!vm
+        .accrued_substate
+        .touched_storage_slots
+        .get(&callee_address)
+        .is_some_and(|slots| slots.contains(&H256::from_low_u64_be(3))));
+    // The callee was warmed by the caller, so it stays warm
+    assert!(vm
+        .accrued_substate
+        .touched_accounts
+        .contains(&callee_address));
+}
+
+fn folded_stacks_gas(folded_stacks: &str) -> u64 {
+    folded_stacks
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+        .sum()
+}
+
+#[test]
+fn gas_profiler_splits_code_in_basic_blocks() {
+    // PUSH1 0x04, JUMP, INVALID, JUMPDEST, PUSH1 0x01, PUSH1 0x00, SSTORE, STOP
+    let mut vm = new_vm_with_bytecode(Bytes::from(vec![
+        0x60, 0x04, 0x56, 0xfe, 0x5b, 0x60, 0x01, 0x60, 0x00, 0x55, 0x00,
+    ]))
+    .unwrap();
+    vm.profiler = Some(GasProfiler::default());
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let address = current_call_frame.code_address;
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+    assert_eq!(tx_report.result, TxResult::Success);
+
+    let profile = vm.profiler.take().unwrap().finish();
+    let first_block = profile
+        .blocks
+        .iter()
+        .find(|block| block.address == address && block.start_pc == 0)
+        .unwrap();
+    assert_eq!((first_block.executions, first_block.gas), (1, 3 + 8));
+    let second_block = profile
+        .blocks
+        .iter()
+        .find(|block| block.address == address && block.start_pc == 4)
+        .unwrap();
+    assert_eq!(second_block.gas, tx_report.gas_used - 11);
+
+    let sstore = profile
+        .opcodes
+        .iter()
+        .find(|opcode| opcode.opcode == "SSTORE")
+        .unwrap();
+    assert_eq!(sstore.count, 1);
+    assert_eq!(profile.opcodes.first(), Some(sstore));
+    assert_eq!(
+        folded_stacks_gas(&profile.folded_stacks),
+        tx_report.gas_used
+    );
+}
+
+#[test]
+fn gas_profiler_excludes_sub_context_gas_from_calls() {
+    let callee_address = Address::from_low_u64_be(22);
+    let callee_ops = [
+        Operation::Push((32, U256::from(5))),
+        Operation::Push0,
+        Operation::Sstore,
+        Operation::Stop,
+    ];
+    let callee_account = Account::default().with_bytecode(ops_to_bytecode(&callee_ops).unwrap());
+
+    let caller_ops = [
+        Operation::Push0,                           // ret_size
+        Operation::Push0,                           // ret_offset
+        Operation::Push0,                           // args_size
+        Operation::Push0,                           // args_offset
+        Operation::Push0,                           // value
+        Operation::Push((32, U256::from(22))),      // address
+        Operation::Push((32, U256::from(100_000))), // gas
+        Operation::Call,
+        Operation::Stop,
+    ];
+
+    let mut db = Db::new();
+    db.add_accounts(vec![(callee_address, callee_account.clone())]);
+
+    let mut cache = CacheDB::default();
+    cache::insert_account(&mut cache, callee_address, callee_account);
+
+    let mut vm = new_vm_with_ops_addr_bal_db(
+        ops_to_bytecode(&caller_ops).unwrap(),
+        Address::from_low_u64_be(21),
+        U256::zero(),
+        db,
+        cache,
+    )
+    .unwrap();
+    vm.profiler = Some(GasProfiler::default());
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let caller_address = current_call_frame.code_address;
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+    assert_eq!(tx_report.result, TxResult::Success);
+
+    let profile = vm.profiler.take().unwrap().finish();
+    let callee = profile
+        .contracts
+        .iter()
+        .find(|contract| contract.address == callee_address)
+        .unwrap();
+    let caller = profile
+        .contracts
+        .iter()
+        .find(|contract| contract.address == caller_address)
+        .unwrap();
+    assert_eq!(callee.calls, 1);
+    // The callee's gas is only counted once, in its own opcodes
+    assert_eq!(caller.gas + callee.gas, tx_report.gas_used);
+    assert_eq!(
+        folded_stacks_gas(&profile.folded_stacks),
+        tx_report.gas_used
+    );
+
+    let callee_sstore_stack = format!("{caller_address:#x};{callee_address:#x};block@0x0;SSTORE ");
+    assert!(profile
+        .folded_stacks
+        .lines()
+        .any(|line| line.starts_with(&callee_sstore_stack)));
+}
+
+#[test]
+fn tracer_records_every_opcode_with_its_cost() {
+    // PUSH1 0x01, PUSH1 0x00, SSTORE, PUSH1 0x00, DUP1, REVERT
+    let mut vm = new_vm_with_bytecode(Bytes::from(vec![
+        0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x80, 0xfd,
+    ]))
+    .unwrap();
+    vm.tracer = Some(Tracer::default());
+
+    let mut current_call_frame = vm.call_frames.pop().unwrap();
+    let tx_report = vm.execute(&mut current_call_frame).unwrap();
+    assert_eq!(tx_report.result, TxResult::Revert(VMError::RevertOpcode));
+
+    let steps = vm.tracer.take().unwrap().steps;
+    let op_names: Vec<&str> = steps.iter().map(|step| step.op_name.as_str()).collect();
+    assert_eq!(
+        op_names,
+        ["PUSH1", "PUSH1", "SSTORE", "PUSH1", "DUP1", "REVERT"]
+    );
+    assert_eq!(steps[2].stack, vec![U256::one(), U256::zero()]);
+    assert_eq!(steps[2].gas_cost, steps[2].gas - steps[3].gas);
+    assert!(steps.iter().all(|step| step.depth == 1));
+    assert_eq!(steps[5].error, Some(VMError::RevertOpcode.to_string()));
+    assert_eq!(
+        steps.iter().map(|step| step.gas_cost).sum::<u64>(),
+        tx_report.gas_used
+    );
+}
+
+#[test]
+fn create_without_gas_for_code_deposit_depends_on_fork() {
+    // Init code returning 4096 bytes of code, whose deposit costs far more than the gas available
+    let initialization_code = hex::decode("6110006000f3").unwrap();
+    let operations = [
+        Operation::Push((6, U256::from_big_endian(&initialization_code))),
+        Operation::Push((1, U256::zero())),
+        Operation::Mstore,
+        Operation::Push((1, U256::from(6))),
+        Operation::Push((1, U256::from(26))),
+        Operation::Push((1, U256::zero())),
+        Operation::Create,
+        Operation::Stop,
+    ];
+    let create_in_fork = |fork| {
+        let mut vm = new_vm_with_ops(&operations).unwrap();
+        vm.env.fork = fork;
+        let mut current_call_frame = vm.call_frames.pop().unwrap();
+        current_call_frame.gas_limit = 100_000;
+        vm.execute(&mut current_call_frame).unwrap();
+        let returned_address = vm.current_call_frame_mut().unwrap().stack.pop().unwrap();
+        (vm, word_to_address(returned_address))
+    };
+
+    // Before Homestead the contract is created without code, and before Spurious Dragon with
+    // a zero nonce
+    let (vm, created_address) = create_in_fork(Fork::Frontier);
+    assert_ne!(created_address, Address::zero());
+    let created_account = cache::get_account(&vm.cache, &created_address).unwrap();
+    assert!(created_account.info.bytecode.is_empty());
+    assert_eq!(created_account.info.nonce, 0);
+
+    let (_, created_address) = create_in_fork(Fork::Homestead);
+    assert_eq!(created_address, Address::zero());
+}
+
+/// Test case of the EIP-2537 vectors, failing ones have no expected output
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PrecompileVector {
+    input: String,
+    expected: Option<String>,
+    name: String,
+}
+
+#[test]
+fn bls12_381_eip2537_vectors() {
+    // Multiplications are MSMs of a single pair, with the same encoding
+    let vector_files: [(&str, PrecompileFn); 18] = [
+        (include_str!("bls12_381/add_G1_bls.json"), bls12_381::g1_add),
+        (
+            include_str!("bls12_381/fail-add_G1_bls.json"),
+            bls12_381::g1_add,
+        ),
+        (include_str!("bls12_381/add_G2_bls.json"), bls12_381::g2_add),
+        (
+            include_str!("bls12_381/fail-add_G2_bls.json"),
+            bls12_381::g2_add,
+        ),
+        (include_str!("bls12_381/mul_G1_bls.json"), bls12_381::g1_msm),
+        (
+            include_str!("bls12_381/fail-mul_G1_bls.json"),
+            bls12_381::g1_msm,
+        ),
+        (include_str!("bls12_381/mul_G2_bls.json"), bls12_381::g2_msm),
+        (
+            include_str!("bls12_381/fail-mul_G2_bls.json"),
+            bls12_381::g2_msm,
+        ),
+        (
+            include_str!("bls12_381/multiexp_G1_bls.json"),
+            bls12_381::g1_msm,
+        ),
+        (
+            include_str!("bls12_381/fail-multiexp_G1_bls.json"),
+            bls12_381::g1_msm,
+        ),
+        (
+            include_str!("bls12_381/multiexp_G2_bls.json"),
+            bls12_381::g2_msm,
+        ),
+        (
+            include_str!("bls12_381/fail-multiexp_G2_bls.json"),
+            bls12_381::g2_msm,
+        ),
+        (
+            include_str!("bls12_381/pairing_check_bls.json"),
+            bls12_381::pairing,
+        ),
+        (
+            include_str!("bls12_381/fail-pairing_check_bls.json"),
+            bls12_381::pairing,
+        ),
+        (
+            include_str!("bls12_381/map_fp_to_G1_bls.json"),
+            bls12_381::map_fp_to_g1,
+        ),
+        (
+            include_str!("bls12_381/fail-map_fp_to_G1_bls.json"),
+            bls12_381::map_fp_to_g1,
+        ),
+        (
+            include_str!("bls12_381/map_fp2_to_G2_bls.json"),
+            bls12_381::map_fp2_to_g2,
+        ),
+        (
+            include_str!("bls12_381/fail-map_fp2_to_G2_bls.json"),
+            bls12_381::map_fp2_to_g2,
+        ),
+    ];
+    for (vectors, precompile) in vector_files {
+        let vectors: Vec<PrecompileVector> = serde_json::from_str(vectors).unwrap();
+        for vector in vectors {
+            let calldata = Bytes::from(hex::decode(&vector.input).unwrap());
+            let mut consumed_gas = 0;
+            let result = precompile(&calldata, u64::MAX, &mut consumed_gas);
+            match vector.expected {
+                Some(expected) => assert_eq!(
+                    result.unwrap(),
+                    Bytes::from(hex::decode(expected).unwrap()),
+                    "{}",
+                    vector.name
+                ),
+                None => assert!(result.is_err(), "{}", vector.name),
+            }
+        }
+    }
+}
@@ -0,0 +1,487 @@
+use crate::{db::StoreWrapper, EvmError, EvmState};
+use bytes::Bytes;
+use ethrex_core::{
+    types::{
+        AccountInfo, BlockHeader, ChainConfig, Fork as ChainFork, GenericTransaction, Log, Receipt,
+        Transaction, Withdrawal, GWEI_TO_WEI,
+    },
+    Address, H256, U256,
+};
+use ethrex_levm::{
+    db::{CacheDB, Database as LevmDatabase},
+    errors::{TransactionReport, TxResult, VMError},
+    jump_destinations::JumpDestinationsCache,
+    profiler::{GasProfile, GasProfiler},
+    vm::VM,
+    Account, AccountInfo as LevmAccountInfo, Environment, Fork, StorageSlot,
+};
+use ethrex_storage::{error::StoreError, AccountUpdate, Store};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+/// Result of executing a single transaction, with the data needed to compare its execution
+/// across different engines
+#[derive(Debug, Clone)]
+pub struct TxOutcome {
+    pub success: bool,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    /// Changes made by the transaction to the state left by the previous ones
+    pub account_updates: Vec<AccountUpdate>,
+}
+
+/// Result of simulating a call with LEVM while profiling the gas it spends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallProfile {
+    pub success: bool,
+    pub gas_used: u64,
+    #[serde(with = "ethrex_core::serde_utils::bytes")]
+    pub output: Bytes,
+    #[serde(flatten)]
+    pub profile: GasProfile,
+}
+
+/// Executes the transactions of a block one by one with LEVM
+/// The state changes of each transaction are kept in a cache which the following transactions
+/// are executed over, so that the whole block can be executed without touching the store
+pub(crate) struct LevmBlockExecutor {
+    db: Arc<StoreWrapper>,
+    cache: CacheDB,
+    /// Info of the cached accounts as left by the last executed transaction, which the changes
+    /// of the next one are computed against
+    committed_infos: HashMap<Address, LevmAccountInfo>,
+    /// Jump destinations of the code run so far, handed from each transaction's VM to the next
+    jump_destinations: JumpDestinationsCache,
+    fork: Fork,
+    receipts: Vec<Receipt>,
+    cumulative_gas_used: u64,
+}
+
+impl LevmBlockExecutor {
+    /// Starts the execution of a block over its parent's state, read from the store backing `state`
+    /// `system_updates` are the changes made by the system calls performed before the block's
+    /// transactions, and are loaded into the executor's state
+    pub fn new(
+        block_header: &BlockHeader,
+        state: &EvmState,
+        system_updates: Vec<AccountUpdate>,
+    ) -> Result<Self, EvmError> {
+        let store = state
+            .database()
+            .ok_or(EvmError::DB(StoreError::MissingStore))?
+            .clone();
+        let fork = levm_fork(&state.chain_config()?, block_header);
+        let mut executor = Self {
+            db: Arc::new(StoreWrapper {
+                store,
+                block_hash: block_header.parent_hash,
+            }),
+            cache: CacheDB::default(),
+            committed_infos: HashMap::new(),
+            jump_destinations: JumpDestinationsCache::default(),
+            fork,
+            receipts: Vec::new(),
+            cumulative_gas_used: 0,
+        };
+        for account_update in system_updates {
+            executor.apply_account_update(account_update);
+        }
+        executor.commit_tx_changes();
+        Ok(executor)
+    }
+
+    /// Executes the next transaction of the block over the state left by the previous ones
+    pub fn execute_tx(
+        &mut self,
+        tx: &Transaction,
+        block_header: &BlockHeader,
+    ) -> Result<TxOutcome, EvmError> {
+        // The cache is moved into the VM instead of cloned, and taken back once the transaction
+        // is executed. If the VM can't be created the block can't be executed any further, so the
+        // cache isn't needed anymore
+        let mut vm = levm_vm_for_tx(
+            tx,
+            block_header,
+            self.fork,
+            self.db.clone(),
+            std::mem::take(&mut self.cache),
+        )
+        .map_err(|error| EvmError::Transaction(error.to_string()))?;
+        vm.jump_destinations = std::mem::take(&mut self.jump_destinations);
+        let report = vm.transact();
+        self.cache = std::mem::take(&mut vm.cache);
+        self.jump_destinations = std::mem::take(&mut vm.jump_destinations);
+        let report = report.map_err(|error| EvmError::Transaction(error.to_string()))?;
+        let account_updates = self.commit_tx_changes();
+
+        let success = matches!(report.result, TxResult::Success);
+        self.cumulative_gas_used += report.gas_used;
+        self.receipts.push(Receipt::new(
+            tx.tx_type(),
+            success,
+            self.cumulative_gas_used,
+            report.logs.clone(),
+        ));
+        Ok(TxOutcome {
+            success,
+            gas_used: report.gas_used,
+            logs: report.logs,
+            account_updates,
+        })
+    }
+
+    /// Current state of the accounts touched so far in the block
+    pub fn state(&self) -> &CacheDB {
+        &self.cache
+    }
+
+    /// Returns the account as it is in the given cached state, or in the parent block's state if it
+    /// wasn't touched
+    pub fn account_at(&self, state: &CacheDB, address: Address) -> Account {
+        state
+            .get(&address)
+            .cloned()
+            .unwrap_or_else(|| Account::from(self.db.get_account_info(address)))
+    }
+
+    /// Returns the value of the storage slot in the given cached state, or in the parent block's
+    /// state if it wasn't touched
+    pub fn storage_at(&self, state: &CacheDB, address: Address, key: H256) -> U256 {
+        state
+            .get(&address)
+            .and_then(|account| account.storage.get(&key))
+            .map(|slot| slot.current_value)
+            .unwrap_or_else(|| self.db.get_storage_slot(address, key))
+    }
+
+    /// Processes the block's withdrawals and returns the receipts and account updates of the whole block
+    pub fn finish(
+        mut self,
+        withdrawals: Option<&[Withdrawal]>,
+    ) -> (Vec<Receipt>, Vec<AccountUpdate>) {
+        for withdrawal in withdrawals
+            .unwrap_or_default()
+            .iter()
+            .filter(|withdrawal| withdrawal.amount > 0)
+        {
+            let account = self.cached_account_mut(withdrawal.address);
+            account.info.balance += U256::from(withdrawal.amount) * U256::from(GWEI_TO_WEI);
+        }
+        let db = self.db.as_ref();
+        let clear_empty_accounts = self.fork.gas_schedule().clear_empty_accounts;
+        let account_updates = self
+            .cache
+            .iter()
+            .filter_map(|(address, account)| {
+                account_update(
+                    *address,
+                    account,
+                    &db.get_account_info(*address),
+                    |key| db.get_storage_slot(*address, key),
+                    clear_empty_accounts,
+                    || db.account_exists(*address),
+                )
+            })
+            .collect();
+        (self.receipts, account_updates)
+    }
+
+    /// Returns the changes made to the cached state since the last call, and makes the values
+    /// left by the last transaction the original ones for the next transaction
+    fn commit_tx_changes(&mut self) -> Vec<AccountUpdate> {
+        let db = self.db.as_ref();
+        let clear_empty_accounts = self.fork.gas_schedule().clear_empty_accounts;
+        // Accounts dropped from the cache are read from the parent block's state again
+        self.committed_infos
+            .retain(|address, _| self.cache.contains_key(address));
+        let mut account_updates = Vec::new();
+        for (address, account) in self.cache.iter_mut() {
+            let parent_info;
+            let previous_info = match self.committed_infos.get(address) {
+                Some(info) => info,
+                None => {
+                    parent_info = db.get_account_info(*address);
+                    &parent_info
+                }
+            };
+            // Slots loaded during the transaction have the parent block's values as original ones
+            let update = account_update(
+                *address,
+                account,
+                previous_info,
+                |key| account.storage[&key].original_value,
+                clear_empty_accounts,
+                || db.account_exists(*address),
+            );
+            account_updates.extend(update);
+            if self.committed_infos.get(address) != Some(&account.info) {
+                self.committed_infos.insert(*address, account.info.clone());
+            }
+            for slot in account.storage.values_mut() {
+                slot.original_value = slot.current_value;
+            }
+        }
+        account_updates
+    }
+
+    fn cached_account_mut(&mut self, address: Address) -> &mut Account {
+        let db = &self.db;
+        self.cache
+            .entry(address)
+            .or_insert_with(|| Account::from(db.get_account_info(address)))
+    }
+
+    fn apply_account_update(&mut self, account_update: AccountUpdate) {
+        let account = self.cached_account_mut(account_update.address);
+        if let Some(info) = account_update.info {
+            account.info.balance = info.balance;
+            account.info.nonce = info.nonce;
+        }
+        if let Some(code) = account_update.code {
+            account.info.set_bytecode(code);
+        }
+        for (key, value) in account_update.added_storage {
+            account.storage.insert(
+                key,
+                StorageSlot {
+                    original_value: value,
+                    current_value: value,
+                },
+            );
+        }
+    }
+}
+
+/// Returns the changes made to the account with respect to its previous info and storage values,
+/// None if there aren't any
+/// Since [EIP-161], as with revm, empty accounts are not included unless they were emptied by a
+/// SELFDESTRUCT. Before it, touched accounts that didn't exist are created even if left empty
+fn account_update(
+    address: Address,
+    account: &Account,
+    previous_info: &LevmAccountInfo,
+    previous_value: impl Fn(H256) -> U256,
+    clear_empty_accounts: bool,
+    existed: impl FnOnce() -> bool,
+) -> Option<AccountUpdate> {
+    let created_empty =
+        account.info.is_empty() && previous_info.is_empty() && !clear_empty_accounts && !existed();
+    if account.info.is_empty() && !created_empty {
+        // Before EIP-161 only a SELFDESTRUCT could empty an existing account, which deletes it
+        return (!previous_info.is_empty()).then(|| AccountUpdate::removed(address));
+    }
+
+    let mut account_update = AccountUpdate::new(address);
+    if account.info != *previous_info || created_empty {
+        account_update.info = Some(AccountInfo {
+            code_hash: account.info.code_hash,
+            balance: account.info.balance,
+            nonce: account.info.nonce,
+        });
+        if account.info.code_hash != previous_info.code_hash {
+            account_update.code = Some(account.info.bytecode.clone());
+        }
+    }
+    for (key, slot) in &account.storage {
+        if slot.current_value != previous_value(*key) {
+            account_update
+                .added_storage
+                .insert(*key, slot.current_value);
+        }
+    }
+    (account_update.info.is_some() || !account_update.added_storage.is_empty())
+        .then_some(account_update)
+}
+
+/// Returns the fork whose rules LEVM should execute the block with
+/// As with revm, Prague is never selected
+pub fn levm_fork(chain_config: &ChainConfig, block_header: &BlockHeader) -> Fork {
+    // Only LEVM executes Osaka, so it's not one of the chain forks
+    if chain_config.is_osaka_activated(block_header.timestamp) {
+        return Fork::Osaka;
+    }
+    match chain_config.get_fork(block_header.timestamp) {
+        ChainFork::Cancun => return Fork::Cancun,
+        ChainFork::Shanghai => return Fork::Shanghai,
+        ChainFork::Paris if block_header.difficulty.is_zero() => return Fork::Paris,
+        ChainFork::Paris => {}
+    }
+
+    // Before the merge forks were activated by block number
+    let block_forks = [
+        (chain_config.london_block, Fork::London),
+        (chain_config.berlin_block, Fork::Berlin),
+        (chain_config.istanbul_block, Fork::Istanbul),
+        (chain_config.petersburg_block, Fork::Petersburg),
+        (chain_config.constantinople_block, Fork::Constantinople),
+        (chain_config.byzantium_block, Fork::Byzantium),
+        (chain_config.eip158_block, Fork::SpuriousDragon),
+        (chain_config.eip150_block, Fork::TangerineWhistle),
+        (chain_config.homestead_block, Fork::Homestead),
+    ];
+    block_forks
+        .into_iter()
+        .find(|(activation_block, _)| {
+            activation_block.is_some_and(|activation_block| block_header.number >= activation_block)
+        })
+        .map(|(_, fork)| fork)
+        .unwrap_or(Fork::Frontier)
+}
+
+/// Executes a single transaction with LEVM over the state stored in `db` and the changes cached in `cache`
+pub fn execute_tx_levm(
+    tx: &Transaction,
+    block_header: &BlockHeader,
+    fork: Fork,
+    db: Arc<dyn LevmDatabase>,
+    cache: CacheDB,
+) -> Result<TransactionReport, VMError> {
+    levm_vm_for_tx(tx, block_header, fork, db, cache)?.transact()
+}
+
+/// Creates the LEVM instance that executes the transaction over the state stored in `db` and the
+/// changes cached in `cache`, so that it can be inspected before and after running it
+pub fn levm_vm_for_tx(
+    tx: &Transaction,
+    block_header: &BlockHeader,
+    fork: Fork,
+    db: Arc<dyn LevmDatabase>,
+    cache: CacheDB,
+) -> Result<VM, VMError> {
+    let gas_price: U256 = tx
+        .effective_gas_price(block_header.base_fee_per_gas)
+        .ok_or(VMError::InvalidTransaction)?
+        .into();
+
+    let env = Environment {
+        origin: tx.sender(),
+        fork,
+        refunded_gas: 0,
+        gas_limit: tx.gas_limit(),
+        block_number: block_header.number.into(),
+        coinbase: block_header.coinbase,
+        timestamp: block_header.timestamp.into(),
+        prev_randao: Some(block_header.prev_randao),
+        difficulty: block_header.difficulty,
+        chain_id: tx.chain_id().unwrap_or_default().into(),
+        base_fee_per_gas: block_header.base_fee_per_gas.unwrap_or_default().into(),
+        gas_price,
+        block_excess_blob_gas: block_header.excess_blob_gas.map(U256::from),
+        block_blob_gas_used: block_header.blob_gas_used.map(U256::from),
+        tx_blob_hashes: tx.blob_versioned_hashes(),
+        tx_max_priority_fee_per_gas: tx.max_priority_fee().map(U256::from),
+        tx_max_fee_per_gas: tx.max_fee_per_gas().map(U256::from),
+        tx_max_fee_per_blob_gas: tx.max_fee_per_blob_gas(),
+        block_gas_limit: block_header.gas_limit,
+        transient_storage: HashMap::new(),
+    };
+
+    VM::new(
+        tx.to(),
+        env,
+        tx.value(),
+        tx.data().clone(),
+        db,
+        cache,
+        tx.access_list(),
+    )
+}
+
+/// Simulates the call with LEVM over the state left by the given block, without committing it,
+/// and profiles the gas it spends
+pub fn profile_call_levm(
+    tx: &GenericTransaction,
+    block_header: &BlockHeader,
+    store: Store,
+) -> Result<CallProfile, EvmError> {
+    let fork = levm_fork(&store.get_chain_config()?, block_header);
+    let db = Arc::new(StoreWrapper {
+        store,
+        block_hash: block_header.compute_block_hash(),
+    });
+
+    let env = Environment {
+        origin: tx.from,
+        fork,
+        refunded_gas: 0,
+        gas_limit: tx.gas.unwrap_or(block_header.gas_limit),
+        block_number: block_header.number.into(),
+        coinbase: block_header.coinbase,
+        timestamp: block_header.timestamp.into(),
+        prev_randao: Some(block_header.prev_randao),
+        difficulty: block_header.difficulty,
+        chain_id: tx.chain_id.unwrap_or_default().into(),
+        base_fee_per_gas: block_header.base_fee_per_gas.unwrap_or_default().into(),
+        gas_price: tx.gas_price.into(),
+        block_excess_blob_gas: block_header.excess_blob_gas.map(U256::from),
+        block_blob_gas_used: block_header.blob_gas_used.map(U256::from),
+        tx_blob_hashes: tx.blob_versioned_hashes.clone(),
+        tx_max_priority_fee_per_gas: tx.max_priority_fee_per_gas.map(U256::from),
+        tx_max_fee_per_gas: tx.max_fee_per_gas.map(U256::from),
+        tx_max_fee_per_blob_gas: tx.max_fee_per_blob_gas,
+        block_gas_limit: block_header.gas_limit,
+        transient_storage: HashMap::new(),
+    };
+
+    let mut vm = VM::new(
+        tx.to.clone(),
+        env,
+        tx.value,
+        tx.input.clone(),
+        db,
+        CacheDB::default(),
+        tx.access_list
+            .iter()
+            .map(|entry| (entry.address, entry.storage_keys.clone()))
+            .collect(),
+    )
+    .map_err(|error| EvmError::Transaction(error.to_string()))?;
+    vm.profiler = Some(GasProfiler::default());
+
+    let report = vm
+        .transact()
+        .map_err(|error| EvmError::Transaction(error.to_string()))?;
+    Ok(CallProfile {
+        success: report.is_success(),
+        gas_used: report.gas_used,
+        output: report.output,
+        profile: vm.profiler.take().unwrap_or_default().finish(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_accounts_are_kept_before_state_clearing() {
+        let address = Address::from_low_u64_be(0xdead);
+        let empty = Account::default();
+        let no_storage = |_| U256::zero();
+
+        // A touched account that didn't exist is created empty before EIP-161
+        let update = account_update(address, &empty, &empty.info, no_storage, false, || false);
+        let update = update.expect("the account is created");
+        assert!(!update.removed);
+        assert_eq!(update.info.unwrap().balance, U256::zero());
+        assert!(account_update(address, &empty, &empty.info, no_storage, true, || false).is_none());
+
+        // Existing empty accounts are left as they are
+        assert!(account_update(address, &empty, &empty.info, no_storage, false, || true).is_none());
+
+        // Accounts emptied by a SELFDESTRUCT are deleted in every fork
+        let previous_info = LevmAccountInfo::new(U256::one(), Bytes::from_static(&[0x00]), 0);
+        for clear_empty_accounts in [false, true] {
+            let update = account_update(
+                address,
+                &empty,
+                &previous_info,
+                no_storage,
+                clear_empty_accounts,
+                || true,
+            );
+            assert!(update.is_some_and(|update| update.removed));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    execute_system_calls, execute_tx, get_state_transitions, levm_executor::LevmBlockExecutor,
+    process_withdrawals, spec_id, EvmEngine, EvmError, EvmState,
+};
+use ethrex_core::types::{Block, Receipt};
+use ethrex_storage::AccountUpdate;
+
+/// Result of executing the transactions of a block, leaving out the ones that can't be included
+/// in it instead of failing
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// Receipts of the included transactions
+    pub receipts: Vec<Receipt>,
+    /// Indexes of the included transactions in the block body
+    pub included: Vec<usize>,
+    pub rejected: Vec<RejectedTransaction>,
+    pub account_updates: Vec<AccountUpdate>,
+}
+
+/// A transaction left out of the block, with the reason it couldn't be included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedTransaction {
+    pub index: usize,
+    pub error: String,
+}
+
+/// Executes a block's transactions with the given engine, as the block building tools used to fill
+/// tests do: invalid transactions, or the ones that don't fit in the block's gas limit, are
+/// rejected and the rest of the block is executed as if they weren't in it
+pub fn execute_transition(
+    engine: EvmEngine,
+    block: &Block,
+    state: &mut EvmState,
+) -> Result<Transition, EvmError> {
+    let block_header = &block.header;
+    let spec_id = spec_id(&state.chain_config()?, block_header.timestamp);
+    let system_updates = execute_system_calls(block_header, state)?;
+    let mut levm = match engine {
+        EvmEngine::REVM => None,
+        EvmEngine::LEVM => Some(LevmBlockExecutor::new(
+            block_header,
+            state,
+            system_updates.clone(),
+        )?),
+    };
+
+    let mut receipts = Vec::new();
+    let mut included = Vec::new();
+    let mut rejected = Vec::new();
+    let mut cumulative_gas_used = 0;
+    for (index, transaction) in block.body.transactions.iter().enumerate() {
+        let gas_left = block_header.gas_limit - cumulative_gas_used;
+        if transaction.gas_limit() > gas_left {
+            rejected.push(RejectedTransaction {
+                index,
+                error: format!(
+                    "gas limit reached: transaction gas {}, block gas left {gas_left}",
+                    transaction.gas_limit()
+                ),
+            });
+            continue;
+        }
+        let outcome = match levm.as_mut() {
+            Some(levm) => {
+                // LEVM leaves the nonce to be checked by the block validation
+                let sender = transaction.sender();
+                let nonce = levm.account_at(levm.state(), sender).info.nonce;
+                if transaction.nonce() != nonce {
+                    Err(EvmError::Transaction(format!(
+                        "nonce mismatch: transaction nonce {}, state nonce {nonce}",
+                        transaction.nonce()
+                    )))
+                } else {
+                    levm.execute_tx(transaction, block_header)
+                        .map(|outcome| (outcome.success, outcome.gas_used, outcome.logs))
+                }
+            }
+            None => execute_tx(transaction, block_header, state, spec_id)
+                .map(|result| (result.is_success(), result.gas_used(), result.logs())),
+        };
+        let (success, gas_used, logs) = match outcome {
+            Ok(outcome) => outcome,
+            Err(EvmError::Transaction(error)) => {
+                rejected.push(RejectedTransaction { index, error });
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        cumulative_gas_used += gas_used;
+        receipts.push(Receipt::new(
+            transaction.tx_type(),
+            success,
+            cumulative_gas_used,
+            logs,
+        ));
+        included.push(index);
+    }
+
+    let account_updates = match levm {
+        Some(levm) => levm.finish(block.body.withdrawals.as_deref()).1,
+        None => {
+            if let Some(withdrawals) = &block.body.withdrawals {
+                process_withdrawals(state, withdrawals)?;
+            }
+            let mut account_updates = system_updates;
+            account_updates.extend(get_state_transitions(state));
+            account_updates
+        }
+    };
+    Ok(Transition {
+        receipts,
+        included,
+        rejected,
+        account_updates,
+    })
+}
@@ -1,12 +1,18 @@
+mod comparison;
 pub mod db;
+mod differential;
+mod engine;
 pub mod errors;
 pub mod execution_db;
 mod execution_result;
+mod levm_executor;
 #[cfg(feature = "l2")]
 mod mods;
+mod transition;
 
 use db::StoreWrapper;
 use execution_db::ExecutionDB;
+use levm_executor::LevmBlockExecutor;
 use std::cmp::min;
 
 use ethrex_core::{
@@ -33,9 +39,16 @@ use revm_primitives::{
     TxKind as RevmTxKind,
 };
 // Export needed types
+pub use comparison::ComparisonReport;
+pub use differential::{execute_block_differential, DifferentialExecution, Divergence};
+pub use engine::{EvmConfig, EvmEngine};
 pub use errors::EvmError;
 pub use execution_result::*;
+pub use levm_executor::{
+    execute_tx_levm, levm_fork, levm_vm_for_tx, profile_call_levm, CallProfile, TxOutcome,
+};
 pub use revm::primitives::{Address as RevmAddress, SpecId, U256 as RevmU256};
+pub use transition::{execute_transition, RejectedTransaction, Transition};
 
 type AccessList = Vec<(Address, Vec<H256>)>;
 
@@ -77,165 +90,104 @@ impl From<ExecutionDB> for EvmState {
     }
 }
 
-cfg_if::cfg_if! {
-    if #[cfg(feature = "levm")] {
-        use ethrex_levm::{
-            db::{CacheDB, Database as LevmDatabase},
-            errors::{TransactionReport, TxResult, VMError},
-            vm::VM,
-            Environment,
-        };
-        use std::{collections::HashMap, sync::Arc};
-        use ethrex_core::types::code_hash;
-
-        /// Executes all transactions in a block and returns their receipts.
-        pub fn execute_block(
-            block: &Block,
-            state: &mut EvmState,
-        ) -> Result<(Vec<Receipt>, Vec<AccountUpdate>), EvmError> {
-            let block_header = &block.header;
+/// Executes all transactions in a block and returns their receipts.
+pub fn execute_block(block: &Block, state: &mut EvmState) -> Result<Vec<Receipt>, EvmError> {
+    let block_header = &block.header;
+    let spec_id = spec_id(&state.chain_config()?, block_header.timestamp);
+    //eip 4788: execute beacon_root_contract_call before block transactions
+    cfg_if::cfg_if! {
+        if #[cfg(not(feature = "l2"))] {
             //eip 4788: execute beacon_root_contract_call before block transactions
-            cfg_if::cfg_if! {
-                if #[cfg(not(feature = "l2"))] {
-                    let spec_id = spec_id(&state.chain_config()?, block_header.timestamp);
-                    if block_header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
-                        beacon_root_contract_call(state, block_header, spec_id)?;
-                    }
-                }
+            if block_header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
+                beacon_root_contract_call(state, block_header, spec_id)?;
             }
-            let mut receipts = Vec::new();
-            let mut cumulative_gas_used = 0;
-
-            let store_wrapper = Arc::new(StoreWrapper {
-                store: state.database().unwrap().clone(),
-                block_hash: block.header.parent_hash,
-            });
-
-            let mut account_updates: Vec<AccountUpdate> = vec![];
-
-            for transaction in block.body.transactions.iter() {
-                let result = execute_tx_levm(transaction, block_header, store_wrapper.clone()).unwrap();
-                cumulative_gas_used += result.gas_used;
-                let receipt = Receipt::new(
-                    transaction.tx_type(),
-                    matches!(result.result, TxResult::Success),
-                    cumulative_gas_used,
-                    result.logs,
-                );
-                receipts.push(receipt);
-
-                for (address, account) in result.new_state {
-                    let mut added_storage = HashMap::new();
-
-                    for (key, value) in account.storage {
-                        added_storage.insert(key, value.current_value);
-                    }
-
-                    let code = if account.info.bytecode.is_empty() {
-                        None
-                    } else {
-                        Some(account.info.bytecode.clone())
-                    };
+        }
+    }
+    let mut receipts = Vec::new();
+    let mut cumulative_gas_used = 0;
+
+    for transaction in block.body.transactions.iter() {
+        let result = execute_tx(transaction, block_header, state, spec_id)?;
+        cumulative_gas_used += result.gas_used();
+        let receipt = Receipt::new(
+            transaction.tx_type(),
+            result.is_success(),
+            cumulative_gas_used,
+            result.logs(),
+        );
+        receipts.push(receipt);
+    }
 
-                    let account_update = AccountUpdate {
-                        address,
-                        removed: false,
-                        info: Some(AccountInfo {
-                            code_hash: code_hash(&account.info.bytecode),
-                            balance: account.info.balance,
-                            nonce: account.info.nonce,
-                        }),
-                        code,
-                        added_storage,
-                    };
+    if let Some(withdrawals) = &block.body.withdrawals {
+        process_withdrawals(state, withdrawals)?;
+    }
 
-                    account_updates.push(account_update);
-                }
-            }
+    Ok(receipts)
+}
 
-            if let Some(withdrawals) = &block.body.withdrawals {
-                process_withdrawals(state, withdrawals)?;
-            }
+/// Executes all transactions in a block with LEVM and returns their receipts and the resulting account updates
+/// The state is read from the store backing `state`, which is only used by revm to perform the system calls
+pub fn execute_block_levm(
+    block: &Block,
+    state: &mut EvmState,
+) -> Result<(Vec<Receipt>, Vec<AccountUpdate>), EvmError> {
+    let system_updates = execute_system_calls(&block.header, state)?;
+    let mut executor = LevmBlockExecutor::new(&block.header, state, system_updates)?;
+    for transaction in block.body.transactions.iter() {
+        executor.execute_tx(transaction, &block.header)?;
+    }
+    Ok(executor.finish(block.body.withdrawals.as_deref()))
+}
 
-            Ok((receipts, account_updates))
+/// Executes all transactions in a block with the given engine and returns their receipts
+/// and the resulting account updates
+pub fn execute_block_with_engine(
+    engine: EvmEngine,
+    block: &Block,
+    state: &mut EvmState,
+) -> Result<(Vec<Receipt>, Vec<AccountUpdate>), EvmError> {
+    match engine {
+        EvmEngine::REVM => {
+            let receipts = execute_block(block, state)?;
+            Ok((receipts, get_state_transitions(state)))
         }
+        EvmEngine::LEVM => execute_block_levm(block, state),
+    }
+}
 
-        pub fn execute_tx_levm(
-            tx: &Transaction,
-            block_header: &BlockHeader,
-            db: Arc<dyn LevmDatabase>,
-        ) -> Result<TransactionReport, VMError> {
-            let gas_price : U256 = tx.effective_gas_price(block_header.base_fee_per_gas).ok_or(VMError::InvalidTransaction)?.into();
-
-            let env = Environment {
-                origin: tx.sender(),
-                refunded_gas: 0,
-                gas_limit: tx.gas_limit(),
-                block_number: block_header.number.into(),
-                coinbase: block_header.coinbase,
-                timestamp: block_header.timestamp.into(),
-                prev_randao: Some(block_header.prev_randao),
-                chain_id: tx.chain_id().unwrap().into(),
-                base_fee_per_gas: block_header.base_fee_per_gas.unwrap_or_default().into(),
-                gas_price,
-                block_excess_blob_gas: block_header.excess_blob_gas.map(U256::from),
-                block_blob_gas_used: block_header.blob_gas_used.map(U256::from),
-                tx_blob_hashes: tx.blob_versioned_hashes(),
-                tx_max_priority_fee_per_gas: tx.max_priority_fee().map(U256::from),
-                tx_max_fee_per_gas: tx.max_fee_per_gas().map(U256::from),
-                tx_max_fee_per_blob_gas: tx.max_fee_per_blob_gas().map(U256::from),
-                block_gas_limit: block_header.gas_limit,
-                transient_storage: HashMap::new(),
-            };
-
-            let mut vm = VM::new(
-                tx.to(),
-                env,
-                tx.value(),
-                tx.data().clone(),
-                db,
-                CacheDB::default(),
-                tx.access_list(),
-            )?;
-
-            vm.transact()
-        }
-    } else if #[cfg(not(feature = "levm"))] {
-        /// Executes all transactions in a block and returns their receipts.
-        pub fn execute_block(block: &Block, state: &mut EvmState) -> Result<Vec<Receipt>, EvmError> {
-            let block_header = &block.header;
-            let spec_id = spec_id(&state.chain_config()?, block_header.timestamp);
-            //eip 4788: execute beacon_root_contract_call before block transactions
-            cfg_if::cfg_if! {
-                if #[cfg(not(feature = "l2"))] {
-                    //eip 4788: execute beacon_root_contract_call before block transactions
-                    if block_header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
-                        beacon_root_contract_call(state, block_header, spec_id)?;
-                    }
-                }
-            }
-            let mut receipts = Vec::new();
-            let mut cumulative_gas_used = 0;
-
-            for transaction in block.body.transactions.iter() {
-                let result = execute_tx(transaction, block_header, state, spec_id)?;
-                cumulative_gas_used += result.gas_used();
-                let receipt = Receipt::new(
-                    transaction.tx_type(),
-                    result.is_success(),
-                    cumulative_gas_used,
-                    result.logs(),
-                );
-                receipts.push(receipt);
-            }
+/// Performs the system calls and executes the first `tx_count` transactions of a block, returning
+/// the resulting account updates in the order they have to be applied
+pub fn execute_block_prefix(
+    block: &Block,
+    tx_count: usize,
+    state: &mut EvmState,
+) -> Result<Vec<AccountUpdate>, EvmError> {
+    let mut account_updates = execute_system_calls(&block.header, state)?;
+    let spec_id = spec_id(&state.chain_config()?, block.header.timestamp);
+    for transaction in block.body.transactions.iter().take(tx_count) {
+        execute_tx(transaction, &block.header, state, spec_id)?;
+    }
+    account_updates.extend(get_state_transitions(state));
+    Ok(account_updates)
+}
 
-            if let Some(withdrawals) = &block.body.withdrawals {
-                process_withdrawals(state, withdrawals)?;
+/// Performs the system calls made before executing a block's transactions and returns
+/// the resulting account updates
+#[cfg_attr(feature = "l2", allow(unused_variables))]
+fn execute_system_calls(
+    block_header: &BlockHeader,
+    state: &mut EvmState,
+) -> Result<Vec<AccountUpdate>, EvmError> {
+    cfg_if::cfg_if! {
+        if #[cfg(not(feature = "l2"))] {
+            //eip 4788: execute beacon_root_contract_call before block transactions
+            let spec_id = spec_id(&state.chain_config()?, block_header.timestamp);
+            if block_header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
+                beacon_root_contract_call(state, block_header, spec_id)?;
             }
-
-            Ok(receipts)
         }
     }
+    Ok(get_state_transitions(state))
 }
 
 // Executes a single tx, doesn't perform state transitions